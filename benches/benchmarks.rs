@@ -6,11 +6,9 @@ pub fn criterion_benchmark(c: &mut Criterion) {
     let environment = CaptchaEnvironment::default();
     let cb: ChineseClick0 = environment.load_captcha_breaker().unwrap();
     let image = image::open("images/0.jpg").unwrap();
-    cb.run(&image);
+    cb.run(&image).unwrap();
     c.bench_function("chinese_click_0", |b| {
-        b.iter(|| {
-            cb.run(black_box(&image));
-        })
+        b.iter(|| cb.run(black_box(&image)))
     });
 }
 
@@ -0,0 +1,30 @@
+use captcha_breaker::lap::{Matrix, Solver, solve};
+use criterion::{BenchmarkId, Criterion, black_box, criterion_group, criterion_main};
+
+/// 固定种子的随机成本矩阵，保证每次运行结果可比
+fn random_matrix(dim: usize) -> Matrix<f64> {
+    let mut state = 0x9E37_79B9_7F4A_7C15u64;
+    Matrix::from_shape_fn((dim, dim), |_| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state % 10_000) as f64 / 100.0
+    })
+}
+
+pub fn lap_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("lap");
+    group.sample_size(10);
+    for dim in [10, 100, 500, 1000] {
+        let costs = random_matrix(dim);
+        for solver in [Solver::LapJV, Solver::Auction] {
+            group.bench_with_input(BenchmarkId::new(format!("{:?}", solver), dim), &costs, |b, costs| {
+                b.iter(|| solve(black_box(costs), solver).unwrap())
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, lap_benchmark);
+criterion_main!(benches);
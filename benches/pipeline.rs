@@ -0,0 +1,127 @@
+//! 不依赖模型文件的流程基准，推理由MockBackend返回固定输出
+//!
+//! 运行 `cargo bench --features bench --bench pipeline`
+
+use captcha_breaker::backend::{ElementType, TensorData, TensorInfo};
+use captcha_breaker::bench::benchmark;
+use captcha_breaker::captcha::ChineseClick0;
+use captcha_breaker::detection::YoloDetector;
+use captcha_breaker::enhance::EnhanceConfig;
+use captcha_breaker::environment::CaptchaEnvironment;
+use captcha_breaker::input;
+use captcha_breaker::model::Model;
+use captcha_breaker::testing::MockBackend;
+use criterion::{BenchmarkId, Criterion, Throughput, black_box, criterion_group, criterion_main};
+use image::{DynamicImage, Rgb, Rgba, RgbaImage};
+use ndarray::{Array2, Array3};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// 样例图片尺寸，与ChineseClick0默认的提示区位置相符
+const IMAGE_SIZE: u32 = 440;
+/// 检测模型输入尺寸
+const INPUT_SIZE: u32 = 640;
+
+/// 原图坐标下的 (中心x, 中心y, 边长)，前4个为答案，后4个在提示区
+const BOXES: [(f32, f32, f32); 8] = [
+    (60.0, 100.0, 40.0),
+    (160.0, 220.0, 40.0),
+    (260.0, 150.0, 40.0),
+    (360.0, 280.0, 40.0),
+    (20.0, 380.0, 24.0),
+    (60.0, 380.0, 24.0),
+    (100.0, 380.0, 24.0),
+    (140.0, 380.0, 24.0),
+];
+
+/// 固定种子的噪点图，模拟带干扰背景的验证码
+fn noisy_image(width: u32, height: u32) -> RgbaImage {
+    let mut state = 0x9E37_79B9_7F4A_7C15u64;
+    RgbaImage::from_fn(width, height, |x, y| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        let noise = (state % 64) as u8;
+        Rgba([(x % 256) as u8 / 2 + noise, (y % 256) as u8 / 2 + noise, 128 + noise, 255 - noise])
+    })
+}
+
+fn info(name: &str, shape: Vec<i64>) -> TensorInfo {
+    TensorInfo {
+        name: name.to_string(),
+        shape,
+        element_type: ElementType::F32,
+    }
+}
+
+/// 在模型输入空间返回 [`BOXES`] 的检测模型
+fn mock_yolo() -> MockBackend {
+    let scale = INPUT_SIZE as f32 / IMAGE_SIZE as f32;
+    // 多一个空锚点，避免被当作已含NMS的布局
+    let mut output = Array3::<f32>::zeros((1, 5, BOXES.len() + 1));
+    for (i, &(cx, cy, size)) in BOXES.iter().enumerate() {
+        for (row, value) in [cx * scale, cy * scale, size * scale, size * scale, 0.9].into_iter().enumerate() {
+            output[[0, row, i]] = value;
+        }
+    }
+    let size = INPUT_SIZE as i64;
+    MockBackend::new(
+        vec![info("images", vec![1, 3, size, size])],
+        vec![info("output0", vec![1, 5, BOXES.len() as i64 + 1])],
+    )
+    .with_response(HashMap::from([("output0".to_string(), TensorData::from(output))]))
+}
+
+/// 第i个答案与第i个问题特征相同
+fn mock_siamese() -> MockBackend {
+    let half = BOXES.len() / 2;
+    let features = Array2::from_shape_fn((BOXES.len(), 64), |(row, col)| if col == row % half { 1.0 } else { 0.0 });
+    MockBackend::new(vec![info("images", vec![-1, 3, 96, 96])], vec![info("features", vec![-1, 64])])
+        .with_response(HashMap::from([("features".to_string(), TensorData::from(features))]))
+}
+
+pub fn preprocess_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("preprocess");
+    for size in [128, 440, 1024] {
+        let image = DynamicImage::ImageRgba8(noisy_image(size, size));
+        let rgb = input::to_rgb8(&image, input::DEFAULT_BACKGROUND);
+        group.throughput(Throughput::Elements(size as u64 * size as u64));
+        group.bench_with_input(BenchmarkId::new("to_rgb8", size), &image, |b, image| {
+            b.iter(|| input::to_rgb8(black_box(image), Rgb([255, 255, 255])))
+        });
+        let enhance = EnhanceConfig {
+            remove_background: Some(16),
+            denoise: true,
+            normalize_contrast: true,
+            sharpen: Some(1.0),
+        };
+        group.bench_with_input(BenchmarkId::new("enhance", size), &rgb, |b, rgb| {
+            b.iter(|| enhance.apply(black_box(rgb)).into_owned())
+        });
+        let detector = YoloDetector::new(Arc::new(mock_yolo()));
+        group.bench_with_input(BenchmarkId::new("detect", size), &rgb, |b, rgb| {
+            b.iter(|| detector.detect_rgb(black_box(rgb)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn mock_chinese_click() -> ChineseClick0 {
+    let environment = CaptchaEnvironment::default();
+    environment.insert_backend(Model::Yolo11n, Arc::new(mock_yolo())).unwrap();
+    environment.insert_backend(Model::Siamese, Arc::new(mock_siamese())).unwrap();
+    environment.load_captcha_breaker().unwrap()
+}
+
+pub fn end_to_end_benchmark(c: &mut Criterion) {
+    let cb = mock_chinese_click();
+    let image = DynamicImage::ImageRgba8(noisy_image(IMAGE_SIZE, IMAGE_SIZE));
+    let report = benchmark(50, || Ok(cb.run_detailed(&image)?.timings)).unwrap();
+    println!("{}", report);
+    c.bench_function("end_to_end/chinese_click_0_mock", |b| {
+        b.iter(|| cb.run_detailed(black_box(&image)).unwrap())
+    });
+}
+
+criterion_group!(benches, preprocess_benchmark, end_to_end_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,30 @@
+//! `cargo +nightly fuzz run lapjv`
+//!
+//! 第一个字节决定矩阵维数，其余每4个字节是一个f32成本，包括NaN与±inf
+#![no_main]
+
+use captcha_breaker::lap::{lapjv, verify};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Some((&dim, rest)) = data.split_first() else {
+        return;
+    };
+    let dim = (dim % 12) as usize + 1;
+    let values: Vec<f32> = rest.chunks_exact(4).map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])).collect();
+    if values.len() < dim * dim {
+        return;
+    }
+    let costs = ndarray::Array2::from_shape_fn((dim, dim), |(i, j)| values[i * dim + j]);
+    let Ok((rows, _)) = lapjv(&costs) else {
+        return;
+    };
+    // 含NaN的矩阵没有明确的最优解，只检查分配是排列
+    if costs.iter().any(|c| c.is_nan()) {
+        let mut sorted = rows.clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, (0..dim).collect::<Vec<_>>());
+    } else if costs.iter().all(|c| c.is_finite()) {
+        verify(&costs, &rows).unwrap();
+    }
+});
@@ -0,0 +1,111 @@
+#ifndef CAPTCHA_BREAKER_H
+#define CAPTCHA_BREAKER_H
+
+/* 由cbindgen生成，请勿手动修改 */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * 返回状态码
+ */
+typedef enum CaptchaStatus {
+  CAPTCHA_STATUS_OK = 0,
+  /**
+   * 传入了空指针
+   */
+  CAPTCHA_STATUS_NULL_POINTER = 1,
+  /**
+   * 参数无效，例如路径不是UTF-8或图片无法解码
+   */
+  CAPTCHA_STATUS_INVALID_ARGUMENT = 2,
+  /**
+   * 模型加载失败
+   */
+  CAPTCHA_STATUS_MODEL_ERROR = 3,
+  /**
+   * 识别失败
+   */
+  CAPTCHA_STATUS_SOLVE_ERROR = 4,
+  /**
+   * 输出缓冲区不足，所需长度已写入 `out_len`
+   */
+  CAPTCHA_STATUS_BUFFER_TOO_SMALL = 5,
+  /**
+   * 内部发生panic
+   */
+  CAPTCHA_STATUS_PANIC = 6,
+} CaptchaStatus;
+
+/**
+ * 不透明的环境句柄
+ */
+typedef struct CaptchaEnv CaptchaEnv;
+
+#if defined(CAPTCHA_BREAKER_CHINESE_CLICK_0)
+/**
+ * 不透明的ChineseClick0句柄
+ */
+typedef struct ChineseClick0Handle ChineseClick0Handle;
+#endif
+
+/**
+ * 点选坐标
+ */
+typedef struct CaptchaPoint {
+  float x;
+  float y;
+} CaptchaPoint;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * 当前线程最近一次返回状态码的调用的错误信息，该调用成功时返回NULL；返回的指针在下一次调用本库函数前有效
+ */
+const char *captcha_last_error(void);
+
+/**
+ * 创建环境，`models_dir` 为NULL时使用当前目录下的 `models`
+ */
+CaptchaStatus captcha_env_new(const char *models_dir, CaptchaEnv **out);
+
+/**
+ * 释放环境，已创建的验证码句柄不受影响
+ */
+void captcha_env_free(CaptchaEnv *env);
+
+#if defined(CAPTCHA_BREAKER_CHINESE_CLICK_0)
+/**
+ * 创建ChineseClick0，首次调用时可能需要下载模型
+ */
+CaptchaStatus chinese_click0_new(const CaptchaEnv *env, ChineseClick0Handle **out);
+#endif
+
+#if defined(CAPTCHA_BREAKER_CHINESE_CLICK_0)
+void chinese_click0_free(ChineseClick0Handle *handle);
+#endif
+
+#if defined(CAPTCHA_BREAKER_CHINESE_CLICK_0)
+/**
+ * 识别编码后的图片(PNG/JPEG等)，按顺序写入需要点选的坐标
+ *
+ * `out_len` 总是写入坐标数量；`capacity` 不足时返回 `BufferTooSmall`，
+ * 可以先传入 `capacity = 0` 查询所需长度，但这会完整识别一次
+ */
+CaptchaStatus chinese_click0_solve(const ChineseClick0Handle *handle,
+                                   const uint8_t *bytes,
+                                   size_t len,
+                                   CaptchaPoint *out_points,
+                                   size_t capacity,
+                                   size_t *out_len);
+#endif
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* CAPTCHA_BREAKER_H */
@@ -0,0 +1,212 @@
+//! 动图验证码
+//!
+//! 部分服务商使用GIF/WebP动图，字在不同帧中闪烁，单独一帧中可能缺字。这里逐帧检测后把各帧中
+//! 同一位置的框聚合为一个，并把每个框在置信度最高的一帧中的像素拼到各帧的中值图上，
+//! 得到所有字都清晰可见的融合图，后续的截取与匹配在融合图上进行
+
+use crate::detection::BBox;
+use crate::geometry::Rect;
+use image::codecs::gif::GifDecoder;
+use image::codecs::webp::WebPDecoder;
+use image::{AnimationDecoder, DynamicImage, ImageFormat, RgbImage};
+use std::error::Error;
+use std::io::Cursor;
+
+/// 动图中的一帧
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub image: DynamicImage,
+    /// 该帧的显示时长(毫秒)
+    pub delay_ms: u32,
+}
+
+/// 解码GIF/WebP动图的所有帧，静态图片返回一帧
+pub fn decode_frames(bytes: &[u8]) -> Result<Vec<Frame>, Box<dyn Error>> {
+    let frames = match image::guess_format(bytes)? {
+        ImageFormat::Gif => GifDecoder::new(Cursor::new(bytes))?.into_frames().collect_frames()?,
+        ImageFormat::WebP => {
+            let decoder = WebPDecoder::new(Cursor::new(bytes))?;
+            if !decoder.has_animation() {
+                return Ok(vec![still(bytes)?]);
+            }
+            decoder.into_frames().collect_frames()?
+        }
+        _ => return Ok(vec![still(bytes)?]),
+    };
+    if frames.is_empty() {
+        return Err("动图中没有任何帧".into());
+    }
+    Ok(frames
+        .into_iter()
+        .map(|frame| {
+            let (numer, denom) = frame.delay().numer_denom_ms();
+            Frame {
+                delay_ms: numer / denom.max(1),
+                image: DynamicImage::ImageRgba8(frame.into_buffer()),
+            }
+        })
+        .collect())
+}
+
+fn still(bytes: &[u8]) -> Result<Frame, Box<dyn Error>> {
+    Ok(Frame {
+        image: image::load_from_memory(bytes)?,
+        delay_ms: 0,
+    })
+}
+
+#[derive(Debug, Clone)]
+pub struct FusionConfig {
+    /// 不同帧中的两个框交并比不低于该值时视为同一个目标
+    pub iou_threshold: f32,
+    /// 出现的帧数占比低于该值的目标会被丢弃，用于过滤偶尔出现的干扰
+    pub min_presence: f32,
+    /// 最多检测的帧数，帧数更多时均匀抽取
+    pub max_frames: usize,
+}
+
+impl Default for FusionConfig {
+    fn default() -> Self {
+        FusionConfig {
+            iou_threshold: 0.5,
+            min_presence: 0.2,
+            max_frames: 16,
+        }
+    }
+}
+
+/// 聚合后的目标
+#[derive(Debug, Clone, PartialEq)]
+pub struct FusedBox {
+    /// 按置信度加权平均的框，置信度、类别与角度取自置信度最高的一帧
+    pub bbox: BBox,
+    /// 检测到该目标的帧下标
+    pub frames: Vec<usize>,
+    /// 置信度最高的帧下标
+    pub best_frame: usize,
+}
+
+/// 从 `count` 帧中均匀抽取不超过 `max_frames` 帧的下标
+pub fn sample_frames(count: usize, max_frames: usize) -> Vec<usize> {
+    let max_frames = max_frames.max(1);
+    if count <= max_frames {
+        return (0..count).collect();
+    }
+    (0..max_frames).map(|i| i * count / max_frames).collect()
+}
+
+struct Cluster {
+    /// 置信度加权的坐标和
+    sum: [f32; 4],
+    weight: f32,
+    best: BBox,
+    frames: Vec<usize>,
+    best_frame: usize,
+}
+
+impl Cluster {
+    fn rect(&self) -> Rect<f32> {
+        let [x_min, y_min, x_max, y_max] = self.sum.map(|v| v / self.weight);
+        Rect::new(x_min, y_min, x_max, y_max)
+    }
+
+    fn add(&mut self, bbox: &BBox, frame: usize) {
+        let weight = bbox.confidence.max(f32::EPSILON);
+        let rect = &bbox.rect;
+        for (sum, v) in self.sum.iter_mut().zip([rect.x_min, rect.y_min, rect.x_max, rect.y_max]) {
+            *sum += v * weight;
+        }
+        self.weight += weight;
+        self.frames.push(frame);
+        if bbox.confidence > self.best.confidence {
+            self.best = bbox.clone();
+            self.best_frame = frame;
+        }
+    }
+}
+
+/// 聚合逐帧的检测结果，`detections[i]` 为第i帧中的框
+///
+/// 每帧中的框按置信度从高到低依次归入交并比最大且本帧尚未归入框的目标，不满足交并比阈值时创建新目标，
+/// 结果按目标创建的先后排列
+pub fn fuse_detections(detections: &[Vec<BBox>], config: &FusionConfig) -> Vec<FusedBox> {
+    let mut clusters: Vec<Cluster> = vec![];
+    for (frame, boxes) in detections.iter().enumerate() {
+        let mut order: Vec<&BBox> = boxes.iter().collect();
+        order.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+        let mut used = vec![false; clusters.len()];
+        for bbox in order {
+            let best = clusters
+                .iter()
+                .enumerate()
+                .filter(|&(i, _)| !used[i])
+                .map(|(i, cluster)| (i, cluster.rect().iou(&bbox.rect)))
+                .filter(|&(_, iou)| iou >= config.iou_threshold)
+                .max_by(|a, b| a.1.total_cmp(&b.1));
+            match best {
+                Some((i, _)) => {
+                    clusters[i].add(bbox, frame);
+                    used[i] = true;
+                }
+                None => {
+                    let mut cluster = Cluster {
+                        sum: [0.0; 4],
+                        weight: 0.0,
+                        best: bbox.clone(),
+                        frames: vec![],
+                        best_frame: frame,
+                    };
+                    cluster.add(bbox, frame);
+                    clusters.push(cluster);
+                    used.push(true);
+                }
+            }
+        }
+    }
+    let frame_count = detections.len().max(1) as f32;
+    clusters
+        .into_iter()
+        .filter(|cluster| cluster.frames.len() as f32 / frame_count >= config.min_presence)
+        .map(|cluster| FusedBox {
+            bbox: BBox {
+                rect: cluster.rect(),
+                ..cluster.best.clone()
+            },
+            frames: cluster.frames,
+            best_frame: cluster.best_frame,
+        })
+        .collect()
+}
+
+/// 生成融合图：背景为各帧逐像素的中值，每个目标的区域取自其置信度最高的一帧
+///
+/// 所有帧的尺寸必须相同
+pub fn compose(frames: &[RgbImage], fused: &[FusedBox]) -> Result<RgbImage, Box<dyn Error>> {
+    let Some(first) = frames.first() else {
+        return Err("没有可融合的帧".into());
+    };
+    let (width, height) = first.dimensions();
+    if frames.iter().any(|frame| frame.dimensions() != (width, height)) {
+        return Err("动图各帧的尺寸不一致".into());
+    }
+    let mut values = Vec::with_capacity(frames.len());
+    let mut composed = RgbImage::from_fn(width, height, |x, y| {
+        image::Rgb(std::array::from_fn(|c| {
+            values.clear();
+            values.extend(frames.iter().map(|frame| frame.get_pixel(x, y)[c]));
+            values.sort_unstable();
+            values[values.len() / 2]
+        }))
+    });
+    for fused in fused {
+        let source = &frames[fused.best_frame];
+        let rect = fused.bbox.rect;
+        let x_range = rect.x_min.max(0.0).floor() as u32..(rect.x_max.ceil().max(0.0) as u32).min(width);
+        for y in rect.y_min.max(0.0).floor() as u32..(rect.y_max.ceil().max(0.0) as u32).min(height) {
+            for x in x_range.clone() {
+                composed.put_pixel(x, y, *source.get_pixel(x, y));
+            }
+        }
+    }
+    Ok(composed)
+}
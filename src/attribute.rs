@@ -0,0 +1,245 @@
+//! 目标属性(颜色、朝向)分类与提示词中的属性解析
+
+use image::RgbImage;
+
+pub use classifier::AttributeClassifier;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Color {
+    Red,
+    Orange,
+    Yellow,
+    Green,
+    Blue,
+    Purple,
+    Gray,
+    Black,
+    White,
+}
+
+impl Color {
+    /// 属性模型颜色输出的类别顺序
+    pub const ALL: [Color; 9] = [
+        Color::Red,
+        Color::Orange,
+        Color::Yellow,
+        Color::Green,
+        Color::Blue,
+        Color::Purple,
+        Color::Gray,
+        Color::Black,
+        Color::White,
+    ];
+
+    /// 提示词中可能出现的写法
+    pub fn words(&self) -> &'static [&'static str] {
+        match self {
+            Color::Red => &["red", "红色", "红"],
+            Color::Orange => &["orange", "橙色", "橙"],
+            Color::Yellow => &["yellow", "黄色", "黄"],
+            Color::Green => &["green", "绿色", "绿"],
+            Color::Blue => &["blue", "蓝色", "蓝"],
+            Color::Purple => &["purple", "紫色", "紫"],
+            Color::Gray => &["gray", "grey", "灰色", "灰"],
+            Color::Black => &["black", "黑色", "黑"],
+            Color::White => &["white", "白色", "白"],
+        }
+    }
+
+    /// 根据像素的色相、饱和度和亮度粗略判断颜色，作为没有属性模型时的启发式方法
+    pub fn from_rgb([r, g, b]: [u8; 3]) -> Color {
+        let (r, g, b) = (r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0);
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let delta = max - min;
+        if max < 0.2 {
+            return Color::Black;
+        }
+        if delta < 0.15 {
+            return if max > 0.8 { Color::White } else { Color::Gray };
+        }
+        let hue = if max == r {
+            60.0 * ((g - b) / delta).rem_euclid(6.0)
+        } else if max == g {
+            60.0 * ((b - r) / delta + 2.0)
+        } else {
+            60.0 * ((r - g) / delta + 4.0)
+        };
+        match hue {
+            h if !(15.0..345.0).contains(&h) => Color::Red,
+            h if h < 40.0 => Color::Orange,
+            h if h < 70.0 => Color::Yellow,
+            h if h < 170.0 => Color::Green,
+            h if h < 260.0 => Color::Blue,
+            _ => Color::Purple,
+        }
+    }
+
+    /// 图像块中饱和度最高的一半像素的主色，忽略背景
+    pub fn dominant(crop: &RgbImage) -> Color {
+        let mut pixels: Vec<[u8; 3]> = crop.pixels().map(|p| p.0).collect();
+        let saturation = |p: &[u8; 3]| {
+            let max = p.iter().max().copied().unwrap_or(0) as i32;
+            let min = p.iter().min().copied().unwrap_or(0) as i32;
+            max - min
+        };
+        pixels.sort_by_key(|p| std::cmp::Reverse(saturation(p)));
+        pixels.truncate(pixels.len().div_ceil(2));
+        let mut counts = [0usize; Color::ALL.len()];
+        for p in pixels {
+            let color = Color::from_rgb(p);
+            counts[Color::ALL.iter().position(|&c| c == color).unwrap()] += 1;
+        }
+        let best = (0..counts.len()).max_by_key(|&i| counts[i]).unwrap();
+        Color::ALL[best]
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Orientation {
+    Upright,
+    Left,
+    Right,
+    UpsideDown,
+}
+
+impl Orientation {
+    /// 属性模型朝向输出的类别顺序
+    pub const ALL: [Orientation; 4] = [
+        Orientation::Upright,
+        Orientation::Left,
+        Orientation::Right,
+        Orientation::UpsideDown,
+    ];
+
+    pub fn words(&self) -> &'static [&'static str] {
+        match self {
+            Orientation::Upright => &["upright", "正立", "正向", "正放"],
+            Orientation::Left => &["left-facing", "facing left", "朝左", "向左"],
+            Orientation::Right => &["right-facing", "facing right", "朝右", "向右"],
+            Orientation::UpsideDown => &["upside-down", "upside down", "倒立", "倒放"],
+        }
+    }
+}
+
+/// 在文本中查找第一个出现的属性词，返回属性和出现位置
+pub(crate) fn find_word<T: Copy>(text: &str, values: &[T], words: impl Fn(&T) -> &'static [&'static str]) -> Option<(T, usize)> {
+    values
+        .iter()
+        .flat_map(|v| words(v).iter().filter_map(move |w| text.find(w).map(|pos| (*v, pos, w.len()))))
+        // 同一位置优先匹配更长的词，例如 "红色" 优先于 "红"
+        .min_by_key(|&(_, pos, len)| (pos, std::cmp::Reverse(len)))
+        .map(|(v, pos, _)| (v, pos))
+}
+
+pub fn parse_color(text: &str) -> Option<Color> {
+    find_word(&text.to_lowercase(), &Color::ALL, Color::words).map(|(c, _)| c)
+}
+
+pub fn parse_orientation(text: &str) -> Option<Orientation> {
+    find_word(&text.to_lowercase(), &Orientation::ALL, Orientation::words).map(|(o, _)| o)
+}
+
+/// 单个目标的属性
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attributes {
+    pub color: Color,
+    pub color_confidence: f32,
+    pub orientation: Orientation,
+    pub orientation_confidence: f32,
+}
+
+/// 提示词中要求的属性，例如 "请依次点击红色的字"
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct AttributeQuery {
+    pub color: Option<Color>,
+    pub orientation: Option<Orientation>,
+}
+
+impl AttributeQuery {
+    pub fn parse(prompt: &str) -> Self {
+        AttributeQuery {
+            color: parse_color(prompt),
+            orientation: parse_orientation(prompt),
+        }
+    }
+
+    /// 提示词中没有任何属性
+    pub fn is_empty(&self) -> bool {
+        self.color.is_none() && self.orientation.is_none()
+    }
+
+    pub fn matches(&self, attributes: &Attributes) -> bool {
+        self.color.is_none_or(|c| c == attributes.color) && self.orientation.is_none_or(|o| o == attributes.orientation)
+    }
+}
+
+mod classifier {
+    use super::{Attributes, Color, Orientation};
+    use crate::backend::InferenceBackend;
+    use image::RgbImage;
+    use image::imageops::FilterType;
+    use ndarray::{Array4, Axis, Ix2};
+    use std::error::Error;
+    use std::sync::Arc;
+
+    /// 属性分类模型，输入 (N, 3, 64, 64)，第一个输出为颜色logits，第二个输出为朝向logits
+    #[derive(Debug)]
+    pub struct AttributeClassifier {
+        backend: Arc<dyn InferenceBackend>,
+    }
+
+    const CROP_SIZE: u32 = 64;
+
+    fn softmax_argmax(row: ndarray::ArrayView1<f32>) -> (usize, f32) {
+        let max = row.fold(f32::NEG_INFINITY, |a, &b| a.max(b));
+        let sum: f32 = row.iter().map(|x| (x - max).exp()).sum();
+        let (best, &value) = row
+            .indexed_iter()
+            .fold((0, &f32::NEG_INFINITY), |acc, (i, v)| if v > acc.1 { (i, v) } else { acc });
+        (best, (value - max).exp() / sum)
+    }
+
+    impl AttributeClassifier {
+        pub fn new(backend: Arc<dyn InferenceBackend>) -> Self {
+            AttributeClassifier { backend }
+        }
+
+        pub fn backend(&self) -> &dyn InferenceBackend {
+            self.backend.as_ref()
+        }
+
+        pub fn classify(&self, crops: &[RgbImage]) -> Result<Vec<Attributes>, Box<dyn Error>> {
+            if crops.is_empty() {
+                return Ok(vec![]);
+            }
+            let size = CROP_SIZE as usize;
+            let mut batch = Array4::<f32>::zeros((crops.len(), 3, size, size));
+            for (i, crop) in crops.iter().enumerate() {
+                let resized = image::imageops::resize(crop, CROP_SIZE, CROP_SIZE, FilterType::Triangle);
+                for (x, y, pixel) in resized.enumerate_pixels() {
+                    for c in 0..3 {
+                        batch[[i, c, y as usize, x as usize]] = pixel[c] as f32 / 255.0;
+                    }
+                }
+            }
+            let mut outputs = self.backend.run_ordered(batch)?.into_iter();
+            let colors = outputs.next().ok_or("属性模型缺少颜色输出")?.into_f32()?.into_dimensionality::<Ix2>()?;
+            let orientations = outputs.next().ok_or("属性模型缺少朝向输出")?.into_f32()?.into_dimensionality::<Ix2>()?;
+            Ok(colors
+                .axis_iter(Axis(0))
+                .zip(orientations.axis_iter(Axis(0)))
+                .map(|(color, orientation)| {
+                    let (c, color_confidence) = softmax_argmax(color);
+                    let (o, orientation_confidence) = softmax_argmax(orientation);
+                    Attributes {
+                        color: Color::ALL[c.min(Color::ALL.len() - 1)],
+                        color_confidence,
+                        orientation: Orientation::ALL[o.min(Orientation::ALL.len() - 1)],
+                        orientation_confidence,
+                    }
+                })
+                .collect())
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use super::Audio;
+use super::fft::fft;
+use ndarray::Array2;
+use std::error::Error;
+
+/// log-mel频谱参数
+#[derive(Debug, Clone)]
+pub struct MelConfig {
+    pub sample_rate: u32,
+    /// FFT长度，必须是2的幂
+    pub n_fft: usize,
+    /// 帧移(采样点数)
+    pub hop_length: usize,
+    pub n_mels: usize,
+}
+
+impl Default for MelConfig {
+    fn default() -> Self {
+        MelConfig {
+            sample_rate: 16000,
+            n_fft: 512,
+            hop_length: 160,
+            n_mels: 64,
+        }
+    }
+}
+
+impl MelConfig {
+    /// 检查参数，帧移或FFT长度为0时无法分帧
+    pub fn validate(&self) -> Result<(), Box<dyn Error>> {
+        if self.hop_length == 0 {
+            return Err("mel频谱的帧移不能为0".into());
+        }
+        if !self.n_fft.is_power_of_two() {
+            return Err(format!("FFT长度 {} 不是2的幂", self.n_fft).into());
+        }
+        if self.sample_rate == 0 || self.n_mels == 0 {
+            return Err("mel频谱的采样率与通道数不能为0".into());
+        }
+        Ok(())
+    }
+}
+
+fn hz_to_mel(hz: f32) -> f32 {
+    2595.0 * (1.0 + hz / 700.0).log10()
+}
+
+fn mel_to_hz(mel: f32) -> f32 {
+    700.0 * (10f32.powf(mel / 2595.0) - 1.0)
+}
+
+/// 三角mel滤波器组，(n_mels, n_fft / 2 + 1)
+fn mel_filters(config: &MelConfig) -> Array2<f32> {
+    let bins = config.n_fft / 2 + 1;
+    let max_mel = hz_to_mel(config.sample_rate as f32 / 2.0);
+    let points: Vec<f32> = (0..config.n_mels + 2)
+        .map(|i| mel_to_hz(max_mel * i as f32 / (config.n_mels + 1) as f32) * config.n_fft as f32 / config.sample_rate as f32)
+        .collect();
+    let mut filters = Array2::<f32>::zeros((config.n_mels, bins));
+    for m in 0..config.n_mels {
+        let (left, center, right) = (points[m], points[m + 1], points[m + 2]);
+        for bin in 0..bins {
+            let f = bin as f32;
+            let weight = if f >= left && f <= center && center > left {
+                (f - left) / (center - left)
+            } else if f > center && f <= right && right > center {
+                (right - f) / (right - center)
+            } else {
+                0.0
+            };
+            filters[[m, bin]] = weight;
+        }
+    }
+    filters
+}
+
+/// 计算log-mel频谱，输出 (n_mels, 帧数)，音频会先重采样到 `config.sample_rate`
+pub fn log_mel_spectrogram(audio: &Audio, config: &MelConfig) -> Result<Array2<f32>, Box<dyn Error>> {
+    config.validate()?;
+    let audio = audio.resample(config.sample_rate);
+    let n_fft = config.n_fft;
+    let bins = n_fft / 2 + 1;
+    let frames = audio.samples.len().saturating_sub(n_fft) / config.hop_length + 1;
+    let window: Vec<f32> = (0..n_fft)
+        .map(|i| 0.5 - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / n_fft as f32).cos())
+        .collect();
+    let mut power = Array2::<f32>::zeros((bins, frames));
+    let mut re = vec![0.0; n_fft];
+    let mut im = vec![0.0; n_fft];
+    for frame in 0..frames {
+        let start = frame * config.hop_length;
+        for i in 0..n_fft {
+            re[i] = audio.samples.get(start + i).copied().unwrap_or(0.0) * window[i];
+            im[i] = 0.0;
+        }
+        fft(&mut re, &mut im);
+        for bin in 0..bins {
+            power[[bin, frame]] = re[bin] * re[bin] + im[bin] * im[bin];
+        }
+    }
+    Ok(mel_filters(config).dot(&power).mapv(|x| (x + 1e-6).ln()))
+}
@@ -0,0 +1,102 @@
+//! 音频验证码的解码与特征提取
+
+mod fft;
+mod mel;
+
+pub use mel::{MelConfig, log_mel_spectrogram};
+
+use crate::error::CaptchaError;
+use std::io::Cursor;
+
+/// 单声道音频，采样值归一化到[-1, 1]
+#[derive(Debug, Clone)]
+pub struct Audio {
+    pub samples: Vec<f32>,
+    pub sample_rate: u32,
+}
+
+impl Audio {
+    /// 线性插值重采样
+    pub fn resample(&self, sample_rate: u32) -> Audio {
+        if sample_rate == self.sample_rate || self.samples.is_empty() {
+            return Audio {
+                samples: self.samples.clone(),
+                sample_rate,
+            };
+        }
+        let ratio = self.sample_rate as f64 / sample_rate as f64;
+        let len = (self.samples.len() as f64 / ratio).floor() as usize;
+        let last = self.samples.len() - 1;
+        let samples = (0..len)
+            .map(|i| {
+                let pos = i as f64 * ratio;
+                let left = (pos.floor() as usize).min(last);
+                let right = (left + 1).min(last);
+                let t = (pos - left as f64) as f32;
+                self.samples[left] * (1.0 - t) + self.samples[right] * t
+            })
+            .collect();
+        Audio { samples, sample_rate }
+    }
+}
+
+/// 从WAV或MP3字节解码音频，多声道会被混合为单声道
+pub fn decode_bytes(bytes: &[u8]) -> Result<Audio, CaptchaError> {
+    if bytes.starts_with(b"RIFF") {
+        decode_wav(bytes)
+    } else {
+        decode_mp3(bytes)
+    }
+}
+
+fn decode_wav(bytes: &[u8]) -> Result<Audio, CaptchaError> {
+    let invalid = |e: hound::Error| CaptchaError::InvalidAudio(e.to_string());
+    let mut reader = hound::WavReader::new(Cursor::new(bytes)).map_err(invalid)?;
+    let spec = reader.spec();
+    let interleaved: Vec<f32> = match spec.sample_format {
+        hound::SampleFormat::Float => reader.samples::<f32>().collect::<Result<_, _>>().map_err(invalid)?,
+        hound::SampleFormat::Int => {
+            let scale = (1i64 << (spec.bits_per_sample - 1)) as f32;
+            reader
+                .samples::<i32>()
+                .map(|s| s.map(|s| s as f32 / scale))
+                .collect::<Result<_, _>>()
+                .map_err(invalid)?
+        }
+    };
+    Ok(Audio {
+        samples: downmix(&interleaved, spec.channels as usize),
+        sample_rate: spec.sample_rate,
+    })
+}
+
+fn decode_mp3(bytes: &[u8]) -> Result<Audio, CaptchaError> {
+    let mut decoder = minimp3::Decoder::new(Cursor::new(bytes));
+    let mut samples = vec![];
+    let mut sample_rate = 0;
+    loop {
+        match decoder.next_frame() {
+            Ok(frame) => {
+                sample_rate = frame.sample_rate as u32;
+                let frame_samples: Vec<f32> = frame.data.iter().map(|&s| s as f32 / 32768.0).collect();
+                samples.extend(downmix(&frame_samples, frame.channels));
+            }
+            Err(minimp3::Error::Eof) => break,
+            Err(e) => return Err(CaptchaError::InvalidAudio(e.to_string())),
+        }
+    }
+    if sample_rate == 0 {
+        return Err(CaptchaError::InvalidAudio("不是有效的WAV或MP3数据".to_string()));
+    }
+    Ok(Audio { samples, sample_rate })
+}
+
+fn downmix(interleaved: &[f32], channels: usize) -> Vec<f32> {
+    if channels <= 1 {
+        return interleaved.to_vec();
+    }
+    interleaved
+        .chunks(channels)
+        .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+        .collect()
+}
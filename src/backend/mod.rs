@@ -0,0 +1,221 @@
+//! 推理后端
+//!
+//! 验证码实现只通过 [`InferenceBackend`] 调用模型，默认使用基于ONNX Runtime的 [`OrtBackend`]，
+//! 也可以实现该trait接入tract、candle或远程推理服务，再通过
+//! [`CaptchaEnvironment::insert_backend`](crate::environment::CaptchaEnvironment::insert_backend) 注册
+
+#[cfg(feature = "native")]
+mod ort_backend;
+mod pool;
+mod reloadable;
+
+#[cfg(feature = "native")]
+pub use ort_backend::OrtBackend;
+pub use pool::{DEFAULT_POOL_BYTES, PoolStats, TensorPool};
+pub use reloadable::ReloadableBackend;
+
+use ndarray::{Array, ArrayD, ArrayViewD, Dimension};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+/// 张量元素类型
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ElementType {
+    F32,
+    I64,
+    /// 本crate不使用的其他类型
+    Other,
+}
+
+impl ElementType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ElementType::F32 => "f32",
+            ElementType::I64 => "i64",
+            ElementType::Other => "其他类型",
+        }
+    }
+}
+
+/// 模型输入或输出的描述
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TensorInfo {
+    pub name: String,
+    /// 各维度的长度，动态维度为-1
+    pub shape: Vec<i64>,
+    pub element_type: ElementType,
+}
+
+impl fmt::Display for TensorInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let dims: Vec<String> = self
+            .shape
+            .iter()
+            .map(|&d| if d < 0 { "?".to_string() } else { d.to_string() })
+            .collect();
+        write!(f, "{}[{}] {}", self.name, dims.join(", "), self.element_type.name())
+    }
+}
+
+/// 验证码实现对模型第一个输入与第一个输出的要求，构建时检查，
+/// 自定义导出的模型不符合时给出可读的错误而不是推理时才失败
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct IoSpec {
+    /// 第一个输入的维数，None时不检查
+    pub input_rank: Option<usize>,
+    pub input_type: ElementType,
+    /// 第一个输出的维数，None时不检查
+    pub output_rank: Option<usize>,
+}
+
+impl IoSpec {
+    /// 检查backend的输入输出，后端没有报告形状或类型时跳过对应的检查
+    pub fn check(&self, backend: &dyn InferenceBackend) -> Result<(), String> {
+        let mismatch = |what: &str| format!("{}，模型的{}", what, backend.describe_io());
+        let input = backend.inputs().first().ok_or_else(|| mismatch("没有输入"))?;
+        let output = backend.outputs().first().ok_or_else(|| mismatch("没有输出"))?;
+        let rank_ok = |info: &TensorInfo, rank: Option<usize>| info.shape.is_empty() || rank.is_none_or(|r| r == info.shape.len());
+        let type_ok = input.element_type == ElementType::Other || input.element_type == self.input_type;
+        if !rank_ok(input, self.input_rank) || !type_ok {
+            let rank = self.input_rank.map(|r| format!("{}维", r)).unwrap_or_default();
+            return Err(mismatch(&format!("第一个输入应为{}{}张量", rank, self.input_type.name())));
+        }
+        if !rank_ok(output, self.output_rank) {
+            return Err(mismatch(&format!("第一个输出应为{}维张量", self.output_rank.unwrap_or_default())));
+        }
+        Ok(())
+    }
+}
+
+/// 后端之间传递的张量
+#[derive(Debug, Clone, PartialEq)]
+pub enum TensorData {
+    F32(ArrayD<f32>),
+    I64(ArrayD<i64>),
+}
+
+impl TensorData {
+    pub fn shape(&self) -> &[usize] {
+        match self {
+            TensorData::F32(a) => a.shape(),
+            TensorData::I64(a) => a.shape(),
+        }
+    }
+
+    pub fn element_type(&self) -> ElementType {
+        match self {
+            TensorData::F32(_) => ElementType::F32,
+            TensorData::I64(_) => ElementType::I64,
+        }
+    }
+
+    pub fn as_f32(&self) -> Result<ArrayViewD<'_, f32>, Box<dyn Error>> {
+        match self {
+            TensorData::F32(a) => Ok(a.view()),
+            TensorData::I64(_) => Err("期望f32张量，实际为i64".into()),
+        }
+    }
+
+    pub fn into_f32(self) -> Result<ArrayD<f32>, Box<dyn Error>> {
+        match self {
+            TensorData::F32(a) => Ok(a),
+            TensorData::I64(_) => Err("期望f32张量，实际为i64".into()),
+        }
+    }
+}
+
+impl<D: Dimension> From<Array<f32, D>> for TensorData {
+    fn from(a: Array<f32, D>) -> Self {
+        TensorData::F32(a.into_dyn())
+    }
+}
+
+impl<D: Dimension> From<Array<i64, D>> for TensorData {
+    fn from(a: Array<i64, D>) -> Self {
+        TensorData::I64(a.into_dyn())
+    }
+}
+
+/// 推理后端：输入 名称→张量，输出 名称→张量
+pub trait InferenceBackend: Send + Sync + fmt::Debug {
+    fn inputs(&self) -> &[TensorInfo];
+
+    fn outputs(&self) -> &[TensorInfo];
+
+    fn run(&self, inputs: Vec<(String, TensorData)>) -> Result<HashMap<String, TensorData>, Box<dyn Error>>;
+
+    /// 与 [`run`](Self::run) 相同，但把用完的输入张量放回pool，输出张量从pool中分配。
+    /// 默认实现直接调用 `run`，输入张量由后端释放
+    fn run_pooled(
+        &self,
+        inputs: Vec<(String, TensorData)>,
+        pool: &TensorPool,
+    ) -> Result<HashMap<String, TensorData>, Box<dyn Error>> {
+        let _ = pool;
+        self.run(inputs)
+    }
+
+    /// 模型文件中的自定义元数据，见 [`EmbeddedMetadata`](crate::metadata::EmbeddedMetadata)
+    fn metadata(&self) -> Result<HashMap<String, String>, Box<dyn Error>> {
+        Ok(HashMap::new())
+    }
+}
+
+impl dyn InferenceBackend + '_ {
+    /// 以第一个输入运行，返回第一个输出
+    pub fn run_single(&self, input: impl Into<TensorData>) -> Result<TensorData, Box<dyn Error>> {
+        let input_name = self.inputs().first().ok_or("模型没有输入")?.name.clone();
+        let output_name = self.outputs().first().ok_or("模型没有输出")?.name.clone();
+        let mut outputs = self.run(vec![(input_name, input.into())])?;
+        take_output(&mut outputs, &output_name)
+    }
+
+    /// 与 [`run_single`](Self::run_single) 相同，使用 [`run_pooled`](InferenceBackend::run_pooled)
+    pub fn run_single_pooled(&self, input: impl Into<TensorData>, pool: &TensorPool) -> Result<TensorData, Box<dyn Error>> {
+        let input_name = self.inputs().first().ok_or("模型没有输入")?.name.clone();
+        let output_name = self.outputs().first().ok_or("模型没有输出")?.name.clone();
+        let mut outputs = self.run_pooled(vec![(input_name, input.into())], pool)?;
+        take_output(&mut outputs, &output_name)
+    }
+
+    /// 以第一个输入运行，按 [`outputs`](InferenceBackend::outputs) 的顺序返回全部输出
+    pub fn run_ordered(&self, input: impl Into<TensorData>) -> Result<Vec<TensorData>, Box<dyn Error>> {
+        let input_name = self.inputs().first().ok_or("模型没有输入")?.name.clone();
+        let mut outputs = self.run(vec![(input_name, input.into())])?;
+        self.outputs().iter().map(|info| take_output(&mut outputs, &info.name)).collect()
+    }
+
+    /// 全部输入输出的名称与形状，用于错误信息
+    pub fn describe_io(&self) -> String {
+        let join = |infos: &[TensorInfo]| infos.iter().map(|info| info.to_string()).collect::<Vec<_>>().join(", ");
+        format!("输入: {}；输出: {}", join(self.inputs()), join(self.outputs()))
+    }
+
+    /// 第一个输入最后一维的长度(正方形输入的边长)，动态尺寸时返回None
+    pub fn input_size(&self) -> Option<u32> {
+        self.inputs()
+            .first()
+            .and_then(|input| input.shape.last().copied())
+            .filter(|&d| d > 0)
+            .map(|d| d as u32)
+    }
+
+    /// 第一个输入第一维的长度(固定的批次大小)，动态批次时返回None
+    pub fn batch_size(&self) -> Option<usize> {
+        self.inputs()
+            .first()
+            .and_then(|input| input.shape.first().copied())
+            .filter(|&d| d > 0)
+            .map(|d| d as usize)
+    }
+}
+
+/// 取出名为name的输出，缺少时列出实际返回的输出名
+fn take_output(outputs: &mut HashMap<String, TensorData>, name: &str) -> Result<TensorData, Box<dyn Error>> {
+    outputs.remove(name).ok_or_else(|| {
+        let mut available: Vec<&str> = outputs.keys().map(String::as_str).collect();
+        available.sort_unstable();
+        format!("模型输出中缺少 {}，实际输出: {}", name, available.join(", ")).into()
+    })
+}
@@ -0,0 +1,137 @@
+use super::{ElementType, InferenceBackend, TensorData, TensorInfo, TensorPool};
+use ndarray::{ArrayD, IxDyn};
+use ort::memory::Allocator;
+use ort::session::{Session, SessionInputValue};
+use ort::tensor::TensorElementType;
+use ort::value::{Tensor, ValueType};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::error::Error;
+
+/// 基于ONNX Runtime的推理后端
+#[derive(Debug)]
+pub struct OrtBackend {
+    session: Session,
+    inputs: Vec<TensorInfo>,
+    outputs: Vec<TensorInfo>,
+}
+
+fn tensor_info(name: &str, value_type: &ValueType) -> TensorInfo {
+    TensorInfo {
+        name: name.to_string(),
+        shape: value_type.tensor_dimensions().cloned().unwrap_or_default(),
+        element_type: match value_type.tensor_type() {
+            Some(TensorElementType::Float32) => ElementType::F32,
+            Some(TensorElementType::Int64) => ElementType::I64,
+            _ => ElementType::Other,
+        },
+    }
+}
+
+impl OrtBackend {
+    pub fn new(session: Session) -> Self {
+        let inputs = session
+            .inputs
+            .iter()
+            .map(|i| tensor_info(&i.name, &i.input_type))
+            .collect();
+        let outputs = session
+            .outputs
+            .iter()
+            .map(|o| tensor_info(&o.name, &o.output_type))
+            .collect();
+        OrtBackend {
+            session,
+            inputs,
+            outputs,
+        }
+    }
+
+    pub fn session(&self) -> &Session {
+        &self.session
+    }
+}
+
+impl InferenceBackend for OrtBackend {
+    fn inputs(&self) -> &[TensorInfo] {
+        &self.inputs
+    }
+
+    fn outputs(&self) -> &[TensorInfo] {
+        &self.outputs
+    }
+
+    fn run(&self, inputs: Vec<(String, TensorData)>) -> Result<HashMap<String, TensorData>, Box<dyn Error>> {
+        let mut values: Vec<(Cow<str>, SessionInputValue)> = Vec::with_capacity(inputs.len());
+        for (name, data) in inputs {
+            let value = match data {
+                TensorData::F32(a) => Tensor::from_array(a)?.into_dyn(),
+                TensorData::I64(a) => Tensor::from_array(a)?.into_dyn(),
+            };
+            values.push((Cow::Owned(name), value.into()));
+        }
+        let outputs = self.session.run(values)?;
+        let mut res = HashMap::new();
+        for info in &self.outputs {
+            let data = match info.element_type {
+                ElementType::F32 => TensorData::F32(outputs[info.name.as_str()].try_extract_tensor::<f32>()?.to_owned()),
+                ElementType::I64 => TensorData::I64(outputs[info.name.as_str()].try_extract_tensor::<i64>()?.to_owned()),
+                ElementType::Other => continue,
+            };
+            res.insert(info.name.clone(), data);
+        }
+        Ok(res)
+    }
+
+    /// 输入复制到ONNX Runtime分配的张量后放回pool，输出复制到pool中的缓冲区，
+    /// 两边的内存都可以复用：ONNX Runtime一侧由内存池(arena)管理，本crate一侧由pool管理
+    fn run_pooled(
+        &self,
+        inputs: Vec<(String, TensorData)>,
+        pool: &TensorPool,
+    ) -> Result<HashMap<String, TensorData>, Box<dyn Error>> {
+        let allocator = Allocator::default();
+        let mut values: Vec<(Cow<str>, SessionInputValue)> = Vec::with_capacity(inputs.len());
+        for (name, data) in inputs {
+            let value = match data {
+                TensorData::F32(a) => {
+                    let shape: Vec<i64> = a.shape().iter().map(|&d| d as i64).collect();
+                    let mut tensor = Tensor::<f32>::new(&allocator, shape)?;
+                    tensor.extract_tensor_mut().assign(&a);
+                    pool.recycle(a);
+                    tensor.into_dyn()
+                }
+                TensorData::I64(a) => Tensor::from_array(a)?.into_dyn(),
+            };
+            values.push((Cow::Owned(name), value.into()));
+        }
+        let outputs = self.session.run(values)?;
+        let mut res = HashMap::new();
+        for info in &self.outputs {
+            let data = match info.element_type {
+                ElementType::F32 => {
+                    let (shape, data) = outputs[info.name.as_str()].try_extract_raw_tensor::<f32>()?;
+                    let mut buffer = pool.take(data.len());
+                    buffer.extend_from_slice(data);
+                    let shape: Vec<usize> = shape.iter().map(|&d| d as usize).collect();
+                    TensorData::F32(ArrayD::from_shape_vec(IxDyn(&shape), buffer)?)
+                }
+                ElementType::I64 => TensorData::I64(outputs[info.name.as_str()].try_extract_tensor::<i64>()?.to_owned()),
+                ElementType::Other => continue,
+            };
+            res.insert(info.name.clone(), data);
+        }
+        Ok(res)
+    }
+
+    fn metadata(&self) -> Result<HashMap<String, String>, Box<dyn Error>> {
+        let metadata = self.session.metadata()?;
+        let mut props = HashMap::new();
+        for key in metadata.custom_keys()? {
+            if let Some(value) = metadata.custom(&key)? {
+                props.insert(key, value);
+            }
+        }
+        Ok(props)
+    }
+}
@@ -0,0 +1,126 @@
+use super::TensorData;
+use ndarray::{Array, Dimension};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// 默认最多缓存的字节数
+pub const DEFAULT_POOL_BYTES: usize = 32 << 20;
+/// 最多缓存的缓冲区数，避免大量小缓冲区占满预算
+const MAX_BUFFERS: usize = 16;
+
+/// 可重复使用的f32张量缓冲区
+///
+/// 预处理的输入张量与后端返回的输出张量用完后放回池中，下一次推理直接复用，
+/// 避免持续负载下反复分配导致RSS上涨。缓存的总字节数不超过预算，由互斥锁保护，可以在多个线程之间共享
+#[derive(Debug)]
+pub struct TensorPool {
+    buffers: Mutex<Vec<Vec<f32>>>,
+    max_bytes: usize,
+    allocations: AtomicU64,
+    reuses: AtomicU64,
+}
+
+/// 缓冲区池的统计
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct PoolStats {
+    /// 没有合适的缓冲区而新分配的次数
+    pub allocations: u64,
+    /// 复用缓冲区的次数
+    pub reuses: u64,
+    /// 当前缓存的缓冲区数
+    pub pooled: usize,
+    /// 当前缓存的字节数
+    pub pooled_bytes: usize,
+}
+
+impl Default for TensorPool {
+    fn default() -> Self {
+        TensorPool::new(DEFAULT_POOL_BYTES)
+    }
+}
+
+impl TensorPool {
+    /// `max_bytes` 为最多缓存的字节数，为0时不缓存
+    pub fn new(max_bytes: usize) -> Self {
+        TensorPool {
+            buffers: Mutex::new(vec![]),
+            max_bytes,
+            allocations: AtomicU64::new(0),
+            reuses: AtomicU64::new(0),
+        }
+    }
+
+    pub fn max_bytes(&self) -> usize {
+        self.max_bytes
+    }
+
+    /// 取出容量不小于len的空缓冲区，优先使用容量最接近的
+    pub fn take(&self, len: usize) -> Vec<f32> {
+        let mut buffers = self.buffers.lock().unwrap();
+        let best = buffers
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.capacity() >= len)
+            .min_by_key(|(_, b)| b.capacity())
+            .map(|(i, _)| i);
+        match best {
+            Some(i) => {
+                self.reuses.fetch_add(1, Ordering::Relaxed);
+                let mut buffer = buffers.swap_remove(i);
+                buffer.clear();
+                buffer
+            }
+            None => {
+                self.allocations.fetch_add(1, Ordering::Relaxed);
+                Vec::with_capacity(len)
+            }
+        }
+    }
+
+    /// 全零的张量，内存来自池中的缓冲区
+    pub fn zeros<D: Dimension>(&self, shape: D) -> Array<f32, D> {
+        let len = shape.size();
+        let mut buffer = self.take(len);
+        buffer.resize(len, 0.0);
+        Array::from_shape_vec(shape, buffer).expect("缓冲区长度与形状一致")
+    }
+
+    /// 放回用完的张量，i64张量直接释放
+    pub fn recycle(&self, tensor: impl Into<TensorData>) {
+        if let TensorData::F32(array) = tensor.into() {
+            self.recycle_vec(array.into_raw_vec_and_offset().0);
+        }
+    }
+
+    /// 放回用完的缓冲区，超出预算或数量上限时释放较小的缓冲区
+    pub fn recycle_vec(&self, buffer: Vec<f32>) {
+        let bytes = buffer.capacity() * size_of::<f32>();
+        if bytes == 0 || bytes > self.max_bytes {
+            return;
+        }
+        let mut buffers = self.buffers.lock().unwrap();
+        buffers.push(buffer);
+        buffers.sort_unstable_by_key(|b| std::cmp::Reverse(b.capacity()));
+        let mut total = 0;
+        buffers.retain(|b| {
+            total += b.capacity() * size_of::<f32>();
+            total <= self.max_bytes
+        });
+        buffers.truncate(MAX_BUFFERS);
+    }
+
+    /// 释放所有缓存的缓冲区
+    pub fn clear(&self) {
+        self.buffers.lock().unwrap().clear();
+    }
+
+    pub fn stats(&self) -> PoolStats {
+        let buffers = self.buffers.lock().unwrap();
+        PoolStats {
+            allocations: self.allocations.load(Ordering::Relaxed),
+            reuses: self.reuses.load(Ordering::Relaxed),
+            pooled: buffers.len(),
+            pooled_bytes: buffers.iter().map(|b| b.capacity() * size_of::<f32>()).sum(),
+        }
+    }
+}
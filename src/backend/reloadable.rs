@@ -0,0 +1,65 @@
+use super::{InferenceBackend, TensorData, TensorInfo, TensorPool};
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::{Arc, RwLock};
+
+/// 可以在运行中替换内部模型的后端
+///
+/// 验证码实现持有的是该后端的引用，替换后下一次推理即使用新模型，正在进行的推理不受影响。
+/// 新模型的输入输出必须与原模型完全一致，否则构建时读取的输入尺寸等信息会失效
+#[derive(Debug)]
+pub struct ReloadableBackend {
+    current: RwLock<Arc<dyn InferenceBackend>>,
+    inputs: Vec<TensorInfo>,
+    outputs: Vec<TensorInfo>,
+}
+
+impl ReloadableBackend {
+    pub fn new(backend: Arc<dyn InferenceBackend>) -> Self {
+        ReloadableBackend {
+            inputs: backend.inputs().to_vec(),
+            outputs: backend.outputs().to_vec(),
+            current: RwLock::new(backend),
+        }
+    }
+
+    /// 当前使用的后端
+    pub fn current(&self) -> Arc<dyn InferenceBackend> {
+        Arc::clone(&self.current.read().unwrap())
+    }
+
+    /// 替换为新的后端，返回被替换的后端
+    pub fn swap(&self, backend: Arc<dyn InferenceBackend>) -> Result<Arc<dyn InferenceBackend>, Box<dyn Error>> {
+        if backend.inputs() != self.inputs.as_slice() || backend.outputs() != self.outputs.as_slice() {
+            return Err("新模型的输入输出与当前模型不一致，需要重新构建验证码实现".into());
+        }
+        Ok(std::mem::replace(&mut *self.current.write().unwrap(), backend))
+    }
+}
+
+impl InferenceBackend for ReloadableBackend {
+    fn inputs(&self) -> &[TensorInfo] {
+        &self.inputs
+    }
+
+    fn outputs(&self) -> &[TensorInfo] {
+        &self.outputs
+    }
+
+    fn run(&self, inputs: Vec<(String, TensorData)>) -> Result<HashMap<String, TensorData>, Box<dyn Error>> {
+        // 先取出当前后端再推理，替换模型时不必等待推理结束
+        self.current().run(inputs)
+    }
+
+    fn run_pooled(
+        &self,
+        inputs: Vec<(String, TensorData)>,
+        pool: &TensorPool,
+    ) -> Result<HashMap<String, TensorData>, Box<dyn Error>> {
+        self.current().run_pooled(inputs, pool)
+    }
+
+    fn metadata(&self) -> Result<HashMap<String, String>, Box<dyn Error>> {
+        self.current().metadata()
+    }
+}
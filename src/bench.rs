@@ -0,0 +1,91 @@
+//! 性能基准辅助，统计多次识别中各阶段耗时的分布
+//!
+//! criterion基准见 `benches/`，运行 `cargo bench --features bench`
+
+use crate::timing::{Stage, Timings};
+use std::error::Error;
+use std::fmt;
+use std::time::Duration;
+
+const STAGES: [Stage; 5] = [Stage::Preprocess, Stage::Detect, Stage::Crop, Stage::Embed, Stage::Assign];
+
+/// 一组耗时的统计量
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub mean: Duration,
+    pub p50: Duration,
+    pub p95: Duration,
+    pub max: Duration,
+}
+
+impl Stats {
+    pub fn from_samples(samples: &[Duration]) -> Stats {
+        if samples.is_empty() {
+            return Stats::default();
+        }
+        let mut sorted = samples.to_vec();
+        sorted.sort();
+        let percentile = |p: f64| sorted[((sorted.len() - 1) as f64 * p).round() as usize];
+        Stats {
+            mean: sorted.iter().sum::<Duration>() / sorted.len() as u32,
+            p50: percentile(0.5),
+            p95: percentile(0.95),
+            max: sorted[sorted.len() - 1],
+        }
+    }
+}
+
+/// [`benchmark`] 的结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BenchmarkReport {
+    pub iterations: usize,
+    /// 各阶段的耗时，顺序与流程一致
+    pub stages: Vec<(Stage, Stats)>,
+    /// 各阶段耗时之和
+    pub total: Stats,
+}
+
+impl BenchmarkReport {
+    pub fn stage(&self, stage: Stage) -> Option<Stats> {
+        self.stages.iter().find(|(s, _)| *s == stage).map(|(_, stats)| *stats)
+    }
+}
+
+impl fmt::Display for BenchmarkReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}次识别", self.iterations)?;
+        writeln!(f, "{:<12}{:>12}{:>12}{:>12}{:>12}", "stage", "mean", "p50", "p95", "max")?;
+        let rows = self.stages.iter().map(|(stage, stats)| (stage.name(), stats));
+        for (name, stats) in rows.chain([("total", &self.total)]) {
+            writeln!(
+                f,
+                "{:<12}{:>12.2?}{:>12.2?}{:>12.2?}{:>12.2?}",
+                name, stats.mean, stats.p50, stats.p95, stats.max
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// 先预热一次，再执行 `iterations` 次run并统计各阶段耗时，run返回一次识别的 [`Timings`]
+///
+/// ```ignore
+/// let report = benchmark(100, || Ok(cb.run_detailed(&image)?.timings))?;
+/// println!("{}", report);
+/// ```
+pub fn benchmark(
+    iterations: usize,
+    mut run: impl FnMut() -> Result<Timings, Box<dyn Error>>,
+) -> Result<BenchmarkReport, Box<dyn Error>> {
+    run()?;
+    let mut samples = Vec::with_capacity(iterations);
+    for _ in 0..iterations {
+        samples.push(run()?);
+    }
+    let stats = |f: &dyn Fn(&Timings) -> Duration| Stats::from_samples(&samples.iter().map(f).collect::<Vec<_>>());
+    Ok(BenchmarkReport {
+        iterations,
+        stages: STAGES.iter().map(|&stage| (stage, stats(&|t| t.get(stage)))).collect(),
+        total: stats(&|t| t.total()),
+    })
+}
@@ -0,0 +1,349 @@
+//! 命令行工具：识别、测速以及输出调试标注图
+//!
+//! ```text
+//! captcha-cli solve chinese-click --image a.png --models ./models
+//! captcha-cli bench chinese-click --image a.png --iterations 50
+//! captcha-cli visualize --image a.png --output out.png
+//! captcha-cli serve --addr 0.0.0.0:8080
+//! captcha-cli inspect siamese --models ./models
+//! captcha-cli inspect my_yolo.onnx
+//! ```
+
+use captcha_breaker::environment::CaptchaEnvironment;
+use captcha_breaker::loader::{DirModelLoader, ModelLoader};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use serde_json::{Value, json};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+
+#[derive(Parser)]
+#[command(name = "captcha-cli", version, about = "多种验证码识别实现")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// 识别一张验证码并以JSON输出结果
+    Solve(SolveArgs),
+    /// 重复识别同一张验证码并输出耗时统计
+    Bench {
+        #[command(flatten)]
+        solve: SolveArgs,
+        /// 识别次数(不含预热)
+        #[arg(long, default_value_t = 20)]
+        iterations: usize,
+    },
+    /// 识别ChineseClick0验证码并保存标注图(需要debug-vis feature)
+    Visualize {
+        #[arg(long)]
+        image: PathBuf,
+        #[arg(long)]
+        output: PathBuf,
+        #[arg(long, default_value = "models")]
+        models: PathBuf,
+    },
+    /// 输出模型的输入输出、opset与元数据，并检查能否作为对应的模型使用
+    Inspect {
+        /// 模型名称(例如siamese，从模型目录加载)或ONNX文件路径
+        target: String,
+        #[arg(long, default_value = "models")]
+        models: PathBuf,
+    },
+    /// 启动HTTP服务(需要server feature)
+    #[cfg(feature = "server")]
+    Serve {
+        #[arg(long, default_value = "0.0.0.0:8080")]
+        addr: std::net::SocketAddr,
+        #[arg(long, default_value = "models")]
+        models: PathBuf,
+        /// 同时进行识别的最大请求数
+        #[arg(long)]
+        max_concurrency: Option<usize>,
+    },
+}
+
+#[derive(Copy, Clone, ValueEnum)]
+enum Kind {
+    #[cfg(feature = "chinese_click_0")]
+    ChineseClick,
+    #[cfg(feature = "text_captcha_0")]
+    Text,
+    #[cfg(feature = "slider_gap_0")]
+    SliderGap,
+    #[cfg(feature = "puzzle_restore_0")]
+    Puzzle,
+    #[cfg(feature = "tile_order_0")]
+    TileOrder,
+    #[cfg(feature = "color_click_0")]
+    ColorClick,
+    #[cfg(feature = "word_order_0")]
+    WordOrder,
+    #[cfg(feature = "space_click_0")]
+    SpaceClick,
+    #[cfg(feature = "shape_click_0")]
+    ShapeClick,
+    #[cfg(feature = "nine_grid_0")]
+    NineGrid,
+    #[cfg(feature = "audio")]
+    Audio,
+}
+
+#[derive(Args)]
+struct SolveArgs {
+    kind: Kind,
+    /// 验证码文件(图片，语音验证码为WAV/MP3)
+    #[arg(long, alias = "input")]
+    image: PathBuf,
+    /// 模型目录
+    #[arg(long, default_value = "models")]
+    models: PathBuf,
+    /// 提示词，ColorClick0、SpaceClick0、ShapeClick0与NineGrid0需要
+    #[arg(long)]
+    prompt: Option<String>,
+    /// 文本编码器的词表，NineGrid0需要
+    #[arg(long)]
+    vocab: Option<PathBuf>,
+    /// n-gram计数表，WordOrder0使用
+    #[arg(long)]
+    ngram: Option<PathBuf>,
+}
+
+type Solver = Box<dyn Fn(&[u8]) -> Result<Value, Box<dyn Error>>>;
+
+fn environment(models: &Path) -> CaptchaEnvironment {
+    CaptchaEnvironment::with_model_loader(ModelLoader::CustomModelLoader(Box::new(DirModelLoader::new(models))))
+}
+
+/// 加载对应的验证码实现，返回从文件字节识别的闭包
+fn build_solver(args: &SolveArgs) -> Result<Solver, Box<dyn Error>> {
+    let environment = environment(&args.models);
+    #[allow(unused_variables)]
+    let prompt = args.prompt.clone().unwrap_or_default();
+    match args.kind {
+        #[cfg(feature = "chinese_click_0")]
+        Kind::ChineseClick => {
+            use captcha_breaker::captcha::ChineseClick0;
+            let cb: ChineseClick0 = environment.load_captcha_breaker_warm()?;
+            Ok(Box::new(move |bytes| Ok(json!({ "points": cb.run_from_bytes(bytes)? }))))
+        }
+        #[cfg(feature = "text_captcha_0")]
+        Kind::Text => {
+            use captcha_breaker::captcha::TextCaptcha0;
+            use captcha_breaker::input::decode_bytes;
+            let cb: TextCaptcha0 = environment.load_captcha_breaker_warm()?;
+            Ok(Box::new(move |bytes| {
+                let (text, confidence) = cb.run_with_confidence(&decode_bytes(bytes)?)?;
+                Ok(json!({ "text": text, "confidence": confidence }))
+            }))
+        }
+        #[cfg(feature = "slider_gap_0")]
+        Kind::SliderGap => {
+            use captcha_breaker::captcha::SliderGap0;
+            let cb: SliderGap0 = environment.load_captcha_breaker_warm()?;
+            Ok(Box::new(move |bytes| Ok(json!({ "offset": cb.run_from_bytes(bytes)? }))))
+        }
+        #[cfg(feature = "puzzle_restore_0")]
+        Kind::Puzzle => {
+            use captcha_breaker::captcha::PuzzleRestore0;
+            let cb: PuzzleRestore0 = environment.load_captcha_breaker()?;
+            Ok(Box::new(move |bytes| {
+                let solution = cb.run_from_bytes(bytes)?;
+                Ok(json!({
+                    "rows": solution.rows,
+                    "cols": solution.cols,
+                    "arrangement": solution.arrangement,
+                    "swaps": solution.swaps,
+                }))
+            }))
+        }
+        #[cfg(feature = "tile_order_0")]
+        Kind::TileOrder => {
+            use captcha_breaker::captcha::TileOrder0;
+            let cb: TileOrder0 = environment.load_captcha_breaker()?;
+            Ok(Box::new(move |bytes| {
+                let solution = cb.run_from_bytes(bytes)?;
+                let drags: Vec<_> = solution
+                    .drags
+                    .iter()
+                    .map(|d| json!({ "from": d.from, "to": d.to, "start": d.start, "end": d.end }))
+                    .collect();
+                Ok(json!({
+                    "rows": solution.rows,
+                    "cols": solution.cols,
+                    "arrangement": solution.arrangement,
+                    "drags": drags,
+                }))
+            }))
+        }
+        #[cfg(feature = "color_click_0")]
+        Kind::ColorClick => {
+            use captcha_breaker::captcha::ColorClick0;
+            let cb: ColorClick0 = environment.load_captcha_breaker_warm()?;
+            Ok(Box::new(move |bytes| Ok(json!({ "points": cb.run_from_bytes(bytes, &prompt)? }))))
+        }
+        #[cfg(feature = "word_order_0")]
+        Kind::WordOrder => {
+            use captcha_breaker::captcha::WordOrder0;
+            use captcha_breaker::input::decode_bytes;
+            use captcha_breaker::language::NgramTable;
+            let cb: WordOrder0 = environment.load_captcha_breaker_warm()?;
+            let cb = match &args.ngram {
+                Some(path) => cb.with_language_model(NgramTable::load(path)?),
+                None => cb,
+            };
+            Ok(Box::new(move |bytes| {
+                let result = cb.run_detailed(&decode_bytes(bytes)?)?;
+                Ok(json!({ "points": result.points, "text": result.text }))
+            }))
+        }
+        #[cfg(feature = "space_click_0")]
+        Kind::SpaceClick => {
+            use captcha_breaker::captcha::SpaceClick0;
+            use captcha_breaker::input::decode_bytes;
+            let cb: SpaceClick0 = environment.load_captcha_breaker_warm()?;
+            Ok(Box::new(move |bytes| Ok(json!({ "point": cb.run(&decode_bytes(bytes)?, &prompt)? }))))
+        }
+        #[cfg(feature = "shape_click_0")]
+        Kind::ShapeClick => {
+            use captcha_breaker::captcha::ShapeClick0;
+            let cb: ShapeClick0 = environment.load_captcha_breaker_warm()?;
+            Ok(Box::new(move |bytes| Ok(json!({ "points": cb.run_from_bytes(bytes, &prompt)? }))))
+        }
+        #[cfg(feature = "nine_grid_0")]
+        Kind::NineGrid => {
+            use captcha_breaker::captcha::NineGrid0;
+            use captcha_breaker::input::decode_bytes;
+            use captcha_breaker::tokenizer::WordPieceTokenizer;
+            let vocab = args.vocab.as_ref().ok_or("NineGrid0需要通过--vocab指定词表")?;
+            let cb: NineGrid0 = environment.load_captcha_breaker_warm()?;
+            let cb = cb.with_tokenizer(WordPieceTokenizer::from_vocab_file(vocab, 52)?);
+            Ok(Box::new(move |bytes| {
+                let selection = cb.run(&decode_bytes(bytes)?, &prompt)?;
+                Ok(json!({ "cells": selection.cells, "similarities": selection.similarities }))
+            }))
+        }
+        #[cfg(feature = "audio")]
+        Kind::Audio => {
+            use captcha_breaker::captcha::AudioCaptcha0;
+            let cb: AudioCaptcha0 = environment.load_captcha_breaker_warm()?;
+            Ok(Box::new(move |bytes| Ok(json!({ "text": cb.run_from_bytes(bytes)? }))))
+        }
+    }
+}
+
+fn solve(args: &SolveArgs) -> Result<Value, Box<dyn Error>> {
+    let solver = build_solver(args)?;
+    solver(&std::fs::read(&args.image)?)
+}
+
+fn bench(args: &SolveArgs, iterations: usize) -> Result<Value, Box<dyn Error>> {
+    let solver = build_solver(args)?;
+    let bytes = std::fs::read(&args.image)?;
+    let mut durations = Vec::with_capacity(iterations);
+    for _ in 0..iterations {
+        let start = Instant::now();
+        solver(&bytes)?;
+        durations.push(start.elapsed());
+    }
+    durations.sort();
+    let millis = |d: Duration| d.as_secs_f64() * 1000.0;
+    let percentile = |p: f64| {
+        durations
+            .get(((durations.len() as f64 - 1.0) * p).round() as usize)
+            .map(|&d| millis(d))
+    };
+    let total: Duration = durations.iter().sum();
+    Ok(json!({
+        "iterations": iterations,
+        "mean_ms": (iterations > 0).then(|| millis(total) / iterations as f64),
+        "p50_ms": percentile(0.5),
+        "p95_ms": percentile(0.95),
+        "max_ms": durations.last().map(|&d| millis(d)),
+    }))
+}
+
+#[cfg(all(feature = "debug-vis", feature = "chinese_click_0"))]
+fn visualize(image: &Path, output: &Path, models: &Path) -> Result<Value, Box<dyn Error>> {
+    use captcha_breaker::captcha::ChineseClick0;
+    let cb: ChineseClick0 = environment(models).load_captcha_breaker()?;
+    let points = cb.run_visualized_to_path(&image::open(image)?, output)?;
+    Ok(json!({ "points": points, "output": output }))
+}
+
+#[cfg(not(all(feature = "debug-vis", feature = "chinese_click_0")))]
+fn visualize(_image: &Path, _output: &Path, _models: &Path) -> Result<Value, Box<dyn Error>> {
+    Err("visualize需要开启debug-vis与chinese_click_0 feature".into())
+}
+
+fn inspect(target: &str, models: &Path) -> Result<Value, Box<dyn Error>> {
+    use captcha_breaker::inspect::ModelDescription;
+    use captcha_breaker::model::Model;
+    let description = match Model::from_name(target) {
+        Some(model) => environment(models).describe_model(model)?,
+        None => ModelDescription::from_file(target)?,
+    };
+    let tensors = |infos: &[captcha_breaker::backend::TensorInfo]| -> Vec<Value> {
+        infos
+            .iter()
+            .map(|info| json!({ "name": info.name, "shape": info.shape, "dtype": info.element_type.name() }))
+            .collect()
+    };
+    let header = description.header.as_ref();
+    Ok(json!({
+        "model": description.model.map(|m| m.name()),
+        "precision": description.precision.map(|p| p.name()),
+        "inputs": tensors(&description.inputs),
+        "outputs": tensors(&description.outputs),
+        "opset": description.opset(),
+        "opsets": header.map(|h| h.opsets.iter().map(|o| json!({ "domain": o.domain, "version": o.version })).collect::<Vec<_>>()),
+        "ir_version": header.and_then(|h| h.ir_version),
+        "producer": header.and_then(|h| h.producer_name.clone()),
+        "metadata": header.map(|h| &h.metadata),
+        "problem": description.problem,
+    }))
+}
+
+#[cfg(feature = "server")]
+fn serve(addr: std::net::SocketAddr, models: &Path, max_concurrency: Option<usize>) -> Result<Value, Box<dyn Error>> {
+    use captcha_breaker::server::{ServerConfig, serve};
+    let mut config = ServerConfig {
+        addr,
+        ..Default::default()
+    };
+    if let Some(max_concurrency) = max_concurrency {
+        config.max_concurrency = max_concurrency;
+    }
+    let environment = environment(models);
+    tokio::runtime::Runtime::new()?.block_on(serve(&environment, config))?;
+    Ok(Value::Null)
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let res = match &cli.command {
+        Command::Solve(args) => solve(args),
+        Command::Bench { solve, iterations } => bench(solve, *iterations),
+        Command::Visualize { image, output, models } => visualize(image, output, models),
+        Command::Inspect { target, models } => inspect(target, models),
+        #[cfg(feature = "server")]
+        Command::Serve {
+            addr,
+            models,
+            max_concurrency,
+        } => serve(*addr, models, *max_concurrency),
+    };
+    match res {
+        Ok(value) => {
+            println!("{}", value);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("{}", json!({ "error": e.to_string() }));
+            ExitCode::FAILURE
+        }
+    }
+}
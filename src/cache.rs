@@ -0,0 +1,223 @@
+//! 答案缓存
+//!
+//! 同一张验证码图片可能被重复提交(例如客户端重试或图片池较小的站点)，[`AnswerCache`] 以图片像素的内容哈希为键
+//! 缓存识别结果，命中时直接返回，不再推理。
+//!
+//! 点选验证码通常在少量重复使用的背景上绘制不同的字，感知哈希([`hash::phash`])只保留低频信息，
+//! 背景相同而字不同的图片也会得到相同的pHash，因此默认要求像素完全相同。
+//! 图片整体重复但会被重新缩放时可以开启 [`CacheConfig::perceptual`]，按pHash近似匹配
+
+use crate::hash;
+use image::{DynamicImage, RgbImage};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CacheConfig {
+    /// 最多缓存的答案数，超过时淘汰最久没有使用的
+    pub capacity: usize,
+    /// 为true时只按pHash匹配，不要求像素相同，可以识别被缩放的图片；背景复用的点选验证码容易误命中，默认关闭
+    pub perceptual: bool,
+    /// `perceptual` 时pHash之间允许不同的位数，为0时只有pHash相同才命中
+    pub max_distance: u32,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        CacheConfig {
+            capacity: 1024,
+            perceptual: false,
+            max_distance: 0,
+        }
+    }
+}
+
+/// 缓存键
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct CacheKey {
+    /// 图片的pHash，用于 [`CacheConfig::perceptual`] 的近似匹配
+    pub phash: u64,
+    /// 尺寸与RGB像素的哈希，重新编码为无损格式时不变
+    pub content: u64,
+}
+
+/// 缓存的统计
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// 因容量不足淘汰的答案数
+    pub evictions: u64,
+    /// 当前缓存的答案数
+    pub entries: usize,
+}
+
+impl CacheStats {
+    /// 命中率，还没有查找过时为0
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 { 0.0 } else { self.hits as f64 / total as f64 }
+    }
+}
+
+#[derive(Debug)]
+struct Entry<T> {
+    answer: T,
+    last_used: u64,
+}
+
+#[derive(Debug)]
+struct State<T> {
+    entries: HashMap<CacheKey, Entry<T>>,
+    clock: u64,
+}
+
+/// 点选验证码的答案缓存，值为点击坐标
+pub type PointCache = AnswerCache<Vec<(f32, f32)>>;
+
+/// 以图片哈希为键的LRU答案缓存，由互斥锁保护，可以在多个线程之间共享
+#[derive(Debug)]
+pub struct AnswerCache<T> {
+    config: CacheConfig,
+    state: Mutex<State<T>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+}
+
+impl<T: Clone> Default for AnswerCache<T> {
+    fn default() -> Self {
+        AnswerCache::new(CacheConfig::default())
+    }
+}
+
+impl<T: Clone> AnswerCache<T> {
+    pub fn new(config: CacheConfig) -> Self {
+        AnswerCache {
+            config,
+            state: Mutex::new(State {
+                entries: HashMap::new(),
+                clock: 0,
+            }),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+        }
+    }
+
+    pub fn config(&self) -> &CacheConfig {
+        &self.config
+    }
+
+    /// 图片的缓存键
+    pub fn key(image: &DynamicImage) -> CacheKey {
+        let rgb = match image {
+            DynamicImage::ImageRgb8(rgb) => Cow::Borrowed(rgb),
+            _ => Cow::<RgbImage>::Owned(image.to_rgb8()),
+        };
+        let mut hasher = DefaultHasher::new();
+        rgb.dimensions().hash(&mut hasher);
+        rgb.as_raw().hash(&mut hasher);
+        CacheKey {
+            phash: hash::phash(image).0,
+            content: hasher.finish(),
+        }
+    }
+
+    /// 查找图片的答案，命中时更新最近使用时间
+    pub fn get(&self, image: &DynamicImage) -> Option<T> {
+        self.get_key(Self::key(image))
+    }
+
+    /// 按缓存键查找，键完全相同的优先；`perceptual` 时其次为pHash不同位数最少的
+    pub fn get_key(&self, key: CacheKey) -> Option<T> {
+        let mut state = self.state.lock().unwrap();
+        let found = if state.entries.contains_key(&key) {
+            Some(key)
+        } else if self.config.perceptual {
+            state
+                .entries
+                .keys()
+                .map(|&k| (k, (k.phash ^ key.phash).count_ones()))
+                .filter(|&(_, distance)| distance <= self.config.max_distance)
+                .min_by_key(|&(k, distance)| (distance, k.phash, k.content))
+                .map(|(k, _)| k)
+        } else {
+            None
+        };
+        let Some(found) = found else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            return None;
+        };
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        state.clock += 1;
+        let clock = state.clock;
+        let entry = state.entries.get_mut(&found).expect("查找到的键存在");
+        entry.last_used = clock;
+        Some(entry.answer.clone())
+    }
+
+    /// 缓存图片的答案
+    pub fn insert(&self, image: &DynamicImage, answer: T) {
+        self.insert_key(Self::key(image), answer)
+    }
+
+    /// 按缓存键缓存答案，已存在时覆盖
+    pub fn insert_key(&self, key: CacheKey, answer: T) {
+        if self.config.capacity == 0 {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        state.clock += 1;
+        let last_used = state.clock;
+        if !state.entries.contains_key(&key) && state.entries.len() >= self.config.capacity {
+            let oldest = state.entries.iter().min_by_key(|(_, e)| e.last_used).map(|(&k, _)| k);
+            if let Some(oldest) = oldest {
+                state.entries.remove(&oldest);
+                self.evictions.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        state.entries.insert(key, Entry { answer, last_used });
+    }
+
+    /// 命中时返回缓存的答案，否则调用solve识别并缓存成功的结果
+    pub fn get_or_try_insert_with<E>(&self, image: &DynamicImage, solve: impl FnOnce(&DynamicImage) -> Result<T, E>) -> Result<T, E> {
+        let key = Self::key(image);
+        if let Some(answer) = self.get_key(key) {
+            return Ok(answer);
+        }
+        let answer = solve(image)?;
+        self.insert_key(key, answer.clone());
+        Ok(answer)
+    }
+
+    /// 删除图片的答案，例如提交后发现答案错误
+    pub fn remove(&self, image: &DynamicImage) -> Option<T> {
+        self.state.lock().unwrap().entries.remove(&Self::key(image)).map(|e| e.answer)
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 清空缓存，统计不清零
+    pub fn clear(&self) {
+        self.state.lock().unwrap().entries.clear();
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+            entries: self.len(),
+        }
+    }
+}
@@ -0,0 +1,173 @@
+//! 模型灰度发布
+//!
+//! 新版本的模型通过 [`CaptchaEnvironment::register_model_version`](crate::environment::CaptchaEnvironment::register_model_version)
+//! 注册后，[`Canary`] 把一定比例的识别交给使用新模型的实验组，其余仍由使用当前模型的对照组处理。
+//! 每个结果都带有所用的版本，对比两组的成功率后再决定全量切换或回滚
+
+use crate::rng::SplitMix64;
+use std::error::Error;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+
+/// 识别交给了哪一组
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Arm {
+    /// 当前模型
+    Control,
+    /// 灰度中的新模型
+    Candidate,
+}
+
+impl Arm {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Arm::Control => "control",
+            Arm::Candidate => "candidate",
+        }
+    }
+}
+
+/// 带有所用版本的结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tagged<T> {
+    pub arm: Arm,
+    /// 该组的版本，由 [`Canary::new`] 给出
+    pub version: String,
+    pub value: T,
+}
+
+/// 一组的识别统计
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct ArmStats {
+    pub solves: u64,
+    /// 返回错误的识别数
+    pub failures: u64,
+}
+
+impl ArmStats {
+    /// 没有识别时为0
+    pub fn success_rate(&self) -> f32 {
+        if self.solves == 0 { 0.0 } else { (self.solves - self.failures) as f32 / self.solves as f32 }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    solves: AtomicU64,
+    failures: AtomicU64,
+}
+
+/// 按比例在对照组与实验组之间分流的一对实例，可以在多个线程之间共享
+#[derive(Debug)]
+pub struct Canary<B> {
+    arms: [(String, B); 2],
+    /// 交给实验组的百分比，以f32的位保存，运行中可以调整
+    percent: AtomicU32,
+    seed: u64,
+    rng: Mutex<SplitMix64>,
+    counters: [Counters; 2],
+}
+
+impl<B> Canary<B> {
+    /// control与candidate为 (版本, 实例)，percent为0~100
+    pub fn new(control: (String, B), candidate: (String, B), percent: f32) -> Result<Self, Box<dyn Error>> {
+        check_percent(percent)?;
+        Ok(Canary {
+            arms: [control, candidate],
+            percent: AtomicU32::new(percent.to_bits()),
+            seed: 0xca9a,
+            rng: Mutex::new(SplitMix64::new(0xca9a)),
+            counters: Default::default(),
+        })
+    }
+
+    /// 分流使用的随机数种子，同时影响 [`route_key`](Self::route_key) 的分组
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self.rng = Mutex::new(SplitMix64::new(seed));
+        self
+    }
+
+    pub fn percent(&self) -> f32 {
+        f32::from_bits(self.percent.load(Ordering::Relaxed))
+    }
+
+    /// 运行中调整交给实验组的比例，例如逐步放量或设为0紧急回滚
+    pub fn set_percent(&self, percent: f32) -> Result<(), Box<dyn Error>> {
+        check_percent(percent)?;
+        self.percent.store(percent.to_bits(), Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn arm_for(&self, sample: f32) -> Arm {
+        if sample * 100.0 < self.percent() { Arm::Candidate } else { Arm::Control }
+    }
+
+    /// 随机选择下一次识别的组
+    pub fn route(&self) -> Arm {
+        let sample = self.rng.lock().unwrap_or_else(|e| e.into_inner()).next_f32();
+        self.arm_for(sample)
+    }
+
+    /// 按key选择组，相同的key总是分到同一组，例如按会话分流，避免同一个会话中途换模型
+    pub fn route_key(&self, key: u64) -> Arm {
+        self.arm_for(SplitMix64::new(key ^ self.seed).next_f32())
+    }
+
+    /// 交给随机选择的组执行
+    pub fn run<T>(&self, f: impl FnOnce(&B) -> Result<T, Box<dyn Error>>) -> Tagged<Result<T, Box<dyn Error>>> {
+        self.run_on(self.route(), f)
+    }
+
+    /// 交给key对应的组执行，见 [`route_key`](Self::route_key)
+    pub fn run_keyed<T>(&self, key: u64, f: impl FnOnce(&B) -> Result<T, Box<dyn Error>>) -> Tagged<Result<T, Box<dyn Error>>> {
+        self.run_on(self.route_key(key), f)
+    }
+
+    /// 交给指定的组执行并计入统计
+    pub fn run_on<T>(&self, arm: Arm, f: impl FnOnce(&B) -> Result<T, Box<dyn Error>>) -> Tagged<Result<T, Box<dyn Error>>> {
+        let value = f(self.get(arm));
+        let counters = &self.counters[arm as usize];
+        counters.solves.fetch_add(1, Ordering::Relaxed);
+        if value.is_err() {
+            counters.failures.fetch_add(1, Ordering::Relaxed);
+        }
+        Tagged {
+            arm,
+            version: self.version(arm).to_string(),
+            value,
+        }
+    }
+
+    pub fn get(&self, arm: Arm) -> &B {
+        &self.arms[arm as usize].1
+    }
+
+    pub fn version(&self, arm: Arm) -> &str {
+        &self.arms[arm as usize].0
+    }
+
+    pub fn stats(&self, arm: Arm) -> ArmStats {
+        let counters = &self.counters[arm as usize];
+        ArmStats {
+            solves: counters.solves.load(Ordering::Relaxed),
+            failures: counters.failures.load(Ordering::Relaxed),
+        }
+    }
+
+    /// 取出指定组的实例，例如灰度结束后全量使用新模型
+    pub fn into_arm(self, arm: Arm) -> B {
+        let [control, candidate] = self.arms;
+        match arm {
+            Arm::Control => control.1,
+            Arm::Candidate => candidate.1,
+        }
+    }
+}
+
+fn check_percent(percent: f32) -> Result<(), Box<dyn Error>> {
+    if !(0.0..=100.0).contains(&percent) {
+        return Err(format!("灰度比例{}不在0~100之间", percent).into());
+    }
+    Ok(())
+}
@@ -0,0 +1,117 @@
+use super::CaptchaBreaker;
+use crate::audio::{self, Audio, MelConfig};
+use crate::ctc;
+use crate::backend::InferenceBackend;
+use crate::environment::CaptchaEnvironment;
+use crate::model::Model;
+use crate::warmup::warmup_backend;
+use ndarray::{Array2, Axis, Ix3};
+use std::error::Error;
+use std::sync::Arc;
+
+#[derive(Debug, Clone)]
+pub struct AudioCaptcha0Config {
+    /// 模型输出类别对应的字符，类别0为blank，类别i对应 `charset[i - 1]`
+    pub charset: Vec<char>,
+    pub mel: MelConfig,
+    /// 束宽，为1时使用贪心解码
+    pub beam_width: usize,
+}
+
+impl Default for AudioCaptcha0Config {
+    fn default() -> Self {
+        AudioCaptcha0Config {
+            charset: "0123456789abcdefghijklmnopqrstuvwxyz".chars().collect(),
+            mel: Default::default(),
+            beam_width: 1,
+        }
+    }
+}
+
+/// 语音验证码识别(log-mel + 语音识别模型 + CTC)，可以在图片识别失败时作为无障碍通道的后备方案
+#[derive(Debug)]
+pub struct AudioCaptcha0 {
+    asr: Arc<dyn InferenceBackend>,
+    config: AudioCaptcha0Config,
+}
+
+impl CaptchaBreaker for AudioCaptcha0 {
+    fn build(captcha_environment: &CaptchaEnvironment) -> Result<Self, Box<dyn Error>> {
+        let models = captcha_environment.load_models(vec![Model::AudioAsr])?;
+        Ok(AudioCaptcha0 {
+            asr: models[0].clone(),
+            config: Default::default(),
+        })
+    }
+
+    fn warmup(&self) -> Result<(), Box<dyn Error>> {
+        warmup_backend(self.asr.as_ref())
+    }
+}
+
+impl AudioCaptcha0 {
+    pub fn with_config(mut self, config: AudioCaptcha0Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn config(&self) -> &AudioCaptcha0Config {
+        &self.config
+    }
+
+    /// 识别WAV/MP3字节中朗读的字符
+    pub fn run_from_bytes(&self, bytes: &[u8]) -> Result<String, Box<dyn Error>> {
+        self.run(&audio::decode_bytes(bytes)?)
+    }
+
+    pub fn run(&self, audio: &Audio) -> Result<String, Box<dyn Error>> {
+        Ok(self.run_with_confidence(audio)?.0)
+    }
+
+    /// 识别并返回置信度
+    pub fn run_with_confidence(&self, audio: &Audio) -> Result<(String, f32), Box<dyn Error>> {
+        // 1. 提取log-mel频谱
+        let features = normalize(audio::log_mel_spectrogram(audio, &self.config.mel)?);
+        // 2. 语音识别模型推理
+        let log_probs = self.infer(features)?;
+        // 3. CTC解码
+        let (labels, confidence) = if self.config.beam_width > 1 {
+            ctc::beam_decode(&log_probs.view(), 0, self.config.beam_width)
+        } else {
+            ctc::greedy_decode(&log_probs.view(), 0)
+        };
+        let text = labels
+            .into_iter()
+            .filter_map(|label| self.config.charset.get(label - 1))
+            .collect();
+        Ok((text, confidence))
+    }
+
+    /// 输入 (n_mels, 帧数)，输出 (时间步, 类别数) 的log概率
+    fn infer(&self, features: Array2<f32>) -> Result<Array2<f32>, Box<dyn Error>> {
+        // 兼容 (1, n_mels, T) 与 (1, 1, n_mels, T) 两种输入
+        let rank = self.asr.inputs().first().map_or(3, |input| input.shape.len());
+        let output = if rank == 4 {
+            self.asr.run_single(features.insert_axis(Axis(0)).insert_axis(Axis(0)))?
+        } else {
+            self.asr.run_single(features.insert_axis(Axis(0)))?
+        };
+        let output = output.into_f32()?.into_dimensionality::<Ix3>()?;
+        let logits = if output.shape()[1] == 1 {
+            output.index_axis_move(Axis(1), 0)
+        } else {
+            output.index_axis_move(Axis(0), 0)
+        };
+        Ok(ctc::log_softmax(&logits.view()))
+    }
+}
+
+/// 按mel通道做均值方差归一化
+fn normalize(mut features: Array2<f32>) -> Array2<f32> {
+    for mut row in features.rows_mut() {
+        let mean = row.mean().unwrap_or(0.0);
+        let std = row.mapv(|x| (x - mean).powi(2)).mean().unwrap_or(0.0).sqrt().max(1e-5);
+        row.mapv_inplace(|x| (x - mean) / std);
+    }
+    features
+}
@@ -0,0 +1,1083 @@
+use super::{CaptchaBreaker, QualityPreset};
+use crate::animated::{self, FusionConfig};
+use crate::backend::{InferenceBackend, TensorPool};
+use crate::cache::PointCache;
+use crate::detection::{self, BBox, BoxOrder, Resize, YoloConfig, YoloDetector};
+use crate::drift::{DriftMonitor, DriftSample};
+use crate::capture::DatasetCapture;
+use crate::click::{ClickConfig, ClickSampler};
+use crate::environment::CaptchaEnvironment;
+use crate::enhance::EnhanceConfig;
+use crate::error::CaptchaError;
+use crate::fallback::{self, BoxFuture, FallbackAnswer, FallbackConfig, FallbackError, FallbackRequest, FallbackSolver};
+use crate::geometry::{Rect, Transform};
+use crate::hooks::Hooks;
+use crate::input;
+use crate::lap::LapJVOptions;
+use crate::model::Model;
+use crate::slo::SloGuard;
+use image::imageops::FilterType;
+use image::{DynamicImage, Rgb, RgbImage};
+use ndarray::{Array2, ArrayView2, Axis};
+use std::error::Error;
+use std::sync::Arc;
+use std::time::Instant;
+use crate::matching::{
+    self, Assignment, Calibration, DistanceMetric, MatchStrategy, SiameseConfig, SiameseEmbedder, SiameseMatcher, SinkhornConfig,
+    TemplateBank, TemplateMatch, TtaConfig,
+};
+use crate::policy::{Attempt, SolvePolicy};
+use crate::report::{Reporter, SolveReport};
+use crate::segment::{self, GlyphSegmenter, MaskConfig};
+use crate::validation::{self, Grade, Validation, ValidationConfig};
+use crate::timing::{self, Outcome, Recorder, Stage, Timings};
+use crate::warmup::warmup_backends;
+
+/// 模型输入尺寸无法从模型读取时使用的默认值
+const DEFAULT_INPUT_SIZE: u32 = 384;
+
+/// 特征提取前过滤答案框，返回每个答案框是否保留，图像块与答案框一一对应
+pub(crate) type AnswerFilter<'a> = &'a dyn Fn(&[BBox], &[RgbImage]) -> Result<Vec<bool>, Box<dyn Error>>;
+
+#[derive(Debug, Clone)]
+pub struct ChineseClick0Config {
+    pub quality: QualityPreset,
+    /// 问题与答案特征之间的距离度量
+    pub metric: DistanceMetric,
+    /// 分配的求解方式，追求速度时可以使用贪心匹配
+    pub strategy: MatchStrategy,
+    /// 求解分配时LAPJV的数值选项，特征数值较大时可以放宽容差或提升到f64
+    pub lapjv: LapJVOptions,
+    /// `strategy` 为 [`MatchStrategy::Sinkhorn`] 时软分配的参数
+    pub sinkhorn: SinkhornConfig,
+    /// 检测模型的配置，替换为自己微调的多类别检测模型时需要修改
+    ///
+    /// `input_size` 为None时从模型读取输入尺寸(例如320或640)，模型为动态尺寸时使用384
+    pub detector: YoloConfig,
+    /// 答案框数量上限，超过时返回 [`CaptchaError::TooManyDetections`]
+    pub max_answers: usize,
+    /// 问题框在提示区中的显示顺序，决定了点选顺序；提示文字换行时使用阅读顺序
+    pub question_order: BoxOrder,
+    /// 答案框的排列顺序，只影响 [`PipelineTrace`] 中答案框的下标
+    pub answer_order: BoxOrder,
+    /// 提示区的上边界，提示文字可以有多行
+    pub prompt_top: f32,
+    /// 检测前的图像增强，只影响检测，截取的图像块仍来自原图
+    pub enhance: EnhanceConfig,
+    /// 透明像素合成到的背景色
+    pub background: Rgb<u8>,
+    /// 点击位置，默认为答案框中心，开启随机化后在答案框内采样
+    pub click: ClickConfig,
+    /// 动图逐帧检测结果的聚合方式，见 [`run_animated`](ChineseClick0::run_animated)
+    pub animation: FusionConfig,
+    /// 设置字形库后，问题框与最近模板的距离超过该值时不给出对应的字
+    pub template_max_distance: f32,
+    /// 设置后跳过目标检测，直接截取布局中的区域匹配，见 [`FixedLayout`]
+    pub layout: Option<FixedLayout>,
+    /// 答案框少于问题框时依次尝试的补救方式，为空时返回错误
+    pub recovery: Vec<CountRecovery>,
+    /// 提取特征前去除图像块的背景，为None时不处理，见 [`MaskConfig`]
+    pub mask: Option<MaskConfig>,
+    /// 匹配时的测试时增强，每个框以多个缩放/翻转截取图像块后汇总距离，默认关闭
+    pub tta: TtaConfig,
+    /// 设置后评估每次识别的答案，等级为 [`Grade::Bad`] 时返回错误，见 [`PipelineTrace::validate`]
+    pub verify: Option<ValidationConfig>,
+}
+
+impl Default for ChineseClick0Config {
+    fn default() -> Self {
+        ChineseClick0Config {
+            quality: QualityPreset::default(),
+            metric: DistanceMetric::default(),
+            strategy: MatchStrategy::default(),
+            lapjv: LapJVOptions::default(),
+            sinkhorn: SinkhornConfig::default(),
+            detector: YoloConfig {
+                input_size: None,
+                resize: Resize::Pad,
+                confidence_threshold: 0.5,
+                ..Default::default()
+            },
+            max_answers: 10,
+            question_order: BoxOrder::ReadingOrder,
+            answer_order: BoxOrder::LeftToRight,
+            prompt_top: 344.0,
+            enhance: EnhanceConfig::default(),
+            background: input::DEFAULT_BACKGROUND,
+            click: ClickConfig::default(),
+            animation: FusionConfig::default(),
+            template_max_distance: f32::INFINITY,
+            layout: None,
+            recovery: vec![],
+            mask: None,
+            tta: TtaConfig::default(),
+            verify: None,
+        }
+    }
+}
+
+impl ChineseClick0Config {
+    /// 按质量预设调整后的配置，`quality` 较低时关闭测试时增强与答案校验
+    pub fn for_quality(&self, quality: QualityPreset) -> Self {
+        ChineseClick0Config {
+            quality,
+            tta: if quality.tta() { self.tta.clone() } else { TtaConfig::default() },
+            verify: self.verify.clone().filter(|_| quality.verify()),
+            ..self.clone()
+        }
+    }
+}
+
+/// 答案框少于问题框(例如漏检了一个字)时的补救方式
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum CountRecovery {
+    /// 用更低的置信度阈值重新检测，只补充答案区中与已有框不重叠的新框，按置信度从高到低补足数量
+    LowerThreshold { confidence_threshold: f32 },
+    /// 把面积最大的答案框沿长边一分为二，用于两个相邻的字被检测为一个框的情况，旋转框不分裂
+    SplitLargest,
+    /// 返回部分答案，[`PipelineTrace::incomplete`] 为true，没有答案的问题框移到 `missing_questions`
+    Partial,
+}
+
+/// 重新检测得到的框与已有框的交并比超过该值时视为同一个框
+const DUPLICATE_IOU: f32 = 0.3;
+
+/// 固定布局：问题与答案的位置不变的验证码直接给出截取区域，跳过目标检测
+///
+/// 区域按给出的顺序使用，不再按 `question_order`/`answer_order` 排序，问题区域的顺序即点选顺序
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FixedLayout {
+    /// 问题区域，按点选顺序排列
+    pub questions: Vec<Rect<f32>>,
+    pub answers: Vec<Rect<f32>>,
+    /// 区域坐标所在图片的尺寸，实际图片尺寸不同时按比例缩放；None时区域直接为原图坐标
+    pub image_size: Option<(u32, u32)>,
+}
+
+impl FixedLayout {
+    pub fn new(questions: Vec<Rect<f32>>, answers: Vec<Rect<f32>>) -> Self {
+        FixedLayout {
+            questions,
+            answers,
+            image_size: None,
+        }
+    }
+
+    pub fn with_image_size(mut self, image_size: (u32, u32)) -> Self {
+        self.image_size = Some(image_size);
+        self
+    }
+
+    /// 映射到尺寸为image_size的图片上的 (答案框, 问题框)，置信度为1
+    pub fn boxes(&self, image_size: (u32, u32)) -> (Vec<BBox>, Vec<BBox>) {
+        let transform = self.image_size.map_or(Transform::IDENTITY, |from| Transform::resize(from, image_size));
+        let to_boxes = |rects: &[Rect<f32>]| -> Vec<BBox> {
+            rects.iter().map(|&rect| BBox::new(transform.apply_rect(rect), 1.0, 0)).collect()
+        };
+        (to_boxes(&self.answers), to_boxes(&self.questions))
+    }
+}
+
+#[derive(Debug)]
+pub struct ChineseClick0 {
+    /// 只使用固定布局时为None
+    detector: Option<YoloDetector>,
+    matcher: SiameseMatcher,
+    config: ChineseClick0Config,
+    capture: Option<Arc<DatasetCapture>>,
+    clicks: ClickSampler,
+    templates: Option<Arc<TemplateBank>>,
+    segmenter: Option<GlyphSegmenter>,
+    cache: Option<Arc<PointCache>>,
+    drift: Option<Arc<DriftMonitor>>,
+    reporter: Option<Reporter>,
+    hooks: Hooks,
+}
+
+impl CaptchaBreaker for ChineseClick0 {
+    fn build(captcha_environment: &CaptchaEnvironment) -> Result<Self, Box<dyn Error>> {
+        let session = captcha_environment.load_models(vec![Model::Yolo11n, Model::Siamese])?;
+        let mut config = ChineseClick0Config::default();
+        if let Some(metadata) = captcha_environment.model_metadata(Model::Yolo11n) {
+            config.detector.class_names = metadata.class_labels;
+        }
+        config.detector.input_size = Some(session[0].input_size().unwrap_or(DEFAULT_INPUT_SIZE));
+        if let Some(threshold) = captcha_environment.confidence_threshold() {
+            config.detector.confidence_threshold = threshold;
+        }
+        if let Some(threshold) = captcha_environment.nms_threshold() {
+            config.detector.nms_threshold = Some(threshold);
+        }
+        let pool = Arc::new(TensorPool::default());
+        let detector = YoloDetector::new(session[0].clone()).with_pool(pool.clone());
+        Ok(Self::from_parts(captcha_environment, Some(detector), session[1].clone(), pool, &[Model::Yolo11n, Model::Siamese])
+            .with_config(config))
+    }
+
+    fn warmup(&self) -> Result<(), Box<dyn Error>> {
+        match &self.detector {
+            Some(detector) => warmup_backends(&[detector.backend(), self.matcher.backend()]),
+            None => warmup_backends(&[self.matcher.backend()]),
+        }
+    }
+}
+
+
+/// incoming中为默认值(或None、空)的输入尺寸、类别名称与阈值使用current中已解析的值
+fn resolve_detector(mut incoming: YoloConfig, current: &YoloConfig) -> YoloConfig {
+    let defaults = ChineseClick0Config::default().detector;
+    if incoming.input_size.is_none() {
+        incoming.input_size = current.input_size;
+    }
+    if incoming.class_names.is_empty() {
+        incoming.class_names = current.class_names.clone();
+    }
+    if incoming.confidence_threshold == defaults.confidence_threshold {
+        incoming.confidence_threshold = current.confidence_threshold;
+    }
+    if incoming.nms_threshold == defaults.nms_threshold {
+        incoming.nms_threshold = current.nms_threshold;
+    }
+    incoming
+}
+
+/// 识别过程中每个阶段完成后的回调，可以用来显示进度或收集各阶段的数据
+///
+/// 所有方法默认什么都不做，只需要实现关心的阶段
+#[allow(unused_variables)]
+pub trait PipelineObserver {
+    /// 检测完成，框为原图坐标
+    fn on_detections(&mut self, image: &RgbImage, detections: &[BBox]) {}
+
+    /// 问题框与答案框分离并截取图像块，均已按点选/排列顺序排序
+    fn on_crops(&mut self, question_boxes: &[BBox], ans_boxes: &[BBox], question_crops: &[RgbImage], ans_crops: &[RgbImage]) {}
+
+    /// 特征提取完成
+    fn on_features(&mut self, question_features: ArrayView2<f32>, ans_features: ArrayView2<f32>) {}
+
+    /// 分配求解完成
+    fn on_assignment(&mut self, assignment: &Assignment) {}
+
+    /// 识别完成
+    fn on_finished(&mut self, trace: &PipelineTrace) {}
+}
+
+impl PipelineObserver for () {}
+
+/// 依次回调两个observer
+impl<A: PipelineObserver, B: PipelineObserver> PipelineObserver for (A, B) {
+    fn on_detections(&mut self, image: &RgbImage, detections: &[BBox]) {
+        self.0.on_detections(image, detections);
+        self.1.on_detections(image, detections);
+    }
+
+    fn on_crops(&mut self, question_boxes: &[BBox], ans_boxes: &[BBox], question_crops: &[RgbImage], ans_crops: &[RgbImage]) {
+        self.0.on_crops(question_boxes, ans_boxes, question_crops, ans_crops);
+        self.1.on_crops(question_boxes, ans_boxes, question_crops, ans_crops);
+    }
+
+    fn on_features(&mut self, question_features: ArrayView2<f32>, ans_features: ArrayView2<f32>) {
+        self.0.on_features(question_features, ans_features);
+        self.1.on_features(question_features, ans_features);
+    }
+
+    fn on_assignment(&mut self, assignment: &Assignment) {
+        self.0.on_assignment(assignment);
+        self.1.on_assignment(assignment);
+    }
+
+    fn on_finished(&mut self, trace: &PipelineTrace) {
+        self.0.on_finished(trace);
+        self.1.on_finished(trace);
+    }
+}
+
+impl<T: PipelineObserver + ?Sized> PipelineObserver for &mut T {
+    fn on_detections(&mut self, image: &RgbImage, detections: &[BBox]) {
+        (**self).on_detections(image, detections)
+    }
+
+    fn on_crops(&mut self, question_boxes: &[BBox], ans_boxes: &[BBox], question_crops: &[RgbImage], ans_crops: &[RgbImage]) {
+        (**self).on_crops(question_boxes, ans_boxes, question_crops, ans_crops)
+    }
+
+    fn on_features(&mut self, question_features: ArrayView2<f32>, ans_features: ArrayView2<f32>) {
+        (**self).on_features(question_features, ans_features)
+    }
+
+    fn on_assignment(&mut self, assignment: &Assignment) {
+        (**self).on_assignment(assignment)
+    }
+
+    fn on_finished(&mut self, trace: &PipelineTrace) {
+        (**self).on_finished(trace)
+    }
+}
+
+/// 一个问题框的答案
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Match {
+    /// 问题框在 [`PipelineTrace::question_boxes`] 中的下标，即点选顺序
+    pub question_index: usize,
+    /// 答案框在 [`PipelineTrace::ans_boxes`] 中的下标
+    pub answer_index: usize,
+    /// 需要点选的坐标，默认为答案框中心，见 [`ChineseClick0Config::click`]
+    pub answer_point: (f32, f32),
+    /// 问题与答案特征之间的距离
+    pub distance: f32,
+}
+
+/// 一次点选的字(没有对应的模板时为None)与坐标
+pub type LabeledPoint = (Option<String>, (f32, f32));
+
+/// 点击坐标与标注后的图片，见 [`run_visualized`](ChineseClick0::run_visualized)
+#[cfg(feature = "debug-vis")]
+pub type Visualized = (Vec<(f32, f32)>, DynamicImage);
+
+/// 一次识别的中间结果
+#[derive(Debug, Clone)]
+pub struct PipelineTrace {
+    /// 答案框(按 `answer_order` 排序)
+    pub ans_boxes: Vec<BBox>,
+    /// 问题框(按 `question_order` 排序，即点选顺序)
+    pub question_boxes: Vec<BBox>,
+    /// 成本矩阵，行为问题框，列为答案框
+    pub cost_matrix: Array2<f32>,
+    /// 第i个问题框匹配的答案框下标
+    pub matches: Vec<usize>,
+    /// 第i个问题框与匹配的答案框为同一个字的概率(经过校准，不随模型版本变化)
+    pub probabilities: Vec<f32>,
+    /// 每个问题框的答案，按 `question_index` 从小到大排列
+    pub answers: Vec<Match>,
+    /// 最终需要点选的坐标，与 `answers` 中的 `answer_point` 一一对应
+    pub points: Vec<(f32, f32)>,
+    /// 设置了字形库时第i个问题框最近的模板，即点选的是哪个字；没有字形库时为空
+    pub labels: Vec<Option<TemplateMatch>>,
+    /// 按 [`CountRecovery::Partial`] 返回了部分答案，`points` 中缺少 `missing_questions` 的答案
+    pub incomplete: bool,
+    /// 没有找到答案的问题框，按点选顺序排列，不在 `question_boxes` 中
+    pub missing_questions: Vec<BBox>,
+    /// 各阶段耗时
+    pub timings: Timings,
+}
+
+impl PipelineTrace {
+    /// 评估答案质量，`image_size` 为原图尺寸
+    pub fn validate(&self, image_size: (u32, u32), config: &ValidationConfig) -> Validation {
+        let matches: Vec<Option<usize>> = self.matches.iter().copied().map(Some).collect();
+        validation::validate(&self.points, image_size, &self.cost_matrix, &matches, config)
+    }
+}
+
+impl Attempt for PipelineTrace {
+    /// 匹配距离的最大值
+    fn score(&self) -> f32 {
+        self.matches
+            .iter()
+            .enumerate()
+            .map(|(q, &a)| self.cost_matrix[[q, a]])
+            .fold(0.0, f32::max)
+    }
+
+    /// 问题框数量
+    fn count(&self) -> usize {
+        self.question_boxes.len()
+    }
+
+    fn is_complete(&self) -> bool {
+        !self.question_boxes.is_empty() && !self.incomplete
+    }
+
+    fn rescale(mut self, scale_x: f32, scale_y: f32) -> Self {
+        self.ans_boxes = self.ans_boxes.into_iter().map(|b| b.scaled(scale_x, scale_y)).collect();
+        self.question_boxes = self.question_boxes.into_iter().map(|b| b.scaled(scale_x, scale_y)).collect();
+        self.missing_questions = self.missing_questions.into_iter().map(|b| b.scaled(scale_x, scale_y)).collect();
+        for answer in self.answers.iter_mut() {
+            answer.answer_point = (answer.answer_point.0 * scale_x, answer.answer_point.1 * scale_y);
+        }
+        self.points = self.points.into_iter().map(|(x, y)| (x * scale_x, y * scale_y)).collect();
+        self
+    }
+}
+
+impl ChineseClick0 {
+    /// 只加载孪生网络，按固定布局截取问题与答案，不需要也不会下载检测模型
+    pub fn build_with_layout(captcha_environment: &CaptchaEnvironment, layout: FixedLayout) -> Result<Self, Box<dyn Error>> {
+        let session = captcha_environment.load_models(vec![Model::Siamese])?;
+        let pool = Arc::new(TensorPool::default());
+        Ok(Self::from_parts(captcha_environment, None, session[0].clone(), pool, &[Model::Siamese]).with_config(ChineseClick0Config {
+            layout: Some(layout),
+            ..Default::default()
+        }))
+    }
+
+    fn from_parts(
+        captcha_environment: &CaptchaEnvironment,
+        detector: Option<YoloDetector>,
+        siamese: Arc<dyn InferenceBackend>,
+        pool: Arc<TensorPool>,
+        models: &[Model],
+    ) -> Self {
+        let calibration = captcha_environment
+            .model_metadata(Model::Siamese)
+            .and_then(|metadata| Calibration::from_metadata(&metadata))
+            .unwrap_or_default();
+        ChineseClick0 {
+            detector,
+            matcher: SiameseMatcher::new(siamese)
+                .with_config(SiameseConfig {
+                    calibration,
+                    ..Default::default()
+                })
+                .with_pool(pool),
+            config: Default::default(),
+            capture: captcha_environment.capture().cloned(),
+            clicks: ClickSampler::default(),
+            templates: None,
+            segmenter: None,
+            cache: None,
+            drift: captcha_environment.drift_monitor().cloned(),
+            reporter: Reporter::from_environment(captcha_environment, models),
+            hooks: captcha_environment.hooks().clone(),
+        }
+    }
+
+    /// `detector` 中构建时从模型与环境得到的输入尺寸、类别名称与阈值，在新配置中仍为默认值时保留，
+    /// 因此 `..Default::default()` 只替换显式设置的字段
+    pub fn with_config(mut self, mut config: ChineseClick0Config) -> Self {
+        config.detector = resolve_detector(config.detector, &self.config.detector);
+        let matcher_config = SiameseConfig {
+            metric: config.metric,
+            strategy: config.strategy,
+            lapjv: config.lapjv,
+            sinkhorn: config.sinkhorn.clone(),
+            ..self.matcher.config().clone()
+        };
+        self.matcher = self.matcher.with_config(matcher_config);
+        self.detector = self.detector.map(|detector| detector.with_config(config.detector.clone()));
+        self.clicks = ClickSampler::new(config.click);
+        self.config = config;
+        self
+    }
+
+    pub fn config(&self) -> &ChineseClick0Config {
+        &self.config
+    }
+
+    /// 设置字形库，之后每次识别还会在 [`PipelineTrace::labels`] 中给出问题框对应的字
+    ///
+    /// 字形库的特征维度与距离度量需要与孪生网络和 `metric` 一致
+    pub fn with_templates(mut self, templates: impl Into<Arc<TemplateBank>>) -> Self {
+        self.templates = Some(templates.into());
+        self
+    }
+
+    /// 识别并返回每次点选的字与坐标，需要先设置字形库
+    pub fn run_labeled(&self, image: &DynamicImage) -> Result<Vec<LabeledPoint>, Box<dyn Error>> {
+        if self.templates.is_none() {
+            return Err("没有设置字形库".into());
+        }
+        let trace = self.solve(image, self.config.quality)?;
+        Ok(trace
+            .labels
+            .into_iter()
+            .map(|label| label.map(|m| m.label))
+            .zip(trace.points)
+            .collect())
+    }
+
+    /// 检测与孪生网络共用的张量缓冲区池，默认最多缓存 [`DEFAULT_POOL_BYTES`](crate::backend::DEFAULT_POOL_BYTES)，
+    /// 可以换成预算不同的池，或在多个实例之间共用同一个池
+    pub fn with_pool(mut self, pool: Arc<TensorPool>) -> Self {
+        self.detector = self.detector.map(|detector| detector.with_pool(pool.clone()));
+        self.matcher = self.matcher.with_pool(pool);
+        self
+    }
+
+    pub fn pool(&self) -> &Arc<TensorPool> {
+        self.matcher.pool()
+    }
+
+    /// 替换构建时从环境得到的处理钩子，见 [`Hooks`]
+    pub fn with_hooks(mut self, hooks: Hooks) -> Self {
+        self.hooks = hooks;
+        self
+    }
+
+    pub fn hooks(&self) -> &Hooks {
+        &self.hooks
+    }
+
+    /// 设置或取消固定布局，见 [`FixedLayout`]
+    pub fn with_layout(mut self, layout: Option<FixedLayout>) -> Self {
+        self.config.layout = layout;
+        self
+    }
+
+    /// 设置分割模型，`mask` 为 [`MaskMethod::Model`](crate::segment::MaskMethod::Model) 时使用
+    pub fn with_segmenter(mut self, segmenter: GlyphSegmenter) -> Self {
+        self.segmenter = Some(segmenter);
+        self
+    }
+
+    /// 与本实例共用模型的特征提取器，可以用来预先提取已知字形的特征
+    pub fn embedder(&self) -> SiameseEmbedder {
+        SiameseEmbedder::from(self.matcher.clone())
+    }
+
+    /// 设置答案缓存后 [`run`](Self::run) 遇到重复的图片直接返回缓存的坐标，
+    /// 开启点击位置随机化时重复的图片也会得到相同的坐标
+    pub fn with_cache(mut self, cache: Arc<PointCache>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    pub fn cache(&self) -> Option<&Arc<PointCache>> {
+        self.cache.as_ref()
+    }
+
+    pub fn run(&self, image: &DynamicImage) -> Result<Vec<(f32, f32)>, Box<dyn Error>> {
+        match &self.cache {
+            Some(cache) => cache.get_or_try_insert_with(image, |image| Ok(self.solve(image, self.config.quality)?.points)),
+            None => Ok(self.solve(image, self.config.quality)?.points),
+        }
+    }
+
+    /// 识别并返回每个问题框的答案，按点选顺序排列
+    pub fn run_matches(&self, image: &DynamicImage) -> Result<Vec<Match>, Box<dyn Error>> {
+        Ok(self.solve(image, self.config.quality)?.answers)
+    }
+
+    /// 识别并返回全部中间结果，便于调用方自行后处理(例如拒绝匹配不明确的答案)
+    pub fn run_detailed(&self, image: &DynamicImage) -> Result<PipelineTrace, Box<dyn Error>> {
+        self.solve(image, self.config.quality)
+    }
+
+    /// 按policy识别，匹配距离过大或问题框数量不符合预期时在扰动后的图片上重试
+    pub fn run_with_policy(&self, image: &DynamicImage, policy: &SolvePolicy) -> Result<PipelineTrace, Box<dyn Error>> {
+        policy.solve(image, |image| self.solve(image, self.config.quality))
+    }
+
+    /// 本地识别，答案的置信度(匹配概率的最小值)低于 `config.min_confidence` 或识别失败时交给solver
+    ///
+    /// 本地识别在调用时同步完成，返回的future只等待兜底识别
+    pub fn run_with_fallback<'a>(
+        &self,
+        image: &DynamicImage,
+        solver: &'a dyn FallbackSolver,
+        config: &FallbackConfig,
+    ) -> BoxFuture<'a, Result<FallbackAnswer, FallbackError>> {
+        let local = self.run_detailed(image).map(|trace| {
+            let confidence = trace.probabilities.iter().copied().reduce(f32::min).unwrap_or(0.0);
+            (trace.points, confidence)
+        });
+        fallback::resolve(local, || FallbackRequest::new("chinese_click_0", image), solver, config)
+    }
+
+    /// 识别并在每个阶段完成后回调observer
+    pub fn run_with_observer(&self, image: &DynamicImage, mut observer: impl PipelineObserver) -> Result<PipelineTrace, Box<dyn Error>> {
+        self.solve_observed(image, self.config.quality, &mut observer)
+    }
+
+    /// 识别动图：逐帧检测并聚合检测框，在融合图上截取与匹配，见 [`animated`](crate::animated)
+    pub fn run_animated(&self, frames: &[DynamicImage]) -> Result<PipelineTrace, Box<dyn Error>> {
+        let res = self.solve_animated(frames);
+        timing::record_outcome("chinese_click_0", Outcome::of(&res));
+        res
+    }
+
+    /// 解码GIF/WebP动图后识别，静态图片与 [`run_detailed`](Self::run_detailed) 相同
+    pub fn run_animated_from_bytes(&self, bytes: &[u8]) -> Result<PipelineTrace, Box<dyn Error>> {
+        let frames: Vec<DynamicImage> = animated::decode_frames(bytes)?.into_iter().map(|f| f.image).collect();
+        self.run_animated(&frames)
+    }
+
+    fn solve_animated(&self, frames: &[DynamicImage]) -> Result<PipelineTrace, Box<dyn Error>> {
+        let mut recorder = Recorder::new("chinese_click_0");
+        let fusion = &self.config.animation;
+        let frames: Vec<RgbImage> = recorder.time(Stage::Preprocess, || {
+            animated::sample_frames(frames.len(), fusion.max_frames)
+                .into_iter()
+                .map(|i| {
+                    let mut rgb = input::to_rgb8(&frames[i], self.config.background);
+                    self.hooks.preprocess(&mut rgb);
+                    rgb
+                })
+                .collect()
+        });
+        let detections = recorder.time(Stage::Detect, || {
+            frames
+                .iter()
+                .map(|frame| self.detect_objects(&self.config.enhance.apply(frame)))
+                .collect::<Result<Vec<_>, _>>()
+        })?;
+        let fused = animated::fuse_detections(&detections, fusion);
+        let composed = recorder.time(Stage::Preprocess, || animated::compose(&frames, &fused))?;
+        let bboxes = fused.into_iter().map(|f| f.bbox).collect();
+        self.solve_detected(recorder, composed, bboxes, self.config.quality, None, &mut ())
+    }
+
+    /// 使用guard当前的质量预设识别，并把耗时反馈给guard
+    pub fn run_guarded(&self, image: &DynamicImage, guard: &SloGuard) -> Result<Vec<(f32, f32)>, Box<dyn Error>> {
+        let start = Instant::now();
+        let trace = self.solve(image, guard.preset());
+        guard.observe(start.elapsed());
+        Ok(trace?.points)
+    }
+
+    fn solve(&self, image: &DynamicImage, quality: QualityPreset) -> Result<PipelineTrace, Box<dyn Error>> {
+        self.solve_observed(image, quality, &mut ())
+    }
+
+    fn solve_observed(
+        &self,
+        image: &DynamicImage,
+        quality: QualityPreset,
+        observer: &mut dyn PipelineObserver,
+    ) -> Result<PipelineTrace, Box<dyn Error>> {
+        let capture = self.capture.as_ref().and_then(|c| c.observer("chinese_click_0"));
+        let res = match capture {
+            Some(capture) => self.solve_inner(image, quality, None, &mut (observer, capture)),
+            None => self.solve_inner(image, quality, None, observer),
+        };
+        timing::record_outcome("chinese_click_0", Outcome::of(&res));
+        if let Some(reporter) = &self.reporter {
+            reporter.emit(&Self::report(reporter.start("chinese_click_0", image), &res));
+        }
+        res
+    }
+
+    fn report(report: SolveReport, res: &Result<PipelineTrace, Box<dyn Error>>) -> SolveReport {
+        let mut report = report.with_outcome(res);
+        if let Ok(trace) = res {
+            report = report.with_timings(&trace.timings);
+            report.confidences = trace.question_boxes.iter().chain(&trace.ans_boxes).map(|b| b.confidence).collect();
+            report.probabilities = trace.probabilities.clone();
+            report.answer = trace.points.clone();
+        }
+        report
+    }
+
+    /// 在特征提取前按filter过滤答案框后识别，`ans_boxes` 中只保留通过过滤的答案框
+    #[cfg(feature = "color_click_0")]
+    pub(crate) fn solve_filtered(&self, image: &DynamicImage, filter: AnswerFilter) -> Result<PipelineTrace, Box<dyn Error>> {
+        let capture = self.capture.as_ref().and_then(|c| c.observer("chinese_click_0"));
+        match capture {
+            Some(mut capture) => self.solve_inner(image, self.config.quality, Some(filter), &mut capture),
+            None => self.solve_inner(image, self.config.quality, Some(filter), &mut ()),
+        }
+    }
+
+    fn solve_inner(
+        &self,
+        image: &DynamicImage,
+        quality: QualityPreset,
+        filter: Option<AnswerFilter>,
+        observer: &mut dyn PipelineObserver,
+    ) -> Result<PipelineTrace, Box<dyn Error>> {
+        let mut recorder = Recorder::new("chinese_click_0");
+        // 1. 统一转换为RGB
+        let processed_image = recorder.time(Stage::Preprocess, || {
+            let mut rgb = input::to_rgb8(image, self.config.background);
+            self.hooks.preprocess(&mut rgb);
+            rgb
+        });
+        // 固定布局直接使用给出的区域
+        if let Some(layout) = &self.config.layout {
+            let (ans_boxes, question_boxes) = layout.boxes(processed_image.dimensions());
+            let detections: Vec<BBox> = question_boxes.iter().chain(&ans_boxes).cloned().collect();
+            observer.on_detections(&processed_image, &detections);
+            return self.solve_boxes(recorder, processed_image, (ans_boxes, question_boxes), quality, filter, observer);
+        }
+        // 2. YOLO目标检测(检测器内部会按模型输入尺寸缩放并补边)
+        let bboxes = recorder.time(Stage::Detect, || self.detect_objects(&self.config.enhance.apply(&processed_image)))?;
+        self.solve_detected(recorder, processed_image, bboxes, quality, filter, observer)
+    }
+
+    /// 检测之后的流程，processed_image为用于截取图像块的RGB图片
+    fn solve_detected(
+        &self,
+        mut recorder: Recorder,
+        processed_image: RgbImage,
+        mut bboxes: Vec<BBox>,
+        quality: QualityPreset,
+        filter: Option<AnswerFilter>,
+        observer: &mut dyn PipelineObserver,
+    ) -> Result<PipelineTrace, Box<dyn Error>> {
+        self.hooks.detections(&processed_image, &mut bboxes);
+        recorder.record_count("detections", bboxes.len());
+        if !bboxes.is_empty() {
+            let confidences = bboxes.iter().map(|b| b.confidence);
+            recorder.record_value("min_confidence", confidences.clone().fold(f32::INFINITY, f32::min));
+            recorder.record_value("mean_confidence", confidences.sum::<f32>() / bboxes.len() as f32);
+        }
+        observer.on_detections(&processed_image, &bboxes);
+        // 3. 分离答案框和问题框，分别排序
+        let boxes = recorder.time(Stage::Crop, || {
+            let (ans_boxes, question_boxes) = self.split_boxes(bboxes);
+            self.order_boxes(ans_boxes, question_boxes)
+        });
+        let boxes = self.recover_counts(&mut recorder, &processed_image, boxes)?;
+        self.solve_boxes(recorder, processed_image, boxes, quality, filter, observer)
+    }
+
+    /// 预处理并检测，只返回答案区的框(已按 `answer_order` 排序)与用于截取图像块的RGB图片，提示区中的框被丢弃；
+    /// 设置了固定布局时使用布局中的答案区域
+    #[cfg(feature = "chinese_click_1")]
+    pub(crate) fn detect_answers(&self, recorder: &mut Recorder, image: &DynamicImage) -> Result<(RgbImage, Vec<BBox>), Box<dyn Error>> {
+        let processed_image = recorder.time(Stage::Preprocess, || {
+            let mut rgb = input::to_rgb8(image, self.config.background);
+            self.hooks.preprocess(&mut rgb);
+            rgb
+        });
+        if let Some(layout) = &self.config.layout {
+            let (ans_boxes, _) = layout.boxes(processed_image.dimensions());
+            return Ok((processed_image, ans_boxes));
+        }
+        let mut bboxes = recorder.time(Stage::Detect, || self.detect_objects(&self.config.enhance.apply(&processed_image)))?;
+        self.hooks.detections(&processed_image, &mut bboxes);
+        recorder.record_count("detections", bboxes.len());
+        let (ans_boxes, _) = self.split_boxes(bboxes);
+        Ok((processed_image, detection::sort_boxes(ans_boxes, self.config.answer_order)))
+    }
+
+    /// 按 `mask` 去除背景后提取特征
+    pub(crate) fn embed_crops(&self, crops: &[&RgbImage], filter: FilterType) -> Result<Array2<f32>, Box<dyn Error>> {
+        let Some(mask) = &self.config.mask else {
+            return self.matcher.embed(crops, filter);
+        };
+        let masked = segment::mask_crops(crops, mask, self.segmenter.as_ref())?;
+        self.matcher.embed(&masked.iter().collect::<Vec<_>>(), filter)
+    }
+
+    /// 按 `tta` 截取其余视角的图像块并在同一批次中提取特征，与原图像块的特征一起汇总成本矩阵
+    fn tta_costs<'a>(
+        &self,
+        image: &RgbImage,
+        question_boxes: &[BBox],
+        ans_boxes: &[BBox],
+        identity: (ArrayView2<'a, f32>, ArrayView2<'a, f32>),
+        filter: FilterType,
+    ) -> Result<Array2<f32>, Box<dyn Error>> {
+        let views = self.config.tta.views();
+        let boxes: Vec<&BBox> = question_boxes.iter().chain(ans_boxes).collect();
+        if boxes.is_empty() {
+            return Ok(Array2::zeros((question_boxes.len(), ans_boxes.len())));
+        }
+        let crops: Vec<RgbImage> = views[1..]
+            .iter()
+            .flat_map(|view| boxes.iter().map(|bbox| view.crop(image, bbox)))
+            .collect();
+        let extra = self.embed_crops(&crops.iter().collect::<Vec<_>>(), filter)?;
+        let mut features = vec![(identity.0.view(), identity.1.view())];
+        features.extend(extra.axis_chunks_iter(Axis(0), boxes.len()).map(|chunk| chunk.split_at(Axis(0), question_boxes.len())));
+        Ok(matching::pooled_costs(&views, &features, self.matcher.config().metric, self.config.tta.pooling))
+    }
+
+    #[cfg(feature = "chinese_click_1")]
+    pub(crate) fn matcher(&self) -> &SiameseMatcher {
+        &self.matcher
+    }
+
+    /// 答案框少于问题框时按 `recovery` 依次补充答案框，直到数量足够
+    fn recover_counts(
+        &self,
+        recorder: &mut Recorder,
+        image: &RgbImage,
+        (mut ans_boxes, question_boxes): (Vec<BBox>, Vec<BBox>),
+    ) -> Result<(Vec<BBox>, Vec<BBox>), Box<dyn Error>> {
+        let initial = ans_boxes.len();
+        for &recovery in &self.config.recovery {
+            let missing = question_boxes.len().saturating_sub(ans_boxes.len());
+            if missing == 0 {
+                break;
+            }
+            match recovery {
+                CountRecovery::LowerThreshold { confidence_threshold } => {
+                    let extra = recorder.time(Stage::Detect, || {
+                        self.redetect_answers(image, confidence_threshold, &ans_boxes, &question_boxes)
+                    })?;
+                    ans_boxes.extend(extra.into_iter().take(missing));
+                }
+                CountRecovery::SplitLargest => {
+                    for _ in 0..missing {
+                        if !split_largest(&mut ans_boxes) {
+                            break;
+                        }
+                    }
+                }
+                // 在匹配之后处理
+                CountRecovery::Partial => {}
+            }
+        }
+        if ans_boxes.len() == initial {
+            return Ok((ans_boxes, question_boxes));
+        }
+        recorder.record_count("recovered_answers", ans_boxes.len() - initial);
+        Ok((detection::sort_boxes(ans_boxes, self.config.answer_order), question_boxes))
+    }
+
+    /// 以更低的阈值重新检测，返回答案区中与已有框都不重叠的新框，按置信度从高到低排列
+    fn redetect_answers(
+        &self,
+        image: &RgbImage,
+        confidence_threshold: f32,
+        ans_boxes: &[BBox],
+        question_boxes: &[BBox],
+    ) -> Result<Vec<BBox>, Box<dyn Error>> {
+        let detector = self.detector.as_ref().ok_or("没有加载检测模型，不能重新检测")?.clone().with_config(YoloConfig {
+            confidence_threshold,
+            ..self.config.detector.clone()
+        });
+        let mut bboxes = detector.detect_rgb(&self.config.enhance.apply(image))?;
+        self.hooks.detections(image, &mut bboxes);
+        let (answers, _) = self.split_boxes(bboxes);
+        let mut extra: Vec<BBox> = answers
+            .into_iter()
+            .filter(|b| ans_boxes.iter().chain(question_boxes).all(|existing| existing.iou(b) <= DUPLICATE_IOU))
+            .collect();
+        extra.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+        Ok(extra)
+    }
+
+    /// 得到答案框与问题框之后的流程，问题框已经按点选顺序排列
+    fn solve_boxes(
+        &self,
+        mut recorder: Recorder,
+        processed_image: RgbImage,
+        (mut ans_boxes, question_boxes): (Vec<BBox>, Vec<BBox>),
+        quality: QualityPreset,
+        filter: Option<AnswerFilter>,
+        observer: &mut dyn PipelineObserver,
+    ) -> Result<PipelineTrace, Box<dyn Error>> {
+        let sample = DriftSample::from_boxes(&[question_boxes.as_slice(), ans_boxes.as_slice()].concat());
+        // 截取图像块
+        let (mut ans_crops, question_crops) = recorder.time(Stage::Crop, || {
+            (self.crop_boxes(&processed_image, &ans_boxes), self.crop_boxes(&processed_image, &question_boxes))
+        });
+        if let Some(filter) = filter {
+            let keep = recorder.time(Stage::Crop, || filter(&ans_boxes, &ans_crops))?;
+            let mut flags = keep.iter().copied();
+            ans_boxes.retain(|_| flags.next().unwrap_or(false));
+            let mut flags = keep.iter().copied();
+            ans_crops.retain(|_| flags.next().unwrap_or(false));
+        }
+        recorder.record_count("questions", question_boxes.len());
+        recorder.record_count("answers", ans_boxes.len());
+        observer.on_crops(&question_boxes, &ans_boxes, &question_crops, &ans_crops);
+        self.check_counts(&ans_boxes, &question_boxes)?;
+        // 4. 孪生网络提取特征，问题与答案在同一批次中
+        let features = recorder.time(Stage::Embed, || {
+            let crops: Vec<&RgbImage> = question_crops.iter().chain(&ans_crops).collect();
+            self.embed_crops(&crops, quality.filter())
+        })?;
+        // 5. 构建成本矩阵并求解分配
+        let (question_features, ans_features) = features.view().split_at(Axis(0), question_crops.len());
+        observer.on_features(question_features, ans_features);
+        let costs = if quality.tta() && self.config.tta.is_enabled() {
+            let identity = (question_features, ans_features);
+            Some(recorder.time(Stage::Embed, || {
+                self.tta_costs(&processed_image, &question_boxes, &ans_boxes, identity, quality.filter())
+            })?)
+        } else {
+            None
+        };
+        let assignment = recorder.time(Stage::Assign, || match costs {
+            Some(costs) => self.matcher.assign_costs(costs),
+            None => self.matcher.assign_features(&question_features, &ans_features),
+        })?;
+        observer.on_assignment(&assignment);
+        let labels = match &self.templates {
+            Some(templates) => templates.lookup(&question_features, self.config.template_max_distance),
+            None => vec![],
+        };
+        // 答案框不足时没有答案的问题框
+        let (matched, missing): (Vec<usize>, Vec<usize>) =
+            (0..question_boxes.len()).partition(|&q| assignment.matches[q].is_some());
+        if !missing.is_empty() && !self.config.recovery.contains(&CountRecovery::Partial) {
+            return Err("答案框数量少于问题框".into());
+        }
+        let matches: Vec<usize> = assignment.matches.iter().flatten().copied().collect();
+        let probabilities = assignment.probabilities.iter().flatten().copied().collect();
+        let (question_boxes, missing_questions, cost_matrix, labels) = if missing.is_empty() {
+            (question_boxes, vec![], assignment.cost_matrix, labels)
+        } else {
+            let pick = |indices: &[usize]| indices.iter().map(|&q| question_boxes[q].clone()).collect::<Vec<_>>();
+            let labels = if labels.is_empty() { labels } else { matched.iter().map(|&q| labels[q].clone()).collect() };
+            (pick(&matched), pick(&missing), assignment.cost_matrix.select(Axis(0), &matched), labels)
+        };
+        recorder.record_value("total_cost", matches.iter().enumerate().map(|(q, &a)| cost_matrix[[q, a]]).sum());
+        if let Some(drift) = &self.drift {
+            let distances = matches.iter().enumerate().map(|(q, &a)| cost_matrix[[q, a]]);
+            drift.observe("chinese_click_0", sample.with_distances(distances));
+        }
+        // 6. 生成结果
+        let mut answers = self.generate_results(&ans_boxes, &matches, &cost_matrix);
+        let mut points: Vec<(f32, f32)> = answers.iter().map(|a| a.answer_point).collect();
+        self.hooks.matches(&mut points);
+        // 钩子只修改坐标时同步到answers，增删了坐标时answers保持原样
+        if points.len() == answers.len() {
+            answers.iter_mut().zip(&points).for_each(|(answer, &point)| answer.answer_point = point);
+        }
+        let trace = PipelineTrace {
+            ans_boxes,
+            question_boxes,
+            cost_matrix,
+            matches,
+            probabilities,
+            answers,
+            points,
+            labels,
+            incomplete: !missing_questions.is_empty(),
+            missing_questions,
+            timings: recorder.finish(),
+        };
+        let rejected = match self.config.verify.as_ref().filter(|_| quality.verify()) {
+            Some(config) => trace.validate(processed_image.dimensions(), config).grade == Grade::Bad,
+            None => false,
+        };
+        if rejected {
+            return Err("答案未通过校验".into());
+        }
+        observer.on_finished(&trace);
+        Ok(trace)
+    }
+
+    /// 识别并返回标注后的图片：检测框、置信度条以及问题与答案之间的连线
+    #[cfg(feature = "debug-vis")]
+    pub fn run_visualized(&self, image: &DynamicImage) -> Result<Visualized, Box<dyn Error>> {
+        use crate::visualize::{PALETTE, UNMATCHED, draw_confidence, draw_line, draw_point, draw_rect};
+
+        let trace = self.solve(image, self.config.quality)?;
+        let mut canvas = image.to_rgba8();
+        for bbox in trace.ans_boxes.iter().chain(trace.question_boxes.iter()) {
+            draw_rect(&mut canvas, bbox.rect, UNMATCHED);
+            draw_confidence(&mut canvas, bbox.rect, bbox.confidence, UNMATCHED);
+        }
+        for (i, (question, &ans_index)) in trace.question_boxes.iter().zip(&trace.matches).enumerate() {
+            let color = PALETTE[i % PALETTE.len()];
+            let ans = &trace.ans_boxes[ans_index];
+            for bbox in [question, ans] {
+                draw_rect(&mut canvas, bbox.rect, color);
+                draw_confidence(&mut canvas, bbox.rect, bbox.confidence, color);
+            }
+            draw_line(&mut canvas, question.center(), ans.center(), color);
+            draw_point(&mut canvas, trace.points[i], 3, color);
+        }
+        Ok((trace.points, DynamicImage::ImageRgba8(canvas)))
+    }
+
+    /// 识别并将标注后的图片保存到path
+    #[cfg(feature = "debug-vis")]
+    pub fn run_visualized_to_path(&self, image: &DynamicImage, path: impl AsRef<std::path::Path>) -> Result<Vec<(f32, f32)>, Box<dyn Error>> {
+        let (points, annotated) = self.run_visualized(image)?;
+        annotated.save(path)?;
+        Ok(points)
+    }
+
+    /// 从编码后的图片字节(PNG/JPEG等)识别
+    pub fn run_from_bytes(&self, bytes: &[u8]) -> Result<Vec<(f32, f32)>, Box<dyn Error>> {
+        self.run(&input::decode_bytes(bytes)?)
+    }
+
+    /// 从base64字符串识别，可以直接传入网页中的 data url
+    pub fn run_from_base64(&self, data: &str) -> Result<Vec<(f32, f32)>, Box<dyn Error>> {
+        self.run(&input::decode_base64(data)?)
+    }
+
+    /// 目标检测
+    fn detect_objects(&self, image: &RgbImage) -> Result<Vec<BBox> , Box<dyn Error>>{
+        match &self.detector {
+            Some(detector) => detector.detect_rgb(image),
+            None => Err("没有加载检测模型，只能按固定布局识别".into()),
+        }
+    }
+
+    /// 分离答案框和问题框
+    ///
+    /// 上边界在 `prompt_top` 以下的框按行聚类，每一行纵向范围内的框都是问题框，
+    /// 提示文字换行或个别字略高于分界线时仍能归入提示区
+    fn split_boxes(&self, bboxes: Vec<BBox>) -> (Vec<BBox>, Vec<BBox>) {
+        let (questions, rest): (Vec<BBox>, Vec<BBox>) =
+            bboxes.into_iter().partition(|b| b.rect.y_min >= self.config.prompt_top);
+        // 每一行的 (平均中心y, 平均高度)
+        let rows: Vec<(f32, f32)> = detection::group_rows(&questions)
+            .iter()
+            .map(|row| {
+                let n = row.len() as f32;
+                let cy = row.iter().map(|&i| questions[i].center().1).sum::<f32>() / n;
+                let height = row.iter().map(|&i| questions[i].height()).sum::<f32>() / n;
+                (cy, height)
+            })
+            .collect();
+        let (straddling, ans_boxes): (Vec<BBox>, Vec<BBox>) = rest
+            .into_iter()
+            .partition(|b| rows.iter().any(|&(cy, height)| (b.center().1 - cy).abs() <= 0.5 * height));
+        (ans_boxes, questions.into_iter().chain(straddling).collect())
+    }
+
+    /// 答案框与问题框分别排序，问题框按在提示区中的显示顺序
+    fn order_boxes(&self, ans_boxes: Vec<BBox>, question_boxes: Vec<BBox>) -> (Vec<BBox>, Vec<BBox>) {
+        (
+            detection::sort_boxes(ans_boxes, self.config.answer_order),
+            detection::sort_boxes(question_boxes, self.config.question_order),
+        )
+    }
+
+    /// 问题框或答案框为空、答案框过多时提前返回，避免在无意义的检测结果上继续匹配
+    fn check_counts(&self, ans_boxes: &[BBox], question_boxes: &[BBox]) -> Result<(), CaptchaError> {
+        if ans_boxes.is_empty() || question_boxes.is_empty() {
+            return Err(CaptchaError::NoDetections);
+        }
+        if ans_boxes.len() > self.config.max_answers {
+            return Err(CaptchaError::TooManyDetections {
+                count: ans_boxes.len(),
+                max: self.config.max_answers,
+            });
+        }
+        Ok(())
+    }
+
+    /// 截取图像块，旋转框会被摆正，避免截入相邻的字
+    pub(crate) fn crop_boxes(&self, image: &RgbImage, boxes: &[BBox]) -> Vec<RgbImage> {
+        boxes.iter().map(|bbox| detection::crop(image, bbox)).collect()
+    }
+
+    /// 按问题框顺序生成答案，`indices[q]` 为第q个问题框匹配的答案框
+    pub(crate) fn generate_results(&self, ans_boxes: &[BBox], indices: &[usize], cost_matrix: &Array2<f32>) -> Vec<Match> {
+        let points = self.clicks.sample(indices.iter().map(|&i| &ans_boxes[i]));
+        indices
+            .iter()
+            .zip(points)
+            .enumerate()
+            .map(|(question_index, (&answer_index, answer_point))| Match {
+                question_index,
+                answer_index,
+                answer_point,
+                distance: cost_matrix[[question_index, answer_index]],
+            })
+            .collect()
+    }
+}
+
+/// 把面积最大的轴对齐框沿长边一分为二，没有可以分裂的框时返回false
+fn split_largest(boxes: &mut Vec<BBox>) -> bool {
+    let Some(index) = (0..boxes.len())
+        .filter(|&i| boxes[i].angle == 0.0)
+        .max_by(|&a, &b| boxes[a].area().total_cmp(&boxes[b].area()))
+    else {
+        return false;
+    };
+    let bbox = boxes.swap_remove(index);
+    let rect = bbox.rect;
+    let (first, second) = if rect.width() >= rect.height() {
+        let x = (rect.x_min + rect.x_max) / 2.0;
+        (Rect::new(rect.x_min, rect.y_min, x, rect.y_max), Rect::new(x, rect.y_min, rect.x_max, rect.y_max))
+    } else {
+        let y = (rect.y_min + rect.y_max) / 2.0;
+        (Rect::new(rect.x_min, rect.y_min, rect.x_max, y), Rect::new(rect.x_min, y, rect.x_max, rect.y_max))
+    };
+    boxes.push(BBox { rect: first, ..bbox.clone() });
+    boxes.push(BBox { rect: second, ..bbox });
+    true
+}
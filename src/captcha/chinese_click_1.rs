@@ -0,0 +1,266 @@
+use super::{CaptchaBreaker, ChineseClick0, ChineseClick0Config, Match};
+use crate::detection::BBox;
+use crate::environment::CaptchaEnvironment;
+use crate::error::CaptchaError;
+use crate::hooks::Hooks;
+use crate::input;
+use crate::matching::{TemplateBank, cost_matrix};
+use crate::timing::{self, Outcome, Recorder, Stage, Timings};
+use image::{DynamicImage, RgbImage};
+use ndarray::{Array2, Axis};
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
+
+/// 把一个字渲染成图像块，图像块会像答案一样缩放后交给孪生网络
+pub type GlyphRenderer = Arc<dyn Fn(char) -> Result<RgbImage, Box<dyn Error>> + Send + Sync>;
+
+/// 文字提示中的字从哪里得到特征
+#[derive(Clone)]
+pub enum PromptGlyphs {
+    /// 字形库中标签为该字的模板，同一个字有多个模板(例如不同字体)时取与答案距离最小的一个
+    Templates(Arc<TemplateBank>),
+    /// 渲染成图像块后与答案图像块在同一批次中提取特征
+    Render(GlyphRenderer),
+}
+
+impl fmt::Debug for PromptGlyphs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PromptGlyphs::Templates(bank) => f.debug_tuple("Templates").field(&bank.len()).finish(),
+            PromptGlyphs::Render(_) => f.write_str("Render"),
+        }
+    }
+}
+
+/// 用字体渲染提示中的字
+#[cfg(feature = "render")]
+impl From<crate::render::FontRenderer> for PromptGlyphs {
+    fn from(renderer: crate::render::FontRenderer) -> Self {
+        PromptGlyphs::Render(Arc::new(move |c| renderer.render(c)))
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ChineseClick1Config {
+    /// 检测、匹配与点击位置的配置，与ChineseClick0相同；问题相关的配置不使用
+    pub base: ChineseClick0Config,
+}
+
+/// 识别结果
+#[derive(Debug, Clone)]
+pub struct TextClickResult {
+    /// 从提示文字中取出的字，即点选顺序
+    pub chars: Vec<char>,
+    /// 答案框(按 `answer_order` 排序)
+    pub ans_boxes: Vec<BBox>,
+    /// 成本矩阵，行为提示中的字，列为答案框
+    pub cost_matrix: Array2<f32>,
+    /// 第i个字匹配的答案框下标
+    pub matches: Vec<usize>,
+    /// 第i个字与匹配的答案框为同一个字的概率
+    pub probabilities: Vec<f32>,
+    /// 每个字的答案，`question_index` 为字在 `chars` 中的下标
+    pub answers: Vec<Match>,
+    /// 最终需要点选的坐标
+    pub points: Vec<(f32, f32)>,
+    pub timings: Timings,
+}
+
+/// 文字提示的点选验证码，例如页面上显示 "请依次点击：鸟 语 花 香"，图片中只有打乱的字
+///
+/// 答案的检测与特征提取与ChineseClick0相同，提示中的字没有图像，需要通过 [`PromptGlyphs`]
+/// 从字形库取出特征或渲染成图像块
+#[derive(Debug)]
+pub struct ChineseClick1 {
+    inner: ChineseClick0,
+    glyphs: Option<PromptGlyphs>,
+    config: ChineseClick1Config,
+}
+
+impl CaptchaBreaker for ChineseClick1 {
+    fn build(captcha_environment: &CaptchaEnvironment) -> Result<Self, Box<dyn Error>> {
+        let inner = ChineseClick0::build(captcha_environment)?;
+        let config = ChineseClick1Config {
+            base: inner.config().clone(),
+        };
+        Ok(ChineseClick1 {
+            inner,
+            glyphs: None,
+            config,
+        })
+    }
+
+    fn warmup(&self) -> Result<(), Box<dyn Error>> {
+        self.inner.warmup()
+    }
+}
+
+impl ChineseClick1 {
+    pub fn with_config(mut self, mut config: ChineseClick1Config) -> Self {
+        self.inner = self.inner.with_config(config.base.clone());
+        config.base = self.inner.config().clone();
+        self.config = config;
+        self
+    }
+
+    pub fn config(&self) -> &ChineseClick1Config {
+        &self.config
+    }
+
+    /// 设置提示中的字的特征来源，未设置时识别返回错误
+    pub fn with_glyphs(mut self, glyphs: PromptGlyphs) -> Self {
+        self.glyphs = Some(glyphs);
+        self
+    }
+
+    /// 使用字形库，字形库的特征维度与距离度量需要与孪生网络和 `metric` 一致
+    pub fn with_templates(self, templates: impl Into<Arc<TemplateBank>>) -> Self {
+        self.with_glyphs(PromptGlyphs::Templates(templates.into()))
+    }
+
+    /// 把字渲染成图像块，见 [`GlyphRenderer`]
+    pub fn with_renderer(self, renderer: impl Fn(char) -> Result<RgbImage, Box<dyn Error>> + Send + Sync + 'static) -> Self {
+        self.with_glyphs(PromptGlyphs::Render(Arc::new(renderer)))
+    }
+
+    pub fn glyphs(&self) -> Option<&PromptGlyphs> {
+        self.glyphs.as_ref()
+    }
+
+    /// 见 [`ChineseClick0::with_hooks`]
+    pub fn with_hooks(mut self, hooks: Hooks) -> Self {
+        self.inner = self.inner.with_hooks(hooks);
+        self
+    }
+
+    /// prompt为页面上的提示文字，见 [`prompt_chars`]
+    pub fn run(&self, image: &DynamicImage, prompt: &str) -> Result<Vec<(f32, f32)>, Box<dyn Error>> {
+        Ok(self.run_detailed(image, prompt)?.points)
+    }
+
+    pub fn run_from_bytes(&self, bytes: &[u8], prompt: &str) -> Result<Vec<(f32, f32)>, Box<dyn Error>> {
+        self.run(&input::decode_bytes(bytes)?, prompt)
+    }
+
+    pub fn run_from_base64(&self, data: &str, prompt: &str) -> Result<Vec<(f32, f32)>, Box<dyn Error>> {
+        self.run(&input::decode_base64(data)?, prompt)
+    }
+
+    pub fn run_detailed(&self, image: &DynamicImage, prompt: &str) -> Result<TextClickResult, Box<dyn Error>> {
+        let res = self.solve(image, &prompt_chars(prompt));
+        timing::record_outcome("chinese_click_1", Outcome::of(&res));
+        res
+    }
+
+    fn solve(&self, image: &DynamicImage, chars: &[char]) -> Result<TextClickResult, Box<dyn Error>> {
+        let glyphs = self.glyphs.as_ref().ok_or("没有设置文字提示的字形来源")?;
+        if chars.is_empty() {
+            return Err("提示文字中没有需要点选的字".into());
+        }
+        let base = &self.config.base;
+        let mut recorder = Recorder::new("chinese_click_1");
+        let (processed_image, ans_boxes) = self.inner.detect_answers(&mut recorder, image)?;
+        if ans_boxes.is_empty() {
+            return Err(CaptchaError::NoDetections.into());
+        }
+        if ans_boxes.len() > base.max_answers {
+            return Err(CaptchaError::TooManyDetections {
+                count: ans_boxes.len(),
+                max: base.max_answers,
+            }
+            .into());
+        }
+        if ans_boxes.len() < chars.len() {
+            return Err("答案框数量少于提示中的字数".into());
+        }
+        recorder.record_count("questions", chars.len());
+        recorder.record_count("answers", ans_boxes.len());
+        let ans_crops = recorder.time(Stage::Crop, || self.inner.crop_boxes(&processed_image, &ans_boxes));
+        let matcher = self.inner.matcher();
+        let filter = base.quality.filter();
+        let costs = match glyphs {
+            PromptGlyphs::Templates(bank) => {
+                let ans_features =
+                    recorder.time(Stage::Embed, || self.inner.embed_crops(&ans_crops.iter().collect::<Vec<_>>(), filter))?;
+                recorder.time(Stage::Assign, || template_costs(bank, chars, &ans_features))?
+            }
+            PromptGlyphs::Render(render) => {
+                let features = recorder.time(Stage::Embed, || {
+                    let rendered = chars.iter().map(|&c| render(c)).collect::<Result<Vec<_>, _>>()?;
+                    let crops: Vec<&RgbImage> = rendered.iter().chain(&ans_crops).collect();
+                    self.inner.embed_crops(&crops, filter)
+                })?;
+                let (question_features, ans_features) = features.view().split_at(Axis(0), chars.len());
+                cost_matrix(&question_features, &ans_features, base.metric)
+            }
+        };
+        let assignment = recorder.time(Stage::Assign, || matcher.assign_costs(costs))?;
+        let matches: Vec<usize> = assignment.matches.iter().flatten().copied().collect();
+        if matches.len() < chars.len() {
+            return Err("答案框数量少于提示中的字数".into());
+        }
+        let probabilities = assignment.probabilities.iter().flatten().copied().collect();
+        let mut answers = self.inner.generate_results(&ans_boxes, &matches, &assignment.cost_matrix);
+        let mut points: Vec<(f32, f32)> = answers.iter().map(|a| a.answer_point).collect();
+        self.inner.hooks().matches(&mut points);
+        if points.len() == answers.len() {
+            answers.iter_mut().zip(&points).for_each(|(answer, &point)| answer.answer_point = point);
+        }
+        Ok(TextClickResult {
+            chars: chars.to_vec(),
+            ans_boxes,
+            cost_matrix: assignment.cost_matrix,
+            matches,
+            probabilities,
+            answers,
+            points,
+            timings: recorder.finish(),
+        })
+    }
+}
+
+/// 每个字与答案之间的距离，为该字所有模板中的最小距离
+fn template_costs(bank: &TemplateBank, chars: &[char], ans_features: &Array2<f32>) -> Result<Array2<f32>, Box<dyn Error>> {
+    if bank.dim() != ans_features.ncols() {
+        return Err(format!("字形库的特征维度{}与孪生网络的{}不一致", bank.dim(), ans_features.ncols()).into());
+    }
+    let all = cost_matrix(&bank.features(), &ans_features.view(), bank.metric());
+    let mut costs = Array2::from_elem((chars.len(), ans_features.nrows()), f32::INFINITY);
+    for (mut row, &c) in costs.rows_mut().into_iter().zip(chars) {
+        let mut found = false;
+        for (label, template) in bank.labels().iter().zip(all.rows()) {
+            if label.chars().eq(std::iter::once(c)) {
+                found = true;
+                row.zip_mut_with(&template, |cost, &distance| *cost = cost.min(distance));
+            }
+        }
+        if !found {
+            return Err(format!("字形库中没有“{}”", c).into());
+        }
+    }
+    Ok(costs)
+}
+
+/// 从提示文字中取出需要点选的字
+///
+/// 有引号或括号时只取其中的字，否则取最后一个冒号之后的部分，空白与标点被忽略。
+/// 例如 `请依次点击：“鸟”“语”“花”` 与 `请依次点击:鸟 语 花` 都得到 `['鸟', '语', '花']`
+pub fn prompt_chars(prompt: &str) -> Vec<char> {
+    const QUOTES: [(char, char); 6] = [('“', '”'), ('「', '」'), ('『', '』'), ('【', '】'), ('‘', '’'), ('"', '"')];
+    let mut quoted = vec![];
+    let mut closing = None;
+    for c in prompt.chars() {
+        match closing {
+            Some(close) if c == close => closing = None,
+            Some(_) => quoted.push(c),
+            None => closing = QUOTES.iter().find(|&&(open, _)| open == c).map(|&(_, close)| close),
+        }
+    }
+    let text: Vec<char> = if quoted.is_empty() {
+        prompt.rsplit([':', '：']).next().unwrap_or(prompt).chars().collect()
+    } else {
+        quoted
+    };
+    text.into_iter().filter(|c| c.is_alphanumeric()).collect()
+}
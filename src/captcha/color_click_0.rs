@@ -0,0 +1,140 @@
+use super::{CaptchaBreaker, ChineseClick0, ChineseClick0Config, PipelineTrace};
+use crate::attribute::{AttributeClassifier, AttributeQuery, Attributes, Color, Orientation};
+use crate::detection::BBox;
+use crate::environment::CaptchaEnvironment;
+use crate::hooks::Hooks;
+use crate::input;
+use crate::timing::{self, Outcome};
+use image::{DynamicImage, RgbImage};
+use std::error::Error;
+
+#[derive(Debug, Clone)]
+pub struct ColorClick0Config {
+    /// 过滤之后的点选流程，与ChineseClick0相同
+    pub base: ChineseClick0Config,
+    /// 使用属性模型时，颜色或朝向的置信度低于该值的答案框不会被过滤掉
+    pub min_confidence: f32,
+}
+
+impl Default for ColorClick0Config {
+    fn default() -> Self {
+        ColorClick0Config {
+            base: ChineseClick0Config::default(),
+            min_confidence: 0.5,
+        }
+    }
+}
+
+/// 按属性点选的验证码，例如 "请依次点击红色的字"
+///
+/// 答案区中同一个字可能以不同颜色出现多次，先按提示词中的颜色(或朝向)过滤答案框，再用孪生网络匹配问题框。
+/// 默认按图像块的主色判断颜色，设置属性模型 [`with_classifier`](Self::with_classifier) 后也可以按朝向过滤
+#[derive(Debug)]
+pub struct ColorClick0 {
+    inner: ChineseClick0,
+    classifier: Option<AttributeClassifier>,
+    config: ColorClick0Config,
+}
+
+impl CaptchaBreaker for ColorClick0 {
+    fn build(captcha_environment: &CaptchaEnvironment) -> Result<Self, Box<dyn Error>> {
+        let inner = ChineseClick0::build(captcha_environment)?;
+        let config = ColorClick0Config {
+            base: inner.config().clone(),
+            ..Default::default()
+        };
+        Ok(ColorClick0 {
+            inner,
+            classifier: None,
+            config,
+        })
+    }
+
+    fn warmup(&self) -> Result<(), Box<dyn Error>> {
+        self.inner.warmup()
+    }
+}
+
+impl ColorClick0 {
+    pub fn with_config(mut self, mut config: ColorClick0Config) -> Self {
+        self.inner = self.inner.with_config(config.base.clone());
+        config.base = self.inner.config().clone();
+        self.config = config;
+        self
+    }
+
+    pub fn config(&self) -> &ColorClick0Config {
+        &self.config
+    }
+
+    /// 见 [`ChineseClick0::with_hooks`]，检测钩子在按属性过滤答案框之前调用
+    pub fn with_hooks(mut self, hooks: Hooks) -> Self {
+        self.inner = self.inner.with_hooks(hooks);
+        self
+    }
+
+    /// 使用属性模型代替主色判断，见 [`AttributeClassifier`]
+    pub fn with_classifier(mut self, classifier: AttributeClassifier) -> Self {
+        self.classifier = Some(classifier);
+        self
+    }
+
+    pub fn run(&self, image: &DynamicImage, prompt: &str) -> Result<Vec<(f32, f32)>, Box<dyn Error>> {
+        Ok(self.run_detailed(image, prompt)?.points)
+    }
+
+    pub fn run_from_bytes(&self, bytes: &[u8], prompt: &str) -> Result<Vec<(f32, f32)>, Box<dyn Error>> {
+        self.run(&input::decode_bytes(bytes)?, prompt)
+    }
+
+    pub fn run_from_base64(&self, data: &str, prompt: &str) -> Result<Vec<(f32, f32)>, Box<dyn Error>> {
+        self.run(&input::decode_base64(data)?, prompt)
+    }
+
+    /// 识别并返回全部中间结果，`ans_boxes` 中只有属性符合提示词的答案框
+    pub fn run_detailed(&self, image: &DynamicImage, prompt: &str) -> Result<PipelineTrace, Box<dyn Error>> {
+        let res = self.solve(image, AttributeQuery::parse(prompt));
+        timing::record_outcome("color_click_0", Outcome::of(&res));
+        res
+    }
+
+    fn solve(&self, image: &DynamicImage, query: AttributeQuery) -> Result<PipelineTrace, Box<dyn Error>> {
+        if query.orientation.is_some() && self.classifier.is_none() {
+            return Err("按朝向过滤需要属性模型".into());
+        }
+        let filter = |_: &[BBox], crops: &[RgbImage]| -> Result<Vec<bool>, Box<dyn Error>> {
+            if query.is_empty() {
+                return Ok(vec![true; crops.len()]);
+            }
+            let attributes = self.classify(crops)?;
+            Ok(attributes.iter().map(|a| self.keep(&query, a)).collect())
+        };
+        self.inner.solve_filtered(image, &filter)
+    }
+
+    /// 每个答案图像块的属性，没有属性模型时颜色为主色，朝向未知
+    fn classify(&self, crops: &[RgbImage]) -> Result<Vec<Attributes>, Box<dyn Error>> {
+        if let Some(classifier) = &self.classifier {
+            return classifier.classify(crops);
+        }
+        Ok(crops
+            .iter()
+            .map(|crop| Attributes {
+                color: Color::dominant(crop),
+                color_confidence: 1.0,
+                orientation: Orientation::Upright,
+                orientation_confidence: 0.0,
+            })
+            .collect())
+    }
+
+    /// 置信度过低的属性不参与过滤，交给孪生网络判断
+    fn keep(&self, query: &AttributeQuery, attributes: &Attributes) -> bool {
+        let min = self.config.min_confidence;
+        let color = query.color.is_none_or(|c| c == attributes.color || attributes.color_confidence < min);
+        let orientation = query
+            .orientation
+            .is_none_or(|o| o == attributes.orientation || attributes.orientation_confidence < min);
+        color && orientation
+    }
+}
@@ -0,0 +1,153 @@
+#[cfg(feature = "native")]
+use crate::environment::CaptchaEnvironment;
+use image::imageops::FilterType;
+#[cfg(feature = "native")]
+use std::error::Error;
+
+#[cfg(feature = "audio")]
+mod audio_captcha_0;
+#[cfg(feature = "chinese_click_0")]
+mod chinese_click_0;
+#[cfg(feature = "chinese_click_1")]
+mod chinese_click_1;
+#[cfg(feature = "color_click_0")]
+mod color_click_0;
+#[cfg(feature = "nine_grid_0")]
+mod nine_grid_0;
+#[cfg(feature = "puzzle_restore_0")]
+mod puzzle_restore_0;
+#[cfg(feature = "shape_click_0")]
+mod shape_click_0;
+#[cfg(feature = "slider_gap_0")]
+mod slider_gap_0;
+#[cfg(feature = "space_click_0")]
+mod space_click_0;
+#[cfg(feature = "text_captcha_0")]
+mod text_captcha_0;
+#[cfg(feature = "tile_order_0")]
+mod tile_order_0;
+#[cfg(feature = "word_order_0")]
+mod word_order_0;
+
+#[cfg(feature = "audio")]
+pub use audio_captcha_0::{AudioCaptcha0, AudioCaptcha0Config};
+#[cfg(feature = "chinese_click_0")]
+pub use chinese_click_0::{ChineseClick0, ChineseClick0Config, CountRecovery, FixedLayout, LabeledPoint, Match, PipelineObserver, PipelineTrace};
+#[cfg(all(feature = "chinese_click_0", feature = "debug-vis"))]
+pub use chinese_click_0::Visualized;
+#[cfg(feature = "chinese_click_1")]
+pub use chinese_click_1::{ChineseClick1, ChineseClick1Config, GlyphRenderer, PromptGlyphs, TextClickResult, prompt_chars};
+#[cfg(feature = "color_click_0")]
+pub use color_click_0::{ColorClick0, ColorClick0Config};
+#[cfg(feature = "nine_grid_0")]
+pub use nine_grid_0::{CellSelection, GridSelection, NineGrid0, NineGrid0Config};
+#[cfg(feature = "puzzle_restore_0")]
+pub use puzzle_restore_0::{PuzzleMode, PuzzleRestore0, PuzzleRestore0Config, PuzzleSolution};
+#[cfg(feature = "shape_click_0")]
+pub use shape_click_0::{DEFAULT_SHAPE_LABELS, ShapeClick0, ShapeClick0Config, ShapeClickResult, parse_targets};
+#[cfg(feature = "slider_gap_0")]
+pub use slider_gap_0::{SliderGap0, SliderGap0Config};
+#[cfg(feature = "space_click_0")]
+pub use space_click_0::{Relation, Selector, SpaceClick0, SpaceClick0Config, SpaceObject, SpaceQuery, SpaceResult};
+#[cfg(feature = "text_captcha_0")]
+pub use text_captcha_0::{CtcDecoder, DEFAULT_CHARSET, TextCaptcha0, TextCaptcha0Config};
+#[cfg(feature = "tile_order_0")]
+pub use tile_order_0::{Drag, DragMode, TileOrder0, TileOrder0Config, TileOrderSolution};
+#[cfg(feature = "word_order_0")]
+pub use word_order_0::{CharCandidate, WordOrder0, WordOrder0Config, WordOrderResult};
+
+/// 验证码实现
+///
+/// 要求 `Send + Sync`：构建后的实现只读，可以放进 `Arc` 由多个线程同时调用 `run`
+#[cfg(feature = "native")]
+pub trait CaptchaBreaker: Send + Sync {
+    fn build(captcha_environment: &CaptchaEnvironment) -> Result<Self, Box<dyn Error>>
+    where
+        Self: Sized;
+
+    /// 用全零输入对每个模型推理一次，把首次推理的图优化开销提前到启动时
+    fn warmup(&self) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+}
+
+/// 编译时检查，某个实现的字段不再是 `Send + Sync` 时在这里报错
+#[allow(dead_code)]
+const fn assert_send_sync<T: Send + Sync>() {}
+
+const _: () = {
+    #[cfg(feature = "audio")]
+    assert_send_sync::<AudioCaptcha0>();
+    #[cfg(feature = "chinese_click_0")]
+    assert_send_sync::<ChineseClick0>();
+    #[cfg(feature = "chinese_click_1")]
+    assert_send_sync::<ChineseClick1>();
+    #[cfg(feature = "color_click_0")]
+    assert_send_sync::<ColorClick0>();
+    #[cfg(feature = "nine_grid_0")]
+    assert_send_sync::<NineGrid0>();
+    #[cfg(feature = "puzzle_restore_0")]
+    assert_send_sync::<PuzzleRestore0>();
+    #[cfg(feature = "shape_click_0")]
+    assert_send_sync::<ShapeClick0>();
+    #[cfg(feature = "slider_gap_0")]
+    assert_send_sync::<SliderGap0>();
+    #[cfg(feature = "space_click_0")]
+    assert_send_sync::<SpaceClick0>();
+    #[cfg(feature = "text_captcha_0")]
+    assert_send_sync::<TextCaptcha0>();
+    #[cfg(feature = "tile_order_0")]
+    assert_send_sync::<TileOrder0>();
+    #[cfg(feature = "word_order_0")]
+    assert_send_sync::<WordOrder0>();
+    #[cfg(feature = "native")]
+    assert_send_sync::<CaptchaEnvironment>();
+};
+
+/// 识别质量预设，越低越快
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum QualityPreset {
+    Fast,
+    Balanced,
+    #[default]
+    High,
+}
+
+impl QualityPreset {
+    /// 降低一档，已是最低档时返回None
+    pub fn downgrade(self) -> Option<Self> {
+        match self {
+            QualityPreset::Fast => None,
+            QualityPreset::Balanced => Some(QualityPreset::Fast),
+            QualityPreset::High => Some(QualityPreset::Balanced),
+        }
+    }
+
+    /// 提高一档，已是最高档时返回None
+    pub fn upgrade(self) -> Option<Self> {
+        match self {
+            QualityPreset::Fast => Some(QualityPreset::Balanced),
+            QualityPreset::Balanced => Some(QualityPreset::High),
+            QualityPreset::High => None,
+        }
+    }
+
+    /// 是否保留测试时增强，只有最高档使用
+    pub fn tta(self) -> bool {
+        self == QualityPreset::High
+    }
+
+    /// 是否保留答案校验，最低档跳过
+    pub fn verify(self) -> bool {
+        self != QualityPreset::Fast
+    }
+
+    /// 截取图像块缩放时使用的插值方式
+    pub fn filter(self) -> FilterType {
+        match self {
+            QualityPreset::Fast => FilterType::Nearest,
+            QualityPreset::Balanced => FilterType::Triangle,
+            QualityPreset::High => FilterType::Lanczos3,
+        }
+    }
+}
@@ -0,0 +1,206 @@
+use super::CaptchaBreaker;
+use crate::backend::{InferenceBackend, TensorData};
+use crate::environment::CaptchaEnvironment;
+use crate::input;
+use crate::model::Model;
+use crate::tiles;
+use crate::tokenizer::PromptTokenizer;
+use crate::warmup::warmup_backends;
+use image::DynamicImage;
+use image::imageops::FilterType;
+use ndarray::{Array1, Array2, Array4, Axis, Ix2};
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
+
+/// 模型输入尺寸无法从模型读取时使用的默认值
+const CLIP_SIZE: u32 = 224;
+const CLIP_MEAN: [f32; 3] = [0.481_454_66, 0.457_827_5, 0.408_210_73];
+const CLIP_STD: [f32; 3] = [0.268_629_54, 0.261_302_6, 0.275_777_1];
+
+/// 按与提示词的余弦相似度选择格子的方式
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum CellSelection {
+    /// 相似度不低于该值的格子都会被选中，没有格子包含目标时不选择
+    Absolute(f32),
+    /// 相似度以 `sigmoid(scale * s + bias)` 校准为概率，不低于 `min_probability` 的格子被选中，
+    /// `scale` 与 `bias` 在标注数据上拟合
+    Calibrated { scale: f32, bias: f32, min_probability: f32 },
+    /// 相似度不低于 `min_similarity` 且与最相似的格子相差不超过 `margin` 的格子被选中，
+    /// 只适合每道题至少有一个正确格子的情况
+    Margin { min_similarity: f32, margin: f32 },
+}
+
+impl CellSelection {
+    /// 按相似度选择格子，返回选中的下标
+    pub fn select(&self, similarities: &[f32]) -> Vec<usize> {
+        let best = similarities.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        let selected = |s: f32| match *self {
+            CellSelection::Absolute(threshold) => s >= threshold,
+            CellSelection::Calibrated { scale, bias, min_probability } => 1.0 / (1.0 + (-(scale * s + bias)).exp()) >= min_probability,
+            CellSelection::Margin { min_similarity, margin } => s >= min_similarity && s >= best - margin,
+        };
+        (0..similarities.len()).filter(|&i| selected(similarities[i])).collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct NineGrid0Config {
+    /// 网格尺寸 (rows, cols)
+    pub grid: (u32, u32),
+    /// 选择格子的方式，默认的绝对阈值适用于CLIP ViT-B类模型，更换模型后需要重新选取
+    pub selection: CellSelection,
+}
+
+impl Default for NineGrid0Config {
+    fn default() -> Self {
+        NineGrid0Config {
+            grid: (3, 3),
+            selection: CellSelection::Absolute(0.25),
+        }
+    }
+}
+
+/// 识别结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct GridSelection {
+    /// 需要选择的格子下标(行优先，从0开始)
+    pub cells: Vec<usize>,
+    /// 每个格子与提示词的余弦相似度
+    pub similarities: Vec<f32>,
+}
+
+/// 九宫格图片选择验证码("请选择所有包含公交车的图片")，使用CLIP类图文模型
+pub struct NineGrid0 {
+    image_encoder: Arc<dyn InferenceBackend>,
+    text_encoder: Arc<dyn InferenceBackend>,
+    tokenizer: Option<Box<dyn PromptTokenizer>>,
+    config: NineGrid0Config,
+}
+
+impl fmt::Debug for NineGrid0 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NineGrid0")
+            .field("image_encoder", &self.image_encoder)
+            .field("text_encoder", &self.text_encoder)
+            .field("config", &self.config)
+            .finish_non_exhaustive()
+    }
+}
+
+impl CaptchaBreaker for NineGrid0 {
+    fn build(captcha_environment: &CaptchaEnvironment) -> Result<Self, Box<dyn Error>> {
+        let models = captcha_environment.load_models(vec![Model::ClipImage, Model::ClipText])?;
+        Ok(NineGrid0 {
+            image_encoder: models[0].clone(),
+            text_encoder: models[1].clone(),
+            tokenizer: None,
+            config: Default::default(),
+        })
+    }
+
+    fn warmup(&self) -> Result<(), Box<dyn Error>> {
+        warmup_backends(&[self.image_encoder.as_ref(), self.text_encoder.as_ref()])
+    }
+}
+
+impl NineGrid0 {
+    pub fn with_config(mut self, config: NineGrid0Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn config(&self) -> &NineGrid0Config {
+        &self.config
+    }
+
+    /// 设置与文本模型配套的分词器，例如 [`WordPieceTokenizer`](crate::tokenizer::WordPieceTokenizer)
+    pub fn with_tokenizer(mut self, tokenizer: impl PromptTokenizer + 'static) -> Self {
+        self.tokenizer = Some(Box::new(tokenizer));
+        self
+    }
+
+    pub fn run(&self, image: &DynamicImage, prompt: &str) -> Result<GridSelection, Box<dyn Error>> {
+        let text_embedding = self.embed_text(prompt)?;
+        self.run_with_text_embedding(image, &text_embedding)
+    }
+
+    pub fn run_from_base64(&self, data: &str, prompt: &str) -> Result<GridSelection, Box<dyn Error>> {
+        self.run(&input::decode_base64(data)?, prompt)
+    }
+
+    /// 使用预先计算好的提示词特征识别，同一提示词多次出现时可以省去文本编码
+    pub fn run_with_text_embedding(&self, image: &DynamicImage, text_embedding: &Array1<f32>) -> Result<GridSelection, Box<dyn Error>> {
+        // 1. 切分网格
+        let (rows, cols) = self.config.grid;
+        let cells = tiles::split_tiles(image, rows, cols)?;
+        // 2. 编码每个格子
+        let size = self.image_encoder.input_size().unwrap_or(CLIP_SIZE);
+        let mut batch = Array4::<f32>::zeros((cells.len(), 3, size as usize, size as usize));
+        for (i, cell) in cells.iter().enumerate() {
+            let resized = image::imageops::resize(cell, size, size, FilterType::CatmullRom);
+            for (x, y, pixel) in resized.enumerate_pixels() {
+                for c in 0..3 {
+                    batch[[i, c, y as usize, x as usize]] = (pixel[c] as f32 / 255.0 - CLIP_MEAN[c]) / CLIP_STD[c];
+                }
+            }
+        }
+        let image_embeddings = normalize_rows(self.image_encoder.run_single(batch)?.into_f32()?.into_dimensionality::<Ix2>()?);
+        // 3. 与提示词计算余弦相似度并选择格子
+        let text = normalize_rows(text_embedding.clone().insert_axis(Axis(0)));
+        let similarities = image_embeddings.dot(&text.row(0)).to_vec();
+        let cells = self.config.selection.select(&similarities);
+        Ok(GridSelection { cells, similarities })
+    }
+
+    /// 编码提示词
+    pub fn embed_text(&self, prompt: &str) -> Result<Array1<f32>, Box<dyn Error>> {
+        let tokenizer = self
+            .tokenizer
+            .as_ref()
+            .ok_or("NineGrid0需要先通过with_tokenizer设置分词器")?;
+        let ids = tokenizer.encode(prompt);
+        let len = ids.len();
+        let mask: Vec<i64> = ids.iter().map(|&id| i64::from(id != 0)).collect();
+        let ids = Array2::from_shape_vec((1, len), ids)?;
+        let mask = Array2::from_shape_vec((1, len), mask)?;
+        // 部分导出的文本模型还需要attention_mask或token_type_ids，按名称对应
+        let inputs = self
+            .text_encoder
+            .inputs()
+            .iter()
+            .map(|info| {
+                let name = info.name.to_lowercase();
+                let data = if name.contains("mask") {
+                    TensorData::from(mask.clone())
+                } else if name.contains("type") {
+                    TensorData::from(Array2::<i64>::zeros((1, len)))
+                } else {
+                    TensorData::from(ids.clone())
+                };
+                (info.name.clone(), data)
+            })
+            .collect();
+        let output_name = &self.text_encoder.outputs().first().ok_or("文本模型没有输出")?.name;
+        let embedding = self
+            .text_encoder
+            .run(inputs)?
+            .remove(output_name)
+            .ok_or("文本模型输出缺失")?
+            .into_f32()?
+            .into_dimensionality::<Ix2>()?
+            .row(0)
+            .to_owned();
+        Ok(embedding)
+    }
+}
+
+fn normalize_rows(mut features: Array2<f32>) -> Array2<f32> {
+    for mut row in features.rows_mut() {
+        let norm = row.dot(&row).sqrt();
+        if norm > 0.0 {
+            row /= norm;
+        }
+    }
+    features
+}
@@ -0,0 +1,118 @@
+#[cfg(feature = "native")]
+use super::CaptchaBreaker;
+#[cfg(feature = "native")]
+use crate::environment::CaptchaEnvironment;
+use crate::input;
+use crate::tiles::{self, Compatibility};
+use image::DynamicImage;
+use std::error::Error;
+
+/// 打乱方式
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PuzzleMode {
+    /// 只有两块被交换
+    Swap,
+    /// 任意打乱，需要还原整张图
+    Restore,
+}
+
+#[derive(Debug, Clone)]
+pub struct PuzzleRestore0Config {
+    pub mode: PuzzleMode,
+    /// 网格尺寸 (rows, cols)，为None时自动检测切块边界
+    pub grid: Option<(u32, u32)>,
+    /// 自动检测时每个方向最多的切分数
+    pub max_splits: u32,
+    /// Restore模式下LAPJV迭代次数上限
+    pub max_iterations: usize,
+}
+
+impl Default for PuzzleRestore0Config {
+    fn default() -> Self {
+        PuzzleRestore0Config {
+            mode: PuzzleMode::Swap,
+            grid: None,
+            max_splits: 6,
+            max_iterations: 16,
+        }
+    }
+}
+
+/// 识别结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct PuzzleSolution {
+    pub rows: u32,
+    pub cols: u32,
+    /// 还原后位置p上应放置当前位于 `arrangement[p]` 的块(行优先编号)
+    pub arrangement: Vec<usize>,
+    /// 依次交换这些位置即可还原图片
+    pub swaps: Vec<(usize, usize)>,
+}
+
+/// 交换两块或还原打乱图片的拼图验证码，不需要模型
+#[derive(Debug)]
+pub struct PuzzleRestore0 {
+    config: PuzzleRestore0Config,
+}
+
+#[cfg(feature = "native")]
+impl CaptchaBreaker for PuzzleRestore0 {
+    fn build(_captcha_environment: &CaptchaEnvironment) -> Result<Self, Box<dyn Error>> {
+        Ok(PuzzleRestore0::new())
+    }
+}
+
+impl Default for PuzzleRestore0 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PuzzleRestore0 {
+    /// 不需要模型，可以不通过 `CaptchaEnvironment` 直接创建
+    pub fn new() -> Self {
+        PuzzleRestore0 {
+            config: Default::default(),
+        }
+    }
+
+    pub fn with_config(mut self, config: PuzzleRestore0Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn config(&self) -> &PuzzleRestore0Config {
+        &self.config
+    }
+
+    pub fn run(&self, image: &DynamicImage) -> Result<PuzzleSolution, Box<dyn Error>> {
+        // 1. 确定网格并切块
+        let (rows, cols) = self
+            .config
+            .grid
+            .unwrap_or_else(|| tiles::detect_grid(image, self.config.max_splits));
+        let tiles = tiles::split_tiles(image, rows, cols)?;
+        // 2. 计算两两之间的边缘相容度
+        let compatibility = Compatibility::new(&tiles)?;
+        // 3. 求使接缝代价最小的排列
+        let arrangement = match self.config.mode {
+            PuzzleMode::Swap => compatibility.best_swap(cols as usize),
+            PuzzleMode::Restore => compatibility.best_arrangement(cols as usize, self.config.max_iterations)?,
+        };
+        let swaps = tiles::swaps_for(&arrangement);
+        Ok(PuzzleSolution {
+            rows,
+            cols,
+            arrangement,
+            swaps,
+        })
+    }
+
+    pub fn run_from_bytes(&self, bytes: &[u8]) -> Result<PuzzleSolution, Box<dyn Error>> {
+        self.run(&input::decode_bytes(bytes)?)
+    }
+
+    pub fn run_from_base64(&self, data: &str) -> Result<PuzzleSolution, Box<dyn Error>> {
+        self.run(&input::decode_base64(data)?)
+    }
+}
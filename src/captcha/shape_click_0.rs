@@ -0,0 +1,362 @@
+use super::CaptchaBreaker;
+use crate::backend::InferenceBackend;
+use crate::click::{ClickConfig, ClickSampler};
+use crate::detection::{self, BBox, YoloConfig, YoloDetector};
+use crate::drift::{DriftMonitor, DriftSample};
+use crate::environment::CaptchaEnvironment;
+use crate::hooks::Hooks;
+use crate::input;
+use crate::lap::Solver;
+use crate::matching::assign;
+use crate::model::Model;
+use crate::report::Reporter;
+use crate::timing::{self, Outcome, Recorder, Stage, Timings};
+use crate::warmup::warmup_backends;
+use image::imageops::FilterType;
+use image::{DynamicImage, Rgb, RgbImage};
+use ndarray::{Array2, Array4, Axis, Ix2};
+use std::error::Error;
+use std::sync::Arc;
+
+/// 检测模型输入尺寸无法从模型读取时使用的默认值
+const DEFAULT_INPUT_SIZE: u32 = 416;
+/// 分类模型输入尺寸无法从模型读取时使用的默认值
+const DEFAULT_CLASSIFIER_SIZE: u32 = 32;
+
+/// 分类模型没有元数据时的类别：常见图形与26个大写字母
+pub const DEFAULT_SHAPE_LABELS: &[&str] = &[
+    "triangle", "circle", "square", "star", "heart", "diamond", "pentagon", "hexagon", "cross", "arrow", "A", "B", "C", "D", "E", "F",
+    "G", "H", "I", "J", "K", "L", "M", "N", "O", "P", "Q", "R", "S", "T", "U", "V", "W", "X", "Y", "Z",
+];
+
+/// 图形类别在提示词中可能出现的写法
+const SHAPE_WORDS: &[(&str, &[&str])] = &[
+    ("triangle", &["triangle", "三角形", "三角"]),
+    ("circle", &["circle", "圆形", "圆圈", "圆"]),
+    ("square", &["square", "正方形", "方形", "方块"]),
+    ("star", &["star", "五角星", "星形", "星星"]),
+    ("heart", &["heart", "心形", "爱心"]),
+    ("diamond", &["diamond", "菱形"]),
+    ("pentagon", &["pentagon", "五边形"]),
+    ("hexagon", &["hexagon", "六边形"]),
+    ("cross", &["cross", "十字形", "十字"]),
+    ("arrow", &["arrow", "箭头"]),
+];
+
+#[derive(Debug, Clone)]
+pub struct ShapeClick0Config {
+    /// 检测模型的配置，`class_names` 为检测模型自己的类别，可以与分类模型不同(例如只有一个 "object" 类)
+    pub detector: YoloConfig,
+    /// 分类模型输出类别的名称，为空时从模型元数据读取，仍为空时使用 [`DEFAULT_SHAPE_LABELS`]
+    pub labels: Vec<String>,
+    /// 分类概率在最终概率中的权重，其余为检测模型类别的置信度；检测类别不在 `labels` 中时只使用分类概率
+    pub classifier_weight: f32,
+    /// 目标与检测框为同一类的概率低于该值时视为没有找到
+    pub min_probability: f32,
+    /// 透明像素合成到的背景色
+    pub background: Rgb<u8>,
+    /// 点击位置，默认为检测框中心
+    pub click: ClickConfig,
+}
+
+impl Default for ShapeClick0Config {
+    fn default() -> Self {
+        ShapeClick0Config {
+            detector: YoloConfig {
+                confidence_threshold: 0.4,
+                ..Default::default()
+            },
+            labels: vec![],
+            classifier_weight: 0.7,
+            min_probability: 0.3,
+            background: input::DEFAULT_BACKGROUND,
+            click: ClickConfig::default(),
+        }
+    }
+}
+
+/// 识别结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShapeClickResult {
+    /// 从提示词中解析出的目标类别，即点选顺序
+    pub targets: Vec<String>,
+    /// 检测框，顺序与检测结果相同
+    pub boxes: Vec<BBox>,
+    /// 每个检测框概率最高的类别
+    pub labels: Vec<String>,
+    /// 第i个检测框属于第j个类别的概率，列与 `ShapeClick0Config::labels` 对应
+    pub probabilities: Array2<f32>,
+    /// 第i个目标匹配的检测框下标，没有找到时为None
+    pub matches: Vec<Option<usize>>,
+    /// 按点选顺序需要点击的坐标，只包含找到的目标
+    pub points: Vec<(f32, f32)>,
+    /// 各阶段耗时
+    pub timings: Timings,
+}
+
+/// 图形/字母点选验证码，例如在噪点背景上 "请依次点击：三角形、五角星、字母K"
+///
+/// 检测出所有图形后用轻量分类模型判断类别，再把提示词中的目标与检测框一一分配
+pub struct ShapeClick0 {
+    detector: YoloDetector,
+    classifier: Arc<dyn InferenceBackend>,
+    config: ShapeClick0Config,
+    clicks: ClickSampler,
+    drift: Option<Arc<DriftMonitor>>,
+    reporter: Option<Reporter>,
+    hooks: Hooks,
+}
+
+impl CaptchaBreaker for ShapeClick0 {
+    fn build(captcha_environment: &CaptchaEnvironment) -> Result<Self, Box<dyn Error>> {
+        let session = captcha_environment.load_models(vec![Model::ShapeDetector, Model::ShapeClassifier])?;
+        let mut config = ShapeClick0Config::default();
+        if let Some(metadata) = captcha_environment.model_metadata(Model::ShapeDetector) {
+            config.detector.class_names = metadata.class_labels;
+        }
+        config.labels = captcha_environment
+            .model_metadata(Model::ShapeClassifier)
+            .map(|m| m.class_labels)
+            .filter(|labels| !labels.is_empty())
+            .unwrap_or_else(|| DEFAULT_SHAPE_LABELS.iter().map(|l| l.to_string()).collect());
+        config.detector.input_size = Some(session[0].input_size().unwrap_or(DEFAULT_INPUT_SIZE));
+        if let Some(threshold) = captcha_environment.confidence_threshold() {
+            config.detector.confidence_threshold = threshold;
+        }
+        Ok(ShapeClick0 {
+            detector: YoloDetector::new(session[0].clone()),
+            classifier: session[1].clone(),
+            config: Default::default(),
+            clicks: ClickSampler::default(),
+            drift: captcha_environment.drift_monitor().cloned(),
+            reporter: Reporter::from_environment(captcha_environment, &[Model::ShapeDetector, Model::ShapeClassifier]),
+            hooks: captcha_environment.hooks().clone(),
+        }
+        .with_config(config))
+    }
+
+    fn warmup(&self) -> Result<(), Box<dyn Error>> {
+        warmup_backends(&[self.detector.backend(), self.classifier.as_ref()])
+    }
+}
+
+impl std::fmt::Debug for ShapeClick0 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ShapeClick0")
+            .field("detector", &self.detector)
+            .field("config", &self.config)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ShapeClick0 {
+    pub fn with_config(mut self, config: ShapeClick0Config) -> Self {
+        self.detector = self.detector.with_config(config.detector.clone());
+        self.clicks = ClickSampler::new(config.click);
+        self.config = config;
+        self
+    }
+
+    pub fn config(&self) -> &ShapeClick0Config {
+        &self.config
+    }
+
+    /// 替换构建时从环境得到的处理钩子，见 [`Hooks`]
+    pub fn with_hooks(mut self, hooks: Hooks) -> Self {
+        self.hooks = hooks;
+        self
+    }
+
+    pub fn hooks(&self) -> &Hooks {
+        &self.hooks
+    }
+
+    /// 按提示词的顺序返回点击坐标，有目标没有找到时返回错误
+    pub fn run(&self, image: &DynamicImage, prompt: &str) -> Result<Vec<(f32, f32)>, Box<dyn Error>> {
+        let result = self.run_detailed(image, prompt)?;
+        if let Some(i) = result.matches.iter().position(Option::is_none) {
+            return Err(format!("没有找到“{}”", result.targets[i]).into());
+        }
+        Ok(result.points)
+    }
+
+    pub fn run_from_bytes(&self, bytes: &[u8], prompt: &str) -> Result<Vec<(f32, f32)>, Box<dyn Error>> {
+        self.run(&input::decode_bytes(bytes)?, prompt)
+    }
+
+    pub fn run_from_base64(&self, data: &str, prompt: &str) -> Result<Vec<(f32, f32)>, Box<dyn Error>> {
+        self.run(&input::decode_base64(data)?, prompt)
+    }
+
+    pub fn run_detailed(&self, image: &DynamicImage, prompt: &str) -> Result<ShapeClickResult, Box<dyn Error>> {
+        let res = self.solve(image, prompt);
+        timing::record_outcome("shape_click_0", Outcome::of(&res));
+        if let Some(reporter) = &self.reporter {
+            let mut report = reporter.start("shape_click_0", image).with_outcome(&res);
+            if let Ok(result) = &res {
+                report = report.with_timings(&result.timings);
+                report.confidences = result.boxes.iter().map(|b| b.confidence).collect();
+                report.answer = result.points.clone();
+            }
+            reporter.emit(&report);
+        }
+        res
+    }
+
+    fn solve(&self, image: &DynamicImage, prompt: &str) -> Result<ShapeClickResult, Box<dyn Error>> {
+        let targets = parse_targets(prompt, &self.config.labels);
+        if targets.is_empty() {
+            return Err(format!("提示词中没有可识别的图形或字母: {}", prompt).into());
+        }
+        let mut recorder = Recorder::new("shape_click_0");
+        let processed_image = recorder.time(Stage::Preprocess, || {
+            let mut rgb = input::to_rgb8(image, self.config.background);
+            self.hooks.preprocess(&mut rgb);
+            rgb
+        });
+        let mut boxes = recorder.time(Stage::Detect, || self.detector.detect_rgb(&processed_image))?;
+        self.hooks.detections(&processed_image, &mut boxes);
+        recorder.record_count("detections", boxes.len());
+        if boxes.is_empty() {
+            return Err("没有检测到任何图形".into());
+        }
+        let crops: Vec<RgbImage> =
+            recorder.time(Stage::Crop, || boxes.iter().map(|bbox| detection::crop(&processed_image, bbox)).collect());
+        let probabilities = recorder.time(Stage::Embed, || self.classify(&crops, &boxes))?;
+        let matches = recorder.time(Stage::Assign, || self.assign_targets(&targets, &probabilities))?;
+        if let Some(drift) = &self.drift {
+            drift.observe("shape_click_0", DriftSample::from_boxes(&boxes));
+        }
+        let labels = probabilities
+            .axis_iter(Axis(0))
+            .map(|row| {
+                let best = (0..row.len()).max_by(|&a, &b| row[a].total_cmp(&row[b])).unwrap_or(0);
+                self.config.labels[best].clone()
+            })
+            .collect();
+        let mut points = self.clicks.sample(matches.iter().flatten().map(|&i| &boxes[i]));
+        self.hooks.matches(&mut points);
+        Ok(ShapeClickResult {
+            targets,
+            boxes,
+            labels,
+            probabilities,
+            matches,
+            points,
+            timings: recorder.finish(),
+        })
+    }
+
+    /// 分类模型输入 (N, 3, size, size)，输出 (N, 类别数) 的logits，与检测类别的置信度按权重合并
+    fn classify(&self, crops: &[RgbImage], boxes: &[BBox]) -> Result<Array2<f32>, Box<dyn Error>> {
+        if self.config.labels.is_empty() {
+            return Err("没有设置分类模型的类别名称".into());
+        }
+        let size = self.classifier.input_size().unwrap_or(DEFAULT_CLASSIFIER_SIZE);
+        let mut batch = Array4::<f32>::zeros((crops.len(), 3, size as usize, size as usize));
+        for (i, crop) in crops.iter().enumerate() {
+            let resized = image::imageops::resize(crop, size, size, FilterType::Triangle);
+            for (x, y, pixel) in resized.enumerate_pixels() {
+                for c in 0..3 {
+                    batch[[i, c, y as usize, x as usize]] = pixel[c] as f32 / 255.0;
+                }
+            }
+        }
+        let logits = self.classifier.run_single(batch)?.into_f32()?.into_dimensionality::<Ix2>()?;
+        if logits.ncols() != self.config.labels.len() {
+            return Err(format!("分类模型输出{}类，类别名称有{}个", logits.ncols(), self.config.labels.len()).into());
+        }
+        let weight = self.config.classifier_weight.clamp(0.0, 1.0);
+        let mut probabilities = logits;
+        for (mut row, bbox) in probabilities.axis_iter_mut(Axis(0)).zip(boxes) {
+            let max = row.fold(f32::NEG_INFINITY, |a, &b| a.max(b));
+            row.mapv_inplace(|x| (x - max).exp());
+            let sum = row.sum();
+            row.mapv_inplace(|x| x / sum);
+            let detected = self.config.detector.class_names.get(bbox.class_id as usize);
+            if let Some(j) = detected.and_then(|name| self.config.labels.iter().position(|l| l.eq_ignore_ascii_case(name))) {
+                row.mapv_inplace(|p| p * weight);
+                row[j] += (1.0 - weight) * bbox.confidence;
+            }
+        }
+        Ok(probabilities)
+    }
+
+    /// 目标与检测框一一分配，成本为 `1 - 概率`，同一个图形不会被点两次
+    fn assign_targets(&self, targets: &[String], probabilities: &Array2<f32>) -> Result<Vec<Option<usize>>, Box<dyn Error>> {
+        let columns: Vec<usize> = targets
+            .iter()
+            .map(|t| self.config.labels.iter().position(|l| l == t).unwrap_or(0))
+            .collect();
+        let costs = Array2::from_shape_fn((targets.len(), probabilities.nrows()), |(i, j)| 1.0 - probabilities[[j, columns[i]]]);
+        let matches = assign(&costs, Solver::LapJV)?;
+        Ok(matches
+            .into_iter()
+            .enumerate()
+            .map(|(i, j)| j.filter(|&j| 1.0 - costs[[i, j]] >= self.config.min_probability))
+            .collect())
+    }
+}
+
+/// 按出现的顺序解析提示词中的目标类别，labels为分类模型的类别名称
+///
+/// 类别名称不区分大小写；[`DEFAULT_SHAPE_LABELS`] 中的图形另有中英文写法，例如 "五角星" 对应 `star`。
+/// 单个字母的类别只匹配独立的大写字母，或 "letter"/"字母" 之后的字母，避免把冠词 "a" 当作目标
+pub fn parse_targets(prompt: &str, labels: &[String]) -> Vec<String> {
+    // ASCII小写不改变字节下标
+    let text = prompt.to_ascii_lowercase();
+    let is_boundary = |c: Option<char>| !c.is_some_and(|c| c.is_ascii_alphanumeric());
+    // (位置, 长度, 类别)
+    let mut found: Vec<(usize, usize, &String)> = vec![];
+    for label in labels {
+        if let Some(letter) = single_letter(label) {
+            for (pos, c) in prompt.char_indices().filter(|(_, c)| c.eq_ignore_ascii_case(&letter)) {
+                let standalone = is_boundary(prompt[..pos].chars().next_back()) && is_boundary(prompt[pos + 1..].chars().next());
+                let before = text[..pos].trim_end_matches([' ', '"', '\'', '“', '‘']);
+                if standalone && (c.is_ascii_uppercase() || before.ends_with("letter") || before.ends_with("字母")) {
+                    found.push((pos, 1, label));
+                }
+            }
+            continue;
+        }
+        let lower = label.to_ascii_lowercase();
+        let aliases = SHAPE_WORDS.iter().find(|(name, _)| *name == lower).map_or(&[][..], |(_, words)| *words);
+        for word in std::iter::once(lower.as_str()).chain(aliases.iter().copied()) {
+            for (pos, _) in text.match_indices(word) {
+                let mut len = word.len();
+                if word.is_ascii() {
+                    // 允许复数 "stars"、"crosses"
+                    let rest = &text[pos + len..];
+                    len += ["es", "s"]
+                        .iter()
+                        .find(|suffix| rest.starts_with(**suffix) && is_boundary(rest[suffix.len()..].chars().next()))
+                        .map_or(0, |suffix| suffix.len());
+                    if !is_boundary(text[..pos].chars().next_back()) || !is_boundary(text[pos + len..].chars().next()) {
+                        continue;
+                    }
+                }
+                found.push((pos, len, label));
+            }
+        }
+    }
+    // 重叠时保留较长的写法，例如 "正方形" 中的 "方形"
+    found.sort_by_key(|&(pos, len, _)| (pos, std::cmp::Reverse(len)));
+    let mut targets = vec![];
+    let mut covered = 0;
+    for (pos, len, label) in found {
+        if pos >= covered {
+            targets.push(label.clone());
+            covered = pos + len;
+        }
+    }
+    targets
+}
+
+/// 只有一个ASCII字母的类别名称
+fn single_letter(label: &str) -> Option<char> {
+    let mut chars = label.chars();
+    match (chars.next(), chars.next()) {
+        (Some(letter), None) if letter.is_ascii_alphabetic() => Some(letter),
+        _ => None,
+    }
+}
@@ -0,0 +1,101 @@
+use super::CaptchaBreaker;
+use crate::detection::{BBox, YoloConfig, YoloDetector, YoloLayout};
+use crate::enhance::EnhanceConfig;
+use crate::environment::CaptchaEnvironment;
+use crate::input;
+use crate::model::Model;
+use crate::warmup::warmup_backend;
+use image::{DynamicImage, Rgb};
+use std::error::Error;
+
+/// 模型输入尺寸无法从模型读取时使用的默认值
+const DEFAULT_INPUT_SIZE: u32 = 416;
+
+#[derive(Debug, Clone)]
+pub struct SliderGap0Config {
+    /// 低于该置信度的检测结果会被忽略
+    pub confidence_threshold: f32,
+    /// 检测前的图像增强
+    pub enhance: EnhanceConfig,
+    /// 透明像素合成到的背景色
+    pub background: Rgb<u8>,
+}
+
+impl Default for SliderGap0Config {
+    fn default() -> Self {
+        SliderGap0Config {
+            confidence_threshold: 0.3,
+            enhance: EnhanceConfig::default(),
+            background: input::DEFAULT_BACKGROUND,
+        }
+    }
+}
+
+/// 只有背景图的滑块验证码，从背景中检测阴影缺口的位置
+#[derive(Debug)]
+pub struct SliderGap0 {
+    detector: YoloDetector,
+    config: SliderGap0Config,
+}
+
+impl CaptchaBreaker for SliderGap0 {
+    fn build(captcha_environment: &CaptchaEnvironment) -> Result<Self, Box<dyn Error>> {
+        let session = captcha_environment.load_models(vec![Model::SliderGap])?;
+        let mut config = SliderGap0Config::default();
+        if let Some(threshold) = captcha_environment.confidence_threshold() {
+            config.confidence_threshold = threshold;
+        }
+        Ok(SliderGap0 {
+            detector: YoloDetector::new(session[0].clone()).with_config(YoloConfig {
+                input_size: Some(session[0].input_size().unwrap_or(DEFAULT_INPUT_SIZE)),
+                layout: YoloLayout::EndToEnd,
+                confidence_threshold: config.confidence_threshold,
+                ..Default::default()
+            }),
+            config,
+        })
+    }
+
+    fn warmup(&self) -> Result<(), Box<dyn Error>> {
+        warmup_backend(self.detector.backend())
+    }
+}
+
+impl SliderGap0 {
+    pub fn with_config(mut self, config: SliderGap0Config) -> Self {
+        let detector_config = YoloConfig {
+            confidence_threshold: config.confidence_threshold,
+            ..self.detector.config().clone()
+        };
+        self.detector = self.detector.with_config(detector_config);
+        self.config = config;
+        self
+    }
+
+    pub fn config(&self) -> &SliderGap0Config {
+        &self.config
+    }
+
+    /// 返回缺口左边缘的x偏移量，即滑块需要移动的距离
+    pub fn run(&self, image: &DynamicImage) -> Result<Option<f32>, Box<dyn Error>> {
+        Ok(self.run_detailed(image)?.map(|gap| gap.rect.x_min))
+    }
+
+    /// 返回置信度最高的缺口框(原图像素坐标)，没有检测到时返回None
+    pub fn run_detailed(&self, image: &DynamicImage) -> Result<Option<BBox>, Box<dyn Error>> {
+        let rgb = input::to_rgb8(image, self.config.background);
+        Ok(self
+            .detector
+            .detect_rgb(&self.config.enhance.apply(&rgb))?
+            .into_iter()
+            .max_by(|a, b| a.confidence.total_cmp(&b.confidence)))
+    }
+
+    pub fn run_from_bytes(&self, bytes: &[u8]) -> Result<Option<f32>, Box<dyn Error>> {
+        self.run(&input::decode_bytes(bytes)?)
+    }
+
+    pub fn run_from_base64(&self, data: &str) -> Result<Option<f32>, Box<dyn Error>> {
+        self.run(&input::decode_base64(data)?)
+    }
+}
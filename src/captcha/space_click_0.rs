@@ -0,0 +1,307 @@
+use super::CaptchaBreaker;
+use crate::attribute::{AttributeClassifier, Attributes, Color, Orientation, parse_color, parse_orientation};
+use crate::click::{ClickConfig, ClickSampler};
+use crate::detection::{self, BBox, YoloConfig, YoloDetector, YoloLayout};
+use crate::enhance::EnhanceConfig;
+use crate::environment::CaptchaEnvironment;
+use crate::input;
+use crate::model::Model;
+use crate::warmup::warmup_backends;
+use image::{DynamicImage, Rgb};
+use std::error::Error;
+
+const DEFAULT_INPUT_SIZE: u32 = 416;
+
+/// 提示词中对目标的描述，未提到的属性为None
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Selector {
+    pub label: Option<String>,
+    pub color: Option<Color>,
+    pub orientation: Option<Orientation>,
+}
+
+/// 目标与参照物之间需要满足的关系
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Relation {
+    SameColor,
+    SameOrientation,
+}
+
+/// 解析后的提示词
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpaceQuery {
+    pub target: Selector,
+    /// 参照物及关系，例如 "与红色立方体颜色相同的字母"
+    pub reference: Option<(Selector, Relation)>,
+}
+
+const SAME_COLOR: &[&str] = &["same color", "same colour", "颜色相同", "相同颜色", "同色", "颜色一样"];
+const SAME_ORIENTATION: &[&str] = &[
+    "same orientation",
+    "same direction",
+    "朝向相同",
+    "相同朝向",
+    "方向相同",
+    "同向",
+    "朝向一样",
+];
+
+impl SpaceQuery {
+    /// 解析中英文提示词，labels为检测模型的类别名称
+    ///
+    /// 支持 "click the {target} that has the same color as the {reference}"
+    /// 以及 "请点击与{reference}颜色相同的{target}" 等形式
+    pub fn parse(prompt: &str, labels: &[String]) -> SpaceQuery {
+        let text = prompt.to_lowercase();
+        let relation = if SAME_COLOR.iter().any(|w| text.contains(w)) {
+            Some(Relation::SameColor)
+        } else if SAME_ORIENTATION.iter().any(|w| text.contains(w)) {
+            Some(Relation::SameOrientation)
+        } else {
+            None
+        };
+        let Some(relation) = relation else {
+            return SpaceQuery {
+                target: Selector::parse(&text, labels),
+                reference: None,
+            };
+        };
+
+        let (target, reference) = if text.is_ascii() {
+            // click the {target} that ... as/of the {reference}
+            let split = [" as ", " of "].iter().filter_map(|w| text.rfind(w).map(|p| (p, w.len()))).max();
+            let reference = split.map_or("", |(p, len)| &text[p + len..]);
+            let end = [" that ", " which ", " with "]
+                .iter()
+                .filter_map(|w| text.find(w))
+                .min()
+                .unwrap_or(text.len());
+            (&text[..end], reference)
+        } else {
+            // 请点击与{reference}...相同的{target}
+            let start = ["与", "和", "跟"].iter().filter_map(|w| text.find(w).map(|p| p + w.len())).min();
+            let relation_pos = SAME_COLOR
+                .iter()
+                .chain(SAME_ORIENTATION)
+                .filter_map(|w| text.find(w))
+                .min()
+                .unwrap_or(text.len());
+            let reference = start.filter(|&s| s <= relation_pos).map_or("", |s| &text[s..relation_pos]);
+            let target = text.rfind('的').map_or(&text[relation_pos..], |p| &text[p + '的'.len_utf8()..]);
+            (target, reference)
+        };
+        SpaceQuery {
+            target: Selector::parse(target, labels),
+            reference: Some((Selector::parse(reference, labels), relation)),
+        }
+    }
+}
+
+impl Selector {
+    fn parse(text: &str, labels: &[String]) -> Selector {
+        Selector {
+            label: find_label(text, labels),
+            color: parse_color(text),
+            orientation: parse_orientation(text),
+        }
+    }
+
+    fn matches(&self, object: &SpaceObject) -> bool {
+        self.label.as_ref().is_none_or(|l| Some(l) == object.label.as_ref())
+            && self.color.is_none_or(|c| c == object.attributes.color)
+            && self.orientation.is_none_or(|o| o == object.attributes.orientation)
+    }
+}
+
+/// 查找文本中出现的类别名称，ASCII名称要求单词边界，避免 "a" 匹配到 "that"
+fn find_label(text: &str, labels: &[String]) -> Option<String> {
+    let mut sorted: Vec<&String> = labels.iter().collect();
+    sorted.sort_by_key(|l| std::cmp::Reverse(l.len()));
+    sorted
+        .into_iter()
+        .find(|label| {
+            let label = label.to_lowercase();
+            if !label.is_ascii() {
+                return text.contains(&label);
+            }
+            text.match_indices(&label).any(|(pos, _)| {
+                let before = text[..pos].chars().next_back();
+                let after = text[pos + label.len()..].chars().next();
+                !before.is_some_and(|c| c.is_ascii_alphanumeric()) && !after.is_some_and(|c| c.is_ascii_alphanumeric())
+            })
+        })
+        .cloned()
+}
+
+/// 检测出的目标
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpaceObject {
+    pub bbox: BBox,
+    pub label: Option<String>,
+    pub attributes: Attributes,
+}
+
+impl SpaceObject {
+    pub fn center(&self) -> (f32, f32) {
+        self.bbox.center()
+    }
+
+    fn score(&self, relation: Option<Relation>) -> f32 {
+        match relation {
+            Some(Relation::SameColor) => self.bbox.confidence * self.attributes.color_confidence,
+            Some(Relation::SameOrientation) => self.bbox.confidence * self.attributes.orientation_confidence,
+            None => self.bbox.confidence,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SpaceResult {
+    pub query: SpaceQuery,
+    pub objects: Vec<SpaceObject>,
+    /// 参照物在objects中的下标
+    pub reference: Option<usize>,
+    /// 答案在objects中的下标
+    pub answer: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SpaceClick0Config {
+    /// 检测模型的类别名称，为空时从模型元数据读取
+    pub labels: Vec<String>,
+    pub confidence_threshold: f32,
+    /// 检测前的图像增强，颜色等属性仍从原图判断
+    pub enhance: EnhanceConfig,
+    /// 透明像素合成到的背景色
+    pub background: Rgb<u8>,
+    /// 点击位置，默认为目标框中心
+    pub click: ClickConfig,
+}
+
+impl Default for SpaceClick0Config {
+    fn default() -> Self {
+        SpaceClick0Config {
+            labels: vec![],
+            confidence_threshold: 0.5,
+            enhance: EnhanceConfig::default(),
+            background: input::DEFAULT_BACKGROUND,
+            click: ClickConfig::default(),
+        }
+    }
+}
+
+/// 3D渲染文字/物体点选验证码，提示词形如 "点击与Y颜色相同的X"
+#[derive(Debug)]
+pub struct SpaceClick0 {
+    detector: YoloDetector,
+    attributes: AttributeClassifier,
+    config: SpaceClick0Config,
+    clicks: ClickSampler,
+}
+
+impl CaptchaBreaker for SpaceClick0 {
+    fn build(captcha_environment: &CaptchaEnvironment) -> Result<Self, Box<dyn Error>> {
+        let session = captcha_environment.load_models(vec![Model::SpaceDetector, Model::SpaceAttributes])?;
+        let labels = captcha_environment
+            .model_metadata(Model::SpaceDetector)
+            .map(|m| m.class_labels)
+            .unwrap_or_default();
+        let mut config = SpaceClick0Config {
+            labels,
+            ..Default::default()
+        };
+        if let Some(threshold) = captcha_environment.confidence_threshold() {
+            config.confidence_threshold = threshold;
+        }
+        Ok(SpaceClick0 {
+            detector: YoloDetector::new(session[0].clone()).with_config(YoloConfig {
+                input_size: Some(session[0].input_size().unwrap_or(DEFAULT_INPUT_SIZE)),
+                layout: YoloLayout::EndToEnd,
+                confidence_threshold: config.confidence_threshold,
+                ..Default::default()
+            }),
+            attributes: AttributeClassifier::new(session[1].clone()),
+            clicks: ClickSampler::new(config.click),
+            config,
+        })
+    }
+
+    fn warmup(&self) -> Result<(), Box<dyn Error>> {
+        warmup_backends(&[self.detector.backend(), self.attributes.backend()])
+    }
+}
+
+impl SpaceClick0 {
+    pub fn with_config(mut self, config: SpaceClick0Config) -> Self {
+        let detector_config = YoloConfig {
+            confidence_threshold: config.confidence_threshold,
+            ..self.detector.config().clone()
+        };
+        self.detector = self.detector.with_config(detector_config);
+        self.clicks = ClickSampler::new(config.click);
+        self.config = config;
+        self
+    }
+
+    pub fn config(&self) -> &SpaceClick0Config {
+        &self.config
+    }
+
+    /// 返回需要点击的坐标，没有满足提示词的目标时返回None
+    pub fn run(&self, image: &DynamicImage, prompt: &str) -> Result<Option<(f32, f32)>, Box<dyn Error>> {
+        let result = self.run_detailed(image, prompt)?;
+        Ok(result.answer.and_then(|i| self.clicks.sample([&result.objects[i].bbox]).pop()))
+    }
+
+    pub fn run_from_base64(&self, data: &str, prompt: &str) -> Result<Option<(f32, f32)>, Box<dyn Error>> {
+        self.run(&input::decode_base64(data)?, prompt)
+    }
+
+    pub fn run_detailed(&self, image: &DynamicImage, prompt: &str) -> Result<SpaceResult, Box<dyn Error>> {
+        // 1. 解析提示词
+        let query = SpaceQuery::parse(prompt, &self.config.labels);
+        // 2. 检测并分类每个目标的属性
+        let objects = self.detect_objects(image)?;
+        // 3. 规则匹配
+        let relation = query.reference.as_ref().map(|(_, r)| *r);
+        let best = |candidates: &mut dyn Iterator<Item = usize>| {
+            candidates.max_by(|&a, &b| objects[a].score(relation).total_cmp(&objects[b].score(relation)))
+        };
+        let (reference, answer) = match &query.reference {
+            None => (None, best(&mut (0..objects.len()).filter(|&i| query.target.matches(&objects[i])))),
+            Some((selector, relation)) => {
+                let reference = best(&mut (0..objects.len()).filter(|&i| selector.matches(&objects[i])));
+                let answer = reference.and_then(|r| {
+                    let same = |i: usize| match relation {
+                        Relation::SameColor => objects[i].attributes.color == objects[r].attributes.color,
+                        Relation::SameOrientation => objects[i].attributes.orientation == objects[r].attributes.orientation,
+                    };
+                    best(&mut (0..objects.len()).filter(|&i| i != r && same(i) && query.target.matches(&objects[i])))
+                });
+                (reference, answer)
+            }
+        };
+        Ok(SpaceResult {
+            query,
+            objects,
+            reference,
+            answer,
+        })
+    }
+
+    fn detect_objects(&self, image: &DynamicImage) -> Result<Vec<SpaceObject>, Box<dyn Error>> {
+        let rgb = input::to_rgb8(image, self.config.background);
+        let boxes = self.detector.detect_rgb(&self.config.enhance.apply(&rgb))?;
+        let crops: Vec<_> = boxes.iter().map(|bbox| detection::crop(&rgb, bbox)).collect();
+        let attributes = self.attributes.classify(&crops)?;
+        Ok(boxes
+            .into_iter()
+            .zip(attributes)
+            .map(|(bbox, attributes)| SpaceObject {
+                label: self.config.labels.get(bbox.class_id as usize).cloned(),
+                bbox,
+                attributes,
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,133 @@
+use super::CaptchaBreaker;
+use crate::backend::InferenceBackend;
+use crate::ctc;
+use crate::environment::CaptchaEnvironment;
+use crate::input;
+use crate::model::Model;
+use crate::warmup::warmup_backend;
+use image::DynamicImage;
+use image::imageops::FilterType;
+use ndarray::{Array4, Ix3};
+use std::error::Error;
+use std::sync::Arc;
+
+/// 默认字符集，可以在编译时通过环境变量 `CAPTCHA_BREAKER_CHARSET` 替换
+pub const DEFAULT_CHARSET: &str = match option_env!("CAPTCHA_BREAKER_CHARSET") {
+    Some(charset) => charset,
+    None => "0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ",
+};
+
+/// CTC解码方式
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CtcDecoder {
+    Greedy,
+    /// 前缀束搜索，参数为束宽
+    Beam(usize),
+}
+
+#[derive(Debug, Clone)]
+pub struct TextCaptcha0Config {
+    /// 模型输出类别对应的字符，类别0为blank，类别i对应 `charset[i - 1]`
+    pub charset: Vec<char>,
+    /// 模型输入的固定高度，宽度按比例缩放
+    pub height: u32,
+    pub decoder: CtcDecoder,
+}
+
+impl Default for TextCaptcha0Config {
+    fn default() -> Self {
+        TextCaptcha0Config {
+            charset: DEFAULT_CHARSET.chars().collect(),
+            height: 64,
+            decoder: CtcDecoder::Greedy,
+        }
+    }
+}
+
+/// 扭曲字母数字验证码识别(CRNN + CTC)
+#[derive(Debug)]
+pub struct TextCaptcha0 {
+    crnn: Arc<dyn InferenceBackend>,
+    config: TextCaptcha0Config,
+}
+
+impl CaptchaBreaker for TextCaptcha0 {
+    fn build(captcha_environment: &CaptchaEnvironment) -> Result<Self, Box<dyn Error>> {
+        let models = captcha_environment.load_models(vec![Model::Crnn])?;
+        Ok(TextCaptcha0 {
+            crnn: models[0].clone(),
+            config: Default::default(),
+        })
+    }
+
+    fn warmup(&self) -> Result<(), Box<dyn Error>> {
+        warmup_backend(self.crnn.as_ref())
+    }
+}
+
+impl TextCaptcha0 {
+    pub fn with_config(mut self, config: TextCaptcha0Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn config(&self) -> &TextCaptcha0Config {
+        &self.config
+    }
+
+    /// 识别图片中的文字
+    pub fn run(&self, image: &DynamicImage) -> Result<String, Box<dyn Error>> {
+        Ok(self.run_with_confidence(image)?.0)
+    }
+
+    /// 识别图片中的文字并返回置信度
+    pub fn run_with_confidence(&self, image: &DynamicImage) -> Result<(String, f32), Box<dyn Error>> {
+        // 1. 灰度化并缩放到固定高度
+        let input = self.preprocess_image(image);
+        // 2. CRNN推理
+        let log_probs = self.infer(input)?;
+        // 3. CTC解码
+        let (labels, confidence) = match self.config.decoder {
+            CtcDecoder::Greedy => ctc::greedy_decode(&log_probs.view(), 0),
+            CtcDecoder::Beam(width) => ctc::beam_decode(&log_probs.view(), 0, width),
+        };
+        let text = labels
+            .into_iter()
+            .filter_map(|label| self.config.charset.get(label - 1))
+            .collect();
+        Ok((text, confidence))
+    }
+
+    pub fn run_from_bytes(&self, bytes: &[u8]) -> Result<String, Box<dyn Error>> {
+        self.run(&input::decode_bytes(bytes)?)
+    }
+
+    pub fn run_from_base64(&self, data: &str) -> Result<String, Box<dyn Error>> {
+        self.run(&input::decode_base64(data)?)
+    }
+
+    /// 图像预处理，输出 (1, 1, height, width)，归一化到[-1, 1]
+    fn preprocess_image(&self, image: &DynamicImage) -> Array4<f32> {
+        let height = self.config.height;
+        let width = ((image.width() as f32 * height as f32 / image.height().max(1) as f32).round() as u32).max(1);
+        let rgb = DynamicImage::ImageRgb8(input::to_rgb8(image, input::DEFAULT_BACKGROUND));
+        let gray = image::imageops::resize(&rgb.to_luma8(), width, height, FilterType::Triangle);
+        let mut input = Array4::<f32>::zeros((1, 1, height as usize, width as usize));
+        for (x, y, pixel) in gray.enumerate_pixels() {
+            input[[0, 0, y as usize, x as usize]] = (pixel[0] as f32 / 255.0 - 0.5) / 0.5;
+        }
+        input
+    }
+
+    /// 推理并返回 (时间步, 类别数) 的log概率
+    fn infer(&self, input: Array4<f32>) -> Result<ndarray::Array2<f32>, Box<dyn Error>> {
+        let output = self.crnn.run_single(input)?.into_f32()?.into_dimensionality::<Ix3>()?;
+        // 兼容 (T, 1, C) 与 (1, T, C) 两种布局
+        let logits = if output.shape()[1] == 1 {
+            output.index_axis_move(ndarray::Axis(1), 0)
+        } else {
+            output.index_axis_move(ndarray::Axis(0), 0)
+        };
+        Ok(ctc::log_softmax(&logits.view()))
+    }
+}
@@ -0,0 +1,145 @@
+#[cfg(feature = "native")]
+use super::CaptchaBreaker;
+#[cfg(feature = "native")]
+use crate::environment::CaptchaEnvironment;
+use crate::input;
+use crate::tiles::{self, Compatibility};
+use image::DynamicImage;
+use std::error::Error;
+
+/// 拖动一块后其余块的变化
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum DragMode {
+    /// 其余块依次挪动，例如把一排图块拖成正确的顺序
+    #[default]
+    Insert,
+    /// 与目标位置上的块交换
+    Swap,
+}
+
+#[derive(Debug, Clone)]
+pub struct TileOrder0Config {
+    pub drag: DragMode,
+    /// 网格尺寸 (rows, cols)，为None时自动检测切块边界
+    pub grid: Option<(u32, u32)>,
+    /// 自动检测时每个方向最多的切分数
+    pub max_splits: u32,
+    /// LAPJV迭代次数上限
+    pub max_iterations: usize,
+}
+
+impl Default for TileOrder0Config {
+    fn default() -> Self {
+        TileOrder0Config {
+            drag: DragMode::default(),
+            grid: None,
+            max_splits: 8,
+            max_iterations: 16,
+        }
+    }
+}
+
+/// 一次拖动，位置按行优先编号
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Drag {
+    pub from: usize,
+    pub to: usize,
+    /// 按下鼠标的坐标，即 `from` 位置的中心
+    pub start: (f32, f32),
+    /// 松开鼠标的坐标，即 `to` 位置的中心
+    pub end: (f32, f32),
+}
+
+/// 识别结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct TileOrderSolution {
+    pub rows: u32,
+    pub cols: u32,
+    /// 还原后位置p上应放置当前位于 `arrangement[p]` 的块
+    pub arrangement: Vec<usize>,
+    /// 依次执行这些拖动即可还原图片
+    pub drags: Vec<Drag>,
+}
+
+/// 拖动图块还原图片的验证码，不需要模型
+///
+/// 按块边缘像素的差异计算两两相邻的代价，用LAPJV求使接缝代价最小的排列并局部搜索改进，再分解为拖动操作
+#[derive(Debug, Default)]
+pub struct TileOrder0 {
+    config: TileOrder0Config,
+}
+
+#[cfg(feature = "native")]
+impl CaptchaBreaker for TileOrder0 {
+    fn build(_captcha_environment: &CaptchaEnvironment) -> Result<Self, Box<dyn Error>> {
+        Ok(TileOrder0::new())
+    }
+}
+
+impl TileOrder0 {
+    /// 不需要模型，可以不通过 `CaptchaEnvironment` 直接创建
+    pub fn new() -> Self {
+        TileOrder0 {
+            config: Default::default(),
+        }
+    }
+
+    pub fn with_config(mut self, config: TileOrder0Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn config(&self) -> &TileOrder0Config {
+        &self.config
+    }
+
+    pub fn run(&self, image: &DynamicImage) -> Result<TileOrderSolution, Box<dyn Error>> {
+        // 1. 确定网格并切块
+        let (rows, cols) = self
+            .config
+            .grid
+            .unwrap_or_else(|| tiles::detect_grid(image, self.config.max_splits));
+        if rows * cols < 2 {
+            return Err("没有检测到可以拖动的图块".into());
+        }
+        let tiles = tiles::split_tiles(image, rows, cols)?;
+        // 2. 计算两两之间的边缘相容度并求排列
+        let compatibility = Compatibility::new(&tiles)?;
+        let arrangement = compatibility.best_arrangement(cols as usize, self.config.max_iterations)?;
+        let arrangement = compatibility.refine(arrangement, cols as usize);
+        // 3. 分解为拖动操作
+        let moves = match self.config.drag {
+            DragMode::Insert => tiles::insertions_for(&arrangement),
+            DragMode::Swap => tiles::swaps_for(&arrangement),
+        };
+        let tile_width = image.width() as f32 / cols as f32;
+        let tile_height = image.height() as f32 / rows as f32;
+        let center = |p: usize| {
+            let (row, col) = (p / cols as usize, p % cols as usize);
+            ((col as f32 + 0.5) * tile_width, (row as f32 + 0.5) * tile_height)
+        };
+        let drags = moves
+            .into_iter()
+            .map(|(from, to)| Drag {
+                from,
+                to,
+                start: center(from),
+                end: center(to),
+            })
+            .collect();
+        Ok(TileOrderSolution {
+            rows,
+            cols,
+            arrangement,
+            drags,
+        })
+    }
+
+    pub fn run_from_bytes(&self, bytes: &[u8]) -> Result<TileOrderSolution, Box<dyn Error>> {
+        self.run(&input::decode_bytes(bytes)?)
+    }
+
+    pub fn run_from_base64(&self, data: &str) -> Result<TileOrderSolution, Box<dyn Error>> {
+        self.run(&input::decode_base64(data)?)
+    }
+}
@@ -0,0 +1,305 @@
+use super::CaptchaBreaker;
+use crate::backend::InferenceBackend;
+use crate::click::{ClickConfig, ClickSampler};
+use crate::detection::{self, BBox, Resize, YoloConfig, YoloDetector};
+use crate::drift::{DriftMonitor, DriftSample};
+use crate::environment::CaptchaEnvironment;
+use crate::hooks::Hooks;
+use crate::input;
+use crate::language::{LanguageModel, NgramTable};
+use crate::model::Model;
+use crate::report::Reporter;
+use crate::timing::{self, Outcome, Recorder, Stage, Timings};
+use crate::warmup::warmup_backends;
+use image::imageops::FilterType;
+use image::{DynamicImage, Rgb, RgbImage};
+use ndarray::{Array4, Axis, Ix2};
+use std::error::Error;
+use std::sync::Arc;
+
+/// 检测模型输入尺寸无法从模型读取时使用的默认值
+const DEFAULT_INPUT_SIZE: u32 = 384;
+/// 识别模型输入尺寸无法从模型读取时使用的默认值
+const DEFAULT_OCR_SIZE: u32 = 64;
+
+#[derive(Debug, Clone)]
+pub struct WordOrder0Config {
+    /// 检测模型的配置，与ChineseClick0使用同一个检测模型
+    pub detector: YoloConfig,
+    /// 识别模型输出类别对应的字，为空时从模型元数据 `captcha_breaker.class_labels` 读取
+    pub charset: Vec<char>,
+    /// 每个字保留识别概率最高的几个候选
+    pub top_k: usize,
+    /// 束搜索的宽度
+    pub beam_width: usize,
+    /// 识别概率的对数在总分中的权重，越大越相信识别结果
+    pub ocr_weight: f32,
+    /// 透明像素合成到的背景色
+    pub background: Rgb<u8>,
+    /// 点击位置，默认为检测框中心
+    pub click: ClickConfig,
+}
+
+impl Default for WordOrder0Config {
+    fn default() -> Self {
+        WordOrder0Config {
+            detector: YoloConfig {
+                input_size: None,
+                resize: Resize::Pad,
+                confidence_threshold: 0.5,
+                ..Default::default()
+            },
+            charset: vec![],
+            top_k: 3,
+            beam_width: 64,
+            ocr_weight: 1.0,
+            background: input::DEFAULT_BACKGROUND,
+            click: ClickConfig::default(),
+        }
+    }
+}
+
+/// 一个字的识别候选
+#[derive(Debug, Clone, PartialEq)]
+pub struct CharCandidate {
+    pub char: char,
+    pub probability: f32,
+}
+
+/// 识别结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct WordOrderResult {
+    /// 检测框，顺序与检测结果相同
+    pub boxes: Vec<BBox>,
+    /// 每个检测框的识别候选，按概率从高到低排列
+    pub candidates: Vec<Vec<CharCandidate>>,
+    /// 点选顺序，`order[i]` 为第i个点选的检测框下标
+    pub order: Vec<usize>,
+    /// 按点选顺序组成的文字
+    pub text: String,
+    /// 语言模型与识别概率的总分
+    pub score: f32,
+    /// 按点选顺序需要点击的坐标
+    pub points: Vec<(f32, f32)>,
+    /// 各阶段耗时
+    pub timings: Timings,
+}
+
+/// 语序点选验证码，例如把打乱的 "一心一意" 按成语的顺序点选
+///
+/// 检测每个字并用单字识别模型给出候选，再用语言模型对候选的排列束搜索，选出最通顺的顺序
+pub struct WordOrder0 {
+    detector: YoloDetector,
+    ocr: Arc<dyn InferenceBackend>,
+    language: Box<dyn LanguageModel + Send + Sync>,
+    config: WordOrder0Config,
+    clicks: ClickSampler,
+    drift: Option<Arc<DriftMonitor>>,
+    reporter: Option<Reporter>,
+    hooks: Hooks,
+}
+
+impl CaptchaBreaker for WordOrder0 {
+    fn build(captcha_environment: &CaptchaEnvironment) -> Result<Self, Box<dyn Error>> {
+        let session = captcha_environment.load_models(vec![Model::Yolo11n, Model::CharOcr])?;
+        let mut config = WordOrder0Config::default();
+        if let Some(metadata) = captcha_environment.model_metadata(Model::Yolo11n) {
+            config.detector.class_names = metadata.class_labels;
+        }
+        if let Some(metadata) = captcha_environment.model_metadata(Model::CharOcr) {
+            config.charset = metadata.class_labels.iter().filter_map(|label| label.chars().next()).collect();
+        }
+        config.detector.input_size = Some(session[0].input_size().unwrap_or(DEFAULT_INPUT_SIZE));
+        if let Some(threshold) = captcha_environment.confidence_threshold() {
+            config.detector.confidence_threshold = threshold;
+        }
+        Ok(WordOrder0 {
+            detector: YoloDetector::new(session[0].clone()),
+            ocr: session[1].clone(),
+            language: Box::new(NgramTable::new()),
+            config: Default::default(),
+            clicks: ClickSampler::default(),
+            drift: captcha_environment.drift_monitor().cloned(),
+            reporter: Reporter::from_environment(captcha_environment, &[Model::Yolo11n, Model::CharOcr]),
+            hooks: captcha_environment.hooks().clone(),
+        }
+        .with_config(config))
+    }
+
+    fn warmup(&self) -> Result<(), Box<dyn Error>> {
+        warmup_backends(&[self.detector.backend(), self.ocr.as_ref()])
+    }
+}
+
+impl std::fmt::Debug for WordOrder0 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WordOrder0")
+            .field("detector", &self.detector)
+            .field("config", &self.config)
+            .finish_non_exhaustive()
+    }
+}
+
+impl WordOrder0 {
+    pub fn with_config(mut self, config: WordOrder0Config) -> Self {
+        self.detector = self.detector.with_config(config.detector.clone());
+        self.clicks = ClickSampler::new(config.click);
+        self.config = config;
+        self
+    }
+
+    pub fn config(&self) -> &WordOrder0Config {
+        &self.config
+    }
+
+    /// 设置语言模型，默认的空n-gram表只按识别概率排序，通常需要加载 [`NgramTable`]
+    pub fn with_language_model(mut self, language: impl LanguageModel + Send + Sync + 'static) -> Self {
+        self.language = Box::new(language);
+        self
+    }
+
+    /// 替换构建时从环境得到的处理钩子，见 [`Hooks`]
+    pub fn with_hooks(mut self, hooks: Hooks) -> Self {
+        self.hooks = hooks;
+        self
+    }
+
+    pub fn hooks(&self) -> &Hooks {
+        &self.hooks
+    }
+
+    pub fn run(&self, image: &DynamicImage) -> Result<Vec<(f32, f32)>, Box<dyn Error>> {
+        Ok(self.run_detailed(image)?.points)
+    }
+
+    pub fn run_from_bytes(&self, bytes: &[u8]) -> Result<Vec<(f32, f32)>, Box<dyn Error>> {
+        self.run(&input::decode_bytes(bytes)?)
+    }
+
+    pub fn run_from_base64(&self, data: &str) -> Result<Vec<(f32, f32)>, Box<dyn Error>> {
+        self.run(&input::decode_base64(data)?)
+    }
+
+    pub fn run_detailed(&self, image: &DynamicImage) -> Result<WordOrderResult, Box<dyn Error>> {
+        let res = self.solve(image);
+        timing::record_outcome("word_order_0", Outcome::of(&res));
+        if let Some(reporter) = &self.reporter {
+            let mut report = reporter.start("word_order_0", image).with_outcome(&res);
+            if let Ok(result) = &res {
+                report = report.with_timings(&result.timings);
+                report.confidences = result.boxes.iter().map(|b| b.confidence).collect();
+                report.answer = result.points.clone();
+            }
+            reporter.emit(&report);
+        }
+        res
+    }
+
+    fn solve(&self, image: &DynamicImage) -> Result<WordOrderResult, Box<dyn Error>> {
+        let mut recorder = Recorder::new("word_order_0");
+        let processed_image = recorder.time(Stage::Preprocess, || {
+            let mut rgb = input::to_rgb8(image, self.config.background);
+            self.hooks.preprocess(&mut rgb);
+            rgb
+        });
+        let mut boxes = recorder.time(Stage::Detect, || self.detector.detect_rgb(&processed_image))?;
+        self.hooks.detections(&processed_image, &mut boxes);
+        recorder.record_count("detections", boxes.len());
+        if boxes.is_empty() {
+            return Err("没有检测到任何字".into());
+        }
+        let crops: Vec<RgbImage> =
+            recorder.time(Stage::Crop, || boxes.iter().map(|bbox| detection::crop(&processed_image, bbox)).collect());
+        let candidates = recorder.time(Stage::Embed, || self.recognize(&crops))?;
+        let beam = recorder
+            .time(Stage::Assign, || self.search(&candidates))
+            .ok_or("没有可用的识别候选")?;
+        recorder.record_value("score", beam.score);
+        if let Some(drift) = &self.drift {
+            drift.observe("word_order_0", DriftSample::from_boxes(&boxes));
+        }
+        let mut points = self.clicks.sample(beam.order.iter().map(|&i| &boxes[i]));
+        self.hooks.matches(&mut points);
+        Ok(WordOrderResult {
+            boxes,
+            candidates,
+            order: beam.order,
+            text: beam.text.into_iter().collect(),
+            score: beam.score,
+            points,
+            timings: recorder.finish(),
+        })
+    }
+
+    /// 单字识别，输入 (N, 3, size, size)，输出 (N, 类别数) 的logits
+    fn recognize(&self, crops: &[RgbImage]) -> Result<Vec<Vec<CharCandidate>>, Box<dyn Error>> {
+        let size = self.ocr.input_size().unwrap_or(DEFAULT_OCR_SIZE);
+        let mut batch = Array4::<f32>::zeros((crops.len(), 3, size as usize, size as usize));
+        for (i, crop) in crops.iter().enumerate() {
+            let resized = image::imageops::resize(crop, size, size, FilterType::Triangle);
+            for (x, y, pixel) in resized.enumerate_pixels() {
+                for c in 0..3 {
+                    batch[[i, c, y as usize, x as usize]] = pixel[c] as f32 / 255.0;
+                }
+            }
+        }
+        let logits = self.ocr.run_single(batch)?.into_f32()?.into_dimensionality::<Ix2>()?;
+        if logits.ncols() > self.config.charset.len() {
+            return Err(format!("识别模型输出{}类，字表只有{}个字", logits.ncols(), self.config.charset.len()).into());
+        }
+        Ok(logits
+            .axis_iter(Axis(0))
+            .map(|row| {
+                let max = row.fold(f32::NEG_INFINITY, |a, &b| a.max(b));
+                let sum: f32 = row.iter().map(|x| (x - max).exp()).sum();
+                let mut candidates: Vec<CharCandidate> = row
+                    .iter()
+                    .enumerate()
+                    .map(|(i, x)| CharCandidate {
+                        char: self.config.charset[i],
+                        probability: (x - max).exp() / sum,
+                    })
+                    .collect();
+                candidates.sort_by(|a, b| b.probability.total_cmp(&a.probability));
+                candidates.truncate(self.config.top_k.max(1));
+                candidates
+            })
+            .collect())
+    }
+
+    /// 对 (顺序, 每个位置的候选字) 束搜索：逐个追加一个尚未使用的字及其候选，
+    /// 每步保留 `beam_width` 个总分最高的部分序列
+    fn search(&self, candidates: &[Vec<CharCandidate>]) -> Option<Beam> {
+        let mut beams = vec![Beam {
+            order: vec![],
+            text: vec![],
+            score: 0.0,
+        }];
+        for _ in 0..candidates.len() {
+            let mut next = vec![];
+            for beam in &beams {
+                for (i, options) in candidates.iter().enumerate().filter(|(i, _)| !beam.order.contains(i)) {
+                    for candidate in options {
+                        let mut extended = beam.clone();
+                        extended.score += self.language.log_prob(&beam.text, candidate.char)
+                            + self.config.ocr_weight * candidate.probability.max(f32::MIN_POSITIVE).ln();
+                        extended.order.push(i);
+                        extended.text.push(candidate.char);
+                        next.push(extended);
+                    }
+                }
+            }
+            next.sort_by(|a, b| b.score.total_cmp(&a.score));
+            next.truncate(self.config.beam_width.max(1));
+            beams = next;
+        }
+        beams.into_iter().next()
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Beam {
+    order: Vec<usize>,
+    text: Vec<char>,
+    score: f32,
+}
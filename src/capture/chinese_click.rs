@@ -0,0 +1,103 @@
+use super::{DatasetCapture, write_npy};
+use crate::captcha::{PipelineObserver, PipelineTrace};
+use crate::detection::BBox;
+use image::RgbImage;
+use ndarray::{Array2, ArrayView2};
+use std::error::Error;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::PathBuf;
+
+/// 收集ChineseClick0一次识别的输入、图像块、特征与答案，识别成功后写入样本目录
+pub(crate) struct CaptureObserver<'a> {
+    capture: &'a DatasetCapture,
+    dir: PathBuf,
+    image: Option<RgbImage>,
+    crops: Option<(Vec<RgbImage>, Vec<RgbImage>)>,
+    features: Option<(Array2<f32>, Array2<f32>)>,
+}
+
+impl PipelineObserver for CaptureObserver<'_> {
+    fn on_detections(&mut self, image: &RgbImage, _detections: &[BBox]) {
+        self.image = Some(image.clone());
+    }
+
+    fn on_crops(&mut self, _question_boxes: &[BBox], _ans_boxes: &[BBox], question_crops: &[RgbImage], ans_crops: &[RgbImage]) {
+        if self.capture.config.save_crops {
+            self.crops = Some((question_crops.to_vec(), ans_crops.to_vec()));
+        }
+    }
+
+    fn on_features(&mut self, question_features: ArrayView2<f32>, ans_features: ArrayView2<f32>) {
+        if self.capture.config.save_features {
+            self.features = Some((question_features.to_owned(), ans_features.to_owned()));
+        }
+    }
+
+    fn on_finished(&mut self, trace: &PipelineTrace) {
+        // 部分答案的图像块与答案对不上，不适合作为训练数据
+        if trace.incomplete {
+            return;
+        }
+        // 采集失败不影响识别结果
+        if let Err(e) = self.write(trace) {
+            log::warn!("保存样本到{}失败：{}", self.dir.display(), e);
+        }
+    }
+}
+
+impl<'a> CaptureObserver<'a> {
+    pub(crate) fn new(capture: &'a DatasetCapture, dir: PathBuf) -> Self {
+        CaptureObserver {
+            capture,
+            dir,
+            image: None,
+            crops: None,
+            features: None,
+        }
+    }
+
+    fn write(&self, trace: &PipelineTrace) -> Result<(), Box<dyn Error>> {
+        fs::create_dir_all(&self.dir)?;
+        if let Some(image) = &self.image {
+            image.save(self.dir.join("image.png"))?;
+        }
+        if let Some((question_crops, ans_crops)) = &self.crops {
+            for (i, crop) in question_crops.iter().enumerate() {
+                crop.save(self.dir.join(format!("question_{}.png", i)))?;
+            }
+            for (i, crop) in ans_crops.iter().enumerate() {
+                crop.save(self.dir.join(format!("answer_{}.png", i)))?;
+            }
+        }
+        if let Some((question_features, ans_features)) = &self.features {
+            write_npy(&self.dir.join("question_features.npy"), question_features.view())?;
+            write_npy(&self.dir.join("answer_features.npy"), ans_features.view())?;
+        }
+        fs::write(self.dir.join("answer.json"), answer_json(trace))?;
+        Ok(())
+    }
+}
+
+/// 答案的JSON，坐标为原图像素坐标
+fn answer_json(trace: &PipelineTrace) -> String {
+    let bbox = |b: &BBox| format!("[{}, {}, {}, {}]", b.rect.x_min, b.rect.y_min, b.rect.x_max, b.rect.y_max);
+    let boxes = |boxes: &[BBox]| boxes.iter().map(bbox).collect::<Vec<_>>().join(", ");
+    let mut answers = String::new();
+    for (i, m) in trace.answers.iter().enumerate() {
+        if i > 0 {
+            answers.push_str(", ");
+        }
+        let _ = write!(
+            answers,
+            "{{\"question\": {}, \"answer\": {}, \"point\": [{}, {}], \"distance\": {}}}",
+            m.question_index, m.answer_index, m.answer_point.0, m.answer_point.1, m.distance
+        );
+    }
+    format!(
+        "{{\"question_boxes\": [{}], \"answer_boxes\": [{}], \"answers\": [{}]}}\n",
+        boxes(&trace.question_boxes),
+        boxes(&trace.ans_boxes),
+        answers
+    )
+}
@@ -0,0 +1,105 @@
+//! 采集识别过的验证码作为重新训练模型的数据集
+//!
+//! 只有通过 [`CaptchaEnvironment::with_capture`](crate::environment::CaptchaEnvironment::with_capture)
+//! 显式开启时才会保存数据，请确认有权保存这些图片
+
+#[cfg(feature = "chinese_click_0")]
+mod chinese_click;
+
+#[cfg(feature = "chinese_click_0")]
+pub(crate) use chinese_click::CaptureObserver;
+
+use crate::rng::SplitMix64;
+use ndarray::ArrayView2;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 数据采集的配置
+#[derive(Debug, Clone)]
+pub struct CaptureConfig {
+    /// 保存数据的目录，每个样本保存在其中的一个子目录
+    pub dir: PathBuf,
+    /// 采样率，0到1之间，1表示保存所有识别成功的验证码
+    pub sample_rate: f32,
+    /// 是否保存截取的图像块
+    pub save_crops: bool,
+    /// 是否保存孪生网络的特征
+    pub save_features: bool,
+    /// 采样使用的随机数种子
+    pub seed: u64,
+}
+
+impl CaptureConfig {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        CaptureConfig {
+            dir: dir.into(),
+            sample_rate: 1.0,
+            save_crops: true,
+            save_features: true,
+            seed: 0xca97,
+        }
+    }
+}
+
+/// 按采样率保存样本，多个验证码实现可以共用
+#[derive(Debug)]
+pub struct DatasetCapture {
+    config: CaptureConfig,
+    /// (采样用的随机数, 已保存的样本数)
+    state: Mutex<(SplitMix64, u64)>,
+}
+
+impl DatasetCapture {
+    pub fn new(config: CaptureConfig) -> Self {
+        let rng = SplitMix64::new(config.seed);
+        DatasetCapture {
+            config,
+            state: Mutex::new((rng, 0)),
+        }
+    }
+
+    pub fn config(&self) -> &CaptureConfig {
+        &self.config
+    }
+
+    /// 按采样率决定是否采集下一个样本，采集时返回 `dir/captcha/时间戳_序号` 形式的样本目录
+    pub fn sample(&self, captcha: &str) -> Option<PathBuf> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if state.0.next_f32() >= self.config.sample_rate {
+            return None;
+        }
+        state.1 += 1;
+        let millis = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis());
+        Some(self.config.dir.join(captcha).join(format!("{}_{:06}", millis, state.1)))
+    }
+
+    /// 创建收集ChineseClick0一次识别数据的observer，没有被采样时返回None
+    #[cfg(feature = "chinese_click_0")]
+    pub(crate) fn observer(&self, captcha: &str) -> Option<CaptureObserver<'_>> {
+        Some(CaptureObserver::new(self, self.sample(captcha)?))
+    }
+}
+
+/// 以NumPy的 `.npy` 格式(v1.0，小端f32)保存矩阵
+pub fn write_npy(path: &Path, array: ArrayView2<f32>) -> Result<(), Box<dyn Error>> {
+    let mut header = format!(
+        "{{'descr': '<f4', 'fortran_order': False, 'shape': ({}, {}), }}",
+        array.nrows(),
+        array.ncols()
+    );
+    // 魔数、版本与长度共10字节，头部需要以换行结尾并对齐到64字节
+    let padding = 64 - (10 + header.len() + 1) % 64;
+    header.extend(std::iter::repeat_n(' ', padding % 64));
+    header.push('\n');
+    let mut bytes = b"\x93NUMPY\x01\x00".to_vec();
+    bytes.extend((header.len() as u16).to_le_bytes());
+    bytes.extend(header.as_bytes());
+    for value in array.iter() {
+        bytes.extend(value.to_le_bytes());
+    }
+    fs::write(path, bytes)?;
+    Ok(())
+}
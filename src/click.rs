@@ -0,0 +1,93 @@
+//! 点击位置采样
+//!
+//! 每次都点在检测框的正中心是明显的脚本特征，开启随机化后在框内按截断的二维高斯分布采样，
+//! 偏移在框自身的坐标系中计算，旋转框同样适用
+
+use crate::detection::BBox;
+use crate::rng::SplitMix64;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// 截断采样最多的重试次数，之后退回框中心
+const MAX_REJECTIONS: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClickConfig {
+    /// 为false时总是返回框中心
+    pub randomize: bool,
+    /// 偏移的标准差，为半宽/半高的比例
+    pub sigma: f32,
+    /// 偏移不超过半宽/半高的该比例，保证点击落在字形内部而不是框的边角
+    pub max_offset: f32,
+    pub seed: u64,
+}
+
+impl Default for ClickConfig {
+    fn default() -> Self {
+        ClickConfig {
+            randomize: false,
+            sigma: 0.3,
+            max_offset: 0.6,
+            seed: 0xc11c,
+        }
+    }
+}
+
+impl ClickConfig {
+    /// 开启随机化，其余参数使用默认值
+    pub fn randomized(seed: u64) -> Self {
+        ClickConfig {
+            randomize: true,
+            seed,
+            ..Default::default()
+        }
+    }
+}
+
+/// 按 [`ClickConfig`] 为检测框生成点击位置
+///
+/// 每次 [`sample`](Self::sample) 使用新的随机状态，相同的seed与相同的调用顺序得到相同的结果
+#[derive(Debug, Default)]
+pub struct ClickSampler {
+    config: ClickConfig,
+    calls: AtomicU64,
+}
+
+impl ClickSampler {
+    pub fn new(config: ClickConfig) -> Self {
+        ClickSampler {
+            config,
+            calls: AtomicU64::new(0),
+        }
+    }
+
+    pub fn config(&self) -> &ClickConfig {
+        &self.config
+    }
+
+    /// 为一次识别中的所有框生成点击位置
+    pub fn sample<'a>(&self, boxes: impl IntoIterator<Item = &'a BBox>) -> Vec<(f32, f32)> {
+        if !self.config.randomize {
+            return boxes.into_iter().map(BBox::center).collect();
+        }
+        let call = self.calls.fetch_add(1, Ordering::Relaxed);
+        let mut rng = SplitMix64::new(self.config.seed ^ call.wrapping_mul(0x9e37_79b9_7f4a_7c15));
+        boxes.into_iter().map(|bbox| sample_point(bbox, &self.config, &mut rng)).collect()
+    }
+}
+
+fn sample_point(bbox: &BBox, config: &ClickConfig, rng: &mut SplitMix64) -> (f32, f32) {
+    let (cx, cy) = bbox.center();
+    let limit = config.max_offset.clamp(0.0, 1.0);
+    let sigma = config.sigma.max(0.0);
+    let mut truncated = || {
+        (0..MAX_REJECTIONS)
+            .map(|_| rng.next_normal() * sigma)
+            .find(|offset| offset.abs() <= limit)
+            .unwrap_or(0.0)
+    };
+    // 框坐标系中的偏移
+    let dx = truncated() * bbox.width() / 2.0;
+    let dy = truncated() * bbox.height() / 2.0;
+    let (sin, cos) = bbox.angle.sin_cos();
+    (cx + dx * cos - dy * sin, cy + dx * sin + dy * cos)
+}
@@ -0,0 +1,138 @@
+use crate::model::Precision;
+use std::error::Error;
+use std::path::PathBuf;
+
+/// 环境变量的前缀，例如 `CAPTCHA_BREAKER_MODEL_DIR`
+pub const ENV_PREFIX: &str = "CAPTCHA_BREAKER_";
+/// 指定配置文件路径的环境变量
+pub const ENV_CONFIG_FILE: &str = "CAPTCHA_BREAKER_CONFIG";
+
+/// [`CaptchaEnvironment`](crate::environment::CaptchaEnvironment) 的部署配置，`None` 表示不覆盖下层的值
+///
+/// 由 [`CaptchaEnvironmentBuilder`](crate::environment::CaptchaEnvironmentBuilder) 按
+/// 代码 → 配置文件 → 环境变量的顺序合并，后面的层覆盖前面的层
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "config-file", derive(serde::Deserialize), serde(default, deny_unknown_fields))]
+pub struct EnvironmentConfig {
+    /// 模型文件所在目录，默认为 `./models`
+    pub model_dir: Option<PathBuf>,
+    /// 按优先级排列的执行器名称，例如 `["cuda", "cpu"]`，见 [`execution_provider`](crate::loader::execution_provider)
+    pub execution_providers: Option<Vec<String>>,
+    /// 见 [`SessionOptions::intra_threads`](crate::loader::SessionOptions::intra_threads)
+    pub intra_threads: Option<usize>,
+    /// 见 [`SessionOptions::inter_threads`](crate::loader::SessionOptions::inter_threads)
+    pub inter_threads: Option<usize>,
+    /// 所有模型优先使用的精度
+    pub precision: Option<Precision>,
+    /// 覆盖检测模型的置信度阈值
+    pub confidence_threshold: Option<f32>,
+    /// 覆盖检测结果的NMS阈值
+    pub nms_threshold: Option<f32>,
+    /// 见 [`SessionOptions::cpu_arena`](crate::loader::SessionOptions::cpu_arena)
+    pub cpu_arena: Option<bool>,
+    /// 见 [`SessionOptions::cuda_memory_limit`](crate::loader::SessionOptions::cuda_memory_limit)
+    pub cuda_memory_limit: Option<usize>,
+    /// 见 [`SessionOptions::device_id`](crate::loader::SessionOptions::device_id)
+    pub device_id: Option<i32>,
+    /// ONNX Runtime动态库或其所在目录，只在开启 `load-dynamic` 时使用，见 [`runtime::probe`](crate::runtime::probe)
+    pub runtime_path: Option<PathBuf>,
+}
+
+impl EnvironmentConfig {
+    /// 用other中设置了的项覆盖当前配置
+    pub fn merge(&mut self, other: EnvironmentConfig) {
+        macro_rules! merge {
+            ($($field:ident),*) => {
+                $(if other.$field.is_some() {
+                    self.$field = other.$field;
+                })*
+            };
+        }
+        merge!(
+            model_dir,
+            execution_providers,
+            intra_threads,
+            inter_threads,
+            precision,
+            confidence_threshold,
+            nms_threshold,
+            cpu_arena,
+            cuda_memory_limit,
+            device_id,
+            runtime_path
+        );
+    }
+
+    /// 从当前进程的环境变量读取
+    pub fn from_env() -> Result<Self, Box<dyn Error>> {
+        Self::from_vars(std::env::vars())
+    }
+
+    /// 从 `CAPTCHA_BREAKER_` 开头的键值对读取，忽略其他键，值为空视为未设置
+    ///
+    /// | 变量 | 字段 |
+    /// |---|---|
+    /// | `CAPTCHA_BREAKER_MODEL_DIR` | `model_dir` |
+    /// | `CAPTCHA_BREAKER_EP` | `execution_providers`，以逗号分隔 |
+    /// | `CAPTCHA_BREAKER_INTRA_THREADS` | `intra_threads` |
+    /// | `CAPTCHA_BREAKER_INTER_THREADS` | `inter_threads` |
+    /// | `CAPTCHA_BREAKER_PRECISION` | `precision`，`fp32` / `fp16` / `int8` |
+    /// | `CAPTCHA_BREAKER_CONFIDENCE_THRESHOLD` | `confidence_threshold` |
+    /// | `CAPTCHA_BREAKER_NMS_THRESHOLD` | `nms_threshold` |
+    /// | `CAPTCHA_BREAKER_CPU_ARENA` | `cpu_arena`，`true` / `false` |
+    /// | `CAPTCHA_BREAKER_CUDA_MEMORY_LIMIT` | `cuda_memory_limit`，字节数 |
+    /// | `CAPTCHA_BREAKER_DEVICE_ID` | `device_id` |
+    /// | `CAPTCHA_BREAKER_RUNTIME_PATH` | `runtime_path` |
+    pub fn from_vars(vars: impl IntoIterator<Item = (String, String)>) -> Result<Self, Box<dyn Error>> {
+        let mut res = EnvironmentConfig::default();
+        for (key, value) in vars {
+            let Some(name) = key.strip_prefix(ENV_PREFIX) else {
+                continue;
+            };
+            let value = value.trim();
+            if value.is_empty() {
+                continue;
+            }
+            let invalid = |e: &dyn std::fmt::Display| format!("环境变量{}的值{}无效: {}", key, value, e);
+            match name {
+                "MODEL_DIR" => res.model_dir = Some(PathBuf::from(value)),
+                "EP" => {
+                    res.execution_providers = Some(
+                        value
+                            .split(',')
+                            .map(|s| s.trim().to_lowercase())
+                            .filter(|s| !s.is_empty())
+                            .collect(),
+                    )
+                }
+                "INTRA_THREADS" => res.intra_threads = Some(value.parse().map_err(|e| invalid(&e))?),
+                "INTER_THREADS" => res.inter_threads = Some(value.parse().map_err(|e| invalid(&e))?),
+                "PRECISION" => {
+                    res.precision = Some(Precision::from_name(value).ok_or_else(|| invalid(&"可选fp32、fp16、int8"))?)
+                }
+                "CONFIDENCE_THRESHOLD" => res.confidence_threshold = Some(value.parse().map_err(|e| invalid(&e))?),
+                "NMS_THRESHOLD" => res.nms_threshold = Some(value.parse().map_err(|e| invalid(&e))?),
+                "CPU_ARENA" => res.cpu_arena = Some(value.parse().map_err(|e| invalid(&e))?),
+                "CUDA_MEMORY_LIMIT" => res.cuda_memory_limit = Some(value.parse().map_err(|e| invalid(&e))?),
+                "DEVICE_ID" => res.device_id = Some(value.parse().map_err(|e| invalid(&e))?),
+                "RUNTIME_PATH" => res.runtime_path = Some(PathBuf::from(value)),
+                _ => {}
+            }
+        }
+        Ok(res)
+    }
+
+    /// 解析TOML格式的配置，键与字段名相同
+    #[cfg(feature = "config-file")]
+    pub fn from_toml(content: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(toml::from_str(content)?)
+    }
+
+    /// 读取TOML配置文件
+    #[cfg(feature = "config-file")]
+    pub fn from_file(path: impl AsRef<std::path::Path>) -> Result<Self, Box<dyn Error>> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path).map_err(|e| format!("读取配置文件{}失败: {}", path.display(), e))?;
+        Self::from_toml(&content).map_err(|e| format!("解析配置文件{}失败: {}", path.display(), e).into())
+    }
+}
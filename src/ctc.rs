@@ -0,0 +1,91 @@
+//! CTC(Connectionist Temporal Classification)解码
+
+use ndarray::{Array2, ArrayView2, Axis};
+use std::collections::HashMap;
+
+/// 对每个时间步做log softmax，输入为 (时间步, 类别数) 的logits
+pub fn log_softmax(logits: &ArrayView2<f32>) -> Array2<f32> {
+    let mut res = logits.to_owned();
+    for mut row in res.axis_iter_mut(Axis(0)) {
+        let max = row.fold(f32::NEG_INFINITY, |a, &b| a.max(b));
+        let log_sum = row.fold(0.0, |acc, &x| acc + (x - max).exp()).ln() + max;
+        row.mapv_inplace(|x| x - log_sum);
+    }
+    res
+}
+
+/// 贪心解码：每个时间步取概率最大的类别，合并重复并去掉blank
+///
+/// 返回类别序列以及整体置信度(各字符最大概率的乘积)
+pub fn greedy_decode(log_probs: &ArrayView2<f32>, blank: usize) -> (Vec<usize>, f32) {
+    let mut res = vec![];
+    let mut score = 0.0;
+    let mut prev = blank;
+    for row in log_probs.axis_iter(Axis(0)) {
+        let (best, &best_log_prob) = row
+            .indexed_iter()
+            .fold((blank, &f32::NEG_INFINITY), |acc, (i, p)| if p > acc.1 { (i, p) } else { acc });
+        if best != blank && best != prev {
+            res.push(best);
+            score += best_log_prob;
+        }
+        prev = best;
+    }
+    (res, score.exp())
+}
+
+fn log_add(a: f32, b: f32) -> f32 {
+    if a == f32::NEG_INFINITY {
+        return b;
+    }
+    if b == f32::NEG_INFINITY {
+        return a;
+    }
+    let max = a.max(b);
+    max + ((a - max).exp() + (b - max).exp()).ln()
+}
+
+/// 前缀束搜索解码
+///
+/// 返回概率最高的类别序列以及它的概率
+pub fn beam_decode(log_probs: &ArrayView2<f32>, blank: usize, beam_width: usize) -> (Vec<usize>, f32) {
+    // 前缀 -> (以blank结尾的log概率, 以非blank结尾的log概率)
+    let mut beams: Vec<(Vec<usize>, (f32, f32))> = vec![(vec![], (0.0, f32::NEG_INFINITY))];
+    for row in log_probs.axis_iter(Axis(0)) {
+        let mut next: HashMap<Vec<usize>, (f32, f32)> = HashMap::new();
+        for (prefix, (p_blank, p_non_blank)) in &beams {
+            let p_total = log_add(*p_blank, *p_non_blank);
+            for (c, &p) in row.indexed_iter() {
+                if c == blank {
+                    let entry = next.entry(prefix.clone()).or_insert((f32::NEG_INFINITY, f32::NEG_INFINITY));
+                    entry.0 = log_add(entry.0, p_total + p);
+                    continue;
+                }
+                let mut extended = prefix.clone();
+                extended.push(c);
+                let entry = next.entry(extended).or_insert((f32::NEG_INFINITY, f32::NEG_INFINITY));
+                if prefix.last() == Some(&c) {
+                    // 重复字符之间必须隔一个blank
+                    entry.1 = log_add(entry.1, p_blank + p);
+                    let same = next.entry(prefix.clone()).or_insert((f32::NEG_INFINITY, f32::NEG_INFINITY));
+                    same.1 = log_add(same.1, p_non_blank + p);
+                } else {
+                    entry.1 = log_add(entry.1, p_total + p);
+                }
+            }
+        }
+        let mut candidates: Vec<_> = next.into_iter().collect();
+        candidates.sort_by(|a, b| {
+            let pa = log_add(a.1.0, a.1.1);
+            let pb = log_add(b.1.0, b.1.1);
+            pb.total_cmp(&pa).then_with(|| a.0.cmp(&b.0))
+        });
+        candidates.truncate(beam_width.max(1));
+        beams = candidates;
+    }
+    beams
+        .into_iter()
+        .next()
+        .map(|(prefix, (p_blank, p_non_blank))| (prefix, log_add(p_blank, p_non_blank).exp()))
+        .unwrap_or_default()
+}
@@ -0,0 +1,44 @@
+use super::{BBox, rotated_corners};
+use image::{Rgb, RgbImage};
+
+/// 截取检测框内的图像块，旋转框会被摆正后截取
+pub fn crop(image: &RgbImage, bbox: &BBox) -> RgbImage {
+    if bbox.angle == 0.0 {
+        let rect = bbox.rect.to_pixels(image.dimensions());
+        return image::imageops::crop_imm(image, rect.x_min, rect.y_min, rect.width(), rect.height()).to_image();
+    }
+    crop_rotated(image, bbox.center(), (bbox.width(), bbox.height()), bbox.angle)
+}
+
+/// 截取以center为中心、旋转angle弧度的 size 大小的区域，输出为摆正后的图像块，超出原图的部分为黑色
+pub fn crop_rotated(image: &RgbImage, center: (f32, f32), size: (f32, f32), angle: f32) -> RgbImage {
+    let (width, height) = ((size.0.round() as u32).max(1), (size.1.round() as u32).max(1));
+    let [origin, right, _, bottom] = rotated_corners(center, (width as f32, height as f32), angle);
+    // 输出每移动一个像素，原图中沿旋转后的x轴/y轴移动的距离
+    let step_x = ((right.0 - origin.0) / width as f32, (right.1 - origin.1) / width as f32);
+    let step_y = ((bottom.0 - origin.0) / height as f32, (bottom.1 - origin.1) / height as f32);
+    RgbImage::from_fn(width, height, |u, v| {
+        let (u, v) = (u as f32 + 0.5, v as f32 + 0.5);
+        let x = origin.0 + step_x.0 * u + step_y.0 * v;
+        let y = origin.1 + step_x.1 * u + step_y.1 * v;
+        bilinear(image, x - 0.5, y - 0.5)
+    })
+}
+
+fn bilinear(image: &RgbImage, x: f32, y: f32) -> Rgb<u8> {
+    let (x0, y0) = (x.floor(), y.floor());
+    let (fx, fy) = (x - x0, y - y0);
+    let pixel = |x: f32, y: f32| {
+        if x < 0.0 || y < 0.0 || x >= image.width() as f32 || y >= image.height() as f32 {
+            [0.0; 3]
+        } else {
+            image.get_pixel(x as u32, y as u32).0.map(f32::from)
+        }
+    };
+    let (a, b, c, d) = (pixel(x0, y0), pixel(x0 + 1.0, y0), pixel(x0, y0 + 1.0), pixel(x0 + 1.0, y0 + 1.0));
+    Rgb(std::array::from_fn(|i| {
+        let top = a[i] * (1.0 - fx) + b[i] * fx;
+        let bottom = c[i] * (1.0 - fx) + d[i] * fx;
+        (top * (1.0 - fy) + bottom * fy).round().clamp(0.0, 255.0) as u8
+    }))
+}
@@ -0,0 +1,178 @@
+use super::BBox;
+use super::sanitize::sanitize;
+use super::tiling::{TilingConfig, cut_by_window, tile_windows};
+use super::yolo::{Resize, YoloConfig, YoloLayout, decode_with_classes, nms, resolve_layout};
+use crate::backend::{InferenceBackend, TensorPool};
+use crate::geometry::{self, Transform};
+use crate::input;
+use image::{DynamicImage, GenericImageView, RgbImage};
+use image::imageops::FilterType;
+use ndarray::{Array4, ArrayView2, ArrayViewMut3, Axis, Ix3, Ix4};
+use std::borrow::Cow;
+use std::error::Error;
+use std::sync::Arc;
+
+/// 模型输入尺寸无法从模型读取时使用的默认值
+const DEFAULT_INPUT_SIZE: u32 = 640;
+
+/// YOLO检测器，负责预处理、推理、输出解码以及NMS
+#[derive(Debug, Clone)]
+pub struct YoloDetector {
+    backend: Arc<dyn InferenceBackend>,
+    config: YoloConfig,
+    pool: Arc<TensorPool>,
+}
+
+impl YoloDetector {
+    pub fn new(backend: Arc<dyn InferenceBackend>) -> Self {
+        YoloDetector {
+            backend,
+            config: Default::default(),
+            pool: Default::default(),
+        }
+    }
+
+    /// 输入输出张量使用的缓冲区池，可以与同一个验证码实现中的其他模型共用
+    pub fn with_pool(mut self, pool: Arc<TensorPool>) -> Self {
+        self.pool = pool;
+        self
+    }
+
+    pub fn pool(&self) -> &Arc<TensorPool> {
+        &self.pool
+    }
+
+    pub fn with_config(mut self, config: YoloConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn config(&self) -> &YoloConfig {
+        &self.config
+    }
+
+    pub fn backend(&self) -> &dyn InferenceBackend {
+        self.backend.as_ref()
+    }
+
+    pub fn class_name(&self, bbox: &BBox) -> Option<&str> {
+        self.config.class_names.get(bbox.class_id as usize).map(String::as_str)
+    }
+
+    /// 模型的正方形输入尺寸
+    pub fn input_size(&self) -> u32 {
+        self.config
+            .input_size
+            .or_else(|| self.backend.input_size())
+            .unwrap_or(DEFAULT_INPUT_SIZE)
+    }
+
+    /// 分块检测时单次推理最多的图块数，不超过模型固定的批次大小
+    pub fn max_batch_size(&self) -> usize {
+        let limit = self.backend.batch_size().unwrap_or(usize::MAX);
+        self.config.max_batch_size.clamp(1, limit.max(1))
+    }
+
+    /// 检测并返回原图坐标下的目标，检测框按 `sanitize` 配置清洗后限制在图片范围内
+    ///
+    /// 设置了 `tiling` 且图片足够大时分块检测，见 [`TilingConfig`]
+    pub fn detect(&self, image: &DynamicImage) -> Result<Vec<BBox>, Box<dyn Error>> {
+        self.detect_rgb(&input::to_rgb8(image, input::DEFAULT_BACKGROUND))
+    }
+
+    /// 与 [`detect`](Self::detect) 相同，输入为已经转换好的RGB图片
+    pub fn detect_rgb(&self, image: &RgbImage) -> Result<Vec<BBox>, Box<dyn Error>> {
+        let size = self.input_size();
+        if let Some(tiling) = self.config.tiling.as_ref().filter(|tiling| {
+            tiling.applies(image.dimensions(), tiling.tile_size.unwrap_or(size))
+        }) {
+            return self.detect_tiled(image, tiling);
+        }
+        let mut input: Array4<f32> = self.pool.zeros(Ix4(1, 3, size as usize, size as usize));
+        let transform = self.preprocess_image(input.index_axis_mut(Axis(0), 0), image, size, self.config.resize);
+        let output = self
+            .backend
+            .run_single_pooled(input, &self.pool)?
+            .into_f32()?
+            .into_dimensionality::<Ix3>()?;
+        let detections = self.decode_output(output.index_axis(Axis(0), 0));
+        self.pool.recycle(output);
+        let detections = detections?.into_iter().map(|d| d.transformed(&transform)).collect();
+        Ok(sanitize(detections, (image.width(), image.height()), &self.config.sanitize))
+    }
+
+    /// 按滑动窗口分块检测，每批最多 [`max_batch_size`](Self::max_batch_size) 个图块，
+    /// 批次维度固定的模型总是输入完整的一批
+    fn detect_tiled(&self, image: &RgbImage, tiling: &TilingConfig) -> Result<Vec<BBox>, Box<dyn Error>> {
+        let size = self.input_size();
+        let windows = tile_windows(image.dimensions(), tiling.tile_size.unwrap_or(size), tiling.overlap);
+        let mut detections = vec![];
+        for chunk in windows.chunks(self.max_batch_size()) {
+            // 模型的批次维度固定时最后一批补零，补上的输出被丢弃
+            let batch = self.backend.batch_size().unwrap_or(chunk.len()).max(chunk.len());
+            let mut input: Array4<f32> = self.pool.zeros(Ix4(batch, 3, size as usize, size as usize));
+            let transforms: Vec<Transform> = chunk
+                .iter()
+                .zip(input.axis_iter_mut(Axis(0)))
+                .map(|(window, input)| {
+                    let tile = image.view(window.x_min, window.y_min, window.width(), window.height()).to_image();
+                    // 图块保持宽高比，不足一个图块的部分补边，而不是拉伸
+                    self.preprocess_image(input, &tile, size, Resize::Pad)
+                        .then(Transform::translate(window.x_min as f32, window.y_min as f32))
+                })
+                .collect();
+            let output = self
+                .backend
+                .run_single_pooled(input, &self.pool)?
+                .into_f32()?
+                .into_dimensionality::<Ix3>()?;
+            if output.len_of(Axis(0)) < chunk.len() {
+                return Err(format!("检测模型输出{}张图片的结果，输入{}个图块", output.len_of(Axis(0)), chunk.len()).into());
+            }
+            let decoded = chunk
+                .iter()
+                .zip(&transforms)
+                .zip(output.axis_iter(Axis(0)))
+                .map(|((window, transform), output)| {
+                    Ok(self
+                        .decode_output(output)?
+                        .into_iter()
+                        .map(|d| d.transformed(transform))
+                        .filter(|d| !cut_by_window(d, window, image.dimensions(), tiling.edge_margin)))
+                })
+                .collect::<Result<Vec<_>, Box<dyn Error>>>();
+            self.pool.recycle(output);
+            detections.extend(decoded?.into_iter().flatten());
+        }
+        let detections = nms(detections, tiling.merge_threshold);
+        Ok(sanitize(detections, (image.width(), image.height()), &self.config.sanitize))
+    }
+
+    /// 解码单张图片的输出并做NMS，坐标为模型输入坐标
+    fn decode_output(&self, output: ArrayView2<f32>) -> Result<Vec<BBox>, Box<dyn Error>> {
+        let num_classes = self.config.class_names.len();
+        let layout = resolve_layout(self.config.layout, &output, num_classes);
+        let detections = decode_with_classes(output, layout, self.config.confidence_threshold, num_classes)?;
+        Ok(match self.config.nms_threshold.filter(|_| layout != YoloLayout::EndToEnd) {
+            Some(threshold) => nms(detections, threshold),
+            None => detections,
+        })
+    }
+
+    /// 把图片写入批次中的一个输入，返回模型输入空间到图片的变换
+    fn preprocess_image(&self, mut input: ArrayViewMut3<f32>, image: &RgbImage, size: u32, resize: Resize) -> Transform {
+        let (width, height) = image.dimensions();
+        let (new_width, new_height) = geometry::fitted_size((width, height), size, resize);
+        let resized = if (new_width, new_height) == (width, height) {
+            Cow::Borrowed(image)
+        } else {
+            Cow::Owned(image::imageops::resize(image, new_width, new_height, FilterType::Triangle))
+        };
+        for (x, y, pixel) in resized.enumerate_pixels() {
+            for c in 0..3 {
+                input[[c, y as usize, x as usize]] = pixel[c] as f32 / 255.0;
+            }
+        }
+        geometry::model_to_image((width, height), size, resize)
+    }
+}
@@ -0,0 +1,140 @@
+//! 通用目标检测
+
+mod crop;
+mod detector;
+mod order;
+mod sanitize;
+mod tiling;
+mod yolo;
+
+pub use crop::{crop, crop_rotated};
+pub use detector::YoloDetector;
+pub use order::{BoxOrder, group_rows, sort_boxes};
+pub use sanitize::{SanitizeConfig, sanitize};
+pub use tiling::{TilingConfig, cut_by_window, tile_windows};
+pub use yolo::{Resize, YoloConfig, YoloLayout, decode, decode_with_classes, nms};
+
+use crate::geometry::{Rect, Transform};
+
+/// 检测框，坐标为原图像素坐标
+///
+/// 旋转框(OBB)的 `rect` 为旋转前的框，`angle` 为绕中心旋转的角度(弧度，图像坐标系下顺时针)
+#[derive(Debug, Clone, PartialEq)]
+pub struct BBox {
+    pub rect: Rect<f32>,
+    pub confidence: f32,
+    /// 模型输出的类别下标
+    pub class_id: u32,
+    /// 轴对齐框为0
+    pub angle: f32,
+}
+
+impl BBox {
+    pub fn new(rect: Rect<f32>, confidence: f32, class_id: u32) -> Self {
+        BBox {
+            rect,
+            confidence,
+            class_id,
+            angle: 0.0,
+        }
+    }
+
+    pub fn width(&self) -> f32 {
+        self.rect.width()
+    }
+
+    pub fn height(&self) -> f32 {
+        self.rect.height()
+    }
+
+    pub fn center(&self) -> (f32, f32) {
+        self.rect.center()
+    }
+
+    pub fn area(&self) -> f32 {
+        self.rect.area()
+    }
+
+    /// 四个顶点，按左上、右上、右下、左下(旋转前)的顺序
+    pub fn corners(&self) -> [(f32, f32); 4] {
+        rotated_corners(self.center(), (self.width(), self.height()), self.angle)
+    }
+
+    /// 交并比，旋转框按实际多边形计算
+    pub fn iou(&self, other: &BBox) -> f32 {
+        if self.angle == 0.0 && other.angle == 0.0 {
+            return self.rect.iou(&other.rect);
+        }
+        let intersection = polygon_area(&clip_polygon(&self.corners(), &other.corners()));
+        let union = self.area() + other.area() - intersection;
+        if union > 0.0 { intersection / union } else { 0.0 }
+    }
+
+    /// 缩放坐标，例如从缩小后的图片映射回原图
+    pub fn scaled(mut self, scale_x: f32, scale_y: f32) -> Self {
+        self.rect = self.rect.scaled(scale_x, scale_y);
+        self
+    }
+
+    pub fn transformed(mut self, transform: &Transform) -> Self {
+        self.rect = transform.apply_rect(self.rect);
+        self
+    }
+}
+
+pub(crate) fn rotated_corners((cx, cy): (f32, f32), (w, h): (f32, f32), angle: f32) -> [(f32, f32); 4] {
+    let (sin, cos) = angle.sin_cos();
+    [(-w, -h), (w, -h), (w, h), (-w, h)].map(|(dx, dy)| {
+        let (dx, dy) = (dx / 2.0, dy / 2.0);
+        (cx + dx * cos - dy * sin, cy + dx * sin + dy * cos)
+    })
+}
+
+/// 鞋带公式
+fn polygon_area(points: &[(f32, f32)]) -> f32 {
+    let n = points.len();
+    (0..n)
+        .map(|i| {
+            let (a, b) = (points[i], points[(i + 1) % n]);
+            a.0 * b.1 - b.0 * a.1
+        })
+        .sum::<f32>()
+        .abs()
+        / 2.0
+}
+
+/// Sutherland-Hodgman裁剪，两个多边形都需要是凸多边形
+fn clip_polygon(subject: &[(f32, f32)], clip: &[(f32, f32)]) -> Vec<(f32, f32)> {
+    // 统一为逆时针(面积为正)方向，保证"在边的左侧"即为内部
+    let area = clip
+        .iter()
+        .zip(clip.iter().cycle().skip(1))
+        .map(|(a, b)| a.0 * b.1 - b.0 * a.1)
+        .sum::<f32>();
+    // 退化为线段或点的多边形没有方向，交集为空；signum对0返回±1，需要在取符号前判断
+    if area == 0.0 {
+        return vec![];
+    }
+    let orientation = area.signum();
+    let cross = |a: (f32, f32), b: (f32, f32), p: (f32, f32)| ((b.0 - a.0) * (p.1 - a.1) - (b.1 - a.1) * (p.0 - a.0)) * orientation;
+    let mut output = subject.to_vec();
+    for i in 0..clip.len() {
+        let (a, b) = (clip[i], clip[(i + 1) % clip.len()]);
+        let input = std::mem::take(&mut output);
+        for j in 0..input.len() {
+            let (p, q) = (input[j], input[(j + 1) % input.len()]);
+            let (cp, cq) = (cross(a, b, p), cross(a, b, q));
+            if cp >= 0.0 {
+                output.push(p);
+            }
+            if (cp >= 0.0) != (cq >= 0.0) {
+                let t = cp / (cp - cq);
+                output.push((p.0 + (q.0 - p.0) * t, p.1 + (q.1 - p.1) * t));
+            }
+        }
+        if output.is_empty() {
+            break;
+        }
+    }
+    output
+}
@@ -0,0 +1,71 @@
+use super::BBox;
+use std::cmp::Ordering;
+
+/// 检测框的排列顺序
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum BoxOrder {
+    /// 按左边界从左到右，左边界相同时从上到下
+    #[default]
+    LeftToRight,
+    /// 按上边界从上到下，上边界相同时从左到右
+    TopToBottom,
+    /// 先分行，行从上到下，行内从左到右；中心的纵向距离不超过行高一半的框视为同一行
+    ReadingOrder,
+}
+
+/// 按order排序，比较时不截断坐标，相同输入总是得到相同顺序
+pub fn sort_boxes(mut boxes: Vec<BBox>, order: BoxOrder) -> Vec<BBox> {
+    let left_to_right = |a: &BBox, b: &BBox| -> Ordering {
+        a.rect
+            .x_min
+            .total_cmp(&b.rect.x_min)
+            .then_with(|| a.rect.y_min.total_cmp(&b.rect.y_min))
+    };
+    match order {
+        BoxOrder::LeftToRight => boxes.sort_by(left_to_right),
+        BoxOrder::TopToBottom => boxes.sort_by(|a, b| {
+            a.rect
+                .y_min
+                .total_cmp(&b.rect.y_min)
+                .then_with(|| a.rect.x_min.total_cmp(&b.rect.x_min))
+        }),
+        BoxOrder::ReadingOrder => {
+            let mut rows = vec![0; boxes.len()];
+            for (row, indices) in group_rows(&boxes).into_iter().enumerate() {
+                for i in indices {
+                    rows[i] = row;
+                }
+            }
+            let mut indexed: Vec<(usize, BBox)> = rows.into_iter().zip(boxes).collect();
+            indexed.sort_by(|(ra, a), (rb, b)| ra.cmp(rb).then_with(|| left_to_right(a, b)));
+            boxes = indexed.into_iter().map(|(_, b)| b).collect();
+        }
+    }
+    boxes
+}
+
+/// 按中心y聚类分行，返回每一行的框下标，行从上到下排列
+///
+/// 按中心y从小到大依次加入当前行，与当前行平均中心的距离超过平均框高一半时另起一行
+pub fn group_rows(boxes: &[BBox]) -> Vec<Vec<usize>> {
+    let mut order: Vec<usize> = (0..boxes.len()).collect();
+    order.sort_by(|&a, &b| boxes[a].center().1.total_cmp(&boxes[b].center().1));
+    let mut rows: Vec<Vec<usize>> = vec![];
+    // 当前行的中心y之和与高度之和
+    let (mut sum_y, mut sum_height) = (0.0, 0.0);
+    for i in order {
+        let (cy, height) = (boxes[i].center().1, boxes[i].height());
+        match rows.last_mut() {
+            Some(row) if (cy - sum_y / row.len() as f32).abs() <= 0.5 * sum_height / row.len() as f32 => {
+                row.push(i);
+            }
+            _ => {
+                rows.push(vec![i]);
+                (sum_y, sum_height) = (0.0, 0.0);
+            }
+        }
+        sum_y += cy;
+        sum_height += height;
+    }
+    rows
+}
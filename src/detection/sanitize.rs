@@ -0,0 +1,66 @@
+use super::BBox;
+use crate::geometry::Rect;
+
+/// 检测框清洗的配置
+#[derive(Debug, Clone)]
+pub struct SanitizeConfig {
+    /// 宽或高小于该值的框视为过小
+    pub min_size: f32,
+    /// 过小的框在每一边向外扩展的像素数
+    pub margin: f32,
+}
+
+impl Default for SanitizeConfig {
+    fn default() -> Self {
+        SanitizeConfig {
+            min_size: 4.0,
+            margin: 2.0,
+        }
+    }
+}
+
+/// 清洗模型输出的检测框，保证之后截取图像块时不会越界或得到空图
+///
+/// - 丢弃含NaN/无穷值以及 `x_max < x_min` 或 `y_max < y_min` 的框
+/// - 轴对齐框限制在图片范围内，限制后不足1像素的框被丢弃
+/// - 过小的框向外扩展 `margin` 后重新限制在图片范围内
+/// - 旋转框只丢弃中心在图片外的框，超出图片的部分在截取时补黑色
+pub fn sanitize(boxes: Vec<BBox>, image_size: (u32, u32), config: &SanitizeConfig) -> Vec<BBox> {
+    let (width, height) = (image_size.0 as f32, image_size.1 as f32);
+    boxes
+        .into_iter()
+        .filter_map(|mut bbox| {
+            let Rect { x_min, y_min, x_max, y_max } = bbox.rect;
+            let finite = [x_min, y_min, x_max, y_max, bbox.confidence, bbox.angle]
+                .iter()
+                .all(|v| v.is_finite());
+            if !finite || x_max < x_min || y_max < y_min {
+                return None;
+            }
+            if bbox.angle != 0.0 {
+                let (cx, cy) = bbox.center();
+                let inside = (0.0..width).contains(&cx) && (0.0..height).contains(&cy);
+                return inside.then_some(bbox);
+            }
+            let mut rect = clip(bbox.rect, width, height);
+            if rect.width() < 1.0 || rect.height() < 1.0 {
+                return None;
+            }
+            if rect.width() < config.min_size || rect.height() < config.min_size {
+                let m = config.margin;
+                rect = clip(Rect::new(rect.x_min - m, rect.y_min - m, rect.x_max + m, rect.y_max + m), width, height);
+            }
+            bbox.rect = rect;
+            Some(bbox)
+        })
+        .collect()
+}
+
+fn clip(rect: Rect<f32>, width: f32, height: f32) -> Rect<f32> {
+    Rect::new(
+        rect.x_min.clamp(0.0, width),
+        rect.y_min.clamp(0.0, height),
+        rect.x_max.clamp(0.0, width),
+        rect.y_max.clamp(0.0, height),
+    )
+}
@@ -0,0 +1,77 @@
+use super::BBox;
+use crate::geometry::Rect;
+
+/// 大图分块检测的配置
+///
+/// 拼接的长截图或整页截图远大于模型输入时，整图缩放后字形只剩几个像素。分块检测以滑动窗口切成
+/// 相互重叠的图块分别检测，坐标映射回原图后跨图块做NMS
+#[derive(Debug, Clone, PartialEq)]
+pub struct TilingConfig {
+    /// 图块边长(原图像素)，为None时使用模型输入尺寸，图块不经缩放送入模型
+    pub tile_size: Option<u32>,
+    /// 相邻图块重叠的比例，应使重叠部分的宽度大于最大的目标
+    pub overlap: f32,
+    /// 图片的宽或高超过图块边长的该倍数时才分块，否则整图缩放后检测
+    pub min_ratio: f32,
+    /// 贴着图块内侧边缘(不是原图边缘)的框是被截断的目标，距离边缘小于该像素数的框被丢弃，
+    /// 完整的目标由相邻的图块检测
+    pub edge_margin: f32,
+    /// 跨图块合并的IoU阈值
+    pub merge_threshold: f32,
+}
+
+impl Default for TilingConfig {
+    fn default() -> Self {
+        TilingConfig {
+            tile_size: None,
+            overlap: 0.2,
+            min_ratio: 1.5,
+            edge_margin: 2.0,
+            merge_threshold: 0.45,
+        }
+    }
+}
+
+impl TilingConfig {
+    /// 该尺寸的图片是否需要分块
+    pub fn applies(&self, image_size: (u32, u32), tile_size: u32) -> bool {
+        let limit = tile_size as f32 * self.min_ratio.max(1.0);
+        image_size.0 as f32 > limit || image_size.1 as f32 > limit
+    }
+}
+
+/// 覆盖整张图片的滑动窗口，按行优先顺序返回
+///
+/// 每个方向上的窗口数为保证重叠比例所需的最少数量，窗口均匀分布且最后一个窗口与图片边缘对齐；
+/// 图片小于图块的方向上只有一个与图片等长的窗口
+pub fn tile_windows(image_size: (u32, u32), tile_size: u32, overlap: f32) -> Vec<Rect<u32>> {
+    let xs = axis_windows(image_size.0, tile_size, overlap);
+    let ys = axis_windows(image_size.1, tile_size, overlap);
+    ys.iter()
+        .flat_map(|&(y, height)| xs.iter().map(move |&(x, width)| Rect::new(x, y, x + width, y + height)))
+        .collect()
+}
+
+/// 一个方向上的 (起点, 长度)
+fn axis_windows(length: u32, tile_size: u32, overlap: f32) -> Vec<(u32, u32)> {
+    let tile_size = tile_size.max(1);
+    if length <= tile_size {
+        return vec![(0, length)];
+    }
+    let stride = (tile_size as f32 * (1.0 - overlap.clamp(0.0, 0.9))).max(1.0);
+    let span = length - tile_size;
+    let count = (span as f32 / stride).ceil() as u32 + 1;
+    (0..count)
+        .map(|i| ((span as u64 * i as u64 / (count - 1) as u64) as u32, tile_size))
+        .collect()
+}
+
+/// 框(原图坐标)是否贴着窗口的内侧边缘，即可能被窗口截断
+pub fn cut_by_window(bbox: &BBox, window: &Rect<u32>, image_size: (u32, u32), margin: f32) -> bool {
+    let rect = &bbox.rect;
+    let (x_min, y_min, x_max, y_max) = (window.x_min as f32, window.y_min as f32, window.x_max as f32, window.y_max as f32);
+    (window.x_min > 0 && rect.x_min - x_min < margin)
+        || (window.y_min > 0 && rect.y_min - y_min < margin)
+        || (window.x_max < image_size.0 && x_max - rect.x_max < margin)
+        || (window.y_max < image_size.1 && y_max - rect.y_max < margin)
+}
@@ -0,0 +1,179 @@
+use super::{BBox, SanitizeConfig, TilingConfig};
+use crate::geometry::Rect;
+use ndarray::{ArrayView1, ArrayView2, Axis, s};
+use std::error::Error;
+
+/// YOLO输出布局
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum YoloLayout {
+    /// 根据输出形状(以及已知的类别数)自动判断
+    #[default]
+    Auto,
+    /// 导出时已包含NMS，每行为 (x_min, y_min, x_max, y_max, confidence, class)
+    EndToEnd,
+    /// YOLOv5，每行为 (cx, cy, w, h, objectness, class_scores...)
+    V5,
+    /// YOLOv8/v11，(4 + 类别数, 锚点数)，每列为 (cx, cy, w, h, class_scores...)
+    V8,
+    /// YOLOv8/v11旋转框(OBB)，(4 + 类别数 + 1, 锚点数)，每列为 (cx, cy, w, h, class_scores..., angle)，
+    /// 角度为弧度；与V8形状相近，Auto不会自动选择该布局
+    Obb,
+}
+
+/// 输入图片缩放方式
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Resize {
+    /// 直接拉伸到输入尺寸
+    #[default]
+    Stretch,
+    /// 保持长宽比缩放后放在左上角，其余部分填充黑色
+    Pad,
+}
+
+#[derive(Debug, Clone)]
+pub struct YoloConfig {
+    /// 正方形输入尺寸，为None时从session读取
+    pub input_size: Option<u32>,
+    pub resize: Resize,
+    pub layout: YoloLayout,
+    /// 类别名称，下标与模型输出的类别对应
+    pub class_names: Vec<String>,
+    /// 低于该置信度的检测结果会被忽略
+    pub confidence_threshold: f32,
+    /// NMS的IoU阈值，为None时不做NMS；EndToEnd布局不会再做NMS
+    pub nms_threshold: Option<f32>,
+    /// 映射回原图后的检测框清洗
+    pub sanitize: SanitizeConfig,
+    /// 大图分块检测，为None时整图缩放到输入尺寸
+    pub tiling: Option<TilingConfig>,
+    /// 分块检测时单次推理最多的图块数，模型的批次维度固定时取模型的值
+    pub max_batch_size: usize,
+}
+
+impl Default for YoloConfig {
+    fn default() -> Self {
+        YoloConfig {
+            input_size: None,
+            resize: Resize::default(),
+            layout: YoloLayout::default(),
+            class_names: vec![],
+            confidence_threshold: 0.5,
+            nms_threshold: Some(0.45),
+            sanitize: SanitizeConfig::default(),
+            tiling: None,
+            max_batch_size: 4,
+        }
+    }
+}
+
+/// `num_classes` 为0表示类别数未知
+pub(super) fn resolve_layout(layout: YoloLayout, output: &ArrayView2<f32>, num_classes: usize) -> YoloLayout {
+    if layout != YoloLayout::Auto {
+        return layout;
+    }
+    let (rows, cols) = output.dim();
+    // 每行6列时v5单类别与已含NMS的输出无法区分，按已含NMS处理
+    if cols == 6 {
+        YoloLayout::EndToEnd
+    } else if num_classes > 0 && cols == 5 + num_classes {
+        YoloLayout::V5
+    } else if (num_classes > 0 && rows == 4 + num_classes) || rows < cols {
+        // v8/v11的锚点数远大于 4 + 类别数
+        YoloLayout::V8
+    } else {
+        YoloLayout::V5
+    }
+}
+
+/// 把单张图片的原始输出解码为检测框(模型输入坐标)
+pub fn decode(output: ArrayView2<f32>, layout: YoloLayout, confidence_threshold: f32) -> Result<Vec<BBox>, Box<dyn Error>> {
+    decode_with_classes(output, layout, confidence_threshold, 0)
+}
+
+/// 与 [`decode`] 相同，已知类别数时可以更准确地判断 [`YoloLayout::Auto`]
+///
+/// v5与v8布局对所有类别分数取argmax，v5的置信度为 objectness × 类别分数；输出的形状不符合布局时返回错误
+pub fn decode_with_classes(
+    output: ArrayView2<f32>,
+    layout: YoloLayout,
+    confidence_threshold: f32,
+    num_classes: usize,
+) -> Result<Vec<BBox>, Box<dyn Error>> {
+    let layout = resolve_layout(layout, &output, num_classes);
+    let (rows, cols) = output.dim();
+    // 每个框至少需要的值：EndToEnd与V5按行，V8与OBB按列
+    let require = |len: usize, required: usize| -> Result<(), Box<dyn Error>> {
+        if len < required {
+            return Err(format!("检测模型的输出形状 ({}, {}) 不符合{:?}布局，每个框至少需要{}个值", rows, cols, layout, required).into());
+        }
+        Ok(())
+    };
+    let from_center =
+        |cx: f32, cy: f32, w: f32, h: f32, confidence: f32, class: usize| BBox::new(Rect::from_center((cx, cy), (w, h)), confidence, class as u32);
+    let best_class = |scores: ArrayView1<f32>| {
+        scores
+            .iter()
+            .copied()
+            .enumerate()
+            .fold((0, f32::NEG_INFINITY), |acc, (i, s)| if s > acc.1 { (i, s) } else { acc })
+    };
+    let detections = match layout {
+        YoloLayout::EndToEnd => {
+            require(cols, 6)?;
+            output
+                .axis_iter(Axis(0))
+                .filter(|row| row[4] > confidence_threshold)
+                .map(|row| BBox::new(Rect::new(row[0], row[1], row[2], row[3]), row[4], row[5].max(0.0) as u32))
+                .collect()
+        }
+        YoloLayout::V5 => {
+            require(cols, 6)?;
+            output
+                .axis_iter(Axis(0))
+                .filter_map(|row| {
+                    let (class, score) = best_class(row.slice(s![5..]));
+                    let confidence = row[4] * score.max(0.0);
+                    (confidence > confidence_threshold).then(|| from_center(row[0], row[1], row[2], row[3], confidence, class))
+                })
+                .collect()
+        }
+        YoloLayout::V8 => {
+            require(rows, 5)?;
+            output
+                .axis_iter(Axis(1))
+                .filter_map(|column| {
+                    let (class, confidence) = best_class(column.slice(s![4..]));
+                    (confidence > confidence_threshold)
+                        .then(|| from_center(column[0], column[1], column[2], column[3], confidence, class))
+                })
+                .collect()
+        }
+        YoloLayout::Obb => {
+            require(rows, 6)?;
+            output
+                .axis_iter(Axis(1))
+                .filter_map(|column| {
+                    let (class, confidence) = best_class(column.slice(s![4..rows - 1]));
+                    (confidence > confidence_threshold).then(|| BBox {
+                        angle: column[rows - 1],
+                        ..from_center(column[0], column[1], column[2], column[3], confidence, class)
+                    })
+                })
+                .collect()
+        }
+        YoloLayout::Auto => unreachable!("resolve_layout不会返回Auto"),
+    };
+    Ok(detections)
+}
+
+/// 按类别做非极大值抑制，结果按置信度从高到低排列
+pub fn nms(mut detections: Vec<BBox>, iou_threshold: f32) -> Vec<BBox> {
+    detections.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+    let mut kept: Vec<BBox> = vec![];
+    for d in detections {
+        if kept.iter().all(|k| k.class_id != d.class_id || k.iou(&d) <= iou_threshold) {
+            kept.push(d);
+        }
+    }
+    kept
+}
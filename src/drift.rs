@@ -0,0 +1,309 @@
+//! 样式漂移检测
+//!
+//! 服务商更换验证码样式后，检测框数量、检测置信度与匹配距离通常会先于识别率发生变化。
+//! [`DriftMonitor`] 按验证码实现分别统计这些指标最近一段时间的均值，与开始时的基线比较，
+//! 偏移超过配置的范围时触发回调，提示需要重新采集数据并训练模型
+
+use crate::detection::BBox;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::Mutex;
+
+/// 一次识别的统计
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct DriftSample {
+    pub detections: usize,
+    /// 检测框置信度的均值，没有检测框时为None
+    pub mean_confidence: Option<f32>,
+    /// 匹配距离的均值，不做特征匹配的验证码为None
+    pub mean_distance: Option<f32>,
+}
+
+impl DriftSample {
+    /// 由检测框得到数量与平均置信度
+    pub fn from_boxes(boxes: &[BBox]) -> Self {
+        DriftSample {
+            detections: boxes.len(),
+            mean_confidence: mean(boxes.iter().map(|b| b.confidence)),
+            mean_distance: None,
+        }
+    }
+
+    /// 加入匹配距离
+    pub fn with_distances(mut self, distances: impl IntoIterator<Item = f32>) -> Self {
+        self.mean_distance = mean(distances);
+        self
+    }
+}
+
+fn mean(values: impl IntoIterator<Item = f32>) -> Option<f32> {
+    let (sum, count) = values.into_iter().fold((0.0, 0usize), |(sum, count), v| (sum + v, count + 1));
+    (count > 0).then(|| sum / count as f32)
+}
+
+/// 监控的指标
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum DriftMetric {
+    Detections,
+    MeanConfidence,
+    MeanDistance,
+}
+
+impl DriftMetric {
+    pub const ALL: [DriftMetric; 3] = [DriftMetric::Detections, DriftMetric::MeanConfidence, DriftMetric::MeanDistance];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DriftMetric::Detections => "detections",
+            DriftMetric::MeanConfidence => "mean_confidence",
+            DriftMetric::MeanDistance => "mean_distance",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DriftConfig {
+    /// 统计最近多少次识别
+    pub window: usize,
+    /// 前多少次识别作为基线，也可以用 [`DriftMonitor::set_baseline`] 直接指定
+    pub baseline: usize,
+    /// 平均检测框数量允许偏离基线的数量
+    pub detections_tolerance: f32,
+    /// 平均置信度允许偏离基线的差值
+    pub confidence_tolerance: f32,
+    /// 平均匹配距离允许偏离基线的差值，距离的量级与度量有关，需要按实际数据调整
+    pub distance_tolerance: f32,
+}
+
+impl Default for DriftConfig {
+    fn default() -> Self {
+        DriftConfig {
+            window: 64,
+            baseline: 64,
+            detections_tolerance: 1.5,
+            confidence_tolerance: 0.1,
+            distance_tolerance: 0.25,
+        }
+    }
+}
+
+impl DriftConfig {
+    fn tolerance(&self, metric: DriftMetric) -> f32 {
+        match metric {
+            DriftMetric::Detections => self.detections_tolerance,
+            DriftMetric::MeanConfidence => self.confidence_tolerance,
+            DriftMetric::MeanDistance => self.distance_tolerance,
+        }
+    }
+}
+
+/// 一段时间内各指标的均值
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct DriftStats {
+    pub samples: usize,
+    pub detections: f32,
+    /// 所有样本都没有该指标时为None
+    pub mean_confidence: Option<f32>,
+    pub mean_distance: Option<f32>,
+}
+
+impl DriftStats {
+    pub fn from_samples<'a>(samples: impl IntoIterator<Item = &'a DriftSample>) -> Self {
+        let samples: Vec<&DriftSample> = samples.into_iter().collect();
+        DriftStats {
+            samples: samples.len(),
+            detections: mean(samples.iter().map(|s| s.detections as f32)).unwrap_or_default(),
+            mean_confidence: mean(samples.iter().filter_map(|s| s.mean_confidence)),
+            mean_distance: mean(samples.iter().filter_map(|s| s.mean_distance)),
+        }
+    }
+
+    pub fn get(&self, metric: DriftMetric) -> Option<f32> {
+        match metric {
+            DriftMetric::Detections => (self.samples > 0).then_some(self.detections),
+            DriftMetric::MeanConfidence => self.mean_confidence,
+            DriftMetric::MeanDistance => self.mean_distance,
+        }
+    }
+}
+
+/// 一个指标开始偏离基线
+#[derive(Debug, Clone, PartialEq)]
+pub struct DriftAlarm {
+    pub breaker: String,
+    pub metric: DriftMetric,
+    pub baseline: f32,
+    pub current: f32,
+}
+
+impl fmt::Display for DriftAlarm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}的{}从{:.3}变为{:.3}，验证码样式可能已经改变",
+            self.breaker,
+            self.metric.name(),
+            self.baseline,
+            self.current
+        )
+    }
+}
+
+#[derive(Default)]
+struct BreakerState {
+    baseline: Option<DriftStats>,
+    /// 基线确定之前收集的样本
+    pending: Vec<DriftSample>,
+    window: VecDeque<DriftSample>,
+    drifting: Vec<DriftMetric>,
+}
+
+/// 指标开始漂移时的回调
+type DriftHook = Box<dyn Fn(&DriftAlarm) + Send + Sync>;
+
+/// 按验证码实现统计识别指标并检测漂移，由互斥锁保护，可以在多个线程之间共享
+///
+/// 窗口填满后每次识别都与基线比较，指标超出范围时触发一次回调，回到范围内后才会再次触发
+pub struct DriftMonitor {
+    config: DriftConfig,
+    state: Mutex<HashMap<String, BreakerState>>,
+    hooks: Vec<DriftHook>,
+}
+
+impl fmt::Debug for DriftMonitor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DriftMonitor").field("config", &self.config).finish_non_exhaustive()
+    }
+}
+
+impl Default for DriftMonitor {
+    fn default() -> Self {
+        DriftMonitor::new(DriftConfig::default())
+    }
+}
+
+impl DriftMonitor {
+    pub fn new(config: DriftConfig) -> Self {
+        DriftMonitor {
+            config,
+            state: Mutex::new(HashMap::new()),
+            hooks: vec![],
+        }
+    }
+
+    /// 注册指标开始漂移时的回调
+    pub fn on_drift(mut self, hook: impl Fn(&DriftAlarm) + Send + Sync + 'static) -> Self {
+        self.hooks.push(Box::new(hook));
+        self
+    }
+
+    pub fn config(&self) -> &DriftConfig {
+        &self.config
+    }
+
+    /// 记录一次识别，返回新出现的漂移
+    pub fn observe(&self, breaker: &str, sample: DriftSample) -> Vec<DriftAlarm> {
+        let alarms = {
+            let mut state = self.state.lock().unwrap();
+            let state = state.entry(breaker.to_string()).or_default();
+            if state.baseline.is_none() {
+                state.pending.push(sample);
+                if state.pending.len() >= self.config.baseline.max(1) {
+                    state.baseline = Some(DriftStats::from_samples(&state.pending));
+                    state.pending.clear();
+                }
+                return vec![];
+            }
+            if state.window.len() == self.config.window.max(1) {
+                state.window.pop_front();
+            }
+            state.window.push_back(sample);
+            self.check(breaker, state)
+        };
+        // 回调在锁外执行，回调中可以再次查询或重置
+        for alarm in &alarms {
+            log::warn!("{}", alarm);
+            for hook in &self.hooks {
+                hook(alarm);
+            }
+        }
+        alarms
+    }
+
+    fn check(&self, breaker: &str, state: &mut BreakerState) -> Vec<DriftAlarm> {
+        let Some(baseline) = state.baseline else {
+            return vec![];
+        };
+        if state.window.len() < self.config.window.max(1) {
+            return vec![];
+        }
+        let current = DriftStats::from_samples(&state.window);
+        let mut alarms = vec![];
+        for metric in DriftMetric::ALL {
+            let (Some(expected), Some(value)) = (baseline.get(metric), current.get(metric)) else {
+                continue;
+            };
+            let drifting = (value - expected).abs() > self.config.tolerance(metric);
+            let was_drifting = state.drifting.contains(&metric);
+            if drifting && !was_drifting {
+                state.drifting.push(metric);
+                alarms.push(DriftAlarm {
+                    breaker: breaker.to_string(),
+                    metric,
+                    baseline: expected,
+                    current: value,
+                });
+            } else if !drifting && was_drifting {
+                state.drifting.retain(|&m| m != metric);
+            }
+        }
+        alarms
+    }
+
+    /// 正在漂移的指标
+    pub fn drifting(&self, breaker: &str) -> Vec<DriftMetric> {
+        self.state
+            .lock()
+            .unwrap()
+            .get(breaker)
+            .map(|state| state.drifting.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn is_drifting(&self, breaker: &str) -> bool {
+        !self.drifting(breaker).is_empty()
+    }
+
+    /// 任意验证码实现的任意指标正在漂移
+    pub fn any_drifting(&self) -> bool {
+        self.state.lock().unwrap().values().any(|state| !state.drifting.is_empty())
+    }
+
+    pub fn baseline(&self, breaker: &str) -> Option<DriftStats> {
+        self.state.lock().unwrap().get(breaker).and_then(|state| state.baseline)
+    }
+
+    /// 指定基线，例如使用评估集上的统计，之后的识别直接与它比较
+    pub fn set_baseline(&self, breaker: &str, baseline: DriftStats) {
+        let mut state = self.state.lock().unwrap();
+        let state = state.entry(breaker.to_string()).or_default();
+        state.baseline = Some(baseline);
+        state.pending.clear();
+        state.drifting.clear();
+    }
+
+    /// 最近窗口内的统计，窗口可能还没有填满
+    pub fn stats(&self, breaker: &str) -> Option<DriftStats> {
+        self.state
+            .lock()
+            .unwrap()
+            .get(breaker)
+            .filter(|state| !state.window.is_empty())
+            .map(|state| DriftStats::from_samples(&state.window))
+    }
+
+    /// 清除breaker的基线与统计，更新模型后重新学习基线
+    pub fn reset(&self, breaker: &str) {
+        self.state.lock().unwrap().remove(breaker);
+    }
+}
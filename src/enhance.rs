@@ -0,0 +1,135 @@
+//! 检测前的图像增强，用于对抗验证码中的噪点与干扰背景
+
+use image::{Rgb, RgbImage};
+use std::borrow::Cow;
+
+/// 图像增强的配置，各步骤默认关闭，按 背景去除 → 去噪 → 对比度归一化 → 锐化 的顺序执行
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EnhanceConfig {
+    /// 把与背景色(图片边缘像素各通道的中位数)相近的像素替换为白色，值为每个通道允许的差值
+    pub remove_background: Option<u8>,
+    /// 3×3中值滤波，去除椒盐噪点
+    pub denoise: bool,
+    /// 按亮度的1%与99%分位数拉伸对比度
+    pub normalize_contrast: bool,
+    /// 反锐化掩模，值为高斯模糊的sigma
+    pub sharpen: Option<f32>,
+}
+
+impl EnhanceConfig {
+    /// 是否有需要执行的步骤
+    pub fn is_enabled(&self) -> bool {
+        self.remove_background.is_some() || self.denoise || self.normalize_contrast || self.sharpen.is_some()
+    }
+
+    /// 增强图片，没有开启任何步骤时直接返回原图
+    pub fn apply<'a>(&self, image: &'a RgbImage) -> Cow<'a, RgbImage> {
+        if !self.is_enabled() {
+            return Cow::Borrowed(image);
+        }
+        Cow::Owned(enhance(image, self))
+    }
+}
+
+/// 按config增强图片
+pub fn enhance(image: &RgbImage, config: &EnhanceConfig) -> RgbImage {
+    let mut image = image.clone();
+    if let Some(tolerance) = config.remove_background {
+        image = remove_background(&image, tolerance);
+    }
+    if config.denoise {
+        image = median_filter(&image);
+    }
+    if config.normalize_contrast {
+        image = normalize_contrast(&image);
+    }
+    if let Some(sigma) = config.sharpen {
+        image = image::imageops::unsharpen(&image, sigma, 0);
+    }
+    image
+}
+
+/// 边缘像素各通道的中位数作为背景色，相近的像素替换为白色
+pub fn remove_background(image: &RgbImage, tolerance: u8) -> RgbImage {
+    let (width, height) = image.dimensions();
+    if width == 0 || height == 0 {
+        return image.clone();
+    }
+    let mut border: Vec<Rgb<u8>> = vec![];
+    for x in 0..width {
+        border.push(*image.get_pixel(x, 0));
+        border.push(*image.get_pixel(x, height - 1));
+    }
+    for y in 0..height {
+        border.push(*image.get_pixel(0, y));
+        border.push(*image.get_pixel(width - 1, y));
+    }
+    let background: [u8; 3] = std::array::from_fn(|c| {
+        let mut values: Vec<u8> = border.iter().map(|p| p[c]).collect();
+        values.sort_unstable();
+        values[values.len() / 2]
+    });
+    let mut output = image.clone();
+    for pixel in output.pixels_mut() {
+        if (0..3).all(|c| pixel[c].abs_diff(background[c]) <= tolerance) {
+            *pixel = Rgb([255, 255, 255]);
+        }
+    }
+    output
+}
+
+/// 3×3中值滤波，边缘按最近的像素补齐
+pub fn median_filter(image: &RgbImage) -> RgbImage {
+    let (width, height) = image.dimensions();
+    RgbImage::from_fn(width, height, |x, y| {
+        let mut window = [[0u8; 9]; 3];
+        for (k, (dx, dy)) in (-1i64..=1).flat_map(|dy| (-1i64..=1).map(move |dx| (dx, dy))).enumerate() {
+            let nx = (x as i64 + dx).clamp(0, width as i64 - 1) as u32;
+            let ny = (y as i64 + dy).clamp(0, height as i64 - 1) as u32;
+            let pixel = image.get_pixel(nx, ny);
+            for (channel, value) in window.iter_mut().zip(pixel.0) {
+                channel[k] = value;
+            }
+        }
+        Rgb(window.map(|mut values| {
+            values.sort_unstable();
+            values[4]
+        }))
+    })
+}
+
+/// 按亮度的1%与99%分位数线性拉伸，三个通道使用相同的系数以保持色调
+pub fn normalize_contrast(image: &RgbImage) -> RgbImage {
+    let mut histogram = [0usize; 256];
+    for pixel in image.pixels() {
+        histogram[luma(pixel) as usize] += 1;
+    }
+    let total: usize = histogram.iter().sum();
+    let percentile = |p: f32| {
+        let target = (total as f32 * p) as usize;
+        let mut count = 0;
+        for (value, &n) in histogram.iter().enumerate() {
+            count += n;
+            if count > target {
+                return value as f32;
+            }
+        }
+        255.0
+    };
+    let (low, high) = (percentile(0.01), percentile(0.99));
+    if high - low < 1.0 {
+        return image.clone();
+    }
+    let scale = 255.0 / (high - low);
+    let mut output = image.clone();
+    for pixel in output.pixels_mut() {
+        for value in pixel.0.iter_mut() {
+            *value = ((*value as f32 - low) * scale).round().clamp(0.0, 255.0) as u8;
+        }
+    }
+    output
+}
+
+fn luma(pixel: &Rgb<u8>) -> u8 {
+    ((pixel[0] as u32 * 299 + pixel[1] as u32 * 587 + pixel[2] as u32 * 114) / 1000) as u8
+}
@@ -1,52 +1,363 @@
+use crate::backend::{InferenceBackend, OrtBackend, ReloadableBackend};
+use crate::capture::{CaptureConfig, DatasetCapture};
+use crate::canary::Canary;
 use crate::captcha::CaptchaBreaker;
-use crate::loader::{ModelLoader, ModelLoaderTrait};
-use crate::model::Model;
-use ort::session::Session;
-use std::cell::RefCell;
-use std::collections::HashMap;
+use crate::config::{ENV_CONFIG_FILE, EnvironmentConfig};
+use crate::drift::DriftMonitor;
+use crate::error::CaptchaError;
+use crate::governor::{Governed, Governor, GovernorConfig};
+use crate::hooks::Hooks;
+use crate::inspect::{ModelDescription, OnnxHeader};
+use crate::loader::{DirModelLoader, ModelLoader, ModelLoaderTrait, ModelWatcher, SessionOptions, execution_provider};
+use crate::metadata::EmbeddedMetadata;
+use crate::model::{Model, Precision};
+use crate::report::{ModelVersion, ReportHook, SolveReport};
+use crate::runtime::{self, Linking};
+use std::collections::{BTreeMap, HashMap};
 use std::error::Error;
-use std::sync::Arc;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, RwLock};
 use ort::execution_providers::{CPUExecutionProvider, ExecutionProviderDispatch};
 
+/// 每个模型注册的版本，按版本名排序
+type ModelVersions = HashMap<Model, BTreeMap<String, Arc<dyn InferenceBackend>>>;
+
+/// 模型加载环境
+///
+/// `CaptchaEnvironment` 与所有验证码实现都是 `Send + Sync`，可以放进 `Arc` 在多个线程(例如tokio的工作线程)之间共享，
+/// 已加载的模型与元数据由读写锁保护
 pub struct CaptchaEnvironment {
-    model_loader: Box<dyn ModelLoaderTrait>,
-    models: RefCell<HashMap<Model, Arc<Session>>>,
+    model_loader: Arc<dyn ModelLoaderTrait>,
+    models: RwLock<HashMap<Model, Arc<ReloadableBackend>>>,
+    /// 注册的其他版本，见 [`register_model_version`](Self::register_model_version)
+    versions: RwLock<ModelVersions>,
+    metadata: RwLock<HashMap<Model, EmbeddedMetadata>>,
+    /// 同一时间只从加载器加载一个模型，避免多个线程重复加载同一个模型
+    loading: Mutex<()>,
     ep: Vec<ExecutionProviderDispatch>,
+    /// 是否只使用CPU执行
+    cpu_only: bool,
+    precision: Precision,
+    model_precision: HashMap<Model, Precision>,
+    loaded_precision: RwLock<HashMap<Model, Precision>>,
+    session_options: SessionOptions,
+    capture: Option<Arc<DatasetCapture>>,
+    drift: Option<Arc<DriftMonitor>>,
+    report: Option<ReportHook>,
+    governor: Option<Arc<Governor>>,
+    hooks: Hooks,
+    confidence_threshold: Option<f32>,
+    nms_threshold: Option<f32>,
+    runtime_path: Option<PathBuf>,
 }
 
 impl Default for CaptchaEnvironment {
     fn default() -> Self {
-        CaptchaEnvironment {
-            model_loader: ModelLoader::DefaultModelLoader.get_model_loader(),
-            models: Default::default(),
-            ep: vec![CPUExecutionProvider::default().build()],
-        }
+        CaptchaEnvironment::new(ModelLoader::DefaultModelLoader.get_model_loader(), None)
     }
 }
 
 impl CaptchaEnvironment {
-    pub fn with_model_loader(model_loader: ModelLoader) -> Self {
+    /// `ep` 为None时只使用CPU
+    fn new(model_loader: Box<dyn ModelLoaderTrait>, ep: Option<Vec<ExecutionProviderDispatch>>) -> Self {
         CaptchaEnvironment {
-            model_loader: model_loader.get_model_loader(),
+            model_loader: Arc::from(model_loader),
             models: Default::default(),
-            ep: vec![CPUExecutionProvider::default().build()],
+            versions: Default::default(),
+            metadata: Default::default(),
+            loading: Mutex::new(()),
+            cpu_only: ep.is_none(),
+            ep: ep.unwrap_or_else(|| vec![CPUExecutionProvider::default().build()]),
+            precision: Precision::Fp32,
+            model_precision: Default::default(),
+            loaded_precision: Default::default(),
+            session_options: Default::default(),
+            capture: None,
+            drift: None,
+            report: None,
+            governor: None,
+            hooks: Hooks::default(),
+            confidence_threshold: None,
+            nms_threshold: None,
+            runtime_path: None,
         }
     }
 
+    /// 分层配置的构建器，见 [`CaptchaEnvironmentBuilder`]
+    pub fn builder() -> CaptchaEnvironmentBuilder {
+        CaptchaEnvironmentBuilder::default()
+    }
+
+    pub fn with_model_loader(model_loader: ModelLoader) -> Self {
+        CaptchaEnvironment::new(model_loader.get_model_loader(), None)
+    }
+
     pub fn with_ep(ep: Vec<ExecutionProviderDispatch>) -> Self {
-        CaptchaEnvironment {
-            model_loader: ModelLoader::DefaultModelLoader.get_model_loader(),
-            models: Default::default(),
-            ep,
-        }
+        CaptchaEnvironment::new(ModelLoader::DefaultModelLoader.get_model_loader(), Some(ep))
     }
 
     pub fn with_model_loader_and_ep(model_loader: Box<dyn ModelLoaderTrait>, ep: Vec<ExecutionProviderDispatch>) -> Self {
-        CaptchaEnvironment {
-            model_loader,
-            models: Default::default(),
-            ep,
+        CaptchaEnvironment::new(model_loader, Some(ep))
+    }
+
+    /// 使用编号为device_id的GPU，provider为 `cuda`、`tensorrt` 或 `directml`，
+    /// 多块GPU时为每块创建一个环境，再用 [`DevicePool`](crate::scheduler::DevicePool) 轮流使用
+    pub fn on_device(provider: &str, device_id: i32) -> Result<Self, Box<dyn Error>> {
+        let options = SessionOptions {
+            device_id: Some(device_id),
+            ..Default::default()
+        };
+        let ep = execution_provider(provider, &options)?;
+        Ok(CaptchaEnvironment::new(ModelLoader::DefaultModelLoader.get_model_loader(), Some(vec![ep])).with_session_options(options))
+    }
+
+    /// 使用的GPU编号，没有指定时为None
+    pub fn device_id(&self) -> Option<i32> {
+        self.session_options.device_id
+    }
+
+    /// 设置所有模型优先使用的精度，对应的模型文件不可用或无法在当前执行器上创建会话时回退到fp32
+    pub fn with_precision(mut self, precision: Precision) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    /// 单独设置某个模型的精度，优先于[`with_precision`](Self::with_precision)
+    pub fn with_model_precision(mut self, model: Model, precision: Precision) -> Self {
+        self.model_precision.insert(model, precision);
+        self
+    }
+
+    /// 设置ONNX Runtime的线程数、图优化级别、内存规划等会话选项，只影响之后加载的模型
+    pub fn with_session_options(mut self, options: SessionOptions) -> Self {
+        if self.cpu_only {
+            self.ep = vec![options.cpu_execution_provider()];
         }
+        self.session_options = options;
+        self
+    }
+
+    /// 确定性模式：之后加载的模型使用单线程会话，多次运行得到逐位相同的输出
+    ///
+    /// 流程中的排序都是稳定的，带随机性的组件([`SolvePolicy`](crate::policy::SolvePolicy)、
+    /// [`TrajectoryConfig`](crate::trajectory::TrajectoryConfig))使用固定的 `seed`
+    pub fn with_deterministic(self) -> Self {
+        let options = self.session_options.clone().deterministic();
+        self.with_session_options(options)
+    }
+
+    pub fn session_options(&self) -> &SessionOptions {
+        &self.session_options
+    }
+
+    /// 开启数据采集：之后构建的验证码实现按采样率把识别成功的输入图片、图像块、特征与答案保存到 `config.dir`，
+    /// 用于重新训练模型。默认关闭，请确认有权保存这些图片
+    pub fn with_capture(mut self, config: CaptureConfig) -> Self {
+        self.capture = Some(Arc::new(DatasetCapture::new(config)));
+        self
+    }
+
+    pub fn capture(&self) -> Option<&Arc<DatasetCapture>> {
+        self.capture.as_ref()
+    }
+
+    /// 开启漂移检测：之后构建的验证码实现把每次识别的检测框数量、置信度与匹配距离交给monitor，
+    /// 可以在多个环境之间共用同一个monitor
+    pub fn with_drift_monitor(mut self, monitor: Arc<DriftMonitor>) -> Self {
+        self.drift = Some(monitor);
+        self
+    }
+
+    pub fn drift_monitor(&self) -> Option<&Arc<DriftMonitor>> {
+        self.drift.as_ref()
+    }
+
+    /// 设置识别报告的回调：之后构建的验证码实现每次识别(包括失败)都生成一份 [`SolveReport`]，用于审计日志
+    pub fn with_report_hook(mut self, hook: impl Fn(&SolveReport) + Send + Sync + 'static) -> Self {
+        self.report = Some(Arc::new(hook));
+        self
+    }
+
+    pub fn report_hook(&self) -> Option<&ReportHook> {
+        self.report.as_ref()
+    }
+
+    /// 设置处理钩子：之后构建的验证码实现在预处理、检测与匹配之后调用hooks，见 [`Hooks`]
+    pub fn with_hooks(mut self, hooks: Hooks) -> Self {
+        self.hooks = hooks;
+        self
+    }
+
+    pub fn hooks(&self) -> &Hooks {
+        &self.hooks
+    }
+
+    /// 限制从本环境加载的 [`Governed`] 验证码实现的并发数、每秒识别数与排队长度，所有实现共用同一个限制
+    pub fn with_governor(mut self, config: GovernorConfig) -> Self {
+        self.governor = Some(Arc::new(Governor::new(config)));
+        self
+    }
+
+    pub fn governor(&self) -> Option<&Arc<Governor>> {
+        self.governor.as_ref()
+    }
+
+    /// 加载验证码实现并套上环境的限制器，没有设置限制器时不做限制
+    pub fn load_governed_breaker<CB>(&self) -> Result<Governed<CB>, Box<dyn Error>>
+    where
+        CB: CaptchaBreaker,
+    {
+        let governor = self.governor.clone().unwrap_or_else(|| Arc::new(Governor::unlimited()));
+        Ok(Governed::new(CB::build(self)?, governor))
+    }
+
+    /// 已加载模型的名称、精度与元数据中的版本
+    pub fn model_version(&self, model: Model) -> ModelVersion {
+        let metadata = self.model_metadata(model).unwrap_or_default();
+        ModelVersion {
+            model: model.name().to_string(),
+            precision: self.loaded_precision(model).map(|p| p.name().to_string()),
+            version: metadata.version,
+            format_version: metadata.format_version,
+        }
+    }
+
+    /// 注册模型的另一个版本，例如准备灰度发布的新模型
+    ///
+    /// 注册不影响已经构建和之后构建的验证码实现，需要通过 [`variant`](Self::variant) 或
+    /// [`load_canary`](Self::load_canary) 使用；输入输出与模型不兼容时返回错误，同名版本会被替换
+    pub fn register_model_version(
+        &self,
+        model: Model,
+        version: impl Into<String>,
+        backend: Arc<dyn InferenceBackend>,
+    ) -> Result<(), Box<dyn Error>> {
+        let version = version.into();
+        let metadata = EmbeddedMetadata::from_backend(backend.as_ref())?;
+        model
+            .io_spec()
+            .check(backend.as_ref())
+            .and_then(|_| metadata.check_compatible(model, backend.as_ref()))
+            .map_err(|e| format!("{}模型的版本{}不兼容: {}", model.name(), version, e))?;
+        self.versions.write().unwrap().entry(model).or_default().insert(version, backend);
+        Ok(())
+    }
+
+    /// 从dir加载模型的另一个版本并注册，文件名与模型目录中的相同，精度与会话选项与本环境一致
+    pub fn load_model_version(&self, model: Model, version: impl Into<String>, dir: impl Into<PathBuf>) -> Result<(), Box<dyn Error>> {
+        let (backend, _) = self.load_with(&DirModelLoader::new(dir), model)?;
+        self.register_model_version(model, version, backend)
+    }
+
+    /// 模型已注册的版本，按名称排序
+    pub fn model_versions(&self, model: Model) -> Vec<String> {
+        self.versions
+            .read()
+            .unwrap()
+            .get(&model)
+            .map_or_else(Vec::new, |versions| versions.keys().cloned().collect())
+    }
+
+    /// 派生一个把versions中的模型换成已注册版本的环境，其余模型与所有设置与本环境共用
+    ///
+    /// 已加载的模型直接共用，热更新对两个环境都生效；本环境尚未加载的模型会在派生环境中另外加载一份。
+    /// 替换的模型元数据中没有版本时使用注册的版本名，识别报告中因此可以区分版本
+    pub fn variant(&self, versions: &[(Model, &str)]) -> Result<CaptchaEnvironment, Box<dyn Error>> {
+        let mut variant = CaptchaEnvironment {
+            model_loader: self.model_loader.clone(),
+            models: RwLock::new(self.models.read().unwrap().clone()),
+            versions: RwLock::new(self.versions.read().unwrap().clone()),
+            metadata: RwLock::new(self.metadata.read().unwrap().clone()),
+            loading: Mutex::new(()),
+            ep: self.ep.clone(),
+            cpu_only: self.cpu_only,
+            precision: self.precision,
+            model_precision: self.model_precision.clone(),
+            loaded_precision: RwLock::new(self.loaded_precision.read().unwrap().clone()),
+            session_options: self.session_options.clone(),
+            capture: self.capture.clone(),
+            drift: self.drift.clone(),
+            report: self.report.clone(),
+            governor: self.governor.clone(),
+            hooks: self.hooks.clone(),
+            confidence_threshold: self.confidence_threshold,
+            nms_threshold: self.nms_threshold,
+            runtime_path: self.runtime_path.clone(),
+        };
+        for &(model, version) in versions {
+            let backend = self
+                .versions
+                .read()
+                .unwrap()
+                .get(&model)
+                .and_then(|versions| versions.get(version))
+                .cloned()
+                .ok_or_else(|| format!("{}模型没有注册版本{}", model.name(), version))?;
+            variant.insert_backend(model, backend)?;
+            variant.loaded_precision.get_mut().unwrap().remove(&model);
+            if let Some(metadata) = variant.metadata.get_mut().unwrap().get_mut(&model) {
+                metadata.version.get_or_insert_with(|| version.to_string());
+            }
+        }
+        Ok(variant)
+    }
+
+    /// 加载灰度发布的一对验证码实现：对照组使用本环境的模型，实验组使用 [`variant`](Self::variant) 替换后的模型，
+    /// percent(0~100)的识别交给实验组，见 [`Canary`]
+    pub fn load_canary<CB>(&self, versions: &[(Model, &str)], percent: f32) -> Result<Canary<CB>, Box<dyn Error>>
+    where
+        CB: CaptchaBreaker,
+    {
+        let control: CB = self.load_captcha_breaker()?;
+        let candidate: CB = self.variant(versions)?.load_captcha_breaker()?;
+        let label = |version: &dyn Fn(Model, &str) -> String| {
+            versions
+                .iter()
+                .map(|&(model, name)| format!("{}@{}", model.name(), version(model, name)))
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        let control_version = label(&|model, _| self.model_version(model).version.unwrap_or_else(|| "current".to_string()));
+        let candidate_version = label(&|_, name| name.to_string());
+        Canary::new((control_version, control), (candidate_version, candidate), percent)
+    }
+
+    /// 指定ONNX Runtime动态库(或其所在目录)的位置，只在开启 `load-dynamic` 时使用
+    pub fn with_runtime_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.runtime_path = Some(path.into());
+        self
+    }
+
+    /// 编译时选择的ONNX Runtime链接方式，只有 [`Linking::LoadDynamic`] 会使用 [`with_runtime_path`](Self::with_runtime_path)
+    pub fn linking(&self) -> Linking {
+        Linking::current()
+    }
+
+    /// 检查ONNX Runtime是否可用，返回加载的动态库路径，见 [`runtime::probe`](crate::runtime::probe)
+    ///
+    /// 从模型文件加载模型之前会自动检查，启动时调用可以尽早发现部署问题
+    pub fn probe_runtime(&self) -> Result<Option<PathBuf>, CaptchaError> {
+        runtime::probe(self.runtime_path.as_deref())
+    }
+
+    /// 配置中覆盖的检测置信度阈值，验证码实现构建时使用
+    pub fn confidence_threshold(&self) -> Option<f32> {
+        self.confidence_threshold
+    }
+
+    /// 配置中覆盖的NMS阈值
+    pub fn nms_threshold(&self) -> Option<f32> {
+        self.nms_threshold
+    }
+
+    /// 模型请求的精度
+    pub fn precision(&self, model: Model) -> Precision {
+        self.model_precision.get(&model).copied().unwrap_or(self.precision)
+    }
+
+    /// 模型实际加载的精度，模型未从模型文件加载时返回None
+    pub fn loaded_precision(&self, model: Model) -> Option<Precision> {
+        self.loaded_precision.read().unwrap().get(&model).copied()
     }
 
     pub fn load_captcha_breaker<CB>(&self) -> Result<CB, Box<dyn Error>>
@@ -56,29 +367,374 @@ impl CaptchaEnvironment {
         CB::build(self)
     }
 
+    /// 加载并预热，适合对首次识别延迟敏感的服务
+    pub fn load_captcha_breaker_warm<CB>(&self) -> Result<CB, Box<dyn Error>>
+    where
+        CB: CaptchaBreaker,
+    {
+        let breaker = CB::build(self)?;
+        breaker.warmup()?;
+        Ok(breaker)
+    }
+
+    /// 获取已加载模型中嵌入的元数据，模型未加载时返回None
+    pub fn model_metadata(&self, model: Model) -> Option<EmbeddedMetadata> {
+        self.metadata.read().unwrap().get(&model).cloned()
+    }
+
     pub(crate) fn load_models(
         &self,
         models: Vec<Model>,
-    ) -> Result<Vec<Arc<Session>>, Box<dyn Error>> {
+    ) -> Result<Vec<Arc<dyn InferenceBackend>>, Box<dyn Error>> {
         let mut res = vec![];
         for model in models {
-            res.push(self.load_one_model(model)?);
+            let backend = self.load_backend(model)?;
+            let metadata = self.model_metadata(model).unwrap_or_default();
+            model
+                .io_spec()
+                .check(backend.as_ref())
+                .and_then(|_| metadata.check_compatible(model, backend.as_ref()))
+                .map_err(|e| format!("{}模型不兼容: {}", model.name(), e))?;
+            res.push(backend);
         }
         Ok(res)
     }
 
-    fn load_one_model(&self, model: Model) -> Result<Arc<Session>, Box<dyn Error>> {
+    /// 使用自定义的推理后端代替从模型文件加载，之后构建的验证码实现都会使用该后端
+    pub fn insert_backend(&self, model: Model, backend: Arc<dyn InferenceBackend>) -> Result<(), Box<dyn Error>> {
+        let metadata = EmbeddedMetadata::from_backend(backend.as_ref())?;
+        self.metadata.write().unwrap().insert(model, metadata);
+        self.models
+            .write()
+            .unwrap()
+            .insert(model, Arc::new(ReloadableBackend::new(backend)));
+        Ok(())
+    }
+
+    /// 取出模型对应的推理后端，尚未加载时从模型文件加载
+    pub fn load_backend(&self, model: Model) -> Result<Arc<dyn InferenceBackend>, Box<dyn Error>> {
         // 检查模型是否已加载
-        if let Some(session) = self.models.borrow().get(&model) {
-            return Ok(Arc::clone(session));
+        if let Some(backend) = self.loaded_backend(model) {
+            return Ok(backend);
+        }
+        let _loading = self.loading.lock().unwrap();
+        // 等待期间其他线程可能已经加载了该模型
+        if let Some(backend) = self.loaded_backend(model) {
+            return Ok(backend);
         }
+        let (backend, precision) = self.load_with(self.model_loader.as_ref(), model)?;
+        self.insert_backend(model, backend)?;
+        self.loaded_precision.write().unwrap().insert(model, precision);
+        Ok(self.loaded_backend(model).ok_or("模型加载后被移除")?)
+    }
 
-        let session = self.model_loader.load_with_execution_providers(model, self.ep.clone())?;
-        let session_rc = Arc::new(session);
+    fn loaded_backend(&self, model: Model) -> Option<Arc<dyn InferenceBackend>> {
         self.models
-            .borrow_mut()
-            .insert(model, Arc::clone(&session_rc));
+            .read()
+            .unwrap()
+            .get(&model)
+            .map(|backend| Arc::clone(backend) as Arc<dyn InferenceBackend>)
+    }
+
+    /// 加载模型并返回输入输出的名称、形状、类型以及opset与元数据，用于排查自定义模型的形状不匹配
+    ///
+    /// opset从模型文件中读取，使用 [`insert_backend`](Self::insert_backend) 注册的后端或加载器不提供模型文件时
+    /// `header` 为None
+    pub fn describe_model(&self, model: Model) -> Result<ModelDescription, Box<dyn Error>> {
+        let backend = self.load_backend(model)?;
+        let precision = self.loaded_precision(model);
+        let header = match precision {
+            Some(precision) => self.model_loader.model_bytes(model, precision)?.map(|bytes| OnnxHeader::parse(&bytes)).transpose()?,
+            None => None,
+        };
+        ModelDescription::new(Some(model), backend.as_ref(), precision, header)
+    }
+
+    /// 重新从模型文件加载，已构建的验证码实现会在下一次推理时使用新模型
+    ///
+    /// 新模型的输入输出必须与当前模型一致，并且与首次加载时一样通过 [`Model::io_spec`] 与元数据的兼容性检查，
+    /// 否则返回错误且继续使用旧模型。
+    /// 更新模型文件时应先写入临时文件再重命名，避免读到写了一半的文件
+    pub fn reload_model(&self, model: Model) -> Result<(), Box<dyn Error>> {
+        let current = self.models.read().unwrap().get(&model).cloned();
+        let Some(current) = current else {
+            return self.load_backend(model).map(|_| ());
+        };
+        let (backend, precision) = self.load_with(self.model_loader.as_ref(), model)?;
+        let metadata = EmbeddedMetadata::from_backend(backend.as_ref())?;
+        model
+            .io_spec()
+            .check(backend.as_ref())
+            .and_then(|_| metadata.check_compatible(model, backend.as_ref()))
+            .map_err(|e| format!("{}模型不兼容: {}", model.name(), e))?;
+        current.swap(backend)?;
+        self.metadata.write().unwrap().insert(model, metadata);
+        self.loaded_precision.write().unwrap().insert(model, precision);
+        Ok(())
+    }
+
+    /// 重新加载watcher发现文件有变化且已经加载过的模型，返回重新加载的模型
+    ///
+    /// 一个模型重新加载失败时继续处理其他模型，失败的模型不会记录新的修改时间，下次调用时重试；
+    /// 有模型失败时返回列出全部失败原因的错误，其余模型已经重新加载
+    pub fn reload_changed(&self, watcher: &mut ModelWatcher) -> Result<Vec<Model>, Box<dyn Error>> {
+        let mut reloaded = vec![];
+        let mut errors = vec![];
+        for (model, modified) in watcher.changed() {
+            if !self.models.read().unwrap().contains_key(&model) {
+                watcher.commit(model, modified);
+                continue;
+            }
+            match self.reload_model(model) {
+                Ok(()) => {
+                    watcher.commit(model, modified);
+                    reloaded.push(model);
+                }
+                Err(e) => {
+                    log::warn!("重新加载{}模型失败：{}", model.name(), e);
+                    errors.push(format!("{}: {}", model.name(), e));
+                }
+            }
+        }
+        if !errors.is_empty() {
+            let names: Vec<&str> = reloaded.iter().map(|model| model.name()).collect();
+            return Err(format!("重新加载失败 {}；已重新加载 [{}]", errors.join("；"), names.join(", ")).into());
+        }
+        Ok(reloaded)
+    }
+
+    /// 按精度的回退顺序用loader加载模型，返回后端与实际加载的精度
+    fn load_with(&self, loader: &dyn ModelLoaderTrait, model: Model) -> Result<(Arc<dyn InferenceBackend>, Precision), Box<dyn Error>> {
+        self.probe_runtime()?;
+        let candidates: Vec<Precision> = self
+            .precision(model)
+            .fallbacks()
+            .into_iter()
+            .filter(|precision| !self.cpu_only || precision.suits_cpu())
+            .collect();
+        let mut last_error = None;
+        for precision in candidates {
+            let result = loader.load_with_session_options(model, precision, self.ep.clone(), &self.session_options);
+            match result {
+                Ok(session) => return Ok((Arc::new(OrtBackend::new(session)), precision)),
+                Err(e) => {
+                    log::warn!("加载{:?}的{:?}模型失败：{}", model, precision, e);
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(|| "没有可用的模型精度".into()))
+    }
+}
+
+/// 按 代码 → 配置文件 → 环境变量 的顺序合并 [`EnvironmentConfig`] 后创建 [`CaptchaEnvironment`]，
+/// 后面的层覆盖前面的层，运维可以不重新编译就调整模型目录、执行器、线程数与阈值
+///
+/// 配置文件由 [`config_file`](Self::config_file) 或环境变量 `CAPTCHA_BREAKER_CONFIG` 指定，需要开启 `config-file` feature
+#[derive(Default)]
+pub struct CaptchaEnvironmentBuilder {
+    config: EnvironmentConfig,
+    config_file: Option<PathBuf>,
+    ignore_env: bool,
+    model_loader: Option<Box<dyn ModelLoaderTrait>>,
+    session_options: SessionOptions,
+    capture: Option<CaptureConfig>,
+    drift: Option<Arc<DriftMonitor>>,
+    report: Option<ReportHook>,
+    governor: Option<GovernorConfig>,
+    hooks: Hooks,
+}
+
+impl CaptchaEnvironmentBuilder {
+    /// 代码中的配置，作为最底层
+    pub fn with_config(mut self, config: EnvironmentConfig) -> Self {
+        self.config.merge(config);
+        self
+    }
+
+    pub fn model_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.config.model_dir = Some(dir.into());
+        self
+    }
+
+    /// 按优先级排列的执行器名称，见 [`execution_provider`]
+    pub fn execution_providers<S: Into<String>>(mut self, names: impl IntoIterator<Item = S>) -> Self {
+        self.config.execution_providers = Some(names.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// 见 [`SessionOptions::device_id`]，也可以在执行器名称中写 `cuda:1`
+    pub fn device_id(mut self, device_id: i32) -> Self {
+        self.config.device_id = Some(device_id);
+        self
+    }
+
+    pub fn intra_threads(mut self, threads: usize) -> Self {
+        self.config.intra_threads = Some(threads);
+        self
+    }
+
+    pub fn inter_threads(mut self, threads: usize) -> Self {
+        self.config.inter_threads = Some(threads);
+        self
+    }
 
-        Ok(session_rc)
+    pub fn precision(mut self, precision: Precision) -> Self {
+        self.config.precision = Some(precision);
+        self
     }
+
+    pub fn confidence_threshold(mut self, threshold: f32) -> Self {
+        self.config.confidence_threshold = Some(threshold);
+        self
+    }
+
+    pub fn nms_threshold(mut self, threshold: f32) -> Self {
+        self.config.nms_threshold = Some(threshold);
+        self
+    }
+
+    /// 见 [`CaptchaEnvironment::with_runtime_path`]
+    pub fn runtime_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.runtime_path = Some(path.into());
+        self
+    }
+
+    /// 读取的TOML配置文件，环境变量 `CAPTCHA_BREAKER_CONFIG` 优先
+    pub fn config_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config_file = Some(path.into());
+        self
+    }
+
+    /// 不读取环境变量，适合测试
+    pub fn ignore_env(mut self) -> Self {
+        self.ignore_env = true;
+        self
+    }
+
+    /// 自定义加载器，设置后忽略 `model_dir`
+    pub fn model_loader(mut self, model_loader: ModelLoader) -> Self {
+        self.model_loader = Some(model_loader.get_model_loader());
+        self
+    }
+
+    /// 其余的会话选项，线程数会被配置中的值覆盖
+    pub fn session_options(mut self, options: SessionOptions) -> Self {
+        self.session_options = options;
+        self
+    }
+
+    pub fn capture(mut self, config: CaptureConfig) -> Self {
+        self.capture = Some(config);
+        self
+    }
+
+    /// 见 [`CaptchaEnvironment::with_drift_monitor`]
+    pub fn drift_monitor(mut self, monitor: Arc<DriftMonitor>) -> Self {
+        self.drift = Some(monitor);
+        self
+    }
+
+    /// 见 [`CaptchaEnvironment::with_governor`]
+    pub fn governor(mut self, config: GovernorConfig) -> Self {
+        self.governor = Some(config);
+        self
+    }
+
+    /// 见 [`CaptchaEnvironment::with_hooks`]
+    pub fn hooks(mut self, hooks: Hooks) -> Self {
+        self.hooks = hooks;
+        self
+    }
+
+    /// 见 [`CaptchaEnvironment::with_report_hook`]
+    pub fn report_hook(mut self, hook: impl Fn(&SolveReport) + Send + Sync + 'static) -> Self {
+        self.report = Some(Arc::new(hook));
+        self
+    }
+
+    /// 合并各层后的配置
+    pub fn resolve(&self) -> Result<EnvironmentConfig, Box<dyn Error>> {
+        let vars: Vec<(String, String)> = if self.ignore_env { vec![] } else { std::env::vars().collect() };
+        self.resolve_with(vars)
+    }
+
+    /// 与 [`resolve`](Self::resolve) 相同，但用给定的键值对代替进程的环境变量
+    pub fn resolve_with(&self, vars: impl IntoIterator<Item = (String, String)>) -> Result<EnvironmentConfig, Box<dyn Error>> {
+        let vars: Vec<(String, String)> = vars.into_iter().collect();
+        let mut config = self.config.clone();
+        let file = vars
+            .iter()
+            .find(|(key, value)| key == ENV_CONFIG_FILE && !value.trim().is_empty())
+            .map(|(_, value)| PathBuf::from(value.trim()))
+            .or_else(|| self.config_file.clone());
+        if let Some(path) = file {
+            config.merge(load_config_file(path)?);
+        }
+        config.merge(EnvironmentConfig::from_vars(vars)?);
+        Ok(config)
+    }
+
+    pub fn build(self) -> Result<CaptchaEnvironment, Box<dyn Error>> {
+        let config = self.resolve()?;
+        self.build_with(config)
+    }
+
+    /// 使用已经合并好的配置创建环境
+    pub fn build_with(self, config: EnvironmentConfig) -> Result<CaptchaEnvironment, Box<dyn Error>> {
+        for threshold in [config.confidence_threshold, config.nms_threshold].into_iter().flatten() {
+            if !(0.0..=1.0).contains(&threshold) {
+                return Err(format!("阈值{}不在0~1之间", threshold).into());
+            }
+        }
+        let model_loader = match (self.model_loader, &config.model_dir) {
+            (Some(loader), _) => loader,
+            (None, Some(dir)) => Box::new(DirModelLoader::new(dir)),
+            (None, None) => ModelLoader::DefaultModelLoader.get_model_loader(),
+        };
+        let mut options = self.session_options;
+        options.intra_threads = config.intra_threads.or(options.intra_threads);
+        options.inter_threads = config.inter_threads.or(options.inter_threads);
+        options.cpu_arena = config.cpu_arena.unwrap_or(options.cpu_arena);
+        options.cuda_memory_limit = config.cuda_memory_limit.or(options.cuda_memory_limit);
+        options.device_id = config.device_id.or(options.device_id);
+        // 只有cpu时仍按只使用CPU处理，从而跳过fp16并应用cpu_arena
+        let ep = match &config.execution_providers {
+            Some(names) if names.iter().any(|name| !name.eq_ignore_ascii_case("cpu")) => Some(
+                names
+                    .iter()
+                    .map(|name| execution_provider(name, &options))
+                    .collect::<Result<Vec<_>, _>>()?,
+            ),
+            _ => None,
+        };
+        let mut environment = CaptchaEnvironment::new(model_loader, ep).with_session_options(options);
+        environment.precision = config.precision.unwrap_or_default();
+        environment.confidence_threshold = config.confidence_threshold;
+        environment.nms_threshold = config.nms_threshold;
+        if config.runtime_path.is_some() && Linking::current() != Linking::LoadDynamic {
+            log::warn!("以{}方式链接ONNX Runtime，忽略runtime_path", Linking::current().name());
+        }
+        environment.runtime_path = config.runtime_path;
+        if let Some(capture) = self.capture {
+            environment = environment.with_capture(capture);
+        }
+        environment.drift = self.drift;
+        environment.report = self.report;
+        environment.hooks = self.hooks;
+        if let Some(governor) = self.governor {
+            environment = environment.with_governor(governor);
+        }
+        Ok(environment)
+    }
+}
+
+#[cfg(feature = "config-file")]
+fn load_config_file(path: PathBuf) -> Result<EnvironmentConfig, Box<dyn Error>> {
+    EnvironmentConfig::from_file(path)
+}
+
+#[cfg(not(feature = "config-file"))]
+fn load_config_file(path: PathBuf) -> Result<EnvironmentConfig, Box<dyn Error>> {
+    Err(format!("读取配置文件{}需要开启config-file feature", path.display()).into())
 }
@@ -0,0 +1,79 @@
+use std::error::Error;
+use std::fmt;
+use std::path::PathBuf;
+
+/// 验证码识别过程中可能出现的错误
+#[derive(Debug)]
+pub enum CaptchaError {
+    /// 图片数据损坏或格式不受支持
+    InvalidImage(image::ImageError),
+    /// base64 数据无法解码
+    InvalidBase64(base64::DecodeError),
+    /// 音频数据损坏或格式不受支持
+    InvalidAudio(String),
+    /// 没有检测到需要的目标
+    NoDetections,
+    /// 检测到的目标数量超过验证码可能容纳的数量，通常说明图片不是该类验证码
+    TooManyDetections { count: usize, max: usize },
+    /// 成本矩阵第query行第candidate列为NaN或无穷，通常是特征提取输出了NaN
+    InvalidCost { query: usize, candidate: usize },
+    /// 找不到可用的ONNX Runtime动态库，`attempted` 为按顺序尝试过的路径，见 [`runtime::probe`](crate::runtime::probe)
+    RuntimeUnavailable { attempted: Vec<PathBuf>, reason: String },
+    /// 识别请求超过 [`Governor`](crate::governor::Governor) 的限制，稍后重试
+    Overloaded { reason: &'static str },
+}
+
+impl fmt::Display for CaptchaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CaptchaError::InvalidImage(e) => write!(f, "无法解码图片: {}", e),
+            CaptchaError::InvalidBase64(e) => write!(f, "无法解码base64数据: {}", e),
+            CaptchaError::InvalidAudio(e) => write!(f, "无法解码音频: {}", e),
+            CaptchaError::NoDetections => write!(f, "没有检测到目标"),
+            CaptchaError::TooManyDetections { count, max } => {
+                write!(f, "检测到{}个目标，超过上限{}", count, max)
+            }
+            CaptchaError::InvalidCost { query, candidate } => {
+                write!(f, "成本矩阵({}, {})不是有限值", query, candidate)
+            }
+            CaptchaError::RuntimeUnavailable { attempted, reason } => {
+                let attempted: Vec<String> = attempted.iter().map(|p| p.display().to_string()).collect();
+                write!(
+                    f,
+                    "无法加载ONNX Runtime动态库({})，已尝试: {}。请安装onnxruntime，或通过 CaptchaEnvironment::with_runtime_path、\
+                     环境变量CAPTCHA_BREAKER_RUNTIME_PATH或ORT_DYLIB_PATH指定libonnxruntime的位置",
+                    reason,
+                    attempted.join(", ")
+                )
+            }
+            CaptchaError::Overloaded { reason } => write!(f, "识别请求过多：{}", reason),
+        }
+    }
+}
+
+impl Error for CaptchaError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CaptchaError::InvalidImage(e) => Some(e),
+            CaptchaError::InvalidBase64(e) => Some(e),
+            CaptchaError::InvalidAudio(_)
+            | CaptchaError::NoDetections
+            | CaptchaError::TooManyDetections { .. }
+            | CaptchaError::InvalidCost { .. }
+            | CaptchaError::RuntimeUnavailable { .. }
+            | CaptchaError::Overloaded { .. } => None,
+        }
+    }
+}
+
+impl From<image::ImageError> for CaptchaError {
+    fn from(e: image::ImageError) -> Self {
+        CaptchaError::InvalidImage(e)
+    }
+}
+
+impl From<base64::DecodeError> for CaptchaError {
+    fn from(e: base64::DecodeError) -> Self {
+        CaptchaError::InvalidBase64(e)
+    }
+}
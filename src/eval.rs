@@ -0,0 +1,425 @@
+//! 在标注数据集上评估识别准确率
+//!
+//! [`evaluate`] 在数据集的每张图片上运行验证码实现，按像素容差判断每次点选是否正确，
+//! 把答错的样本归因到检测、匹配或点击位置中最早出错的阶段，并生成可以保存为JSON的 [`EvalReport`]，
+//! 用于在部署前比较不同版本的模型
+//!
+//! 数据集目录中每个样本为同名的图片与JSON，例如 `0001.png` 与 `0001.json`：
+//!
+//! ```json
+//! {"points": [[120.5, 88.0], [40.0, 200.0]], "answer_boxes": [[100, 70, 140, 106], [20, 180, 60, 220]]}
+//! ```
+//!
+//! `points` 为按点选顺序的正确坐标，`answer_boxes` 可选，为对应的答案框 `[x_min, y_min, x_max, y_max]`。
+//! [`DatasetCapture`](crate::capture::DatasetCapture) 采集的样本目录(`image.png` 与 `answer.json`)人工校对后也可以直接使用
+
+use crate::error::CaptchaError;
+use crate::geometry::Rect;
+use image::DynamicImage;
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+const IMAGE_EXTENSIONS: [&str; 5] = ["png", "jpg", "jpeg", "webp", "bmp"];
+
+/// 评估的参数
+#[derive(Debug, Clone, PartialEq)]
+pub struct EvalConfig {
+    /// 点选坐标与正确坐标的距离不超过该值(像素)时视为正确
+    pub tolerance: f32,
+    /// 标注了答案框时，检测框与之交并比不低于该值视为检测到
+    pub box_iou: f32,
+}
+
+impl Default for EvalConfig {
+    fn default() -> Self {
+        EvalConfig {
+            tolerance: 10.0,
+            box_iou: 0.5,
+        }
+    }
+}
+
+/// 一个样本的标注
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GroundTruth {
+    /// 按点选顺序的正确坐标
+    pub points: Vec<(f32, f32)>,
+    /// 与 `points` 对应的答案框，没有标注时为空
+    pub answer_boxes: Vec<Rect<f32>>,
+}
+
+impl GroundTruth {
+    /// 解析标注JSON，同时支持数据采集保存的 `answer.json`
+    pub fn from_json(text: &str) -> Result<Self, Box<dyn Error>> {
+        let value: Value = serde_json::from_str(text)?;
+        if let Some(points) = value.get("points") {
+            let points = parse_points(points)?;
+            let answer_boxes = match value.get("answer_boxes") {
+                Some(boxes) => parse_rects(boxes)?,
+                None => vec![],
+            };
+            if !answer_boxes.is_empty() && answer_boxes.len() != points.len() {
+                return Err("answer_boxes与points的数量不一致".into());
+            }
+            return Ok(GroundTruth { points, answer_boxes });
+        }
+        // 数据采集的格式：answers中为每个问题的坐标与答案框下标
+        let answers = value.get("answers").and_then(Value::as_array).ok_or("标注中没有points")?;
+        let boxes = match value.get("answer_boxes") {
+            Some(boxes) => parse_rects(boxes)?,
+            None => vec![],
+        };
+        let mut truth = GroundTruth::default();
+        for answer in answers {
+            truth.points.push(parse_point(answer.get("point").ok_or("answers中缺少point")?)?);
+            if let Some(&rect) = answer.get("answer").and_then(Value::as_u64).and_then(|i| boxes.get(i as usize)) {
+                truth.answer_boxes.push(rect);
+            }
+        }
+        if truth.answer_boxes.len() != truth.points.len() {
+            truth.answer_boxes.clear();
+        }
+        Ok(truth)
+    }
+}
+
+fn parse_point(value: &Value) -> Result<(f32, f32), Box<dyn Error>> {
+    match value.as_array().map(Vec::as_slice) {
+        Some([x, y]) => match (x.as_f64(), y.as_f64()) {
+            (Some(x), Some(y)) => Ok((x as f32, y as f32)),
+            _ => Err("坐标不是数字".into()),
+        },
+        _ => Err("坐标需要是 [x, y]".into()),
+    }
+}
+
+fn parse_points(value: &Value) -> Result<Vec<(f32, f32)>, Box<dyn Error>> {
+    value.as_array().ok_or("points需要是数组")?.iter().map(parse_point).collect()
+}
+
+fn parse_rects(value: &Value) -> Result<Vec<Rect<f32>>, Box<dyn Error>> {
+    let rects = value.as_array().ok_or("框需要是数组")?;
+    rects
+        .iter()
+        .map(|rect| {
+            let values: Option<Vec<f32>> = rect.as_array().map(|v| v.iter().filter_map(|c| c.as_f64().map(|c| c as f32)).collect());
+            match values.as_deref() {
+                Some(&[x_min, y_min, x_max, y_max]) => Ok(Rect::new(x_min, y_min, x_max, y_max)),
+                _ => Err("框需要是 [x_min, y_min, x_max, y_max]".into()),
+            }
+        })
+        .collect()
+}
+
+/// 数据集中的一个样本
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sample {
+    /// 相对数据集目录的路径，不含扩展名
+    pub name: String,
+    pub image: PathBuf,
+    pub truth: GroundTruth,
+}
+
+/// 递归读取数据集目录中的样本，按名称排序；没有对应图片的JSON被忽略
+pub fn load_dataset(dir: impl AsRef<Path>) -> Result<Vec<Sample>, Box<dyn Error>> {
+    let dir = dir.as_ref();
+    let mut samples = vec![];
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        for entry in fs::read_dir(&current)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                pending.push(path);
+                continue;
+            }
+            if path.extension().is_none_or(|extension| extension != "json") {
+                continue;
+            }
+            let stem = path.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+            let (image, name) = if stem == "answer" && current.join("image.png").is_file() {
+                (current.join("image.png"), current.clone())
+            } else {
+                let Some(image) = IMAGE_EXTENSIONS.iter().map(|e| path.with_extension(e)).find(|p| p.is_file()) else {
+                    continue;
+                };
+                (image, path.with_extension(""))
+            };
+            let truth =
+                GroundTruth::from_json(&fs::read_to_string(&path)?).map_err(|e| format!("{}: {}", path.display(), e))?;
+            let name = name.strip_prefix(dir).unwrap_or(&name).to_string_lossy().replace('\\', "/");
+            samples.push(Sample { name, image, truth });
+        }
+    }
+    samples.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(samples)
+}
+
+/// 一次识别的结果与用于归因的中间结果，坐标均为原图像素坐标
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Prediction {
+    /// 按点选顺序的坐标
+    pub points: Vec<(f32, f32)>,
+    /// 检测到的所有答案框，不做检测的实现为空
+    pub detected: Vec<Rect<f32>>,
+    /// 与 `points` 对应的答案框，不做检测的实现为空
+    pub selected: Vec<Rect<f32>>,
+}
+
+/// 可以在数据集上评估的验证码实现
+pub trait Evaluate {
+    fn predict(&self, image: &DynamicImage) -> Result<Prediction, Box<dyn Error>>;
+}
+
+#[cfg(feature = "chinese_click_0")]
+impl From<&crate::captcha::PipelineTrace> for Prediction {
+    fn from(trace: &crate::captcha::PipelineTrace) -> Self {
+        Prediction {
+            points: trace.points.clone(),
+            detected: trace.ans_boxes.iter().map(|b| b.rect).collect(),
+            selected: trace.answers.iter().map(|m| trace.ans_boxes[m.answer_index].rect).collect(),
+        }
+    }
+}
+
+#[cfg(feature = "chinese_click_0")]
+impl Evaluate for crate::captcha::ChineseClick0 {
+    fn predict(&self, image: &DynamicImage) -> Result<Prediction, Box<dyn Error>> {
+        Ok(Prediction::from(&self.run_detailed(image)?))
+    }
+}
+
+impl<F: Fn(&DynamicImage) -> Result<Prediction, Box<dyn Error>>> Evaluate for F {
+    fn predict(&self, image: &DynamicImage) -> Result<Prediction, Box<dyn Error>> {
+        self(image)
+    }
+}
+
+/// 答错的样本最早出错的阶段
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Failure {
+    /// 图片无法读取
+    Load,
+    /// 漏检了答案或数量不对，包括没有检测到目标的错误
+    Detect,
+    /// 检测到了正确的答案框，但与问题的对应关系错误
+    Match,
+    /// 选中了正确的答案框，但点击位置超出容差
+    Click,
+    /// 其他识别错误
+    Error,
+}
+
+impl Failure {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Failure::Load => "load",
+            Failure::Detect => "detect",
+            Failure::Match => "match",
+            Failure::Click => "click",
+            Failure::Error => "error",
+        }
+    }
+
+    /// 识别错误对应的阶段
+    fn of_error(error: &(dyn Error + 'static)) -> Failure {
+        match error.downcast_ref::<CaptchaError>() {
+            Some(CaptchaError::NoDetections | CaptchaError::TooManyDetections { .. }) => Failure::Detect,
+            Some(CaptchaError::InvalidCost { .. }) => Failure::Match,
+            Some(CaptchaError::InvalidImage(_)) => Failure::Load,
+            _ => Failure::Error,
+        }
+    }
+}
+
+/// 一个样本的评估结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct SampleResult {
+    pub name: String,
+    pub expected: Vec<(f32, f32)>,
+    /// 识别失败时为空
+    pub predicted: Vec<(f32, f32)>,
+    /// 落在容差内的点选数
+    pub correct_clicks: usize,
+    /// 全部正确时为None
+    pub failure: Option<Failure>,
+    pub error: Option<String>,
+    pub elapsed_ms: f64,
+}
+
+impl SampleResult {
+    pub fn is_correct(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+/// 整个数据集的评估结果
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EvalReport {
+    pub tolerance: f32,
+    pub samples: Vec<SampleResult>,
+}
+
+impl EvalReport {
+    pub fn total(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// 全部点选正确的样本数
+    pub fn solved(&self) -> usize {
+        self.samples.iter().filter(|s| s.is_correct()).count()
+    }
+
+    /// 全部点选正确的样本比例
+    pub fn accuracy(&self) -> f32 {
+        if self.samples.is_empty() { 0.0 } else { self.solved() as f32 / self.total() as f32 }
+    }
+
+    /// 正确的点选占所有应点选次数的比例
+    pub fn click_accuracy(&self) -> f32 {
+        let expected: usize = self.samples.iter().map(|s| s.expected.len()).sum();
+        let correct: usize = self.samples.iter().map(|s| s.correct_clicks).sum();
+        if expected == 0 { 0.0 } else { correct as f32 / expected as f32 }
+    }
+
+    /// 各阶段答错的样本数
+    pub fn failures(&self) -> BTreeMap<Failure, usize> {
+        let mut failures = BTreeMap::new();
+        for failure in self.samples.iter().filter_map(|s| s.failure) {
+            *failures.entry(failure).or_insert(0) += 1;
+        }
+        failures
+    }
+
+    pub fn to_json(&self) -> Value {
+        let failures: serde_json::Map<String, Value> =
+            self.failures().into_iter().map(|(failure, count)| (failure.name().to_string(), json!(count))).collect();
+        let mean_ms = self.samples.iter().map(|s| s.elapsed_ms).sum::<f64>() / self.samples.len().max(1) as f64;
+        let samples: Vec<Value> = self
+            .samples
+            .iter()
+            .map(|s| {
+                json!({
+                    "name": s.name,
+                    "expected": s.expected,
+                    "predicted": s.predicted,
+                    "correct_clicks": s.correct_clicks,
+                    "failure": s.failure.map(|f| f.name()),
+                    "error": s.error,
+                    "elapsed_ms": s.elapsed_ms,
+                })
+            })
+            .collect();
+        json!({
+            "total": self.total(),
+            "solved": self.solved(),
+            "accuracy": self.accuracy(),
+            "click_accuracy": self.click_accuracy(),
+            "tolerance": self.tolerance,
+            "mean_elapsed_ms": mean_ms,
+            "failures": failures,
+            "samples": samples,
+        })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+        fs::write(path, serde_json::to_string_pretty(&self.to_json())?)?;
+        Ok(())
+    }
+}
+
+/// 用默认参数评估数据集目录中的所有样本
+pub fn evaluate(breaker: &impl Evaluate, dataset_dir: impl AsRef<Path>) -> Result<EvalReport, Box<dyn Error>> {
+    evaluate_with(breaker, &load_dataset(dataset_dir)?, &EvalConfig::default())
+}
+
+/// 评估给定的样本，单个样本识别失败不会中断评估
+pub fn evaluate_with(breaker: &impl Evaluate, samples: &[Sample], config: &EvalConfig) -> Result<EvalReport, Box<dyn Error>> {
+    let samples = samples
+        .iter()
+        .map(|sample| {
+            let start = Instant::now();
+            let prediction = image::open(&sample.image)
+                .map_err(|e| Box::new(CaptchaError::InvalidImage(e)) as Box<dyn Error>)
+                .and_then(|image| breaker.predict(&image));
+            let elapsed_ms = start.elapsed().as_secs_f64() * 1000.0;
+            score(sample, prediction, elapsed_ms, config)
+        })
+        .collect();
+    Ok(EvalReport {
+        tolerance: config.tolerance,
+        samples,
+    })
+}
+
+/// 与标注比较，答错时归因到最早出错的阶段
+pub fn score(
+    sample: &Sample,
+    prediction: Result<Prediction, Box<dyn Error>>,
+    elapsed_ms: f64,
+    config: &EvalConfig,
+) -> SampleResult {
+    let expected = sample.truth.points.clone();
+    let prediction = match prediction {
+        Ok(prediction) => prediction,
+        Err(e) => {
+            return SampleResult {
+                name: sample.name.clone(),
+                expected,
+                predicted: vec![],
+                correct_clicks: 0,
+                failure: Some(Failure::of_error(e.as_ref())),
+                error: Some(e.to_string()),
+                elapsed_ms,
+            };
+        }
+    };
+    let near = |(x, y): (f32, f32), (tx, ty): (f32, f32)| (x - tx).hypot(y - ty) <= config.tolerance;
+    let correct: Vec<bool> = expected
+        .iter()
+        .enumerate()
+        .map(|(i, &truth)| prediction.points.get(i).is_some_and(|&point| near(point, truth)))
+        .collect();
+    let correct_clicks = correct.iter().filter(|&&c| c).count();
+    let failure = if prediction.points.len() == expected.len() && correct_clicks == expected.len() {
+        None
+    } else {
+        Some(attribute(&sample.truth, &prediction, &correct, config))
+    };
+    SampleResult {
+        name: sample.name.clone(),
+        expected,
+        predicted: prediction.points,
+        correct_clicks,
+        failure,
+        error: None,
+        elapsed_ms,
+    }
+}
+
+/// 按检测、匹配、点击的顺序找到最早出错的阶段
+fn attribute(truth: &GroundTruth, prediction: &Prediction, correct: &[bool], config: &EvalConfig) -> Failure {
+    if prediction.points.len() != truth.points.len() {
+        return Failure::Detect;
+    }
+    // 标注了答案框时按交并比判断，否则按框是否包含正确坐标判断
+    let is_truth = |i: usize, rect: &Rect<f32>| match truth.answer_boxes.get(i) {
+        Some(answer) => rect.iou(answer) >= config.box_iou,
+        None => rect.contains(truth.points[i]),
+    };
+    let wrong: Vec<usize> = (0..correct.len()).filter(|&i| !correct[i]).collect();
+    if prediction.detected.is_empty() || prediction.selected.len() != prediction.points.len() {
+        // 没有中间结果时无法区分检测与匹配
+        return Failure::Match;
+    }
+    if wrong.iter().any(|&i| !prediction.detected.iter().any(|rect| is_truth(i, rect))) {
+        return Failure::Detect;
+    }
+    if wrong.iter().any(|&i| !is_truth(i, &prediction.selected[i])) {
+        return Failure::Match;
+    }
+    Failure::Click
+}
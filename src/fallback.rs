@@ -0,0 +1,249 @@
+//! 远程兜底识别
+//!
+//! 本地识别的置信度低于阈值(或识别失败)时，把验证码交给 [`FallbackSolver`]，例如人工打码平台或另一个识别服务，
+//! 调用方得到的总是一组坐标，不需要关心答案来自哪里。开启 `fallback-http` feature 后可以使用 [`HttpFallback`]
+//! 把请求转发到任意HTTP接口
+
+use image::DynamicImage;
+use std::error::Error;
+use std::future::Future;
+use std::pin::Pin;
+
+/// 兜底识别返回的future，需要是 `Send` 才能在tokio等多线程运行时中使用
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// 兜底识别的错误，可以跨线程传递
+pub type FallbackError = Box<dyn Error + Send + Sync>;
+
+/// 本地识别的结果：答案的坐标与置信度
+pub type LocalAnswer = Result<(Vec<(f32, f32)>, f32), Box<dyn Error>>;
+
+/// 交给兜底识别的验证码
+#[derive(Debug, Clone, PartialEq)]
+pub struct FallbackRequest {
+    /// 验证码类型，例如 `chinese_click_0`
+    pub breaker: String,
+    /// PNG编码的原图
+    pub image: Vec<u8>,
+    pub image_size: (u32, u32),
+    /// 提示词，不需要时为空
+    pub prompt: String,
+    /// 本地识别的答案，本地识别失败时为空
+    pub local: Vec<(f32, f32)>,
+    /// 本地答案的置信度，本地识别失败时为0
+    pub confidence: f32,
+    /// 本地识别失败的原因
+    pub local_error: Option<String>,
+}
+
+impl FallbackRequest {
+    pub fn new(breaker: impl Into<String>, image: &DynamicImage) -> Result<Self, Box<dyn Error>> {
+        let mut png = std::io::Cursor::new(vec![]);
+        image.write_to(&mut png, image::ImageFormat::Png)?;
+        Ok(FallbackRequest {
+            breaker: breaker.into(),
+            image: png.into_inner(),
+            image_size: (image.width(), image.height()),
+            prompt: String::new(),
+            local: vec![],
+            confidence: 0.0,
+            local_error: None,
+        })
+    }
+}
+
+/// 远程兜底识别，返回需要点选的坐标(原图坐标)
+///
+/// 方法返回装箱的future而不是 `async fn`，从而可以作为 `dyn FallbackSolver` 使用
+pub trait FallbackSolver: Send + Sync {
+    fn solve<'a>(&'a self, request: &'a FallbackRequest) -> BoxFuture<'a, Result<Vec<(f32, f32)>, FallbackError>>;
+}
+
+impl<T: FallbackSolver + ?Sized> FallbackSolver for std::sync::Arc<T> {
+    fn solve<'a>(&'a self, request: &'a FallbackRequest) -> BoxFuture<'a, Result<Vec<(f32, f32)>, FallbackError>> {
+        (**self).solve(request)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FallbackConfig {
+    /// 本地答案的置信度低于该值时使用兜底识别
+    pub min_confidence: f32,
+    /// 本地识别失败时是否使用兜底识别
+    pub on_error: bool,
+    /// 兜底识别失败时是否返回置信度不足的本地答案，否则返回兜底识别的错误
+    pub keep_local: bool,
+}
+
+impl Default for FallbackConfig {
+    fn default() -> Self {
+        FallbackConfig {
+            min_confidence: 0.6,
+            on_error: true,
+            keep_local: true,
+        }
+    }
+}
+
+/// 答案的来源
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum AnswerSource {
+    Local,
+    Fallback,
+}
+
+/// 带来源的答案
+#[derive(Debug, Clone, PartialEq)]
+pub struct FallbackAnswer {
+    pub points: Vec<(f32, f32)>,
+    pub source: AnswerSource,
+    /// 本地答案的置信度，本地识别失败时为0
+    pub confidence: f32,
+}
+
+/// 按config决定是否使用兜底识别
+///
+/// `local` 为本地识别的答案与置信度，`request` 只在需要兜底识别时调用。本地部分同步执行，
+/// 返回的future只包含兜底识别，可以交给多线程运行时；本地的错误转换为字符串后返回
+pub fn resolve<'a>(
+    local: LocalAnswer,
+    request: impl FnOnce() -> Result<FallbackRequest, Box<dyn Error>>,
+    solver: &'a dyn FallbackSolver,
+    config: &FallbackConfig,
+) -> BoxFuture<'a, Result<FallbackAnswer, FallbackError>> {
+    let (local, confidence, local_error) = match local {
+        Ok((points, confidence)) if confidence >= config.min_confidence => {
+            return Box::pin(std::future::ready(Ok(FallbackAnswer {
+                points,
+                source: AnswerSource::Local,
+                confidence,
+            })));
+        }
+        Ok((points, confidence)) => (points, confidence, None),
+        Err(e) if config.on_error => (vec![], 0.0, Some(e.to_string())),
+        Err(e) => return Box::pin(std::future::ready(Err(e.to_string().into()))),
+    };
+    let mut request = match request() {
+        Ok(request) => request,
+        Err(e) => return Box::pin(std::future::ready(Err(e.to_string().into()))),
+    };
+    request.local = local;
+    request.confidence = confidence;
+    request.local_error = local_error;
+    let keep_local = config.keep_local;
+    Box::pin(async move {
+        match solver.solve(&request).await {
+            Ok(points) => Ok(FallbackAnswer {
+                points,
+                source: AnswerSource::Fallback,
+                confidence,
+            }),
+            Err(e) if keep_local && request.local_error.is_none() => {
+                log::warn!("{}的兜底识别失败，使用本地答案：{}", request.breaker, e);
+                Ok(FallbackAnswer {
+                    points: request.local,
+                    source: AnswerSource::Local,
+                    confidence,
+                })
+            }
+            Err(e) => Err(e),
+        }
+    })
+}
+
+/// 把验证码POST到HTTP接口的兜底识别
+///
+/// 请求体为 `{"breaker", "image"(base64 PNG), "prompt", "local", "confidence", "local_error"}`，
+/// 接口返回 `{"points": [[x, y], ...]}`，与本crate的HTTP服务(见 `server` feature)兼容，
+/// 可以直接指向另一台机器上的 `POST /chinese_click_0`
+#[cfg(feature = "fallback-http")]
+#[derive(Debug, Clone)]
+pub struct HttpFallback {
+    url: String,
+    headers: Vec<(String, String)>,
+    client: reqwest::Client,
+}
+
+#[cfg(feature = "fallback-http")]
+impl HttpFallback {
+    pub fn new(url: impl Into<String>) -> Self {
+        HttpFallback {
+            url: url.into(),
+            headers: vec![],
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// 附加请求头，例如打码平台的 `Authorization`
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// 请求超时，人工打码通常需要数十秒
+    pub fn with_timeout(mut self, timeout: std::time::Duration) -> Result<Self, Box<dyn Error>> {
+        self.client = reqwest::Client::builder().timeout(timeout).build()?;
+        Ok(self)
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    fn body(request: &FallbackRequest) -> String {
+        use base64::Engine;
+        serde_json::json!({
+            "breaker": request.breaker,
+            "image": base64::engine::general_purpose::STANDARD.encode(&request.image),
+            "prompt": request.prompt,
+            "local": request.local,
+            "confidence": request.confidence,
+            "local_error": request.local_error,
+        })
+        .to_string()
+    }
+
+    /// 解析接口返回的 `{"points": [[x, y], ...]}`
+    pub fn parse_points(body: &str) -> Result<Vec<(f32, f32)>, FallbackError> {
+        let value: serde_json::Value = serde_json::from_str(body)?;
+        if let Some(error) = value.get("error").and_then(|e| e.as_str()) {
+            return Err(format!("兜底识别失败：{}", error).into());
+        }
+        let points = value.get("points").and_then(|p| p.as_array()).ok_or("兜底识别的响应中没有points")?;
+        points
+            .iter()
+            .map(|point| match point.as_array().map(Vec::as_slice) {
+                Some([x, y]) => match (x.as_f64(), y.as_f64()) {
+                    (Some(x), Some(y)) => Ok((x as f32, y as f32)),
+                    _ => Err("坐标不是数字".into()),
+                },
+                _ => Err("坐标需要是 [x, y]".into()),
+            })
+            .collect()
+    }
+}
+
+#[cfg(feature = "fallback-http")]
+impl FallbackSolver for HttpFallback {
+    fn solve<'a>(&'a self, request: &'a FallbackRequest) -> BoxFuture<'a, Result<Vec<(f32, f32)>, FallbackError>> {
+        Box::pin(async move {
+            let mut builder = self
+                .client
+                .post(&self.url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(Self::body(request));
+            for (name, value) in &self.headers {
+                builder = builder.header(name.as_str(), value.as_str());
+            }
+            let response = builder.send().await?;
+            let status = response.status();
+            let body = response.text().await?;
+            let points = Self::parse_points(&body);
+            if !status.is_success() {
+                // 错误响应中有error字段时使用它
+                return Err(points.err().unwrap_or_else(|| format!("兜底识别返回{}", status).into()));
+            }
+            points
+        })
+    }
+}
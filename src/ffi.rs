@@ -0,0 +1,191 @@
+//! C ABI，便于在Go/Python/C#等语言中直接嵌入
+//!
+//! 头文件位于 `include/captcha_breaker.h`，可以用 `cbindgen --config cbindgen.toml -o include/captcha_breaker.h` 重新生成。
+//! 所有函数返回 [`CaptchaStatus`]，失败时可以通过 [`captcha_last_error`] 获取当前线程最近一次的错误信息
+
+use crate::environment::CaptchaEnvironment;
+use crate::loader::{DirModelLoader, ModelLoader};
+use std::cell::RefCell;
+use std::error::Error;
+use std::ffi::{CStr, CString, c_char};
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::ptr;
+
+/// 返回状态码
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CaptchaStatus {
+    Ok = 0,
+    /// 传入了空指针
+    NullPointer = 1,
+    /// 参数无效，例如路径不是UTF-8或图片无法解码
+    InvalidArgument = 2,
+    /// 模型加载失败
+    ModelError = 3,
+    /// 识别失败
+    SolveError = 4,
+    /// 输出缓冲区不足，所需长度已写入 `out_len`
+    BufferTooSmall = 5,
+    /// 内部发生panic
+    Panic = 6,
+}
+
+/// 点选坐标
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CaptchaPoint {
+    pub x: f32,
+    pub y: f32,
+}
+
+/// 不透明的环境句柄
+pub struct CaptchaEnv {
+    inner: CaptchaEnvironment,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: impl ToString) {
+    let message = CString::new(message.to_string().replace('\0', "")).unwrap_or_default();
+    LAST_ERROR.with(|e| *e.borrow_mut() = Some(message));
+}
+
+/// 执行f，把错误与panic转换为状态码；先清除上一次的错误，成功时 [`captcha_last_error`] 返回NULL
+fn guard(f: impl FnOnce() -> Result<(), (CaptchaStatus, Box<dyn Error>)>) -> CaptchaStatus {
+    LAST_ERROR.with(|e| e.borrow_mut().take());
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => CaptchaStatus::Ok,
+        Ok(Err((status, e))) => {
+            set_last_error(e);
+            status
+        }
+        Err(_) => {
+            set_last_error("panic");
+            CaptchaStatus::Panic
+        }
+    }
+}
+
+fn null_pointer() -> (CaptchaStatus, Box<dyn Error>) {
+    (CaptchaStatus::NullPointer, "null pointer".into())
+}
+
+/// 当前线程最近一次返回状态码的调用的错误信息，该调用成功时返回NULL；返回的指针在下一次调用本库函数前有效
+#[unsafe(no_mangle)]
+pub extern "C" fn captcha_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| e.borrow().as_ref().map_or(ptr::null(), |s| s.as_ptr()))
+}
+
+/// 创建环境，`models_dir` 为NULL时使用当前目录下的 `models`
+///
+/// # Safety
+/// `models_dir` 必须为NULL或以NUL结尾的字符串，`out` 必须是有效的指针
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn captcha_env_new(models_dir: *const c_char, out: *mut *mut CaptchaEnv) -> CaptchaStatus {
+    guard(|| {
+        if out.is_null() {
+            return Err(null_pointer());
+        }
+        let inner = if models_dir.is_null() {
+            CaptchaEnvironment::default()
+        } else {
+            let dir = unsafe { CStr::from_ptr(models_dir) }
+                .to_str()
+                .map_err(|e| (CaptchaStatus::InvalidArgument, e.into()))?;
+            CaptchaEnvironment::with_model_loader(ModelLoader::CustomModelLoader(Box::new(DirModelLoader::new(dir))))
+        };
+        unsafe { *out = Box::into_raw(Box::new(CaptchaEnv { inner })) };
+        Ok(())
+    })
+}
+
+/// 释放环境，已创建的验证码句柄不受影响
+///
+/// # Safety
+/// `env` 必须为NULL或由 [`captcha_env_new`] 创建且未被释放
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn captcha_env_free(env: *mut CaptchaEnv) {
+    if !env.is_null() {
+        drop(unsafe { Box::from_raw(env) });
+    }
+}
+
+#[cfg(feature = "chinese_click_0")]
+pub use chinese_click_0::*;
+
+#[cfg(feature = "chinese_click_0")]
+mod chinese_click_0 {
+    use super::*;
+    use crate::captcha::ChineseClick0;
+
+    /// 不透明的ChineseClick0句柄
+    pub struct ChineseClick0Handle {
+        inner: ChineseClick0,
+    }
+
+    /// 创建ChineseClick0，首次调用时可能需要下载模型
+    ///
+    /// # Safety
+    /// `env` 必须是有效的环境句柄，`out` 必须是有效的指针
+    #[unsafe(no_mangle)]
+    pub unsafe extern "C" fn chinese_click0_new(env: *const CaptchaEnv, out: *mut *mut ChineseClick0Handle) -> CaptchaStatus {
+        guard(|| {
+            if env.is_null() || out.is_null() {
+                return Err(null_pointer());
+            }
+            let env = unsafe { &*env };
+            let inner: ChineseClick0 = env
+                .inner
+                .load_captcha_breaker()
+                .map_err(|e| (CaptchaStatus::ModelError, e))?;
+            unsafe { *out = Box::into_raw(Box::new(ChineseClick0Handle { inner })) };
+            Ok(())
+        })
+    }
+
+    /// # Safety
+    /// `handle` 必须为NULL或由 [`chinese_click0_new`] 创建且未被释放
+    #[unsafe(no_mangle)]
+    pub unsafe extern "C" fn chinese_click0_free(handle: *mut ChineseClick0Handle) {
+        if !handle.is_null() {
+            drop(unsafe { Box::from_raw(handle) });
+        }
+    }
+
+    /// 识别编码后的图片(PNG/JPEG等)，按顺序写入需要点选的坐标
+    ///
+    /// `out_len` 总是写入坐标数量；`capacity` 不足时返回 `BufferTooSmall`，
+    /// 可以先传入 `capacity = 0` 查询所需长度，但这会完整识别一次
+    ///
+    /// # Safety
+    /// `bytes` 必须指向 `len` 字节的有效内存，`out_points` 必须能容纳 `capacity` 个坐标(`capacity` 为0时可以为NULL)
+    #[unsafe(no_mangle)]
+    pub unsafe extern "C" fn chinese_click0_solve(
+        handle: *const ChineseClick0Handle,
+        bytes: *const u8,
+        len: usize,
+        out_points: *mut CaptchaPoint,
+        capacity: usize,
+        out_len: *mut usize,
+    ) -> CaptchaStatus {
+        guard(|| {
+            if handle.is_null() || bytes.is_null() || out_len.is_null() || (capacity > 0 && out_points.is_null()) {
+                return Err(null_pointer());
+            }
+            let handle = unsafe { &*handle };
+            let bytes = unsafe { std::slice::from_raw_parts(bytes, len) };
+            let image = crate::input::decode_bytes(bytes).map_err(|e| (CaptchaStatus::InvalidArgument, e.into()))?;
+            let points = handle.inner.run(&image).map_err(|e| (CaptchaStatus::SolveError, e))?;
+            unsafe { *out_len = points.len() };
+            if points.len() > capacity {
+                return Err((CaptchaStatus::BufferTooSmall, format!("需要 {} 个坐标的空间", points.len()).into()));
+            }
+            for (i, (x, y)) in points.into_iter().enumerate() {
+                unsafe { *out_points.add(i) = CaptchaPoint { x, y } };
+            }
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,332 @@
+//! 合成的点选验证码
+//!
+//! 不需要服务商的真实数据，按随机种子生成布局与ChineseClick0相同的图片：答案区随机摆放若干字形并旋转、缩放、
+//! 扭曲，提示区按点选顺序排列其中的一部分，同时给出每个字的真实位置与点选顺序，用于端到端的准确率测试。
+//! 字形由随机笔画组成，同一个编号的字形在不同图片中笔画相同，每次绘制时另加随机扭曲
+
+use crate::detection::BBox;
+use crate::geometry::Rect;
+use crate::rng::SplitMix64;
+use image::{Rgb, RgbImage};
+use std::error::Error;
+
+/// 合成验证码的参数
+#[derive(Debug, Clone, PartialEq)]
+pub struct SynthConfig {
+    pub image_size: (u32, u32),
+    /// 提示区的上边界，与 `ChineseClick0Config::prompt_top` 对应
+    pub prompt_top: f32,
+    /// 答案区的字数
+    pub answers: usize,
+    /// 提示区的字数，不能多于答案区的字数
+    pub questions: usize,
+    /// 字形编号的范围，每张图中的字互不相同
+    pub alphabet: usize,
+    /// 答案区字形的边长范围
+    pub answer_size: (f32, f32),
+    /// 提示区字形的边长
+    pub prompt_size: f32,
+    /// 答案区字形的最大旋转角度(弧度)
+    pub max_rotation: f32,
+    /// 笔画端点的随机偏移与错切的强度，相对于字形边长
+    pub distortion: f32,
+    /// 像素噪声的标准差
+    pub noise: f32,
+    /// 答案区中的干扰线条数
+    pub distractors: usize,
+    pub seed: u64,
+}
+
+impl Default for SynthConfig {
+    fn default() -> Self {
+        SynthConfig {
+            image_size: (344, 384),
+            prompt_top: 344.0,
+            answers: 4,
+            questions: 3,
+            alphabet: 64,
+            answer_size: (40.0, 56.0),
+            prompt_size: 24.0,
+            max_rotation: 0.5,
+            distortion: 0.04,
+            noise: 6.0,
+            distractors: 3,
+            seed: 0,
+        }
+    }
+}
+
+/// 图中的一个字
+#[derive(Debug, Clone, PartialEq)]
+pub struct SynthGlyph {
+    /// 字形编号，小于 `alphabet`
+    pub glyph: usize,
+    /// 字形所在的框，旋转的字形 `angle` 不为0
+    pub bbox: BBox,
+}
+
+/// 一张合成的验证码与真实答案
+#[derive(Debug, Clone)]
+pub struct SynthCaptcha {
+    pub image: RgbImage,
+    /// 答案区的字，顺序随机
+    pub answers: Vec<SynthGlyph>,
+    /// 提示区的字，按点选顺序(从左到右)排列
+    pub questions: Vec<SynthGlyph>,
+    /// 第i个问题对应的答案下标
+    pub order: Vec<usize>,
+}
+
+impl SynthCaptcha {
+    /// 按点选顺序的正确坐标，为答案框中心
+    pub fn clicks(&self) -> Vec<(f32, f32)> {
+        self.order.iter().map(|&i| self.answers[i].bbox.center()).collect()
+    }
+
+    /// 所有字的轴对齐框，先答案后问题，可以作为模拟检测模型的输出
+    pub fn boxes(&self) -> Vec<BBox> {
+        self.answers
+            .iter()
+            .chain(&self.questions)
+            .map(|glyph| BBox::new(glyph.bbox.rect, 1.0, 0))
+            .collect()
+    }
+
+    /// points是否按顺序落在对应答案框中心的tolerance像素以内
+    pub fn is_correct(&self, points: &[(f32, f32)], tolerance: f32) -> bool {
+        let clicks = self.clicks();
+        points.len() == clicks.len()
+            && points
+                .iter()
+                .zip(&clicks)
+                .all(|(&(x, y), &(cx, cy))| (x - cx).hypot(y - cy) <= tolerance)
+    }
+
+    /// 直接使用真实位置的固定布局，用于只测试匹配的场景
+    #[cfg(feature = "chinese_click_0")]
+    pub fn layout(&self) -> crate::captcha::FixedLayout {
+        crate::captcha::FixedLayout::new(
+            self.questions.iter().map(|glyph| glyph.bbox.rect).collect(),
+            self.answers.iter().map(|glyph| glyph.bbox.rect).collect(),
+        )
+        .with_image_size(self.image.dimensions())
+    }
+}
+
+/// 合成验证码的生成器，相同的config总是生成相同的图片序列
+#[derive(Debug)]
+pub struct SynthGenerator {
+    config: SynthConfig,
+    rng: SplitMix64,
+    /// 答案区划分的网格，每个字占一格，避免字形重叠
+    grid: (u32, u32),
+}
+
+impl SynthGenerator {
+    pub fn new(config: SynthConfig) -> Result<Self, Box<dyn Error>> {
+        let (width, height) = config.image_size;
+        if config.questions == 0 || config.questions > config.answers {
+            return Err("提示区的字数需要在1到答案区的字数之间".into());
+        }
+        if config.answers > config.alphabet {
+            return Err("字形数少于答案区的字数".into());
+        }
+        if config.answer_size.0 <= 0.0 || config.answer_size.0 > config.answer_size.1 {
+            return Err("答案区字形的边长范围无效".into());
+        }
+        if config.prompt_top >= height as f32 || config.prompt_size > height as f32 - config.prompt_top {
+            return Err("提示区放不下提示字形".into());
+        }
+        // 旋转后的字形不超出格子
+        let cell = config.answer_size.1 * std::f32::consts::SQRT_2;
+        let grid = ((width as f32 / cell) as u32, (config.prompt_top / cell) as u32);
+        if ((grid.0 * grid.1) as usize) < config.answers {
+            return Err(format!("答案区放不下{}个字", config.answers).into());
+        }
+        if config.questions as f32 * config.prompt_size * 1.5 > width as f32 {
+            return Err(format!("提示区放不下{}个字", config.questions).into());
+        }
+        Ok(SynthGenerator {
+            rng: SplitMix64::new(config.seed),
+            config,
+            grid,
+        })
+    }
+
+    pub fn config(&self) -> &SynthConfig {
+        &self.config
+    }
+
+    /// 生成下一张验证码
+    pub fn generate(&mut self) -> SynthCaptcha {
+        let config = self.config.clone();
+        let (width, height) = config.image_size;
+        let mut image = self.background(width, height);
+
+        let glyphs = self.choose(config.alphabet, config.answers);
+        let cells = self.choose((self.grid.0 * self.grid.1) as usize, config.answers);
+        let (cell_width, cell_height) = (width as f32 / self.grid.0 as f32, config.prompt_top / self.grid.1 as f32);
+        let mut answers = Vec::with_capacity(config.answers);
+        for (&glyph, &cell) in glyphs.iter().zip(&cells) {
+            let (column, row) = (cell as u32 % self.grid.0, cell as u32 / self.grid.0);
+            let size = config.answer_size.0 + self.rng.next_f32() * (config.answer_size.1 - config.answer_size.0);
+            // 格子内除去字形外接圆后的空余随机偏移
+            let slack = |extent: f32| (extent - size * std::f32::consts::SQRT_2).max(0.0) * 0.5;
+            let cx = (column as f32 + 0.5) * cell_width + self.rng.next_signed() * slack(cell_width);
+            let cy = (row as f32 + 0.5) * cell_height + self.rng.next_signed() * slack(cell_height);
+            let angle = self.rng.next_signed() * config.max_rotation;
+            let color = self.glyph_color();
+            self.draw_glyph(&mut image, glyph, (cx, cy), size, angle, color);
+            let mut bbox = BBox::new(Rect::from_center((cx, cy), (size, size)), 1.0, 0);
+            bbox.angle = angle;
+            answers.push(SynthGlyph { glyph, bbox });
+        }
+        for _ in 0..config.distractors {
+            self.draw_distractor(&mut image);
+        }
+
+        let order = self.choose(config.answers, config.questions);
+        let prompt_y = (config.prompt_top + height as f32) * 0.5;
+        let spacing = config.prompt_size * 1.5;
+        let questions = order
+            .iter()
+            .enumerate()
+            .map(|(i, &answer)| {
+                let glyph = answers[answer].glyph;
+                let center = (spacing * (i as f32 + 0.5), prompt_y);
+                self.draw_glyph(&mut image, glyph, center, config.prompt_size, 0.0, Rgb([40, 40, 40]));
+                SynthGlyph {
+                    glyph,
+                    bbox: BBox::new(Rect::from_center(center, (config.prompt_size, config.prompt_size)), 1.0, 0),
+                }
+            })
+            .collect();
+
+        self.add_noise(&mut image);
+        SynthCaptcha {
+            image,
+            answers,
+            questions,
+            order,
+        }
+    }
+
+    /// 从0..n中不重复地随机取k个，顺序随机
+    fn choose(&mut self, n: usize, k: usize) -> Vec<usize> {
+        let mut items: Vec<usize> = (0..n).collect();
+        for i in 0..k.min(n) {
+            let j = i + (self.rng.next_u64() % (n - i) as u64) as usize;
+            items.swap(i, j);
+        }
+        items.truncate(k);
+        items
+    }
+
+    /// 答案区为两种浅色之间的纵向渐变，提示区为浅灰色
+    fn background(&mut self, width: u32, height: u32) -> RgbImage {
+        let mut light = || -> [f32; 3] { std::array::from_fn(|_| 170.0 + self.rng.next_f32() * 85.0) };
+        let (top, bottom) = (light(), light());
+        let prompt_top = self.config.prompt_top;
+        RgbImage::from_fn(width, height, |_, y| {
+            if y as f32 >= prompt_top {
+                return Rgb([235, 235, 235]);
+            }
+            let t = y as f32 / prompt_top;
+            Rgb(std::array::from_fn(|c| (top[c] + (bottom[c] - top[c]) * t) as u8))
+        })
+    }
+
+    /// 与浅色背景对比明显的深色
+    fn glyph_color(&mut self) -> Rgb<u8> {
+        Rgb(std::array::from_fn(|_| (self.rng.next_f32() * 110.0) as u8))
+    }
+
+    /// 按编号确定的笔画，坐标在单位正方形内，旋转后仍在外接圆内
+    fn strokes(glyph: usize) -> Vec<[(f32, f32); 2]> {
+        let mut rng = SplitMix64::new(0x5eed ^ (glyph as u64).wrapping_mul(0x9e37_79b9));
+        let lattice = |v: u64| 0.2 + (v % 4) as f32 * 0.2;
+        let count = 3 + rng.next_u64() % 3;
+        (0..count)
+            .map(|_| loop {
+                let from = (lattice(rng.next_u64()), lattice(rng.next_u64()));
+                let to = (lattice(rng.next_u64()), lattice(rng.next_u64()));
+                if from != to {
+                    break [from, to];
+                }
+            })
+            .collect()
+    }
+
+    fn draw_glyph(&mut self, image: &mut RgbImage, glyph: usize, center: (f32, f32), size: f32, angle: f32, color: Rgb<u8>) {
+        let distortion = self.config.distortion;
+        let shear = self.rng.next_signed() * distortion * 4.0;
+        let (sin, cos) = angle.sin_cos();
+        let segments: Vec<[(f32, f32); 2]> = Self::strokes(glyph)
+            .into_iter()
+            .map(|stroke| {
+                stroke.map(|(x, y)| {
+                    let x = x - 0.5 + self.rng.next_signed() * distortion;
+                    let y = y - 0.5 + self.rng.next_signed() * distortion;
+                    let (x, y) = ((x + shear * y) * size, y * size);
+                    (center.0 + x * cos - y * sin, center.1 + x * sin + y * cos)
+                })
+            })
+            .collect();
+        draw_segments(image, &segments, size * 0.09, color);
+    }
+
+    /// 穿过答案区的细线
+    fn draw_distractor(&mut self, image: &mut RgbImage) {
+        let (width, prompt_top) = (self.config.image_size.0 as f32, self.config.prompt_top);
+        let mut point = || (self.rng.next_f32() * width, self.rng.next_f32() * prompt_top);
+        let segment = [point(), point()];
+        let color = self.glyph_color();
+        draw_segments(image, &[segment], 1.0, color);
+    }
+
+    fn add_noise(&mut self, image: &mut RgbImage) {
+        if self.config.noise <= 0.0 {
+            return;
+        }
+        for pixel in image.pixels_mut() {
+            let delta = self.rng.next_normal() * self.config.noise;
+            for c in pixel.0.iter_mut() {
+                *c = (*c as f32 + delta).clamp(0.0, 255.0) as u8;
+            }
+        }
+    }
+}
+
+impl Iterator for SynthGenerator {
+    type Item = SynthCaptcha;
+
+    fn next(&mut self) -> Option<SynthCaptcha> {
+        Some(self.generate())
+    }
+}
+
+/// 以thickness为线宽绘制线段，边缘按覆盖率混合
+fn draw_segments(image: &mut RgbImage, segments: &[[(f32, f32); 2]], thickness: f32, color: Rgb<u8>) {
+    let radius = thickness.max(1.0) * 0.5;
+    let (width, height) = image.dimensions();
+    for &[(x0, y0), (x1, y1)] in segments {
+        let x_range = (x0.min(x1) - radius - 1.0).max(0.0) as u32..((x0.max(x1) + radius + 1.0).max(0.0) as u32).min(width);
+        let y_range = (y0.min(y1) - radius - 1.0).max(0.0) as u32..((y0.max(y1) + radius + 1.0).max(0.0) as u32).min(height);
+        let (dx, dy) = (x1 - x0, y1 - y0);
+        let length = (dx * dx + dy * dy).max(f32::EPSILON);
+        for y in y_range {
+            for x in x_range.clone() {
+                let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+                let t = (((px - x0) * dx + (py - y0) * dy) / length).clamp(0.0, 1.0);
+                let distance = (px - x0 - t * dx).hypot(py - y0 - t * dy);
+                let coverage = (radius + 0.5 - distance).clamp(0.0, 1.0);
+                if coverage > 0.0 {
+                    let pixel = image.get_pixel_mut(x, y);
+                    for (channel, &target) in pixel.0.iter_mut().zip(&color.0) {
+                        *channel = (*channel as f32 * (1.0 - coverage) + target as f32 * coverage) as u8;
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,288 @@
+//! 坐标空间变换
+//!
+//! 检测结果会在三种坐标空间之间转换：模型输入空间(缩放/补边后的正方形)、原图像素空间，
+//! 以及网页中渲染验证码的元素所在的CSS像素空间。这里的 [`Transform`] 统一处理其中的缩放与平移。
+//!
+//! [`Rect`] 同时提供NMS使用的交并比等度量，校验答案是否落在页面布局中的预期区域时可以直接复用
+
+use crate::detection::Resize;
+use ndarray::Array2;
+use std::ops::Sub;
+
+/// 轴对齐矩形，`x_max`/`y_max` 不包含在内
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Rect<T> {
+    pub x_min: T,
+    pub y_min: T,
+    pub x_max: T,
+    pub y_max: T,
+}
+
+impl<T> Rect<T> {
+    pub fn new(x_min: T, y_min: T, x_max: T, y_max: T) -> Self {
+        Rect { x_min, y_min, x_max, y_max }
+    }
+}
+
+impl<T: Copy + Sub<Output = T>> Rect<T> {
+    pub fn width(&self) -> T {
+        self.x_max - self.x_min
+    }
+
+    pub fn height(&self) -> T {
+        self.y_max - self.y_min
+    }
+}
+
+impl Rect<f32> {
+    pub fn from_center((cx, cy): (f32, f32), (width, height): (f32, f32)) -> Self {
+        Rect::new(cx - width / 2.0, cy - height / 2.0, cx + width / 2.0, cy + height / 2.0)
+    }
+
+    pub fn center(&self) -> (f32, f32) {
+        ((self.x_min + self.x_max) / 2.0, (self.y_min + self.y_max) / 2.0)
+    }
+
+    pub fn area(&self) -> f32 {
+        self.width().max(0.0) * self.height().max(0.0)
+    }
+
+    pub fn contains(&self, (x, y): (f32, f32)) -> bool {
+        (self.x_min..self.x_max).contains(&x) && (self.y_min..self.y_max).contains(&y)
+    }
+
+    /// 相交部分，不相交时返回None
+    pub fn intersection(&self, other: &Rect<f32>) -> Option<Rect<f32>> {
+        let rect = Rect::new(
+            self.x_min.max(other.x_min),
+            self.y_min.max(other.y_min),
+            self.x_max.min(other.x_max),
+            self.y_max.min(other.y_max),
+        );
+        (rect.width() > 0.0 && rect.height() > 0.0).then_some(rect)
+    }
+
+    /// 相交部分的面积，不相交时为0
+    pub fn intersection_area(&self, other: &Rect<f32>) -> f32 {
+        self.intersection(other).map_or(0.0, |r| r.area())
+    }
+
+    /// 并集的面积
+    pub fn union_area(&self, other: &Rect<f32>) -> f32 {
+        self.area() + other.area() - self.intersection_area(other)
+    }
+
+    /// 同时包含两个矩形的最小矩形
+    pub fn enclosing(&self, other: &Rect<f32>) -> Rect<f32> {
+        Rect::new(
+            self.x_min.min(other.x_min),
+            self.y_min.min(other.y_min),
+            self.x_max.max(other.x_max),
+            self.y_max.max(other.y_max),
+        )
+    }
+
+    /// 交并比，取值0~1，两个矩形面积都为0时为0
+    pub fn iou(&self, other: &Rect<f32>) -> f32 {
+        let union = self.union_area(other);
+        if union > 0.0 { self.intersection_area(other) / union } else { 0.0 }
+    }
+
+    /// 广义交并比(GIoU)，取值-1~1，不相交时按最小包围矩形中的空白比例继续区分远近
+    pub fn giou(&self, other: &Rect<f32>) -> f32 {
+        let enclosing = self.enclosing(other).area();
+        if enclosing <= 0.0 {
+            return 0.0;
+        }
+        self.iou(other) - (enclosing - self.union_area(other)) / enclosing
+    }
+
+    /// 距离交并比(DIoU)，取值-1~1，在交并比的基础上减去中心距离与最小包围矩形对角线之比的平方
+    pub fn diou(&self, other: &Rect<f32>) -> f32 {
+        let enclosing = self.enclosing(other);
+        let diagonal = enclosing.width().powi(2) + enclosing.height().powi(2);
+        if diagonal <= 0.0 {
+            return 0.0;
+        }
+        self.iou(other) - self.center_distance(other).powi(2) / diagonal
+    }
+
+    /// 中心点之间的欧氏距离
+    pub fn center_distance(&self, other: &Rect<f32>) -> f32 {
+        let ((x0, y0), (x1, y1)) = (self.center(), other.center());
+        (x0 - x1).hypot(y0 - y1)
+    }
+
+    /// 点到矩形的欧氏距离，点在矩形内时为0
+    pub fn distance_to(&self, (x, y): (f32, f32)) -> f32 {
+        let dx = (self.x_min - x).max(x - self.x_max).max(0.0);
+        let dy = (self.y_min - y).max(y - self.y_max).max(0.0);
+        dx.hypot(dy)
+    }
+
+    pub fn scaled(&self, scale_x: f32, scale_y: f32) -> Self {
+        Transform::scale(scale_x, scale_y).apply_rect(*self)
+    }
+
+    /// 截断为整数像素区域(左上角与宽高分别截断)，限制在 `bounds` (宽, 高) 内且至少为1x1
+    pub fn to_pixels(&self, bounds: (u32, u32)) -> Rect<u32> {
+        let x_min = (self.x_min.max(0.0) as u32).min(bounds.0.saturating_sub(1));
+        let y_min = (self.y_min.max(0.0) as u32).min(bounds.1.saturating_sub(1));
+        let width = (self.width().max(0.0) as u32).clamp(1, bounds.0.saturating_sub(x_min).max(1));
+        let height = (self.height().max(0.0) as u32).clamp(1, bounds.1.saturating_sub(y_min).max(1));
+        Rect::new(x_min, y_min, x_min + width, y_min + height)
+    }
+}
+
+/// 两组矩形两两之间的交并比，`a` 的每个矩形为一行
+pub fn pairwise_iou(a: &[Rect<f32>], b: &[Rect<f32>]) -> Array2<f32> {
+    Array2::from_shape_fn((a.len(), b.len()), |(i, j)| a[i].iou(&b[j]))
+}
+
+/// 轴对齐的仿射变换 `p' = p * scale + offset`
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Transform {
+    pub scale_x: f32,
+    pub scale_y: f32,
+    pub offset_x: f32,
+    pub offset_y: f32,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Transform::IDENTITY
+    }
+}
+
+impl Transform {
+    pub const IDENTITY: Transform = Transform {
+        scale_x: 1.0,
+        scale_y: 1.0,
+        offset_x: 0.0,
+        offset_y: 0.0,
+    };
+
+    pub fn scale(scale_x: f32, scale_y: f32) -> Self {
+        Transform {
+            scale_x,
+            scale_y,
+            ..Transform::IDENTITY
+        }
+    }
+
+    pub fn translate(offset_x: f32, offset_y: f32) -> Self {
+        Transform {
+            offset_x,
+            offset_y,
+            ..Transform::IDENTITY
+        }
+    }
+
+    /// 把 `from` 尺寸的像素坐标缩放到 `to` 尺寸
+    pub fn resize(from: (u32, u32), to: (u32, u32)) -> Self {
+        Transform::scale(
+            to.0 as f32 / from.0.max(1) as f32,
+            to.1 as f32 / from.1.max(1) as f32,
+        )
+    }
+
+    /// 先应用self，再应用next
+    pub fn then(self, next: Transform) -> Self {
+        Transform {
+            scale_x: self.scale_x * next.scale_x,
+            scale_y: self.scale_y * next.scale_y,
+            offset_x: self.offset_x * next.scale_x + next.offset_x,
+            offset_y: self.offset_y * next.scale_y + next.offset_y,
+        }
+    }
+
+    pub fn inverse(self) -> Self {
+        Transform {
+            scale_x: 1.0 / self.scale_x,
+            scale_y: 1.0 / self.scale_y,
+            offset_x: -self.offset_x / self.scale_x,
+            offset_y: -self.offset_y / self.scale_y,
+        }
+    }
+
+    pub fn apply(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        (x * self.scale_x + self.offset_x, y * self.scale_y + self.offset_y)
+    }
+
+    /// 变换矩形，缩放系数为负时会重新排列端点
+    pub fn apply_rect(&self, rect: Rect<f32>) -> Rect<f32> {
+        let (x0, y0) = self.apply((rect.x_min, rect.y_min));
+        let (x1, y1) = self.apply((rect.x_max, rect.y_max));
+        Rect::new(x0.min(x1), y0.min(y1), x0.max(x1), y0.max(y1))
+    }
+}
+
+/// 图片缩放到模型输入后内容区域的尺寸，使用 [`Resize::Pad`] 时保持宽高比并在右侧/下方补边
+pub fn fitted_size(image_size: (u32, u32), input_size: u32, resize: Resize) -> (u32, u32) {
+    let (width, height) = (image_size.0.max(1), image_size.1.max(1));
+    match resize {
+        Resize::Stretch => (input_size, input_size),
+        Resize::Pad => {
+            let scale = (input_size as f32 / width as f32).min(input_size as f32 / height as f32);
+            (
+                ((width as f32 * scale).round() as u32).clamp(1, input_size),
+                ((height as f32 * scale).round() as u32).clamp(1, input_size),
+            )
+        }
+    }
+}
+
+/// 模型输入空间到原图像素空间的变换
+pub fn model_to_image(image_size: (u32, u32), input_size: u32, resize: Resize) -> Transform {
+    let fitted = fitted_size(image_size, input_size, resize);
+    Transform::resize(fitted, (image_size.0.max(1), image_size.1.max(1)))
+}
+
+/// 网页中渲染验证码图片的元素，取值通常来自 `getBoundingClientRect()` 与 `window.devicePixelRatio`
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ElementRect {
+    /// 元素左上角的CSS像素坐标
+    pub left: f32,
+    pub top: f32,
+    /// 元素渲染后的CSS像素尺寸
+    pub width: f32,
+    pub height: f32,
+    pub device_pixel_ratio: f32,
+}
+
+impl ElementRect {
+    pub fn new(left: f32, top: f32, width: f32, height: f32) -> Self {
+        ElementRect {
+            left,
+            top,
+            width,
+            height,
+            device_pixel_ratio: 1.0,
+        }
+    }
+
+    pub fn with_device_pixel_ratio(mut self, device_pixel_ratio: f32) -> Self {
+        self.device_pixel_ratio = device_pixel_ratio;
+        self
+    }
+
+    /// 原图像素坐标到页面CSS像素坐标，用于 `page.mouse.click(x, y)` 一类的接口
+    pub fn image_to_page(&self, image_size: (u32, u32)) -> Transform {
+        Transform::scale(
+            self.width / image_size.0.max(1) as f32,
+            self.height / image_size.1.max(1) as f32,
+        )
+        .then(Transform::translate(self.left, self.top))
+    }
+
+    /// 原图像素坐标到元素内的CSS像素坐标(相对元素左上角)
+    pub fn image_to_element(&self, image_size: (u32, u32)) -> Transform {
+        self.image_to_page(image_size).then(Transform::translate(-self.left, -self.top))
+    }
+
+    /// 原图像素坐标到设备(物理)像素坐标，用于直接向操作系统注入事件的场景
+    pub fn image_to_device(&self, image_size: (u32, u32)) -> Transform {
+        self.image_to_page(image_size)
+            .then(Transform::scale(self.device_pixel_ratio, self.device_pixel_ratio))
+    }
+}
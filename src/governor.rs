@@ -0,0 +1,243 @@
+//! 并发与速率限制
+//!
+//! 多个调用方共用一个部署时，[`Governor`] 限制同时进行的识别数与每秒识别数，超出的请求在有界队列中等待，
+//! 队列已满或等待超时时立即返回 [`CaptchaError::Overloaded`]，避免请求堆积与显存无限增长
+
+use crate::error::CaptchaError;
+use std::error::Error;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GovernorConfig {
+    /// 同时进行的最大识别数，None为不限制
+    pub max_concurrent: Option<usize>,
+    /// 每秒最多开始的识别数(令牌桶)，None为不限制
+    pub max_per_second: Option<f32>,
+    /// 令牌桶容量，即空闲后允许连续开始的识别数
+    pub burst: u32,
+    /// 最多排队等待的请求数，为0时不能立即开始的请求直接被拒绝
+    pub max_queue: usize,
+    /// 排队的最长时间，None为一直等待
+    pub queue_timeout: Option<Duration>,
+}
+
+impl Default for GovernorConfig {
+    fn default() -> Self {
+        GovernorConfig {
+            max_concurrent: Some(std::thread::available_parallelism().map_or(4, |n| n.get())),
+            max_per_second: None,
+            burst: 1,
+            max_queue: 64,
+            queue_timeout: Some(Duration::from_secs(10)),
+        }
+    }
+}
+
+/// 限制器的统计
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct GovernorStats {
+    /// 正在进行的识别数
+    pub active: usize,
+    /// 正在排队的请求数
+    pub waiting: usize,
+    /// 已放行的请求数
+    pub admitted: u64,
+    /// 因队列已满或排队超时被拒绝的请求数
+    pub rejected: u64,
+}
+
+#[derive(Debug)]
+struct State {
+    active: usize,
+    waiting: usize,
+    tokens: f32,
+    refilled: Instant,
+    admitted: u64,
+    rejected: u64,
+}
+
+/// 识别的并发与速率限制器，可以在多个线程之间共享
+///
+/// 排队的请求被唤醒的顺序不保证先来先服务
+#[derive(Debug)]
+pub struct Governor {
+    config: GovernorConfig,
+    state: Mutex<State>,
+    released: Condvar,
+}
+
+/// 一次识别的许可，drop时归还
+#[derive(Debug)]
+pub struct Permit<'a> {
+    governor: &'a Governor,
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        self.governor.state.lock().unwrap().active -= 1;
+        self.governor.released.notify_one();
+    }
+}
+
+impl Default for Governor {
+    fn default() -> Self {
+        Governor::new(GovernorConfig::default())
+    }
+}
+
+impl Governor {
+    pub fn new(config: GovernorConfig) -> Self {
+        Governor {
+            state: Mutex::new(State {
+                active: 0,
+                waiting: 0,
+                tokens: config.burst.max(1) as f32,
+                refilled: Instant::now(),
+                admitted: 0,
+                rejected: 0,
+            }),
+            config,
+            released: Condvar::new(),
+        }
+    }
+
+    /// 没有任何限制的限制器
+    pub fn unlimited() -> Self {
+        Governor::new(GovernorConfig {
+            max_concurrent: None,
+            max_per_second: None,
+            burst: 1,
+            max_queue: 0,
+            queue_timeout: None,
+        })
+    }
+
+    pub fn config(&self) -> &GovernorConfig {
+        &self.config
+    }
+
+    /// 按速率补充令牌
+    fn refill(&self, state: &mut State) {
+        let Some(rate) = self.config.max_per_second else {
+            return;
+        };
+        let now = Instant::now();
+        let elapsed = now.duration_since(state.refilled).as_secs_f32();
+        state.tokens = (state.tokens + elapsed * rate).min(self.config.burst.max(1) as f32);
+        state.refilled = now;
+    }
+
+    /// 现在能否开始，不能时返回距离下一个令牌的时间(只受并发限制时为None)
+    fn ready(&self, state: &State) -> Result<(), Option<Duration>> {
+        if self.config.max_concurrent.is_some_and(|max| state.active >= max.max(1)) {
+            return Err(None);
+        }
+        match self.config.max_per_second {
+            Some(rate) if state.tokens < 1.0 => Err(Some(Duration::from_secs_f32((1.0 - state.tokens) / rate.max(f32::EPSILON)))),
+            _ => Ok(()),
+        }
+    }
+
+    fn admit(&self, state: &mut State) -> Permit<'_> {
+        if self.config.max_per_second.is_some() {
+            state.tokens -= 1.0;
+        }
+        state.active += 1;
+        state.admitted += 1;
+        Permit { governor: self }
+    }
+
+    /// 等待许可，队列已满或排队超时时返回 [`CaptchaError::Overloaded`]
+    pub fn acquire(&self) -> Result<Permit<'_>, CaptchaError> {
+        let mut state = self.state.lock().unwrap();
+        self.refill(&mut state);
+        if self.ready(&state).is_ok() {
+            return Ok(self.admit(&mut state));
+        }
+        if state.waiting >= self.config.max_queue {
+            state.rejected += 1;
+            return Err(CaptchaError::Overloaded { reason: "等待队列已满" });
+        }
+        let deadline = self.config.queue_timeout.map(|timeout| Instant::now() + timeout);
+        state.waiting += 1;
+        loop {
+            self.refill(&mut state);
+            let wait = match self.ready(&state) {
+                Ok(()) => {
+                    state.waiting -= 1;
+                    return Ok(self.admit(&mut state));
+                }
+                Err(wait) => wait,
+            };
+            let remaining = deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+            if remaining.is_some_and(|r| r.is_zero()) {
+                state.waiting -= 1;
+                state.rejected += 1;
+                return Err(CaptchaError::Overloaded { reason: "排队超时" });
+            }
+            // 等待归还许可或下一个令牌，两者都有上限时取较早的
+            state = match [wait, remaining].into_iter().flatten().min() {
+                Some(timeout) => self.released.wait_timeout(state, timeout).unwrap().0,
+                None => self.released.wait(state).unwrap(),
+            };
+        }
+    }
+
+    /// 不等待，不能立即开始时返回None
+    pub fn try_acquire(&self) -> Option<Permit<'_>> {
+        let mut state = self.state.lock().unwrap();
+        self.refill(&mut state);
+        match self.ready(&state) {
+            Ok(()) => Some(self.admit(&mut state)),
+            Err(_) => None,
+        }
+    }
+
+    /// 取得许可后执行f
+    pub fn run<T>(&self, f: impl FnOnce() -> Result<T, Box<dyn Error>>) -> Result<T, Box<dyn Error>> {
+        let _permit = self.acquire()?;
+        f()
+    }
+
+    pub fn stats(&self) -> GovernorStats {
+        let state = self.state.lock().unwrap();
+        GovernorStats {
+            active: state.active,
+            waiting: state.waiting,
+            admitted: state.admitted,
+            rejected: state.rejected,
+        }
+    }
+}
+
+/// 受限制器约束的验证码实现，见 [`CaptchaEnvironment::load_governed_breaker`](crate::environment::CaptchaEnvironment::load_governed_breaker)
+#[derive(Debug)]
+pub struct Governed<B> {
+    breaker: B,
+    governor: Arc<Governor>,
+}
+
+impl<B> Governed<B> {
+    pub fn new(breaker: B, governor: Arc<Governor>) -> Self {
+        Governed { breaker, governor }
+    }
+
+    /// 取得许可后调用breaker，例如 `governed.run(|cb| cb.run(&image))`
+    pub fn run<T>(&self, f: impl FnOnce(&B) -> Result<T, Box<dyn Error>>) -> Result<T, Box<dyn Error>> {
+        self.governor.run(|| f(&self.breaker))
+    }
+
+    /// 不受限制地直接使用
+    pub fn inner(&self) -> &B {
+        &self.breaker
+    }
+
+    pub fn governor(&self) -> &Arc<Governor> {
+        &self.governor
+    }
+
+    pub fn into_inner(self) -> B {
+        self.breaker
+    }
+}
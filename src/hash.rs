@@ -0,0 +1,138 @@
+//! 图片的感知哈希
+//!
+//! 相似的图片得到相近的64位哈希，用汉明距离比较。可以用来给采集的数据集去重，
+//! 或比较新旧验证码的哈希分布判断服务商是否更换了样式。[`AnswerCache`](crate::cache::AnswerCache) 使用 [`phash`]
+
+use image::imageops::FilterType;
+use image::{DynamicImage, GrayImage};
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// pHash计算时缩放到的边长
+const PHASH_SIZE: usize = 32;
+/// 哈希的边长，8x8共64位
+const HASH_SIZE: usize = 8;
+
+/// 64位图片哈希，第 `y * 8 + x` 位对应缩放后第y行第x个位置
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ImageHash(pub u64);
+
+impl ImageHash {
+    /// 汉明距离，即不同的位数
+    pub fn distance(&self, other: &ImageHash) -> u32 {
+        (self.0 ^ other.0).count_ones()
+    }
+}
+
+/// 16位十六进制
+impl fmt::Display for ImageHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+impl FromStr for ImageHash {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() != 16 {
+            return Err(format!("图片哈希需要16位十六进制：{}", s).into());
+        }
+        Ok(ImageHash(u64::from_str_radix(s, 16)?))
+    }
+}
+
+/// 哈希算法
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub enum HashKind {
+    /// 均值哈希，最快，但对亮度与对比度的变化敏感
+    Average,
+    /// 感知哈希，对重新编码、缩放与亮度变化最稳定
+    #[default]
+    Perceptual,
+    /// 差值哈希，比较相邻像素，对渐变与亮度变化稳定
+    Difference,
+}
+
+impl HashKind {
+    pub fn compute(&self, image: &DynamicImage) -> ImageHash {
+        match self {
+            HashKind::Average => ahash(image),
+            HashKind::Perceptual => phash(image),
+            HashKind::Difference => dhash(image),
+        }
+    }
+}
+
+fn gray(image: &DynamicImage, width: usize, height: usize) -> GrayImage {
+    image
+        .resize_exact(width as u32, height as u32, FilterType::Triangle)
+        .into_luma8()
+}
+
+fn from_bits(bits: impl IntoIterator<Item = bool>) -> ImageHash {
+    ImageHash(
+        bits.into_iter()
+            .enumerate()
+            .fold(0u64, |hash, (i, bit)| if bit { hash | (1 << i) } else { hash }),
+    )
+}
+
+/// 均值哈希：缩放为8x8灰度图，每个像素与均值比较
+pub fn ahash(image: &DynamicImage) -> ImageHash {
+    let gray = gray(image, HASH_SIZE, HASH_SIZE);
+    let mean = gray.pixels().map(|p| p.0[0] as u32).sum::<u32>() as f32 / (HASH_SIZE * HASH_SIZE) as f32;
+    from_bits(gray.pixels().map(|p| p.0[0] as f32 > mean))
+}
+
+/// 感知哈希：缩放为32x32灰度图后做DCT，低频8x8系数与中位数比较
+pub fn phash(image: &DynamicImage) -> ImageHash {
+    let gray = gray(image, PHASH_SIZE, PHASH_SIZE);
+    let cos: Vec<[f32; PHASH_SIZE]> = (0..HASH_SIZE)
+        .map(|u| {
+            std::array::from_fn(|x| {
+                ((2 * x + 1) as f32 * u as f32 * std::f32::consts::PI / (2 * PHASH_SIZE) as f32).cos()
+            })
+        })
+        .collect();
+    // 先对每一行做DCT，再对列做DCT，只计算需要的低频系数
+    let rows: Vec<[f32; HASH_SIZE]> = gray
+        .rows()
+        .map(|row| {
+            let row: Vec<f32> = row.map(|p| p.0[0] as f32).collect();
+            std::array::from_fn(|u| row.iter().zip(cos[u]).map(|(p, c)| p * c).sum())
+        })
+        .collect();
+    let mut coefficients = [0f32; HASH_SIZE * HASH_SIZE];
+    for v in 0..HASH_SIZE {
+        for u in 0..HASH_SIZE {
+            coefficients[v * HASH_SIZE + u] = rows.iter().zip(cos[v]).map(|(row, c)| row[u] * c).sum();
+        }
+    }
+    // 直流分量远大于其他系数，不参与中位数
+    let mut ac = coefficients[1..].to_vec();
+    ac.sort_unstable_by(f32::total_cmp);
+    let median = ac[ac.len() / 2];
+    from_bits(coefficients.iter().map(|&c| c > median))
+}
+
+/// 差值哈希：缩放为9x8灰度图，每行相邻像素比较，右侧更亮时为1
+pub fn dhash(image: &DynamicImage) -> ImageHash {
+    let gray = gray(image, HASH_SIZE + 1, HASH_SIZE);
+    from_bits((0..HASH_SIZE as u32).flat_map(|y| {
+        let gray = &gray;
+        (0..HASH_SIZE as u32).map(move |x| gray.get_pixel(x + 1, y).0[0] > gray.get_pixel(x, y).0[0])
+    }))
+}
+
+/// 去重，返回需要保留的下标：与之前保留的哈希距离都超过 `max_distance` 的才保留，按原顺序排列
+pub fn dedup(hashes: &[ImageHash], max_distance: u32) -> Vec<usize> {
+    let mut kept: Vec<usize> = vec![];
+    for (i, hash) in hashes.iter().enumerate() {
+        if kept.iter().all(|&k| hashes[k].distance(hash) > max_distance) {
+            kept.push(i);
+        }
+    }
+    kept
+}
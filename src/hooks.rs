@@ -0,0 +1,93 @@
+//! 处理钩子
+//!
+//! 不同服务商的验证码常有各自的小问题，例如固定位置的水印被检测为字、答案需要换算到另一套坐标。
+//! [`Hooks`] 在预处理、检测与匹配之后调用注册的闭包，闭包可以检查并修改中间数据，不需要为此fork本crate。
+//! 与只读的 [`PipelineObserver`](crate::captcha::PipelineObserver) 不同，钩子的修改会影响之后的流程
+
+use crate::detection::BBox;
+use image::RgbImage;
+use std::fmt;
+use std::sync::Arc;
+
+/// 预处理之后、检测之前，图片已统一转换为RGB
+pub type PreprocessHook = Arc<dyn Fn(&mut RgbImage) + Send + Sync>;
+
+/// 检测之后、截取图像块之前，框为原图坐标
+pub type DetectionsHook = Arc<dyn Fn(&RgbImage, &mut Vec<BBox>) + Send + Sync>;
+
+/// 得到答案之后，坐标按点选顺序排列
+pub type MatchesHook = Arc<dyn Fn(&mut Vec<(f32, f32)>) + Send + Sync>;
+
+/// 一组钩子，同一个位置的多个钩子按注册顺序依次调用
+///
+/// 钩子在识别线程中同步调用，需要是 `Send + Sync`；克隆只复制引用
+#[derive(Clone, Default)]
+pub struct Hooks {
+    preprocess: Vec<PreprocessHook>,
+    detections: Vec<DetectionsHook>,
+    matches: Vec<MatchesHook>,
+}
+
+impl fmt::Debug for Hooks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Hooks")
+            .field("preprocess", &self.preprocess.len())
+            .field("detections", &self.detections.len())
+            .field("matches", &self.matches.len())
+            .finish()
+    }
+}
+
+impl Hooks {
+    pub fn new() -> Self {
+        Hooks::default()
+    }
+
+    /// 修改预处理后的图片，例如遮盖固定位置的水印；改变图片尺寸时坐标也会随之改变
+    pub fn on_preprocess(mut self, hook: impl Fn(&mut RgbImage) + Send + Sync + 'static) -> Self {
+        self.preprocess.push(Arc::new(hook));
+        self
+    }
+
+    /// 修改检测框，例如丢弃水印的框或补上漏检的框
+    pub fn on_detections(mut self, hook: impl Fn(&RgbImage, &mut Vec<BBox>) + Send + Sync + 'static) -> Self {
+        self.detections.push(Arc::new(hook));
+        self
+    }
+
+    /// 修改最终需要点选的坐标，例如换算到页面上的坐标
+    pub fn on_matches(mut self, hook: impl Fn(&mut Vec<(f32, f32)>) + Send + Sync + 'static) -> Self {
+        self.matches.push(Arc::new(hook));
+        self
+    }
+
+    /// 追加other中的钩子，在self已有的钩子之后调用
+    pub fn extend(mut self, other: &Hooks) -> Self {
+        self.preprocess.extend(other.preprocess.iter().cloned());
+        self.detections.extend(other.detections.iter().cloned());
+        self.matches.extend(other.matches.iter().cloned());
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.preprocess.is_empty() && self.detections.is_empty() && self.matches.is_empty()
+    }
+
+    pub fn preprocess(&self, image: &mut RgbImage) {
+        for hook in &self.preprocess {
+            hook(image);
+        }
+    }
+
+    pub fn detections(&self, image: &RgbImage, boxes: &mut Vec<BBox>) {
+        for hook in &self.detections {
+            hook(image, boxes);
+        }
+    }
+
+    pub fn matches(&self, points: &mut Vec<(f32, f32)>) {
+        for hook in &self.matches {
+            hook(points);
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use crate::error::CaptchaError;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use image::{DynamicImage, Rgb, RgbImage};
+
+/// 从编码后的图片字节(PNG/JPEG等)解码图片
+pub fn decode_bytes(bytes: &[u8]) -> Result<DynamicImage, CaptchaError> {
+    Ok(image::load_from_memory(bytes)?)
+}
+
+/// 从base64字符串解码图片，支持网页中常见的 `data:image/png;base64,` 前缀
+pub fn decode_base64(data: &str) -> Result<DynamicImage, CaptchaError> {
+    decode_bytes(&decode_base64_bytes(data)?)
+}
+
+/// 解码base64字符串为原始字节，去掉 `data:...;base64,` 前缀，语音等非图片数据也使用该函数
+pub fn decode_base64_bytes(data: &str) -> Result<Vec<u8>, CaptchaError> {
+    let data = match data.split_once(";base64,") {
+        Some((prefix, payload)) if prefix.starts_with("data:") => payload,
+        _ => data,
+    };
+    // 网页复制出来的数据经常带有换行
+    let cleaned: String = data.chars().filter(|c| !c.is_ascii_whitespace()).collect();
+    Ok(STANDARD.decode(cleaned)?)
+}
+
+/// 透明像素默认合成到的背景色(白色)
+pub const DEFAULT_BACKGROUND: Rgb<u8> = Rgb([255, 255, 255]);
+
+/// 统一转换为RGB8，识别流程只在入口处转换一次
+///
+/// 灰度与调色板图片展开为三通道，16位与浮点图片按比例缩放到8位，
+/// 带透明通道的图片与background做alpha合成，而不是直接丢弃透明通道
+pub fn to_rgb8(image: &DynamicImage, background: Rgb<u8>) -> RgbImage {
+    if !image.color().has_alpha() {
+        return image.to_rgb8();
+    }
+    let rgba = image.to_rgba8();
+    RgbImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+        let pixel = rgba.get_pixel(x, y);
+        let alpha = pixel[3] as u32;
+        Rgb(std::array::from_fn(|c| {
+            ((pixel[c] as u32 * alpha + background[c] as u32 * (255 - alpha) + 127) / 255) as u8
+        }))
+    })
+}
@@ -0,0 +1,247 @@
+//! 模型检查
+//!
+//! 接入自定义导出的模型时，用 [`ModelDescription`] 查看模型的输入输出、opset与元数据，
+//! 不需要netron等外部工具就能对照 [`Model::io_spec`] 排查形状不匹配。
+//! ONNX Runtime不提供opset，[`OnnxHeader`] 直接从模型文件的protobuf中读取，跳过计算图
+
+#[cfg(feature = "native")]
+use crate::backend::InferenceBackend;
+use crate::backend::TensorInfo;
+use crate::metadata::EmbeddedMetadata;
+use crate::model::{Model, Precision};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+/// 模型依赖的算子集版本
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpsetImport {
+    /// 空字符串为默认的 `ai.onnx`
+    pub domain: String,
+    pub version: i64,
+}
+
+/// ONNX `ModelProto` 中计算图以外的字段
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OnnxHeader {
+    pub ir_version: Option<i64>,
+    pub producer_name: Option<String>,
+    pub producer_version: Option<String>,
+    pub opsets: Vec<OpsetImport>,
+    /// `metadata_props`，包括本crate的 [`EmbeddedMetadata`]
+    pub metadata: HashMap<String, String>,
+}
+
+impl OnnxHeader {
+    /// 解析模型文件的字节
+    pub fn parse(bytes: &[u8]) -> Result<Self, Box<dyn Error>> {
+        let mut header = OnnxHeader::default();
+        for field in Fields::new(bytes) {
+            match field? {
+                (1, Value::Varint(v)) => header.ir_version = Some(v as i64),
+                (2, Value::Bytes(b)) => header.producer_name = Some(String::from_utf8_lossy(b).into_owned()),
+                (3, Value::Bytes(b)) => header.producer_version = Some(String::from_utf8_lossy(b).into_owned()),
+                (8, Value::Bytes(b)) => {
+                    let mut opset = OpsetImport {
+                        domain: String::new(),
+                        version: 0,
+                    };
+                    for field in Fields::new(b) {
+                        match field? {
+                            (1, Value::Bytes(b)) => opset.domain = String::from_utf8_lossy(b).into_owned(),
+                            (2, Value::Varint(v)) => opset.version = v as i64,
+                            _ => {}
+                        }
+                    }
+                    header.opsets.push(opset);
+                }
+                (14, Value::Bytes(b)) => {
+                    let (mut key, mut value) = (String::new(), String::new());
+                    for field in Fields::new(b) {
+                        match field? {
+                            (1, Value::Bytes(b)) => key = String::from_utf8_lossy(b).into_owned(),
+                            (2, Value::Bytes(b)) => value = String::from_utf8_lossy(b).into_owned(),
+                            _ => {}
+                        }
+                    }
+                    header.metadata.insert(key, value);
+                }
+                _ => {}
+            }
+        }
+        if header.ir_version.is_none() && header.opsets.is_empty() {
+            return Err("不是ONNX模型文件".into());
+        }
+        Ok(header)
+    }
+
+    /// 默认算子集 `ai.onnx` 的版本
+    pub fn opset(&self) -> Option<i64> {
+        self.opsets
+            .iter()
+            .find(|o| o.domain.is_empty() || o.domain == "ai.onnx")
+            .map(|o| o.version)
+    }
+}
+
+/// 模型的输入输出、opset与元数据
+#[derive(Debug, Clone)]
+pub struct ModelDescription {
+    /// 对应的模型，直接检查文件且元数据没有声明用途时为None
+    pub model: Option<Model>,
+    pub inputs: Vec<TensorInfo>,
+    pub outputs: Vec<TensorInfo>,
+    /// 实际加载的精度，使用自定义后端时为None
+    pub precision: Option<Precision>,
+    /// 模型文件头，加载器无法提供模型文件时为None
+    pub header: Option<OnnxHeader>,
+    pub metadata: EmbeddedMetadata,
+    /// 不能作为 `model` 使用的原因，与构建验证码实现时的检查相同
+    pub problem: Option<String>,
+}
+
+impl ModelDescription {
+    #[cfg(feature = "native")]
+    pub(crate) fn new(
+        model: Option<Model>,
+        backend: &dyn InferenceBackend,
+        precision: Option<Precision>,
+        header: Option<OnnxHeader>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let metadata = EmbeddedMetadata::from_backend(backend)?;
+        let model = model.or_else(|| metadata.model.as_deref().and_then(Model::from_name));
+        let problem = model.and_then(|model| {
+            model
+                .io_spec()
+                .check(backend)
+                .and_then(|_| metadata.check_compatible(model, backend))
+                .err()
+        });
+        Ok(ModelDescription {
+            model,
+            inputs: backend.inputs().to_vec(),
+            outputs: backend.outputs().to_vec(),
+            precision,
+            header,
+            metadata,
+            problem,
+        })
+    }
+
+    /// 直接检查一个模型文件，元数据声明了用途时按对应的模型检查兼容性
+    #[cfg(feature = "native")]
+    pub fn from_file(path: impl AsRef<std::path::Path>) -> Result<Self, Box<dyn Error>> {
+        crate::runtime::probe(None)?;
+        let bytes = std::fs::read(path)?;
+        let header = OnnxHeader::parse(&bytes)?;
+        let session = crate::loader::SessionOptions::default().builder()?.commit_from_memory(&bytes)?;
+        Self::new(None, &crate::backend::OrtBackend::new(session), None, Some(header))
+    }
+
+    pub fn opset(&self) -> Option<i64> {
+        self.header.as_ref().and_then(OnnxHeader::opset)
+    }
+}
+
+impl fmt::Display for ModelDescription {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.model, self.precision) {
+            (Some(model), Some(precision)) => writeln!(f, "{} ({})", model.name(), precision.name())?,
+            (Some(model), None) => writeln!(f, "{}", model.name())?,
+            (None, _) => writeln!(f, "未知模型")?,
+        }
+        if let Some(header) = &self.header {
+            let opsets: Vec<String> = header
+                .opsets
+                .iter()
+                .map(|o| format!("{} {}", if o.domain.is_empty() { "ai.onnx" } else { &o.domain }, o.version))
+                .collect();
+            writeln!(f, "  opset: {}", opsets.join(", "))?;
+            if let Some(name) = &header.producer_name {
+                writeln!(f, "  producer: {} {}", name, header.producer_version.as_deref().unwrap_or_default())?;
+            }
+        }
+        for input in &self.inputs {
+            writeln!(f, "  输入: {}", input)?;
+        }
+        for output in &self.outputs {
+            writeln!(f, "  输出: {}", output)?;
+        }
+        if !self.metadata.class_labels.is_empty() {
+            writeln!(f, "  类别: {}个", self.metadata.class_labels.len())?;
+        }
+        match &self.problem {
+            Some(problem) => write!(f, "  不兼容: {}", problem),
+            None => write!(f, "  兼容"),
+        }
+    }
+}
+
+enum Value<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+    Fixed,
+}
+
+/// protobuf的顶层字段，只解析需要的线路类型，其余跳过
+struct Fields<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Fields<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Fields { bytes }
+    }
+
+    fn varint(&mut self) -> Result<u64, Box<dyn Error>> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let (&byte, rest) = self.bytes.split_first().ok_or("模型文件已截断")?;
+            self.bytes = rest;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err("无效的varint".into())
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Box<dyn Error>> {
+        if len > self.bytes.len() {
+            return Err("模型文件已截断".into());
+        }
+        let (head, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(head)
+    }
+
+    fn field(&mut self) -> Result<(u64, Value<'a>), Box<dyn Error>> {
+        let key = self.varint()?;
+        let value = match key & 7 {
+            0 => Value::Varint(self.varint()?),
+            1 => self.take(8).map(|_| Value::Fixed)?,
+            2 => {
+                let len = self.varint()? as usize;
+                Value::Bytes(self.take(len)?)
+            }
+            5 => self.take(4).map(|_| Value::Fixed)?,
+            wire => return Err(format!("不支持的protobuf线路类型{}", wire).into()),
+        };
+        Ok((key >> 3, value))
+    }
+}
+
+impl<'a> Iterator for Fields<'a> {
+    type Item = Result<(u64, Value<'a>), Box<dyn Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.bytes.is_empty() {
+            return None;
+        }
+        let res = self.field();
+        if res.is_err() {
+            self.bytes = &[];
+        }
+        Some(res)
+    }
+}
@@ -0,0 +1,116 @@
+//! 语序点选使用的语言模型
+//!
+//! [`LanguageModel`] 给出下一个字在上下文之后出现的对数概率。内置的 [`NgramTable`] 从n-gram计数表读取，
+//! 按stupid backoff回退到更短的上下文；使用ONNX语言模型时可以用闭包实现该trait
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::Path;
+
+/// 回退到更短上下文时乘上的系数
+const BACKOFF: f32 = 0.4;
+
+pub trait LanguageModel {
+    /// 下一个字在context之后出现的对数概率(或可比较的分数)
+    fn log_prob(&self, context: &[char], next: char) -> f32;
+
+    /// 整段文字的分数
+    fn score(&self, text: &[char]) -> f32 {
+        (0..text.len()).map(|i| self.log_prob(&text[..i], text[i])).sum()
+    }
+}
+
+impl<F: Fn(&[char], char) -> f32> LanguageModel for F {
+    fn log_prob(&self, context: &[char], next: char) -> f32 {
+        self(context, next)
+    }
+}
+
+/// n-gram计数表
+///
+/// 文本格式为每行一个n-gram与出现次数，以空白分隔，例如 `一心\t1520`，空行与 `#` 开头的行会被忽略。
+/// 表中需要包含各阶的n-gram(包括单字)，最长的n-gram长度即为阶数
+#[derive(Debug, Clone, Default)]
+pub struct NgramTable {
+    counts: HashMap<String, u64>,
+    /// 单字出现次数的和
+    total: u64,
+    order: usize,
+}
+
+impl NgramTable {
+    pub fn new() -> Self {
+        NgramTable::default()
+    }
+
+    pub fn from_text(text: &str) -> Result<Self, Box<dyn Error>> {
+        let mut table = NgramTable::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (ngram, count) = line
+                .rsplit_once(char::is_whitespace)
+                .ok_or_else(|| format!("n-gram表第{}行缺少次数", i + 1))?;
+            let count = count
+                .parse()
+                .map_err(|_| format!("n-gram表第{}行的次数不是整数: {}", i + 1, count))?;
+            table.insert(ngram.trim(), count);
+        }
+        Ok(table)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        Self::from_text(&std::fs::read_to_string(path)?)
+    }
+
+    /// 累加一个n-gram的次数
+    pub fn insert(&mut self, ngram: &str, count: u64) {
+        let len = ngram.chars().count();
+        if len == 0 {
+            return;
+        }
+        if len == 1 {
+            self.total += count;
+        }
+        self.order = self.order.max(len);
+        *self.counts.entry(ngram.to_string()).or_default() += count;
+    }
+
+    pub fn count(&self, ngram: &str) -> u64 {
+        self.counts.get(ngram).copied().unwrap_or(0)
+    }
+
+    /// 最长的n-gram长度
+    pub fn order(&self) -> usize {
+        self.order
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+}
+
+impl LanguageModel for NgramTable {
+    /// stupid backoff：从最长的上下文开始，没有出现过时换成更短的上下文并乘上0.4，
+    /// 没有出现过的单字按出现半次计算
+    fn log_prob(&self, context: &[char], next: char) -> f32 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        let longest = context.len().min(self.order.saturating_sub(1));
+        let mut penalty = 0.0;
+        for k in (1..=longest).rev() {
+            let history: String = context[context.len() - k..].iter().collect();
+            let denominator = self.count(&history);
+            let numerator = self.count(&format!("{}{}", history, next));
+            if numerator > 0 && denominator > 0 {
+                return penalty + (numerator as f32 / denominator as f32).ln();
+            }
+            penalty += BACKOFF.ln();
+        }
+        let unigram = (self.count(&next.to_string()) as f32).max(0.5);
+        penalty + (unigram / self.total as f32).ln()
+    }
+}
@@ -0,0 +1,207 @@
+use super::lapjv::{ErrorKind, LapJVCost, LapJVError, Matrix, check_costs};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use std::collections::VecDeque;
+
+const UNASSIGNED: usize = usize::MAX;
+
+#[derive(Debug, Clone)]
+pub struct AuctionOptions {
+    /// Factor by which epsilon shrinks between scaling phases
+    pub scaling: f64,
+    /// Final bid increment. Defaults to `1 / (n + 1)` for integral costs, which
+    /// guarantees an optimal assignment, and to a small relative tolerance otherwise.
+    pub epsilon_final: Option<f64>,
+    /// Upper bound on bidding rounds before giving up
+    pub max_iterations: usize,
+}
+
+impl Default for AuctionOptions {
+    fn default() -> Self {
+        AuctionOptions {
+            scaling: 4.0,
+            epsilon_final: None,
+            max_iterations: 10_000_000,
+        }
+    }
+}
+
+/// Solve LAP problem given cost matrix with the auction algorithm
+///
+/// This is the Jacobi variant with epsilon scaling described in:
+/// D. P. Bertsekas. The Auction Algorithm: A Distributed Relaxation Method for
+/// the Assignment Problem. Annals of Operations Research 14, 105-123 (1988).
+/// With the `parallel` feature the bids of all unassigned rows are computed in
+/// parallel, which pays off on large matrices.
+pub fn auction<T>(costs: &Matrix<T>) -> Result<(Vec<usize>, Vec<usize>), LapJVError>
+where
+    T: LapJVCost,
+{
+    auction_with_options(costs, &AuctionOptions::default())
+}
+
+pub fn auction_with_options<T>(costs: &Matrix<T>, options: &AuctionOptions) -> Result<(Vec<usize>, Vec<usize>), LapJVError>
+where
+    T: LapJVCost,
+{
+    let (dim, cols) = costs.dim();
+    if dim != cols {
+        return Err(LapJVError::new(ErrorKind::Msg("Input error: matrix is not square")));
+    }
+    check_costs(costs)?;
+
+    // the auction maximizes benefit, forbidden cells have no benefit at all
+    let mut benefit = Vec::with_capacity(dim * dim);
+    for &c in costs.iter() {
+        let b = if c.is_forbidden() { None } else { c.to_f64().map(|c| -c) };
+        benefit.push(b.unwrap_or(f64::NEG_INFINITY));
+    }
+    // without a perfect matching on the allowed cells (e.g. two rows that only
+    // allow the same column) the rows would keep outbidding each other until
+    // `max_iterations`
+    if !has_perfect_matching(dim, |i, j| benefit[i * dim + j] != f64::NEG_INFINITY) {
+        return Err(LapJVError::new(ErrorKind::Infeasible));
+    }
+    let mut finite = benefit.iter().filter(|b| b.is_finite());
+    let max_abs = finite.clone().fold(0.0f64, |acc, b| acc.max(b.abs()));
+    let integral = finite.all(|b| b.fract() == 0.0);
+    let eps_final = options.epsilon_final.unwrap_or(if integral {
+        1.0 / (dim + 1) as f64
+    } else {
+        max_abs.max(1.0) * 1e-9
+    });
+    // increment used when a row has a single allowed column
+    let no_second = 2.0 * max_abs + 1.0;
+
+    let mut prices = vec![0.0f64; dim];
+    let mut eps = (max_abs / 2.0).max(eps_final);
+    let mut iterations = 0;
+    loop {
+        let mut in_row = vec![UNASSIGNED; dim];
+        let mut in_col = vec![UNASSIGNED; dim];
+        loop {
+            let unassigned: Vec<usize> = (0..dim).filter(|&i| in_row[i] == UNASSIGNED).collect();
+            if unassigned.is_empty() {
+                break;
+            }
+            iterations += 1;
+            if iterations > options.max_iterations {
+                return Err(LapJVError::new(ErrorKind::Msg("Error: auction did not converge")));
+            }
+
+            let make_bid = |&i: &usize| bid(&benefit[i * dim..(i + 1) * dim], &prices, eps, no_second).map(|(j, b)| (i, j, b));
+            #[cfg(feature = "parallel")]
+            let bids: Vec<Option<(usize, usize, f64)>> = unassigned.par_iter().map(make_bid).collect();
+            #[cfg(not(feature = "parallel"))]
+            let bids: Vec<Option<(usize, usize, f64)>> = unassigned.iter().map(make_bid).collect();
+
+            // each column goes to its highest bidder
+            let mut best: Vec<Option<(usize, f64)>> = vec![None; dim];
+            for entry in bids {
+                let (i, j, amount) = entry.ok_or(LapJVError::new(ErrorKind::Infeasible))?;
+                if best[j].is_none_or(|(_, current)| amount > current) {
+                    best[j] = Some((i, amount));
+                }
+            }
+            for (j, winner) in best.into_iter().enumerate() {
+                if let Some((i, amount)) = winner {
+                    if in_col[j] != UNASSIGNED {
+                        in_row[in_col[j]] = UNASSIGNED;
+                    }
+                    in_col[j] = i;
+                    in_row[i] = j;
+                    prices[j] = amount;
+                }
+            }
+        }
+
+        if eps <= eps_final {
+            if in_row.iter().enumerate().any(|(i, &j)| benefit[i * dim + j] == f64::NEG_INFINITY) {
+                return Err(LapJVError::new(ErrorKind::Infeasible));
+            }
+            return Ok((in_row, in_col));
+        }
+        eps = (eps / options.scaling).max(eps_final);
+    }
+}
+
+/// Whether the allowed cells of a square matrix admit a perfect matching,
+/// checked with Hopcroft–Karp in `O(E sqrt(V))`
+fn has_perfect_matching(dim: usize, allowed: impl Fn(usize, usize) -> bool) -> bool {
+    let edges: Vec<Vec<usize>> = (0..dim).map(|i| (0..dim).filter(|&j| allowed(i, j)).collect()).collect();
+    let mut in_row = vec![UNASSIGNED; dim];
+    let mut in_col = vec![UNASSIGNED; dim];
+    let mut layer = vec![usize::MAX; dim];
+    let mut matched = 0;
+    loop {
+        // layer the rows by their distance from a free row along alternating paths
+        let mut queue = VecDeque::new();
+        for (i, (&j, l)) in in_row.iter().zip(layer.iter_mut()).enumerate() {
+            *l = if j == UNASSIGNED { 0 } else { usize::MAX };
+            if j == UNASSIGNED {
+                queue.push_back(i);
+            }
+        }
+        let mut reachable = false;
+        while let Some(i) = queue.pop_front() {
+            for &j in &edges[i] {
+                match in_col[j] {
+                    UNASSIGNED => reachable = true,
+                    k if layer[k] == usize::MAX => {
+                        layer[k] = layer[i] + 1;
+                        queue.push_back(k);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        if !reachable {
+            return matched == dim;
+        }
+        for i in 0..dim {
+            if in_row[i] == UNASSIGNED && augment(i, &edges, &mut in_row, &mut in_col, &mut layer) {
+                matched += 1;
+            }
+        }
+    }
+}
+
+/// Augments along the layered graph from the free row `i`
+fn augment(i: usize, edges: &[Vec<usize>], in_row: &mut [usize], in_col: &mut [usize], layer: &mut [usize]) -> bool {
+    for &j in &edges[i] {
+        let k = in_col[j];
+        if k == UNASSIGNED || (layer[k] == layer[i] + 1 && augment(k, edges, in_row, in_col, layer)) {
+            in_row[i] = j;
+            in_col[j] = i;
+            return true;
+        }
+    }
+    // dead end, skip this row for the rest of the phase
+    layer[i] = usize::MAX;
+    false
+}
+
+/// Column and bid amount of a single row, `None` when every column is forbidden
+fn bid(benefit: &[f64], prices: &[f64], eps: f64, no_second: f64) -> Option<(usize, f64)> {
+    let mut best = f64::NEG_INFINITY;
+    let mut second = f64::NEG_INFINITY;
+    let mut best_j = UNASSIGNED;
+    for (j, (&b, &p)) in benefit.iter().zip(prices).enumerate() {
+        if b == f64::NEG_INFINITY {
+            continue;
+        }
+        let value = b - p;
+        if value > best {
+            second = best;
+            best = value;
+            best_j = j;
+        } else if value > second {
+            second = value;
+        }
+    }
+    if best_j == UNASSIGNED {
+        return None;
+    }
+    let gap = if second == f64::NEG_INFINITY { no_second } else { best - second };
+    Some((best_j, prices[best_j] + gap + eps))
+}
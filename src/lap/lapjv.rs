@@ -0,0 +1,787 @@
+use num_traits::{Bounded, Num, NumCast};
+use std::fmt;
+use std::ops;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+#[cfg(not(feature = "tracing"))]
+use log::trace;
+#[cfg(feature = "tracing")]
+use tracing::trace;
+
+pub type Matrix<T> = ndarray::Array2<T>;
+
+/// Smallest number of columns for which the `parallel` feature splits the
+/// reductions and scans across threads, below it the overhead dominates
+#[cfg(feature = "parallel")]
+const PARALLEL_MIN_DIM: usize = 256;
+
+/// Cost types accepted by the solver
+///
+/// Floating point costs compare reduced costs with an epsilon tolerance, signed
+/// integer costs use exact arithmetic. Unsigned integers are not supported since
+/// the dual variables may become negative.
+pub trait LapJVCost:
+    Num + Bounded + NumCast + PartialOrd + Copy + ops::AddAssign + ops::SubAssign + std::fmt::Debug + Send + Sync
+{
+    /// Whether two reduced costs are considered equal
+    fn approx_eq(self, other: Self) -> bool;
+
+    /// Whether this cost marks a cell that may never be assigned (positive infinity)
+    fn is_forbidden(self) -> bool {
+        false
+    }
+
+    /// Whether this cost cannot be solved for (NaN or negative infinity)
+    fn is_invalid(self) -> bool {
+        false
+    }
+
+    /// Machine epsilon, zero for integer costs which compare exactly
+    fn machine_epsilon() -> Self {
+        Self::zero()
+    }
+
+    fn abs_value(self) -> Self {
+        if self < Self::zero() { Self::zero() - self } else { self }
+    }
+}
+
+macro_rules! impl_float_cost {
+    ($($t:ty),*) => {$(
+        impl LapJVCost for $t {
+            #[inline(always)]
+            fn approx_eq(self, other: Self) -> bool {
+                (self - other).abs() < <$t>::EPSILON
+            }
+
+            #[inline(always)]
+            fn is_forbidden(self) -> bool {
+                self == <$t>::INFINITY
+            }
+
+            #[inline(always)]
+            fn is_invalid(self) -> bool {
+                self.is_nan() || self == <$t>::NEG_INFINITY
+            }
+
+            #[inline(always)]
+            fn machine_epsilon() -> Self {
+                <$t>::EPSILON
+            }
+        }
+    )*};
+}
+
+macro_rules! impl_int_cost {
+    ($($t:ty),*) => {$(
+        impl LapJVCost for $t {
+            #[inline(always)]
+            fn approx_eq(self, other: Self) -> bool {
+                self == other
+            }
+        }
+    )*};
+}
+
+impl_float_cost!(f32, f64);
+impl_int_cost!(i16, i32, i64, i128, isize);
+
+/// Numerical options of [`lapjv_with_options`]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct LapJVOptions {
+    /// Absolute tolerance under which two reduced costs are considered equal,
+    /// defaults to the machine epsilon of the cost type
+    pub absolute_tolerance: Option<f64>,
+    /// Tolerance relative to the largest absolute finite cost, added to the
+    /// absolute tolerance. Float costs of large magnitude need this since their
+    /// rounding error grows with the costs.
+    pub relative_tolerance: f64,
+    /// Solve in f64 whatever the cost type, for numerically hard f32 matrices
+    pub promote_to_f64: bool,
+}
+
+impl Default for LapJVOptions {
+    fn default() -> Self {
+        LapJVOptions {
+            absolute_tolerance: None,
+            relative_tolerance: 0.0,
+            promote_to_f64: false,
+        }
+    }
+}
+
+impl LapJVOptions {
+    /// Tolerance for comparing reduced costs of `costs`, always zero for integer costs
+    pub fn tolerance<T>(&self, costs: &Matrix<T>) -> T
+    where
+        T: LapJVCost,
+    {
+        let epsilon = T::machine_epsilon();
+        if epsilon == T::zero() {
+            return epsilon;
+        }
+        let absolute = self.absolute_tolerance.or(epsilon.to_f64()).unwrap_or(0.0);
+        let scale = costs
+            .iter()
+            .filter(|c| !c.is_forbidden())
+            .filter_map(|c| c.abs_value().to_f64())
+            .fold(0.0, f64::max);
+        T::from(absolute + self.relative_tolerance * scale).unwrap_or(epsilon)
+    }
+}
+
+/// Assignment together with its total cost and the final dual potentials
+#[derive(Debug, Clone, PartialEq)]
+pub struct Solution<T> {
+    /// Column assigned to each row
+    pub in_row: Vec<usize>,
+    /// Row assigned to each column
+    pub in_col: Vec<usize>,
+    /// Total cost of the assignment
+    pub cost: T,
+    /// Row potentials, `u[i] = costs[(i, in_row[i])] - v[in_row[i]]`
+    pub u: Vec<T>,
+    /// Column potentials, `costs[(i, j)] - u[i] - v[j]` is non-negative up to the tolerance
+    pub v: Vec<T>,
+}
+
+impl<T> Solution<T>
+where
+    T: LapJVCost,
+{
+    fn new(costs: &Matrix<T>, in_row: Vec<usize>, in_col: Vec<usize>, v: Vec<T>) -> Self {
+        let u: Vec<T> = in_row.iter().enumerate().map(|(i, &j)| costs[(i, j)] - v[j]).collect();
+        Solution {
+            cost: cost(costs, &in_row),
+            in_row,
+            in_col,
+            u,
+            v,
+        }
+    }
+
+    /// Reduced cost of cell `(i, j)`: zero for assigned cells, otherwise how much
+    /// cheaper the cell would have to become before it could enter an optimal
+    /// assignment. Small values mean the matching is sensitive to that cell.
+    pub fn reduced_cost(&self, costs: &Matrix<T>, i: usize, j: usize) -> T {
+        costs[(i, j)] - self.u[i] - self.v[j]
+    }
+
+    /// Convert the cost type, e.g. back from a solve promoted to f64
+    pub fn cast<U>(&self) -> Option<Solution<U>>
+    where
+        U: LapJVCost,
+    {
+        let cast_all = |values: &[T]| values.iter().map(|&x| U::from(x)).collect::<Option<Vec<U>>>();
+        Some(Solution {
+            in_row: self.in_row.clone(),
+            in_col: self.in_col.clone(),
+            cost: U::from(self.cost)?,
+            u: cast_all(&self.u)?,
+            v: cast_all(&self.v)?,
+        })
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub enum ErrorKind {
+    Msg(&'static str),
+    Cancelled,
+    /// Every assignment uses a masked (forbidden) cell
+    Infeasible,
+    /// The cost at `(row, col)` is NaN or negative infinity
+    InvalidCost { row: usize, col: usize },
+}
+
+
+#[derive(Debug)]
+pub struct LapJVError {
+    kind: ErrorKind,
+}
+
+impl LapJVError {
+    pub(crate) fn new(kind: ErrorKind) -> Self {
+        LapJVError { kind }
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+}
+
+impl std::fmt::Display for LapJVError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self.kind {
+            ErrorKind::Msg(string) => write!(f, "{}", string),
+            ErrorKind::Cancelled => write!(f, "cancelled"),
+            ErrorKind::Infeasible => write!(f, "no feasible assignment"),
+            ErrorKind::InvalidCost { row, col } => write!(f, "invalid cost at ({}, {})", row, col),
+        }
+    }
+}
+
+impl std::error::Error for LapJVError {}
+
+pub struct LapJV<'a, T: 'a> {
+    costs: &'a Matrix<T>,
+    dim: usize,
+    free_rows: Vec<usize>,
+    v: Vec<T>,
+    in_col: Vec<usize>,
+    in_row: Vec<usize>,
+    tolerance: T,
+    warm_start: Option<(Vec<usize>, Vec<T>)>,
+    cancellation: Cancellation,
+}
+
+/// Solve LAP problem given cost matrix
+/// This is an implementation of the LAPJV algorithm described in:
+/// R. Jonker, A. Volgenant. A Shortest Augmenting Path Algorithm for
+/// Dense and Sparse Linear Assignment Problems. Computing 38, 325-340
+/// (1987)
+///
+/// Cells with a cost of positive infinity are never assigned, see
+/// [`lapjv_masked`](super::lapjv_masked). NaN and negative infinity are
+/// rejected with `ErrorKind::InvalidCost`.
+pub fn lapjv<T>(costs: &Matrix<T>) -> Result<(Vec<usize>, Vec<usize>), LapJVError>
+where
+    T: LapJVCost,
+{
+    lapjv_with_options(costs, &LapJVOptions::default())
+}
+
+/// [`lapjv`] with the tolerance and precision given by `options`
+pub fn lapjv_with_options<T>(costs: &Matrix<T>, options: &LapJVOptions) -> Result<(Vec<usize>, Vec<usize>), LapJVError>
+where
+    T: LapJVCost,
+{
+    lapjv_solution(costs, options).map(|s| (s.in_row, s.in_col))
+}
+
+/// [`lapjv_with_options`] returning the total cost and the dual potentials as well
+///
+/// With forbidden cells the potentials belong to the matrix in which those
+/// cells are replaced by a large finite cost, see [`lapjv_masked`](super::lapjv_masked).
+pub fn lapjv_solution<T>(costs: &Matrix<T>, options: &LapJVOptions) -> Result<Solution<T>, LapJVError>
+where
+    T: LapJVCost,
+{
+    check_costs(costs)?;
+    if options.promote_to_f64 {
+        let promoted = costs.mapv(|c| c.to_f64().unwrap_or(f64::NAN));
+        let options = LapJVOptions { promote_to_f64: false, ..*options };
+        return lapjv_solution(&promoted, &options)?
+            .cast()
+            .ok_or(LapJVError { kind: ErrorKind::Msg("Error: potentials not representable in the cost type") });
+    }
+    if costs.iter().any(|&c| c.is_forbidden()) {
+        return super::masked::masked_with_options(costs, None, options);
+    }
+    LapJV::new(costs).with_tolerance(options.tolerance(costs)).solve_full()
+}
+
+/// [`lapjv_solution`] starting from a previous solution of a matrix of the same size,
+/// see [`LapJV::with_warm_start`]
+///
+/// Matrices with forbidden cells are solved from scratch.
+pub fn lapjv_warm<T>(costs: &Matrix<T>, previous: &Solution<T>, options: &LapJVOptions) -> Result<Solution<T>, LapJVError>
+where
+    T: LapJVCost,
+{
+    check_costs(costs)?;
+    if options.promote_to_f64 || costs.iter().any(|&c| c.is_forbidden()) {
+        return lapjv_solution(costs, options);
+    }
+    LapJV::new(costs)
+        .with_tolerance(options.tolerance(costs))
+        .with_warm_start(previous)
+        .solve_full()
+}
+
+/// Reject NaN and negative infinity, which break the comparisons of every solver
+pub(crate) fn check_costs<T>(costs: &Matrix<T>) -> Result<(), LapJVError>
+where
+    T: LapJVCost,
+{
+    match costs.indexed_iter().find(|(_, c)| c.is_invalid()) {
+        Some(((row, col), _)) => Err(LapJVError { kind: ErrorKind::InvalidCost { row, col } }),
+        None => Ok(()),
+    }
+}
+
+/// Calculate solution cost by a result row
+pub fn cost<T>(input: &Matrix<T>, row: &[usize]) -> T
+where
+    T: LapJVCost,
+{
+    (0..row.len())
+        .fold(T::zero(), |acc, i| acc + input[(i, row[i])])
+}
+
+#[derive(Clone)]
+pub struct Cancellation(Arc<AtomicBool>);
+
+impl Cancellation {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst)
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// Solve LAP problem given cost matrix
+/// This is an implementation of the LAPJV algorithm described in:
+/// R. Jonker, A. Volgenant. A Shortest Augmenting Path Algorithm for
+/// Dense and Sparse Linear Assignment Problems. Computing 38, 325-340
+/// (1987)
+impl<'a, T> LapJV<'a, T>
+where
+    T: LapJVCost,
+{
+    pub fn new(costs: &'a Matrix<T>) -> Self {
+        let dim = costs.dim().0; // square matrix dimensions
+        let free_rows = Vec::with_capacity(dim); // list of unassigned rows.
+        let v = Vec::with_capacity(dim);
+        let in_row = vec![0; dim];
+        let in_col = Vec::with_capacity(dim);
+        let cancellation = Cancellation(Default::default());
+        Self {
+            costs,
+            dim,
+            free_rows,
+            v,
+            in_col,
+            in_row,
+            tolerance: T::machine_epsilon(),
+            warm_start: None,
+            cancellation
+        }
+    }
+
+    /// Start from the assignment and column potentials of a previous solve
+    /// instead of the column reduction, which saves most of the work when the
+    /// matrix only changed slightly, e.g. between frames of an animated captcha
+    ///
+    /// Rows whose previous column is no longer of minimal reduced cost are
+    /// reassigned, so the result is optimal however much the matrix changed.
+    pub fn with_warm_start(mut self, previous: &Solution<T>) -> Self {
+        self.warm_start = Some((previous.in_row.clone(), previous.v.clone()));
+        self
+    }
+
+    /// Reduced costs closer than `tolerance` are considered equal, see [`LapJVOptions::tolerance`]
+    pub fn with_tolerance(mut self, tolerance: T) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Returns a `Cancellation` token which can be cancelled from another thread.
+    pub fn cancellation(&self) -> Cancellation {
+        self.cancellation.clone()
+    }
+
+    fn check_cancelled(&self) -> Result<(), LapJVError> {
+        if self.cancellation.is_cancelled() {
+            return Err(LapJVError { kind: ErrorKind::Cancelled });
+        }
+        Ok(())
+    }
+
+    pub fn solve(self) -> Result<(Vec<usize>, Vec<usize>), LapJVError> {
+        self.solve_full().map(|s| (s.in_row, s.in_col))
+    }
+
+    /// Like [`solve`](Self::solve), also returning the total cost and the dual potentials
+    pub fn solve_full(mut self) -> Result<Solution<T>, LapJVError> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("lapjv", dim = self.dim).entered();
+        if self.costs.dim().0 != self.costs.dim().1 {
+            return Err(LapJVError { kind: ErrorKind::Msg("Input error: matrix is not square") } );
+        }
+        check_costs(self.costs)?;
+        if self.dim == 1 {
+            // the reductions below start from T::max_value(), which would overflow integer costs
+            return Ok(Solution::new(self.costs, vec![0], vec![0], vec![self.cost(0, 0)]));
+        }
+        match self.warm_start.take() {
+            Some((previous, v)) => self.warm_start_dense(previous, v)?,
+            None => self.ccrrt_dense(),
+        }
+
+        let mut i = 0;
+        while !self.free_rows.is_empty() && i < 2 {
+            self.check_cancelled()?;
+            self.carr_dense();
+            i += 1;
+        }
+
+        if !self.free_rows.is_empty() {
+            self.ca_dense()?;
+        }
+
+        Ok(Solution::new(self.costs, self.in_row, self.in_col, self.v))
+    }
+
+    // Column-reduction and reduction transfer for a dense cost matrix
+    fn ccrrt_dense(&mut self) {
+        let mut unique = vec![true; self.dim];
+        let mut in_row_not_set = vec![true; self.dim];
+
+        let column_min = |column: ndarray::ArrayView1<T>| {
+            column.indexed_iter().skip(1).fold(
+                (0, column[0]),
+                |(old_idx, old_min), (new_idx, &new_min)| {
+                    if new_min < old_min {
+                        (new_idx, new_min)
+                    } else {
+                        (old_idx, old_min)
+                    }
+                },
+            )
+        };
+        #[cfg(feature = "parallel")]
+        let minima: Vec<(usize, T)> = if self.dim >= PARALLEL_MIN_DIM {
+            (0..self.dim).into_par_iter().map(|j| column_min(self.costs.column(j))).collect()
+        } else {
+            self.costs.columns().into_iter().map(column_min).collect()
+        };
+        #[cfg(not(feature = "parallel"))]
+        let minima: Vec<(usize, T)> = self.costs.columns().into_iter().map(column_min).collect();
+        for (min_index, min_value) in minima {
+            self.in_col.push(min_index);
+            self.v.push(min_value);
+        }
+
+        for j in (0..self.dim).rev() {
+            let i = self.in_col[j];
+            if in_row_not_set[i] {
+                self.in_row[i] = j;
+                in_row_not_set[i] = false;
+            } else {
+                unique[i] = false;
+                self.in_col[j] = usize::MAX;
+            }
+        }
+
+        self.free_rows.extend((0..self.dim).filter(|&i| in_row_not_set[i]));
+        let transfer_rows: Vec<usize> = (0..self.dim).filter(|&i| !in_row_not_set[i] && unique[i]).collect();
+        // the parallel variant computes every transfer from the column reduction,
+        // which lowers the potentials less but keeps them feasible
+        #[cfg(feature = "parallel")]
+        if self.dim >= PARALLEL_MIN_DIM {
+            let transfers: Vec<T> = transfer_rows.par_iter().map(|&i| self.transfer_min(i)).collect();
+            for (&i, min) in transfer_rows.iter().zip(transfers) {
+                let j = self.in_row[i];
+                self.v[j] -= min;
+            }
+            return;
+        }
+        for i in transfer_rows {
+            let min = self.transfer_min(i);
+            let j = self.in_row[i];
+            self.v[j] -= min;
+        }
+    }
+
+    // Minimum reduced cost of a uniquely assigned row over the other columns
+    fn transfer_min(&self, i: usize) -> T {
+        let j = self.in_row[i];
+        let mut min = T::max_value();
+        for j2 in 0..self.dim {
+            if j2 == j {
+                continue;
+            }
+            let c = self.reduced_cost(i, j2);
+            if c < min {
+                min = c;
+            }
+        }
+        min
+    }
+
+    // Initialization from a previous solution: keep the pairs that still satisfy
+    // complementary slackness under the previous column potentials
+    fn warm_start_dense(&mut self, previous: Vec<usize>, v: Vec<T>) -> Result<(), LapJVError> {
+        if previous.len() != self.dim || v.len() != self.dim {
+            return Err(LapJVError { kind: ErrorKind::Msg("Input error: warm start dimensions differ") });
+        }
+        self.v = v;
+        self.in_col = vec![usize::MAX; self.dim];
+        for (i, &j) in previous.iter().enumerate() {
+            let row_min = (0..self.dim).map(|k| self.reduced_cost(i, k)).fold(T::max_value(), |a, b| if b < a { b } else { a });
+            let keep = j < self.dim && self.in_col[j] == usize::MAX && self.reduced_cost(i, j) - row_min <= self.tolerance;
+            if keep {
+                self.in_row[i] = j;
+                self.in_col[j] = i;
+            } else {
+                self.free_rows.push(i);
+            }
+        }
+        trace!("warm start keeps {} of {} rows", self.dim - self.free_rows.len(), self.dim);
+        Ok(())
+    }
+
+    // Augmenting row reduction for a dense cost matrix
+    fn carr_dense(&mut self) {
+        // AUGMENTING ROW REDUCTION
+        // scan all free rows.
+        // in some cases, a free row may be replaced with another one to be scanned next.
+        trace!("carr_dense");
+        let dim = self.dim;
+        let mut current = 0;
+        let mut new_free_rows = 0; // start list of rows still free after augmenting row reduction.
+        let mut rr_cnt = 0;
+        let num_free_rows = self.free_rows.len();
+
+        while current < num_free_rows {
+            rr_cnt += 1;
+            let free_i = self.free_rows[current];
+            current += 1;
+            // find minimum and second minimum reduced cost over columns.
+            let (v1, v2, mut j1, j2) = find_umins_plain(self.costs.row(free_i), &self.v);
+
+            let mut i0 = self.in_col[j1];
+            let v1_new = self.v[j1] - (v2 - v1);
+            let v1_lowers = v1_new < self.v[j1]; // the trick to eliminate the epsilon bug
+
+            if rr_cnt < current * dim {
+                if v1_lowers {
+                    // change the reduction of the minimum column to increase the minimum
+                    // reduced cost in the row to the subminimum.
+                    self.v[j1] = v1_new;
+                } else if i0 != usize::MAX && j2.is_some() {
+                    // minimum and subminimum equal.
+                    // minimum column j1 is assigned.
+                    // swap columns j1 and j2, as j2 may be unassigned.
+                    j1 = j2.unwrap();
+                    i0 = self.in_col[j1];
+                }
+                if i0 != usize::MAX {
+                    // minimum column j1 assigned earlier.
+                    if v1_lowers {
+                        // put in current k, and go back to that k.
+                        // continue augmenting path i - j1 with i0.
+                        current -= 1;
+                        self.free_rows[current] = i0;
+                    } else {
+                        // no further augmenting reduction possible.
+                        // store i0 in list of free rows for next phase.
+                        self.free_rows[new_free_rows] = i0;
+                        new_free_rows += 1;
+                    }
+                }
+            } else if i0 != usize::MAX {
+                self.free_rows[new_free_rows] = i0;
+                new_free_rows += 1;
+            }
+            self.in_row[free_i] = j1;
+            self.in_col[j1] = free_i;
+        }
+        self.free_rows.truncate(new_free_rows);
+    }
+
+    // Augment for a dense cost matrix
+    fn ca_dense(&mut self) -> Result<(), LapJVError> {
+        let dim = self.dim;
+        let mut pred = vec![0; dim];
+
+        let free_rows = std::mem::take(&mut self.free_rows);
+        for freerow in free_rows {
+            trace!("looking at freerow={}", freerow);
+
+            self.check_cancelled()?;
+
+            let mut i = usize::MAX;
+            let mut k = 0;
+            let mut j = self.find_path_dense(freerow, &mut pred)?;
+            debug_assert!(j < dim);
+            while i != freerow {
+                i = pred[j];
+                self.in_col[j] = i;
+                std::mem::swap(&mut j, &mut self.in_row[i]);
+                k += 1;
+                if k > dim {
+                    return Err(LapJVError { kind: ErrorKind::Msg("Error: ca_dense will not finish") });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Single iteration of modified Dijkstra shortest path algorithm as explained in the JV paper
+    /// return The closest free column index
+    fn find_path_dense(&mut self, start_i: usize, pred: &mut [usize]) -> Result<usize, LapJVError> {
+        let dim = self.dim;
+        let mut collist = Vec::with_capacity(dim); // list of columns to be scanned in various ways.
+        let mut d = Vec::with_capacity(dim); // 'cost-distance' in augmenting path calculation.
+
+        let mut lo = 0;
+        let mut hi = 0;
+        let mut n_ready = 0;
+
+        // Dijkstra shortest path algorithm.
+        // runs until unassigned column added to shortest path tree.
+        collist.extend(0..dim);
+        pred[..dim].fill(start_i);
+        #[cfg(feature = "parallel")]
+        if dim >= PARALLEL_MIN_DIM {
+            d.par_extend((0..dim).into_par_iter().map(|j| self.reduced_cost(start_i, j)));
+        }
+        if d.is_empty() {
+            d.extend((0..dim).map(|j| self.reduced_cost(start_i, j)));
+        }
+
+        trace!("d: {:?}", d);
+        let mut final_j = None;
+        while final_j.is_none() {
+            if lo == hi {
+                if lo == dim {
+                    // every column was scanned without reaching a free one, only
+                    // possible when NaN costs break the comparisons
+                    return Err(LapJVError { kind: ErrorKind::Msg("Error: find_path_dense found no free column") });
+                }
+                trace!("{}..{} -> find", lo, hi);
+                n_ready = lo;
+                hi = find_dense(dim, lo, &d, &mut collist);
+                trace!("check {}..{}", lo, hi);
+                // check if any of the minimum columns happens to be unassigned.
+                // if so, we have an augmenting path right away.
+                for &j in collist.iter().take(hi).skip(lo) {
+                    if self.in_col[j] == usize::MAX {
+                        final_j = Some(j);
+                    }
+                }
+            }
+
+            if final_j.is_none() {
+                trace!("{}..{} -> scan", lo, hi);
+                final_j = self.scan_dense(&mut lo, &mut hi, &mut d, &mut collist, pred);
+            }
+        }
+
+        trace!("found final_j={:?}", final_j);
+        trace!("cols={:?}", collist);
+        let mind = d[collist[lo]];
+        for &j in collist.iter().take(n_ready) {
+            self.v[j] += d[j] - mind;
+        }
+        Ok(final_j.unwrap())
+    }
+
+
+    fn scan_dense(
+        &self,
+        plo: &mut usize,
+        phi: &mut usize,
+        d: &mut [T],
+        collist: &mut [usize],
+        pred: &mut [usize],
+    ) -> Option<usize> {
+        let mut lo = *plo;
+        let mut hi = *phi;
+        while lo != hi {
+            let j = collist[lo];
+            lo += 1;
+            let i = self.in_col[j];
+            let mind = d[j];
+            let h = self.reduced_cost(i, j) - mind;
+            // columns before k are only ever swapped among themselves, so the
+            // column at position k is still the one it was when the scan started
+            let start = hi;
+            #[cfg(feature = "parallel")]
+            let precomputed: Option<Vec<T>> = (collist.len() - start >= PARALLEL_MIN_DIM)
+                .then(|| collist[start..].par_iter().map(|&j| self.reduced_cost(i, j) - h).collect());
+            #[cfg(not(feature = "parallel"))]
+            let precomputed: Option<Vec<T>> = None;
+            for k in start..collist.len() {
+                let j = collist[k];
+                let cred_ij = match &precomputed {
+                    Some(creds) => creds[k - start],
+                    None => self.reduced_cost(i, j) - h,
+                };
+                if cred_ij < d[j] {
+                    d[j] = cred_ij;
+                    pred[j] = i;
+                    if (cred_ij - mind).abs_value() <= self.tolerance {
+                        if self.in_col[j] == usize::MAX {
+                            return Some(j);
+                        }
+                        collist[k] = collist[hi];
+                        collist[hi] = j;
+                        hi += 1;
+                    }
+                }
+            }
+        }
+        // Note: only change lo and hi if the item was not found
+        *plo = lo;
+        *phi = hi;
+        None
+    }
+
+    #[inline(always)]
+    fn cost(&self, i: usize, j: usize) -> T {
+        self.costs[(i, j)]
+    }
+
+    #[inline(always)]
+    fn reduced_cost(&self, i: usize, j: usize) -> T {
+        self.cost(i, j) - self.v[j]
+    }
+}
+
+fn find_dense<T>(dim: usize, lo: usize, d: &[T], collist: &mut [usize]) -> usize
+where
+    T: LapJVCost,
+{
+    let start = lo + 1;
+    let mut hi = start;
+    let mut mind = d[collist[lo]];
+    for k in start..dim {
+        let j = collist[k];
+        let h = d[j];
+        if h <= mind {
+            if h < mind {
+                // new minimum.
+                hi = lo; // restart list at index low.
+                mind = h;
+            }
+            // new index with same minimum, put on undex up, and extend list.
+            collist[k] = collist[hi];
+            collist[hi] = j;
+            hi += 1;
+        }
+    }
+    hi
+}
+
+// Finds minimum and second minimum from a row, returns (min, second_min, min_index, second_min_index)
+#[inline(always)]
+fn find_umins_plain<T>(local_cost: ndarray::ArrayView1<T>, v: &[T]) -> (T, T, usize, Option<usize>)
+where
+    T: LapJVCost,
+{
+    let mut umin = local_cost[0] - v[0];
+    let mut usubmin = T::max_value();
+    let mut j1 = 0;
+    let mut j2 = None;
+    for j in 1..local_cost.dim() {
+        let h = local_cost[j] - v[j];
+        if h < usubmin {
+            if h >= umin {
+                usubmin = h;
+                j2 = Some(j);
+            } else {
+                usubmin = umin;
+                umin = h;
+                j2 = Some(j1);
+                j1 = j;
+            }
+        }
+    }
+    (umin, usubmin, j1, j2)
+}
\ No newline at end of file
@@ -0,0 +1,58 @@
+use super::lapjv::{ErrorKind, LapJV, LapJVCost, LapJVError, LapJVOptions, Matrix, Solution};
+
+/// Solve LAP problem where some cells may not be assigned
+///
+/// A cell `(i, j)` is forbidden when `mask[(i, j)]` is `false` or its cost is
+/// positive infinity. Forbidden cells are replaced by a finite cost larger than
+/// any feasible assignment, so the solver never selects them unless no feasible
+/// assignment exists, in which case `ErrorKind::Infeasible` is returned.
+pub fn lapjv_masked<T>(costs: &Matrix<T>, mask: Option<&Matrix<bool>>) -> Result<(Vec<usize>, Vec<usize>), LapJVError>
+where
+    T: LapJVCost,
+{
+    masked_with_options(costs, mask, &LapJVOptions::default()).map(|s| (s.in_row, s.in_col))
+}
+
+pub(crate) fn masked_with_options<T>(
+    costs: &Matrix<T>,
+    mask: Option<&Matrix<bool>>,
+    options: &LapJVOptions,
+) -> Result<Solution<T>, LapJVError>
+where
+    T: LapJVCost,
+{
+    if mask.is_some_and(|m| m.dim() != costs.dim()) {
+        return Err(LapJVError::new(ErrorKind::Msg("Input error: mask and matrix dimensions differ")));
+    }
+    let allowed = |i: usize, j: usize| mask.is_none_or(|m| m[(i, j)]) && !costs[(i, j)].is_forbidden();
+
+    let big = forbidden_cost(costs, allowed)
+        .ok_or(LapJVError::new(ErrorKind::Msg("Input error: costs too large to mask")))?;
+    let finite = Matrix::from_shape_fn(costs.dim(), |(i, j)| if allowed(i, j) { costs[(i, j)] } else { big });
+    // the tolerance scales with the original costs, not with the replacement cost
+    let solution = LapJV::new(&finite).with_tolerance(options.tolerance(costs)).solve_full()?;
+    if solution.in_row.iter().enumerate().any(|(i, &j)| !allowed(i, j)) {
+        return Err(LapJVError::new(ErrorKind::Infeasible));
+    }
+    Ok(solution)
+}
+
+/// A cost large enough that any assignment using it is worse than every
+/// assignment that only uses allowed cells, `None` if it is not representable
+fn forbidden_cost<T>(costs: &Matrix<T>, allowed: impl Fn(usize, usize) -> bool) -> Option<T>
+where
+    T: LapJVCost,
+{
+    let max_abs = costs
+        .indexed_iter()
+        .filter(|&((i, j), _)| allowed(i, j))
+        .filter_map(|(_, &c)| c.abs_value().to_f64())
+        .fold(0.0, f64::max);
+    // keep headroom below T::max_value() for the dual variable updates
+    let big = (max_abs + 1.0) * (costs.nrows() + 1) as f64 * 2.0;
+    let limit = T::max_value().to_f64()? / 4.0;
+    if big > limit {
+        return None;
+    }
+    T::from(big)
+}
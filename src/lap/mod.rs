@@ -0,0 +1,49 @@
+//! Linear assignment problem solvers
+//!
+//! All solvers accept floating point costs as well as signed integer costs,
+//! see [`LapJVCost`].
+
+mod auction;
+mod lapjv;
+mod masked;
+mod murty;
+mod verify;
+
+pub use auction::{AuctionOptions, auction, auction_with_options};
+pub use lapjv::{Cancellation, ErrorKind, LapJV, LapJVCost, LapJVError, LapJVOptions, Matrix, Solution, cost, lapjv, lapjv_solution, lapjv_warm, lapjv_with_options};
+pub use masked::lapjv_masked;
+pub use murty::kbest;
+pub use verify::{BRUTE_FORCE_MAX_DIM, brute_force, verify};
+
+/// Available assignment solvers
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub enum Solver {
+    /// Exact shortest augmenting path solver, best for small and medium matrices
+    #[default]
+    LapJV,
+    /// Auction algorithm, parallel with the `parallel` feature, for large matrices
+    Auction,
+}
+
+/// Solve LAP problem given cost matrix with the chosen solver
+pub fn solve<T>(costs: &Matrix<T>, solver: Solver) -> Result<(Vec<usize>, Vec<usize>), LapJVError>
+where
+    T: LapJVCost,
+{
+    solve_with_options(costs, solver, &LapJVOptions::default())
+}
+
+/// [`solve`] with the numerical options of LAPJV, ignored by the auction solver
+pub fn solve_with_options<T>(
+    costs: &Matrix<T>,
+    solver: Solver,
+    options: &LapJVOptions,
+) -> Result<(Vec<usize>, Vec<usize>), LapJVError>
+where
+    T: LapJVCost,
+{
+    match solver {
+        Solver::LapJV => lapjv_with_options(costs, options),
+        Solver::Auction => auction(costs),
+    }
+}
@@ -0,0 +1,100 @@
+use super::lapjv::{ErrorKind, LapJVCost, LapJVError, Matrix};
+use super::masked::lapjv_masked;
+
+/// A subproblem of Murty's partitioning: some cells are forced into the
+/// assignment, some are excluded from it.
+struct Node<T> {
+    assignment: Vec<usize>,
+    cost: T,
+    forced: Vec<(usize, usize)>,
+    forbidden: Vec<(usize, usize)>,
+}
+
+/// Find the `k` best assignments of a square cost matrix, ordered by total cost
+///
+/// This is Murty's ranking algorithm (K. G. Murty. An Algorithm for Ranking all
+/// the Assignments in Order of Increasing Cost. Operations Research 16, 682-687
+/// (1968)) with LAPJV solving each subproblem. Each result is the row to column
+/// assignment together with its total cost. Fewer than `k` results are returned
+/// when the matrix has fewer distinct assignments.
+pub fn kbest<T>(costs: &Matrix<T>, k: usize) -> Result<Vec<(Vec<usize>, T)>, LapJVError>
+where
+    T: LapJVCost,
+{
+    let (rows, cols) = costs.dim();
+    if rows != cols {
+        return Err(LapJVError::new(ErrorKind::Msg("Input error: matrix is not square")));
+    }
+    if k == 0 {
+        return Ok(vec![]);
+    }
+
+    let mut results = vec![];
+    let mut queue = vec![];
+    if let Some(root) = solve_node(costs, vec![], vec![])? {
+        queue.push(root);
+    }
+
+    while results.len() < k && !queue.is_empty() {
+        // k is small in practice, a linear scan is cheaper than a heap for non-Ord costs
+        let best = (1..queue.len()).fold(0, |best, i| {
+            if queue[i].cost < queue[best].cost { i } else { best }
+        });
+        let node = queue.swap_remove(best);
+
+        // Partition the remaining solution space around the current assignment
+        let mut forced = node.forced.clone();
+        for row in 0..rows {
+            if node.forced.iter().any(|&(i, _)| i == row) {
+                continue;
+            }
+            let cell = (row, node.assignment[row]);
+            let mut forbidden = node.forbidden.clone();
+            forbidden.push(cell);
+            if let Some(child) = solve_node(costs, forced.clone(), forbidden)? {
+                queue.push(child);
+            }
+            forced.push(cell);
+        }
+        results.push((node.assignment, node.cost));
+    }
+    Ok(results)
+}
+
+fn solve_node<T>(
+    costs: &Matrix<T>,
+    forced: Vec<(usize, usize)>,
+    forbidden: Vec<(usize, usize)>,
+) -> Result<Option<Node<T>>, LapJVError>
+where
+    T: LapJVCost,
+{
+    let dim = costs.nrows();
+    let free_rows: Vec<usize> = (0..dim).filter(|&i| forced.iter().all(|&(r, _)| r != i)).collect();
+    let free_cols: Vec<usize> = (0..dim).filter(|&j| forced.iter().all(|&(_, c)| c != j)).collect();
+
+    let shape = (free_rows.len(), free_cols.len());
+    let sub = Matrix::from_shape_fn(shape, |(i, j)| costs[(free_rows[i], free_cols[j])]);
+    let mask = Matrix::from_shape_fn(shape, |(i, j)| !forbidden.contains(&(free_rows[i], free_cols[j])));
+    let sub_assignment = match lapjv_masked(&sub, Some(&mask)) {
+        Ok((in_row, _)) => in_row,
+        // every completion of this node uses a forbidden cell
+        Err(e) if matches!(e.kind(), ErrorKind::Infeasible) => return Ok(None),
+        Err(e) => return Err(e),
+    };
+
+    let mut assignment = vec![0; dim];
+    for &(i, j) in &forced {
+        assignment[i] = j;
+    }
+    for (i, &j) in sub_assignment.iter().enumerate() {
+        assignment[free_rows[i]] = free_cols[j];
+    }
+    let cost = super::cost(costs, &assignment);
+    Ok(Some(Node {
+        assignment,
+        cost,
+        forced,
+        forbidden,
+    }))
+}
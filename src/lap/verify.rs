@@ -0,0 +1,103 @@
+use super::lapjv::{ErrorKind, LapJVCost, LapJVError, Matrix};
+
+/// Largest dimension for which [`verify`] checks optimality against [`brute_force`]
+pub const BRUTE_FORCE_MAX_DIM: usize = 8;
+
+/// Check a solution returned by one of the solvers, intended for property tests and fuzzing
+///
+/// `in_row[i]` is the column assigned to row `i`. The assignment must be a
+/// permutation that never uses a forbidden (positive infinity) cell. For matrices
+/// of at most [`BRUTE_FORCE_MAX_DIM`] rows its cost must also match the
+/// [`brute_force`] optimum. Float costs are compared with a tolerance relative to
+/// the largest absolute cost, integer costs exactly.
+pub fn verify<T>(costs: &Matrix<T>, in_row: &[usize]) -> Result<(), LapJVError>
+where
+    T: LapJVCost,
+{
+    let (dim, cols) = costs.dim();
+    if dim != cols {
+        return Err(LapJVError::new(ErrorKind::Msg("Input error: matrix is not square")));
+    }
+    if in_row.len() != dim {
+        return Err(LapJVError::new(ErrorKind::Msg("Verify error: assignment length differs from matrix")));
+    }
+    let mut used = vec![false; dim];
+    for &j in in_row {
+        if j >= dim || std::mem::replace(&mut used[j], true) {
+            return Err(LapJVError::new(ErrorKind::Msg("Verify error: assignment is not a permutation")));
+        }
+    }
+    if in_row.iter().enumerate().any(|(i, &j)| costs[(i, j)].is_forbidden()) {
+        return Err(LapJVError::new(ErrorKind::Msg("Verify error: forbidden cell assigned")));
+    }
+    if dim > BRUTE_FORCE_MAX_DIM {
+        return Ok(());
+    }
+    let Some(best) = brute_force(costs) else {
+        return Err(LapJVError::new(ErrorKind::Msg("Verify error: no feasible assignment to compare with")));
+    };
+    let (found, optimum) = (total(costs, in_row), total(costs, &best));
+    // the machine epsilon of T, which truncates to zero for integer costs
+    let epsilon = T::from(f32::EPSILON).and_then(|e| e.to_f64()).unwrap_or(0.0);
+    let max_abs = costs
+        .iter()
+        .filter(|c| !c.is_forbidden())
+        .filter_map(|c| c.abs_value().to_f64())
+        .fold(0.0, f64::max);
+    let tolerance = epsilon * max_abs * (4 * dim * dim) as f64;
+    if found.is_nan() || found - optimum > tolerance {
+        return Err(LapJVError::new(ErrorKind::Msg("Verify error: assignment is not optimal")));
+    }
+    Ok(())
+}
+
+/// Optimal assignment found by enumerating all permutations, `None` if every
+/// permutation uses a forbidden cell or has a NaN cost
+///
+/// Runs in `O(n! * n)`, only meant as a reference for small matrices.
+pub fn brute_force<T>(costs: &Matrix<T>) -> Option<Vec<usize>>
+where
+    T: LapJVCost,
+{
+    let dim = costs.nrows();
+    let mut permutation: Vec<usize> = (0..dim).collect();
+    let mut best: Option<(Vec<usize>, f64)> = None;
+    let mut consider = |p: &[usize]| {
+        if p.iter().enumerate().any(|(i, &j)| costs[(i, j)].is_forbidden()) {
+            return;
+        }
+        let cost = total(costs, p);
+        if !cost.is_nan() && best.as_ref().is_none_or(|(_, b)| cost < *b) {
+            best = Some((p.to_vec(), cost));
+        }
+    };
+    // Heap's algorithm, iterative form
+    let mut counters = vec![0; dim];
+    consider(&permutation);
+    let mut i = 1;
+    while i < dim {
+        if counters[i] < i {
+            let k = if i % 2 == 0 { 0 } else { counters[i] };
+            permutation.swap(k, i);
+            consider(&permutation);
+            counters[i] += 1;
+            i = 1;
+        } else {
+            counters[i] = 0;
+            i += 1;
+        }
+    }
+    best.map(|(p, _)| p)
+}
+
+/// Assignment cost summed in f64 so that verification does not share the solver's rounding
+fn total<T>(costs: &Matrix<T>, in_row: &[usize]) -> f64
+where
+    T: LapJVCost,
+{
+    in_row
+        .iter()
+        .enumerate()
+        .map(|(i, &j)| costs[(i, j)].to_f64().unwrap_or(f64::NAN))
+        .sum()
+}
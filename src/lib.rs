@@ -1,7 +1,68 @@
+pub mod animated;
+pub mod attribute;
+#[cfg(feature = "audio")]
+pub mod audio;
+pub mod backend;
+#[cfg(feature = "bench")]
+pub mod bench;
+pub mod cache;
+pub mod canary;
 pub mod captcha;
+pub mod capture;
+pub mod click;
+pub mod config;
+pub mod ctc;
+pub mod detection;
+pub mod drift;
+pub mod enhance;
+#[cfg(feature = "native")]
 pub mod environment;
+pub mod error;
+#[cfg(feature = "eval")]
+pub mod eval;
+pub mod fallback;
+#[cfg(any(test, feature = "fixtures"))]
+pub mod fixtures;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod geometry;
+pub mod governor;
+pub mod hash;
+pub mod hooks;
+pub mod input;
+pub mod inspect;
+pub mod language;
+pub mod lap;
+#[cfg(feature = "native")]
 pub mod loader;
-mod model;
+pub mod matching;
+pub mod metadata;
+pub mod model;
+pub mod policy;
+pub mod report;
+#[cfg(feature = "render")]
+pub mod render;
+mod rng;
+#[cfg(feature = "native")]
+pub mod runtime;
+pub mod scheduler;
+pub mod segment;
+#[cfg(feature = "server")]
+pub mod server;
+pub mod session;
+pub mod slo;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+pub mod tiles;
+pub mod timing;
+pub mod tokenizer;
 #[cfg(test)]
 mod tests;
-mod lapjv;
+pub mod tracking;
+pub mod trajectory;
+pub mod validation;
+#[cfg(feature = "debug-vis")]
+pub mod visualize;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod warmup;
@@ -1,77 +1,468 @@
-use crate::model::Model;
+use crate::inspect::OnnxHeader;
+use crate::metadata::KEY_MODEL;
+use crate::model::{Model, Precision};
 use ort::execution_providers::{CPUExecutionProvider, ExecutionProviderDispatch};
 use ort::session::Session;
+use ort::session::builder::{GraphOptimizationLevel, SessionBuilder};
 use reqwest::Url;
+use std::collections::HashMap;
 use std::error::Error;
 use std::fs::File;
 use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 use std::{env, fs};
-use image::EncodableLayout;
 
+/// ONNX Runtime会话选项，`None` 表示使用ONNX Runtime的默认值
+///
+/// 与其他CPU密集的任务共用进程时可以限制线程数
+#[derive(Clone, Debug, Default)]
+pub struct SessionOptions {
+    /// 单个算子内部并行的线程数
+    pub intra_threads: Option<usize>,
+    /// 算子之间并行的线程数，只在 `parallel_execution` 开启时生效
+    pub inter_threads: Option<usize>,
+    /// 是否并行执行图中互不依赖的节点
+    pub parallel_execution: Option<bool>,
+    /// 图优化级别
+    pub optimization_level: Option<OptimizationLevel>,
+    /// 是否按首次推理的形状预先规划内存，输入形状经常变化时关闭可以减少内存占用
+    pub memory_pattern: Option<bool>,
+    /// CPU执行器是否使用内存池(arena)，只对只使用CPU的环境生效
+    pub cpu_arena: bool,
+    /// CUDA执行器内存池的上限(字节)，对CPU与其他GPU执行器没有作用
+    pub cuda_memory_limit: Option<usize>,
+    /// GPU执行器内存池不够时的扩展方式，目前只对CUDA执行器生效
+    pub arena_extend: Option<ArenaExtend>,
+    /// GPU执行器使用的设备编号，对CUDA、TensorRT与DirectML执行器生效，None为设备0
+    pub device_id: Option<i32>,
+}
+
+/// 图优化级别，对应ONNX Runtime的 `GraphOptimizationLevel`
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OptimizationLevel {
+    /// 关闭全部图优化
+    Disable,
+    /// 只做与执行器无关的基础优化，例如常量折叠与删除冗余节点
+    Level1,
+    /// 另外做算子融合等扩展优化
+    Level2,
+    /// 全部优化，包括内存布局优化
+    Level3,
+}
+
+impl From<OptimizationLevel> for GraphOptimizationLevel {
+    fn from(level: OptimizationLevel) -> Self {
+        match level {
+            OptimizationLevel::Disable => GraphOptimizationLevel::Disable,
+            OptimizationLevel::Level1 => GraphOptimizationLevel::Level1,
+            OptimizationLevel::Level2 => GraphOptimizationLevel::Level2,
+            OptimizationLevel::Level3 => GraphOptimizationLevel::Level3,
+        }
+    }
+}
+
+/// 内存池不够时的扩展方式
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ArenaExtend {
+    /// 每次扩展到2的幂，ONNX Runtime的默认值，分配次数少但可能多占用接近一倍的内存
+    NextPowerOfTwo,
+    /// 只按请求的大小扩展，长时间运行的服务内存占用更可预测
+    SameAsRequested,
+}
+
+impl SessionOptions {
+    /// 单线程顺序执行，多次运行得到逐位相同的输出，适合回归测试
+    pub fn deterministic(mut self) -> Self {
+        self.intra_threads = Some(1);
+        self.inter_threads = Some(1);
+        self.parallel_execution = Some(false);
+        self
+    }
+
+    /// 创建应用了这些选项的SessionBuilder
+    pub fn builder(&self) -> Result<SessionBuilder, Box<dyn Error>> {
+        let mut builder = Session::builder()?;
+        if let Some(threads) = self.intra_threads {
+            builder = builder.with_intra_threads(threads)?;
+        }
+        if let Some(threads) = self.inter_threads {
+            builder = builder.with_inter_threads(threads)?;
+        }
+        if let Some(parallel) = self.parallel_execution {
+            builder = builder.with_parallel_execution(parallel)?;
+        }
+        if let Some(level) = self.optimization_level {
+            builder = builder.with_optimization_level(level.into())?;
+        }
+        if let Some(enable) = self.memory_pattern {
+            builder = builder.with_memory_pattern(enable)?;
+        }
+        Ok(builder)
+    }
+
+    /// 按 `cpu_arena` 创建CPU执行器
+    pub fn cpu_execution_provider(&self) -> ExecutionProviderDispatch {
+        let cpu = CPUExecutionProvider::default();
+        if self.cpu_arena {
+            cpu.with_arena_allocator().build()
+        } else {
+            cpu.build()
+        }
+    }
+}
+
+/// 按名称创建执行器：`cpu`、`cuda`、`tensorrt`、`directml`、`coreml`、`nnapi`，
+/// 除cpu外需要开启同名的feature。GPU执行器可以用 `cuda:1` 的形式指定设备编号，优先于 `options.device_id`
+pub fn execution_provider(name: &str, options: &SessionOptions) -> Result<ExecutionProviderDispatch, Box<dyn Error>> {
+    let name = name.to_lowercase();
+    let (name, device_id) = match name.split_once(':') {
+        Some((name, id)) => (name, Some(id.trim().parse::<i32>().map_err(|e| format!("执行器{}的设备编号无效: {}", name, e))?)),
+        None => (name.as_str(), None),
+    };
+    #[allow(unused_variables)]
+    let device_id = device_id.or(options.device_id);
+    let provider = match name {
+        "cpu" => options.cpu_execution_provider(),
+        #[cfg(feature = "cuda")]
+        "cuda" => {
+            use ort::execution_providers::{ArenaExtendStrategy, CUDAExecutionProvider};
+            let mut cuda = CUDAExecutionProvider::default();
+            if let Some(id) = device_id {
+                cuda = cuda.with_device_id(id);
+            }
+            if let Some(limit) = options.cuda_memory_limit {
+                cuda = cuda.with_memory_limit(limit);
+            }
+            if let Some(extend) = options.arena_extend {
+                cuda = cuda.with_arena_extend_strategy(match extend {
+                    ArenaExtend::NextPowerOfTwo => ArenaExtendStrategy::NextPowerOfTwo,
+                    ArenaExtend::SameAsRequested => ArenaExtendStrategy::SameAsRequested,
+                });
+            }
+            cuda.build()
+        }
+        #[cfg(feature = "tensorrt")]
+        "tensorrt" => {
+            let tensorrt = ort::execution_providers::TensorRTExecutionProvider::default();
+            match device_id {
+                Some(id) => tensorrt.with_device_id(id).build(),
+                None => tensorrt.build(),
+            }
+        }
+        #[cfg(feature = "directml")]
+        "directml" => {
+            let directml = ort::execution_providers::DirectMLExecutionProvider::default();
+            match device_id {
+                Some(id) => directml.with_device_id(id).build(),
+                None => directml.build(),
+            }
+        }
+        #[cfg(feature = "coreml")]
+        "coreml" => ort::execution_providers::CoreMLExecutionProvider::default().build(),
+        #[cfg(feature = "nnapi")]
+        "nnapi" => ort::execution_providers::NNAPIExecutionProvider::default().build(),
+        name if ["cuda", "tensorrt", "directml", "coreml", "nnapi"].contains(&name) => {
+            return Err(format!("执行器{}需要开启{} feature", name, name).into());
+        }
+        name => return Err(format!("未知的执行器{}", name).into()),
+    };
+    Ok(provider)
+}
 
 pub enum ModelLoader {
     DefaultModelLoader,
     CustomModelLoader(Box<dyn ModelLoaderTrait>),
 }
-pub trait ModelLoaderTrait {
+/// 模型加载器，需要是 `Send + Sync` 才能让 [`CaptchaEnvironment`](crate::environment::CaptchaEnvironment) 在线程之间共享
+pub trait ModelLoaderTrait: Send + Sync {
     fn load(&self, model: Model) -> Result<Session, Box<dyn Error>> {
         self.load_with_execution_providers(model, vec![CPUExecutionProvider::default().build()])
     }
     fn load_with_execution_providers(&self, model: Model, providers: Vec<ExecutionProviderDispatch>) -> Result<Session, Box<dyn Error>>;
+
+    /// 加载指定精度的模型，默认只支持fp32
+    fn load_with_precision(
+        &self,
+        model: Model,
+        precision: Precision,
+        providers: Vec<ExecutionProviderDispatch>,
+    ) -> Result<Session, Box<dyn Error>> {
+        match precision {
+            Precision::Fp32 => self.load_with_execution_providers(model, providers),
+            _ => Err(format!("该加载器不支持{:?}模型", precision).into()),
+        }
+    }
+
+    /// 使用指定的会话选项加载模型，默认忽略会话选项
+    fn load_with_session_options(
+        &self,
+        model: Model,
+        precision: Precision,
+        providers: Vec<ExecutionProviderDispatch>,
+        options: &SessionOptions,
+    ) -> Result<Session, Box<dyn Error>> {
+        let _ = options;
+        self.load_with_precision(model, precision, providers)
+    }
+
+    /// 将要加载的模型文件内容，用于 [`describe_model`](crate::environment::CaptchaEnvironment::describe_model)
+    /// 读取opset等信息，默认返回None
+    fn model_bytes(&self, model: Model, precision: Precision) -> Result<Option<Vec<u8>>, Box<dyn Error>> {
+        let _ = (model, precision);
+        Ok(None)
+    }
 }
 
 #[derive(Default)]
 pub struct DefaultModelLoader;
 
-fn load_one_model(path: &Path, url: &Url, providers: impl IntoIterator<Item = ExecutionProviderDispatch>) -> Result<Session, Box<dyn Error>> {
-    let bytes;
-    if !path.exists() {
-        let client = reqwest::blocking::Client::builder()
-            .user_agent("CaptchaBreaker")
-            .build()?;
-        bytes = client.get(url.as_str()).send()?.bytes()?.as_bytes().to_owned();
-        let mut file = File::create(path)?;
-        file.write_all(bytes.as_ref())?;
-
+/// 本地没有模型文件时从url下载，url为None表示该精度没有发布
+fn load_one_model(
+    path: &Path,
+    url: Option<&Url>,
+    providers: impl IntoIterator<Item = ExecutionProviderDispatch>,
+    options: &SessionOptions,
+) -> Result<Session, Box<dyn Error>> {
+    let bytes = if path.exists() {
+        fs::read(path)?
     } else {
-        bytes = fs::read(path)?;
-    }
-    Ok(Session::builder()?
+        let url = url.ok_or_else(|| format!("{}不存在，该精度的模型没有发布，需要自行放到模型目录", path.display()))?;
+        download(path, url)?
+    };
+    Ok(options
+        .builder()?
         .with_execution_providers(providers)?
         .commit_from_memory(bytes.as_ref())?)
 }
+
+/// 下载到临时文件后重命名，HTTP错误(例如404页面)与写了一半的文件不会留在模型目录中
+fn download(path: &Path, url: &Url) -> Result<Vec<u8>, Box<dyn Error>> {
+    let client = reqwest::blocking::Client::builder()
+        .user_agent("CaptchaBreaker")
+        .build()?;
+    let bytes = client.get(url.as_str()).send()?.error_for_status()?.bytes()?.to_vec();
+    let partial = path.with_extension("onnx.part");
+    let mut file = File::create(&partial)?;
+    file.write_all(&bytes)?;
+    file.sync_all()?;
+    fs::rename(&partial, path)?;
+    Ok(bytes)
+}
+
+/// 在模型目录中查找通过元数据声明了自己用途的模型文件
+///
+/// 默认文件名(包括各精度版本)的模型不参与查找，它们由 [`load_one_model`] 处理。
+/// 只为声明的用途与model一致的文件创建会话
+fn find_self_described_model(
+    model_root: &Path,
+    model: Model,
+    providers: &[ExecutionProviderDispatch],
+    options: &SessionOptions,
+) -> Result<Option<Session>, Box<dyn Error>> {
+    let Some(bytes) = self_described_bytes(model_root, model)? else {
+        return Ok(None);
+    };
+    Ok(Some(
+        options
+            .builder()?
+            .with_execution_providers(providers.to_vec())?
+            .commit_from_memory(&bytes)?,
+    ))
+}
+
+/// 第一个通过元数据声明为model的候选文件的内容，只解析文件头，无法读取或解析的文件被跳过
+fn self_described_bytes(model_root: &Path, model: Model) -> Result<Option<Vec<u8>>, Box<dyn Error>> {
+    for path in self_described_candidates(model_root)? {
+        let declared = fs::read(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|bytes| OnnxHeader::parse(&bytes).map(|header| (header, bytes)).map_err(|e| e.to_string()));
+        match declared {
+            Ok((header, bytes)) if header.metadata.get(KEY_MODEL).map(String::as_str) == Some(model.name()) => {
+                return Ok(Some(bytes));
+            }
+            Ok(_) => {}
+            Err(e) => log::warn!("跳过无法解析的模型文件{}：{}", path.display(), e),
+        }
+    }
+    Ok(None)
+}
+
+/// 模型目录中不是默认文件名的ONNX文件
+fn self_described_candidates(model_root: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let mut paths = vec![];
+    for entry in fs::read_dir(model_root)? {
+        let path = entry?.path();
+        let is_onnx = path.extension().and_then(|e| e.to_str()) == Some("onnx");
+        let file_name = path.file_name().and_then(|n| n.to_str());
+        let is_default = Model::ALL.iter().any(|m| {
+            [Precision::Fp32, Precision::Fp16, Precision::Int8]
+                .iter()
+                .any(|p| file_name == Some(m.file_name_with(*p).as_str()))
+        });
+        if is_onnx && !is_default {
+            paths.push(path);
+        }
+    }
+    // 保证多个候选文件时的选择顺序稳定
+    paths.sort();
+    Ok(paths)
+}
+
 impl ModelLoaderTrait for DefaultModelLoader {
     fn load_with_execution_providers(&self, model: Model, providers: Vec<ExecutionProviderDispatch>) -> Result<Session, Box<dyn Error>> {
-        let root = env::current_dir()?;
-        let model_root = root.join("models");
+        DirModelLoader::new(env::current_dir()?.join("models")).load_with_execution_providers(model, providers)
+    }
+
+    fn load_with_precision(
+        &self,
+        model: Model,
+        precision: Precision,
+        providers: Vec<ExecutionProviderDispatch>,
+    ) -> Result<Session, Box<dyn Error>> {
+        DirModelLoader::new(env::current_dir()?.join("models")).load_with_precision(model, precision, providers)
+    }
+
+    fn load_with_session_options(
+        &self,
+        model: Model,
+        precision: Precision,
+        providers: Vec<ExecutionProviderDispatch>,
+        options: &SessionOptions,
+    ) -> Result<Session, Box<dyn Error>> {
+        DirModelLoader::new(env::current_dir()?.join("models"))
+            .load_with_session_options(model, precision, providers, options)
+    }
+
+    fn model_bytes(&self, model: Model, precision: Precision) -> Result<Option<Vec<u8>>, Box<dyn Error>> {
+        DirModelLoader::new(env::current_dir()?.join("models")).model_bytes(model, precision)
+    }
+}
+
+/// 从指定目录加载模型，与 [`DefaultModelLoader`] 的行为相同，只是目录不固定为 `./models`
+pub struct DirModelLoader {
+    root: PathBuf,
+}
+
+impl DirModelLoader {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        DirModelLoader { root: root.into() }
+    }
+}
+
+impl ModelLoaderTrait for DirModelLoader {
+    fn load_with_execution_providers(&self, model: Model, providers: Vec<ExecutionProviderDispatch>) -> Result<Session, Box<dyn Error>> {
+        self.load_with_session_options(model, Precision::Fp32, providers, &SessionOptions::default())
+    }
+
+    fn load_with_precision(
+        &self,
+        model: Model,
+        precision: Precision,
+        providers: Vec<ExecutionProviderDispatch>,
+    ) -> Result<Session, Box<dyn Error>> {
+        self.load_with_session_options(model, precision, providers, &SessionOptions::default())
+    }
+
+    fn load_with_session_options(
+        &self,
+        model: Model,
+        precision: Precision,
+        providers: Vec<ExecutionProviderDispatch>,
+        options: &SessionOptions,
+    ) -> Result<Session, Box<dyn Error>> {
+        let model_root = &self.root;
         if !model_root.exists() {
-            fs::create_dir_all(&model_root)?;
+            fs::create_dir_all(model_root)?;
         }
-        match model {
-            Model::Yolo11n => load_one_model(
-                model_root.join("yolov11n_captcha.onnx").as_path(),
-                &Url::parse(
-                    "https://www.modelscope.cn/models/Amorter/CaptchaBreakerModels/resolve/master/yolov11n_captcha.onnx",
-                )?,
-                providers,
-            ),
-            Model::Siamese => load_one_model(
-                model_root.join("siamese.onnx").as_path(),
-                &Url::parse(
-                    "https://www.modelscope.cn/models/Amorter/CaptchaBreakerModels/resolve/master/siamese.onnx",
-                )?,
-                providers,
-            ),
+        let described = match precision {
+            Precision::Fp32 => find_self_described_model(model_root, model, &providers, options)?,
+            _ => None,
+        };
+        if let Some(session) = described {
+            return Ok(session);
         }
+        load_one_model(
+            model_root.join(model.file_name_with(precision)).as_path(),
+            model.url_with(precision).map(|url| Url::parse(&url)).transpose()?.as_ref(),
+            providers,
+            options,
+        )
+    }
+
+    /// 与加载时的查找顺序相同，不会下载缺少的模型
+    fn model_bytes(&self, model: Model, precision: Precision) -> Result<Option<Vec<u8>>, Box<dyn Error>> {
+        if !self.root.exists() {
+            return Ok(None);
+        }
+        let described = match precision {
+            Precision::Fp32 => self_described_bytes(&self.root, model)?,
+            _ => None,
+        };
+        if described.is_some() {
+            return Ok(described);
+        }
+        let path = self.root.join(model.file_name_with(precision));
+        Ok(path.exists().then(|| fs::read(path)).transpose()?)
     }
 }
 
 impl ModelLoader {
     pub(crate) fn get_model_loader(self) -> Box<dyn ModelLoaderTrait> {
         match self {
-            ModelLoader::DefaultModelLoader => Box::new(DefaultModelLoader::default()),
+            ModelLoader::DefaultModelLoader => Box::new(DefaultModelLoader),
             ModelLoader::CustomModelLoader(model_loader) => model_loader,
         }
     }
 }
+
+/// 轮询模型目录中默认文件名的修改时间，配合
+/// [`CaptchaEnvironment::reload_changed`](crate::environment::CaptchaEnvironment::reload_changed) 实现热更新
+///
+/// 只跟踪 [`Model::file_name`] 对应的文件，通过元数据声明用途的模型文件不在跟踪范围内
+pub struct ModelWatcher {
+    root: PathBuf,
+    modified: HashMap<Model, SystemTime>,
+}
+
+impl ModelWatcher {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        let mut watcher = ModelWatcher {
+            root: root.into(),
+            modified: HashMap::new(),
+        };
+        watcher.poll();
+        watcher
+    }
+
+    /// 返回自上次调用以来文件被修改过的模型，并记录新的修改时间
+    pub fn poll(&mut self) -> Vec<Model> {
+        let changed = self.changed();
+        for &(model, modified) in &changed {
+            self.commit(model, modified);
+        }
+        changed.into_iter().map(|(model, _)| model).collect()
+    }
+
+    /// 与记录的修改时间不同的模型及其当前修改时间，不记录新的修改时间；首次看到的文件直接记录
+    pub fn changed(&mut self) -> Vec<(Model, SystemTime)> {
+        let mut changed = vec![];
+        for &model in Model::ALL {
+            let modified = fs::metadata(self.root.join(model.file_name())).and_then(|m| m.modified());
+            let Ok(modified) = modified else { continue };
+            match self.modified.get(&model) {
+                Some(&previous) if previous != modified => changed.push((model, modified)),
+                Some(_) => {}
+                None => {
+                    self.modified.insert(model, modified);
+                }
+            }
+        }
+        changed
+    }
+
+    /// 记录模型已经按该修改时间处理过，例如重新加载成功之后
+    pub fn commit(&mut self, model: Model, modified: SystemTime) {
+        self.modified.insert(model, modified);
+    }
+}
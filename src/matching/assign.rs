@@ -0,0 +1,122 @@
+use super::sinkhorn::{SinkhornConfig, soft_assign};
+use crate::lap::{self, LapJVError, LapJVOptions, Solver};
+use ndarray::Array2;
+
+/// 两组图像块之间的最优匹配
+#[derive(Debug, Clone)]
+pub struct Assignment {
+    /// 成本矩阵，行为query，列为candidate
+    pub cost_matrix: Array2<f32>,
+    /// 第i个query匹配的candidate下标，candidate数量不足时为None
+    pub matches: Vec<Option<usize>>,
+    /// 第i个query与匹配的candidate为同一目标的概率，见 [`Calibration`](super::Calibration)
+    pub probabilities: Vec<Option<f32>>,
+    /// [`MatchStrategy::Sinkhorn`] 的软分配矩阵，其他求解方式为None
+    pub soft: Option<Array2<f32>>,
+}
+
+impl Assignment {
+    /// 第i个query与匹配的candidate之间的距离
+    pub fn distance(&self, query: usize) -> Option<f32> {
+        self.matches[query].map(|c| self.cost_matrix[[query, c]])
+    }
+
+    pub fn distances(&self) -> Vec<Option<f32>> {
+        (0..self.matches.len()).map(|i| self.distance(i)).collect()
+    }
+}
+
+/// 分配的求解方式，在耗时与准确度之间取舍
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub enum MatchStrategy {
+    /// LAPJV精确求解
+    #[default]
+    Exact,
+    /// 按成本从小到大贪心选取，不保证最优，见 [`greedy`]
+    Greedy,
+    /// 拍卖算法，大规模矩阵开启 `parallel` 后更快
+    Auction,
+    /// Sinkhorn软分配后再取一一对应的匹配，特征有噪声时同时给出每个答案的分配概率，见 [`sinkhorn`](super::sinkhorn)
+    Sinkhorn,
+}
+
+impl From<Solver> for MatchStrategy {
+    fn from(solver: Solver) -> Self {
+        match solver {
+            Solver::LapJV => MatchStrategy::Exact,
+            Solver::Auction => MatchStrategy::Auction,
+        }
+    }
+}
+
+/// 按strategy求解可能不是方阵的分配问题，options只对 [`MatchStrategy::Exact`] 生效，
+/// [`MatchStrategy::Sinkhorn`] 使用默认的 [`SinkhornConfig`]
+pub fn assign_with_strategy(
+    costs: &Array2<f32>,
+    strategy: MatchStrategy,
+    options: &LapJVOptions,
+) -> Result<Vec<Option<usize>>, LapJVError> {
+    match strategy {
+        MatchStrategy::Exact => assign_with_options(costs, Solver::LapJV, options),
+        MatchStrategy::Greedy => Ok(greedy(costs)),
+        MatchStrategy::Auction => assign_with_options(costs, Solver::Auction, options),
+        MatchStrategy::Sinkhorn => Ok(soft_assign(costs, &SinkhornConfig::default(), None)?.matches),
+    }
+}
+
+/// 贪心匹配：按成本从小到大依次选取行列都未被使用的格子，O(n² log n)
+///
+/// 返回第i行匹配的列，列数不足时为None；结果不一定是最优分配
+pub fn greedy(costs: &Array2<f32>) -> Vec<Option<usize>> {
+    let (rows, cols) = costs.dim();
+    let mut cells: Vec<(usize, usize)> = (0..rows).flat_map(|i| (0..cols).map(move |j| (i, j))).collect();
+    cells.sort_by(|&a, &b| costs[a].total_cmp(&costs[b]).then(a.cmp(&b)));
+    let mut matches = vec![None; rows];
+    let mut used = vec![false; cols];
+    let mut remaining = rows.min(cols);
+    for (i, j) in cells {
+        if remaining == 0 {
+            break;
+        }
+        if matches[i].is_none() && !used[j] {
+            matches[i] = Some(j);
+            used[j] = true;
+            remaining -= 1;
+        }
+    }
+    matches
+}
+
+/// 求解可能不是方阵的分配问题，行数多于列数时多出的行为None
+///
+/// 缺少的行或列用0补齐为方阵，补齐的部分不影响真实行列之间的最优分配
+pub fn assign(costs: &Array2<f32>, solver: Solver) -> Result<Vec<Option<usize>>, LapJVError> {
+    assign_with_options(costs, solver, &LapJVOptions::default())
+}
+
+/// 与 [`assign`] 相同，LAPJV使用options指定的容差与精度
+pub fn assign_with_options(
+    costs: &Array2<f32>,
+    solver: Solver,
+    options: &LapJVOptions,
+) -> Result<Vec<Option<usize>>, LapJVError> {
+    let (rows, cols) = costs.dim();
+    if rows == 0 {
+        return Ok(vec![]);
+    }
+    if cols == 0 {
+        return Ok(vec![None; rows]);
+    }
+    let dim = rows.max(cols);
+    let padded;
+    let square = if rows == cols {
+        costs
+    } else {
+        let mut matrix = Array2::zeros((dim, dim));
+        matrix.slice_mut(ndarray::s![..rows, ..cols]).assign(costs);
+        padded = matrix;
+        &padded
+    };
+    let (row_to_col, _) = lap::solve_with_options(square, solver, options)?;
+    Ok(row_to_col[..rows].iter().map(|&c| (c < cols).then_some(c)).collect())
+}
@@ -0,0 +1,102 @@
+use super::{DistanceMetric, cost_matrix};
+use crate::detection::{self, BBox};
+use crate::geometry::Rect;
+use image::RgbImage;
+use ndarray::{Array2, ArrayView2, Zip};
+
+/// 多个视角的距离的汇总方式
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum CostPooling {
+    /// 问题与答案使用相同视角时的距离的平均值
+    #[default]
+    Mean,
+    /// 翻转状态相同的任意两个视角之间的最小距离，问题框偏紧而答案框偏松时也能对上
+    Min,
+}
+
+/// 匹配时的测试时增强(TTA)：每个框额外以不同的缩放与翻转截取图像块，汇总各视角的距离后再求解分配
+///
+/// 原图像块总是第一个视角，`scales` 为空且不翻转时不增强
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TtaConfig {
+    /// 框以中心缩放的比例，例如 `[0.9, 1.15]` 分别截取偏紧与偏松的区域，1.0会被忽略
+    pub scales: Vec<f32>,
+    /// 是否为每个缩放比例再加一个水平翻转的视角
+    pub flip: bool,
+    pub pooling: CostPooling,
+}
+
+/// 截取图像块的一个视角
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct View {
+    pub scale: f32,
+    pub flip: bool,
+}
+
+impl View {
+    pub const IDENTITY: View = View { scale: 1.0, flip: false };
+
+    /// 按视角从原图截取框的图像块
+    pub fn crop(&self, image: &RgbImage, bbox: &BBox) -> RgbImage {
+        let crop = if self.scale == 1.0 {
+            detection::crop(image, bbox)
+        } else {
+            let size = (bbox.width() * self.scale, bbox.height() * self.scale);
+            detection::crop(image, &BBox { rect: Rect::from_center(bbox.center(), size), ..bbox.clone() })
+        };
+        if self.flip { image::imageops::flip_horizontal(&crop) } else { crop }
+    }
+}
+
+impl TtaConfig {
+    /// 所有视角，第一个为原图像块
+    pub fn views(&self) -> Vec<View> {
+        let mut scales = vec![1.0];
+        for &scale in &self.scales {
+            if scale > 0.0 && !scales.contains(&scale) {
+                scales.push(scale);
+            }
+        }
+        let flips: &[bool] = if self.flip { &[false, true] } else { &[false] };
+        flips.iter().flat_map(|&flip| scales.iter().map(move |&scale| View { scale, flip })).collect()
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.views().len() > 1
+    }
+}
+
+/// 汇总各视角的成本矩阵，features的第i项为第i个视角下 (问题特征, 答案特征)
+pub fn pooled_costs(views: &[View], features: &[(ArrayView2<f32>, ArrayView2<f32>)], metric: DistanceMetric, pooling: CostPooling) -> Array2<f32> {
+    let pairs: Vec<(usize, usize)> = match pooling {
+        CostPooling::Mean => (0..views.len()).map(|i| (i, i)).collect(),
+        CostPooling::Min => (0..views.len())
+            .flat_map(|i| (0..views.len()).map(move |j| (i, j)))
+            .filter(|&(i, j)| views[i].flip == views[j].flip)
+            .collect(),
+    };
+    let mut pooled: Option<Array2<f32>> = None;
+    let mut used = 0;
+    for (i, j) in pairs {
+        let (Some(query), Some(candidate)) = (features.get(i), features.get(j)) else {
+            continue;
+        };
+        let costs = cost_matrix(&query.0, &candidate.1, metric);
+        used += 1;
+        pooled = Some(match pooled {
+            None => costs,
+            Some(mut pooled) => {
+                match pooling {
+                    CostPooling::Mean => pooled += &costs,
+                    CostPooling::Min => Zip::from(&mut pooled).and(&costs).for_each(|p, &c| *p = p.min(c)),
+                }
+                pooled
+            }
+        });
+    }
+    let mut pooled = pooled.unwrap_or_else(|| Array2::zeros((0, 0)));
+    if pooling == CostPooling::Mean && used > 1 {
+        pooled /= used as f32;
+    }
+    pooled
+}
@@ -0,0 +1,91 @@
+use crate::metadata::{EmbeddedMetadata, KEY_CALIBRATION_TEMPERATURE, KEY_CALIBRATION_THRESHOLD};
+use ndarray::Array2;
+
+/// 匹配距离到匹配概率的校准，`p = sigmoid((threshold - distance) / temperature)`
+///
+/// 不同版本模型的距离尺度不同，阈值写在概率上可以在升级模型后保持不变。
+/// 参数随模型一起保存在ONNX元数据中，见 [`from_metadata`](Self::from_metadata)
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Calibration {
+    /// 概率为0.5时的距离
+    pub threshold: f32,
+    /// 越小概率随距离变化得越陡
+    pub temperature: f32,
+}
+
+impl Default for Calibration {
+    fn default() -> Self {
+        Calibration {
+            threshold: 1.0,
+            temperature: 0.25,
+        }
+    }
+}
+
+impl Calibration {
+    pub fn probability(&self, distance: f32) -> f32 {
+        let z = (self.threshold - distance) / self.temperature.max(f32::EPSILON);
+        1.0 / (1.0 + (-z).exp())
+    }
+
+    pub fn probabilities(&self, distances: &Array2<f32>) -> Array2<f32> {
+        distances.mapv(|d| self.probability(d))
+    }
+
+    /// 从模型元数据读取，两个键都存在且能解析时返回Some
+    pub fn from_metadata(metadata: &EmbeddedMetadata) -> Option<Self> {
+        let read = |key: &str| metadata.extra.get(key).and_then(|v| v.trim().parse::<f32>().ok());
+        Some(Calibration {
+            threshold: read(KEY_CALIBRATION_THRESHOLD)?,
+            temperature: read(KEY_CALIBRATION_TEMPERATURE)?.max(f32::EPSILON),
+        })
+    }
+
+    /// 写入元数据，供导出工具使用
+    pub fn write_metadata(&self, metadata: &mut EmbeddedMetadata) {
+        metadata.extra.insert(KEY_CALIBRATION_THRESHOLD.to_string(), self.threshold.to_string());
+        metadata.extra.insert(KEY_CALIBRATION_TEMPERATURE.to_string(), self.temperature.to_string());
+    }
+
+    /// 用标注过的距离(是否为正确匹配)做逻辑回归拟合参数
+    pub fn fit(distances: &[f32], matched: &[bool]) -> Self {
+        // 拟合 p = sigmoid(a + b * d)，再换算为 threshold = -a / b，temperature = -1 / b
+        let n = distances.len().min(matched.len()).max(1) as f64;
+        let mean = distances.iter().map(|&d| d as f64).sum::<f64>() / n;
+        let std = (distances.iter().map(|&d| (d as f64 - mean).powi(2)).sum::<f64>() / n).sqrt().max(1e-6);
+        let (mut a, mut b) = (0.0f64, -1.0f64);
+        // 在标准化后的距离上做牛顿迭代，加入少量L2正则避免完全可分时发散
+        for _ in 0..50 {
+            let (mut ga, mut gb, mut haa, mut hab, mut hbb) = (1e-3 * a, 1e-3 * b, 1e-3, 0.0, 1e-3);
+            for (&d, &y) in distances.iter().zip(matched) {
+                let x = (d as f64 - mean) / std;
+                let p = 1.0 / (1.0 + (-(a + b * x)).exp());
+                let r = p - if y { 1.0 } else { 0.0 };
+                let w = p * (1.0 - p);
+                ga += r;
+                gb += r * x;
+                haa += w;
+                hab += w * x;
+                hbb += w * x * x;
+            }
+            let det = haa * hbb - hab * hab;
+            if det.abs() < 1e-12 {
+                break;
+            }
+            let (da, db) = ((hbb * ga - hab * gb) / det, (haa * gb - hab * ga) / det);
+            a -= da;
+            b -= db;
+            if da.abs() + db.abs() < 1e-9 {
+                break;
+            }
+        }
+        if b >= 0.0 {
+            // 距离越大越可能匹配，说明数据有问题，保持默认参数
+            return Calibration::default();
+        }
+        Calibration {
+            threshold: (mean - a / b * std) as f32,
+            temperature: (-std / b) as f32,
+        }
+    }
+}
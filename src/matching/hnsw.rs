@@ -0,0 +1,389 @@
+use super::DistanceMetric;
+use super::template::{metric_from_tag, metric_tag, read_u32};
+use crate::rng::SplitMix64;
+use ndarray::{Array2, ArrayView1, ArrayView2, Axis};
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::error::Error;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+const MAGIC: &[u8; 4] = b"CBHN";
+const VERSION: u32 = 1;
+const NONE: u32 = u32::MAX;
+
+/// HNSW索引的参数，`ef_search` 越大召回率越高、查找越慢
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HnswConfig {
+    /// 每层每个节点的邻居数，第0层为两倍
+    pub m: usize,
+    /// 构建时每次插入搜索的候选数
+    pub ef_construction: usize,
+    /// 查找时搜索的候选数，不小于k
+    pub ef_search: usize,
+    /// 节点层数的随机种子，相同的种子与插入顺序得到相同的索引
+    pub seed: u64,
+}
+
+impl Default for HnswConfig {
+    fn default() -> Self {
+        HnswConfig {
+            m: 16,
+            ef_construction: 100,
+            ef_search: 64,
+            seed: 0x45e2,
+        }
+    }
+}
+
+/// 按距离排序的候选，距离相同时按下标
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Candidate {
+    distance: f32,
+    id: u32,
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.distance.total_cmp(&other.distance).then(self.id.cmp(&other.id))
+    }
+}
+
+/// 分层可导航小世界图(HNSW)近似最近邻索引
+///
+/// 字形库达到数万个模板时，逐个计算距离太慢。索引保存自己的一份特征，
+/// 可以单独保存与加载，也可以通过 [`TemplateBank::build_index`](super::TemplateBank::build_index) 为字形库构建
+#[derive(Debug, Clone, PartialEq)]
+pub struct HnswIndex {
+    config: HnswConfig,
+    metric: DistanceMetric,
+    vectors: Array2<f32>,
+    /// `neighbors[node][layer]` 为节点在该层的邻居
+    neighbors: Vec<Vec<Vec<u32>>>,
+    entry: Option<u32>,
+    rng: u64,
+}
+
+impl HnswIndex {
+    pub fn new(dim: usize, metric: DistanceMetric, config: HnswConfig) -> Self {
+        HnswIndex {
+            config,
+            metric,
+            vectors: Array2::zeros((0, dim)),
+            neighbors: vec![],
+            entry: None,
+            rng: config.seed,
+        }
+    }
+
+    /// 依次插入features的每一行，节点编号即行号
+    pub fn build(features: &ArrayView2<f32>, metric: DistanceMetric, config: HnswConfig) -> Self {
+        let mut index = HnswIndex::new(features.ncols(), metric, config);
+        for feature in features.axis_iter(Axis(0)) {
+            index.insert(feature);
+        }
+        index
+    }
+
+    pub fn config(&self) -> &HnswConfig {
+        &self.config
+    }
+
+    /// 只修改查找参数，不需要重建
+    pub fn set_ef_search(&mut self, ef_search: usize) {
+        self.config.ef_search = ef_search;
+    }
+
+    pub fn metric(&self) -> DistanceMetric {
+        self.metric
+    }
+
+    pub fn dim(&self) -> usize {
+        self.vectors.ncols()
+    }
+
+    pub fn len(&self) -> usize {
+        self.vectors.nrows()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vectors.nrows() == 0
+    }
+
+    fn distance(&self, query: ArrayView1<f32>, id: u32) -> f32 {
+        self.metric.distance(query, self.vectors.row(id as usize))
+    }
+
+    fn max_neighbors(&self, layer: usize) -> usize {
+        let m = self.config.m.max(2);
+        if layer == 0 { 2 * m } else { m }
+    }
+
+    fn random_level(&mut self) -> usize {
+        let mut rng = SplitMix64::new(self.rng);
+        let u = rng.next_f32().max(f32::MIN_POSITIVE);
+        self.rng = rng.next_u64();
+        let ml = 1.0 / (self.config.m.max(2) as f32).ln();
+        (-u.ln() * ml).floor() as usize
+    }
+
+    /// 插入一个特征，返回节点编号；维度不一致时panic
+    pub fn insert(&mut self, feature: ArrayView1<f32>) -> usize {
+        assert_eq!(feature.len(), self.dim(), "特征维度与索引不一致");
+        let id = self.len() as u32;
+        let level = self.random_level();
+        self.vectors.push_row(feature).expect("维度已检查");
+        self.neighbors.push(vec![vec![]; level + 1]);
+        let Some(entry) = self.entry else {
+            self.entry = Some(id);
+            return id as usize;
+        };
+        let top = self.neighbors[entry as usize].len() - 1;
+        let mut nearest = vec![Candidate {
+            distance: self.distance(feature, entry),
+            id: entry,
+        }];
+        for layer in (level + 1..=top).rev() {
+            nearest = self.search_layer(feature, &nearest, 1, layer);
+        }
+        for layer in (0..=level.min(top)).rev() {
+            let found = self.search_layer(feature, &nearest, self.config.ef_construction.max(1), layer);
+            let max = self.max_neighbors(layer);
+            let selected: Vec<u32> = found.iter().take(max).map(|c| c.id).collect();
+            for &neighbor in &selected {
+                self.connect(neighbor, id, layer);
+            }
+            self.neighbors[id as usize][layer] = selected;
+            nearest = found;
+        }
+        if level > top {
+            self.entry = Some(id);
+        }
+        id as usize
+    }
+
+    /// 把node加入neighbor的邻居，超过上限时只保留最近的
+    fn connect(&mut self, neighbor: u32, node: u32, layer: usize) {
+        let max = self.max_neighbors(layer);
+        let mut list = std::mem::take(&mut self.neighbors[neighbor as usize][layer]);
+        list.push(node);
+        if list.len() > max {
+            let base = self.vectors.row(neighbor as usize);
+            let mut scored: Vec<Candidate> = list
+                .iter()
+                .map(|&id| Candidate {
+                    distance: self.metric.distance(base, self.vectors.row(id as usize)),
+                    id,
+                })
+                .collect();
+            scored.sort();
+            list = scored.into_iter().take(max).map(|c| c.id).collect();
+        }
+        self.neighbors[neighbor as usize][layer] = list;
+    }
+
+    /// 在一层中从entries出发做best-first搜索，返回最近的ef个节点(按距离从近到远)
+    fn search_layer(&self, query: ArrayView1<f32>, entries: &[Candidate], ef: usize, layer: usize) -> Vec<Candidate> {
+        let mut visited = vec![false; self.len()];
+        let mut candidates: BinaryHeap<Reverse<Candidate>> = BinaryHeap::new();
+        let mut results: BinaryHeap<Candidate> = BinaryHeap::new();
+        for &entry in entries {
+            if !std::mem::replace(&mut visited[entry.id as usize], true) {
+                candidates.push(Reverse(entry));
+                results.push(entry);
+            }
+        }
+        while results.len() > ef {
+            results.pop();
+        }
+        while let Some(Reverse(current)) = candidates.pop() {
+            if results.len() >= ef && results.peek().is_some_and(|worst| current.distance > worst.distance) {
+                break;
+            }
+            let Some(list) = self.neighbors[current.id as usize].get(layer) else {
+                continue;
+            };
+            for &id in list {
+                if std::mem::replace(&mut visited[id as usize], true) {
+                    continue;
+                }
+                let candidate = Candidate {
+                    distance: self.distance(query, id),
+                    id,
+                };
+                if results.len() < ef || results.peek().is_some_and(|worst| candidate < *worst) {
+                    candidates.push(Reverse(candidate));
+                    results.push(candidate);
+                    if results.len() > ef {
+                        results.pop();
+                    }
+                }
+            }
+        }
+        results.into_sorted_vec()
+    }
+
+    /// 最近的k个节点，返回 (节点编号, 距离)，按距离从近到远
+    pub fn search(&self, query: ArrayView1<f32>, k: usize) -> Vec<(usize, f32)> {
+        self.search_with(query, k, self.config.ef_search)
+    }
+
+    /// 与 [`search`](Self::search) 相同，本次使用指定的 `ef_search`
+    pub fn search_with(&self, query: ArrayView1<f32>, k: usize, ef_search: usize) -> Vec<(usize, f32)> {
+        let Some(entry) = self.entry else {
+            return vec![];
+        };
+        let top = self.neighbors[entry as usize].len() - 1;
+        let mut nearest = vec![Candidate {
+            distance: self.distance(query, entry),
+            id: entry,
+        }];
+        for layer in (1..=top).rev() {
+            nearest = self.search_layer(query, &nearest, 1, layer);
+        }
+        self.search_layer(query, &nearest, ef_search.max(k).max(1), 0)
+            .into_iter()
+            .take(k)
+            .map(|c| (c.id as usize, c.distance))
+            .collect()
+    }
+
+    /// 与逐个计算距离的精确结果相比，前k个近邻的召回率，用于选择 `ef_search`
+    pub fn recall(&self, queries: &ArrayView2<f32>, k: usize) -> f32 {
+        if queries.nrows() == 0 || k == 0 || self.is_empty() {
+            return 1.0;
+        }
+        let mut hits = 0;
+        let mut total = 0;
+        for query in queries.axis_iter(Axis(0)) {
+            let mut exact: Vec<Candidate> = (0..self.len() as u32)
+                .map(|id| Candidate {
+                    distance: self.distance(query, id),
+                    id,
+                })
+                .collect();
+            exact.sort();
+            let exact: Vec<usize> = exact.iter().take(k).map(|c| c.id as usize).collect();
+            let found = self.search(query, k);
+            hits += found.iter().filter(|(id, _)| exact.contains(id)).count();
+            total += exact.len();
+        }
+        hits as f32 / total as f32
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+        if let Some(parent) = path.as_ref().parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, self.to_bytes())?;
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        Self::from_bytes(&fs::read(path)?)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut w = Vec::new();
+        let u32s = |w: &mut Vec<u8>, values: &[u32]| values.iter().for_each(|v| w.extend_from_slice(&v.to_le_bytes()));
+        w.extend_from_slice(MAGIC);
+        u32s(&mut w, &[VERSION]);
+        w.push(metric_tag(self.metric));
+        let config = &self.config;
+        u32s(&mut w, &[config.m as u32, config.ef_construction as u32, config.ef_search as u32]);
+        w.extend_from_slice(&config.seed.to_le_bytes());
+        w.extend_from_slice(&self.rng.to_le_bytes());
+        u32s(&mut w, &[self.dim() as u32, self.len() as u32, self.entry.unwrap_or(NONE)]);
+        self.vectors.iter().for_each(|v| w.extend_from_slice(&v.to_le_bytes()));
+        for layers in &self.neighbors {
+            u32s(&mut w, &[layers.len() as u32]);
+            for list in layers {
+                u32s(&mut w, &[list.len() as u32]);
+                u32s(&mut w, list);
+            }
+        }
+        w
+    }
+
+    pub fn from_bytes(mut bytes: &[u8]) -> Result<Self, Box<dyn Error>> {
+        let r = &mut bytes;
+        let mut magic = [0u8; 4];
+        r.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err("不是HNSW索引文件".into());
+        }
+        let version = read_u32(r)?;
+        if version != VERSION {
+            return Err(format!("不支持的HNSW索引版本 {}", version).into());
+        }
+        let mut tag = [0u8; 1];
+        r.read_exact(&mut tag)?;
+        let metric = metric_from_tag(tag[0])?;
+        let (m, ef_construction, ef_search) = (read_u32(r)? as usize, read_u32(r)? as usize, read_u32(r)? as usize);
+        let seed = read_u64(r)?;
+        let rng = read_u64(r)?;
+        let (dim, count, entry) = (read_u32(r)? as usize, read_u32(r)? as usize, read_u32(r)?);
+        if dim == 0 {
+            return Err("HNSW索引的特征维度为0".into());
+        }
+        // 每个节点至少有特征与层数、第0层邻居数两个u32
+        if count.checked_mul(dim * 4 + 8).is_none_or(|n| n > r.len()) {
+            return Err("HNSW索引文件已截断".into());
+        }
+        let mut data = Vec::with_capacity(count * dim);
+        let mut buf = [0u8; 4];
+        for _ in 0..count * dim {
+            r.read_exact(&mut buf)?;
+            data.push(f32::from_le_bytes(buf));
+        }
+        let mut neighbors = Vec::with_capacity(count);
+        for _ in 0..count {
+            let layers = read_u32(r)? as usize;
+            if layers == 0 || layers > r.len() {
+                return Err("HNSW索引文件已损坏".into());
+            }
+            let mut node = Vec::with_capacity(layers);
+            for _ in 0..layers {
+                let len = read_u32(r)? as usize;
+                let list = (0..len).map(|_| read_u32(r)).collect::<Result<Vec<_>, _>>()?;
+                if list.iter().any(|&id| id as usize >= count) {
+                    return Err("HNSW索引文件已损坏".into());
+                }
+                node.push(list);
+            }
+            neighbors.push(node);
+        }
+        let entry = (entry != NONE).then_some(entry);
+        if entry.is_some_and(|e| e as usize >= count) || (entry.is_none() && count > 0) {
+            return Err("HNSW索引文件已损坏".into());
+        }
+        Ok(HnswIndex {
+            config: HnswConfig {
+                m,
+                ef_construction,
+                ef_search,
+                seed,
+            },
+            metric,
+            vectors: Array2::from_shape_vec((count, dim), data)?,
+            neighbors,
+            entry,
+            rng,
+        })
+    }
+}
+
+fn read_u64(r: &mut &[u8]) -> Result<u64, Box<dyn Error>> {
+    let mut buf = [0u8; 8];
+    r.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
@@ -0,0 +1,81 @@
+use crate::error::CaptchaError;
+use ndarray::{Array1, Array2, ArrayView1, ArrayView2, Axis};
+
+/// 特征之间的距离度量，值越小越相似
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum DistanceMetric {
+    /// 欧氏距离(L2)
+    #[default]
+    Euclidean,
+    /// 曼哈顿距离(L1)
+    Manhattan,
+    /// 余弦距离 `1 - cos(a, b)`，适用于用余弦损失训练的孪生网络
+    Cosine,
+    /// 负内积 `-a·b`，适用于已归一化的特征
+    Dot,
+}
+
+impl DistanceMetric {
+    pub fn distance(&self, a: ArrayView1<f32>, b: ArrayView1<f32>) -> f32 {
+        match self {
+            DistanceMetric::Euclidean => a.iter().zip(b).map(|(x, y)| (x - y).powi(2)).sum::<f32>().sqrt(),
+            DistanceMetric::Manhattan => a.iter().zip(b).map(|(x, y)| (x - y).abs()).sum(),
+            DistanceMetric::Cosine => {
+                let norm = a.dot(&a).sqrt() * b.dot(&b).sqrt();
+                if norm == 0.0 { 1.0 } else { 1.0 - a.dot(&b) / norm }
+            }
+            DistanceMetric::Dot => -a.dot(&b),
+        }
+    }
+}
+
+/// 构建成本矩阵，行为query，列为candidate
+///
+/// 欧氏、余弦和内积距离通过矩阵乘法一次算出 (‖a‖² + ‖b‖² − 2a·b)，不再逐对分配临时数组
+pub fn cost_matrix(queries: &ArrayView2<f32>, candidates: &ArrayView2<f32>, metric: DistanceMetric) -> Array2<f32> {
+    match metric {
+        DistanceMetric::Euclidean => {
+            let q_norms = squared_norms(queries).insert_axis(Axis(1));
+            let c_norms = squared_norms(candidates).insert_axis(Axis(0));
+            let mut matrix = queries.dot(&candidates.t());
+            matrix *= -2.0;
+            matrix += &q_norms;
+            matrix += &c_norms;
+            // 浮点误差可能产生极小的负数，f32::max会把NaN变成0，这里保留NaN
+            matrix.mapv_inplace(|x| if x < 0.0 { 0.0 } else { x.sqrt() });
+            matrix
+        }
+        DistanceMetric::Manhattan => {
+            let mut matrix = Array2::zeros((queries.nrows(), candidates.nrows()));
+            for (mut row, q_feat) in matrix.rows_mut().into_iter().zip(queries.rows()) {
+                let diff = candidates - &q_feat.insert_axis(Axis(0));
+                row.assign(&diff.mapv(f32::abs).sum_axis(Axis(1)));
+            }
+            matrix
+        }
+        DistanceMetric::Cosine => {
+            let mut matrix = normalized(queries).dot(&normalized(candidates).t());
+            matrix.mapv_inplace(|x| 1.0 - x);
+            matrix
+        }
+        DistanceMetric::Dot => -queries.dot(&candidates.t()),
+    }
+}
+
+/// 检查成本矩阵中没有NaN或无穷，全黑的图像块可能使孪生网络输出NaN
+pub fn validate_costs(matrix: &Array2<f32>) -> Result<(), CaptchaError> {
+    match matrix.indexed_iter().find(|(_, c)| !c.is_finite()) {
+        Some(((query, candidate), _)) => Err(CaptchaError::InvalidCost { query, candidate }),
+        None => Ok(()),
+    }
+}
+
+fn squared_norms(features: &ArrayView2<f32>) -> Array1<f32> {
+    features.map_axis(Axis(1), |row| row.dot(&row))
+}
+
+/// 按行归一化，零向量保持为零，与 [`DistanceMetric::distance`] 中余弦距离为1的约定一致
+fn normalized(features: &ArrayView2<f32>) -> Array2<f32> {
+    let norms = squared_norms(features).mapv(|x| if x == 0.0 { 1.0 } else { x.sqrt() });
+    features / &norms.insert_axis(Axis(1))
+}
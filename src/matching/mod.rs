@@ -0,0 +1,19 @@
+//! 特征匹配相关的通用工具
+
+mod assign;
+mod augment;
+mod calibration;
+mod hnsw;
+mod metric;
+mod siamese;
+mod sinkhorn;
+mod template;
+
+pub use assign::{Assignment, MatchStrategy, assign, assign_with_options, assign_with_strategy, greedy};
+pub use augment::{CostPooling, TtaConfig, View, pooled_costs};
+pub use calibration::Calibration;
+pub use hnsw::{HnswConfig, HnswIndex};
+pub use metric::{DistanceMetric, cost_matrix, validate_costs};
+pub use siamese::{SiameseConfig, SiameseEmbedder, SiameseMatcher};
+pub use sinkhorn::{Hysteresis, SinkhornConfig, SoftAssignment, sinkhorn, soft_assign};
+pub use template::{TemplateBank, TemplateMatch};
@@ -0,0 +1,278 @@
+use super::{
+    Assignment, Calibration, DistanceMetric, MatchStrategy, SinkhornConfig, assign_with_strategy, cost_matrix, soft_assign, validate_costs,
+};
+use crate::backend::{InferenceBackend, TensorPool};
+use crate::input;
+use crate::lap::LapJVOptions;
+use image::{DynamicImage, RgbImage};
+use image::imageops::FilterType;
+use ndarray::{Array2, Array4, ArrayView2, Axis, Ix2, Ix4, concatenate, s};
+use std::error::Error;
+use std::sync::Arc;
+
+#[derive(Debug, Clone)]
+pub struct SiameseConfig {
+    /// 图像块缩放后的边长
+    pub input_size: u32,
+    pub filter: FilterType,
+    pub metric: DistanceMetric,
+    /// 分配的求解方式
+    pub strategy: MatchStrategy,
+    /// LAPJV比较约化成本的容差，以及是否提升到f64求解
+    pub lapjv: LapJVOptions,
+    /// [`MatchStrategy::Sinkhorn`] 的参数
+    pub sinkhorn: SinkhornConfig,
+    /// 距离到匹配概率的转换，构建时从模型元数据读取
+    pub calibration: Calibration,
+    /// 单次推理最多的图像块数，图像块很多时分批推理以降低内存峰值；模型的批次维度固定时取模型的值
+    pub max_batch_size: usize,
+}
+
+impl Default for SiameseConfig {
+    fn default() -> Self {
+        SiameseConfig {
+            input_size: 96,
+            filter: FilterType::Lanczos3,
+            metric: DistanceMetric::default(),
+            strategy: MatchStrategy::default(),
+            lapjv: LapJVOptions::default(),
+            sinkhorn: SinkhornConfig::default(),
+            calibration: Calibration::default(),
+            max_batch_size: 16,
+        }
+    }
+}
+
+/// 孪生网络匹配器：缩放图像块 → 提取特征 → 成本矩阵 → 分配
+#[derive(Debug, Clone)]
+pub struct SiameseMatcher {
+    backend: Arc<dyn InferenceBackend>,
+    config: SiameseConfig,
+    pool: Arc<TensorPool>,
+}
+
+impl SiameseMatcher {
+    pub fn new(backend: Arc<dyn InferenceBackend>) -> Self {
+        SiameseMatcher {
+            backend,
+            config: Default::default(),
+            pool: Default::default(),
+        }
+    }
+
+    /// 输入张量使用的缓冲区池，见 [`TensorPool`]
+    pub fn with_pool(mut self, pool: Arc<TensorPool>) -> Self {
+        self.pool = pool;
+        self
+    }
+
+    pub fn pool(&self) -> &Arc<TensorPool> {
+        &self.pool
+    }
+
+    pub fn with_config(mut self, config: SiameseConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn config(&self) -> &SiameseConfig {
+        &self.config
+    }
+
+    pub fn backend(&self) -> &dyn InferenceBackend {
+        self.backend.as_ref()
+    }
+
+    /// 单次推理最多的图像块数，不超过模型固定的批次大小
+    pub fn max_batch_size(&self) -> usize {
+        let limit = self.backend.batch_size().unwrap_or(usize::MAX);
+        self.config.max_batch_size.clamp(1, limit.max(1))
+    }
+
+    /// 为queries中的每个图像块在candidates中找到最相似且互不重复的一个
+    pub fn match_crops(&self, queries: &[RgbImage], candidates: &[RgbImage]) -> Result<Assignment, Box<dyn Error>> {
+        self.match_crops_with_filter(queries, candidates, self.config.filter)
+    }
+
+    /// 与 [`match_crops`](Self::match_crops) 相同，但使用指定的缩放滤波器
+    pub fn match_crops_with_filter(
+        &self,
+        queries: &[RgbImage],
+        candidates: &[RgbImage],
+        filter: FilterType,
+    ) -> Result<Assignment, Box<dyn Error>> {
+        // query与candidate在同一批次中提取特征
+        let crops: Vec<&RgbImage> = queries.iter().chain(candidates).collect();
+        let features = self.embed(&crops, filter)?;
+        let (query_features, candidate_features) = features.view().split_at(Axis(0), queries.len());
+        self.assign_features(&query_features, &candidate_features)
+    }
+
+    /// 根据已提取的特征构建成本矩阵并求解分配
+    ///
+    /// 成本矩阵含NaN或无穷时返回 [`CaptchaError::InvalidCost`](crate::error::CaptchaError::InvalidCost)
+    pub fn assign_features(
+        &self,
+        query_features: &ArrayView2<f32>,
+        candidate_features: &ArrayView2<f32>,
+    ) -> Result<Assignment, Box<dyn Error>> {
+        self.assign_features_with(query_features, candidate_features, self.config.strategy)
+    }
+
+    /// 与 [`assign_features`](Self::assign_features) 相同，本次使用指定的求解方式
+    pub fn assign_features_with(
+        &self,
+        query_features: &ArrayView2<f32>,
+        candidate_features: &ArrayView2<f32>,
+        strategy: MatchStrategy,
+    ) -> Result<Assignment, Box<dyn Error>> {
+        self.assign_costs_with(cost_matrix(query_features, candidate_features, self.config.metric), strategy)
+    }
+
+    /// 对已经构建好的成本矩阵求解分配，例如按多个模板取最小距离后的矩阵
+    pub fn assign_costs(&self, cost_matrix: Array2<f32>) -> Result<Assignment, Box<dyn Error>> {
+        self.assign_costs_with(cost_matrix, self.config.strategy)
+    }
+
+    /// 与 [`assign_costs`](Self::assign_costs) 相同，本次使用指定的求解方式
+    pub fn assign_costs_with(&self, cost_matrix: Array2<f32>, strategy: MatchStrategy) -> Result<Assignment, Box<dyn Error>> {
+        validate_costs(&cost_matrix)?;
+        let (matches, soft) = match strategy {
+            MatchStrategy::Sinkhorn => {
+                let soft = soft_assign(&cost_matrix, &self.config.sinkhorn, None)?;
+                (soft.matches, Some(soft.probabilities))
+            }
+            _ => (assign_with_strategy(&cost_matrix, strategy, &self.config.lapjv)?, None),
+        };
+        let probabilities = matches
+            .iter()
+            .enumerate()
+            .map(|(q, m)| m.map(|c| self.config.calibration.probability(cost_matrix[[q, c]])))
+            .collect();
+        Ok(Assignment {
+            cost_matrix,
+            matches,
+            probabilities,
+            soft,
+        })
+    }
+
+    /// 提取特征，输出 (图像块数, 特征维度)
+    ///
+    /// 每批最多 [`max_batch_size`](Self::max_batch_size) 个图像块，只为当前批次分配输入张量
+    pub fn embed(&self, crops: &[&RgbImage], filter: FilterType) -> Result<Array2<f32>, Box<dyn Error>> {
+        if crops.is_empty() {
+            return self.embed_batch(crops, filter);
+        }
+        let mut features = crops
+            .chunks(self.max_batch_size())
+            .map(|chunk| self.embed_batch(chunk, filter))
+            .collect::<Result<Vec<_>, _>>()?;
+        if features.len() == 1 {
+            return Ok(features.remove(0));
+        }
+        let views: Vec<_> = features.iter().map(|f| f.view()).collect();
+        Ok(concatenate(Axis(0), &views)?)
+    }
+
+    /// 模型的批次维度固定时，不足一批的部分补零，输出中对应的行被丢弃
+    fn embed_batch(&self, crops: &[&RgbImage], filter: FilterType) -> Result<Array2<f32>, Box<dyn Error>> {
+        let size = self.config.input_size;
+        let rows = match self.backend.batch_size() {
+            Some(fixed) if !crops.is_empty() => fixed.max(crops.len()),
+            _ => crops.len(),
+        };
+        let mut batch: Array4<f32> = self.pool.zeros(Ix4(rows, 3, size as usize, size as usize));
+        for (i, crop) in crops.iter().enumerate() {
+            let resized = image::imageops::resize(*crop, size, size, filter);
+            for (x, y, pixel) in resized.enumerate_pixels() {
+                for c in 0..3 {
+                    batch[[i, c, y as usize, x as usize]] = pixel[c] as f32 / 255.0;
+                }
+            }
+        }
+        // 输入张量按值交给后端，用完后放回缓冲区池
+        let features = self
+            .backend
+            .run_single_pooled(batch, &self.pool)?
+            .into_f32()?
+            .into_dimensionality::<Ix2>()?;
+        if rows > crops.len() && features.nrows() == rows {
+            return Ok(features.slice_move(s![..crops.len(), ..]));
+        }
+        Ok(features)
+    }
+}
+
+/// 只提取特征的孪生网络，输出与ChineseClick0内部使用的特征相同
+///
+/// 服务商的字形有限时，可以预先提取已知字形的特征，再用 [`cost_matrix`](Self::cost_matrix) 与新验证码的图像块比较
+#[derive(Debug, Clone)]
+pub struct SiameseEmbedder {
+    matcher: SiameseMatcher,
+}
+
+#[cfg(feature = "chinese_click_0")]
+impl crate::captcha::CaptchaBreaker for SiameseEmbedder {
+    fn build(captcha_environment: &crate::environment::CaptchaEnvironment) -> Result<Self, Box<dyn Error>> {
+        use crate::model::Model;
+        let backend = captcha_environment.load_models(vec![Model::Siamese])?.remove(0);
+        let calibration = captcha_environment
+            .model_metadata(Model::Siamese)
+            .and_then(|metadata| Calibration::from_metadata(&metadata))
+            .unwrap_or_default();
+        Ok(SiameseEmbedder::new(backend).with_config(SiameseConfig {
+            calibration,
+            ..Default::default()
+        }))
+    }
+
+    fn warmup(&self) -> Result<(), Box<dyn Error>> {
+        crate::warmup::warmup_backends(&[self.matcher.backend()])
+    }
+}
+
+impl SiameseEmbedder {
+    pub fn new(backend: Arc<dyn InferenceBackend>) -> Self {
+        SiameseEmbedder {
+            matcher: SiameseMatcher::new(backend),
+        }
+    }
+
+    pub fn with_config(mut self, config: SiameseConfig) -> Self {
+        self.matcher = self.matcher.with_config(config);
+        self
+    }
+
+    pub fn config(&self) -> &SiameseConfig {
+        self.matcher.config()
+    }
+
+    /// 提取特征，输出 (图片数, 特征维度)，透明像素合成到白色背景上
+    pub fn embed(&self, images: &[DynamicImage]) -> Result<Array2<f32>, Box<dyn Error>> {
+        let crops: Vec<RgbImage> = images.iter().map(|image| input::to_rgb8(image, input::DEFAULT_BACKGROUND)).collect();
+        self.embed_rgb(&crops)
+    }
+
+    /// 与 [`embed`](Self::embed) 相同，输入为已转换的RGB图像块
+    pub fn embed_rgb(&self, crops: &[RgbImage]) -> Result<Array2<f32>, Box<dyn Error>> {
+        let crops: Vec<&RgbImage> = crops.iter().collect();
+        self.matcher.embed(&crops, self.config().filter)
+    }
+
+    /// 按配置的距离度量计算两组特征之间的距离，行为queries，列为bank
+    pub fn cost_matrix(&self, queries: &ArrayView2<f32>, bank: &ArrayView2<f32>) -> Array2<f32> {
+        cost_matrix(queries, bank, self.config().metric)
+    }
+
+    /// 在已有特征上求解分配，见 [`SiameseMatcher::assign_features`]
+    pub fn assign(&self, queries: &ArrayView2<f32>, bank: &ArrayView2<f32>) -> Result<Assignment, Box<dyn Error>> {
+        self.matcher.assign_features(queries, bank)
+    }
+}
+
+impl From<SiameseMatcher> for SiameseEmbedder {
+    fn from(matcher: SiameseMatcher) -> Self {
+        SiameseEmbedder { matcher }
+    }
+}
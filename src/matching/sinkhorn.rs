@@ -0,0 +1,132 @@
+use super::assign::assign_with_options;
+use crate::lap::{LapJVError, LapJVOptions, Solver};
+use ndarray::{Array2, s};
+
+/// Sinkhorn软分配的参数
+#[derive(Debug, Clone, PartialEq)]
+pub struct SinkhornConfig {
+    /// 熵正则的强度，与成本(距离)的量级相同；越大概率越平滑，越小越接近硬分配
+    pub epsilon: f32,
+    pub max_iterations: usize,
+    /// 每行概率之和与1的最大偏差小于该值时停止迭代
+    pub tolerance: f32,
+    /// 连续求解时上一次的匹配在概率上的优势，新的匹配需要超出该值才会替换，见 [`Hysteresis`]
+    pub hysteresis: f32,
+}
+
+impl Default for SinkhornConfig {
+    fn default() -> Self {
+        SinkhornConfig {
+            epsilon: 0.1,
+            max_iterations: 100,
+            tolerance: 1e-4,
+            hysteresis: 0.15,
+        }
+    }
+}
+
+/// 软分配的结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct SoftAssignment {
+    /// 第i行分配给第j列的概率，列数不少于行数时每行之和为1
+    pub probabilities: Array2<f32>,
+    /// 由概率得到的一一对应的匹配，列数不足时为None
+    pub matches: Vec<Option<usize>>,
+}
+
+/// 熵正则最优传输(Sinkhorn迭代)，返回与costs形状相同的软分配矩阵
+///
+/// 不是方阵时按 [`assign`](super::assign) 的方式用常数补齐，在对数域迭代以避免下溢
+pub fn sinkhorn(costs: &Array2<f32>, config: &SinkhornConfig) -> Array2<f32> {
+    let (rows, cols) = costs.dim();
+    if rows == 0 || cols == 0 {
+        return Array2::zeros((rows, cols));
+    }
+    let max = costs.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    let epsilon = config.epsilon.max(f32::EPSILON) as f64;
+    let n = rows.max(cols);
+    let mut kernel = Array2::from_elem((n, n), -(max as f64) / epsilon);
+    kernel
+        .slice_mut(s![..rows, ..cols])
+        .assign(&costs.mapv(|c| -(c as f64) / epsilon));
+    let (mut f, mut g) = (vec![0.0f64; n], vec![0.0f64; n]);
+    let logsumexp = |values: &mut dyn Iterator<Item = f64>| {
+        let values: Vec<f64> = values.collect();
+        let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        max + values.iter().map(|v| (v - max).exp()).sum::<f64>().ln()
+    };
+    for _ in 0..config.max_iterations.max(1) {
+        for (i, fi) in f.iter_mut().enumerate() {
+            *fi = -logsumexp(&mut (0..n).map(|j| kernel[[i, j]] + g[j]));
+        }
+        for (j, gj) in g.iter_mut().enumerate() {
+            *gj = -logsumexp(&mut (0..n).map(|i| kernel[[i, j]] + f[i]));
+        }
+        // 列在更新g后精确归一，只需检查行
+        let error = (0..n)
+            .map(|i| ((0..n).map(|j| (kernel[[i, j]] + f[i] + g[j]).exp()).sum::<f64>() - 1.0).abs())
+            .fold(0.0, f64::max);
+        if error < config.tolerance as f64 {
+            break;
+        }
+    }
+    Array2::from_shape_fn((rows, cols), |(i, j)| (kernel[[i, j]] + f[i] + g[j]).exp() as f32)
+}
+
+/// Sinkhorn软分配后按 `1 - 概率` 求解一一对应的匹配
+///
+/// 给出previous时其中的匹配在概率上享有 `hysteresis` 的优势，概率接近的答案不会来回跳变
+pub fn soft_assign(
+    costs: &Array2<f32>,
+    config: &SinkhornConfig,
+    previous: Option<&[Option<usize>]>,
+) -> Result<SoftAssignment, LapJVError> {
+    let probabilities = sinkhorn(costs, config);
+    // 加上hysteresis使扣除优势后的成本仍不为负
+    let hysteresis = config.hysteresis.max(0.0);
+    let mut decision = probabilities.mapv(|p| 1.0 + hysteresis - p);
+    let (rows, cols) = costs.dim();
+    if let Some(previous) = previous.filter(|previous| previous.len() == rows) {
+        for (i, &j) in previous.iter().enumerate() {
+            if let Some(j) = j.filter(|&j| j < cols) {
+                decision[[i, j]] -= hysteresis;
+            }
+        }
+    }
+    let matches = assign_with_options(&decision, Solver::LapJV, &LapJVOptions::default())?;
+    Ok(SoftAssignment { probabilities, matches })
+}
+
+/// 带滞回的连续软分配，例如视频流或重试中对同一个验证码反复求解
+///
+/// 保存上一次的匹配，成本矩阵的行数改变时不再使用上一次的匹配
+#[derive(Debug, Clone, Default)]
+pub struct Hysteresis {
+    config: SinkhornConfig,
+    previous: Option<Vec<Option<usize>>>,
+}
+
+impl Hysteresis {
+    pub fn new(config: SinkhornConfig) -> Self {
+        Hysteresis { config, previous: None }
+    }
+
+    pub fn config(&self) -> &SinkhornConfig {
+        &self.config
+    }
+
+    /// 求解并记住本次的匹配
+    pub fn update(&mut self, costs: &Array2<f32>) -> Result<SoftAssignment, LapJVError> {
+        let assignment = soft_assign(costs, &self.config, self.previous.as_deref())?;
+        self.previous = Some(assignment.matches.clone());
+        Ok(assignment)
+    }
+
+    pub fn previous(&self) -> Option<&[Option<usize>]> {
+        self.previous.as_deref()
+    }
+
+    pub fn reset(&mut self) {
+        self.previous = None;
+    }
+}
@@ -0,0 +1,245 @@
+use super::{DistanceMetric, HnswConfig, HnswIndex};
+use ndarray::{Array2, ArrayView1, ArrayView2, Axis};
+use std::error::Error;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+const MAGIC: &[u8; 4] = b"CBTB";
+const VERSION: u32 = 1;
+
+/// 与模板字形最近邻查找的结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemplateMatch {
+    /// 模板在字形库中的下标
+    pub index: usize,
+    pub label: String,
+    pub distance: f32,
+}
+
+/// 带标签的字形特征库
+///
+/// 用 [`SiameseEmbedder`](super::SiameseEmbedder) 提取已知字形的特征后加入，查找时返回距离最近的模板。
+/// 使用小端序的简单二进制格式保存，特征与提取它的模型绑定，更换孪生网络后需要重新提取。
+/// 模板很多时可以 [`build_index`](Self::build_index) 改为近似最近邻查找
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemplateBank {
+    labels: Vec<String>,
+    features: Array2<f32>,
+    metric: DistanceMetric,
+    index: Option<HnswIndex>,
+}
+
+impl TemplateBank {
+    /// dim为特征维度，metric需要与匹配时使用的距离度量一致
+    pub fn new(dim: usize, metric: DistanceMetric) -> Self {
+        TemplateBank {
+            labels: vec![],
+            features: Array2::zeros((0, dim)),
+            metric,
+            index: None,
+        }
+    }
+
+    pub fn dim(&self) -> usize {
+        self.features.ncols()
+    }
+
+    pub fn metric(&self) -> DistanceMetric {
+        self.metric
+    }
+
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+
+    pub fn labels(&self) -> &[String] {
+        &self.labels
+    }
+
+    /// 所有模板的特征，第i行对应 `labels()[i]`
+    pub fn features(&self) -> ArrayView2<'_, f32> {
+        self.features.view()
+    }
+
+    /// 加入一个模板，同一个字可以有多个模板(例如不同字体)
+    pub fn insert(&mut self, label: impl Into<String>, feature: ArrayView1<f32>) -> Result<(), Box<dyn Error>> {
+        if feature.len() != self.dim() {
+            return Err(format!("模板特征维度为{}，字形库为{}", feature.len(), self.dim()).into());
+        }
+        self.features.push_row(feature)?;
+        self.labels.push(label.into());
+        if let Some(index) = &mut self.index {
+            index.insert(feature);
+        }
+        Ok(())
+    }
+
+    /// 为所有模板构建HNSW索引，之后的查找使用近似最近邻，新加入的模板也会加入索引
+    pub fn build_index(&mut self, config: HnswConfig) {
+        self.index = Some(HnswIndex::build(&self.features.view(), self.metric, config));
+    }
+
+    /// 使用保存的索引，索引需要是为同样的模板按同样的顺序构建的
+    pub fn with_index(mut self, index: HnswIndex) -> Result<Self, Box<dyn Error>> {
+        if index.len() != self.len() || index.dim() != self.dim() || index.metric() != self.metric {
+            return Err("HNSW索引与字形库不一致".into());
+        }
+        self.index = Some(index);
+        Ok(self)
+    }
+
+    pub fn index(&self) -> Option<&HnswIndex> {
+        self.index.as_ref()
+    }
+
+    /// 修改查找参数，例如调大 `ef_search` 提高召回率
+    pub fn index_mut(&mut self) -> Option<&mut HnswIndex> {
+        self.index.as_mut()
+    }
+
+    /// 批量加入模板，`features` 的第i行为 `labels[i]` 的特征
+    pub fn extend<S: Into<String>>(
+        &mut self,
+        labels: impl IntoIterator<Item = S>,
+        features: &ArrayView2<f32>,
+    ) -> Result<(), Box<dyn Error>> {
+        let labels: Vec<String> = labels.into_iter().map(Into::into).collect();
+        if labels.len() != features.nrows() {
+            return Err(format!("{}个标签与{}个特征不对应", labels.len(), features.nrows()).into());
+        }
+        for (label, feature) in labels.into_iter().zip(features.axis_iter(Axis(0))) {
+            self.insert(label, feature)?;
+        }
+        Ok(())
+    }
+
+    /// 距离最近的模板，字形库为空时返回None；构建了索引时为近似结果
+    pub fn nearest(&self, feature: ArrayView1<f32>) -> Option<TemplateMatch> {
+        if let Some(index) = &self.index {
+            return index.search(feature, 1).first().map(|&(index, distance)| TemplateMatch {
+                index,
+                label: self.labels[index].clone(),
+                distance,
+            });
+        }
+        self.features
+            .axis_iter(Axis(0))
+            .map(|template| self.metric.distance(feature, template))
+            .enumerate()
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(index, distance)| TemplateMatch {
+                index,
+                label: self.labels[index].clone(),
+                distance,
+            })
+    }
+
+    /// 为每一行特征查找最近的模板，距离超过 `max_distance` 时为None
+    pub fn lookup(&self, features: &ArrayView2<f32>, max_distance: f32) -> Vec<Option<TemplateMatch>> {
+        features
+            .axis_iter(Axis(0))
+            .map(|feature| self.nearest(feature).filter(|m| m.distance <= max_distance))
+            .collect()
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+        if let Some(parent) = path.as_ref().parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, self.to_bytes())?;
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        Self::from_bytes(&fs::read(path)?)
+    }
+
+    /// 不包括索引，索引需要单独保存
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut w = Vec::with_capacity(16 + self.features.len() * 4);
+        w.extend_from_slice(MAGIC);
+        w.extend_from_slice(&VERSION.to_le_bytes());
+        w.push(metric_tag(self.metric));
+        w.extend_from_slice(&(self.dim() as u32).to_le_bytes());
+        w.extend_from_slice(&(self.len() as u32).to_le_bytes());
+        for (label, feature) in self.labels.iter().zip(self.features.axis_iter(Axis(0))) {
+            w.extend_from_slice(&(label.len() as u32).to_le_bytes());
+            w.extend_from_slice(label.as_bytes());
+            feature.iter().for_each(|v| w.extend_from_slice(&v.to_le_bytes()));
+        }
+        w
+    }
+
+    pub fn from_bytes(mut bytes: &[u8]) -> Result<Self, Box<dyn Error>> {
+        let r = &mut bytes;
+        let mut magic = [0u8; 4];
+        r.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err("不是字形库文件".into());
+        }
+        let version = read_u32(r)?;
+        if version != VERSION {
+            return Err(format!("不支持的字形库版本 {}", version).into());
+        }
+        let mut tag = [0u8; 1];
+        r.read_exact(&mut tag)?;
+        let metric = metric_from_tag(tag[0])?;
+        let dim = read_u32(r)? as usize;
+        let count = read_u32(r)? as usize;
+        if count.checked_mul(dim * 4 + 4).is_none_or(|n| n > r.len()) {
+            return Err("字形库文件已截断".into());
+        }
+        let mut labels = Vec::with_capacity(count);
+        let mut data = Vec::with_capacity(count * dim);
+        let mut buf = [0u8; 4];
+        for _ in 0..count {
+            let len = read_u32(r)? as usize;
+            if len > r.len() {
+                return Err("字形库文件已截断".into());
+            }
+            let (label, rest) = r.split_at(len);
+            *r = rest;
+            labels.push(String::from_utf8(label.to_vec())?);
+            for _ in 0..dim {
+                r.read_exact(&mut buf)?;
+                data.push(f32::from_le_bytes(buf));
+            }
+        }
+        Ok(TemplateBank {
+            labels,
+            features: Array2::from_shape_vec((count, dim), data)?,
+            metric,
+            index: None,
+        })
+    }
+}
+
+pub(super) fn metric_tag(metric: DistanceMetric) -> u8 {
+    match metric {
+        DistanceMetric::Euclidean => 0,
+        DistanceMetric::Manhattan => 1,
+        DistanceMetric::Cosine => 2,
+        DistanceMetric::Dot => 3,
+    }
+}
+
+pub(super) fn metric_from_tag(tag: u8) -> Result<DistanceMetric, Box<dyn Error>> {
+    match tag {
+        0 => Ok(DistanceMetric::Euclidean),
+        1 => Ok(DistanceMetric::Manhattan),
+        2 => Ok(DistanceMetric::Cosine),
+        3 => Ok(DistanceMetric::Dot),
+        _ => Err(format!("未知的距离度量 {}", tag).into()),
+    }
+}
+
+pub(super) fn read_u32(r: &mut &[u8]) -> Result<u32, Box<dyn Error>> {
+    let mut buf = [0u8; 4];
+    r.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
@@ -0,0 +1,168 @@
+use crate::backend::InferenceBackend;
+use crate::model::{Model, Normalization};
+#[cfg(feature = "native")]
+use ort::session::Session;
+use std::collections::HashMap;
+use std::error::Error;
+
+/// 模型对应的 [`Model`](crate::model::Model) 名称
+pub const KEY_MODEL: &str = "captcha_breaker.model";
+/// 模型服务的验证码类型，例如 `chinese_click_0`
+pub const KEY_BREAKER: &str = "captcha_breaker.breaker";
+/// 预处理预设名称
+pub const KEY_PREPROCESS: &str = "captcha_breaker.preprocess";
+/// 类别标签，以逗号分隔
+pub const KEY_CLASS_LABELS: &str = "captcha_breaker.class_labels";
+/// 匹配概率为0.5时的距离，见 [`Calibration`](crate::matching::Calibration)
+pub const KEY_CALIBRATION_THRESHOLD: &str = "captcha_breaker.calibration.threshold";
+/// 距离到概率的温度参数
+pub const KEY_CALIBRATION_TEMPERATURE: &str = "captcha_breaker.calibration.temperature";
+/// 模型自身的版本，例如训练日期或提交号，会记录在 [`SolveReport`](crate::report::SolveReport) 中
+pub const KEY_VERSION: &str = "captcha_breaker.version";
+/// 模型文件格式版本，见 [`Model::FORMAT_VERSION`]
+pub const KEY_FORMAT_VERSION: &str = "captcha_breaker.format_version";
+/// 第一个输入的形状，以逗号分隔，动态维度为-1，例如 `1,3,384,384`
+pub const KEY_INPUT_SHAPE: &str = "captcha_breaker.input_shape";
+/// 输入的归一化方式，见 [`Normalization`]
+pub const KEY_NORMALIZATION: &str = "captcha_breaker.normalization";
+
+const PREFIX: &str = "captcha_breaker.";
+
+/// 写在ONNX `metadata_props` 中的本crate专用元数据
+///
+/// 导出模型时写入这些键(例如python中 `onnx.helper.set_model_props`)，
+/// 放入模型目录后加载器会自动识别模型用途
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EmbeddedMetadata {
+    pub model: Option<String>,
+    pub breaker: Option<String>,
+    pub preprocess: Option<String>,
+    pub class_labels: Vec<String>,
+    pub version: Option<String>,
+    pub format_version: Option<u32>,
+    pub input_shape: Option<Vec<i64>>,
+    pub normalization: Option<String>,
+    /// 其他以 `captcha_breaker.` 开头的键
+    pub extra: HashMap<String, String>,
+}
+
+impl EmbeddedMetadata {
+    /// 从推理后端读取元数据
+    pub fn from_backend(backend: &dyn InferenceBackend) -> Result<Self, Box<dyn Error>> {
+        Ok(Self::from_props(backend.metadata()?))
+    }
+
+    /// 从已加载的session中读取元数据
+    #[cfg(feature = "native")]
+    pub fn from_session(session: &Session) -> Result<Self, Box<dyn Error>> {
+        let metadata = session.metadata()?;
+        let mut props = HashMap::new();
+        for key in metadata.custom_keys()? {
+            if let Some(value) = metadata.custom(&key)? {
+                props.insert(key, value);
+            }
+        }
+        Ok(Self::from_props(props))
+    }
+
+    pub fn from_props(props: HashMap<String, String>) -> Self {
+        let mut res = EmbeddedMetadata::default();
+        for (key, value) in props {
+            match key.as_str() {
+                KEY_MODEL => res.model = Some(value),
+                KEY_BREAKER => res.breaker = Some(value),
+                KEY_PREPROCESS => res.preprocess = Some(value),
+                KEY_CLASS_LABELS => {
+                    res.class_labels = value
+                        .split(',')
+                        .map(|s| s.trim().to_string())
+                        .filter(|s| !s.is_empty())
+                        .collect()
+                }
+                KEY_VERSION => res.version = Some(value.trim().to_string()),
+                KEY_FORMAT_VERSION if value.trim().parse::<u32>().is_ok() => {
+                    res.format_version = value.trim().parse().ok()
+                }
+                KEY_INPUT_SHAPE if parse_shape(&value).is_some() => res.input_shape = parse_shape(&value),
+                KEY_NORMALIZATION => res.normalization = Some(value.trim().to_string()),
+                _ if key.starts_with(PREFIX) => {
+                    res.extra.insert(key, value);
+                }
+                _ => {}
+            }
+        }
+        res
+    }
+
+    /// 转换为可以写入ONNX `metadata_props` 的键值对，供导出工具使用
+    pub fn to_props(&self) -> Vec<(String, String)> {
+        let mut props = vec![];
+        if let Some(model) = &self.model {
+            props.push((KEY_MODEL.to_string(), model.clone()));
+        }
+        if let Some(breaker) = &self.breaker {
+            props.push((KEY_BREAKER.to_string(), breaker.clone()));
+        }
+        if let Some(preprocess) = &self.preprocess {
+            props.push((KEY_PREPROCESS.to_string(), preprocess.clone()));
+        }
+        if !self.class_labels.is_empty() {
+            props.push((KEY_CLASS_LABELS.to_string(), self.class_labels.join(",")));
+        }
+        if let Some(version) = &self.version {
+            props.push((KEY_VERSION.to_string(), version.clone()));
+        }
+        if let Some(version) = self.format_version {
+            props.push((KEY_FORMAT_VERSION.to_string(), version.to_string()));
+        }
+        if let Some(shape) = &self.input_shape {
+            let dims: Vec<String> = shape.iter().map(|d| d.to_string()).collect();
+            props.push((KEY_INPUT_SHAPE.to_string(), dims.join(",")));
+        }
+        if let Some(normalization) = &self.normalization {
+            props.push((KEY_NORMALIZATION.to_string(), normalization.clone()));
+        }
+        let mut extra: Vec<_> = self.extra.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+        extra.sort();
+        props.extend(extra);
+        props
+    }
+
+    /// 检查元数据声明的模型名称、格式版本、输入形状与归一化方式是否与本crate对model的要求一致，
+    /// 没有声明的项不检查，backend用于对照声明的形状与实际的输入
+    pub fn check_compatible(&self, model: Model, backend: &dyn InferenceBackend) -> Result<(), String> {
+        if let Some(name) = self.model.as_deref().filter(|&name| name != model.name()) {
+            return Err(format!("模型文件声明为{}，不能作为{}使用", name, model.name()));
+        }
+        match self.format_version {
+            Some(version) if version > Model::FORMAT_VERSION => {
+                return Err(format!("模型格式版本{}高于本crate支持的{}，请升级captcha_breaker", version, Model::FORMAT_VERSION));
+            }
+            Some(version) if version < Model::FORMAT_VERSION => {
+                return Err(format!("模型格式版本{}低于本crate要求的{}，请重新下载模型文件", version, Model::FORMAT_VERSION));
+            }
+            _ => {}
+        }
+        if let Some(shape) = &self.input_shape {
+            if let Some(rank) = model.io_spec().input_rank.filter(|&rank| rank != shape.len()) {
+                return Err(format!("元数据中的输入形状{:?}不是{}维", shape, rank));
+            }
+            let actual = backend.inputs().first().map(|input| input.shape.as_slice()).unwrap_or_default();
+            let differs = actual.len() == shape.len() && actual.iter().zip(shape).any(|(&a, &b)| a > 0 && b > 0 && a != b);
+            if differs {
+                return Err(format!("元数据中的输入形状{:?}与模型的实际输入{:?}不一致", shape, actual));
+            }
+        }
+        if let Some(normalization) = &self.normalization {
+            let expected = model.normalization();
+            if Normalization::from_name(normalization) != Some(expected) {
+                return Err(format!("模型的输入归一化为{}，本crate按{}预处理", normalization, expected.name()));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_shape(value: &str) -> Option<Vec<i64>> {
+    value.split(',').map(|d| d.trim().parse().ok()).collect()
+}
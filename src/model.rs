@@ -1,5 +1,297 @@
-#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg(feature = "native")]
+use crate::backend::ElementType;
+use crate::backend::IoSpec;
+
+/// 模型精度
+///
+/// 非fp32的模型文件名带有后缀，例如 `siamese_int8.onnx`
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "config-file", derive(serde::Deserialize), serde(rename_all = "lowercase"))]
+pub enum Precision {
+    #[default]
+    Fp32,
+    /// 半精度，适合GPU
+    Fp16,
+    /// 8位量化，CPU上通常快2~4倍
+    Int8,
+}
+
+impl Precision {
+    /// 配置文件与环境变量中使用的名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            Precision::Fp32 => "fp32",
+            Precision::Fp16 => "fp16",
+            Precision::Int8 => "int8",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Precision> {
+        [Precision::Fp32, Precision::Fp16, Precision::Int8]
+            .into_iter()
+            .find(|p| p.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            Precision::Fp32 => "",
+            Precision::Fp16 => "_fp16",
+            Precision::Int8 => "_int8",
+        }
+    }
+
+    /// 依次尝试的精度，最后总是回退到fp32
+    pub fn fallbacks(self) -> Vec<Precision> {
+        match self {
+            Precision::Fp32 => vec![Precision::Fp32],
+            precision => vec![precision, Precision::Fp32],
+        }
+    }
+
+    /// 只使用CPU时是否适合该精度：CPU没有原生的fp16算子，ONNX Runtime会插入类型转换，反而更慢
+    pub fn suits_cpu(&self) -> bool {
+        *self != Precision::Fp16
+    }
+}
+
+/// 模型输入的归一化方式，像素值 `x` 在0~255之间
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Normalization {
+    /// `x / 255`
+    Unit,
+    /// `(x / 255 - 0.5) / 0.5`
+    Symmetric,
+    /// `(x / 255 - mean) / std`，使用CLIP的均值与标准差
+    Clip,
+    /// 音频的log-mel频谱，按mel通道做均值方差归一化
+    LogMel,
+}
+
+impl Normalization {
+    /// 元数据 `captcha_breaker.normalization` 中使用的名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            Normalization::Unit => "unit",
+            Normalization::Symmetric => "symmetric",
+            Normalization::Clip => "clip",
+            Normalization::LogMel => "log_mel",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Normalization> {
+        [Normalization::Unit, Normalization::Symmetric, Normalization::Clip, Normalization::LogMel]
+            .into_iter()
+            .find(|n| n.name() == name)
+    }
+}
+
+/// 各验证码实现使用的模型，只有开启了对应验证码feature的模型可用
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Model {
+    #[cfg(feature = "chinese_click_0")]
     Yolo11n,
+    #[cfg(feature = "chinese_click_0")]
     Siamese,
+    /// 可选的字形分割模型，见 [`segment::GlyphSegmenter`](crate::segment::GlyphSegmenter)
+    #[cfg(feature = "chinese_click_0")]
+    GlyphSegmenter,
+    #[cfg(feature = "text_captcha_0")]
+    Crnn,
+    #[cfg(feature = "slider_gap_0")]
+    SliderGap,
+    #[cfg(feature = "space_click_0")]
+    SpaceDetector,
+    #[cfg(feature = "space_click_0")]
+    SpaceAttributes,
+    #[cfg(feature = "nine_grid_0")]
+    ClipImage,
+    #[cfg(feature = "nine_grid_0")]
+    ClipText,
+    #[cfg(feature = "audio")]
+    AudioAsr,
+    #[cfg(feature = "word_order_0")]
+    CharOcr,
+    #[cfg(feature = "shape_click_0")]
+    ShapeDetector,
+    #[cfg(feature = "shape_click_0")]
+    ShapeClassifier,
+}
+
+impl Model {
+    /// 本crate支持的模型文件格式版本，模型的输入输出或预处理发生不兼容的变化时递增
+    pub const FORMAT_VERSION: u32 = 1;
+
+    /// 当前开启的feature下可用的模型
+    pub const ALL: &'static [Model] = &[
+        #[cfg(feature = "chinese_click_0")]
+        Model::Yolo11n,
+        #[cfg(feature = "chinese_click_0")]
+        Model::Siamese,
+        #[cfg(feature = "chinese_click_0")]
+        Model::GlyphSegmenter,
+        #[cfg(feature = "text_captcha_0")]
+        Model::Crnn,
+        #[cfg(feature = "slider_gap_0")]
+        Model::SliderGap,
+        #[cfg(feature = "space_click_0")]
+        Model::SpaceDetector,
+        #[cfg(feature = "space_click_0")]
+        Model::SpaceAttributes,
+        #[cfg(feature = "nine_grid_0")]
+        Model::ClipImage,
+        #[cfg(feature = "nine_grid_0")]
+        Model::ClipText,
+        #[cfg(feature = "audio")]
+        Model::AudioAsr,
+        #[cfg(feature = "word_order_0")]
+        Model::CharOcr,
+        #[cfg(feature = "shape_click_0")]
+        Model::ShapeDetector,
+        #[cfg(feature = "shape_click_0")]
+        Model::ShapeClassifier,
+    ];
+
+    /// 模型名称，与模型文件元数据中的 `captcha_breaker.model` 对应
+    pub fn name(&self) -> &'static str {
+        match *self {
+            #[cfg(feature = "chinese_click_0")]
+            Model::Yolo11n => "yolo11n",
+            #[cfg(feature = "chinese_click_0")]
+            Model::Siamese => "siamese",
+            #[cfg(feature = "chinese_click_0")]
+            Model::GlyphSegmenter => "glyph_segmenter",
+            #[cfg(feature = "text_captcha_0")]
+            Model::Crnn => "crnn",
+            #[cfg(feature = "slider_gap_0")]
+            Model::SliderGap => "slider_gap",
+            #[cfg(feature = "space_click_0")]
+            Model::SpaceDetector => "space_detector",
+            #[cfg(feature = "space_click_0")]
+            Model::SpaceAttributes => "space_attributes",
+            #[cfg(feature = "nine_grid_0")]
+            Model::ClipImage => "clip_image",
+            #[cfg(feature = "nine_grid_0")]
+            Model::ClipText => "clip_text",
+            #[cfg(feature = "audio")]
+            Model::AudioAsr => "audio_asr",
+            #[cfg(feature = "word_order_0")]
+            Model::CharOcr => "char_ocr",
+            #[cfg(feature = "shape_click_0")]
+            Model::ShapeDetector => "shape_detector",
+            #[cfg(feature = "shape_click_0")]
+            Model::ShapeClassifier => "shape_classifier",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Model> {
+        Model::ALL.iter().copied().find(|m| m.name() == name)
+    }
+
+    /// 默认的本地文件名
+    pub fn file_name(&self) -> &'static str {
+        match *self {
+            #[cfg(feature = "chinese_click_0")]
+            Model::Yolo11n => "yolov11n_captcha.onnx",
+            #[cfg(feature = "chinese_click_0")]
+            Model::Siamese => "siamese.onnx",
+            #[cfg(feature = "chinese_click_0")]
+            Model::GlyphSegmenter => "glyph_segmenter.onnx",
+            #[cfg(feature = "text_captcha_0")]
+            Model::Crnn => "crnn.onnx",
+            #[cfg(feature = "slider_gap_0")]
+            Model::SliderGap => "slider_gap.onnx",
+            #[cfg(feature = "space_click_0")]
+            Model::SpaceDetector => "space_detector.onnx",
+            #[cfg(feature = "space_click_0")]
+            Model::SpaceAttributes => "space_attributes.onnx",
+            #[cfg(feature = "nine_grid_0")]
+            Model::ClipImage => "clip_image.onnx",
+            #[cfg(feature = "nine_grid_0")]
+            Model::ClipText => "clip_text.onnx",
+            #[cfg(feature = "audio")]
+            Model::AudioAsr => "audio_asr.onnx",
+            #[cfg(feature = "word_order_0")]
+            Model::CharOcr => "char_ocr.onnx",
+            #[cfg(feature = "shape_click_0")]
+            Model::ShapeDetector => "shape_detector.onnx",
+            #[cfg(feature = "shape_click_0")]
+            Model::ShapeClassifier => "shape_classifier.onnx",
+        }
+    }
+
+    /// 验证码实现对该模型输入输出的要求
+    pub fn io_spec(&self) -> IoSpec {
+        #[cfg(feature = "native")]
+        let spec = |input_rank, input_type, output_rank| IoSpec { input_rank, input_type, output_rank };
+        match *self {
+            #[cfg(feature = "chinese_click_0")]
+            Model::Yolo11n => spec(Some(4), ElementType::F32, Some(3)),
+            #[cfg(feature = "chinese_click_0")]
+            Model::Siamese => spec(Some(4), ElementType::F32, Some(2)),
+            // 兼容 (N, 1, H, W) 与 (N, H, W) 两种输出
+            #[cfg(feature = "chinese_click_0")]
+            Model::GlyphSegmenter => spec(Some(4), ElementType::F32, None),
+            #[cfg(feature = "text_captcha_0")]
+            Model::Crnn => spec(Some(4), ElementType::F32, Some(3)),
+            #[cfg(feature = "slider_gap_0")]
+            Model::SliderGap => spec(Some(4), ElementType::F32, Some(3)),
+            #[cfg(feature = "space_click_0")]
+            Model::SpaceDetector => spec(Some(4), ElementType::F32, Some(3)),
+            #[cfg(feature = "space_click_0")]
+            Model::SpaceAttributes => spec(Some(4), ElementType::F32, Some(2)),
+            #[cfg(feature = "nine_grid_0")]
+            Model::ClipImage => spec(Some(4), ElementType::F32, Some(2)),
+            #[cfg(feature = "nine_grid_0")]
+            Model::ClipText => spec(Some(2), ElementType::I64, Some(2)),
+            // 兼容 (1, n_mels, T) 与 (1, 1, n_mels, T) 两种输入
+            #[cfg(feature = "audio")]
+            Model::AudioAsr => spec(None, ElementType::F32, Some(3)),
+            #[cfg(feature = "word_order_0")]
+            Model::CharOcr => spec(Some(4), ElementType::F32, Some(2)),
+            #[cfg(feature = "shape_click_0")]
+            Model::ShapeDetector => spec(Some(4), ElementType::F32, Some(3)),
+            #[cfg(feature = "shape_click_0")]
+            Model::ShapeClassifier => spec(Some(4), ElementType::F32, Some(2)),
+        }
+    }
+
+    /// 验证码实现对该模型输入的归一化方式
+    pub fn normalization(&self) -> Normalization {
+        match *self {
+            #[cfg(feature = "text_captcha_0")]
+            Model::Crnn => Normalization::Symmetric,
+            #[cfg(feature = "nine_grid_0")]
+            Model::ClipImage | Model::ClipText => Normalization::Clip,
+            #[cfg(feature = "audio")]
+            Model::AudioAsr => Normalization::LogMel,
+            _ => Normalization::Unit,
+        }
+    }
+
+    /// 默认的下载地址
+    pub fn url(&self) -> String {
+        download_url(self.file_name())
+    }
+
+    /// 下载地址上发布了的精度，fp16/int8版本目前需要自行转换后放到模型目录
+    pub fn published_precisions(&self) -> &'static [Precision] {
+        &[Precision::Fp32]
+    }
+
+    /// 指定精度的本地文件名
+    pub fn file_name_with(&self, precision: Precision) -> String {
+        let stem = self.file_name().trim_end_matches(".onnx");
+        format!("{}{}.onnx", stem, precision.suffix())
+    }
+
+    /// 指定精度的下载地址，该精度没有发布时返回None
+    pub fn url_with(&self, precision: Precision) -> Option<String> {
+        self.published_precisions()
+            .contains(&precision)
+            .then(|| download_url(&self.file_name_with(precision)))
+    }
+}
+
+fn download_url(file_name: &str) -> String {
+    format!("https://www.modelscope.cn/models/Amorter/CaptchaBreakerModels/resolve/master/{}", file_name)
 }
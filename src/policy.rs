@@ -0,0 +1,118 @@
+//! 低置信度答案的重试策略
+//!
+//! 匹配距离过大或检测数量不符合预期时，对输入做轻微的缩放与对比度扰动后重新识别，
+//! 最后返回得分最好的一次
+
+use crate::rng::SplitMix64;
+use image::DynamicImage;
+use image::imageops::FilterType;
+use std::error::Error;
+
+/// 一次识别的结果，由 [`SolvePolicy`] 决定是否需要重试
+pub trait Attempt: Sized {
+    /// 越小越好，例如匹配距离的最大值
+    fn score(&self) -> f32;
+
+    /// 检测到的目标数量，用于与 [`SolvePolicy::expected_count`] 比较
+    fn count(&self) -> usize;
+
+    /// 结果本身是否完整(例如问题框都找到了答案)，不完整时总会重试
+    fn is_complete(&self) -> bool {
+        true
+    }
+
+    /// 把缩放后图片上的坐标映射回原图
+    fn rescale(self, scale_x: f32, scale_y: f32) -> Self;
+}
+
+#[derive(Debug, Clone)]
+pub struct SolvePolicy {
+    /// 首次识别之外最多重试的次数
+    pub max_retries: usize,
+    /// 得分高于该值时重试
+    pub max_score: Option<f32>,
+    /// 目标数量不等于该值时重试
+    pub expected_count: Option<usize>,
+    /// 缩放扰动幅度，0.05表示在 [0.95, 1.05] 之间随机缩放
+    pub scale_jitter: f32,
+    /// 对比度扰动幅度，传给 [`DynamicImage::adjust_contrast`]
+    pub contrast_jitter: f32,
+    /// 扰动使用的随机种子，相同种子的重试序列相同
+    pub seed: u64,
+}
+
+impl Default for SolvePolicy {
+    fn default() -> Self {
+        SolvePolicy {
+            max_retries: 3,
+            max_score: None,
+            expected_count: None,
+            scale_jitter: 0.05,
+            contrast_jitter: 10.0,
+            seed: 0x5eed,
+        }
+    }
+}
+
+impl SolvePolicy {
+    /// 结果是否可以直接接受
+    pub fn accepts<T: Attempt>(&self, attempt: &T) -> bool {
+        attempt.is_complete()
+            && self.max_score.is_none_or(|max| attempt.score() <= max)
+            && self.expected_count.is_none_or(|n| attempt.count() == n)
+    }
+
+    /// 调用 `solve` 识别，必要时在扰动后的图片上重试
+    ///
+    /// 有结果被接受时立即返回；否则返回所有尝试中最好的一次，全部失败时返回最后一个错误
+    pub fn solve<T: Attempt>(
+        &self,
+        image: &DynamicImage,
+        mut solve: impl FnMut(&DynamicImage) -> Result<T, Box<dyn Error>>,
+    ) -> Result<T, Box<dyn Error>> {
+        let mut rng = SplitMix64::new(self.seed);
+        let mut best: Option<T> = None;
+        let mut last_error = None;
+        for attempt in 0..=self.max_retries {
+            let res = if attempt == 0 {
+                solve(image)
+            } else {
+                let (jittered, scale_x, scale_y) = self.jitter(image, &mut rng);
+                solve(&jittered).map(|res| res.rescale(scale_x, scale_y))
+            };
+            match res {
+                Ok(res) if self.accepts(&res) => return Ok(res),
+                Ok(res) => {
+                    if best.as_ref().is_none_or(|b| self.rank(&res) < self.rank(b)) {
+                        best = Some(res);
+                    }
+                }
+                Err(e) => last_error = Some(e),
+            }
+        }
+        best.ok_or_else(|| last_error.unwrap_or_else(|| "没有可用的识别结果".into()))
+    }
+
+    /// 排序键：完整性、数量是否符合预期、得分
+    fn rank<T: Attempt>(&self, attempt: &T) -> (bool, bool, f32) {
+        (
+            !attempt.is_complete(),
+            self.expected_count.is_some_and(|n| attempt.count() != n),
+            attempt.score(),
+        )
+    }
+
+    /// 返回 (扰动后的图片, x缩放, y缩放)，缩放系数用于把坐标映射回原图
+    fn jitter(&self, image: &DynamicImage, rng: &mut SplitMix64) -> (DynamicImage, f32, f32) {
+        let scale = 1.0 + self.scale_jitter * rng.next_signed();
+        let width = ((image.width() as f32 * scale).round() as u32).max(1);
+        let height = ((image.height() as f32 * scale).round() as u32).max(1);
+        let resized = image.resize_exact(width, height, FilterType::Triangle);
+        let jittered = resized.adjust_contrast(self.contrast_jitter * rng.next_signed());
+        (
+            jittered,
+            image.width() as f32 / width as f32,
+            image.height() as f32 / height as f32,
+        )
+    }
+}
@@ -0,0 +1,233 @@
+//! 用字体渲染字形
+//!
+//! 提示为文字的点选验证码与字形库都需要没有出现在图片中的字的图像，[`FontRenderer`] 从用户提供的TTF/OTF
+//! 字体把任意字渲染成与检测框截图相似的图像块：字的外接框居中并保持宽高比缩放到图像块中，
+//! 可以设置粗细、倾斜、旋转与颜色，之后像答案一样交给孪生网络提取特征
+
+use crate::matching::{SiameseEmbedder, TemplateBank};
+use ab_glyph::{Font, FontVec, PxScale, point};
+use image::{Rgb, RgbImage};
+use std::error::Error;
+use std::fmt;
+
+/// 渲染的尺寸与样式
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderConfig {
+    /// 输出图像块的边长
+    pub size: u32,
+    /// 栅格化时的字号(像素)，之后缩放到 `size`，字号越大边缘越平滑
+    pub font_size: f32,
+    /// 字与图像块边缘的距离，相对于边长
+    pub padding: f32,
+    /// 笔画向外加粗的像素数(按 `font_size` 计)，0为字体本身的粗细
+    pub weight: f32,
+    /// 水平错切的比例，正值向右倾斜
+    pub italic: f32,
+    /// 顺时针旋转的角度(弧度)
+    pub rotation: f32,
+    pub foreground: Rgb<u8>,
+    pub background: Rgb<u8>,
+}
+
+impl Default for RenderConfig {
+    fn default() -> Self {
+        RenderConfig {
+            size: 64,
+            font_size: 64.0,
+            padding: 0.1,
+            weight: 0.0,
+            italic: 0.0,
+            rotation: 0.0,
+            foreground: Rgb([0, 0, 0]),
+            background: Rgb([255, 255, 255]),
+        }
+    }
+}
+
+/// 字形的覆盖率，坐标原点在外接框的左上角
+struct Coverage {
+    width: usize,
+    height: usize,
+    values: Vec<f32>,
+}
+
+impl Coverage {
+    fn get(&self, x: isize, y: isize) -> f32 {
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return 0.0;
+        }
+        self.values[y as usize * self.width + x as usize]
+    }
+
+    /// 双线性插值，(x, y)为像素中心坐标
+    fn sample(&self, x: f32, y: f32) -> f32 {
+        let (x, y) = (x - 0.5, y - 0.5);
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as isize, y0 as isize);
+        let top = self.get(x0, y0) * (1.0 - fx) + self.get(x0 + 1, y0) * fx;
+        let bottom = self.get(x0, y0 + 1) * (1.0 - fx) + self.get(x0 + 1, y0 + 1) * fx;
+        top * (1.0 - fy) + bottom * fy
+    }
+
+    /// 以radius为半径的圆形最大值滤波，外接框向四周扩大radius
+    fn dilate(&self, radius: f32) -> Coverage {
+        let r = radius.ceil() as isize;
+        let offsets: Vec<(isize, isize, f32)> = (-r..=r)
+            .flat_map(|dy| (-r..=r).map(move |dx| (dx, dy)))
+            .filter_map(|(dx, dy)| {
+                // 半径边缘按距离部分覆盖，粗细可以连续调整
+                let weight = (radius + 0.5 - (dx as f32).hypot(dy as f32)).clamp(0.0, 1.0);
+                (weight > 0.0).then_some((dx, dy, weight))
+            })
+            .collect();
+        let (width, height) = (self.width + 2 * r as usize, self.height + 2 * r as usize);
+        let mut values = vec![0.0; width * height];
+        for (i, value) in values.iter_mut().enumerate() {
+            let (x, y) = ((i % width) as isize - r, (i / width) as isize - r);
+            *value = offsets.iter().map(|&(dx, dy, w)| self.get(x + dx, y + dy) * w).fold(0.0, f32::max);
+        }
+        Coverage { width, height, values }
+    }
+}
+
+/// 从字体渲染字形，可以在多个线程之间共享
+///
+/// 可以加入多个字体，按加入的顺序使用第一个包含该字的字体，例如中文字体缺少的字母由后备字体补充
+pub struct FontRenderer {
+    fonts: Vec<FontVec>,
+    config: RenderConfig,
+}
+
+impl fmt::Debug for FontRenderer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FontRenderer")
+            .field("fonts", &self.fonts.len())
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+impl FontRenderer {
+    /// font为TTF/OTF字体文件的内容
+    pub fn new(font: impl Into<Vec<u8>>) -> Result<Self, Box<dyn Error>> {
+        Ok(FontRenderer {
+            fonts: vec![parse_font(font.into())?],
+            config: RenderConfig::default(),
+        })
+    }
+
+    /// 加入后备字体，之前的字体中没有的字使用该字体
+    pub fn with_fallback(mut self, font: impl Into<Vec<u8>>) -> Result<Self, Box<dyn Error>> {
+        self.fonts.push(parse_font(font.into())?);
+        Ok(self)
+    }
+
+    pub fn with_config(mut self, config: RenderConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn config(&self) -> &RenderConfig {
+        &self.config
+    }
+
+    /// 是否有字体包含该字
+    pub fn contains(&self, c: char) -> bool {
+        self.font_for(c).is_some()
+    }
+
+    fn font_for(&self, c: char) -> Option<&FontVec> {
+        self.fonts.iter().find(|font| font.glyph_id(c).0 != 0)
+    }
+
+    /// 按 [`config`](Self::config) 渲染
+    pub fn render(&self, c: char) -> Result<RgbImage, Box<dyn Error>> {
+        self.render_with(c, &self.config)
+    }
+
+    /// 按给定的样式渲染，字体中没有该字或该字没有轮廓(例如空格)时返回错误
+    pub fn render_with(&self, c: char, config: &RenderConfig) -> Result<RgbImage, Box<dyn Error>> {
+        if config.size == 0 || config.font_size <= 0.0 || !(0.0..0.5).contains(&config.padding) {
+            return Err(format!("渲染配置无效: {:?}", config).into());
+        }
+        let font = self.font_for(c).ok_or_else(|| format!("字体中没有“{}”", c))?;
+        let glyph = font.glyph_id(c).with_scale_and_position(PxScale::from(config.font_size), point(0.0, 0.0));
+        let outline = font.outline_glyph(glyph).ok_or_else(|| format!("“{}”没有字形轮廓", c))?;
+        let bounds = outline.px_bounds();
+        let (width, height) = ((bounds.max.x - bounds.min.x) as usize, (bounds.max.y - bounds.min.y) as usize);
+        let mut coverage = Coverage { width, height, values: vec![0.0; width * height] };
+        outline.draw(|x, y, value| {
+            if (x as usize) < width && (y as usize) < height {
+                coverage.values[y as usize * width + x as usize] = value.clamp(0.0, 1.0);
+            }
+        });
+        if config.weight > 0.0 {
+            coverage = coverage.dilate(config.weight);
+        }
+        Ok(compose(&coverage, config))
+    }
+
+    /// 渲染文字中的每个字，忽略空白
+    pub fn render_text(&self, text: &str) -> Result<Vec<RgbImage>, Box<dyn Error>> {
+        text.chars().filter(|c| !c.is_whitespace()).map(|c| self.render(c)).collect()
+    }
+
+    /// 以每种样式渲染chars中的每个字并提取特征，得到以字为标签的字形库
+    ///
+    /// styles为空时只使用 [`config`](Self::config)；同一个字的多种样式都是该字的模板，
+    /// 字形库的距离度量与embedder一致
+    pub fn templates(&self, embedder: &SiameseEmbedder, chars: &str, styles: &[RenderConfig]) -> Result<TemplateBank, Box<dyn Error>> {
+        let styles = if styles.is_empty() { std::slice::from_ref(&self.config) } else { styles };
+        let mut labels = vec![];
+        let mut crops = vec![];
+        for c in chars.chars().filter(|c| !c.is_whitespace()) {
+            for style in styles {
+                labels.push(c.to_string());
+                crops.push(self.render_with(c, style)?);
+            }
+        }
+        let features = embedder.embed_rgb(&crops)?;
+        let mut bank = TemplateBank::new(features.ncols(), embedder.config().metric);
+        bank.extend(labels, &features.view())?;
+        Ok(bank)
+    }
+}
+
+fn parse_font(font: Vec<u8>) -> Result<FontVec, Box<dyn Error>> {
+    FontVec::try_from_vec(font).map_err(|e| format!("无法解析字体: {}", e).into())
+}
+
+/// 把覆盖率按样式变换后缩放到图像块中央，并与背景混合
+fn compose(coverage: &Coverage, config: &RenderConfig) -> RgbImage {
+    let (sin, cos) = config.rotation.sin_cos();
+    // 先错切再旋转，坐标原点为字形中心，y轴向下
+    let forward = |x: f32, y: f32| {
+        let x = x - config.italic * y;
+        (x * cos - y * sin, x * sin + y * cos)
+    };
+    let (half_width, half_height) = (coverage.width as f32 / 2.0, coverage.height as f32 / 2.0);
+    let corners = [(-half_width, -half_height), (half_width, -half_height), (-half_width, half_height), (half_width, half_height)];
+    let (mut extent_x, mut extent_y) = (0.0f32, 0.0f32);
+    for (x, y) in corners.map(|(x, y)| forward(x, y)) {
+        extent_x = extent_x.max(x.abs());
+        extent_y = extent_y.max(y.abs());
+    }
+    let size = config.size as f32;
+    let scale = size * (1.0 - 2.0 * config.padding) / (2.0 * extent_x.max(extent_y)).max(1.0);
+    let mut image = RgbImage::from_pixel(config.size, config.size, config.background);
+    for (u, v, pixel) in image.enumerate_pixels_mut() {
+        let x = (u as f32 + 0.5 - size / 2.0) / scale;
+        let y = (v as f32 + 0.5 - size / 2.0) / scale;
+        // 逆变换：先逆旋转再逆错切
+        let (x, y) = (x * cos + y * sin, -x * sin + y * cos);
+        let x = x + config.italic * y;
+        let alpha = coverage.sample(x + half_width, y + half_height);
+        if alpha > 0.0 {
+            for (channel, (&fg, &bg)) in pixel.0.iter_mut().zip(config.foreground.0.iter().zip(&config.background.0)) {
+                *channel = (bg as f32 * (1.0 - alpha) + fg as f32 * alpha).round() as u8;
+            }
+        }
+    }
+    image
+}
@@ -0,0 +1,136 @@
+//! 识别报告
+//!
+//! 每次识别生成一份 [`SolveReport`]，记录输入图片的哈希、使用的模型版本、各阶段耗时、置信度、最终答案与结果，
+//! 通过环境上设置的回调交给调用方写入审计日志，便于事后分析与处理争议。
+//! 开启 `serde` feature 后报告实现 `serde::Serialize`
+
+use crate::hash;
+use crate::timing::{Outcome, Stage, Timings};
+use image::DynamicImage;
+use std::collections::BTreeMap;
+use std::error::Error;
+#[cfg(any(feature = "chinese_click_0", feature = "shape_click_0"))]
+use std::fmt;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 报告回调，在识别线程中同步调用，耗时的处理(例如写入远程日志)请转交给其他线程
+pub type ReportHook = Arc<dyn Fn(&SolveReport) + Send + Sync>;
+
+/// 识别使用的一个模型
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ModelVersion {
+    /// [`Model::name`](crate::model::Model::name)
+    pub model: String,
+    /// 实际加载的精度，使用自定义后端时为None
+    pub precision: Option<String>,
+    /// 元数据 `captcha_breaker.version`
+    pub version: Option<String>,
+    /// 元数据 `captcha_breaker.format_version`
+    pub format_version: Option<u32>,
+}
+
+/// 一次识别的报告
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SolveReport {
+    pub breaker: String,
+    /// 开始生成报告的时间，自UNIX纪元起的毫秒数
+    pub timestamp_ms: u64,
+    /// 输入图片的pHash，16位十六进制，见 [`hash::phash`]
+    pub input_hash: String,
+    pub image_size: (u32, u32),
+    pub models: Vec<ModelVersion>,
+    /// 各阶段耗时(毫秒)，键为 [`Stage::name`]
+    pub timings_ms: BTreeMap<String, f64>,
+    /// 检测框的置信度
+    pub confidences: Vec<f32>,
+    /// 答案的匹配概率，不做特征匹配的验证码为空
+    pub probabilities: Vec<f32>,
+    /// 最终需要点选的坐标，识别失败时为空
+    pub answer: Vec<(f32, f32)>,
+    pub outcome: Outcome,
+    /// 识别失败的原因
+    pub error: Option<String>,
+}
+
+impl SolveReport {
+    pub fn new(breaker: impl Into<String>, image: &DynamicImage, models: Vec<ModelVersion>) -> Self {
+        SolveReport {
+            breaker: breaker.into(),
+            timestamp_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or_default(),
+            input_hash: hash::phash(image).to_string(),
+            image_size: (image.width(), image.height()),
+            models,
+            timings_ms: BTreeMap::new(),
+            confidences: vec![],
+            probabilities: vec![],
+            answer: vec![],
+            outcome: Outcome::Solved,
+            error: None,
+        }
+    }
+
+    /// 记录识别结果与失败原因
+    pub fn with_outcome<T>(mut self, result: &Result<T, Box<dyn Error>>) -> Self {
+        self.outcome = Outcome::of(result);
+        self.error = result.as_ref().err().map(|e| e.to_string());
+        self
+    }
+
+    pub fn with_timings(mut self, timings: &Timings) -> Self {
+        self.timings_ms = [Stage::Preprocess, Stage::Detect, Stage::Crop, Stage::Embed, Stage::Assign]
+            .into_iter()
+            .map(|stage| (stage.name().to_string(), timings.get(stage).as_secs_f64() * 1000.0))
+            .collect();
+        self
+    }
+
+    /// 各阶段耗时之和(毫秒)
+    pub fn total_ms(&self) -> f64 {
+        self.timings_ms.values().sum()
+    }
+}
+
+/// 验证码实现持有的报告生成器，保存构建时的模型版本
+#[cfg(any(feature = "chinese_click_0", feature = "shape_click_0"))]
+#[derive(Clone)]
+pub(crate) struct Reporter {
+    hook: ReportHook,
+    models: Vec<ModelVersion>,
+}
+
+#[cfg(any(feature = "chinese_click_0", feature = "shape_click_0"))]
+impl fmt::Debug for Reporter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Reporter").field("models", &self.models).finish_non_exhaustive()
+    }
+}
+
+#[cfg(any(feature = "chinese_click_0", feature = "shape_click_0"))]
+impl Reporter {
+    /// 环境没有设置报告回调时为None
+    pub(crate) fn from_environment(
+        environment: &crate::environment::CaptchaEnvironment,
+        models: &[crate::model::Model],
+    ) -> Option<Reporter> {
+        let hook = environment.report_hook()?.clone();
+        Some(Reporter {
+            hook,
+            models: models.iter().map(|&model| environment.model_version(model)).collect(),
+        })
+    }
+
+    /// 使用构建时的模型版本开始一份报告
+    pub(crate) fn start(&self, breaker: &str, image: &DynamicImage) -> SolveReport {
+        SolveReport::new(breaker, image, self.models.clone())
+    }
+
+    pub(crate) fn emit(&self, report: &SolveReport) {
+        (self.hook)(report)
+    }
+}
@@ -0,0 +1,123 @@
+//! ONNX Runtime动态库检查
+//!
+//! 开启 `load-dynamic` feature 时ONNX Runtime在运行时加载，找不到动态库时ort会在第一次创建会话时panic，
+//! 只给出一条动态加载的错误。[`probe`] 在创建会话之前按顺序查找动态库，都不可用时返回带有全部查找路径的
+//! [`CaptchaError::RuntimeUnavailable`]。其他链接方式(见 [`Linking`])在编译时链接，总是可用
+
+use crate::error::CaptchaError;
+use std::path::{Path, PathBuf};
+
+/// ONNX Runtime的链接方式，由本crate的feature决定，不需要直接依赖ort
+///
+/// 同时开启多个时 `load-dynamic` 优先，其次为 `download-binaries`；使用 `static` 需要关闭默认feature
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Linking {
+    /// `download-binaries`：编译时下载ort提供的预编译库，并把需要的动态库复制到输出目录
+    DownloadBinaries,
+    /// `load-dynamic`：运行时按 [`candidates`] 的顺序加载动态库
+    LoadDynamic,
+    /// `static`：由ort的构建脚本链接环境变量 `ORT_LIB_LOCATION` 中的库
+    Static,
+}
+
+impl Linking {
+    /// 编译时选择的链接方式
+    pub const fn current() -> Linking {
+        if cfg!(feature = "load-dynamic") {
+            Linking::LoadDynamic
+        } else if cfg!(feature = "download-binaries") {
+            Linking::DownloadBinaries
+        } else {
+            Linking::Static
+        }
+    }
+
+    /// 对应的feature名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            Linking::DownloadBinaries => "download-binaries",
+            Linking::LoadDynamic => "load-dynamic",
+            Linking::Static => "static",
+        }
+    }
+}
+
+/// ort读取的动态库路径环境变量
+pub const ENV_DYLIB_PATH: &str = "ORT_DYLIB_PATH";
+
+/// 当前平台上ONNX Runtime动态库的文件名
+pub fn library_name() -> &'static str {
+    if cfg!(target_os = "windows") {
+        "onnxruntime.dll"
+    } else if cfg!(target_os = "macos") {
+        "libonnxruntime.dylib"
+    } else {
+        "libonnxruntime.so"
+    }
+}
+
+/// 按顺序查找的位置：`custom`、环境变量 `ORT_DYLIB_PATH`、可执行文件所在目录，最后交给系统按文件名搜索。
+/// 目录会拼上 [`library_name`]
+pub fn candidates(custom: Option<&Path>) -> Vec<PathBuf> {
+    let file = |path: PathBuf| if path.is_dir() { path.join(library_name()) } else { path };
+    let mut paths = vec![];
+    paths.extend(custom.map(|path| file(path.to_path_buf())));
+    paths.extend(std::env::var_os(ENV_DYLIB_PATH).filter(|v| !v.is_empty()).map(|v| file(PathBuf::from(v))));
+    if let Some(dir) = std::env::current_exe().ok().and_then(|exe| exe.parent().map(Path::to_path_buf)) {
+        paths.push(dir.join(library_name()));
+    }
+    paths.push(PathBuf::from(library_name()));
+    paths.dedup();
+    paths
+}
+
+/// 加载成功的动态库，ort在同一进程中只会加载一次
+#[cfg(feature = "load-dynamic")]
+static LOADED: std::sync::Mutex<Option<PathBuf>> = std::sync::Mutex::new(None);
+
+/// 确认ONNX Runtime可用，返回加载的动态库路径，编译时链接时返回None
+///
+/// 成功后同一进程中不再查找，之后传入的 `custom` 不会生效；失败时可以换一个路径再次调用
+#[cfg(feature = "load-dynamic")]
+pub fn probe(custom: Option<&Path>) -> Result<Option<PathBuf>, CaptchaError> {
+    let mut loaded = LOADED.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+    if let Some(path) = loaded.as_ref() {
+        return Ok(Some(path.clone()));
+    }
+    let attempted = candidates(custom);
+    let mut reasons = vec![];
+    for path in &attempted {
+        // 只检查能否打开以及是否导出了ort需要的入口，库中的初始化代码与ort自己加载时相同
+        let library = match unsafe { libloading::Library::new(path) } {
+            Ok(library) => library,
+            Err(e) => {
+                reasons.push(format!("{}: {}", path.display(), e));
+                continue;
+            }
+        };
+        let entry = unsafe { library.get::<unsafe extern "C" fn() -> *const std::ffi::c_void>(b"OrtGetApiBase\0") };
+        if entry.is_err() {
+            reasons.push(format!("{}: 不是ONNX Runtime动态库", path.display()));
+            continue;
+        }
+        ort::init_from(path.to_string_lossy())
+            .commit()
+            .map_err(|e| CaptchaError::RuntimeUnavailable {
+                attempted: attempted.clone(),
+                reason: e.to_string(),
+            })?;
+        *loaded = Some(path.clone());
+        return Ok(Some(path.clone()));
+    }
+    Err(CaptchaError::RuntimeUnavailable {
+        attempted,
+        reason: reasons.join("；"),
+    })
+}
+
+/// 确认ONNX Runtime可用，返回加载的动态库路径，编译时链接时返回None
+#[cfg(not(feature = "load-dynamic"))]
+pub fn probe(custom: Option<&Path>) -> Result<Option<PathBuf>, CaptchaError> {
+    let _ = custom;
+    Ok(None)
+}
@@ -0,0 +1,108 @@
+//! 多GPU调度
+//!
+//! 一台机器有多块GPU时，为每块GPU创建一个绑定设备编号的 [`CaptchaEnvironment`]，
+//! 在每个环境中各加载一份验证码实现，由 [`RoundRobin`] 把识别请求轮流分给它们
+
+#[cfg(feature = "native")]
+use crate::captcha::CaptchaBreaker;
+#[cfg(feature = "native")]
+use crate::environment::CaptchaEnvironment;
+use std::error::Error;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// 按顺序轮流使用的一组实例，可以在多个线程之间共享
+#[derive(Debug)]
+pub struct RoundRobin<B> {
+    items: Vec<B>,
+    next: AtomicUsize,
+}
+
+impl<B> RoundRobin<B> {
+    /// items不能为空
+    pub fn new(items: Vec<B>) -> Result<Self, Box<dyn Error>> {
+        if items.is_empty() {
+            return Err("RoundRobin至少需要一个实例".into());
+        }
+        Ok(RoundRobin {
+            items,
+            next: AtomicUsize::new(0),
+        })
+    }
+
+    /// 下一个实例的下标与实例
+    pub fn next(&self) -> (usize, &B) {
+        let index = self.next.fetch_add(1, Ordering::Relaxed) % self.items.len();
+        (index, &self.items[index])
+    }
+
+    /// 交给下一个实例执行
+    pub fn run<T>(&self, f: impl FnOnce(&B) -> T) -> T {
+        f(self.next().1)
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&B> {
+        self.items.get(index)
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, B> {
+        self.items.iter()
+    }
+}
+
+/// 每块GPU一个环境
+#[cfg(feature = "native")]
+pub struct DevicePool {
+    environments: Vec<CaptchaEnvironment>,
+}
+
+#[cfg(feature = "native")]
+impl DevicePool {
+    /// 使用已经创建好的环境，例如各自设置了模型目录或会话选项的环境
+    pub fn new(environments: Vec<CaptchaEnvironment>) -> Result<Self, Box<dyn Error>> {
+        if environments.is_empty() {
+            return Err("DevicePool至少需要一个环境".into());
+        }
+        Ok(DevicePool { environments })
+    }
+
+    /// 为每个设备编号创建一个使用provider的环境，见 [`CaptchaEnvironment::on_device`]
+    pub fn with_devices(provider: &str, device_ids: impl IntoIterator<Item = i32>) -> Result<Self, Box<dyn Error>> {
+        let environments = device_ids
+            .into_iter()
+            .map(|id| CaptchaEnvironment::on_device(provider, id))
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::new(environments)
+    }
+
+    pub fn environments(&self) -> &[CaptchaEnvironment] {
+        &self.environments
+    }
+
+    /// 在每个环境中各加载一份验证码实现，顺序与环境相同
+    pub fn load_captcha_breaker<CB: CaptchaBreaker>(&self) -> Result<RoundRobin<CB>, Box<dyn Error>> {
+        let breakers = self
+            .environments
+            .iter()
+            .map(|environment| environment.load_captcha_breaker())
+            .collect::<Result<Vec<_>, _>>()?;
+        RoundRobin::new(breakers)
+    }
+
+    /// 与 [`load_captcha_breaker`](Self::load_captcha_breaker) 相同，并预热每一份
+    pub fn load_captcha_breaker_warm<CB: CaptchaBreaker>(&self) -> Result<RoundRobin<CB>, Box<dyn Error>> {
+        let breakers = self
+            .environments
+            .iter()
+            .map(|environment| environment.load_captcha_breaker_warm())
+            .collect::<Result<Vec<_>, _>>()?;
+        RoundRobin::new(breakers)
+    }
+}
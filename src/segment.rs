@@ -0,0 +1,257 @@
+//! 提取特征前去除字形图像块的背景
+//!
+//! 答案区的背景通常是复杂的图片，截取的图像块中背景占了大部分像素，孪生网络的距离容易被背景左右。
+//! 把字形以外的像素替换为统一的颜色后，同一个字的图像块更接近，不同字之间的距离也更大。
+//! 可以按与背景色的差异判断字形，也可以使用分割模型 [`GlyphSegmenter`]
+
+use crate::backend::InferenceBackend;
+use image::imageops::FilterType;
+use image::{GrayImage, Luma, Rgb, RgbImage};
+use ndarray::{Array4, Axis, Ix3};
+use std::collections::VecDeque;
+use std::error::Error;
+use std::sync::Arc;
+
+/// 分割模型输入尺寸无法从模型读取时使用的默认值
+const DEFAULT_INPUT_SIZE: u32 = 64;
+
+/// 判断字形像素的方式
+#[derive(Debug, Clone, PartialEq)]
+pub enum MaskMethod {
+    /// 与背景色(图像块边缘像素各通道的中位数)的差异超过tolerance的像素为字形，为None时按Otsu法自动选择；
+    /// 面积小于图像块 `min_area` 比例的连通区域视为噪点
+    Threshold { tolerance: Option<u8>, min_area: f32 },
+    /// 分割模型输出的前景概率超过threshold的像素为字形，需要 [`ChineseClick0::with_segmenter`](crate::captcha::ChineseClick0::with_segmenter)
+    Model { threshold: f32 },
+}
+
+/// 背景去除的配置
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaskConfig {
+    pub method: MaskMethod,
+    /// 背景像素替换为的颜色
+    pub fill: Rgb<u8>,
+    /// 字形区域向外扩大的像素数，保留笔画边缘的抗锯齿像素
+    pub dilate: u32,
+}
+
+impl Default for MaskConfig {
+    fn default() -> Self {
+        MaskConfig {
+            method: MaskMethod::Threshold {
+                tolerance: None,
+                min_area: 0.005,
+            },
+            fill: Rgb([255, 255, 255]),
+            dilate: 1,
+        }
+    }
+}
+
+/// 按config去除每个图像块的背景，字形区域为空时保留原图像块
+pub fn mask_crops(crops: &[&RgbImage], config: &MaskConfig, segmenter: Option<&GlyphSegmenter>) -> Result<Vec<RgbImage>, Box<dyn Error>> {
+    let masks = match config.method {
+        MaskMethod::Threshold { tolerance, min_area } => crops.iter().map(|crop| threshold_mask(crop, tolerance, min_area)).collect(),
+        MaskMethod::Model { threshold } => segmenter.ok_or("没有设置分割模型")?.masks(crops, threshold)?,
+    };
+    Ok(crops
+        .iter()
+        .zip(masks)
+        .map(|(crop, mask)| apply_mask(crop, &dilate_mask(&mask, config.dilate), config.fill))
+        .collect())
+}
+
+/// 字形像素为255，其余为0
+pub fn threshold_mask(crop: &RgbImage, tolerance: Option<u8>, min_area: f32) -> GrayImage {
+    let (width, height) = crop.dimensions();
+    let mut mask = GrayImage::new(width, height);
+    if width == 0 || height == 0 {
+        return mask;
+    }
+    let background = border_median(crop);
+    let distances: Vec<u8> = crop
+        .pixels()
+        .map(|p| p.0.iter().zip(&background).map(|(&a, &b)| a.abs_diff(b)).max().unwrap_or(0))
+        .collect();
+    let tolerance = tolerance.unwrap_or_else(|| otsu(&distances));
+    for (pixel, &distance) in mask.pixels_mut().zip(&distances) {
+        if distance > tolerance {
+            *pixel = Luma([255]);
+        }
+    }
+    let min_pixels = (min_area.max(0.0) * (width * height) as f32).ceil() as usize;
+    if min_pixels > 1 {
+        remove_small_components(&mut mask, min_pixels);
+    }
+    mask
+}
+
+/// mask为0的像素替换为fill，mask全为0时返回原图像块
+pub fn apply_mask(crop: &RgbImage, mask: &GrayImage, fill: Rgb<u8>) -> RgbImage {
+    if mask.dimensions() != crop.dimensions() || mask.pixels().all(|p| p.0[0] == 0) {
+        return crop.clone();
+    }
+    let mut masked = crop.clone();
+    for (pixel, m) in masked.pixels_mut().zip(mask.pixels()) {
+        if m.0[0] == 0 {
+            *pixel = fill;
+        }
+    }
+    masked
+}
+
+/// 边长为 `2 * radius + 1` 的方形膨胀
+pub fn dilate_mask(mask: &GrayImage, radius: u32) -> GrayImage {
+    if radius == 0 {
+        return mask.clone();
+    }
+    let (width, height) = mask.dimensions();
+    GrayImage::from_fn(width, height, |x, y| {
+        let xs = x.saturating_sub(radius)..(x + radius + 1).min(width);
+        let ys = y.saturating_sub(radius)..(y + radius + 1).min(height);
+        let on = ys.clone().any(|y| xs.clone().any(|x| mask.get_pixel(x, y).0[0] > 0));
+        Luma([if on { 255 } else { 0 }])
+    })
+}
+
+fn border_median(crop: &RgbImage) -> [u8; 3] {
+    let (width, height) = crop.dimensions();
+    let border: Vec<[u8; 3]> = crop
+        .enumerate_pixels()
+        .filter(|&(x, y, _)| x == 0 || y == 0 || x + 1 == width || y + 1 == height)
+        .map(|(_, _, p)| p.0)
+        .collect();
+    std::array::from_fn(|c| {
+        let mut channel: Vec<u8> = border.iter().map(|p| p[c]).collect();
+        channel.sort_unstable();
+        channel[channel.len() / 2]
+    })
+}
+
+/// 使类间方差最大的阈值，大于阈值的为前景
+fn otsu(values: &[u8]) -> u8 {
+    let mut histogram = [0usize; 256];
+    for &v in values {
+        histogram[v as usize] += 1;
+    }
+    let total = values.len() as f64;
+    let sum: f64 = histogram.iter().enumerate().map(|(v, &n)| v as f64 * n as f64).sum();
+    let (mut weight, mut partial, mut best, mut best_variance) = (0.0, 0.0, 0u8, 0.0);
+    for (v, &n) in histogram.iter().enumerate() {
+        weight += n as f64;
+        partial += v as f64 * n as f64;
+        if weight == 0.0 || weight == total {
+            continue;
+        }
+        let (mean_low, mean_high) = (partial / weight, (sum - partial) / (total - weight));
+        let variance = weight * (total - weight) * (mean_low - mean_high).powi(2);
+        if variance > best_variance {
+            best_variance = variance;
+            best = v as u8;
+        }
+    }
+    best
+}
+
+/// 去除像素数少于min_pixels的8连通区域
+fn remove_small_components(mask: &mut GrayImage, min_pixels: usize) {
+    let (width, height) = mask.dimensions();
+    let mut visited = vec![false; (width * height) as usize];
+    let index = |x: u32, y: u32| (y * width + x) as usize;
+    for start in 0..visited.len() {
+        let (sx, sy) = (start as u32 % width, start as u32 / width);
+        if visited[start] || mask.get_pixel(sx, sy).0[0] == 0 {
+            continue;
+        }
+        visited[start] = true;
+        let mut component = vec![(sx, sy)];
+        let mut queue = VecDeque::from([(sx, sy)]);
+        while let Some((x, y)) = queue.pop_front() {
+            for ny in y.saturating_sub(1)..(y + 2).min(height) {
+                for nx in x.saturating_sub(1)..(x + 2).min(width) {
+                    if !visited[index(nx, ny)] && mask.get_pixel(nx, ny).0[0] > 0 {
+                        visited[index(nx, ny)] = true;
+                        component.push((nx, ny));
+                        queue.push_back((nx, ny));
+                    }
+                }
+            }
+        }
+        if component.len() < min_pixels {
+            for (x, y) in component {
+                mask.put_pixel(x, y, Luma([0]));
+            }
+        }
+    }
+}
+
+/// 字形分割模型，输入 (N, 3, size, size)，输出 (N, 1, size, size) 或 (N, size, size) 的前景概率或logits
+///
+/// 例如蒸馏得到的小型U²-Net，输出不在 `[0, 1]` 内时按logits经过sigmoid
+#[derive(Debug, Clone)]
+pub struct GlyphSegmenter {
+    backend: Arc<dyn InferenceBackend>,
+}
+
+#[cfg(feature = "chinese_click_0")]
+impl crate::captcha::CaptchaBreaker for GlyphSegmenter {
+    fn build(captcha_environment: &crate::environment::CaptchaEnvironment) -> Result<Self, Box<dyn Error>> {
+        let session = captcha_environment.load_models(vec![crate::model::Model::GlyphSegmenter])?;
+        Ok(GlyphSegmenter::new(session[0].clone()))
+    }
+
+    fn warmup(&self) -> Result<(), Box<dyn Error>> {
+        crate::warmup::warmup_backends(&[self.backend()])
+    }
+}
+
+impl GlyphSegmenter {
+    pub fn new(backend: Arc<dyn InferenceBackend>) -> Self {
+        GlyphSegmenter { backend }
+    }
+
+    pub fn backend(&self) -> &dyn InferenceBackend {
+        self.backend.as_ref()
+    }
+
+    /// 每个图像块的字形掩码，尺寸与图像块相同
+    pub fn masks(&self, crops: &[&RgbImage], threshold: f32) -> Result<Vec<GrayImage>, Box<dyn Error>> {
+        if crops.is_empty() {
+            return Ok(vec![]);
+        }
+        let size = self.backend.input_size().unwrap_or(DEFAULT_INPUT_SIZE);
+        let mut batch = Array4::<f32>::zeros((crops.len(), 3, size as usize, size as usize));
+        for (i, crop) in crops.iter().enumerate() {
+            let resized = image::imageops::resize(*crop, size, size, FilterType::Triangle);
+            for (x, y, pixel) in resized.enumerate_pixels() {
+                for c in 0..3 {
+                    batch[[i, c, y as usize, x as usize]] = pixel[c] as f32 / 255.0;
+                }
+            }
+        }
+        let output = self.backend.run_single(batch)?.into_f32()?;
+        let output = match output.ndim() {
+            4 => output.index_axis_move(Axis(1), 0),
+            3 => output,
+            n => return Err(format!("分割模型输出为{}维，需要3或4维", n).into()),
+        }
+        .into_dimensionality::<Ix3>()?;
+        let (n, rows, cols) = output.dim();
+        if n != crops.len() {
+            return Err(format!("分割模型输出{}个掩码，输入{}个图像块", n, crops.len()).into());
+        }
+        let logits = output.iter().any(|&v| !(0.0..=1.0).contains(&v));
+        Ok(output
+            .axis_iter(Axis(0))
+            .zip(crops)
+            .map(|(probabilities, crop)| {
+                let mask = GrayImage::from_fn(cols as u32, rows as u32, |x, y| {
+                    let value = probabilities[[y as usize, x as usize]];
+                    let probability = if logits { 1.0 / (1.0 + (-value).exp()) } else { value };
+                    Luma([if probability > threshold { 255 } else { 0 }])
+                });
+                image::imageops::resize(&mask, crop.width(), crop.height(), FilterType::Nearest)
+            })
+            .collect())
+    }
+}
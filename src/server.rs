@@ -0,0 +1,161 @@
+//! HTTP服务
+//!
+//! 每种开启了feature的验证码对应一个 `POST /<feature名>` 接口，例如 `POST /chinese_click_0`，
+//! 请求体为 `{"image": "<base64>", "prompt": "..."}`(图片与语音都可以带 `data:...;base64,` 前缀)，
+//! 成功时返回JSON结果，失败时返回 `{"error": "..."}`；`GET /health` 用于健康检查
+//!
+//! ChineseClick1需要调用方提供字形库或字体，NineGrid0需要与文本模型配套的分词器，这两种不会自动注册，
+//! 可以在 [`router`] 返回的路由上自行添加
+
+use crate::environment::CaptchaEnvironment;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::error::Error;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    pub addr: SocketAddr,
+    /// 同时进行识别的最大请求数，超出的请求会排队等待
+    pub max_concurrency: usize,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            addr: SocketAddr::from(([0, 0, 0, 0], 8080)),
+            max_concurrency: std::thread::available_parallelism().map_or(4, |n| n.get()),
+        }
+    }
+}
+
+/// 识别请求
+#[derive(Debug, Deserialize)]
+pub struct SolveRequest {
+    /// base64编码的验证码，语音验证码为WAV/MP3
+    pub image: String,
+    /// 提示词，ColorClick0、SpaceClick0、ShapeClick0与NineGrid0需要
+    #[serde(default)]
+    pub prompt: String,
+}
+
+#[derive(Clone)]
+struct AppState {
+    permits: Arc<Semaphore>,
+}
+
+/// 一种验证码的识别接口，把请求转换为JSON结果
+type SolveFn<B> = fn(&B, &SolveRequest) -> Result<Value, Box<dyn Error>>;
+
+/// 在阻塞线程池中执行识别，并受并发数限制
+async fn solve<B>(state: AppState, breaker: Arc<B>, f: SolveFn<B>, request: SolveRequest) -> Response
+where
+    B: Send + Sync + 'static,
+{
+    let Ok(_permit) = state.permits.acquire_owned().await else {
+        return (StatusCode::SERVICE_UNAVAILABLE, Json(json!({ "error": "服务正在关闭" }))).into_response();
+    };
+    let res = tokio::task::spawn_blocking(move || f(&breaker, &request).map_err(|e| e.to_string())).await;
+    match res {
+        Ok(Ok(value)) => Json(value).into_response(),
+        Ok(Err(e)) => (StatusCode::UNPROCESSABLE_ENTITY, Json(json!({ "error": e }))).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))).into_response(),
+    }
+}
+
+/// 为一种验证码注册 `POST /<path>` 接口
+macro_rules! route {
+    ($router:ident, $environment:ident, $path:literal, $breaker:ty, $f:expr) => {{
+        let breaker: Arc<$breaker> = Arc::new($environment.load_captcha_breaker_warm()?);
+        $router = $router.route(
+            $path,
+            post(move |State(state): State<AppState>, Json(request): Json<SolveRequest>| {
+                let breaker = breaker.clone();
+                async move { solve(state, breaker, $f, request).await }
+            }),
+        );
+    }};
+}
+
+/// 加载所有开启了feature的验证码实现并构建路由
+pub fn router(environment: &CaptchaEnvironment, config: &ServerConfig) -> Result<Router, Box<dyn Error>> {
+    #[allow(unused_mut)]
+    let mut router: Router<AppState> = Router::new().route("/health", get(|| async { Json(json!({ "status": "ok" })) }));
+    #[cfg(feature = "chinese_click_0")]
+    route!(router, environment, "/chinese_click_0", crate::captcha::ChineseClick0, |cb, req| {
+        Ok(json!({ "points": cb.run_from_base64(&req.image)? }))
+    });
+    #[cfg(feature = "word_order_0")]
+    route!(router, environment, "/word_order_0", crate::captcha::WordOrder0, |cb, req| {
+        Ok(json!({ "points": cb.run_from_base64(&req.image)? }))
+    });
+    #[cfg(feature = "text_captcha_0")]
+    route!(router, environment, "/text_captcha_0", crate::captcha::TextCaptcha0, |cb, req| {
+        let (text, confidence) = cb.run_with_confidence(&crate::input::decode_base64(&req.image)?)?;
+        Ok(json!({ "text": text, "confidence": confidence }))
+    });
+    #[cfg(feature = "slider_gap_0")]
+    route!(router, environment, "/slider_gap_0", crate::captcha::SliderGap0, |cb, req| {
+        Ok(json!({ "offset": cb.run_from_base64(&req.image)? }))
+    });
+    #[cfg(feature = "puzzle_restore_0")]
+    route!(router, environment, "/puzzle_restore_0", crate::captcha::PuzzleRestore0, |cb, req| {
+        let solution = cb.run(&crate::input::decode_base64(&req.image)?)?;
+        Ok(json!({
+            "rows": solution.rows,
+            "cols": solution.cols,
+            "arrangement": solution.arrangement,
+            "swaps": solution.swaps,
+        }))
+    });
+    #[cfg(feature = "tile_order_0")]
+    route!(router, environment, "/tile_order_0", crate::captcha::TileOrder0, |cb, req| {
+        let solution = cb.run_from_base64(&req.image)?;
+        let drags: Vec<Value> = solution
+            .drags
+            .iter()
+            .map(|d| json!({ "from": d.from, "to": d.to, "start": d.start, "end": d.end }))
+            .collect();
+        Ok(json!({
+            "rows": solution.rows,
+            "cols": solution.cols,
+            "arrangement": solution.arrangement,
+            "drags": drags,
+        }))
+    });
+    #[cfg(feature = "color_click_0")]
+    route!(router, environment, "/color_click_0", crate::captcha::ColorClick0, |cb, req| {
+        Ok(json!({ "points": cb.run_from_base64(&req.image, &req.prompt)? }))
+    });
+    #[cfg(feature = "space_click_0")]
+    route!(router, environment, "/space_click_0", crate::captcha::SpaceClick0, |cb, req| {
+        Ok(json!({ "point": cb.run_from_base64(&req.image, &req.prompt)? }))
+    });
+    #[cfg(feature = "shape_click_0")]
+    route!(router, environment, "/shape_click_0", crate::captcha::ShapeClick0, |cb, req| {
+        Ok(json!({ "points": cb.run_from_base64(&req.image, &req.prompt)? }))
+    });
+    #[cfg(feature = "audio")]
+    route!(router, environment, "/audio_captcha_0", crate::captcha::AudioCaptcha0, |cb, req| {
+        Ok(json!({ "text": cb.run_from_bytes(&crate::input::decode_base64_bytes(&req.image)?)? }))
+    });
+    Ok(router.with_state(AppState {
+        permits: Arc::new(Semaphore::new(config.max_concurrency.max(1))),
+    }))
+}
+
+/// 启动HTTP服务，直到进程退出
+pub async fn serve(environment: &CaptchaEnvironment, config: ServerConfig) -> Result<(), Box<dyn Error>> {
+    let app = router(environment, &config)?;
+    let listener = tokio::net::TcpListener::bind(config.addr).await?;
+    log::info!("captcha_breaker server listening on {}", config.addr);
+    axum::serve(listener, app).await?;
+    Ok(())
+}
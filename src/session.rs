@@ -0,0 +1,261 @@
+//! 多步验证码会话
+//!
+//! 部分服务商要求依次通过多个验证码(例如先点选再滑块)，步骤之间共享服务商下发的令牌等状态。
+//! [`SolveSession`] 按顺序串联多个步骤，每次 [`next`](SolveSession::next) 识别当前步骤的验证码
+
+use image::DynamicImage;
+use std::collections::HashMap;
+use std::error::Error;
+
+/// [`SessionState`] 中提示词的键，需要提示词的验证码(例如SpaceClick0)从这里读取
+pub const KEY_PROMPT: &str = "prompt";
+
+/// 一个步骤的答案
+#[derive(Debug, Clone, PartialEq)]
+pub enum StepAnswer {
+    /// 依次点击的坐标
+    Clicks(Vec<(f32, f32)>),
+    /// 滑块需要移动的距离(像素)
+    Slide(f32),
+    Text(String),
+    /// 需要选中的格子下标
+    Cells(Vec<usize>),
+    /// 没有找到答案
+    NotFound,
+}
+
+/// 步骤之间共享的状态
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SessionState {
+    /// 令牌、提示词等键值对，由调用方或步骤写入
+    pub values: HashMap<String, String>,
+    /// 已完成步骤的答案
+    pub history: Vec<StepAnswer>,
+}
+
+impl SessionState {
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(String::as_str)
+    }
+
+    pub fn set(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        self.values.insert(key.into(), value.into());
+    }
+
+    /// 取出并删除，适合只对下一步有效的值(例如提示词)
+    pub fn take(&mut self, key: &str) -> Option<String> {
+        self.values.remove(key)
+    }
+}
+
+/// 会话中的一个步骤
+pub trait SessionStep {
+    fn name(&self) -> &str;
+
+    fn solve(&self, image: &DynamicImage, state: &mut SessionState) -> Result<StepAnswer, Box<dyn Error>>;
+}
+
+impl<S: SessionStep + ?Sized> SessionStep for &S {
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+
+    fn solve(&self, image: &DynamicImage, state: &mut SessionState) -> Result<StepAnswer, Box<dyn Error>> {
+        (**self).solve(image, state)
+    }
+}
+
+/// 由闭包实现的步骤，见 [`step`]
+pub struct FnStep<F> {
+    name: String,
+    f: F,
+}
+
+impl<F> SessionStep for FnStep<F>
+where
+    F: Fn(&DynamicImage, &mut SessionState) -> Result<StepAnswer, Box<dyn Error>>,
+{
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn solve(&self, image: &DynamicImage, state: &mut SessionState) -> Result<StepAnswer, Box<dyn Error>> {
+        (self.f)(image, state)
+    }
+}
+
+/// 用闭包创建步骤，可以在识别前后读写令牌等状态
+pub fn step<F>(name: impl Into<String>, f: F) -> FnStep<F>
+where
+    F: Fn(&DynamicImage, &mut SessionState) -> Result<StepAnswer, Box<dyn Error>>,
+{
+    FnStep { name: name.into(), f }
+}
+
+/// [`SolveSession::next`] 的结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct StepResult {
+    /// 步骤的下标
+    pub index: usize,
+    pub name: String,
+    pub answer: StepAnswer,
+    /// 是否已经是最后一步
+    pub finished: bool,
+}
+
+/// 按顺序串联多个步骤的会话
+///
+/// 步骤出错时不会前进，可以用新的图片重试同一步
+#[derive(Default)]
+pub struct SolveSession<'a> {
+    steps: Vec<Box<dyn SessionStep + 'a>>,
+    state: SessionState,
+    current: usize,
+}
+
+impl<'a> SolveSession<'a> {
+    pub fn new() -> Self {
+        SolveSession {
+            steps: vec![],
+            state: SessionState::default(),
+            current: 0,
+        }
+    }
+
+    /// 追加一个步骤，验证码实现可以直接传入引用
+    pub fn then(mut self, step: impl SessionStep + 'a) -> Self {
+        self.steps.push(Box::new(step));
+        self
+    }
+
+    /// 设置初始状态，例如服务商下发的会话令牌
+    pub fn with_state(mut self, state: SessionState) -> Self {
+        self.state = state;
+        self
+    }
+
+    pub fn state(&self) -> &SessionState {
+        &self.state
+    }
+
+    pub fn state_mut(&mut self) -> &mut SessionState {
+        &mut self.state
+    }
+
+    /// 当前步骤的名称，所有步骤完成后返回None
+    pub fn current_step(&self) -> Option<&str> {
+        self.steps.get(self.current).map(|step| step.name())
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.current >= self.steps.len()
+    }
+
+    /// 识别当前步骤的验证码并前进到下一步
+    pub fn next(&mut self, image: &DynamicImage) -> Result<StepResult, Box<dyn Error>> {
+        let Some(step) = self.steps.get(self.current) else {
+            return Err("会话的所有步骤都已完成".into());
+        };
+        let answer = step.solve(image, &mut self.state)?;
+        let result = StepResult {
+            index: self.current,
+            name: step.name().to_string(),
+            answer: answer.clone(),
+            finished: self.current + 1 == self.steps.len(),
+        };
+        self.state.history.push(answer);
+        self.current += 1;
+        Ok(result)
+    }
+
+    /// 服务商拒绝答案、要求从头开始时调用，清空答案历史但保留键值对
+    pub fn reset(&mut self) {
+        self.current = 0;
+        self.state.history.clear();
+    }
+}
+
+#[cfg(feature = "chinese_click_0")]
+impl SessionStep for crate::captcha::ChineseClick0 {
+    fn name(&self) -> &str {
+        "chinese_click_0"
+    }
+
+    fn solve(&self, image: &DynamicImage, _: &mut SessionState) -> Result<StepAnswer, Box<dyn Error>> {
+        Ok(StepAnswer::Clicks(self.run(image)?))
+    }
+}
+
+/// 提示词从状态的 [`KEY_PROMPT`] 中读取
+#[cfg(feature = "color_click_0")]
+impl SessionStep for crate::captcha::ColorClick0 {
+    fn name(&self) -> &str {
+        "color_click_0"
+    }
+
+    fn solve(&self, image: &DynamicImage, state: &mut SessionState) -> Result<StepAnswer, Box<dyn Error>> {
+        let prompt = state.get(KEY_PROMPT).ok_or("ColorClick0需要在会话状态中设置提示词")?;
+        Ok(StepAnswer::Clicks(self.run(image, prompt)?))
+    }
+}
+
+#[cfg(feature = "word_order_0")]
+impl SessionStep for crate::captcha::WordOrder0 {
+    fn name(&self) -> &str {
+        "word_order_0"
+    }
+
+    fn solve(&self, image: &DynamicImage, _: &mut SessionState) -> Result<StepAnswer, Box<dyn Error>> {
+        Ok(StepAnswer::Clicks(self.run(image)?))
+    }
+}
+
+#[cfg(feature = "slider_gap_0")]
+impl SessionStep for crate::captcha::SliderGap0 {
+    fn name(&self) -> &str {
+        "slider_gap_0"
+    }
+
+    fn solve(&self, image: &DynamicImage, _: &mut SessionState) -> Result<StepAnswer, Box<dyn Error>> {
+        Ok(self.run(image)?.map_or(StepAnswer::NotFound, StepAnswer::Slide))
+    }
+}
+
+#[cfg(feature = "text_captcha_0")]
+impl SessionStep for crate::captcha::TextCaptcha0 {
+    fn name(&self) -> &str {
+        "text_captcha_0"
+    }
+
+    fn solve(&self, image: &DynamicImage, _: &mut SessionState) -> Result<StepAnswer, Box<dyn Error>> {
+        Ok(StepAnswer::Text(self.run(image)?))
+    }
+}
+
+/// 提示词从状态的 [`KEY_PROMPT`] 中读取
+#[cfg(feature = "space_click_0")]
+impl SessionStep for crate::captcha::SpaceClick0 {
+    fn name(&self) -> &str {
+        "space_click_0"
+    }
+
+    fn solve(&self, image: &DynamicImage, state: &mut SessionState) -> Result<StepAnswer, Box<dyn Error>> {
+        let prompt = state.get(KEY_PROMPT).ok_or("SpaceClick0需要在会话状态中设置提示词")?;
+        Ok(self
+            .run(image, prompt)?
+            .map_or(StepAnswer::NotFound, |point| StepAnswer::Clicks(vec![point])))
+    }
+}
+
+/// 提示词从状态的 [`KEY_PROMPT`] 中读取
+#[cfg(feature = "shape_click_0")]
+impl SessionStep for crate::captcha::ShapeClick0 {
+    fn name(&self) -> &str {
+        "shape_click_0"
+    }
+
+    fn solve(&self, image: &DynamicImage, state: &mut SessionState) -> Result<StepAnswer, Box<dyn Error>> {
+        let prompt = state.get(KEY_PROMPT).ok_or("ShapeClick0需要在会话状态中设置提示词")?;
+        Ok(StepAnswer::Clicks(self.run(image, prompt)?))
+    }
+}
@@ -0,0 +1,139 @@
+use crate::captcha::QualityPreset;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// 延迟SLO配置
+#[derive(Debug, Clone)]
+pub struct SloConfig {
+    /// 单次识别的目标延迟
+    pub target: Duration,
+    /// 统计最近多少次识别
+    pub window: usize,
+    /// 窗口内超过目标延迟的比例高于该值时降级
+    pub violation_ratio: f32,
+    /// 窗口内所有延迟都低于 `target * recovery_factor` 时升级
+    pub recovery_factor: f32,
+    /// 两次调整之间至少间隔的识别次数
+    pub cooldown: usize,
+    /// 允许降到的最低档
+    pub floor: QualityPreset,
+    /// 允许升到的最高档
+    pub ceiling: QualityPreset,
+}
+
+impl Default for SloConfig {
+    fn default() -> Self {
+        SloConfig {
+            target: Duration::from_millis(200),
+            window: 32,
+            violation_ratio: 0.2,
+            recovery_factor: 0.6,
+            cooldown: 32,
+            floor: QualityPreset::Fast,
+            ceiling: QualityPreset::High,
+        }
+    }
+}
+
+/// 一次质量调整
+#[derive(Debug, Clone, PartialEq)]
+pub struct SloDecision {
+    pub from: QualityPreset,
+    pub to: QualityPreset,
+    /// 做出决定时窗口内超时的比例
+    pub violation_ratio: f32,
+}
+
+struct SloState {
+    samples: VecDeque<Duration>,
+    preset: QualityPreset,
+    since_change: usize,
+}
+
+/// 预设切换时的回调
+type SloHook = Box<dyn Fn(&SloDecision) + Send + Sync>;
+
+/// 根据最近的识别延迟自动调整质量预设
+///
+/// 延迟持续超过目标时降级，负载下降后逐级恢复
+pub struct SloGuard {
+    config: SloConfig,
+    state: Mutex<SloState>,
+    hooks: Vec<SloHook>,
+}
+
+impl SloGuard {
+    pub fn new(config: SloConfig) -> Self {
+        SloGuard {
+            state: Mutex::new(SloState {
+                samples: VecDeque::with_capacity(config.window),
+                preset: config.ceiling,
+                since_change: 0,
+            }),
+            config,
+            hooks: vec![],
+        }
+    }
+
+    /// 注册质量调整时的回调
+    pub fn on_change(mut self, hook: impl Fn(&SloDecision) + Send + Sync + 'static) -> Self {
+        self.hooks.push(Box::new(hook));
+        self
+    }
+
+    /// 当前应使用的质量预设
+    pub fn preset(&self) -> QualityPreset {
+        self.state.lock().unwrap().preset
+    }
+
+    /// 记录一次识别耗时，发生调整时返回调整结果
+    pub fn observe(&self, latency: Duration) -> Option<SloDecision> {
+        let decision = {
+            let mut state = self.state.lock().unwrap();
+            if state.samples.len() == self.config.window.max(1) {
+                state.samples.pop_front();
+            }
+            state.samples.push_back(latency);
+            state.since_change += 1;
+            self.decide(&mut state)
+        };
+        // 回调在锁外执行，回调中可以再次调用preset
+        if let Some(decision) = &decision {
+            for hook in &self.hooks {
+                hook(decision);
+            }
+        }
+        decision
+    }
+
+    fn decide(&self, state: &mut SloState) -> Option<SloDecision> {
+        if state.since_change < self.config.cooldown || state.samples.len() < self.config.window {
+            return None;
+        }
+        let violations = state.samples.iter().filter(|&&d| d > self.config.target).count();
+        let violation_ratio = violations as f32 / state.samples.len() as f32;
+        let recovered = state
+            .samples
+            .iter()
+            .all(|&d| d.as_secs_f32() < self.config.target.as_secs_f32() * self.config.recovery_factor);
+
+        let next = if violation_ratio > self.config.violation_ratio {
+            state.preset.downgrade().filter(|&p| p >= self.config.floor)
+        } else if recovered {
+            state.preset.upgrade().filter(|&p| p <= self.config.ceiling)
+        } else {
+            None
+        }?;
+
+        let decision = SloDecision {
+            from: state.preset,
+            to: next,
+            violation_ratio,
+        };
+        state.preset = next;
+        state.since_change = 0;
+        state.samples.clear();
+        Some(decision)
+    }
+}
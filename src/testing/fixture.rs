@@ -0,0 +1,235 @@
+use crate::backend::{ElementType, TensorData, TensorInfo};
+use ndarray::ArrayD;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+const MAGIC: &[u8; 4] = b"CBFX";
+const VERSION: u32 = 1;
+
+/// 一次推理调用的输入与输出
+#[derive(Debug, Clone, PartialEq)]
+pub struct Call {
+    pub inputs: Vec<(String, TensorData)>,
+    pub outputs: HashMap<String, TensorData>,
+}
+
+/// 录制下来的模型描述与推理调用，使用小端序的简单二进制格式保存
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Fixture {
+    pub inputs: Vec<TensorInfo>,
+    pub outputs: Vec<TensorInfo>,
+    pub metadata: HashMap<String, String>,
+    pub calls: Vec<Call>,
+}
+
+impl Fixture {
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+        if let Some(parent) = path.as_ref().parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, self.to_bytes())?;
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        Self::from_bytes(&fs::read(path)?)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut w = Vec::new();
+        w.extend_from_slice(MAGIC);
+        write_u32(&mut w, VERSION);
+        for infos in [&self.inputs, &self.outputs] {
+            write_u32(&mut w, infos.len() as u32);
+            for info in infos {
+                write_str(&mut w, &info.name);
+                w.push(element_type_tag(info.element_type));
+                write_u32(&mut w, info.shape.len() as u32);
+                for &d in &info.shape {
+                    w.extend_from_slice(&d.to_le_bytes());
+                }
+            }
+        }
+        let mut metadata: Vec<_> = self.metadata.iter().collect();
+        metadata.sort();
+        write_u32(&mut w, metadata.len() as u32);
+        for (key, value) in metadata {
+            write_str(&mut w, key);
+            write_str(&mut w, value);
+        }
+        write_u32(&mut w, self.calls.len() as u32);
+        for call in &self.calls {
+            write_u32(&mut w, call.inputs.len() as u32);
+            for (name, tensor) in &call.inputs {
+                write_str(&mut w, name);
+                write_tensor(&mut w, tensor);
+            }
+            let mut outputs: Vec<_> = call.outputs.iter().collect();
+            outputs.sort_by(|a, b| a.0.cmp(b.0));
+            write_u32(&mut w, outputs.len() as u32);
+            for (name, tensor) in outputs {
+                write_str(&mut w, name);
+                write_tensor(&mut w, tensor);
+            }
+        }
+        w
+    }
+
+    pub fn from_bytes(mut bytes: &[u8]) -> Result<Self, Box<dyn Error>> {
+        let r = &mut bytes;
+        let mut magic = [0u8; 4];
+        r.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err("不是fixture文件".into());
+        }
+        let version = read_u32(r)?;
+        if version != VERSION {
+            return Err(format!("不支持的fixture版本 {}", version).into());
+        }
+        let mut infos = [vec![], vec![]];
+        for list in infos.iter_mut() {
+            for _ in 0..read_u32(r)? {
+                let name = read_str(r)?;
+                let element_type = element_type_from_tag(read_u8(r)?)?;
+                let rank = read_u32(r)? as usize;
+                let shape = (0..rank).map(|_| read_i64(r)).collect::<Result<_, _>>()?;
+                list.push(TensorInfo { name, shape, element_type });
+            }
+        }
+        let [inputs, outputs] = infos;
+        let mut metadata = HashMap::new();
+        for _ in 0..read_u32(r)? {
+            let key = read_str(r)?;
+            metadata.insert(key, read_str(r)?);
+        }
+        let mut calls = vec![];
+        for _ in 0..read_u32(r)? {
+            let mut call_inputs = vec![];
+            for _ in 0..read_u32(r)? {
+                let name = read_str(r)?;
+                call_inputs.push((name, read_tensor(r)?));
+            }
+            let mut call_outputs = HashMap::new();
+            for _ in 0..read_u32(r)? {
+                let name = read_str(r)?;
+                call_outputs.insert(name, read_tensor(r)?);
+            }
+            calls.push(Call {
+                inputs: call_inputs,
+                outputs: call_outputs,
+            });
+        }
+        Ok(Fixture {
+            inputs,
+            outputs,
+            metadata,
+            calls,
+        })
+    }
+}
+
+fn element_type_tag(element_type: ElementType) -> u8 {
+    match element_type {
+        ElementType::F32 => 0,
+        ElementType::I64 => 1,
+        ElementType::Other => 255,
+    }
+}
+
+fn element_type_from_tag(tag: u8) -> Result<ElementType, Box<dyn Error>> {
+    match tag {
+        0 => Ok(ElementType::F32),
+        1 => Ok(ElementType::I64),
+        255 => Ok(ElementType::Other),
+        _ => Err(format!("未知的元素类型 {}", tag).into()),
+    }
+}
+
+fn write_u32(w: &mut Vec<u8>, v: u32) {
+    w.extend_from_slice(&v.to_le_bytes());
+}
+
+fn write_str(w: &mut Vec<u8>, s: &str) {
+    write_u32(w, s.len() as u32);
+    w.extend_from_slice(s.as_bytes());
+}
+
+fn write_tensor(w: &mut Vec<u8>, tensor: &TensorData) {
+    w.push(element_type_tag(tensor.element_type()));
+    write_u32(w, tensor.shape().len() as u32);
+    for &d in tensor.shape() {
+        write_u32(w, d as u32);
+    }
+    match tensor {
+        TensorData::F32(a) => a.iter().for_each(|v| w.extend_from_slice(&v.to_le_bytes())),
+        TensorData::I64(a) => a.iter().for_each(|v| w.extend_from_slice(&v.to_le_bytes())),
+    }
+}
+
+fn read_u8(r: &mut &[u8]) -> Result<u8, Box<dyn Error>> {
+    let mut buf = [0u8; 1];
+    r.read_exact(&mut buf)?;
+    Ok(buf[0])
+}
+
+fn read_u32(r: &mut &[u8]) -> Result<u32, Box<dyn Error>> {
+    let mut buf = [0u8; 4];
+    r.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_i64(r: &mut &[u8]) -> Result<i64, Box<dyn Error>> {
+    let mut buf = [0u8; 8];
+    r.read_exact(&mut buf)?;
+    Ok(i64::from_le_bytes(buf))
+}
+
+fn read_str(r: &mut &[u8]) -> Result<String, Box<dyn Error>> {
+    let len = read_u32(r)? as usize;
+    if len > r.len() {
+        return Err("fixture文件已截断".into());
+    }
+    let (s, rest) = r.split_at(len);
+    *r = rest;
+    Ok(String::from_utf8(s.to_vec())?)
+}
+
+fn read_tensor(r: &mut &[u8]) -> Result<TensorData, Box<dyn Error>> {
+    let element_type = element_type_from_tag(read_u8(r)?)?;
+    let rank = read_u32(r)? as usize;
+    let shape: Vec<usize> = (0..rank).map(|_| read_u32(r).map(|d| d as usize)).collect::<Result<_, _>>()?;
+    let len: usize = shape.iter().product();
+    let element_size = match element_type {
+        ElementType::F32 => 4,
+        ElementType::I64 => 8,
+        ElementType::Other => return Err("fixture中的张量类型无效".into()),
+    };
+    if len.checked_mul(element_size).is_none_or(|n| n > r.len()) {
+        return Err("fixture文件已截断".into());
+    }
+    if element_type == ElementType::F32 {
+        let mut data = Vec::with_capacity(len);
+        let mut buf = [0u8; 4];
+        for _ in 0..len {
+            r.read_exact(&mut buf)?;
+            data.push(f32::from_le_bytes(buf));
+        }
+        Ok(TensorData::F32(ArrayD::from_shape_vec(shape, data)?))
+    } else {
+        let data = (0..len).map(|_| read_i64(r)).collect::<Result<_, _>>()?;
+        Ok(TensorData::I64(ArrayD::from_shape_vec(shape, data)?))
+    }
+}
+
+/// 比较两个f32张量，形状相同且每个元素相差不超过 `tolerance` 时返回true
+pub fn approx_eq(a: &TensorData, b: &TensorData, tolerance: f32) -> bool {
+    match (a, b) {
+        (TensorData::F32(a), TensorData::F32(b)) => {
+            a.shape() == b.shape() && a.iter().zip(b.iter()).all(|(x, y)| (x - y).abs() <= tolerance)
+        }
+        _ => a == b,
+    }
+}
@@ -0,0 +1,168 @@
+use super::fixture::{Call, Fixture, approx_eq};
+use crate::backend::{ElementType, InferenceBackend, TensorData, TensorInfo, TensorPool};
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::{Arc, Mutex};
+
+impl TensorInfo {
+    /// f32张量的描述，用于构造假模型，动态维度为-1
+    pub fn f32(name: &str, shape: Vec<i64>) -> Self {
+        TensorInfo {
+            name: name.to_string(),
+            shape,
+            element_type: ElementType::F32,
+        }
+    }
+}
+
+/// 按录制顺序回放输出的假模型，全部回放完后从头开始
+#[derive(Debug)]
+pub struct MockBackend {
+    fixture: Fixture,
+    next: Mutex<usize>,
+    input_tolerance: Option<f32>,
+}
+
+impl MockBackend {
+    pub fn new(inputs: Vec<TensorInfo>, outputs: Vec<TensorInfo>) -> Self {
+        Self::from_fixture(Fixture {
+            inputs,
+            outputs,
+            ..Default::default()
+        })
+    }
+
+    pub fn from_fixture(fixture: Fixture) -> Self {
+        MockBackend {
+            fixture,
+            next: Mutex::new(0),
+            input_tolerance: None,
+        }
+    }
+
+    /// 追加一次推理的输出，不校验输入
+    pub fn with_response(mut self, outputs: HashMap<String, TensorData>) -> Self {
+        self.fixture.calls.push(Call { inputs: vec![], outputs });
+        self
+    }
+
+    pub fn with_metadata(mut self, metadata: HashMap<String, String>) -> Self {
+        self.fixture.metadata = metadata;
+        self
+    }
+
+    /// 回放前检查输入与录制时一致(元素相差不超过 `tolerance`)，用于发现预处理的变化
+    pub fn with_input_tolerance(mut self, tolerance: f32) -> Self {
+        self.input_tolerance = Some(tolerance);
+        self
+    }
+
+    /// 已经回放的次数
+    pub fn calls(&self) -> usize {
+        *self.next.lock().unwrap()
+    }
+}
+
+impl InferenceBackend for MockBackend {
+    fn inputs(&self) -> &[TensorInfo] {
+        &self.fixture.inputs
+    }
+
+    fn outputs(&self) -> &[TensorInfo] {
+        &self.fixture.outputs
+    }
+
+    fn run(&self, inputs: Vec<(String, TensorData)>) -> Result<HashMap<String, TensorData>, Box<dyn Error>> {
+        self.replay(&inputs)
+    }
+
+    /// 回放只读取输入，读取后把输入放回pool
+    fn run_pooled(
+        &self,
+        inputs: Vec<(String, TensorData)>,
+        pool: &TensorPool,
+    ) -> Result<HashMap<String, TensorData>, Box<dyn Error>> {
+        let outputs = self.replay(&inputs);
+        inputs.into_iter().for_each(|(_, tensor)| pool.recycle(tensor));
+        outputs
+    }
+
+    fn metadata(&self) -> Result<HashMap<String, String>, Box<dyn Error>> {
+        Ok(self.fixture.metadata.clone())
+    }
+}
+
+impl MockBackend {
+    fn replay(&self, inputs: &[(String, TensorData)]) -> Result<HashMap<String, TensorData>, Box<dyn Error>> {
+        if self.fixture.calls.is_empty() {
+            return Err("MockBackend没有可回放的输出".into());
+        }
+        let index = {
+            let mut next = self.next.lock().unwrap();
+            *next += 1;
+            (*next - 1) % self.fixture.calls.len()
+        };
+        let call = &self.fixture.calls[index];
+        if let Some(tolerance) = self.input_tolerance {
+            for (name, expected) in &call.inputs {
+                let actual = inputs
+                    .iter()
+                    .find(|(n, _)| n == name)
+                    .ok_or_else(|| format!("第{}次调用缺少输入 {}", index, name))?;
+                if !approx_eq(&actual.1, expected, tolerance) {
+                    return Err(format!("第{}次调用的输入 {} 与录制时不一致", index, name).into());
+                }
+            }
+        }
+        Ok(call.outputs.clone())
+    }
+}
+
+/// 转发给真实后端并记录每次调用，用于生成 [`Fixture`]
+#[derive(Debug)]
+pub struct RecordingBackend {
+    inner: Arc<dyn InferenceBackend>,
+    calls: Mutex<Vec<Call>>,
+}
+
+impl RecordingBackend {
+    pub fn new(inner: Arc<dyn InferenceBackend>) -> Self {
+        RecordingBackend {
+            inner,
+            calls: Mutex::new(vec![]),
+        }
+    }
+
+    /// 目前为止录制的全部调用
+    pub fn fixture(&self) -> Fixture {
+        Fixture {
+            inputs: self.inner.inputs().to_vec(),
+            outputs: self.inner.outputs().to_vec(),
+            metadata: self.inner.metadata().unwrap_or_default(),
+            calls: self.calls.lock().unwrap().clone(),
+        }
+    }
+}
+
+impl InferenceBackend for RecordingBackend {
+    fn inputs(&self) -> &[TensorInfo] {
+        self.inner.inputs()
+    }
+
+    fn outputs(&self) -> &[TensorInfo] {
+        self.inner.outputs()
+    }
+
+    fn run(&self, inputs: Vec<(String, TensorData)>) -> Result<HashMap<String, TensorData>, Box<dyn Error>> {
+        let outputs = self.inner.run(inputs.clone())?;
+        self.calls.lock().unwrap().push(Call {
+            inputs,
+            outputs: outputs.clone(),
+        });
+        Ok(outputs)
+    }
+
+    fn metadata(&self) -> Result<HashMap<String, String>, Box<dyn Error>> {
+        self.inner.metadata()
+    }
+}
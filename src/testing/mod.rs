@@ -0,0 +1,25 @@
+//! 不依赖模型文件的测试工具
+//!
+//! 先用 [`RecordingBackend`] 包装真实模型跑一遍样例，把输入输出保存为 [`Fixture`]，
+//! CI中再用 [`MockBackend`] 回放，验证码实现的后处理(检测框划分、匹配、坐标换算)
+//! 就可以在没有模型文件的环境中测试
+//!
+//! ```ignore
+//! // 录制
+//! let environment = CaptchaEnvironment::default();
+//! let recorder = Arc::new(RecordingBackend::new(environment.load_backend(Model::Yolo11n)?));
+//! environment.insert_backend(Model::Yolo11n, recorder.clone())?;
+//! let cb: ChineseClick0 = environment.load_captcha_breaker()?;
+//! cb.run(&image)?;
+//! recorder.fixture().save("tests/fixtures/yolo11n_16.bin")?;
+//!
+//! // 回放
+//! let environment = CaptchaEnvironment::default();
+//! environment.insert_backend(Model::Yolo11n, Arc::new(MockBackend::from_fixture(Fixture::load("tests/fixtures/yolo11n_16.bin")?)))?;
+//! ```
+
+mod fixture;
+mod mock;
+
+pub use fixture::{Call, Fixture, approx_eq};
+pub use mock::{MockBackend, RecordingBackend};
@@ -25,3 +25,3697 @@ fn test2() {
         .write_all(&bytes)
         .unwrap();
 }
+
+#[test]
+fn test_decode_base64() {
+    use base64::Engine;
+    let mut bytes = vec![];
+    image::DynamicImage::new_rgb8(4, 4)
+        .write_to(&mut std::io::Cursor::new(&mut bytes), image::ImageFormat::Png)
+        .unwrap();
+    let data = format!(
+        "data:image/png;base64,{}",
+        base64::engine::general_purpose::STANDARD.encode(&bytes)
+    );
+    let image = crate::input::decode_base64(&data).unwrap();
+    assert_eq!((image.width(), image.height()), (4, 4));
+    assert!(matches!(
+        crate::input::decode_base64("not base64!"),
+        Err(crate::error::CaptchaError::InvalidBase64(_))
+    ));
+    assert!(matches!(
+        crate::input::decode_bytes(&bytes[..8]),
+        Err(crate::error::CaptchaError::InvalidImage(_))
+    ));
+}
+
+#[test]
+fn test_slo_guard() {
+    use crate::captcha::QualityPreset;
+    use crate::slo::{SloConfig, SloGuard};
+    use std::time::Duration;
+
+    let guard = SloGuard::new(SloConfig {
+        target: Duration::from_millis(100),
+        window: 4,
+        cooldown: 4,
+        ..Default::default()
+    });
+    for _ in 0..4 {
+        guard.observe(Duration::from_millis(150));
+    }
+    assert_eq!(guard.preset(), QualityPreset::Balanced);
+    for _ in 0..4 {
+        guard.observe(Duration::from_millis(10));
+    }
+    assert_eq!(guard.preset(), QualityPreset::High);
+}
+
+#[test]
+#[cfg(feature = "chinese_click_0")]
+fn test_quality_preset_config() {
+    use crate::captcha::{ChineseClick0Config, QualityPreset};
+    use crate::matching::TtaConfig;
+    use crate::validation::ValidationConfig;
+
+    let config = ChineseClick0Config {
+        tta: TtaConfig { scales: vec![1.2], ..Default::default() },
+        verify: Some(ValidationConfig::default()),
+        ..Default::default()
+    };
+    let high = config.for_quality(QualityPreset::High);
+    assert!(high.tta.is_enabled() && high.verify.is_some());
+    let balanced = config.for_quality(QualityPreset::Balanced);
+    assert_eq!(balanced.quality, QualityPreset::Balanced);
+    assert!(!balanced.tta.is_enabled() && balanced.verify.is_some());
+    let fast = config.for_quality(QualityPreset::Fast);
+    assert!(!fast.tta.is_enabled() && fast.verify.is_none());
+    assert_eq!(fast.max_answers, config.max_answers);
+}
+
+#[test]
+fn test_kbest() {
+    let costs = ndarray::array![[4.0f64, 1.0, 3.0], [2.0, 0.0, 5.0], [3.0, 2.0, 2.0]];
+    let best = crate::lap::kbest(&costs, 10).unwrap();
+    // 3x3 only has 3! assignments
+    assert_eq!(best.len(), 6);
+    assert_eq!(best[0].0, crate::lap::lapjv(&costs).unwrap().0);
+    assert_eq!(best[0].1, 5.0);
+    for pair in best.windows(2) {
+        assert!(pair[0].1 <= pair[1].1);
+        assert_ne!(pair[0].0, pair[1].0);
+    }
+}
+
+#[test]
+fn test_lapjv_masked() {
+    use crate::lap::{ErrorKind, lapjv, lapjv_masked};
+    let inf = f64::INFINITY;
+    // the unmasked optimum would be the diagonal
+    let costs = ndarray::array![[0.0, 5.0, inf], [inf, 0.0, 1.0], [2.0, inf, 0.0]];
+    let (rows, _) = lapjv(&costs).unwrap();
+    assert_eq!(rows, vec![0, 1, 2]);
+
+    let mask = ndarray::array![[false, true, true], [true, true, true], [true, true, true]];
+    let (rows, _) = lapjv_masked(&costs, Some(&mask)).unwrap();
+    assert_eq!(rows, vec![1, 2, 0]);
+    for (i, &j) in rows.iter().enumerate() {
+        assert!(mask[(i, j)] && costs[(i, j)].is_finite());
+    }
+
+    let infeasible = ndarray::array![[inf, inf], [1.0, 2.0]];
+    let err = lapjv(&infeasible).unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::Infeasible));
+}
+
+#[test]
+fn test_lapjv_integer_costs() {
+    let costs = ndarray::array![[4i64, 1, 3], [2, 0, 5], [3, 2, 2]];
+    let (rows, _) = crate::lap::lapjv(&costs).unwrap();
+    assert_eq!(crate::lap::cost(&costs, &rows), 5);
+    let best = crate::lap::kbest(&costs, 2).unwrap();
+    assert_eq!(best[1].1, 6);
+    assert_eq!(crate::lap::lapjv(&ndarray::array![[-7i32]]).unwrap().0, vec![0]);
+}
+
+#[test]
+fn test_auction_matches_lapjv() {
+    use crate::lap::{ErrorKind, Solver, cost, solve};
+    let costs = ndarray::Array2::from_shape_fn((8, 8), |(i, j)| ((i * 7 + j * 13) % 11) as f64);
+    let (exact, _) = solve(&costs, Solver::LapJV).unwrap();
+    let (auction, _) = solve(&costs, Solver::Auction).unwrap();
+    assert_eq!(cost(&costs, &exact), cost(&costs, &auction));
+
+    // 整列被屏蔽时直接报告无解，而不是竞价到迭代上限
+    let inf = f64::INFINITY;
+    let infeasible = ndarray::array![[1.0, inf], [2.0, inf]];
+    let err = solve(&infeasible, Solver::Auction).unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::Infeasible));
+    // 每行每列都有可用的格子，但前两行只能选第0列，没有完美匹配
+    let hall = ndarray::array![[1.0, inf, inf], [2.0, inf, inf], [3.0, 4.0, 5.0]];
+    let err = solve(&hall, Solver::Auction).unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::Infeasible));
+    // 只有一种可行分配时仍然能求解
+    let single = ndarray::array![[inf, 1.0, inf], [2.0, inf, inf], [3.0, 4.0, 5.0]];
+    assert_eq!(solve(&single, Solver::Auction).unwrap().0, vec![1, 0, 2]);
+}
+
+#[test]
+fn test_cost_matrix_metrics() {
+    use crate::matching::{DistanceMetric, cost_matrix};
+    let queries = ndarray::array![[1.0f32, 0.0, 2.0], [0.0, 0.0, 0.0]];
+    let candidates = ndarray::array![[0.5f32, 1.0, 2.0], [3.0, -1.0, 0.0], [1.0, 0.0, 2.0]];
+    for metric in [
+        DistanceMetric::Euclidean,
+        DistanceMetric::Manhattan,
+        DistanceMetric::Cosine,
+        DistanceMetric::Dot,
+    ] {
+        let matrix = cost_matrix(&queries.view(), &candidates.view(), metric);
+        for (i, q) in queries.rows().into_iter().enumerate() {
+            for (j, c) in candidates.rows().into_iter().enumerate() {
+                assert!((matrix[[i, j]] - metric.distance(q, c)).abs() < 1e-5, "{:?}", metric);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_puzzle_swap() {
+    use crate::tiles::{Compatibility, split_tiles, swaps_for};
+    // 平滑渐变图，交换第0块和第3块
+    let image = image::RgbImage::from_fn(40, 40, |x, y| image::Rgb([(x * 6) as u8, (y * 6) as u8, 128]));
+    let image = image::DynamicImage::ImageRgb8(image);
+    let mut tiles = split_tiles(&image, 2, 2).unwrap();
+    tiles.swap(0, 3);
+    let arrangement = Compatibility::new(&tiles).unwrap().best_swap(2);
+    assert_eq!(arrangement, vec![3, 1, 2, 0]);
+    assert_eq!(swaps_for(&arrangement), vec![(0, 3)]);
+
+    // 网格或图块大小无效时返回错误而不是panic
+    assert!(split_tiles(&image, 0, 2).is_err());
+    assert!(split_tiles(&image, 2, 0).is_err());
+    assert!(split_tiles(&image, 1, 41).is_err());
+    assert!(Compatibility::new(&[image::RgbImage::new(0, 4)]).is_err());
+    assert!(Compatibility::new(&[image::RgbImage::new(4, 4), image::RgbImage::new(2, 4)]).is_err());
+}
+
+#[test]
+fn test_tile_insertions() {
+    use crate::tiles::insertions_for;
+    // 依次执行拖动后，位置p上应为原来位于arrangement[p]的tile
+    let apply = |arrangement: &[usize]| {
+        let mut board: Vec<usize> = (0..arrangement.len()).collect();
+        for (from, to) in insertions_for(arrangement) {
+            let tile = board.remove(from);
+            board.insert(to, tile);
+        }
+        board
+    };
+    assert_eq!(insertions_for(&[1, 2, 3, 4, 0]), vec![(0, 4)]);
+    assert!(insertions_for(&[0, 1, 2, 3]).is_empty());
+    for arrangement in [vec![3, 0, 1, 2], vec![2, 0, 3, 1, 4], vec![5, 4, 3, 2, 1, 0]] {
+        assert_eq!(apply(&arrangement), arrangement);
+    }
+    // 完全逆序时只有一块可以不动
+    assert_eq!(insertions_for(&[5, 4, 3, 2, 1, 0]).len(), 5);
+}
+
+#[cfg(feature = "tile_order_0")]
+#[test]
+fn test_tile_order() {
+    use crate::captcha::{DragMode, TileOrder0, TileOrder0Config};
+    // 横向渐变图切成1x4，打乱后拼回
+    let image = image::RgbImage::from_fn(80, 20, |x, y| image::Rgb([(x * 3) as u8, (y * 8) as u8, 200 - x as u8]));
+    let order = [2, 0, 3, 1];
+    let mut shuffled = image::RgbImage::new(80, 20);
+    for (slot, &tile) in order.iter().enumerate() {
+        let view = image::imageops::crop_imm(&image, tile * 20, 0, 20, 20).to_image();
+        image::imageops::replace(&mut shuffled, &view, slot as i64 * 20, 0);
+    }
+    let shuffled = image::DynamicImage::ImageRgb8(shuffled);
+    let config = TileOrder0Config {
+        grid: Some((1, 4)),
+        ..Default::default()
+    };
+    let solution = TileOrder0::new().with_config(config.clone()).run(&shuffled).unwrap();
+    // 原来的第k块当前位于order中值为k的位置
+    assert_eq!(solution.arrangement, vec![1, 3, 0, 2]);
+    assert!(!solution.drags.is_empty());
+    let first = solution.drags[0];
+    assert_eq!(first.start, (first.from as f32 * 20.0 + 10.0, 10.0));
+
+    let swaps = TileOrder0::new()
+        .with_config(TileOrder0Config {
+            drag: DragMode::Swap,
+            ..config
+        })
+        .run(&shuffled)
+        .unwrap();
+    let mut board: Vec<usize> = (0..4).collect();
+    for drag in &swaps.drags {
+        board.swap(drag.from, drag.to);
+    }
+    assert_eq!(board, swaps.arrangement);
+}
+
+#[cfg(feature = "space_click_0")]
+#[test]
+fn test_space_query_parse() {
+    use crate::attribute::Color;
+    use crate::captcha::{Relation, SpaceQuery};
+    let labels: Vec<String> = ["cube", "cone", "A", "立方体"].iter().map(|s| s.to_string()).collect();
+
+    let query = SpaceQuery::parse("Click the cone that has the same color as the red cube", &labels);
+    assert_eq!(query.target.label.as_deref(), Some("cone"));
+    let (reference, relation) = query.reference.unwrap();
+    assert_eq!(relation, Relation::SameColor);
+    assert_eq!(reference.label.as_deref(), Some("cube"));
+    assert_eq!(reference.color, Some(Color::Red));
+
+    let query = SpaceQuery::parse("请点击与蓝色立方体颜色相同的A", &labels);
+    assert_eq!(query.target.label.as_deref(), Some("A"));
+    let (reference, _) = query.reference.unwrap();
+    assert_eq!(reference.label.as_deref(), Some("立方体"));
+    assert_eq!(reference.color, Some(Color::Blue));
+}
+
+#[cfg(feature = "audio")]
+#[test]
+fn test_log_mel_spectrogram() {
+    use crate::audio::{Audio, MelConfig, log_mel_spectrogram};
+    // 8kHz采样的1kHz正弦波，会先被重采样到16kHz
+    let samples = (0..8000)
+        .map(|i| (2.0 * std::f32::consts::PI * 1000.0 * i as f32 / 8000.0).sin())
+        .collect();
+    let audio = Audio { samples, sample_rate: 8000 };
+    let config = MelConfig::default();
+    let mel = log_mel_spectrogram(&audio, &config).unwrap();
+    assert_eq!(mel.shape()[0], config.n_mels);
+    assert_eq!(mel.shape()[1], (16000 - config.n_fft) / config.hop_length + 1);
+    let frame = mel.column(10);
+    let peak = (0..config.n_mels).max_by(|&a, &b| frame[a].total_cmp(&frame[b])).unwrap();
+    let low = frame[0];
+    assert!(frame[peak] > low + 5.0);
+    assert!(peak > 0 && peak < config.n_mels / 2);
+    // 帧移或FFT长度为0时返回错误，而不是除以0
+    assert!(log_mel_spectrogram(&audio, &MelConfig { hop_length: 0, ..Default::default() }).is_err());
+    assert!(log_mel_spectrogram(&audio, &MelConfig { n_fft: 0, ..Default::default() }).is_err());
+}
+
+#[test]
+fn test_yolo_decode_and_nms() {
+    use crate::detection::{YoloLayout, decode, decode_with_classes, nms};
+    use ndarray::array;
+    // v8布局：(4 + 2个类别, 3个锚点)
+    let output = array![
+        [50.0, 52.0, 150.0],
+        [50.0, 50.0, 50.0],
+        [20.0, 20.0, 20.0],
+        [20.0, 20.0, 20.0],
+        [0.9, 0.8, 0.1],
+        [0.05, 0.1, 0.7],
+    ];
+    let detections = decode(output.view(), YoloLayout::V8, 0.5).unwrap();
+    assert_eq!(detections.len(), 3);
+    assert_eq!(detections[2].class_id, 1);
+    assert_eq!(detections[0].rect.x_min, 40.0);
+    let kept = nms(detections, 0.45);
+    assert_eq!(kept.len(), 2);
+    assert_eq!(kept[0].confidence, 0.9);
+
+    // v5布局，3个类别：置信度为 objectness × 类别分数
+    let output = array![
+        [50.0, 50.0, 20.0, 20.0, 0.9, 0.1, 0.2, 0.8],
+        [80.0, 80.0, 20.0, 20.0, 0.4, 0.9, 0.0, 0.0],
+        [10.0, 10.0, 20.0, 20.0, 0.95, 0.6, 0.3, 0.1],
+    ];
+    let detections = decode_with_classes(output.view(), YoloLayout::Auto, 0.5, 3).unwrap();
+    assert_eq!(detections.len(), 2);
+    assert_eq!(detections[0].class_id, 2);
+    assert!((detections[0].confidence - 0.72).abs() < 1e-6);
+    assert_eq!(detections[1].class_id, 0);
+
+    // 形状与布局不符的输出返回错误，而不是越界panic
+    assert!(decode(ndarray::Array2::zeros((1, 5)).view(), YoloLayout::EndToEnd, 0.5).is_err());
+    assert!(decode(ndarray::Array2::zeros((3, 4)).view(), YoloLayout::V5, 0.5).is_err());
+    assert!(decode(ndarray::Array2::zeros((4, 10)).view(), YoloLayout::V8, 0.5).is_err());
+    assert!(decode(ndarray::Array2::zeros((5, 10)).view(), YoloLayout::Obb, 0.5).is_err());
+    assert!(decode(ndarray::Array2::zeros((5, 3)).view(), YoloLayout::Auto, 0.5).is_err());
+}
+
+#[test]
+fn test_assign_rectangular() {
+    use crate::lap::Solver;
+    use crate::matching::assign;
+    use ndarray::array;
+    let costs = array![[5.0, 1.0, 9.0, 4.0], [1.0, 6.0, 8.0, 7.0]];
+    assert_eq!(assign(&costs, Solver::LapJV).unwrap(), vec![Some(1), Some(0)]);
+    let costs = costs.t().to_owned();
+    let matches = assign(&costs, Solver::LapJV).unwrap();
+    assert_eq!(matches.iter().filter(|m| m.is_some()).count(), 2);
+    assert_eq!((matches[0], matches[1]), (Some(1), Some(0)));
+}
+
+#[test]
+fn test_outcome_of() {
+    use crate::lap::{ErrorKind, LapJVError};
+    use crate::timing::Outcome;
+    let ok: Result<(), Box<dyn std::error::Error>> = Ok(());
+    assert_eq!(Outcome::of(&ok), Outcome::Solved);
+    let cancelled: Result<(), Box<dyn std::error::Error>> = Err(Box::new(LapJVError::new(ErrorKind::Cancelled)));
+    assert_eq!(Outcome::of(&cancelled), Outcome::Cancelled);
+    let failed: Result<(), Box<dyn std::error::Error>> = Err("检测失败".into());
+    assert_eq!(Outcome::of(&failed), Outcome::Failed);
+}
+
+#[test]
+fn test_custom_backend() {
+    use crate::backend::{InferenceBackend, TensorData, TensorInfo};
+    use crate::matching::{SiameseConfig, SiameseMatcher};
+    use image::{Rgb, RgbImage};
+    use ndarray::Axis;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    /// 以各通道均值作为特征
+    #[derive(Debug)]
+    struct ChannelMean(Vec<TensorInfo>, Vec<TensorInfo>);
+
+    impl InferenceBackend for ChannelMean {
+        fn inputs(&self) -> &[TensorInfo] {
+            &self.0
+        }
+
+        fn outputs(&self) -> &[TensorInfo] {
+            &self.1
+        }
+
+        fn run(&self, inputs: Vec<(String, TensorData)>) -> Result<HashMap<String, TensorData>, Box<dyn std::error::Error>> {
+            let input = inputs[0].1.as_f32()?;
+            let features = input
+                .mean_axis(Axis(3))
+                .and_then(|a| a.mean_axis(Axis(2)))
+                .ok_or("空输入")?;
+            Ok(HashMap::from([("features".to_string(), TensorData::F32(features))]))
+        }
+    }
+
+    let backend: Arc<dyn InferenceBackend> = Arc::new(ChannelMean(vec![TensorInfo::f32("images", vec![-1, 3, 96, 96])], vec![TensorInfo::f32("features", vec![-1, 3])]));
+    assert_eq!(backend.input_size(), Some(96));
+
+    let solid = |c: [u8; 3]| RgbImage::from_pixel(8, 8, Rgb(c));
+    let queries = [solid([255, 0, 0]), solid([0, 255, 0]), solid([0, 0, 255])];
+    let candidates = [solid([0, 0, 250]), solid([250, 0, 0]), solid([0, 250, 0])];
+    let assignment = SiameseMatcher::new(backend.clone()).match_crops(&queries, &candidates).unwrap();
+    assert_eq!(assignment.matches, vec![Some(1), Some(2), Some(0)]);
+
+    // 分批推理的结果与一次推理相同
+    let chunked = SiameseMatcher::new(backend).with_config(SiameseConfig { max_batch_size: 2, ..Default::default() });
+    let chunked_assignment = chunked.match_crops(&queries, &candidates).unwrap();
+    assert_eq!(chunked_assignment.matches, assignment.matches);
+    assert_eq!(chunked_assignment.cost_matrix, assignment.cost_matrix);
+}
+
+#[test]
+fn test_mock_backend_replay() {
+    use crate::backend::{InferenceBackend, TensorData, TensorInfo};
+    use crate::testing::{Fixture, MockBackend, RecordingBackend};
+    use ndarray::array;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    let model = MockBackend::new(vec![TensorInfo::f32("x", vec![1, 2])], vec![TensorInfo::f32("y", vec![1])])
+        .with_response(HashMap::from([("y".to_string(), TensorData::from(array![3.0f32]))]));
+    let recorder = RecordingBackend::new(Arc::new(model));
+    recorder.run(vec![("x".to_string(), TensorData::from(array![[1.0f32, 2.0]]))]).unwrap();
+
+    let fixture = Fixture::from_bytes(&recorder.fixture().to_bytes()).unwrap();
+    assert_eq!(fixture, recorder.fixture());
+    let replay: Arc<dyn InferenceBackend> = Arc::new(MockBackend::from_fixture(fixture).with_input_tolerance(1e-3));
+    assert_eq!(replay.run_single(array![[1.0005f32, 2.0]]).unwrap(), TensorData::from(array![3.0f32]));
+    assert!(replay.run_single(array![[1.5f32, 2.0]]).is_err());
+}
+
+#[test]
+fn test_solve_policy() {
+    use crate::policy::{Attempt, SolvePolicy};
+    use image::DynamicImage;
+
+    #[derive(Debug)]
+    struct Answer(f32);
+
+    impl Attempt for Answer {
+        fn score(&self) -> f32 {
+            self.0
+        }
+
+        fn count(&self) -> usize {
+            1
+        }
+
+        fn rescale(self, _: f32, _: f32) -> Self {
+            self
+        }
+    }
+
+    let image = DynamicImage::new_rgb8(40, 20);
+    let policy = SolvePolicy { max_retries: 3, max_score: Some(0.5), ..Default::default() };
+    let mut scores = vec![0.9, 0.7, 0.3, 0.1].into_iter();
+    let mut sizes = vec![];
+    let res = policy
+        .solve(&image, |image| {
+            sizes.push((image.width(), image.height()));
+            Ok(Answer(scores.next().unwrap()))
+        })
+        .unwrap();
+    assert_eq!(res.0, 0.3);
+    assert_eq!(sizes.len(), 3);
+    assert_eq!(sizes[0], (40, 20));
+
+    let mut scores = vec![0.9, 0.6, 0.8, 0.7].into_iter();
+    let res = policy.solve(&image, |_| Ok(Answer(scores.next().unwrap()))).unwrap();
+    assert_eq!(res.0, 0.6);
+    assert!(policy.solve(&image, |_| Err::<Answer, _>("失败".into())).is_err());
+}
+
+#[test]
+fn test_validate_answers() {
+    use crate::validation::{Grade, ValidationConfig, validate};
+    use ndarray::array;
+    let config = ValidationConfig::default();
+    let costs = array![[0.1, 0.9, 0.8], [0.7, 0.2, 0.9]];
+    let points = [(10.0, 10.0), (50.0, 20.0)];
+    let res = validate(&points, (100, 100), &costs, &[Some(0), Some(1)], &config);
+    assert_eq!(res.grade, Grade::Good);
+    assert!((res.margins[0] - 0.875).abs() < 1e-6);
+
+    let ambiguous = array![[0.50, 0.52, 0.9], [0.7, 0.2, 0.9]];
+    assert_eq!(validate(&points, (100, 100), &ambiguous, &[Some(0), Some(1)], &config).grade, Grade::Uncertain);
+    assert_eq!(validate(&points, (100, 100), &costs, &[Some(0), Some(0)], &config).grade, Grade::Bad);
+    assert_eq!(validate(&[(10.0, 10.0), (120.0, 20.0)], (100, 100), &costs, &[Some(0), Some(1)], &config).grade, Grade::Bad);
+}
+
+#[test]
+fn test_trajectory() {
+    use crate::trajectory::{TrajectoryConfig, click_path, slide_path};
+    let config = TrajectoryConfig::default();
+    let targets = [(120.0, 80.0), (40.0, 200.0)];
+    let path = click_path((0.0, 0.0), &targets, &config);
+    assert_eq!(path.presses.len(), 2);
+    for (&i, target) in path.presses.iter().zip(&targets) {
+        assert_eq!((path.points[i].x, path.points[i].y), *target);
+    }
+    assert!(path.points.windows(2).all(|w| w[1].t > w[0].t));
+    assert_eq!(path, click_path((0.0, 0.0), &targets, &config));
+
+    let path = slide_path((30.0, 100.0), 150.0, &config);
+    let release = path.points[*path.releases.last().unwrap()];
+    assert!((release.x - 180.0).abs() < 1e-3);
+    assert_eq!(path.presses, vec![0]);
+}
+
+#[test]
+fn test_geometry_transforms() {
+    use crate::detection::Resize;
+    use crate::geometry::{ElementRect, Transform, model_to_image};
+    let close = |a: (f32, f32), b: (f32, f32)| (a.0 - b.0).abs() < 1e-3 && (a.1 - b.1).abs() < 1e-3;
+
+    // 344x384的图片补边缩放到384，内容区域为344x384
+    let to_image = model_to_image((344, 384), 384, Resize::Pad);
+    assert!(close(to_image.apply((172.0, 192.0)), (172.0, 192.0)));
+    let to_image = model_to_image((688, 768), 384, Resize::Pad);
+    assert!(close(to_image.apply((172.0, 192.0)), (344.0, 384.0)));
+    let to_image = model_to_image((640, 320), 320, Resize::Stretch);
+    assert!(close(to_image.apply((160.0, 160.0)), (320.0, 160.0)));
+
+    let element = ElementRect::new(100.0, 50.0, 172.0, 192.0).with_device_pixel_ratio(2.0);
+    let to_page = element.image_to_page((344, 384));
+    assert!(close(to_page.apply((344.0, 384.0)), (272.0, 242.0)));
+    assert!(close(to_page.inverse().apply((272.0, 242.0)), (344.0, 384.0)));
+    assert!(close(element.image_to_element((344, 384)).apply((172.0, 0.0)), (86.0, 0.0)));
+    assert!(close(element.image_to_device((344, 384)).apply((0.0, 0.0)), (200.0, 100.0)));
+    let t = Transform::scale(2.0, 4.0).then(Transform::translate(1.0, 1.0));
+    assert_eq!(t.then(t.inverse()), Transform::IDENTITY);
+}
+
+#[test]
+fn test_oriented_boxes() {
+    use crate::detection::{BBox, YoloLayout, crop_rotated, decode};
+    use crate::geometry::Rect;
+    use image::{Rgb, RgbImage};
+    use ndarray::array;
+    use std::f32::consts::FRAC_PI_2;
+
+    // 两个锚点，单类别，最后一行为角度
+    let output = array![[50.0, 10.0], [50.0, 10.0], [40.0, 4.0], [10.0, 4.0], [0.9, 0.1], [FRAC_PI_2, 0.0]];
+    let detections = decode(output.view(), YoloLayout::Obb, 0.5).unwrap();
+    assert_eq!(detections.len(), 1);
+    let d = &detections[0];
+    assert_eq!(d.angle, FRAC_PI_2);
+    let (x, y) = d.corners()[0];
+    assert!((x - 55.0).abs() < 1e-3 && (y - 30.0).abs() < 1e-3);
+
+    // 旋转90°后与轴对齐的竖直框完全重合
+    let mut upright = BBox::new(Rect::new(45.0, 30.0, 55.0, 70.0), 0.9, 0);
+    assert!((d.iou(&upright) - 1.0).abs() < 1e-3);
+    upright.rect.x_min = 50.0;
+    assert!((d.iou(&upright) - 0.5).abs() < 1e-3);
+    // 宽为0的旋转框与任何框都没有交集
+    let mut flat = d.clone();
+    flat.rect.x_min = flat.rect.x_max;
+    assert_eq!(d.iou(&flat), 0.0);
+    assert_eq!(flat.iou(d), 0.0);
+
+    // 竖直的白条旋转截取后变为水平
+    let image = RgbImage::from_fn(100, 100, |x, y| if (45..55).contains(&x) && (30..70).contains(&y) { Rgb([255; 3]) } else { Rgb([0; 3]) });
+    let crop = crop_rotated(&image, (50.0, 50.0), (40.0, 10.0), FRAC_PI_2);
+    assert_eq!(crop.dimensions(), (40, 10));
+    assert!(crop.pixels().all(|p| p[0] > 200));
+}
+
+#[test]
+fn test_bbox_helpers() {
+    use crate::detection::BBox;
+    use crate::geometry::Rect;
+    let a = BBox::new(Rect::new(0.0, 0.0, 10.0, 10.0), 0.9, 1);
+    let b = BBox::new(Rect::new(5.0, 0.0, 15.0, 10.0), 0.8, 1);
+    assert_eq!(a.center(), (5.0, 5.0));
+    assert_eq!(a.area(), 100.0);
+    assert!((a.iou(&b) - 1.0 / 3.0).abs() < 1e-6);
+    assert_eq!(a.clone().scaled(2.0, 0.5).rect, Rect::new(0.0, 0.0, 20.0, 5.0));
+    assert_eq!(Rect::new(2.7f32, 1.2, 12.2, 30.0).to_pixels((10, 20)), Rect::new(2, 1, 10, 20));
+}
+
+#[test]
+fn test_rect_overlap_metrics() {
+    use crate::geometry::{Rect, pairwise_iou};
+    let close = |a: f32, b: f32| (a - b).abs() < 1e-6;
+    let a = Rect::new(0.0, 0.0, 10.0, 10.0);
+    let b = Rect::new(5.0, 0.0, 15.0, 10.0);
+    assert_eq!(a.intersection(&b), Some(Rect::new(5.0, 0.0, 10.0, 10.0)));
+    assert_eq!(a.intersection_area(&b), 50.0);
+    assert_eq!(a.union_area(&b), 150.0);
+    assert_eq!(a.enclosing(&b), Rect::new(0.0, 0.0, 15.0, 10.0));
+    assert!(close(a.iou(&b), 1.0 / 3.0));
+    // 包围矩形恰好是并集，GIoU等于IoU
+    assert!(close(a.giou(&b), 1.0 / 3.0));
+    assert!(close(a.diou(&b), 1.0 / 3.0 - 25.0 / 325.0));
+    assert_eq!(a.center_distance(&b), 5.0);
+
+    // 完全重合
+    assert!(close(a.iou(&a), 1.0) && close(a.giou(&a), 1.0) && close(a.diou(&a), 1.0));
+
+    // 只有边相接时不算相交
+    let touching = Rect::new(10.0, 0.0, 20.0, 10.0);
+    assert_eq!(a.intersection(&touching), None);
+    assert_eq!(a.iou(&touching), 0.0);
+    assert!(close(a.giou(&touching), 0.0));
+
+    // 不相交时IoU都为0，GIoU随距离变远而减小并趋近-1
+    let near = Rect::new(20.0, 0.0, 30.0, 10.0);
+    let far = Rect::new(90.0, 0.0, 100.0, 10.0);
+    assert_eq!(a.iou(&near), a.iou(&far));
+    assert!(close(a.giou(&near), -1.0 / 3.0));
+    assert!(close(a.giou(&far), -0.8));
+    assert!(a.diou(&far) < a.diou(&near));
+
+    // 包含关系
+    let inner = Rect::new(2.0, 2.0, 8.0, 8.0);
+    assert!(close(a.iou(&inner), 0.36) && close(a.giou(&inner), 0.36));
+    assert_eq!(a.center_distance(&inner), 0.0);
+
+    // 对称性
+    for other in [b, touching, near, inner] {
+        assert!(close(a.iou(&other), other.iou(&a)));
+        assert!(close(a.giou(&other), other.giou(&a)));
+        assert!(close(a.diou(&other), other.diou(&a)));
+    }
+
+    // 退化的矩形不产生NaN
+    let empty = Rect::new(3.0, 3.0, 3.0, 3.0);
+    assert_eq!(empty.iou(&empty), 0.0);
+    assert_eq!(empty.giou(&empty), 0.0);
+    assert_eq!(empty.diou(&empty), 0.0);
+    assert_eq!(a.iou(&empty), 0.0);
+
+    assert_eq!(a.distance_to((5.0, 5.0)), 0.0);
+    assert_eq!(a.distance_to((13.0, 5.0)), 3.0);
+    assert_eq!(a.distance_to((13.0, 14.0)), 5.0);
+    assert_eq!(a.distance_to((-3.0, -4.0)), 5.0);
+
+    let ious = pairwise_iou(&[a, inner], &[b, a, far]);
+    assert_eq!(ious.dim(), (2, 3));
+    assert!(close(ious[[0, 0]], 1.0 / 3.0) && close(ious[[0, 1]], 1.0) && ious[[0, 2]] == 0.0);
+    assert!(close(ious[[1, 1]], 0.36));
+    assert_eq!(pairwise_iou(&[], &[a]).dim(), (0, 1));
+}
+
+#[test]
+fn test_calibration() {
+    use crate::matching::Calibration;
+    use crate::metadata::EmbeddedMetadata;
+    let calibration = Calibration { threshold: 0.8, temperature: 0.1 };
+    assert!((calibration.probability(0.8) - 0.5).abs() < 1e-6);
+    assert!(calibration.probability(0.2) > 0.99 && calibration.probability(1.4) < 0.01);
+
+    let mut metadata = EmbeddedMetadata::default();
+    calibration.write_metadata(&mut metadata);
+    let props = metadata.to_props().into_iter().collect();
+    assert_eq!(Calibration::from_metadata(&EmbeddedMetadata::from_props(props)), Some(calibration));
+
+    // 从带噪声的标注数据中恢复参数
+    let distances: Vec<f32> = (0..200).map(|i| i as f32 * 0.01).collect();
+    let matched: Vec<bool> = distances
+        .iter()
+        .enumerate()
+        .map(|(i, &d)| if i % 10 == 0 { d >= 0.8 } else { d < 0.8 })
+        .collect();
+    let fitted = Calibration::fit(&distances, &matched);
+    assert!((fitted.threshold - 0.8).abs() < 0.1, "{:?}", fitted);
+    assert!(fitted.probability(0.3) > 0.8 && fitted.probability(1.5) < 0.2);
+}
+
+#[test]
+fn test_reloadable_backend() {
+    use crate::backend::{InferenceBackend, ReloadableBackend, TensorData, TensorInfo};
+    use crate::testing::MockBackend;
+    use ndarray::array;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    let mock = |value: f32, output: &str| {
+        Arc::new(
+            MockBackend::new(vec![TensorInfo::f32("x", vec![1])], vec![TensorInfo::f32(output, vec![1])])
+                .with_response(HashMap::from([(output.to_string(), TensorData::from(array![value]))])),
+        )
+    };
+    let reloadable = Arc::new(ReloadableBackend::new(mock(1.0, "y")));
+    let backend: Arc<dyn InferenceBackend> = reloadable.clone();
+    assert_eq!(backend.run_single(array![0.0f32]).unwrap(), TensorData::from(array![1.0f32]));
+    reloadable.swap(mock(2.0, "y")).unwrap();
+    assert_eq!(backend.run_single(array![0.0f32]).unwrap(), TensorData::from(array![2.0f32]));
+    assert!(reloadable.swap(mock(3.0, "z")).is_err());
+    assert_eq!(backend.run_single(array![0.0f32]).unwrap(), TensorData::from(array![2.0f32]));
+}
+
+#[test]
+fn test_model_precision() {
+    use crate::model::{Model, Precision};
+
+    assert_eq!(Model::Siamese.file_name_with(Precision::Fp32), Model::Siamese.file_name());
+    assert_eq!(Model::Siamese.file_name_with(Precision::Int8), "siamese_int8.onnx");
+    assert_eq!(Model::Yolo11n.url_with(Precision::Fp32), Some(Model::Yolo11n.url()));
+    // 没有发布的精度不提供下载地址，避免把404页面当作模型写入
+    assert_eq!(Model::Yolo11n.url_with(Precision::Fp16), None);
+    assert_eq!(Precision::Int8.fallbacks(), vec![Precision::Int8, Precision::Fp32]);
+    assert_eq!(Precision::Fp32.fallbacks(), vec![Precision::Fp32]);
+    assert!(!Precision::Fp16.suits_cpu());
+}
+
+#[test]
+#[cfg(feature = "chinese_click_0")]
+fn test_chinese_click_no_detections() {
+    use crate::captcha::ChineseClick0;
+    use crate::error::CaptchaError;
+    use image::DynamicImage;
+
+    let environment = mock_click_environment(CaptchaEnvironment::default(), &[], ndarray::Array2::zeros((0, 8)), 384);
+    let cb: ChineseClick0 = environment.load_captcha_breaker().unwrap();
+    let err = cb.run(&DynamicImage::new_rgb8(384, 384)).unwrap_err();
+    assert!(matches!(err.downcast_ref::<CaptchaError>(), Some(CaptchaError::NoDetections)));
+}
+
+#[test]
+fn test_sanitize_boxes() {
+    use crate::detection::{BBox, SanitizeConfig, crop, sanitize};
+    use crate::geometry::Rect;
+    use image::RgbImage;
+
+    let bbox = |x_min, y_min, x_max, y_max| BBox::new(Rect::new(x_min, y_min, x_max, y_max), 0.9, 0);
+    let boxes = vec![
+        bbox(10.0, 10.0, 30.0, 30.0),
+        // 反向
+        bbox(30.0, 10.0, 10.0, 30.0),
+        bbox(f32::NAN, 10.0, 30.0, 30.0),
+        // 完全在图片外
+        bbox(120.0, 10.0, 150.0, 30.0),
+        // 部分超出
+        bbox(-20.0, 90.0, 20.0, 140.0),
+        // 过小
+        bbox(50.0, 50.0, 51.0, 52.0),
+    ];
+    let sanitized = sanitize(boxes, (100, 100), &SanitizeConfig::default());
+    let rects: Vec<Rect<f32>> = sanitized.iter().map(|b| b.rect).collect();
+    assert_eq!(
+        rects,
+        vec![Rect::new(10.0, 10.0, 30.0, 30.0), Rect::new(0.0, 90.0, 20.0, 100.0), Rect::new(48.0, 48.0, 53.0, 54.0)]
+    );
+    let image = RgbImage::new(100, 100);
+    for bbox in &sanitized {
+        let patch = crop(&image, bbox);
+        assert!(patch.width() > 0 && patch.height() > 0);
+    }
+}
+
+#[test]
+#[cfg(feature = "native")]
+fn test_deterministic_session_options() {
+    use crate::loader::SessionOptions;
+
+    let options = SessionOptions { memory_pattern: Some(false), ..Default::default() }.deterministic();
+    assert_eq!((options.intra_threads, options.inter_threads), (Some(1), Some(1)));
+    assert_eq!(options.parallel_execution, Some(false));
+    assert_eq!(options.memory_pattern, Some(false));
+}
+
+#[test]
+fn test_sort_boxes() {
+    use crate::detection::{BBox, BoxOrder, sort_boxes};
+    use crate::geometry::Rect;
+
+    let bbox = |x: f32, y: f32| BBox::new(Rect::new(x, y, x + 20.0, y + 20.0), 0.9, 0);
+    let x_of = |boxes: &[BBox]| boxes.iter().map(|b| (b.rect.x_min, b.rect.y_min)).collect::<Vec<_>>();
+    // 相差不到0.01像素的框也能正确排序
+    let boxes = vec![bbox(10.009, 0.0), bbox(10.001, 50.0)];
+    assert_eq!(x_of(&sort_boxes(boxes.clone(), BoxOrder::LeftToRight)), vec![(10.001, 50.0), (10.009, 0.0)]);
+    assert_eq!(x_of(&sort_boxes(boxes, BoxOrder::TopToBottom)), vec![(10.009, 0.0), (10.001, 50.0)]);
+
+    // 第二行的框左边界更小，但阅读顺序在第一行之后；同一行内y略有起伏
+    let boxes = vec![bbox(60.0, 3.0), bbox(5.0, 40.0), bbox(30.0, 0.0), bbox(35.0, 42.0), bbox(0.0, 2.0)];
+    assert_eq!(
+        x_of(&sort_boxes(boxes, BoxOrder::ReadingOrder)),
+        vec![(0.0, 2.0), (30.0, 0.0), (60.0, 3.0), (5.0, 40.0), (35.0, 42.0)]
+    );
+}
+
+/// 用模拟的检测与孪生网络后端构建ChineseClick0
+///
+/// boxes为 (cx, cy, 边长)，features为按识别顺序排列的问题与答案的特征
+#[cfg(feature = "chinese_click_0")]
+fn mock_chinese_click(boxes: &[(f32, f32, f32)], features: ndarray::Array2<f32>, input_size: i64) -> crate::captcha::ChineseClick0 {
+    mock_chinese_click_in(CaptchaEnvironment::default(), boxes, features, input_size)
+}
+
+/// 与 [`mock_chinese_click`] 相同，使用指定的环境
+#[cfg(feature = "chinese_click_0")]
+fn mock_chinese_click_in(
+    environment: CaptchaEnvironment,
+    boxes: &[(f32, f32, f32)],
+    features: ndarray::Array2<f32>,
+    input_size: i64,
+) -> crate::captcha::ChineseClick0 {
+    mock_click_environment(environment, boxes, features, input_size).load_captcha_breaker().unwrap()
+}
+
+/// 在环境中注册返回固定检测框与特征的检测、孪生模型
+#[cfg(feature = "chinese_click_0")]
+fn mock_click_environment(
+    environment: CaptchaEnvironment,
+    boxes: &[(f32, f32, f32)],
+    features: ndarray::Array2<f32>,
+    input_size: i64,
+) -> CaptchaEnvironment {
+    use crate::model::Model;
+    use std::sync::Arc;
+
+    let boxes: Vec<(f32, f32, f32, f32, f32)> = boxes.iter().map(|&(cx, cy, size)| (cx, cy, size, size, 0.9)).collect();
+    let yolo = mock_yolo(&boxes, input_size);
+    let siamese = mock_siamese(features);
+    environment.insert_backend(Model::Yolo11n, Arc::new(yolo)).unwrap();
+    environment.insert_backend(Model::Siamese, Arc::new(siamese)).unwrap();
+    environment
+}
+
+/// 收集f执行期间span记录的整数字段，没有在span中声明的字段不会出现
+#[cfg(all(feature = "tracing", feature = "chinese_click_0"))]
+fn recorded_span_fields(f: impl FnOnce()) -> std::collections::HashMap<String, u64> {
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+
+    struct Fields<'a>(&'a mut HashMap<String, u64>);
+    impl Visit for Fields<'_> {
+        fn record_u64(&mut self, field: &Field, value: u64) {
+            self.0.insert(field.name().to_string(), value);
+        }
+
+        fn record_debug(&mut self, _: &Field, _: &dyn std::fmt::Debug) {}
+    }
+
+    #[derive(Default)]
+    struct Collector {
+        next_id: AtomicU64,
+        fields: Mutex<HashMap<String, u64>>,
+    }
+    impl tracing::Subscriber for Collector {
+        fn enabled(&self, _: &tracing::Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            span.record(&mut Fields(&mut self.fields.lock().unwrap()));
+            Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+        }
+
+        fn record(&self, _: &Id, values: &Record<'_>) {
+            values.record(&mut Fields(&mut self.fields.lock().unwrap()));
+        }
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, _: &tracing::Event<'_>) {}
+
+        fn enter(&self, _: &Id) {}
+
+        fn exit(&self, _: &Id) {}
+    }
+
+    let dispatch = tracing::Dispatch::new(Collector::default());
+    tracing::dispatcher::with_default(&dispatch, f);
+    let collector = dispatch.downcast_ref::<Collector>().unwrap();
+    collector.fields.lock().unwrap().clone()
+}
+
+/// 每次推理都返回features的孪生网络
+#[cfg(any(feature = "chinese_click_0", feature = "render"))]
+fn mock_siamese(features: ndarray::Array2<f32>) -> crate::testing::MockBackend {
+    use crate::backend::{TensorData, TensorInfo};
+    use crate::testing::MockBackend;
+    use std::collections::HashMap;
+
+    let dim = features.ncols() as i64;
+    MockBackend::new(vec![TensorInfo::f32("images", vec![-1, 3, 96, 96])], vec![TensorInfo::f32("features", vec![-1, dim])])
+        .with_response(HashMap::from([("features".to_string(), TensorData::from(features))]))
+}
+
+/// 单类别YOLO检测模型，每个框为 (中心x, 中心y, 宽, 高, 置信度)
+#[cfg(any(feature = "chinese_click_0", feature = "shape_click_0"))]
+fn mock_yolo(boxes: &[(f32, f32, f32, f32, f32)], input_size: i64) -> crate::testing::MockBackend {
+    use crate::backend::{TensorData, TensorInfo};
+    use crate::testing::MockBackend;
+    use ndarray::Array3;
+    use std::collections::HashMap;
+
+    // 锚点数多于 4 + 类别数，自动判断的布局为V8；空锚点的分数为0
+    let anchors = (boxes.len() + 1).max(8);
+    let mut output = Array3::<f32>::zeros((1, 5, anchors));
+    for (i, &(cx, cy, width, height, confidence)) in boxes.iter().enumerate() {
+        for (row, value) in [cx, cy, width, height, confidence].into_iter().enumerate() {
+            output[[0, row, i]] = value;
+        }
+    }
+    MockBackend::new(vec![TensorInfo::f32("images", vec![1, 3, input_size, input_size])], vec![TensorInfo::f32("output0", vec![1, 5, anchors as i64])])
+        .with_response(HashMap::from([("output0".to_string(), TensorData::from(output))]))
+}
+
+/// 第row行为第class类的one-hot特征
+#[cfg(feature = "chinese_click_0")]
+fn one_hot_features(classes: &[usize], dim: usize) -> ndarray::Array2<f32> {
+    let mut features = ndarray::Array2::<f32>::zeros((classes.len(), dim));
+    for (row, &class) in classes.iter().enumerate() {
+        features[[row, class]] = 1.0;
+    }
+    features
+}
+
+#[test]
+#[cfg(feature = "chinese_click_0")]
+fn test_chinese_click_two_row_prompt() {
+    use image::DynamicImage;
+
+    // 三个答案，提示区两行共三个问题，第二行的问题左边界比第一行第二个问题小
+    let boxes = [
+        (50.0, 100.0, 40.0),
+        (150.0, 100.0, 40.0),
+        (250.0, 100.0, 40.0),
+        (30.0, 370.0, 20.0),
+        (230.0, 370.0, 20.0),
+        (70.0, 415.0, 20.0),
+    ];
+    // 特征依次为按显示顺序排列的问题与从左到右的答案，问题 i 与答案 [2, 0, 1][i] 相同
+    let cb = mock_chinese_click(&boxes, one_hot_features(&[2, 0, 1, 0, 1, 2], 3), 440);
+    let trace = cb.run_detailed(&DynamicImage::new_rgb8(440, 440)).unwrap();
+    let question_x: Vec<f32> = trace.question_boxes.iter().map(|b| b.center().0).collect();
+    assert_eq!(question_x, vec![30.0, 230.0, 70.0]);
+    assert_eq!(trace.points, vec![(250.0, 100.0), (50.0, 100.0), (150.0, 100.0)]);
+    let answers: Vec<(usize, usize)> = trace.answers.iter().map(|m| (m.question_index, m.answer_index)).collect();
+    assert_eq!(answers, vec![(0, 2), (1, 0), (2, 1)]);
+    assert!(trace.answers.iter().zip(&trace.points).all(|(m, p)| m.answer_point == *p && m.distance.abs() < 1e-6));
+}
+
+#[test]
+#[cfg(feature = "chinese_click_0")]
+fn test_chinese_click_prompt_rows() {
+    use image::DynamicImage;
+
+    // 提示区两行，第一行中间的字上边界(342)略高于344，仍属于第一行
+    let boxes = [
+        (60.0, 100.0, 40.0),
+        (160.0, 100.0, 40.0),
+        (260.0, 150.0, 40.0),
+        (360.0, 150.0, 40.0),
+        (20.0, 356.0, 24.0),
+        (60.0, 354.0, 24.0),
+        (100.0, 357.0, 24.0),
+        (20.0, 400.0, 24.0),
+    ];
+    let cb = mock_chinese_click(&boxes, one_hot_features(&[3, 2, 1, 0, 0, 1, 2, 3], 4), 440);
+    let trace = cb.run_detailed(&DynamicImage::new_rgb8(440, 440)).unwrap();
+    assert_eq!(trace.ans_boxes.len(), 4);
+    let questions: Vec<(f32, f32)> = trace.question_boxes.iter().map(|b| b.center()).collect();
+    assert_eq!(questions, vec![(20.0, 356.0), (60.0, 354.0), (100.0, 357.0), (20.0, 400.0)]);
+    assert_eq!(trace.points, vec![(360.0, 150.0), (260.0, 150.0), (160.0, 100.0), (60.0, 100.0)]);
+}
+
+#[test]
+fn test_enhance() {
+    use crate::enhance::{EnhanceConfig, enhance, median_filter, normalize_contrast, remove_background};
+    use image::{Rgb, RgbImage};
+    use std::borrow::Cow;
+
+    // 椒盐噪点被中值滤波去除
+    let mut noisy = RgbImage::from_pixel(5, 5, Rgb([100, 100, 100]));
+    noisy.put_pixel(2, 2, Rgb([255, 255, 255]));
+    assert_eq!(median_filter(&noisy).get_pixel(2, 2), &Rgb([100, 100, 100]));
+
+    // 低对比度的图片被拉伸到整个范围
+    let low = RgbImage::from_fn(10, 10, |x, _| if x < 5 { Rgb([100, 100, 100]) } else { Rgb([150, 150, 150]) });
+    let stretched = normalize_contrast(&low);
+    assert_eq!((stretched.get_pixel(0, 0)[0], stretched.get_pixel(9, 0)[0]), (0, 255));
+
+    // 与边缘颜色相近的背景变为白色，前景保留
+    let mut image = RgbImage::from_pixel(6, 6, Rgb([30, 60, 90]));
+    image.put_pixel(3, 3, Rgb([200, 0, 0]));
+    image.put_pixel(2, 2, Rgb([33, 58, 92]));
+    let cleaned = remove_background(&image, 5);
+    assert_eq!(cleaned.get_pixel(2, 2), &Rgb([255, 255, 255]));
+    assert_eq!(cleaned.get_pixel(3, 3), &Rgb([200, 0, 0]));
+
+    assert!(matches!(EnhanceConfig::default().apply(&image), Cow::Borrowed(_)));
+    let config = EnhanceConfig { denoise: true, sharpen: Some(1.0), ..Default::default() };
+    assert_eq!(enhance(&image, &config).dimensions(), (6, 6));
+}
+
+#[test]
+fn test_color_normalization() {
+    use crate::input::{self, DEFAULT_BACKGROUND};
+    use image::codecs::png::PngEncoder;
+    use image::{DynamicImage, ImageBuffer, ImageEncoder, Luma, LumaA, Rgb, Rgba};
+
+    let gray = DynamicImage::ImageLuma8(ImageBuffer::from_pixel(2, 2, Luma([80u8])));
+    assert_eq!(input::to_rgb8(&gray, DEFAULT_BACKGROUND).get_pixel(0, 0), &Rgb([80, 80, 80]));
+
+    let gray_alpha = DynamicImage::ImageLumaA8(ImageBuffer::from_pixel(2, 2, LumaA([0u8, 0])));
+    assert_eq!(input::to_rgb8(&gray_alpha, DEFAULT_BACKGROUND).get_pixel(0, 0), &Rgb([255, 255, 255]));
+
+    let wide = DynamicImage::ImageRgb16(ImageBuffer::from_pixel(2, 2, Rgb([65535u16, 0, 32896])));
+    assert_eq!(input::to_rgb8(&wide, DEFAULT_BACKGROUND).get_pixel(0, 0), &Rgb([255, 0, 128]));
+
+    // 半透明的红色合成到黑色背景上，而不是直接丢弃透明通道
+    let rgba = DynamicImage::ImageRgba8(ImageBuffer::from_pixel(2, 2, Rgba([255u8, 0, 0, 128])));
+    assert_eq!(input::to_rgb8(&rgba, Rgb([0, 0, 0])).get_pixel(0, 0), &Rgb([128, 0, 0]));
+    assert_eq!(input::to_rgb8(&rgba, DEFAULT_BACKGROUND).get_pixel(0, 0), &Rgb([255, 127, 127]));
+
+    // 编码为PNG后再解码的结果一致
+    let mut bytes = vec![];
+    PngEncoder::new(&mut bytes).write_image(rgba.as_bytes(), 2, 2, image::ExtendedColorType::Rgba8).unwrap();
+    let decoded = input::decode_bytes(&bytes).unwrap();
+    assert_eq!(input::to_rgb8(&decoded, DEFAULT_BACKGROUND), input::to_rgb8(&rgba, DEFAULT_BACKGROUND));
+
+    // 2x2的调色板PNG，调色板 [(10, 20, 30), (200, 0, 0)]，第二个颜色完全透明，像素为 [[0, 1], [1, 0]]
+    let paletted: [u8; 101] = [
+        137, 80, 78, 71, 13, 10, 26, 10, 0, 0, 0, 13, 73, 72, 68, 82, 0, 0, 0, 2, 0, 0, 0, 2, 8, 3, 0, 0, 0, 69, 104, 253,
+        22, 0, 0, 0, 6, 80, 76, 84, 69, 10, 20, 30, 200, 0, 0, 190, 230, 76, 191, 0, 0, 0, 2, 116, 82, 78, 83, 255, 0, 229,
+        183, 48, 74, 0, 0, 0, 12, 73, 68, 65, 84, 120, 156, 99, 96, 96, 4, 66, 0, 0, 12, 0, 3, 43, 99, 203, 80, 0, 0, 0, 0,
+        73, 69, 78, 68, 174, 66, 96, 130,
+    ];
+    let rgb = input::to_rgb8(&input::decode_bytes(&paletted).unwrap(), DEFAULT_BACKGROUND);
+    assert_eq!(rgb.get_pixel(0, 0), &Rgb([10, 20, 30]));
+    assert_eq!(rgb.get_pixel(1, 0), &Rgb([255, 255, 255]));
+}
+
+#[test]
+#[cfg(feature = "chinese_click_0")]
+fn test_pipeline_observer() {
+    use crate::captcha::{PipelineObserver, PipelineTrace};
+    use crate::detection::BBox;
+    use crate::matching::Assignment;
+    use image::{DynamicImage, RgbImage};
+    use ndarray::ArrayView2;
+
+    #[derive(Default)]
+    struct Stages(Vec<String>);
+
+    impl PipelineObserver for Stages {
+        fn on_detections(&mut self, _image: &RgbImage, detections: &[BBox]) {
+            self.0.push(format!("detections {}", detections.len()));
+        }
+
+        fn on_features(&mut self, question_features: ArrayView2<f32>, ans_features: ArrayView2<f32>) {
+            self.0.push(format!("features {} {}", question_features.nrows(), ans_features.nrows()));
+        }
+
+        fn on_assignment(&mut self, assignment: &Assignment) {
+            self.0.push(format!("assignment {}", assignment.matches.len()));
+        }
+
+        fn on_finished(&mut self, trace: &PipelineTrace) {
+            self.0.push(format!("finished {}", trace.points.len()));
+        }
+    }
+
+    let boxes = [(50.0, 100.0, 40.0), (150.0, 100.0, 40.0), (30.0, 370.0, 20.0), (80.0, 370.0, 20.0)];
+    let cb = mock_chinese_click(&boxes, one_hot_features(&[1, 0, 0, 1], 2), 440);
+    let mut stages = Stages::default();
+    let trace = cb.run_with_observer(&DynamicImage::new_rgb8(440, 440), &mut stages).unwrap();
+    assert_eq!(trace.points, vec![(150.0, 100.0), (50.0, 100.0)]);
+    assert_eq!(stages.0, vec!["detections 4", "features 2 2", "assignment 2", "finished 2"]);
+}
+
+#[test]
+#[cfg(feature = "chinese_click_0")]
+fn test_dataset_capture() {
+    use crate::capture::CaptureConfig;
+    use crate::environment::CaptchaEnvironment;
+    use image::DynamicImage;
+
+    let dir = std::env::temp_dir().join(format!("captcha_breaker_capture_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let environment = CaptchaEnvironment::default().with_capture(CaptureConfig::new(&dir));
+    let boxes = [(50.0, 100.0, 40.0), (150.0, 100.0, 40.0), (30.0, 370.0, 20.0), (80.0, 370.0, 20.0)];
+    let cb = mock_chinese_click_in(environment, &boxes, one_hot_features(&[1, 0, 0, 1], 2), 440);
+    cb.run(&DynamicImage::new_rgb8(440, 440)).unwrap();
+
+    let samples: Vec<_> = std::fs::read_dir(dir.join("chinese_click_0")).unwrap().map(|e| e.unwrap().path()).collect();
+    assert_eq!(samples.len(), 1);
+    for file in ["image.png", "question_0.png", "answer_1.png", "question_features.npy", "answer.json"] {
+        assert!(samples[0].join(file).exists(), "{}", file);
+    }
+    let npy = std::fs::read(samples[0].join("answer_features.npy")).unwrap();
+    let header_len = u16::from_le_bytes([npy[8], npy[9]]) as usize;
+    assert_eq!((10 + header_len) % 64, 0);
+    assert_eq!(npy.len(), 10 + header_len + 2 * 2 * 4);
+    let answer = std::fs::read_to_string(samples[0].join("answer.json")).unwrap();
+    assert!(answer.contains("\"point\": [150, 100]"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+#[cfg(feature = "bench")]
+fn test_benchmark_report() {
+    use crate::bench::{Stats, benchmark};
+    use crate::timing::{Stage, Timings};
+    use std::time::Duration;
+
+    let samples: Vec<Duration> = (1..=20).map(Duration::from_millis).collect();
+    let stats = Stats::from_samples(&samples);
+    assert_eq!(stats.p50, Duration::from_millis(11));
+    assert_eq!(stats.p95, Duration::from_millis(19));
+    assert_eq!(stats.max, Duration::from_millis(20));
+    assert_eq!(Stats::from_samples(&[]), Stats::default());
+
+    // 预热的一次不计入统计
+    let mut calls = 0u64;
+    let report = benchmark(4, || {
+        calls += 1;
+        Ok(Timings {
+            detect: Duration::from_millis(calls),
+            embed: Duration::from_millis(1),
+            ..Default::default()
+        })
+    })
+    .unwrap();
+    assert_eq!(calls, 5);
+    assert_eq!(report.iterations, 4);
+    assert_eq!(report.stage(Stage::Detect).unwrap().mean, Duration::from_micros(3500));
+    assert_eq!(report.stage(Stage::Preprocess).unwrap().max, Duration::ZERO);
+    assert_eq!(report.total.max, Duration::from_millis(6));
+    assert!(report.to_string().contains("detect"));
+    assert!(benchmark(1, || Err("失败".into())).is_err());
+}
+
+#[test]
+fn test_lap_verify() {
+    use crate::lap::{brute_force, lapjv, verify};
+
+    let costs = ndarray::array![[4.0f64, 1.0, 3.0], [2.0, 0.0, 5.0], [3.0, 2.0, 2.0]];
+    assert_eq!(brute_force(&costs).unwrap(), vec![1, 0, 2]);
+    verify(&costs, &lapjv(&costs).unwrap().0).unwrap();
+    assert!(verify(&costs, &[0, 1, 2]).is_err());
+    assert!(verify(&costs, &[1, 1, 2]).is_err());
+    assert!(verify(&costs, &[1, 0]).is_err());
+    let inf = f64::INFINITY;
+    let masked = ndarray::array![[inf, 1.0], [1.0, 0.0]];
+    assert!(verify(&masked, &[0, 1]).is_err());
+    assert_eq!(brute_force(&ndarray::array![[inf, inf], [1.0, 2.0]]), None);
+}
+
+proptest::proptest! {
+    #[test]
+    fn proptest_lapjv_optimal_f64(dim in 1usize..=6, values in proptest::collection::vec(-1e3f64..1e3, 36)) {
+        let costs = ndarray::Array2::from_shape_fn((dim, dim), |(i, j)| values[i * 6 + j]);
+        let (rows, _) = crate::lap::lapjv(&costs).unwrap();
+        crate::lap::verify(&costs, &rows).unwrap();
+    }
+
+    #[test]
+    fn proptest_lapjv_optimal_f32(dim in 1usize..=6, values in proptest::collection::vec(0f32..1e4, 36)) {
+        let costs = ndarray::Array2::from_shape_fn((dim, dim), |(i, j)| values[i * 6 + j]);
+        let (rows, _) = crate::lap::lapjv(&costs).unwrap();
+        crate::lap::verify(&costs, &rows).unwrap();
+    }
+
+    #[test]
+    fn proptest_lapjv_optimal_integer(dim in 1usize..=6, values in proptest::collection::vec(-20i64..20, 36)) {
+        let costs = ndarray::Array2::from_shape_fn((dim, dim), |(i, j)| values[i * 6 + j]);
+        let (rows, _) = crate::lap::lapjv(&costs).unwrap();
+        crate::lap::verify(&costs, &rows).unwrap();
+    }
+
+    #[test]
+    fn proptest_lapjv_equal_costs(dim in 1usize..=40, value in -1e6f64..1e6) {
+        let costs = ndarray::Array2::from_elem((dim, dim), value);
+        let (rows, _) = crate::lap::lapjv(&costs).unwrap();
+        crate::lap::verify(&costs, &rows).unwrap();
+    }
+
+    /// NaN/±inf的矩阵不能panic或死循环，返回的结果必须是排列
+    #[test]
+    fn proptest_lapjv_adversarial(
+        dim in 1usize..=8,
+        cells in proptest::collection::vec(
+            proptest::prop_oneof![
+                (-1e3f64..1e3),
+                proptest::strategy::Just(f64::NAN),
+                proptest::strategy::Just(f64::INFINITY),
+                proptest::strategy::Just(f64::NEG_INFINITY),
+                proptest::strategy::Just(0.0),
+            ],
+            64,
+        ),
+    ) {
+        let costs = ndarray::Array2::from_shape_fn((dim, dim), |(i, j)| cells[i * 8 + j]);
+        if let Ok((rows, cols)) = crate::lap::lapjv(&costs) {
+            let mut sorted = rows.clone();
+            sorted.sort_unstable();
+            proptest::prop_assert_eq!(sorted, (0..dim).collect::<Vec<_>>());
+            proptest::prop_assert!(rows.iter().enumerate().all(|(i, &j)| cols[j] == i));
+        }
+    }
+}
+
+#[test]
+fn test_lapjv_invalid_costs() {
+    use crate::lap::{ErrorKind, Solver, kbest, lapjv, solve};
+    use crate::matching::{DistanceMetric, cost_matrix, validate_costs};
+
+    let nan = ndarray::array![[1.0f32, 2.0], [f32::NAN, 0.0]];
+    let err = lapjv(&nan).unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::InvalidCost { row: 1, col: 0 }));
+    assert!(matches!(solve(&nan, Solver::Auction).unwrap_err().kind(), ErrorKind::InvalidCost { .. }));
+    assert!(kbest(&nan, 2).is_err());
+    let neg_inf = ndarray::array![[1.0f64, f64::NEG_INFINITY], [f64::INFINITY, 0.0]];
+    assert!(matches!(lapjv(&neg_inf).unwrap_err().kind(), ErrorKind::InvalidCost { row: 0, col: 1 }));
+
+    // NaN特征不会被欧氏距离的截断变成0
+    let queries = ndarray::array![[f32::NAN, 0.0], [1.0, 0.0]];
+    let candidates = ndarray::array![[1.0f32, 0.0], [0.0, 1.0]];
+    let costs = cost_matrix(&queries.view(), &candidates.view(), DistanceMetric::Euclidean);
+    assert!(costs[[0, 0]].is_nan());
+    assert!(validate_costs(&costs).is_err());
+    assert!(validate_costs(&ndarray::array![[0.0f32, 1.0]]).is_ok());
+}
+
+#[test]
+#[cfg(feature = "chinese_click_0")]
+fn test_chinese_click_nan_features() {
+    use crate::error::CaptchaError;
+    use image::DynamicImage;
+
+    let boxes = [(60.0, 100.0, 40.0), (160.0, 100.0, 40.0), (20.0, 400.0, 24.0), (60.0, 400.0, 24.0)];
+    let mut features = one_hot_features(&[1, 0, 0, 1], 4);
+    features[[1, 0]] = f32::NAN;
+    let cb = mock_chinese_click(&boxes, features, 440);
+    let err = cb.run(&DynamicImage::new_rgb8(440, 440)).unwrap_err();
+    assert!(matches!(err.downcast_ref::<CaptchaError>(), Some(CaptchaError::InvalidCost { .. })));
+}
+
+#[test]
+fn test_lapjv_options() {
+    use crate::lap::{LapJVOptions, lapjv, lapjv_with_options, verify};
+
+    let costs = ndarray::Array2::from_shape_fn((6, 6), |(i, j)| 1e6f32 + ((i * 5 + j * 3) % 7) as f32 * 0.25);
+    let options = LapJVOptions::default();
+    assert_eq!(options.tolerance(&costs), f32::EPSILON);
+    let relative = LapJVOptions { relative_tolerance: 1e-6, ..Default::default() };
+    assert!((relative.tolerance(&costs) - (f32::EPSILON + 1.0000015)).abs() < 1e-3);
+    // 整数成本总是精确比较
+    assert_eq!(relative.tolerance(&ndarray::array![[1000i64, 2], [3, 4]]), 0);
+
+    let (rows, _) = lapjv(&costs).unwrap();
+    verify(&costs, &rows).unwrap();
+    for options in [relative, LapJVOptions { promote_to_f64: true, ..Default::default() }] {
+        let (promoted, _) = lapjv_with_options(&costs, &options).unwrap();
+        verify(&costs, &promoted).unwrap();
+    }
+    let inf = f32::INFINITY;
+    let masked = ndarray::array![[inf, 1.0], [1.0, 0.0]];
+    let promote = LapJVOptions { promote_to_f64: true, ..Default::default() };
+    assert_eq!(lapjv_with_options(&masked, &promote).unwrap().0, vec![1, 0]);
+}
+
+#[test]
+fn test_lapjv_solution_duals() {
+    use crate::lap::{LapJV, LapJVOptions, lapjv_solution};
+
+    let costs = ndarray::array![[4.0f64, 1.0, 3.0], [2.0, 0.0, 5.0], [3.0, 2.0, 2.0]];
+    let solution = LapJV::new(&costs).solve_full().unwrap();
+    assert_eq!(solution.in_row, vec![1, 0, 2]);
+    assert_eq!(solution.cost, 5.0);
+    // 强对偶：势之和等于最优成本，且所有约化成本非负
+    let duals: f64 = solution.u.iter().chain(&solution.v).sum();
+    assert!((duals - solution.cost).abs() < 1e-9);
+    for ((i, j), _) in costs.indexed_iter() {
+        let reduced = solution.reduced_cost(&costs, i, j);
+        assert!(reduced >= -1e-9);
+        if solution.in_row[i] == j {
+            assert!(reduced.abs() < 1e-9);
+        }
+    }
+
+    let integer = ndarray::array![[7i64]];
+    let single = lapjv_solution(&integer, &LapJVOptions::default()).unwrap();
+    assert_eq!((single.cost, single.u[0] + single.v[0]), (7, 7));
+}
+
+proptest::proptest! {
+    #[test]
+    fn proptest_lapjv_duals(dim in 1usize..=12, values in proptest::collection::vec(-100i64..100, 144)) {
+        use crate::lap::{LapJVOptions, lapjv_solution};
+
+        let costs = ndarray::Array2::from_shape_fn((dim, dim), |(i, j)| values[i * 12 + j]);
+        let solution = lapjv_solution(&costs, &LapJVOptions::default()).unwrap();
+        proptest::prop_assert_eq!(solution.u.iter().chain(&solution.v).sum::<i64>(), solution.cost);
+        for ((i, j), _) in costs.indexed_iter() {
+            proptest::prop_assert!(solution.reduced_cost(&costs, i, j) >= 0);
+        }
+    }
+}
+
+#[test]
+fn test_lapjv_warm_start() {
+    use crate::lap::{LapJVOptions, lapjv_solution, lapjv_warm, verify};
+
+    let options = LapJVOptions::default();
+    let costs = ndarray::Array2::from_shape_fn((30, 30), |(i, j)| ((i * 17 + j * 31) % 23) as f64 + 0.5 * i as f64);
+    let cold = lapjv_solution(&costs, &options).unwrap();
+    let same = lapjv_warm(&costs, &cold, &options).unwrap();
+    assert_eq!(same.cost, cold.cost);
+
+    let mut perturbed = costs.clone();
+    perturbed[[0, cold.in_row[0]]] += 5.0;
+    perturbed[[3, 7]] -= 2.0;
+    let warm = lapjv_warm(&perturbed, &cold, &options).unwrap();
+    assert_eq!(warm.cost, lapjv_solution(&perturbed, &options).unwrap().cost);
+    // 维数不同的上一次结果
+    let small = lapjv_solution(&ndarray::array![[1.0, 2.0], [3.0, 4.0]], &options).unwrap();
+    assert!(lapjv_warm(&costs, &small, &options).is_err());
+    verify(&ndarray::array![[1.0, 2.0], [3.0, 4.0]], &small.in_row).unwrap();
+}
+
+proptest::proptest! {
+    #[test]
+    fn proptest_lapjv_warm_start(
+        dim in 2usize..=10,
+        values in proptest::collection::vec(-50i64..50, 100),
+        noise in proptest::collection::vec(-5i64..=5, 100),
+    ) {
+        use crate::lap::{LapJVOptions, lapjv_solution, lapjv_warm};
+
+        let options = LapJVOptions::default();
+        let costs = ndarray::Array2::from_shape_fn((dim, dim), |(i, j)| values[i * 10 + j]);
+        let previous = lapjv_solution(&costs, &options).unwrap();
+        let perturbed = ndarray::Array2::from_shape_fn((dim, dim), |(i, j)| values[i * 10 + j] + noise[i * 10 + j]);
+        let warm = lapjv_warm(&perturbed, &previous, &options).unwrap();
+        proptest::prop_assert_eq!(warm.cost, lapjv_solution(&perturbed, &options).unwrap().cost);
+        crate::lap::verify(&perturbed, &warm.in_row).unwrap();
+    }
+}
+
+/// 超过并行阈值的矩阵，开启 `parallel` 时走并行的规约与扫描
+#[test]
+fn test_lapjv_large_dual_certificate() {
+    use crate::lap::{LapJVOptions, lapjv_solution};
+
+    let mut state = 0x2545_F491_4F6C_DD1Du64;
+    let costs = ndarray::Array2::from_shape_fn((300, 300), |_| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state % 1000) as i64
+    });
+    let solution = lapjv_solution(&costs, &LapJVOptions::default()).unwrap();
+    assert_eq!(solution.u.iter().chain(&solution.v).sum::<i64>(), solution.cost);
+    assert!(costs.indexed_iter().all(|((i, j), _)| solution.reduced_cost(&costs, i, j) >= 0));
+}
+
+#[test]
+fn test_greedy_matching() {
+    use crate::lap::{LapJVOptions, Solver};
+    use crate::matching::{MatchStrategy, SiameseMatcher, assign_with_strategy, greedy};
+    use crate::testing::MockBackend;
+    use std::sync::Arc;
+
+    // 贪心先取最小的(0, 0)，之后只能取(1, 1)，总成本10；最优分配为(0, 1)(1, 0)，总成本3
+    let costs = ndarray::array![[0.0f32, 1.0], [2.0, 10.0]];
+    assert_eq!(greedy(&costs), vec![Some(0), Some(1)]);
+    let options = LapJVOptions::default();
+    assert_eq!(assign_with_strategy(&costs, MatchStrategy::Exact, &options).unwrap(), vec![Some(1), Some(0)]);
+    assert_eq!(assign_with_strategy(&costs, MatchStrategy::Auction, &options).unwrap(), vec![Some(1), Some(0)]);
+    assert_eq!(MatchStrategy::from(Solver::Auction), MatchStrategy::Auction);
+
+    // 行数多于列数
+    let tall = ndarray::array![[3.0f32], [1.0], [2.0]];
+    assert_eq!(greedy(&tall), vec![None, Some(0), None]);
+    assert!(greedy(&ndarray::Array2::zeros((0, 3))).is_empty());
+
+    let matcher = SiameseMatcher::new(Arc::new(MockBackend::new(vec![], vec![])));
+    let queries = ndarray::array![[1.0f32, 0.0], [0.0, 1.0]];
+    let candidates = ndarray::array![[0.0f32, 1.0], [1.0, 0.0]];
+    let assignment = matcher.assign_features_with(&queries.view(), &candidates.view(), MatchStrategy::Greedy).unwrap();
+    assert_eq!(assignment.matches, vec![Some(1), Some(0)]);
+}
+
+#[test]
+fn test_sinkhorn_matching() {
+    use crate::lap::LapJVOptions;
+    use crate::matching::{Hysteresis, MatchStrategy, SiameseMatcher, SinkhornConfig, assign_with_strategy, sinkhorn, soft_assign};
+    use crate::testing::MockBackend;
+    use ndarray::{Axis, array};
+    use std::sync::Arc;
+
+    let config = SinkhornConfig::default();
+    let sums_to_one = |values: ndarray::Array1<f32>| values.iter().all(|s| (s - 1.0).abs() < 1e-3);
+
+    // 成本差距明显时接近硬分配，行列之和都为1
+    let clear = array![[0.0f32, 1.0], [1.0, 0.0]];
+    let p = sinkhorn(&clear, &config);
+    assert!(p[[0, 0]] > 0.99 && p[[1, 1]] > 0.99);
+    assert!(sums_to_one(p.sum_axis(Axis(0))) && sums_to_one(p.sum_axis(Axis(1))));
+
+    // 接近平局时给出接近0.5的概率：a / (1 - a) = exp(0.02 * 2 / 2 / 0.1)
+    let tie = array![[0.50f32, 0.52], [0.52, 0.50]];
+    let p = sinkhorn(&tie, &config);
+    let expected = 1.0 / (1.0 + (-0.2f32).exp());
+    assert!((p[[0, 0]] - expected).abs() < 1e-3 && (p[[0, 1]] - (1.0 - expected)).abs() < 1e-3);
+
+    // 不是方阵时每行之和仍为1
+    let wide = array![[0.1f32, 0.9, 0.5], [0.8, 0.2, 0.6]];
+    let p = sinkhorn(&wide, &config);
+    assert_eq!(p.dim(), (2, 3));
+    assert!(sums_to_one(p.sum_axis(Axis(1))));
+    assert_eq!(soft_assign(&wide, &config, None).unwrap().matches, vec![Some(0), Some(1)]);
+    assert_eq!(sinkhorn(&ndarray::Array2::zeros((0, 2)), &config).dim(), (0, 2));
+
+    // 滞回：平局时保持上一次的匹配，差距明显时才替换
+    let swapped = [Some(1), Some(0)];
+    assert_eq!(soft_assign(&tie, &config, None).unwrap().matches, vec![Some(0), Some(1)]);
+    assert_eq!(soft_assign(&tie, &config, Some(&swapped)).unwrap().matches, swapped.to_vec());
+    assert_eq!(soft_assign(&clear, &config, Some(&swapped)).unwrap().matches, vec![Some(0), Some(1)]);
+
+    let mut hysteresis = Hysteresis::new(config.clone());
+    assert_eq!(hysteresis.update(&tie).unwrap().matches, vec![Some(0), Some(1)]);
+    let jittered = array![[0.52f32, 0.50], [0.50, 0.52]];
+    assert_eq!(hysteresis.update(&jittered).unwrap().matches, vec![Some(0), Some(1)]);
+    assert_eq!(hysteresis.update(&array![[1.0f32, 0.0], [0.0, 1.0]]).unwrap().matches, swapped.to_vec());
+    assert_eq!(hysteresis.previous(), Some(&swapped[..]));
+    hysteresis.reset();
+    assert_eq!(hysteresis.update(&jittered).unwrap().matches, swapped.to_vec());
+
+    let costs = array![[0.0f32, 1.0], [2.0, 10.0]];
+    assert_eq!(assign_with_strategy(&costs, MatchStrategy::Sinkhorn, &LapJVOptions::default()).unwrap(), vec![Some(1), Some(0)]);
+
+    let matcher = SiameseMatcher::new(Arc::new(MockBackend::new(vec![], vec![])));
+    let queries = array![[1.0f32, 0.0], [0.0, 1.0]];
+    let candidates = array![[0.0f32, 1.0], [1.0, 0.0]];
+    let assignment = matcher.assign_features_with(&queries.view(), &candidates.view(), MatchStrategy::Sinkhorn).unwrap();
+    assert_eq!(assignment.matches, vec![Some(1), Some(0)]);
+    let soft = assignment.soft.unwrap();
+    assert!(soft[[0, 1]] > 0.99 && sums_to_one(soft.sum_axis(Axis(1))));
+    let exact = matcher.assign_features_with(&queries.view(), &candidates.view(), MatchStrategy::Exact).unwrap();
+    assert!(exact.soft.is_none());
+}
+
+#[test]
+fn test_io_spec() {
+    use crate::backend::{InferenceBackend, TensorData, TensorInfo};
+    use crate::model::Model;
+    use crate::testing::MockBackend;
+    use ndarray::array;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    let yolo = MockBackend::new(vec![TensorInfo::f32("pixel_values", vec![1, 3, -1, -1])], vec![TensorInfo::f32("boxes", vec![1, 5, 100])]);
+    assert_eq!(yolo.inputs()[0].to_string(), "pixel_values[1, 3, ?, ?] f32");
+    Model::Yolo11n.io_spec().check(&yolo).unwrap();
+    // 孪生网络的输出应为2维
+    let err = Model::Siamese.io_spec().check(&yolo).unwrap_err();
+    assert!(err.contains("2维") && err.contains("boxes[1, 5, 100]"), "{}", err);
+    #[cfg(feature = "nine_grid_0")]
+    {
+        let text = MockBackend::new(vec![TensorInfo::f32("input_ids", vec![1, -1])], vec![TensorInfo::f32("embeds", vec![1, 512])]);
+        assert!(Model::ClipText.io_spec().check(&text).unwrap_err().contains("i64"));
+    }
+    assert!(Model::Yolo11n.io_spec().check(&MockBackend::new(vec![], vec![])).is_err());
+
+    // 输出缺失时列出实际的输出名
+    let backend: Arc<dyn InferenceBackend> = Arc::new(
+        MockBackend::new(vec![TensorInfo::f32("x", vec![1])], vec![TensorInfo::f32("y", vec![1])])
+            .with_response(HashMap::from([("z".to_string(), TensorData::from(array![1.0f32]))])),
+    );
+    let err = backend.run_single(array![0.0f32]).unwrap_err().to_string();
+    assert!(err.contains("缺少 y") && err.contains("z"), "{}", err);
+}
+
+#[test]
+#[cfg(feature = "chinese_click_0")]
+fn test_incompatible_model_io() {
+    use crate::backend::TensorInfo;
+    use crate::captcha::ChineseClick0;
+    use crate::model::Model;
+    use crate::testing::MockBackend;
+    use std::sync::Arc;
+
+    let environment = CaptchaEnvironment::default();
+    let yolo = MockBackend::new(vec![TensorInfo::f32("images", vec![1, 3, 384, 384])], vec![TensorInfo::f32("output0", vec![1, 5, 10])]);
+    let siamese = MockBackend::new(vec![TensorInfo::f32("images", vec![-1, 3, 96, 96])], vec![TensorInfo::f32("features", vec![-1, 8, 1])]);
+    environment.insert_backend(Model::Yolo11n, Arc::new(yolo)).unwrap();
+    environment.insert_backend(Model::Siamese, Arc::new(siamese)).unwrap();
+    let err = environment.load_captcha_breaker::<ChineseClick0>().unwrap_err().to_string();
+    assert!(err.contains("siamese") && err.contains("features[?, 8, 1]"), "{}", err);
+}
+
+#[test]
+fn test_model_compatibility() {
+    use crate::backend::TensorInfo;
+    use crate::metadata::{EmbeddedMetadata, KEY_FORMAT_VERSION, KEY_INPUT_SHAPE, KEY_MODEL, KEY_NORMALIZATION};
+    use crate::model::{Model, Normalization};
+    use crate::testing::MockBackend;
+    use std::collections::HashMap;
+
+    let backend = MockBackend::new(vec![TensorInfo::f32("images", vec![-1, 3, 96, 96])], vec![TensorInfo::f32("features", vec![-1, 64])]);
+    let metadata = |props: &[(&str, &str)]| {
+        EmbeddedMetadata::from_props(props.iter().map(|&(k, v)| (k.to_string(), v.to_string())).collect::<HashMap<_, _>>())
+    };
+
+    // 没有声明的项不检查
+    EmbeddedMetadata::default().check_compatible(Model::Siamese, &backend).unwrap();
+    let full = metadata(&[
+        (KEY_MODEL, "siamese"),
+        (KEY_FORMAT_VERSION, "1"),
+        (KEY_INPUT_SHAPE, "-1, 3, 96, 96"),
+        (KEY_NORMALIZATION, "unit"),
+    ]);
+    assert_eq!(full.input_shape, Some(vec![-1, 3, 96, 96]));
+    full.check_compatible(Model::Siamese, &backend).unwrap();
+    assert_eq!(EmbeddedMetadata::from_props(full.to_props().into_iter().collect()), full);
+
+    assert!(full.check_compatible(Model::Yolo11n, &backend).unwrap_err().contains("siamese"));
+    let newer = metadata(&[(KEY_FORMAT_VERSION, "2")]);
+    assert!(newer.check_compatible(Model::Siamese, &backend).unwrap_err().contains("升级"));
+    let shape = metadata(&[(KEY_INPUT_SHAPE, "1,3,128,128")]);
+    assert!(shape.check_compatible(Model::Siamese, &backend).unwrap_err().contains("不一致"));
+    let rank = metadata(&[(KEY_INPUT_SHAPE, "3,96,96")]);
+    assert!(rank.check_compatible(Model::Siamese, &backend).is_err());
+    let normalization = metadata(&[(KEY_NORMALIZATION, "symmetric")]);
+    assert!(normalization.check_compatible(Model::Siamese, &backend).is_err());
+    #[cfg(feature = "text_captcha_0")]
+    normalization.check_compatible(Model::Crnn, &MockBackend::new(vec![], vec![])).unwrap();
+    #[cfg(feature = "nine_grid_0")]
+    assert_eq!(Normalization::from_name("clip"), Some(Model::ClipImage.normalization()));
+    assert_eq!(Normalization::from_name("symmetric"), Some(Normalization::Symmetric));
+}
+
+#[test]
+fn test_layered_environment_config() {
+    use crate::config::EnvironmentConfig;
+    use crate::model::Precision;
+    use std::path::PathBuf;
+
+    let vars = |pairs: &[(&str, &str)]| pairs.iter().map(|&(k, v)| (k.to_string(), v.to_string())).collect::<Vec<_>>();
+    let from_env = EnvironmentConfig::from_vars(vars(&[
+        ("CAPTCHA_BREAKER_EP", "CUDA, cpu"),
+        ("CAPTCHA_BREAKER_PRECISION", "int8"),
+        ("CAPTCHA_BREAKER_INTRA_THREADS", ""),
+        ("PATH", "/usr/bin"),
+    ]))
+    .unwrap();
+    assert_eq!(from_env.execution_providers, Some(vec!["cuda".to_string(), "cpu".to_string()]));
+    assert_eq!(from_env.precision, Some(Precision::Int8));
+    assert_eq!(from_env.intra_threads, None);
+    assert!(EnvironmentConfig::from_vars(vars(&[("CAPTCHA_BREAKER_INTRA_THREADS", "two")])).is_err());
+    assert!(EnvironmentConfig::from_vars(vars(&[("CAPTCHA_BREAKER_PRECISION", "fp8")])).is_err());
+
+    // 环境变量覆盖代码中的配置，没有设置的项保留
+    let builder = CaptchaEnvironment::builder().model_dir("models").intra_threads(4).confidence_threshold(0.3);
+    let config = builder
+        .resolve_with(vars(&[("CAPTCHA_BREAKER_INTRA_THREADS", "2"), ("CAPTCHA_BREAKER_NMS_THRESHOLD", "0.6")]))
+        .unwrap();
+    assert_eq!(config.model_dir, Some(PathBuf::from("models")));
+    assert_eq!(config.intra_threads, Some(2));
+    assert_eq!(config.confidence_threshold, Some(0.3));
+    assert_eq!(config.nms_threshold, Some(0.6));
+
+    let environment = builder.build_with(config).unwrap();
+    assert_eq!(environment.session_options().intra_threads, Some(2));
+    assert_eq!(environment.confidence_threshold(), Some(0.3));
+    assert_eq!(environment.nms_threshold(), Some(0.6));
+
+    assert!(CaptchaEnvironment::builder().ignore_env().confidence_threshold(1.5).build().is_err());
+    assert!(CaptchaEnvironment::builder().ignore_env().execution_providers(["gpu"]).build().is_err());
+    let environment = CaptchaEnvironment::builder().ignore_env().precision(Precision::Fp16).build().unwrap();
+    assert_eq!(environment.precision(crate::model::Model::Siamese), Precision::Fp16);
+}
+
+#[test]
+#[cfg(feature = "config-file")]
+fn test_environment_config_file() {
+    use crate::config::EnvironmentConfig;
+    use crate::model::Precision;
+
+    let config = EnvironmentConfig::from_toml(
+        r#"
+model_dir = "/opt/models"
+execution_providers = ["cpu"]
+inter_threads = 1
+precision = "fp16"
+confidence_threshold = 0.4
+"#,
+    )
+    .unwrap();
+    assert_eq!(config.precision, Some(Precision::Fp16));
+    assert_eq!(config.inter_threads, Some(1));
+    assert!(EnvironmentConfig::from_toml("modeldir = \"x\"").is_err());
+
+    let path = std::env::temp_dir().join(format!("captcha_breaker_config_{}.toml", std::process::id()));
+    std::fs::write(&path, "intra_threads = 3\nnms_threshold = 0.5\n").unwrap();
+    // 配置文件覆盖代码，环境变量再覆盖配置文件
+    let resolved = CaptchaEnvironment::builder()
+        .intra_threads(8)
+        .nms_threshold(0.4)
+        .resolve_with(vec![
+            ("CAPTCHA_BREAKER_CONFIG".to_string(), path.display().to_string()),
+            ("CAPTCHA_BREAKER_NMS_THRESHOLD".to_string(), "0.7".to_string()),
+        ])
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(resolved.intra_threads, Some(3));
+    assert_eq!(resolved.nms_threshold, Some(0.7));
+}
+
+#[test]
+fn test_model_features() {
+    use crate::model::Model;
+
+    // 只有开启了对应验证码feature的模型可用，默认只下载ChineseClick0需要的模型
+    for model in Model::ALL {
+        assert_eq!(Model::from_name(model.name()), Some(*model));
+    }
+    assert!(Model::ALL.contains(&Model::Yolo11n) && Model::ALL.contains(&Model::Siamese));
+    assert_eq!(Model::from_name("crnn").is_some(), cfg!(feature = "text_captcha_0"));
+    assert_eq!(Model::from_name("audio_asr").is_some(), cfg!(feature = "audio"));
+}
+
+#[test]
+fn test_click_randomization() {
+    use crate::click::{ClickConfig, ClickSampler};
+    use crate::detection::BBox;
+    use crate::geometry::Rect;
+
+    let bbox = BBox::new(Rect::new(100.0, 50.0, 140.0, 70.0), 0.9, 0);
+    assert_eq!(ClickSampler::default().sample([&bbox]), vec![(120.0, 60.0)]);
+
+    let config = ClickConfig::randomized(7);
+    let sampler = ClickSampler::new(config);
+    let points: Vec<(f32, f32)> = (0..200).flat_map(|_| sampler.sample([&bbox])).collect();
+    // 偏移不超过半宽/半高的max_offset
+    assert!(points.iter().all(|&(x, y)| (x - 120.0).abs() <= 20.0 * config.max_offset + 1e-4 && (y - 60.0).abs() <= 10.0 * config.max_offset + 1e-4));
+    assert!(points.windows(2).any(|w| w[0] != w[1]));
+    let mean_x = points.iter().map(|p| p.0).sum::<f32>() / points.len() as f32;
+    assert!((mean_x - 120.0).abs() < 2.0, "{}", mean_x);
+
+    // 相同的seed与调用顺序得到相同的结果
+    let again = ClickSampler::new(config);
+    let repeated: Vec<(f32, f32)> = (0..200).flat_map(|_| again.sample([&bbox])).collect();
+    assert_eq!(points, repeated);
+
+    // 旋转框的偏移沿框自身的方向
+    let rotated = BBox {
+        angle: std::f32::consts::FRAC_PI_2,
+        ..bbox.clone()
+    };
+    for (x, y) in (0..50).flat_map(|_| sampler.sample([&rotated])) {
+        assert!((x - 120.0).abs() <= 10.0 * config.max_offset + 1e-3 && (y - 60.0).abs() <= 20.0 * config.max_offset + 1e-3);
+    }
+}
+
+#[test]
+#[cfg(feature = "chinese_click_0")]
+fn test_chinese_click_randomized_points() {
+    use crate::captcha::ChineseClick0Config;
+    use crate::click::ClickConfig;
+    use image::DynamicImage;
+
+    let boxes = [(50.0, 100.0, 40.0), (150.0, 100.0, 40.0), (30.0, 370.0, 20.0), (70.0, 370.0, 20.0)];
+    let cb = mock_chinese_click(&boxes, one_hot_features(&[1, 0, 0, 1], 2), 440).with_config(ChineseClick0Config {
+        click: ClickConfig::randomized(42),
+        ..Default::default()
+    });
+    let image = DynamicImage::new_rgb8(440, 440);
+    let first = cb.run_detailed(&image).unwrap();
+    let centers: Vec<(f32, f32)> = first.answers.iter().map(|m| first.ans_boxes[m.answer_index].center()).collect();
+    assert_eq!(centers, vec![(150.0, 100.0), (50.0, 100.0)]);
+    for ((x, y), (cx, cy)) in first.points.iter().zip(&centers) {
+        assert!((x - cx).abs() <= 12.0 + 1e-4 && (y - cy).abs() <= 12.0 + 1e-4);
+    }
+    assert!(first.answers.iter().zip(&first.points).all(|(m, p)| m.answer_point == *p));
+    // 每次识别重新采样
+    assert_ne!(cb.run(&image).unwrap(), first.points);
+}
+
+#[test]
+#[cfg(feature = "chinese_click_0")]
+fn test_solve_session() {
+    use crate::session::{SessionState, SolveSession, StepAnswer, step};
+    use image::DynamicImage;
+
+    let boxes = [(50.0, 100.0, 40.0), (150.0, 100.0, 40.0), (30.0, 370.0, 20.0), (70.0, 370.0, 20.0)];
+    let cb = mock_chinese_click(&boxes, one_hot_features(&[1, 0, 0, 1], 2), 440);
+    let mut state = SessionState::default();
+    state.set("token", "t0");
+    let mut session = SolveSession::new()
+        .with_state(state)
+        .then(&cb)
+        .then(step("slider", |image: &DynamicImage, state: &mut SessionState| {
+            // 第二步可以读取第一步的答案与令牌
+            let Some(StepAnswer::Clicks(points)) = state.history.last() else {
+                return Err("缺少点选结果".into());
+            };
+            let distance = image.width() as f32 - points[0].0;
+            let token = format!("{}-{}", state.get("token").ok_or("缺少令牌")?, points.len());
+            state.set("token", token);
+            Ok(StepAnswer::Slide(distance))
+        }));
+
+    let image = DynamicImage::new_rgb8(440, 440);
+    assert_eq!(session.current_step(), Some("chinese_click_0"));
+    let first = session.next(&image).unwrap();
+    assert_eq!((first.index, first.finished), (0, false));
+    assert_eq!(first.answer, StepAnswer::Clicks(vec![(150.0, 100.0), (50.0, 100.0)]));
+    let second = session.next(&image).unwrap();
+    assert_eq!((second.name.as_str(), second.finished), ("slider", true));
+    assert_eq!(second.answer, StepAnswer::Slide(290.0));
+    assert_eq!(session.state().get("token"), Some("t0-2"));
+    assert!(session.is_finished() && session.next(&image).is_err());
+
+    // 从头开始时保留令牌；出错时停留在当前步骤
+    session.reset();
+    assert_eq!(session.current_step(), Some("chinese_click_0"));
+    assert!(session.state().history.is_empty() && session.state().get("token").is_some());
+    let mut failing = SolveSession::new().then(step("text", |_: &DynamicImage, _: &mut SessionState| Err("识别失败".into())));
+    assert!(failing.next(&image).is_err());
+    assert_eq!(failing.current_step(), Some("text"));
+}
+
+#[test]
+fn test_animated_fusion() {
+    use crate::animated::{FusionConfig, compose, decode_frames, fuse_detections, sample_frames};
+    use crate::detection::BBox;
+    use crate::geometry::Rect;
+    use image::codecs::gif::GifEncoder;
+    use image::{Delay, Frame, Rgb, RgbImage, RgbaImage};
+
+    let bbox = |x: f32, confidence: f32| BBox::new(Rect::new(x, 10.0, x + 20.0, 30.0), confidence, 0);
+    // 左边的字只在第0、2帧出现，右边的字每帧都有，干扰框只出现一次
+    let detections = vec![
+        vec![bbox(10.0, 0.6), bbox(60.0, 0.9)],
+        vec![bbox(61.0, 0.8), bbox(120.0, 0.3)],
+        vec![bbox(12.0, 0.8), bbox(59.0, 0.7)],
+        vec![bbox(60.0, 0.9)],
+        vec![bbox(60.0, 0.9)],
+        vec![bbox(60.0, 0.9)],
+    ];
+    let fused = fuse_detections(&detections, &FusionConfig::default());
+    assert_eq!(fused.len(), 2);
+    let (right, left) = (&fused[0], &fused[1]);
+    assert_eq!(right.frames, vec![0, 1, 2, 3, 4, 5]);
+    assert_eq!(left.frames, vec![0, 2]);
+    assert_eq!(left.best_frame, 2);
+    assert_eq!(left.bbox.confidence, 0.8);
+    assert!((left.bbox.rect.x_min - (10.0 * 0.6 + 12.0 * 0.8) / 1.4).abs() < 1e-4);
+
+    // 融合图中每个字的区域取自置信度最高的一帧
+    let frames: Vec<RgbImage> = (0..3).map(|i| RgbImage::from_pixel(100, 40, Rgb([i as u8 * 10, 0, 0]))).collect();
+    let composed = compose(&frames, std::slice::from_ref(left)).unwrap();
+    assert_eq!(composed.get_pixel(15, 20)[0], 20);
+    assert_eq!(composed.get_pixel(90, 5)[0], 10);
+    assert!(compose(&[], &fused).is_err());
+    assert_eq!(sample_frames(40, 4), vec![0, 10, 20, 30]);
+    assert_eq!(sample_frames(3, 16), vec![0, 1, 2]);
+
+    let mut bytes = vec![];
+    {
+        let mut encoder = GifEncoder::new(&mut bytes);
+        let frames = (0..3)
+            .map(|i| Frame::from_parts(RgbaImage::from_pixel(8, 8, image::Rgba([i * 80, 0, 0, 255])), 0, 0, Delay::from_numer_denom_ms(100, 1)));
+        encoder.encode_frames(frames).unwrap();
+    }
+    let decoded = decode_frames(&bytes).unwrap();
+    assert_eq!(decoded.len(), 3);
+    assert_eq!(decoded[1].delay_ms, 100);
+    // GIF会量化颜色
+    assert!(decoded[2].image.to_rgb8().get_pixel(0, 0)[0].abs_diff(160) <= 8);
+}
+
+#[test]
+#[cfg(feature = "chinese_click_0")]
+fn test_chinese_click_animated() {
+    use image::DynamicImage;
+
+    let boxes = [(50.0, 100.0, 40.0), (150.0, 100.0, 40.0), (30.0, 370.0, 20.0), (70.0, 370.0, 20.0)];
+    let cb = mock_chinese_click(&boxes, one_hot_features(&[1, 0, 0, 1], 2), 440);
+    let frames = vec![DynamicImage::new_rgb8(440, 440); 3];
+    let trace = cb.run_animated(&frames).unwrap();
+    // 跨帧平均后的框只有浮点误差
+    let single = cb.run(&frames[0]).unwrap();
+    assert_eq!(trace.points.len(), single.len());
+    assert!(trace.points.iter().zip(&single).all(|(a, b)| (a.0 - b.0).abs() < 1e-3 && (a.1 - b.1).abs() < 1e-3));
+    assert!(cb.run_animated(&[]).is_err());
+}
+
+#[test]
+fn test_frame_solver_tracking() {
+    use crate::detection::BBox;
+    use crate::geometry::Rect;
+    use crate::tracking::{FrameSolver, IouTracker, TrackerConfig};
+    use image::DynamicImage;
+
+    let bbox = |x: f32, y: f32, confidence: f32| BBox::new(Rect::new(x, y, x + 30.0, y + 30.0), confidence, 0);
+    // 向右匀速移动的目标，第3帧漏检，另有一个只出现一次的误检
+    let frames: Vec<Vec<BBox>> = (0..10)
+        .map(|i| {
+            let mut boxes = vec![bbox(200.0, 50.0, 0.8)];
+            if i != 3 {
+                boxes.insert(0, bbox(10.0 + 5.0 * i as f32, 100.0, 0.9));
+            }
+            if i == 1 {
+                boxes.push(bbox(300.0, 300.0, 0.4));
+            }
+            boxes
+        })
+        .collect();
+
+    let mut tracker = IouTracker::new(TrackerConfig::default());
+    for boxes in &frames[..6] {
+        tracker.update(boxes).unwrap();
+    }
+    let moving = tracker.tracks().iter().find(|t| t.bbox.rect.y_min == 100.0).unwrap();
+    assert_eq!((moving.id, moving.hits, moving.missed), (0, 5, 0));
+    assert!((moving.velocity.0 - 5.0).abs() < 1.0, "{:?}", moving.velocity);
+    assert!((moving.position().x_min - 35.0).abs() < 2.0);
+    assert!(tracker.tracks().iter().all(|t| t.bbox.rect.x_min != 300.0), "误检应被删除");
+
+    let detections = std::cell::RefCell::new(frames.into_iter());
+    let mut solver = FrameSolver::new(|_: &DynamicImage| Ok(detections.borrow_mut().next().unwrap_or_default()));
+    let frame = DynamicImage::new_rgb8(8, 8);
+    let results: Vec<_> = (0..10).map(|_| solver.push(&frame).unwrap()).collect();
+    // 轨迹确认前没有答案
+    assert!(results[0].tracks.is_empty() && !results[0].stable);
+    let first_stable = results.iter().position(|r| r.stable).unwrap();
+    assert!(first_stable >= 4, "{}", first_stable);
+    let last = results.last().unwrap();
+    assert!(last.stable && last.tracks.len() == 2);
+    assert_eq!(last.tracks.iter().map(|t| t.id).collect::<Vec<_>>(), vec![0, 1]);
+    assert!((last.tracks[0].bbox.center().0 - 70.0).abs() < 2.0);
+
+    solver.reset();
+    assert!(solver.tracker().tracks().is_empty());
+    assert!(!solver.push_detections(&[]).unwrap().stable);
+}
+
+#[test]
+fn test_attribute_query() {
+    use crate::attribute::{AttributeQuery, Attributes, Color, Orientation};
+    let query = AttributeQuery::parse("请依次点击红色的字");
+    assert_eq!(query.color, Some(Color::Red));
+    assert_eq!(query.orientation, None);
+    let attributes = Attributes {
+        color: Color::Red,
+        color_confidence: 0.9,
+        orientation: Orientation::Left,
+        orientation_confidence: 0.8,
+    };
+    assert!(query.matches(&attributes));
+    assert!(!AttributeQuery::parse("click the upside-down blue character").matches(&attributes));
+    assert!(AttributeQuery::parse("请按顺序点击").is_empty());
+}
+
+#[test]
+#[cfg(feature = "color_click_0")]
+fn test_color_click_filters_answers() {
+    use crate::captcha::ColorClick0;
+    use image::{DynamicImage, Rgb, RgbImage};
+
+    // 两个答案是同一个字，只有颜色不同，左边红色、右边蓝色
+    let boxes = [(50.0, 100.0, 40.0), (150.0, 100.0, 40.0), (30.0, 370.0, 20.0)];
+    let mut image = RgbImage::from_pixel(440, 440, Rgb([255, 255, 255]));
+    for (x, y, pixel) in image.enumerate_pixels_mut() {
+        if (85..115).contains(&y) {
+            if (35..65).contains(&x) {
+                *pixel = Rgb([220, 30, 30]);
+            } else if (135..165).contains(&x) {
+                *pixel = Rgb([30, 30, 220]);
+            }
+        }
+    }
+    let image = DynamicImage::ImageRgb8(image);
+    // 过滤后只剩一个答案，模型依次输出问题与答案的特征
+    let environment = mock_click_environment(CaptchaEnvironment::default(), &boxes, one_hot_features(&[0, 0], 1), 440);
+    let cb: ColorClick0 = environment.load_captcha_breaker().unwrap();
+    let trace = cb.run_detailed(&image, "请点击红色的字").unwrap();
+    assert_eq!(trace.ans_boxes.len(), 1);
+    assert_eq!(trace.points, vec![(50.0, 100.0)]);
+    assert_eq!(cb.run(&image, "click the blue character").unwrap(), vec![(150.0, 100.0)]);
+    // 没有属性模型时不能按朝向过滤
+    assert!(cb.run(&image, "请点击倒立的字").is_err());
+}
+
+#[test]
+fn test_ngram_language_model() {
+    use crate::language::{LanguageModel, NgramTable};
+    let table = NgramTable::from_text("# 一心一意\n一 2\n心 1\n意 1\n一心 1\n心一 1\n一意 1\n一心一 1\n心一意 1\n").unwrap();
+    assert_eq!(table.order(), 3);
+    assert_eq!(table.count("一心"), 1);
+    let score = |text: &str| table.score(&text.chars().collect::<Vec<_>>());
+    // P(一) · P(心|一) · P(一|一心) · P(意|心一) = 2/4 · 1/2 · 1 · 1
+    assert!((score("一心一意") - 0.25f32.ln()).abs() < 1e-5);
+    assert!(score("一心一意") > score("一意一心"));
+    assert!(score("一心一意") > score("意一心一"));
+    // 闭包也可以作为语言模型
+    let uniform = |_: &[char], _: char| 0.0;
+    assert_eq!(uniform.score(&['一', '心']), 0.0);
+    assert!(NgramTable::from_text("一心").is_err());
+}
+
+#[test]
+#[cfg(feature = "word_order_0")]
+fn test_word_order() {
+    use crate::backend::{TensorData, TensorInfo};
+    use crate::captcha::WordOrder0;
+    use crate::language::NgramTable;
+    use crate::metadata::KEY_CLASS_LABELS;
+    use crate::model::Model;
+    use crate::testing::MockBackend;
+    use image::DynamicImage;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    // 从左到右识别为 意、一、心、一
+    let boxes = [(50.0, 100.0, 40.0), (150.0, 100.0, 40.0), (250.0, 100.0, 40.0), (350.0, 100.0, 40.0)];
+    let environment = mock_click_environment(CaptchaEnvironment::default(), &boxes, one_hot_features(&[0], 1), 440);
+    let mut logits = ndarray::Array2::<f32>::zeros((4, 3));
+    for (row, class) in [2, 0, 1, 0].into_iter().enumerate() {
+        logits[[row, class]] = 5.0;
+    }
+    let ocr = MockBackend::new(vec![TensorInfo::f32("images", vec![-1, 3, 64, 64])], vec![TensorInfo::f32("logits", vec![-1, 3])])
+        .with_response(HashMap::from([("logits".to_string(), TensorData::from(logits))]))
+        .with_metadata(HashMap::from([(KEY_CLASS_LABELS.to_string(), "一,心,意".to_string())]));
+    environment.insert_backend(Model::CharOcr, Arc::new(ocr)).unwrap();
+    let table = NgramTable::from_text("一 2\n心 1\n意 1\n一心 1\n心一 1\n一意 1\n一心一 1\n心一意 1\n").unwrap();
+    let cb: WordOrder0 = environment.load_captcha_breaker().unwrap();
+    let cb = cb.with_language_model(table);
+
+    let result = cb.run_detailed(&DynamicImage::new_rgb8(440, 440)).unwrap();
+    assert_eq!(result.text, "一心一意");
+    assert_eq!((result.order[1], result.order[3]), (2, 0));
+    assert_eq!(result.candidates[0][0].char, '意');
+    assert_eq!(result.points[3], (50.0, 100.0));
+}
+
+#[test]
+#[cfg(feature = "chinese_click_0")]
+fn test_siamese_embedder() {
+    use crate::matching::SiameseEmbedder;
+    use image::DynamicImage;
+
+    let boxes = [(50.0, 100.0, 40.0), (30.0, 370.0, 20.0)];
+    let environment = mock_click_environment(CaptchaEnvironment::default(), &boxes, one_hot_features(&[0, 1], 2), 440);
+    let embedder: SiameseEmbedder = environment.load_captcha_breaker().unwrap();
+    // 预先提取的字形特征可以直接与新图像块的特征比较
+    let glyphs = [DynamicImage::new_rgba8(30, 30), DynamicImage::new_rgb8(40, 20)];
+    let bank = embedder.embed(&glyphs).unwrap();
+    assert_eq!(bank.dim(), (2, 2));
+    let costs = embedder.cost_matrix(&bank.view(), &bank.view());
+    assert!(costs[[0, 0]].abs() < 1e-6 && costs[[0, 1]] > costs[[0, 0]]);
+    assert_eq!(embedder.assign(&bank.view(), &bank.view()).unwrap().matches, vec![Some(0), Some(1)]);
+
+    // 从ChineseClick0取出的提取器与其共用模型
+    let cb = mock_chinese_click_in(environment, &boxes, one_hot_features(&[0, 1], 2), 440);
+    assert_eq!(cb.embedder().embed(&glyphs).unwrap(), bank);
+}
+
+#[test]
+fn test_template_bank() {
+    use crate::matching::{DistanceMetric, TemplateBank};
+    use ndarray::array;
+
+    let mut bank = TemplateBank::new(2, DistanceMetric::Euclidean);
+    bank.extend(["甲", "乙"], &array![[1.0, 0.0], [0.0, 1.0]].view()).unwrap();
+    bank.insert("甲", array![0.9, 0.1].view()).unwrap();
+    assert!(bank.insert("丙", array![1.0].view()).is_err());
+    assert_eq!(bank.len(), 3);
+
+    let nearest = bank.nearest(array![0.1, 0.8].view()).unwrap();
+    assert_eq!((nearest.index, nearest.label.as_str()), (1, "乙"));
+    let labels = bank.lookup(&array![[0.95, 0.05], [5.0, 5.0]].view(), 1.0);
+    assert_eq!(labels[0].as_ref().map(|m| m.label.as_str()), Some("甲"));
+    assert_eq!(labels[1], None);
+
+    let path = std::env::temp_dir().join(format!("captcha_breaker_templates_{}.bin", std::process::id()));
+    bank.save(&path).unwrap();
+    let loaded = TemplateBank::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded, bank);
+    assert!(TemplateBank::from_bytes(&bank.to_bytes()[..20]).is_err());
+}
+
+#[test]
+#[cfg(feature = "chinese_click_0")]
+fn test_chinese_click_templates() {
+    use crate::matching::{DistanceMetric, TemplateBank};
+    use image::DynamicImage;
+
+    let boxes = [(50.0, 100.0, 40.0), (150.0, 100.0, 40.0), (30.0, 370.0, 20.0), (70.0, 370.0, 20.0)];
+    let mut bank = TemplateBank::new(2, DistanceMetric::Euclidean);
+    bank.extend(["甲", "乙"], &one_hot_features(&[0, 1], 2).view()).unwrap();
+    let cb = mock_chinese_click(&boxes, one_hot_features(&[1, 0, 0, 1], 2), 440);
+    let image = DynamicImage::new_rgb8(440, 440);
+    assert!(cb.run_detailed(&image).unwrap().labels.is_empty());
+    assert!(cb.run_labeled(&image).is_err());
+
+    let cb = cb.with_templates(bank);
+    let labeled = cb.run_labeled(&image).unwrap();
+    assert_eq!(
+        labeled,
+        vec![(Some("乙".to_string()), (150.0, 100.0)), (Some("甲".to_string()), (50.0, 100.0))]
+    );
+}
+
+#[test]
+fn test_hnsw_index() {
+    use crate::matching::{DistanceMetric, HnswConfig, HnswIndex, TemplateBank};
+    use crate::rng::SplitMix64;
+
+    let mut rng = SplitMix64::new(3);
+    let features = ndarray::Array2::from_shape_fn((500, 16), |_| rng.next_f32());
+    let queries = ndarray::Array2::from_shape_fn((50, 16), |_| rng.next_f32());
+    let mut index = HnswIndex::build(&features.view(), DistanceMetric::Euclidean, HnswConfig::default());
+    assert_eq!(index.len(), 500);
+    assert!(index.recall(&queries.view(), 5) >= 0.9);
+    for i in (0..500).step_by(37) {
+        assert_eq!(index.search(features.row(i), 1)[0].0, i);
+    }
+    let found = index.search(queries.row(0), 5);
+    assert!(found.windows(2).all(|w| w[0].1 <= w[1].1));
+
+    let path = std::env::temp_dir().join(format!("captcha_breaker_hnsw_{}.bin", std::process::id()));
+    index.save(&path).unwrap();
+    let loaded = HnswIndex::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded, index);
+    assert!(HnswIndex::from_bytes(&index.to_bytes()[..100]).is_err());
+    // 维度为0或节点数超过剩余字节时在分配内存前报错
+    assert!(HnswIndex::from_bytes(&HnswIndex::new(0, DistanceMetric::Euclidean, HnswConfig::default()).to_bytes()).is_err());
+    let mut bytes = HnswIndex::new(1, DistanceMetric::Euclidean, HnswConfig::default()).to_bytes();
+    bytes[41..45].copy_from_slice(&1000u32.to_le_bytes());
+    bytes.extend(std::iter::repeat_n(0, 4000));
+    assert!(HnswIndex::from_bytes(&bytes).is_err());
+    // 调小ef_search只影响召回率，不需要重建
+    index.set_ef_search(128);
+    assert_eq!(index.config().ef_search, 128);
+    assert!(index.recall(&queries.view(), 5) >= 0.9);
+
+    // 字形库使用索引查找，新加入的模板也能找到
+    let labels: Vec<String> = (0..500).map(|i| format!("g{}", i)).collect();
+    let mut bank = TemplateBank::new(16, DistanceMetric::Euclidean);
+    bank.extend(labels, &features.view()).unwrap();
+    assert!(TemplateBank::new(16, DistanceMetric::Cosine).with_index(loaded.clone()).is_err());
+    let mut bank = bank.with_index(loaded).unwrap();
+    bank.insert("new", queries.row(1)).unwrap();
+    assert_eq!(bank.index().unwrap().len(), 501);
+    assert_eq!(bank.nearest(queries.row(1)).unwrap().label, "new");
+    assert_eq!(bank.nearest(features.row(42)).unwrap().index, 42);
+}
+
+#[test]
+fn test_describe_model() {
+    use crate::backend::TensorInfo;
+    use crate::inspect::{OnnxHeader, OpsetImport};
+    use crate::metadata::KEY_MODEL;
+    use crate::model::Model;
+    use crate::testing::MockBackend;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    // 手工编码的ModelProto：ir_version、producer_name、计算图(跳过)、两个opset_import与一条metadata_props
+    let string = |field: u8, s: &str| [vec![field << 3 | 2, s.len() as u8], s.as_bytes().to_vec()].concat();
+    let message = |field: u8, body: Vec<u8>| [vec![field << 3 | 2, body.len() as u8], body].concat();
+    let bytes = [
+        vec![1 << 3, 8],
+        string(2, "pytorch"),
+        message(7, vec![0x0d, 1, 2, 3, 4]),
+        message(8, [string(1, ""), vec![2 << 3, 17]].concat()),
+        message(8, [string(1, "com.microsoft"), vec![2 << 3, 1]].concat()),
+        message(14, [string(1, KEY_MODEL), string(2, "siamese")].concat()),
+    ]
+    .concat();
+    let header = OnnxHeader::parse(&bytes).unwrap();
+    assert_eq!(header.ir_version, Some(8));
+    assert_eq!(header.producer_name.as_deref(), Some("pytorch"));
+    assert_eq!(header.opset(), Some(17));
+    assert_eq!(header.opsets[1], OpsetImport { domain: "com.microsoft".to_string(), version: 1 });
+    assert_eq!(header.metadata[KEY_MODEL], "siamese");
+    assert!(OnnxHeader::parse(&bytes[..bytes.len() - 3]).is_err());
+    assert!(OnnxHeader::parse(b"").is_err());
+
+    // 自定义后端没有模型文件，只有输入输出与兼容性检查
+    let environment = CaptchaEnvironment::default();
+    let siamese = MockBackend::new(vec![TensorInfo::f32("images", vec![-1, 3, 96, 96])], vec![TensorInfo::f32("features", vec![-1, 8, 1])])
+        .with_metadata(HashMap::from([(KEY_MODEL.to_string(), "siamese".to_string())]));
+    environment.insert_backend(Model::Siamese, Arc::new(siamese)).unwrap();
+    let description = environment.describe_model(Model::Siamese).unwrap();
+    assert_eq!(description.model, Some(Model::Siamese));
+    assert_eq!(description.inputs[0].shape, vec![-1, 3, 96, 96]);
+    assert!(description.header.is_none() && description.precision.is_none());
+    assert!(description.problem.as_deref().unwrap().contains("2维"));
+    let text = description.to_string();
+    assert!(text.contains("输出: features[?, 8, 1] f32") && text.contains("不兼容"), "{}", text);
+}
+
+#[test]
+fn test_runtime_probe() {
+    use crate::config::EnvironmentConfig;
+    use crate::error::CaptchaError;
+    use crate::runtime::{self, library_name};
+    use std::path::PathBuf;
+
+    // 目录会拼上动态库文件名，最后交给系统按文件名搜索
+    let dir = std::env::temp_dir();
+    let candidates = runtime::candidates(Some(&dir));
+    assert_eq!(candidates[0], dir.join(library_name()));
+    assert_eq!(candidates.last().unwrap(), &PathBuf::from(library_name()));
+    #[cfg(not(feature = "load-dynamic"))]
+    assert_eq!(CaptchaEnvironment::default().with_runtime_path(&dir).probe_runtime().unwrap(), None);
+
+    let err = CaptchaError::RuntimeUnavailable {
+        attempted: vec![PathBuf::from("/opt/ort/libonnxruntime.so")],
+        reason: "cannot open shared object file".to_string(),
+    };
+    let message = err.to_string();
+    assert!(message.contains("/opt/ort/libonnxruntime.so") && message.contains("with_runtime_path"), "{}", message);
+
+    let config = EnvironmentConfig::from_vars([("CAPTCHA_BREAKER_RUNTIME_PATH".to_string(), "/opt/ort".to_string())]).unwrap();
+    assert_eq!(config.runtime_path, Some(PathBuf::from("/opt/ort")));
+}
+
+#[test]
+fn test_linking() {
+    use crate::runtime::Linking;
+
+    let linking = CaptchaEnvironment::default().linking();
+    assert_eq!(linking, Linking::current());
+    #[cfg(feature = "load-dynamic")]
+    assert_eq!(linking, Linking::LoadDynamic);
+    #[cfg(all(feature = "download-binaries", not(feature = "load-dynamic")))]
+    assert_eq!(linking.name(), "download-binaries");
+}
+
+#[test]
+#[cfg(feature = "chinese_click_0")]
+fn test_shared_across_threads() {
+    use image::DynamicImage;
+    use std::sync::Arc;
+
+    // 环境与验证码实现都可以放进Arc在线程之间共享
+    let boxes = [(50.0, 100.0, 40.0), (150.0, 100.0, 40.0), (30.0, 370.0, 20.0), (70.0, 370.0, 20.0)];
+    let environment = Arc::new(mock_click_environment(
+        CaptchaEnvironment::default(),
+        &boxes,
+        one_hot_features(&[1, 0, 0, 1], 2),
+        440,
+    ));
+    let image = Arc::new(DynamicImage::new_rgb8(440, 440));
+    let cb: Arc<ChineseClick0> = Arc::new(environment.load_captcha_breaker().unwrap());
+    let expected = cb.run(&image).unwrap();
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let (environment, cb, image) = (environment.clone(), cb.clone(), image.clone());
+            std::thread::spawn(move || {
+                let own: ChineseClick0 = environment.load_captcha_breaker().unwrap();
+                (cb.run(&image).unwrap(), own.run(&image).unwrap())
+            })
+        })
+        .collect();
+    for handle in handles {
+        assert_eq!(handle.join().unwrap(), (expected.clone(), expected.clone()));
+    }
+}
+
+#[test]
+fn test_tensor_pool() {
+    use crate::backend::TensorPool;
+    use ndarray::{Array1, Ix1, Ix2};
+
+    let pool = TensorPool::new(1000);
+    let mut a = pool.zeros(Ix2(10, 10));
+    a.fill(1.0);
+    pool.recycle(a);
+    // 复用的缓冲区重新填零
+    let b = pool.zeros(Ix1(50));
+    assert!(b.iter().all(|&v| v == 0.0));
+    let stats = pool.stats();
+    assert_eq!((stats.allocations, stats.reuses, stats.pooled), (1, 1, 0));
+
+    // 超过预算的缓冲区直接释放，总量超出时保留较大的
+    pool.recycle(Array1::<f32>::zeros(500));
+    assert_eq!(pool.stats().pooled, 0);
+    pool.recycle(b);
+    pool.recycle(Array1::<f32>::zeros(100));
+    pool.recycle(Array1::<f32>::zeros(120));
+    let stats = pool.stats();
+    assert_eq!(stats.pooled, 2);
+    assert!(stats.pooled_bytes <= 1000);
+    assert_eq!(pool.take(110).capacity(), 120);
+    pool.clear();
+    assert_eq!(pool.stats().pooled, 0);
+}
+
+#[test]
+#[cfg(feature = "chinese_click_0")]
+fn test_chinese_click_reuses_buffers() {
+    use crate::config::EnvironmentConfig;
+    use image::DynamicImage;
+
+    let boxes = [(50.0, 100.0, 40.0), (150.0, 100.0, 40.0), (30.0, 370.0, 20.0), (70.0, 370.0, 20.0)];
+    let cb = mock_chinese_click(&boxes, one_hot_features(&[1, 0, 0, 1], 2), 440);
+    let image = DynamicImage::new_rgb8(440, 440);
+    let expected = cb.run(&image).unwrap();
+    let warm = cb.pool().stats();
+    // 第一次识别之后检测与孪生网络的输入张量都从池中复用，不再分配
+    for _ in 0..3 {
+        assert_eq!(cb.run(&image).unwrap(), expected);
+    }
+    let stats = cb.pool().stats();
+    assert_eq!(stats.allocations, warm.allocations);
+    assert!(stats.reuses >= warm.reuses + 6);
+
+    let vars = [("CAPTCHA_BREAKER_CUDA_MEMORY_LIMIT", "268435456"), ("CAPTCHA_BREAKER_CPU_ARENA", "false")];
+    let config = EnvironmentConfig::from_vars(vars.map(|(k, v)| (k.to_string(), v.to_string()))).unwrap();
+    assert_eq!((config.cuda_memory_limit, config.cpu_arena), (Some(268435456), Some(false)));
+}
+
+#[test]
+fn test_answer_cache() {
+    use crate::cache::{AnswerCache, CacheConfig, CacheKey};
+    use image::{DynamicImage, Rgb, RgbImage};
+
+    let pattern = |flip: bool| {
+        DynamicImage::ImageRgb8(RgbImage::from_fn(120, 80, |x, y| {
+            let x = if flip { 119 - x } else { x };
+            let v = ((x / 20 + y / 20) % 2) as u8 * 120 + x as u8 / 2;
+            Rgb([v, v, v])
+        }))
+    };
+    // 重新编码后像素相同，哈希相同
+    let (a, other) = (pattern(false), pattern(true));
+    let mut png = std::io::Cursor::new(vec![]);
+    a.write_to(&mut png, image::ImageFormat::Png).unwrap();
+    let reencoded = image::load_from_memory(png.get_ref()).unwrap();
+    assert_eq!(AnswerCache::<u32>::key(&a), AnswerCache::<u32>::key(&reencoded));
+    assert_ne!(AnswerCache::<u32>::key(&a), AnswerCache::<u32>::key(&other));
+
+    let cache = AnswerCache::new(CacheConfig {
+        capacity: 2,
+        ..Default::default()
+    });
+    assert_eq!(cache.get(&a), None);
+    cache.insert(&a, 1);
+    assert_eq!(cache.get(&reencoded), Some(1));
+    let mut calls = 0;
+    let mut solve = |_: &DynamicImage| -> Result<u32, ()> {
+        calls += 1;
+        Ok(2)
+    };
+    assert_eq!(cache.get_or_try_insert_with(&other, &mut solve), Ok(2));
+    assert_eq!(cache.get_or_try_insert_with(&other, &mut solve), Ok(2));
+    assert_eq!(calls, 1);
+    let stats = cache.stats();
+    assert_eq!((stats.hits, stats.misses, stats.entries), (2, 2, 2));
+    assert_eq!(stats.hit_rate(), 0.5);
+
+    // 容量已满时淘汰最久没有使用的a
+    cache.insert_key(CacheKey { phash: 42, content: 42 }, 3);
+    assert_eq!(cache.stats().evictions, 1);
+    assert_eq!(cache.get(&a), None);
+    assert_eq!(cache.get(&other), Some(2));
+
+    // 允许少量不同的位时相近的哈希也能命中
+    let fuzzy = AnswerCache::new(CacheConfig {
+        capacity: 8,
+        perceptual: true,
+        max_distance: 2,
+    });
+    fuzzy.insert_key(CacheKey { phash: 0b1000, content: 1 }, "a");
+    assert_eq!(fuzzy.get_key(CacheKey { phash: 0b1011, content: 2 }), Some("a"));
+    assert_eq!(fuzzy.get_key(CacheKey { phash: 0b0111, content: 3 }), None);
+    assert_eq!(fuzzy.remove(&a), None);
+    fuzzy.clear();
+    assert!(fuzzy.is_empty());
+
+    // 同一背景上的不同字：pHash只保留低频信息，默认要求像素相同，不会返回另一张图片的答案
+    let glyph = |x0: u32| {
+        let mut image = pattern(false).to_rgb8();
+        for y in 30..36 {
+            for x in x0..x0 + 6 {
+                image.put_pixel(x, y, Rgb([255, 0, 0]));
+            }
+        }
+        DynamicImage::ImageRgb8(image)
+    };
+    let (first, second) = (glyph(30), glyph(80));
+    assert_ne!(AnswerCache::<u32>::key(&first).content, AnswerCache::<u32>::key(&second).content);
+    let cache = AnswerCache::default();
+    cache.insert(&first, 1);
+    assert_eq!(cache.get(&second), None);
+    assert_eq!(cache.get(&first), Some(1));
+    // 开启近似匹配后按pHash命中，只适合图片整体重复的场景
+    let perceptual = AnswerCache::new(CacheConfig { perceptual: true, max_distance: 64, ..Default::default() });
+    perceptual.insert(&first, 1);
+    assert_eq!(perceptual.get(&second), Some(1));
+}
+
+#[cfg(feature = "chinese_click_0")]
+#[test]
+fn test_chinese_click_answer_cache() {
+    use crate::cache::AnswerCache;
+    use image::DynamicImage;
+    use std::sync::Arc;
+
+    let boxes = [(50.0, 100.0, 40.0), (150.0, 100.0, 40.0), (30.0, 370.0, 20.0), (70.0, 370.0, 20.0)];
+    let cache = Arc::new(AnswerCache::default());
+    let cb = mock_chinese_click(&boxes, one_hot_features(&[1, 0, 0, 1], 2), 440).with_cache(cache.clone());
+    let image = DynamicImage::new_rgb8(440, 440);
+    let expected = cb.run(&image).unwrap();
+    let pool = cb.pool().stats();
+    // 命中时不再推理，缓冲区池没有任何变化
+    assert_eq!(cb.run(&image).unwrap(), expected);
+    assert_eq!(cb.pool().stats(), pool);
+    let stats = cache.stats();
+    assert_eq!((stats.hits, stats.misses, stats.entries), (1, 1, 1));
+}
+
+#[test]
+#[cfg(feature = "chinese_click_0")]
+fn test_chinese_click_count_recovery() {
+    use crate::captcha::{ChineseClick0Config, CountRecovery};
+    use crate::model::Model;
+    use crate::policy::Attempt;
+    use image::DynamicImage;
+    use std::sync::Arc;
+
+    let image = DynamicImage::new_rgb8(440, 440);
+    let questions = [(30.0, 370.0, 20.0, 20.0, 0.9), (70.0, 370.0, 20.0, 20.0, 0.9), (110.0, 370.0, 20.0, 20.0, 0.9)];
+    let load = |answers: &[(f32, f32, f32, f32, f32)], features, recovery| {
+        let environment = mock_click_environment(CaptchaEnvironment::default(), &[], features, 440);
+        let boxes: Vec<_> = answers.iter().chain(&questions).copied().collect();
+        environment.insert_backend(Model::Yolo11n, Arc::new(mock_yolo(&boxes, 440))).unwrap();
+        let cb: ChineseClick0 = environment.load_captcha_breaker().unwrap();
+        let config = ChineseClick0Config { recovery, ..cb.config().clone() };
+        cb.with_config(config)
+    };
+
+    // 第三个答案的置信度低于默认阈值0.5，降低阈值重新检测后补上
+    let answers = [(50.0, 100.0, 40.0, 40.0, 0.9), (150.0, 100.0, 40.0, 40.0, 0.9), (250.0, 100.0, 40.0, 40.0, 0.3)];
+    let features = || one_hot_features(&[0, 1, 2, 2, 0, 1], 3);
+    assert!(load(&answers, one_hot_features(&[0, 1, 2, 2, 0], 3), vec![]).run(&image).is_err());
+    let cb = load(&answers, features(), vec![CountRecovery::LowerThreshold { confidence_threshold: 0.2 }]);
+    assert_eq!(cb.run(&image).unwrap(), vec![(150.0, 100.0), (250.0, 100.0), (50.0, 100.0)]);
+    #[cfg(feature = "tracing")]
+    {
+        let fields = recorded_span_fields(|| {
+            cb.run(&image).unwrap();
+        });
+        assert_eq!(fields.get("recovered_answers"), Some(&1));
+    }
+
+    // 两个相邻的字被检测为一个宽框，沿长边分裂
+    let answers = [(100.0, 100.0, 80.0, 40.0, 0.9), (250.0, 100.0, 40.0, 40.0, 0.9)];
+    let cb = load(&answers, features(), vec![CountRecovery::LowerThreshold { confidence_threshold: 0.2 }, CountRecovery::SplitLargest]);
+    let trace = cb.run_detailed(&image).unwrap();
+    assert_eq!(trace.ans_boxes.len(), 3);
+    assert_eq!(trace.ans_boxes[0].rect, crate::geometry::Rect::new(60.0, 80.0, 100.0, 120.0));
+    assert_eq!(trace.points, vec![(120.0, 100.0), (250.0, 100.0), (80.0, 100.0)]);
+    assert!(!trace.incomplete && trace.is_complete());
+
+    // 无法补足时返回部分答案：第一个问题没有答案
+    let answers = [(50.0, 100.0, 40.0, 40.0, 0.9), (150.0, 100.0, 40.0, 40.0, 0.9)];
+    let cb = load(&answers, one_hot_features(&[0, 1, 2, 1, 2], 3), vec![CountRecovery::Partial]);
+    let trace = cb.run_detailed(&image).unwrap();
+    assert!(trace.incomplete && !trace.is_complete());
+    assert_eq!(trace.missing_questions.len(), 1);
+    assert_eq!(trace.missing_questions[0].center(), (30.0, 370.0));
+    assert_eq!(trace.question_boxes.len(), 2);
+    assert_eq!(trace.cost_matrix.dim(), (2, 2));
+    assert_eq!(trace.points, vec![(50.0, 100.0), (150.0, 100.0)]);
+}
+
+#[test]
+#[cfg(feature = "chinese_click_0")]
+fn test_chinese_click_hooks() {
+    use crate::hooks::Hooks;
+    use image::DynamicImage;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // 第5个框是固定位置的水印，由检测钩子丢弃
+    let boxes = [(50.0, 100.0, 40.0), (150.0, 100.0, 40.0), (30.0, 370.0, 20.0), (70.0, 370.0, 20.0), (420.0, 20.0, 20.0)];
+    let preprocessed = Arc::new(AtomicUsize::new(0));
+    let counter = preprocessed.clone();
+    let hooks = Hooks::new()
+        .on_preprocess(move |image| {
+            counter.fetch_add(1, Ordering::Relaxed);
+            assert_eq!(image.dimensions(), (440, 440));
+        })
+        .on_detections(|_, boxes| boxes.retain(|b| b.center().0 < 400.0))
+        .on_matches(|points| points.iter_mut().for_each(|p| *p = (p.0 / 2.0, p.1 / 2.0)));
+    let environment = CaptchaEnvironment::default().with_hooks(hooks);
+    let cb = mock_chinese_click_in(environment, &boxes, one_hot_features(&[1, 0, 0, 1], 2), 440);
+    assert!(!cb.hooks().is_empty());
+    let trace = cb.run_detailed(&DynamicImage::new_rgb8(440, 440)).unwrap();
+    assert_eq!(preprocessed.load(Ordering::Relaxed), 1);
+    assert_eq!(trace.ans_boxes.len() + trace.question_boxes.len(), 4);
+    assert_eq!(trace.points, vec![(75.0, 50.0), (25.0, 50.0)]);
+    assert_eq!(trace.answers[0].answer_point, (75.0, 50.0));
+
+    // 同一位置的钩子按注册顺序调用，实例上的钩子替换环境的钩子
+    let hooks = cb.hooks().clone().extend(&Hooks::new().on_matches(|points| points.truncate(1)));
+    let cb = cb.with_hooks(hooks);
+    let trace = cb.run_detailed(&DynamicImage::new_rgb8(440, 440)).unwrap();
+    assert_eq!(trace.points, vec![(75.0, 50.0)]);
+    assert_eq!(trace.answers.len(), 2);
+    assert_eq!(preprocessed.load(Ordering::Relaxed), 2);
+}
+
+#[test]
+#[cfg(feature = "chinese_click_0")]
+fn test_chinese_click_fixed_layout() {
+    use crate::captcha::{CaptchaBreaker, FixedLayout};
+    use crate::geometry::Rect;
+    use crate::model::Model;
+    use image::DynamicImage;
+    use std::sync::Arc;
+
+    // 只注册孪生网络，固定布局不需要检测模型
+    let siamese = mock_siamese(one_hot_features(&[1, 0, 0, 1], 2));
+    let environment = CaptchaEnvironment::default();
+    environment.insert_backend(Model::Siamese, Arc::new(siamese)).unwrap();
+
+    let layout = FixedLayout::new(
+        vec![Rect::from_center((30.0, 370.0), (20.0, 20.0)), Rect::from_center((70.0, 370.0), (20.0, 20.0))],
+        vec![Rect::from_center((50.0, 100.0), (40.0, 40.0)), Rect::from_center((150.0, 100.0), (40.0, 40.0))],
+    )
+    .with_image_size((440, 440));
+    let cb = ChineseClick0::build_with_layout(&environment, layout.clone()).unwrap();
+    cb.warmup().unwrap();
+    let trace = cb.run_detailed(&DynamicImage::new_rgb8(440, 440)).unwrap();
+    assert_eq!(trace.points, vec![(150.0, 100.0), (50.0, 100.0)]);
+    assert!(trace.question_boxes.iter().chain(&trace.ans_boxes).all(|b| b.confidence == 1.0));
+    assert!(trace.timings.get(crate::timing::Stage::Detect).is_zero());
+
+    // 图片尺寸不同时区域按比例缩放
+    let points = cb.run(&DynamicImage::new_rgb8(880, 880)).unwrap();
+    assert_eq!(points, vec![(300.0, 200.0), (100.0, 200.0)]);
+
+    // 取消布局后没有检测模型可用
+    let cb = cb.with_layout(None);
+    assert!(cb.run(&DynamicImage::new_rgb8(440, 440)).is_err());
+
+    // 加载了检测模型时固定布局同样优先于检测结果
+    let boxes = [(250.0, 100.0, 40.0), (350.0, 100.0, 40.0), (230.0, 370.0, 20.0), (270.0, 370.0, 20.0)];
+    let cb = mock_chinese_click(&boxes, one_hot_features(&[1, 0, 0, 1], 2), 440).with_layout(Some(layout));
+    assert_eq!(cb.run(&DynamicImage::new_rgb8(440, 440)).unwrap(), vec![(150.0, 100.0), (50.0, 100.0)]);
+}
+
+#[test]
+fn test_image_hash() {
+    use crate::hash::{self, HashKind, ImageHash};
+    use image::{DynamicImage, Rgb, RgbImage};
+
+    let gradient = |offset: u8, flip: bool| {
+        DynamicImage::ImageRgb8(RgbImage::from_fn(180, 80, |x, _| {
+            let x = if flip { 179 - x } else { x };
+            let v = x as u8 + offset;
+            Rgb([v, v, v])
+        }))
+    };
+    let (a, brighter, flipped) = (gradient(0, false), gradient(40, false), gradient(0, true));
+    // 从左到右变亮：差值哈希全为1，均值哈希右半边为1
+    assert_eq!(hash::dhash(&a), ImageHash(u64::MAX));
+    assert_eq!(hash::dhash(&flipped), ImageHash(0));
+    assert_eq!(hash::ahash(&a).0 & 0x0f0f_0f0f_0f0f_0f0f, 0);
+    assert_eq!(hash::ahash(&a).0 & 0xf0f0_f0f0_f0f0_f0f0, 0xf0f0_f0f0_f0f0_f0f0);
+    // 整体变亮不改变均值哈希与差值哈希
+    assert_eq!(hash::ahash(&a), hash::ahash(&brighter));
+    assert_eq!(hash::dhash(&a), hash::dhash(&brighter));
+    assert_eq!(HashKind::default().compute(&a), hash::phash(&a));
+    assert_eq!(HashKind::Average.compute(&a).distance(&HashKind::Average.compute(&flipped)), 64);
+
+    let text = hash::phash(&a).to_string();
+    assert_eq!(text.len(), 16);
+    assert_eq!(text.parse::<ImageHash>().unwrap(), hash::phash(&a));
+    assert!("xyz".parse::<ImageHash>().is_err());
+
+    let hashes = [ImageHash(0), ImageHash(0b11), ImageHash(u64::MAX), ImageHash(0b1)];
+    assert_eq!(hash::dedup(&hashes, 2), vec![0, 2]);
+    assert_eq!(hash::dedup(&hashes, 0), vec![0, 1, 2, 3]);
+}
+
+#[test]
+fn test_drift_monitor() {
+    use crate::drift::{DriftConfig, DriftMetric, DriftMonitor, DriftSample, DriftStats};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let fired = Arc::new(AtomicUsize::new(0));
+    let counter = fired.clone();
+    let monitor = DriftMonitor::new(DriftConfig {
+        window: 4,
+        baseline: 4,
+        ..Default::default()
+    })
+    .on_drift(move |_| {
+        counter.fetch_add(1, Ordering::SeqCst);
+    });
+    let sample = |detections, confidence, distance| DriftSample {
+        detections,
+        mean_confidence: Some(confidence),
+        mean_distance: Some(distance),
+    };
+    for _ in 0..4 {
+        assert!(monitor.observe("a", sample(8, 0.75, 0.2)).is_empty());
+    }
+    let baseline = monitor.baseline("a").unwrap();
+    assert_eq!((baseline.samples, baseline.detections, baseline.mean_confidence), (4, 8.0, Some(0.75)));
+    for _ in 0..4 {
+        assert!(monitor.observe("a", sample(8, 0.6875, 0.25)).is_empty());
+    }
+    assert!(!monitor.is_drifting("a"));
+
+    // 检测框变少、置信度下降：窗口均值越过范围时各触发一次
+    let mut alarms = vec![];
+    for _ in 0..4 {
+        alarms.extend(monitor.observe("a", sample(4, 0.5, 0.25)));
+    }
+    let metrics: Vec<DriftMetric> = alarms.iter().map(|a| a.metric).collect();
+    assert_eq!(metrics, vec![DriftMetric::MeanConfidence, DriftMetric::Detections]);
+    assert_eq!(fired.load(Ordering::SeqCst), 2);
+    assert!(alarms[1].to_string().contains("detections"));
+    assert!(monitor.observe("a", sample(4, 0.5, 0.25)).is_empty());
+    assert_eq!(monitor.drifting("a"), metrics);
+    assert!(monitor.any_drifting() && !monitor.is_drifting("b"));
+    assert_eq!(monitor.stats("a").unwrap().detections, 4.0);
+
+    // 恢复后清除标记
+    for _ in 0..4 {
+        monitor.observe("a", sample(8, 0.75, 0.2));
+    }
+    assert!(!monitor.is_drifting("a"));
+
+    // 直接指定基线，没有匹配距离的样本不比较距离
+    monitor.set_baseline(
+        "b",
+        DriftStats {
+            samples: 1,
+            detections: 4.0,
+            mean_confidence: None,
+            mean_distance: Some(0.1),
+        },
+    );
+    for _ in 0..4 {
+        monitor.observe("b", DriftSample::from_boxes(&[]));
+    }
+    assert_eq!(monitor.drifting("b"), vec![DriftMetric::Detections]);
+    monitor.reset("b");
+    assert_eq!(monitor.baseline("b"), None);
+}
+
+#[cfg(feature = "chinese_click_0")]
+#[test]
+fn test_chinese_click_drift() {
+    use crate::drift::{DriftConfig, DriftMonitor};
+    use image::DynamicImage;
+    use std::sync::Arc;
+
+    let monitor = Arc::new(DriftMonitor::new(DriftConfig {
+        window: 2,
+        baseline: 2,
+        ..Default::default()
+    }));
+    let environment = CaptchaEnvironment::default().with_drift_monitor(monitor.clone());
+    let boxes = [(50.0, 100.0, 40.0), (150.0, 100.0, 40.0), (30.0, 370.0, 20.0), (70.0, 370.0, 20.0)];
+    let cb = mock_chinese_click_in(environment, &boxes, one_hot_features(&[1, 0, 0, 1], 2), 440);
+    let image = DynamicImage::new_rgb8(440, 440);
+    for _ in 0..4 {
+        cb.run(&image).unwrap();
+    }
+    let baseline = monitor.baseline("chinese_click_0").unwrap();
+    assert_eq!(baseline.detections, 4.0);
+    assert!(baseline.mean_confidence.is_some() && baseline.mean_distance.is_some());
+    assert_eq!(monitor.stats("chinese_click_0"), Some(baseline));
+    assert!(!monitor.any_drifting());
+}
+
+#[cfg(feature = "chinese_click_0")]
+#[test]
+fn test_solve_report() {
+    use crate::metadata::{EmbeddedMetadata, KEY_VERSION};
+    use crate::report::SolveReport;
+    use crate::timing::Outcome;
+    use image::DynamicImage;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    let reports: Arc<Mutex<Vec<SolveReport>>> = Arc::default();
+    let sink = reports.clone();
+    let environment = CaptchaEnvironment::default().with_report_hook(move |report| sink.lock().unwrap().push(report.clone()));
+    let boxes = [(50.0, 100.0, 40.0), (150.0, 100.0, 40.0), (30.0, 370.0, 20.0), (70.0, 370.0, 20.0)];
+    let cb = mock_chinese_click_in(environment, &boxes, one_hot_features(&[1, 0, 0, 1], 2), 440);
+    let image = DynamicImage::new_rgb8(440, 440);
+    let points = cb.run(&image).unwrap();
+
+    let reports = reports.lock().unwrap();
+    assert_eq!(reports.len(), 1);
+    let report = &reports[0];
+    assert_eq!(report.breaker, "chinese_click_0");
+    assert_eq!(report.input_hash, crate::hash::phash(&image).to_string());
+    assert_eq!(report.image_size, (440, 440));
+    let models: Vec<&str> = report.models.iter().map(|m| m.model.as_str()).collect();
+    assert_eq!(models, vec!["yolo11n", "siamese"]);
+    assert_eq!((report.outcome, report.error.as_deref()), (Outcome::Solved, None));
+    assert_eq!(report.answer, points);
+    assert_eq!(report.confidences.len(), 4);
+    assert_eq!(report.probabilities.len(), 2);
+    assert_eq!(report.timings_ms.len(), 5);
+    assert!(report.total_ms() >= 0.0);
+
+    let failed = SolveReport::new("x", &image, vec![]).with_outcome::<()>(&Err("没有检测到任何字".into()));
+    assert_eq!((failed.outcome, failed.error.as_deref()), (Outcome::Failed, Some("没有检测到任何字")));
+
+    let metadata = EmbeddedMetadata::from_props(HashMap::from([(KEY_VERSION.to_string(), " 2026.10 ".to_string())]));
+    assert_eq!(metadata.version.as_deref(), Some("2026.10"));
+    assert!(metadata.to_props().contains(&(KEY_VERSION.to_string(), "2026.10".to_string())));
+}
+
+/// 轮询一次future，测试中的兜底识别都是立即完成的
+fn poll_ready<F: std::future::Future>(future: F) -> F::Output {
+    use std::task::{Context, Poll, Waker};
+
+    let mut future = std::pin::pin!(future);
+    match future.as_mut().poll(&mut Context::from_waker(Waker::noop())) {
+        Poll::Ready(output) => output,
+        Poll::Pending => panic!("future没有立即完成"),
+    }
+}
+
+#[test]
+fn test_fallback_resolve() {
+    use crate::fallback::{self, AnswerSource, BoxFuture, FallbackConfig, FallbackError, FallbackRequest, FallbackSolver};
+    use image::DynamicImage;
+    use std::sync::Mutex;
+
+    /// 记录收到的请求，返回固定答案或错误
+    struct Recorded {
+        requests: Mutex<Vec<FallbackRequest>>,
+        fail: bool,
+    }
+
+    impl FallbackSolver for Recorded {
+        fn solve<'a>(&'a self, request: &'a FallbackRequest) -> BoxFuture<'a, Result<Vec<(f32, f32)>, FallbackError>> {
+            self.requests.lock().unwrap().push(request.clone());
+            let fail = self.fail;
+            Box::pin(async move { if fail { Err("打码平台超时".into()) } else { Ok(vec![(1.0, 2.0)]) } })
+        }
+    }
+
+    let image = DynamicImage::new_rgb8(20, 10);
+    let solver = Recorded {
+        requests: Mutex::new(vec![]),
+        fail: false,
+    };
+    let config = FallbackConfig::default();
+    let request = || FallbackRequest::new("chinese_click_0", &image);
+
+    let answer = poll_ready(fallback::resolve(Ok((vec![(5.0, 5.0)], 0.9)), request, &solver, &config)).unwrap();
+    assert_eq!((answer.source, answer.points), (AnswerSource::Local, vec![(5.0, 5.0)]));
+    assert!(solver.requests.lock().unwrap().is_empty());
+
+    let answer = poll_ready(fallback::resolve(Ok((vec![(5.0, 5.0)], 0.3)), request, &solver, &config)).unwrap();
+    assert_eq!((answer.source, answer.points, answer.confidence), (AnswerSource::Fallback, vec![(1.0, 2.0)], 0.3));
+    let answer = poll_ready(fallback::resolve(Err("没有检测到任何字".into()), request, &solver, &config)).unwrap();
+    assert_eq!(answer.source, AnswerSource::Fallback);
+    {
+        let requests = solver.requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!((requests[0].local.clone(), requests[0].image_size), (vec![(5.0, 5.0)], (20, 10)));
+        assert_eq!(image::load_from_memory(&requests[0].image).unwrap().width(), 20);
+        assert_eq!(requests[1].local_error.as_deref(), Some("没有检测到任何字"));
+    }
+
+    // 兜底识别失败时退回本地答案；本地也失败时返回兜底识别的错误
+    let failing = Recorded {
+        requests: Mutex::new(vec![]),
+        fail: true,
+    };
+    let answer = poll_ready(fallback::resolve(Ok((vec![(5.0, 5.0)], 0.3)), request, &failing, &config)).unwrap();
+    assert_eq!((answer.source, answer.points), (AnswerSource::Local, vec![(5.0, 5.0)]));
+    let error = poll_ready(fallback::resolve(Err("失败".into()), request, &failing, &config)).unwrap_err();
+    assert_eq!(error.to_string(), "打码平台超时");
+    let strict = FallbackConfig {
+        on_error: false,
+        ..Default::default()
+    };
+    let error = poll_ready(fallback::resolve(Err("失败".into()), request, &failing, &strict)).unwrap_err();
+    assert_eq!(error.to_string(), "失败");
+    assert_eq!(failing.requests.lock().unwrap().len(), 2);
+}
+
+#[cfg(feature = "chinese_click_0")]
+#[test]
+fn test_chinese_click_fallback() {
+    use crate::fallback::{AnswerSource, BoxFuture, FallbackConfig, FallbackError, FallbackRequest, FallbackSolver};
+    use image::DynamicImage;
+
+    struct Fixed;
+
+    impl FallbackSolver for Fixed {
+        fn solve<'a>(&'a self, request: &'a FallbackRequest) -> BoxFuture<'a, Result<Vec<(f32, f32)>, FallbackError>> {
+            let points = vec![(request.image_size.0 as f32, 0.0)];
+            Box::pin(std::future::ready(Ok(points)))
+        }
+    }
+
+    let boxes = [(50.0, 100.0, 40.0), (150.0, 100.0, 40.0), (30.0, 370.0, 20.0), (70.0, 370.0, 20.0)];
+    let cb = mock_chinese_click(&boxes, one_hot_features(&[1, 0, 0, 1], 2), 440);
+    let image = DynamicImage::new_rgb8(440, 440);
+    let local = cb.run(&image).unwrap();
+    let trusting = FallbackConfig {
+        min_confidence: 0.0,
+        ..Default::default()
+    };
+    let answer = poll_ready(cb.run_with_fallback(&image, &Fixed, &trusting)).unwrap();
+    assert_eq!((answer.source, answer.points), (AnswerSource::Local, local));
+    let strict = FallbackConfig {
+        min_confidence: 1.1,
+        ..Default::default()
+    };
+    let answer = poll_ready(cb.run_with_fallback(&image, &Fixed, &strict)).unwrap();
+    assert_eq!((answer.source, answer.points), (AnswerSource::Fallback, vec![(440.0, 0.0)]));
+}
+
+#[cfg(feature = "fallback-http")]
+#[test]
+fn test_http_fallback_response() {
+    use crate::fallback::HttpFallback;
+
+    assert_eq!(HttpFallback::parse_points(r#"{"points": [[1, 2.5], [3, 4]]}"#).unwrap(), vec![(1.0, 2.5), (3.0, 4.0)]);
+    let error = HttpFallback::parse_points(r#"{"error": "余额不足"}"#).unwrap_err();
+    assert!(error.to_string().contains("余额不足"));
+    assert!(HttpFallback::parse_points(r#"{"points": [[1]]}"#).is_err());
+    assert!(HttpFallback::parse_points("{}").is_err());
+    assert_eq!(HttpFallback::new("http://127.0.0.1:8080/chinese_click_0").url(), "http://127.0.0.1:8080/chinese_click_0");
+}
+
+#[test]
+fn test_governor() {
+    use crate::error::CaptchaError;
+    use crate::governor::{Governed, Governor, GovernorConfig};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::{Duration, Instant};
+
+    let overloaded = |e: CaptchaError| match e {
+        CaptchaError::Overloaded { reason } => reason,
+        e => panic!("{}", e),
+    };
+    let single = Governor::new(GovernorConfig {
+        max_concurrent: Some(1),
+        max_queue: 0,
+        ..Default::default()
+    });
+    let permit = single.acquire().unwrap();
+    assert!(single.try_acquire().is_none());
+    assert_eq!(overloaded(single.acquire().unwrap_err()), "等待队列已满");
+    drop(permit);
+    drop(single.acquire().unwrap());
+    assert_eq!(single.stats().admitted, 2);
+    assert_eq!(single.stats().rejected, 1);
+
+    let queued = Governor::new(GovernorConfig {
+        max_concurrent: Some(1),
+        max_queue: 1,
+        queue_timeout: Some(Duration::from_millis(20)),
+        ..Default::default()
+    });
+    let _permit = queued.acquire().unwrap();
+    let start = Instant::now();
+    assert_eq!(overloaded(queued.acquire().unwrap_err()), "排队超时");
+    assert!(start.elapsed() >= Duration::from_millis(20));
+    assert_eq!(queued.stats().waiting, 0);
+
+    // 每秒20次：第二次需要等待约50ms的令牌
+    let rated = Governor::new(GovernorConfig {
+        max_concurrent: None,
+        max_per_second: Some(20.0),
+        ..Default::default()
+    });
+    let start = Instant::now();
+    drop(rated.acquire().unwrap());
+    drop(rated.acquire().unwrap());
+    assert!(start.elapsed() >= Duration::from_millis(40));
+
+    // 多个线程同时识别时不超过并发上限
+    let governed = Arc::new(Governed::new(
+        AtomicUsize::new(0),
+        Arc::new(Governor::new(GovernorConfig {
+            max_concurrent: Some(2),
+            queue_timeout: None,
+            ..Default::default()
+        })),
+    ));
+    let peak = Arc::new(AtomicUsize::new(0));
+    let threads: Vec<_> = (0..6)
+        .map(|_| {
+            let (governed, peak) = (governed.clone(), peak.clone());
+            std::thread::spawn(move || {
+                governed
+                    .run(|active| {
+                        let now = active.fetch_add(1, Ordering::SeqCst) + 1;
+                        peak.fetch_max(now, Ordering::SeqCst);
+                        std::thread::sleep(Duration::from_millis(10));
+                        active.fetch_sub(1, Ordering::SeqCst);
+                        Ok(())
+                    })
+                    .unwrap()
+            })
+        })
+        .collect();
+    threads.into_iter().for_each(|t| t.join().unwrap());
+    assert!(peak.load(Ordering::SeqCst) <= 2);
+    assert_eq!(governed.governor().stats().admitted, 6);
+    assert!(Governor::unlimited().try_acquire().is_some());
+}
+
+#[cfg(feature = "chinese_click_0")]
+#[test]
+fn test_governed_breaker() {
+    use crate::captcha::ChineseClick0;
+    use crate::governor::GovernorConfig;
+    use image::DynamicImage;
+
+    let boxes = [(50.0, 100.0, 40.0), (150.0, 100.0, 40.0), (30.0, 370.0, 20.0), (70.0, 370.0, 20.0)];
+    let environment = mock_click_environment(CaptchaEnvironment::default(), &boxes, one_hot_features(&[1, 0, 0, 1], 2), 440)
+        .with_governor(GovernorConfig {
+            max_concurrent: Some(1),
+            ..Default::default()
+        });
+    let cb = environment.load_governed_breaker::<ChineseClick0>().unwrap();
+    let image = DynamicImage::new_rgb8(440, 440);
+    let points = cb.run(|cb| cb.run(&image)).unwrap();
+    assert_eq!(points, cb.inner().run(&image).unwrap());
+    assert!(std::sync::Arc::ptr_eq(cb.governor(), environment.governor().unwrap()));
+    assert_eq!(cb.governor().stats().admitted, 1);
+}
+
+#[test]
+fn test_round_robin() {
+    use crate::scheduler::RoundRobin;
+
+    assert!(RoundRobin::<u8>::new(vec![]).is_err());
+    let rr = RoundRobin::new(vec!["gpu0", "gpu1", "gpu2"]).unwrap();
+    let order: Vec<&str> = (0..7).map(|_| rr.run(|&device| device)).collect();
+    assert_eq!(order, vec!["gpu0", "gpu1", "gpu2", "gpu0", "gpu1", "gpu2", "gpu0"]);
+    assert_eq!(rr.next().0, 1);
+    assert_eq!((rr.len(), rr.get(2), rr.iter().count()), (3, Some(&"gpu2"), 3));
+}
+
+#[cfg(feature = "chinese_click_0")]
+#[test]
+fn test_device_pool() {
+    use crate::captcha::ChineseClick0;
+    use crate::config::EnvironmentConfig;
+    use crate::loader::{SessionOptions, execution_provider};
+    use crate::scheduler::DevicePool;
+    use image::DynamicImage;
+
+    let boxes = [(50.0, 100.0, 40.0), (150.0, 100.0, 40.0), (30.0, 370.0, 20.0), (70.0, 370.0, 20.0)];
+    let environments = (0..2)
+        .map(|_| mock_click_environment(CaptchaEnvironment::default(), &boxes, one_hot_features(&[1, 0, 0, 1], 2), 440))
+        .collect();
+    let pool = DevicePool::new(environments).unwrap();
+    let breakers = pool.load_captcha_breaker::<ChineseClick0>().unwrap();
+    assert_eq!(breakers.len(), 2);
+    let image = DynamicImage::new_rgb8(440, 440);
+    let used: Vec<usize> = (0..4).map(|_| breakers.next()).map(|(i, cb)| cb.run(&image).map(|_| i).unwrap()).collect();
+    assert_eq!(used, vec![0, 1, 0, 1]);
+    assert!(DevicePool::new(vec![]).is_err());
+
+    let environment = CaptchaEnvironment::on_device("cpu", 1).unwrap();
+    assert_eq!(environment.device_id(), Some(1));
+    assert!(execution_provider("cuda:x", &SessionOptions::default()).is_err());
+    #[cfg(not(feature = "cuda"))]
+    assert!(execution_provider("cuda:1", &SessionOptions::default()).err().unwrap().to_string().contains("feature"));
+    let vars = [("CAPTCHA_BREAKER_DEVICE_ID".to_string(), "3".to_string())];
+    assert_eq!(EnvironmentConfig::from_vars(vars).unwrap().device_id, Some(3));
+}
+
+#[test]
+#[cfg(feature = "chinese_click_0")]
+fn test_synthetic_fixtures() {
+    use crate::fixtures::{SynthConfig, SynthGenerator};
+    use image::DynamicImage;
+
+    let config = SynthConfig {
+        image_size: (440, 440),
+        seed: 7,
+        ..Default::default()
+    };
+    // 相同的种子生成相同的图片与答案
+    let first = SynthGenerator::new(config.clone()).unwrap().generate();
+    let again = SynthGenerator::new(config.clone()).unwrap().generate();
+    assert_eq!(first.image, again.image);
+    assert_eq!(first.answers, again.answers);
+    assert_ne!(first.image, SynthGenerator::new(config.clone()).unwrap().nth(1).unwrap().image);
+
+    assert!(SynthGenerator::new(SynthConfig { questions: 5, ..config.clone() }).is_err());
+    assert!(SynthGenerator::new(SynthConfig { answers: 40, alphabet: 64, ..config.clone() }).is_err());
+
+    for captcha in SynthGenerator::new(config.clone()).unwrap().take(8) {
+        assert_eq!((captcha.answers.len(), captcha.questions.len()), (4, 3));
+        assert!(captcha.answers.iter().all(|a| a.bbox.rect.x_min >= 0.0 && a.bbox.rect.x_max <= 440.0 && a.bbox.rect.y_max <= 344.0));
+        assert!(captcha.questions.iter().all(|q| q.bbox.rect.y_min >= 344.0 && q.bbox.rect.y_max <= 440.0));
+        let boxes = captcha.boxes();
+        for (i, a) in boxes.iter().enumerate() {
+            assert!(boxes[i + 1..].iter().all(|b| a.iou(b) == 0.0));
+        }
+        // 每个问题与对应答案的字形相同
+        for (question, &answer) in captcha.questions.iter().zip(&captcha.order) {
+            assert_eq!(question.glyph, captcha.answers[answer].glyph);
+        }
+        assert!(captcha.is_correct(&captcha.clicks(), 0.0));
+        assert!(!captcha.is_correct(&captcha.clicks()[1..], 100.0));
+
+        // 模拟检测出真实位置、特征为字形编号的模型，端到端的答案应与真实答案一致
+        let mut answers: Vec<_> = captcha.answers.iter().collect();
+        answers.sort_by(|a, b| a.bbox.rect.x_min.total_cmp(&b.bbox.rect.x_min));
+        let classes: Vec<usize> = captcha.questions.iter().chain(answers).map(|g| g.glyph).collect();
+        let boxes: Vec<(f32, f32, f32)> = boxes.iter().map(|b| (b.center().0, b.center().1, b.width())).collect();
+        let cb = mock_chinese_click(&boxes, one_hot_features(&classes, config.alphabet), 440);
+        let points = cb.run(&DynamicImage::ImageRgb8(captcha.image.clone())).unwrap();
+        assert!(captcha.is_correct(&points, 1.0));
+    }
+}
+
+#[test]
+#[cfg(all(feature = "eval", feature = "chinese_click_0"))]
+fn test_eval_dataset() {
+    use crate::error::CaptchaError;
+    use crate::eval::{self, EvalConfig, Failure, Prediction};
+    use image::{DynamicImage, RgbImage};
+    use std::fs;
+
+    let dir = std::env::temp_dir().join(format!("captcha_breaker_eval_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("sub")).unwrap();
+    fs::create_dir_all(dir.join("captured")).unwrap();
+    let image = RgbImage::new(440, 440);
+    let sample = |name: &str, json: &str| {
+        image.save(dir.join(format!("{}.png", name))).unwrap();
+        fs::write(dir.join(format!("{}.json", name)), json).unwrap();
+    };
+    // 模拟的识别结果总是 [(150, 100), (50, 100)]
+    sample("correct", r#"{"points": [[150, 100], [50, 100]]}"#);
+    sample("sub/swapped", r#"{"points": [[50, 100], [150, 100]], "answer_boxes": [[30, 80, 70, 120], [130, 80, 170, 120]]}"#);
+    sample("offset", r#"{"points": [[150, 100], [58, 100]]}"#);
+    image.save(dir.join("captured/image.png")).unwrap();
+    fs::write(
+        dir.join("captured/answer.json"),
+        r#"{"question_boxes": [], "answer_boxes": [[130, 80, 170, 120], [230, 80, 270, 120]], "answers": [{"question": 0, "answer": 0, "point": [150, 100], "distance": 0}, {"question": 1, "answer": 1, "point": [250, 100], "distance": 0}]}"#,
+    )
+    .unwrap();
+    fs::write(dir.join("broken.png"), b"not a png").unwrap();
+    fs::write(dir.join("broken.json"), r#"{"points": [[1, 1]]}"#).unwrap();
+    // 没有图片的标注被忽略
+    fs::write(dir.join("orphan.json"), r#"{"points": []}"#).unwrap();
+
+    let samples = eval::load_dataset(&dir).unwrap();
+    let names: Vec<&str> = samples.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, ["broken", "captured", "correct", "offset", "sub/swapped"]);
+    assert_eq!(samples[1].truth.answer_boxes.len(), 2);
+
+    let boxes = [(50.0, 100.0, 40.0), (150.0, 100.0, 40.0), (30.0, 370.0, 20.0), (70.0, 370.0, 20.0)];
+    let cb = mock_chinese_click(&boxes, one_hot_features(&[1, 0, 0, 1], 2), 440);
+    let config = EvalConfig {
+        tolerance: 5.0,
+        ..Default::default()
+    };
+    let report = eval::evaluate_with(&cb, &samples, &config).unwrap();
+    let failures: Vec<Option<Failure>> = report.samples.iter().map(|s| s.failure).collect();
+    assert_eq!(
+        failures,
+        [Some(Failure::Load), Some(Failure::Detect), None, Some(Failure::Click), Some(Failure::Match)]
+    );
+    assert_eq!(report.solved(), 1);
+    assert!((report.accuracy() - 0.2).abs() < 1e-6);
+    // 9次点选中正确的有 correct 2次、offset 1次、captured 1次
+    assert!((report.click_accuracy() - 4.0 / 9.0).abs() < 1e-6);
+    assert_eq!(report.failures().get(&Failure::Detect), Some(&1));
+
+    // 默认容差下偏移8像素的点选视为正确
+    let report = eval::evaluate(&cb, &dir).unwrap();
+    assert!(report.samples[3].is_correct());
+    let json = report.to_json();
+    assert_eq!(json["total"], 5);
+    assert_eq!(json["solved"], 2);
+    assert_eq!(json["failures"]["load"], 1);
+    assert_eq!(json["samples"][4]["failure"], "match");
+    report.save(dir.join("report.json")).unwrap();
+    assert!(fs::read_to_string(dir.join("report.json")).unwrap().contains("click_accuracy"));
+
+    // 闭包也可以评估，识别错误按错误类型归因
+    let failing = |_: &DynamicImage| -> Result<Prediction, Box<dyn std::error::Error>> { Err(CaptchaError::NoDetections.into()) };
+    let report = eval::evaluate_with(&failing, &samples[2..3], &config).unwrap();
+    assert_eq!(report.samples[0].failure, Some(Failure::Detect));
+    assert!(report.samples[0].error.is_some());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+#[cfg(feature = "chinese_click_0")]
+fn test_model_version_canary() {
+    use crate::canary::Arm;
+    use crate::model::Model;
+    use image::DynamicImage;
+    use std::sync::Arc;
+
+    let boxes = [(50.0, 100.0, 40.0), (150.0, 100.0, 40.0), (30.0, 370.0, 20.0), (70.0, 370.0, 20.0)];
+    let environment = mock_click_environment(CaptchaEnvironment::default(), &boxes, one_hot_features(&[1, 0, 0, 1], 2), 440);
+    // 新版本的孪生网络把答案的对应关系反过来
+    let candidate = mock_siamese(one_hot_features(&[0, 1, 0, 1], 2));
+    environment.register_model_version(Model::Siamese, "v2", Arc::new(candidate)).unwrap();
+    assert_eq!(environment.model_versions(Model::Siamese), ["v2"]);
+    // 输入输出不兼容的版本不能注册
+    let yolo = Arc::new(mock_yolo(&[], 440));
+    assert!(environment.register_model_version(Model::Siamese, "broken", yolo).is_err());
+    assert!(environment.variant(&[(Model::Siamese, "v3")]).is_err());
+
+    // 派生环境只替换孪生网络，报告中的版本为注册的版本名
+    let variant = environment.variant(&[(Model::Siamese, "v2")]).unwrap();
+    assert_eq!(variant.model_version(Model::Siamese).version.as_deref(), Some("v2"));
+    assert_eq!(environment.model_version(Model::Siamese).version, None);
+
+    let image = DynamicImage::new_rgb8(440, 440);
+    let canary = environment.load_canary::<ChineseClick0>(&[(Model::Siamese, "v2")], 0.0).unwrap();
+    assert_eq!(canary.version(Arm::Candidate), "siamese@v2");
+    let result = canary.run(|cb| cb.run(&image));
+    assert_eq!((result.arm, result.version.as_str()), (Arm::Control, "siamese@current"));
+    assert_eq!(result.value.unwrap(), vec![(150.0, 100.0), (50.0, 100.0)]);
+
+    canary.set_percent(100.0).unwrap();
+    let result = canary.run(|cb| cb.run(&image));
+    assert_eq!(result.arm, Arm::Candidate);
+    assert_eq!(result.value.unwrap(), vec![(50.0, 100.0), (150.0, 100.0)]);
+    assert!(canary.set_percent(120.0).is_err());
+
+    // 按比例随机分流，相同的key总是同一组
+    canary.set_percent(30.0).unwrap();
+    let candidates = (0..1000).filter(|_| canary.route() == Arm::Candidate).count();
+    assert!((200..400).contains(&candidates), "{}", candidates);
+    assert!((0..100).all(|key| canary.route_key(key) == canary.route_key(key)));
+    let _ = canary.run_on(Arm::Candidate, |_| -> Result<(), Box<dyn std::error::Error>> { Err("失败".into()) });
+    let stats = canary.stats(Arm::Candidate);
+    assert_eq!((stats.solves, stats.failures), (2, 1));
+    assert_eq!(canary.stats(Arm::Control).success_rate(), 1.0);
+}
+
+#[test]
+#[cfg(feature = "chinese_click_1")]
+fn test_chinese_click_text_prompt() {
+    use crate::captcha::{ChineseClick1, prompt_chars};
+    use crate::matching::{DistanceMetric, TemplateBank};
+    use image::{DynamicImage, Rgb, RgbImage};
+    use ndarray::array;
+
+    assert_eq!(prompt_chars("请依次点击：“鸟”“语”“花”"), vec!['鸟', '语', '花']);
+    assert_eq!(prompt_chars("请依次点击:鸟 语 花"), vec!['鸟', '语', '花']);
+    assert_eq!(prompt_chars("请按顺序点击【春】【风】"), vec!['春', '风']);
+    assert_eq!(prompt_chars("click: A B"), vec!['A', 'B']);
+
+    // 图片中只有两个答案，没有提示区
+    let boxes = [(50.0, 100.0, 40.0), (150.0, 100.0, 40.0)];
+    let image = DynamicImage::new_rgb8(440, 440);
+
+    // 渲染的字与答案在同一批次中，模型依次输出字与答案的特征
+    let environment = mock_click_environment(CaptchaEnvironment::default(), &boxes, one_hot_features(&[1, 0, 0, 1], 2), 440);
+    let cb: ChineseClick1 = environment.load_captcha_breaker().unwrap();
+    assert!(cb.run(&image, "请依次点击：鸟 语").is_err());
+    let cb = cb.with_renderer(|_| Ok(RgbImage::from_pixel(32, 32, Rgb([255, 255, 255]))));
+    let result = cb.run_detailed(&image, "请依次点击：鸟 语").unwrap();
+    assert_eq!(result.chars, vec!['鸟', '语']);
+    assert_eq!(result.matches, vec![1, 0]);
+    assert_eq!(result.points, vec![(150.0, 100.0), (50.0, 100.0)]);
+    assert!(cb.run(&image, "请依次点击：").is_err());
+
+    // 字形库中同一个字有多个模板时取最近的一个，只有答案需要提取特征
+    let environment = mock_click_environment(CaptchaEnvironment::default(), &boxes, one_hot_features(&[0, 1], 2), 440);
+    let mut bank = TemplateBank::new(2, DistanceMetric::Euclidean);
+    bank.extend(["鸟", "鸟", "语"], &array![[5.0, 5.0], [0.0, 1.0], [1.0, 0.0]].view()).unwrap();
+    let cb: ChineseClick1 = environment.load_captcha_breaker().unwrap();
+    let cb = cb.with_templates(bank);
+    assert_eq!(cb.run(&image, "请依次点击“鸟”“语”").unwrap(), vec![(150.0, 100.0), (50.0, 100.0)]);
+    assert!(cb.run(&image, "请依次点击“鸟”“花”").is_err());
+}
+
+/// 只有“口”(正方形)与“一”(横条)两个字的最小TrueType字体
+#[cfg(feature = "render")]
+fn square_font() -> Vec<u8> {
+    let be16 = |values: &[i32]| values.iter().flat_map(|&v| (v as u16).to_be_bytes()).collect::<Vec<u8>>();
+    let be32 = |values: &[u32]| values.iter().flat_map(|v| v.to_be_bytes()).collect::<Vec<u8>>();
+    // 单个矩形轮廓的简单字形，点依次为左下、左上、右上、右下
+    let rect = |x0: i32, y0: i32, x1: i32, y1: i32| {
+        let mut glyph = be16(&[1, x0, y0, x1, y1, 3, 0]);
+        glyph.extend([1u8; 4]);
+        glyph.extend(be16(&[x0, 0, x1 - x0, 0, y0, y1 - y0, 0, y0 - y1]));
+        glyph
+    };
+    let glyf = [rect(100, 0, 600, 700), rect(50, 300, 950, 400)].concat();
+    let mut head = be32(&[0x0001_0000, 0x0001_0000, 0, 0x5F0F_3CF5]);
+    head.extend(be16(&[0, 1000]));
+    head.extend([0u8; 16]);
+    head.extend(be16(&[0, 0, 1000, 1000, 0, 8, 2, 0, 0]));
+    let hhea = [be32(&[0x0001_0000]), be16(&[800, -200, 0, 1000, 0, 0, 1000, 1, 0, 0, 0, 0, 0, 0, 0, 3])].concat();
+    let cmap = [be16(&[0, 1, 3, 10]), be32(&[12]), be16(&[12, 0]), be32(&[40, 0, 2, 0x4E00, 0x4E00, 2, 0x53E3, 0x53E3, 1])].concat();
+    let tables: [(&[u8; 4], Vec<u8>); 7] = [
+        (b"cmap", cmap),
+        (b"glyf", glyf),
+        (b"head", head),
+        (b"hhea", hhea),
+        (b"hmtx", be16(&[1000, 0, 1000, 100, 1000, 50])),
+        (b"loca", be16(&[0, 0, 17, 34])),
+        (b"maxp", [be32(&[0x0000_5000]), be16(&[3])].concat()),
+    ];
+    let mut font = [be32(&[0x0001_0000]), be16(&[tables.len() as i32, 64, 2, 48])].concat();
+    let mut offset = 12 + 16 * tables.len();
+    for (tag, data) in &tables {
+        font.extend(tag.as_slice());
+        font.extend(be32(&[0, offset as u32, data.len() as u32]));
+        offset += data.len().div_ceil(4) * 4;
+    }
+    for (_, data) in &tables {
+        font.extend(data);
+        font.resize(font.len().div_ceil(4) * 4, 0);
+    }
+    font
+}
+
+#[test]
+#[cfg(feature = "render")]
+fn test_font_render() {
+    use crate::matching::SiameseEmbedder;
+    use crate::render::{FontRenderer, RenderConfig};
+    use std::sync::Arc;
+
+    assert!(FontRenderer::new(vec![0u8; 16]).is_err());
+    let renderer = FontRenderer::new(square_font()).unwrap();
+    assert!(renderer.contains('口') && !renderer.contains('a'));
+    assert!(renderer.render('a').is_err());
+    assert!(renderer.render_with('口', &RenderConfig { padding: 0.5, ..Default::default() }).is_err());
+
+    // 外接框居中并按较长的边缩放，正方形字形高于宽，左右留白
+    let square = renderer.render('口').unwrap();
+    assert_eq!(square.dimensions(), (64, 64));
+    assert_eq!(square.get_pixel(32, 32).0, [0, 0, 0]);
+    assert_eq!(square.get_pixel(4, 32).0, [255, 255, 255]);
+    let bar = renderer.render('一').unwrap();
+    assert_eq!(bar.get_pixel(32, 32).0, [0, 0, 0]);
+    assert_eq!(bar.get_pixel(32, 20).0, [255, 255, 255]);
+    let dark = |image: &image::RgbImage| image.pixels().filter(|p| p.0[0] < 128).count();
+    let bold = renderer.render_with('一', &RenderConfig { weight: 4.0, ..Default::default() }).unwrap();
+    assert!(dark(&bold) > dark(&bar));
+    let config = RenderConfig { rotation: std::f32::consts::FRAC_PI_2, foreground: image::Rgb([200, 0, 0]), ..Default::default() };
+    let rotated = renderer.render_with('一', &config).unwrap();
+    assert_eq!(rotated.get_pixel(32, 20).0, [200, 0, 0]);
+    assert_eq!(rotated.get_pixel(20, 32).0, [255, 255, 255]);
+    assert_eq!(renderer.render_text("口 一").unwrap().len(), 2);
+
+    // 每个字的每种样式都是一个模板
+    let features = ndarray::array![[1.0, 0.0], [1.0, 0.0], [0.0, 1.0], [0.0, 1.0]];
+    let siamese = mock_siamese(features);
+    let embedder = SiameseEmbedder::new(Arc::new(siamese));
+    let styles = [RenderConfig::default(), RenderConfig { italic: 0.2, ..Default::default() }];
+    let bank = renderer.templates(&embedder, "口一", &styles).unwrap();
+    assert_eq!(bank.labels(), ["口", "口", "一", "一"]);
+    assert_eq!(bank.nearest(ndarray::array![0.0, 1.0].view()).unwrap().label, "一");
+}
+
+#[test]
+#[cfg(feature = "shape_click_0")]
+fn test_shape_click() {
+    use crate::backend::{TensorData, TensorInfo};
+    use crate::captcha::{DEFAULT_SHAPE_LABELS, ShapeClick0, parse_targets};
+    use crate::metadata::KEY_CLASS_LABELS;
+    use crate::model::Model;
+    use crate::testing::MockBackend;
+    use image::DynamicImage;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    let labels: Vec<String> = DEFAULT_SHAPE_LABELS.iter().map(|l| l.to_string()).collect();
+    assert_eq!(parse_targets("请依次点击：三角形、五角星、字母K", &labels), ["triangle", "star", "K"]);
+    assert_eq!(parse_targets("Click the stars, then the letter a and the square", &labels), ["star", "A", "square"]);
+    // 冠词a不是字母目标，"正方形" 中的 "方形" 不重复计数
+    assert_eq!(parse_targets("click a circle", &labels), ["circle"]);
+    assert_eq!(parse_targets("点击正方形", &labels), ["square"]);
+
+    // 检测模型只有一个类别，分类模型从左到右识别为 星、K、三角形
+    let boxes = [(50.0, 100.0, 30.0, 30.0, 0.9), (150.0, 100.0, 30.0, 30.0, 0.9), (250.0, 100.0, 30.0, 30.0, 0.9)];
+    let mut logits = ndarray::Array2::<f32>::zeros((3, 3));
+    for (row, class) in [1, 2, 0].into_iter().enumerate() {
+        logits[[row, class]] = 5.0;
+    }
+    let classifier = MockBackend::new(vec![TensorInfo::f32("images", vec![-1, 3, 32, 32])], vec![TensorInfo::f32("logits", vec![-1, 3])])
+        .with_response(HashMap::from([("logits".to_string(), TensorData::from(logits))]))
+        .with_metadata(HashMap::from([(KEY_CLASS_LABELS.to_string(), "triangle,star,K".to_string())]));
+    let environment = CaptchaEnvironment::default();
+    environment.insert_backend(Model::ShapeDetector, Arc::new(mock_yolo(&boxes, 416))).unwrap();
+    environment.insert_backend(Model::ShapeClassifier, Arc::new(classifier)).unwrap();
+    let cb: ShapeClick0 = environment.load_captcha_breaker().unwrap();
+    assert_eq!(cb.config().labels, ["triangle", "star", "K"]);
+
+    let image = DynamicImage::new_rgb8(416, 416);
+    let points = cb.run(&image, "请依次点击：三角形、五角星、字母K").unwrap();
+    assert_eq!(points, vec![(250.0, 100.0), (50.0, 100.0), (150.0, 100.0)]);
+    // 同一个图形不会被点两次，第二个三角形没有找到
+    let result = cb.run_detailed(&image, "点击三角形和三角形").unwrap();
+    assert_eq!(result.labels, ["star", "K", "triangle"]);
+    assert_eq!(result.matches, vec![Some(2), None]);
+    assert!(cb.run(&image, "点击三角形和三角形").is_err());
+    assert!(cb.run(&image, "点击圆形").is_err());
+}
+
+#[test]
+#[cfg(feature = "chinese_click_0")]
+fn test_glyph_mask() {
+    use crate::backend::{TensorData, TensorInfo};
+    use crate::captcha::ChineseClick0Config;
+    use crate::segment::{GlyphSegmenter, MaskConfig, MaskMethod, mask_crops, threshold_mask};
+    use crate::testing::MockBackend;
+    use image::{DynamicImage, Rgb, RgbImage};
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    // 灰色背景上的黑色方块与一个噪点
+    let mut crop = RgbImage::from_pixel(40, 40, Rgb([200, 200, 200]));
+    for y in 15..25 {
+        for x in 15..25 {
+            crop.put_pixel(x, y, Rgb([20, 20, 20]));
+        }
+    }
+    crop.put_pixel(3, 30, Rgb([20, 20, 20]));
+    let mask = threshold_mask(&crop, None, 0.005);
+    assert_eq!(mask.get_pixel(20, 20).0, [255]);
+    assert_eq!((mask.get_pixel(3, 30).0, mask.get_pixel(0, 0).0), ([0], [0]));
+    let masked = mask_crops(&[&crop], &MaskConfig::default(), None).unwrap();
+    assert_eq!((masked[0].get_pixel(20, 20).0, masked[0].get_pixel(5, 5).0), ([20, 20, 20], [255, 255, 255]));
+    // 没有字形时保留原图像块
+    let blank = RgbImage::from_pixel(20, 20, Rgb([90, 90, 90]));
+    assert_eq!(mask_crops(&[&blank], &MaskConfig::default(), None).unwrap()[0], blank);
+
+    // 分割模型输出logits，掩码缩放回图像块的尺寸
+    let model = MaskConfig { method: MaskMethod::Model { threshold: 0.5 }, dilate: 0, ..Default::default() };
+    assert!(mask_crops(&[&crop], &model, None).is_err());
+    let mut logits = ndarray::Array4::<f32>::from_elem((1, 1, 4, 4), -5.0);
+    logits[[0, 0, 1, 1]] = 5.0;
+    let backend = MockBackend::new(vec![TensorInfo::f32("images", vec![-1, 3, 4, 4])], vec![TensorInfo::f32("mask", vec![-1, 1, 4, 4])])
+        .with_response(HashMap::from([("mask".to_string(), TensorData::from(logits))]));
+    let segmenter = GlyphSegmenter::new(Arc::new(backend));
+    let masks = segmenter.masks(&[&crop], 0.5).unwrap();
+    assert_eq!(masks[0].dimensions(), (40, 40));
+    assert_eq!((masks[0].get_pixel(15, 15).0, masks[0].get_pixel(30, 30).0), ([255], [0]));
+    let masked = mask_crops(&[&crop], &model, Some(&segmenter)).unwrap();
+    assert_eq!(masked[0].get_pixel(30, 30).0, [255, 255, 255]);
+
+    // ChineseClick0在提取特征前去除背景，使用分割模型时需要先设置
+    let boxes = [(50.0, 100.0, 40.0), (150.0, 100.0, 40.0), (30.0, 370.0, 20.0), (70.0, 370.0, 20.0)];
+    let cb = mock_chinese_click(&boxes, one_hot_features(&[1, 0, 0, 1], 2), 440);
+    let config = ChineseClick0Config { mask: Some(MaskConfig::default()), ..cb.config().clone() };
+    let cb = cb.with_config(config);
+    let image = DynamicImage::new_rgb8(440, 440);
+    assert_eq!(cb.run(&image).unwrap(), vec![(150.0, 100.0), (50.0, 100.0)]);
+    let config = ChineseClick0Config { mask: Some(model), ..cb.config().clone() };
+    let cb = cb.with_config(config);
+    assert!(cb.run(&image).is_err());
+}
+
+#[test]
+#[cfg(feature = "chinese_click_0")]
+fn test_matching_tta() {
+    use crate::captcha::ChineseClick0Config;
+    use crate::detection::BBox;
+    use crate::geometry::Rect;
+    use crate::matching::{CostPooling, DistanceMetric, TtaConfig, View, pooled_costs};
+    use image::{DynamicImage, Rgb, RgbImage};
+    use ndarray::array;
+
+    // 原图像块总是第一个视角，1.0与重复的比例被忽略，翻转使视角数量加倍
+    assert!(!TtaConfig::default().is_enabled());
+    let tta = TtaConfig { scales: vec![1.0, 0.9, 0.9, 1.2], flip: true, ..Default::default() };
+    let views = tta.views();
+    assert_eq!(views.len(), 6);
+    assert_eq!(views[0], View::IDENTITY);
+    assert_eq!(views.iter().filter(|view| view.flip).count(), 3);
+
+    // 按中心缩放截取，翻转后左右颠倒
+    let mut image = RgbImage::from_pixel(100, 100, Rgb([255, 255, 255]));
+    image.put_pixel(40, 50, Rgb([0, 0, 0]));
+    let bbox = BBox { rect: Rect::from_center((50.0, 50.0), (20.0, 20.0)), confidence: 0.9, class_id: 0, angle: 0.0 };
+    assert_eq!(View { scale: 1.5, flip: false }.crop(&image, &bbox).dimensions(), (30, 30));
+    let flipped = View { scale: 1.0, flip: true }.crop(&image, &bbox);
+    assert_eq!((flipped.get_pixel(0, 10).0, flipped.get_pixel(19, 10).0), ([255, 255, 255], [0, 0, 0]));
+
+    // 平均只比较相同视角，最小值允许问题与答案使用不同的缩放
+    let views = [View::IDENTITY, View { scale: 1.2, flip: false }];
+    let (q0, a0) = (array![[1.0f32, 0.0]], array![[1.0f32, 0.0], [0.0, 1.0]]);
+    let (q1, a1) = (array![[0.0f32, 1.0]], array![[0.0f32, 1.0], [0.0, 1.0]]);
+    let features = [(q0.view(), a0.view()), (q1.view(), a1.view())];
+    let mean = pooled_costs(&views, &features, DistanceMetric::Euclidean, CostPooling::Mean);
+    let min = pooled_costs(&views, &features, DistanceMetric::Euclidean, CostPooling::Min);
+    assert_eq!(mean.dim(), (1, 2));
+    assert!((mean[[0, 0]] - 0.0).abs() < 1e-6 && (mean[[0, 1]] - 2f32.sqrt() / 2.0).abs() < 1e-6);
+    assert!(min.iter().all(|&cost| cost.abs() < 1e-6));
+
+    // ChineseClick0在同一批次中提取其余视角的特征
+    let boxes = [(50.0, 100.0, 40.0), (150.0, 100.0, 40.0), (30.0, 370.0, 20.0), (70.0, 370.0, 20.0)];
+    let cb = mock_chinese_click(&boxes, one_hot_features(&[1, 0, 0, 1], 2), 440);
+    let config = ChineseClick0Config {
+        tta: TtaConfig { scales: vec![1.2], pooling: CostPooling::Min, ..Default::default() },
+        ..cb.config().clone()
+    };
+    let cb = cb.with_config(config);
+    assert_eq!(cb.run(&DynamicImage::new_rgb8(440, 440)).unwrap(), vec![(150.0, 100.0), (50.0, 100.0)]);
+}
+
+#[test]
+#[cfg(feature = "chinese_click_0")]
+fn test_tiled_detection() {
+    use crate::detection::{TilingConfig, YoloConfig, YoloDetector, YoloLayout, tile_windows};
+    use crate::geometry::Rect;
+    use image::RgbImage;
+    use std::sync::Arc;
+
+    // 窗口均匀分布，最后一个与图片边缘对齐；小于图块的方向只有一个窗口
+    let windows = tile_windows((1000, 300), 400, 0.2);
+    assert_eq!(windows, vec![Rect::new(0, 0, 400, 300), Rect::new(300, 0, 700, 300), Rect::new(600, 0, 1000, 300)]);
+    assert_eq!(tile_windows((400, 400), 400, 0.2).len(), 1);
+    assert!(!TilingConfig::default().applies((500, 500), 400));
+    assert!(TilingConfig::default().applies((1000, 400), 400));
+
+    // 每个图块返回相同的两个框，贴着图块内侧边缘的框只在最后一个图块中保留
+    let yolo = mock_yolo(&[(200.0, 200.0, 40.0, 40.0, 0.9), (390.0, 200.0, 20.0, 20.0, 0.9)], 400);
+    let config = YoloConfig { layout: YoloLayout::V8, ..Default::default() };
+    let detector = YoloDetector::new(Arc::new(yolo)).with_config(config.clone());
+    // 模型的批次维度固定为1
+    assert_eq!(detector.max_batch_size(), 1);
+    let image = RgbImage::new(1000, 400);
+    assert_eq!(detector.detect_rgb(&image).unwrap().len(), 2);
+    let detector = detector.with_config(YoloConfig { tiling: Some(TilingConfig::default()), ..config });
+    let mut centers: Vec<(f32, f32)> = detector.detect_rgb(&image).unwrap().iter().map(|b| b.center()).collect();
+    centers.sort_by(|a, b| a.0.total_cmp(&b.0));
+    assert_eq!(centers, vec![(200.0, 200.0), (500.0, 200.0), (800.0, 200.0), (990.0, 200.0)]);
+    // 图片不够大时整图检测
+    assert_eq!(detector.detect_rgb(&RgbImage::new(500, 400)).unwrap().len(), 2);
+}
+
+#[test]
+#[cfg(feature = "chinese_click_0")]
+fn test_tiled_detection_fixed_batch() {
+    use crate::backend::{TensorData, TensorInfo};
+    use crate::detection::{TilingConfig, YoloConfig, YoloDetector, YoloLayout};
+    use crate::matching::SiameseMatcher;
+    use crate::testing::{MockBackend, RecordingBackend};
+    use image::RgbImage;
+    use image::imageops::FilterType;
+    use ndarray::{Array2, Array3};
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    // 批次维度固定为2的模型，两张图片的输出相同
+    let mut output = Array3::<f32>::zeros((2, 5, 2));
+    for batch in 0..2 {
+        for (row, value) in [200.0, 200.0, 40.0, 40.0, 0.9].into_iter().enumerate() {
+            output[[batch, row, 0]] = value;
+        }
+    }
+    let yolo = MockBackend::new(vec![TensorInfo::f32("images", vec![2, 3, 400, 400])], vec![TensorInfo::f32("output0", vec![2, 5, 2])])
+        .with_response(HashMap::from([("output0".to_string(), TensorData::from(output))]));
+    let recorder = Arc::new(RecordingBackend::new(Arc::new(yolo)));
+    let config = YoloConfig { layout: YoloLayout::V8, tiling: Some(TilingConfig::default()), ..Default::default() };
+    let detector = YoloDetector::new(recorder.clone()).with_config(config);
+    assert_eq!(detector.max_batch_size(), 2);
+    // 3个图块分两批，最后一批补齐到2张，补上的输出被丢弃
+    let mut centers: Vec<(f32, f32)> = detector.detect_rgb(&RgbImage::new(1000, 400)).unwrap().iter().map(|b| b.center()).collect();
+    centers.sort_by(|a, b| a.0.total_cmp(&b.0));
+    assert_eq!(centers, vec![(200.0, 200.0), (500.0, 200.0), (800.0, 200.0)]);
+    let calls = recorder.fixture().calls;
+    assert_eq!(calls.len(), 2);
+    assert!(calls.iter().all(|call| call.inputs[0].1.shape()[0] == 2));
+
+    // 匹配模型同样补齐最后一批，3个图像块得到3行特征
+    let siamese = MockBackend::new(vec![TensorInfo::f32("images", vec![2, 3, 96, 96])], vec![TensorInfo::f32("features", vec![2, 4])])
+        .with_response(HashMap::from([("features".to_string(), TensorData::from(Array2::<f32>::ones((2, 4))))]));
+    let recorder = Arc::new(RecordingBackend::new(Arc::new(siamese)));
+    let matcher = SiameseMatcher::new(recorder.clone());
+    assert_eq!(matcher.max_batch_size(), 2);
+    let crop = RgbImage::new(8, 8);
+    let features = matcher.embed(&[&crop, &crop, &crop], FilterType::Triangle).unwrap();
+    assert_eq!(features.dim(), (3, 4));
+    let calls = recorder.fixture().calls;
+    assert_eq!(calls.len(), 2);
+    assert!(calls.iter().all(|call| call.inputs[0].1.shape()[0] == 2));
+}
+
+#[test]
+#[cfg(feature = "chinese_click_0")]
+fn test_chinese_click_config_keeps_resolved_detector() {
+    use crate::captcha::ChineseClick0Config;
+    use crate::matching::DistanceMetric;
+    use image::DynamicImage;
+
+    let environment = CaptchaEnvironment::builder().ignore_env().confidence_threshold(0.3).nms_threshold(0.6).build().unwrap();
+    let boxes = [(50.0, 100.0, 40.0), (150.0, 100.0, 40.0), (30.0, 370.0, 20.0), (70.0, 370.0, 20.0)];
+    let cb = mock_chinese_click_in(environment, &boxes, one_hot_features(&[1, 0, 0, 1], 2), 440);
+    let cb = cb.with_config(ChineseClick0Config { metric: DistanceMetric::Cosine, ..Default::default() });
+    // 从模型读取的输入尺寸与环境中的阈值不会被默认配置覆盖
+    let detector = &cb.config().detector;
+    assert_eq!(detector.input_size, Some(440));
+    assert_eq!((detector.confidence_threshold, detector.nms_threshold), (0.3, Some(0.6)));
+    assert_eq!(cb.config().metric, DistanceMetric::Cosine);
+    assert_eq!(cb.run(&DynamicImage::new_rgb8(440, 440)).unwrap(), vec![(150.0, 100.0), (50.0, 100.0)]);
+    // 显式设置的值仍然生效
+    let cb = cb.with_config(ChineseClick0Config {
+        detector: crate::detection::YoloConfig { input_size: Some(320), confidence_threshold: 0.7, ..Default::default() },
+        ..Default::default()
+    });
+    assert_eq!((cb.config().detector.input_size, cb.config().detector.confidence_threshold), (Some(320), 0.7));
+}
+
+#[test]
+#[cfg(feature = "chinese_click_0")]
+fn test_model_watcher_retries_uncommitted() {
+    use crate::loader::ModelWatcher;
+    use crate::model::Model;
+    use std::time::{Duration, SystemTime};
+
+    let dir = std::env::temp_dir().join(format!("captcha_breaker_watch_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(Model::Siamese.file_name());
+    fs::write(&path, b"old").unwrap();
+    let mut watcher = ModelWatcher::new(&dir);
+    assert!(watcher.changed().is_empty());
+
+    let modified = SystemTime::now() + Duration::from_secs(60);
+    fs::File::options().write(true).open(&path).unwrap().set_modified(modified).unwrap();
+    // 没有提交新的修改时间时下次仍然报告，例如重新加载失败后重试
+    assert_eq!(watcher.changed(), vec![(Model::Siamese, modified)]);
+    assert_eq!(watcher.changed(), vec![(Model::Siamese, modified)]);
+    watcher.commit(Model::Siamese, modified);
+    assert!(watcher.changed().is_empty());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+#[cfg(feature = "chinese_click_0")]
+fn test_self_described_model_skips_unparsable_files() {
+    use crate::loader::{DirModelLoader, ModelLoaderTrait};
+    use crate::metadata::KEY_MODEL;
+    use crate::model::{Model, Precision};
+
+    let dir = std::env::temp_dir().join(format!("captcha_breaker_described_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let string = |field: u8, s: &str| [vec![field << 3 | 2, s.len() as u8], s.as_bytes().to_vec()].concat();
+    let declared = |name: &str| {
+        let props = [string(1, KEY_MODEL), string(2, name)].concat();
+        [vec![1 << 3, 8, 14 << 3 | 2, props.len() as u8], props].concat()
+    };
+    // 按文件名排序时损坏的文件与其他用途的模型排在前面，都只读取文件头
+    fs::write(dir.join("a_corrupt.onnx"), [0xff, 0xff, 0xff]).unwrap();
+    fs::write(dir.join("b_other.onnx"), declared("yolo")).unwrap();
+    fs::write(dir.join("c_siamese.onnx"), declared(Model::Siamese.name())).unwrap();
+    let loader = DirModelLoader::new(&dir);
+    let bytes = loader.model_bytes(Model::Siamese, Precision::Fp32).unwrap();
+    assert_eq!(bytes, Some(declared(Model::Siamese.name())));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+#[cfg(feature = "nine_grid_0")]
+fn test_nine_grid_selection() {
+    use crate::backend::{ElementType, TensorData, TensorInfo};
+    use crate::captcha::{CellSelection, NineGrid0, NineGrid0Config};
+    use crate::model::Model;
+    use crate::testing::MockBackend;
+    use crate::tokenizer::WordPieceTokenizer;
+    use image::{DynamicImage, RgbImage};
+    use ndarray::{Array2, array};
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    // 格子0、4、8与提示词的相似度分别为1.0、0.6、0.3，其余为0
+    let mut cells = Array2::<f32>::zeros((9, 2));
+    cells.column_mut(1).fill(1.0);
+    for (cell, x) in [(0, 1.0f32), (4, 0.6), (8, 0.3)] {
+        cells.row_mut(cell).assign(&array![x, (1.0 - x * x).sqrt()]);
+    }
+    let image_encoder = MockBackend::new(vec![TensorInfo::f32("pixel_values", vec![-1, 3, 8, 8])], vec![TensorInfo::f32("image_embeds", vec![-1, 2])])
+        .with_response(HashMap::from([("image_embeds".to_string(), TensorData::from(cells))]));
+    let ids = |name: &str| TensorInfo { name: name.to_string(), shape: vec![1, -1], element_type: ElementType::I64 };
+    let text_encoder = MockBackend::new(vec![ids("input_ids"), ids("attention_mask")], vec![TensorInfo::f32("text_embeds", vec![1, 2])])
+        .with_response(HashMap::from([("text_embeds".to_string(), TensorData::from(array![[2.0f32, 0.0]]))]));
+    let environment = CaptchaEnvironment::default();
+    environment.insert_backend(Model::ClipImage, Arc::new(image_encoder)).unwrap();
+    environment.insert_backend(Model::ClipText, Arc::new(text_encoder)).unwrap();
+    let nine_grid: NineGrid0 = environment.load_captcha_breaker().unwrap();
+    let nine_grid = nine_grid.with_tokenizer(WordPieceTokenizer::from_vocab_str("[PAD]\n[UNK]\n[CLS]\n[SEP]\n车", 8));
+    let image = DynamicImage::ImageRgb8(RgbImage::new(30, 30));
+
+    let selection = nine_grid.run(&image, "车").unwrap();
+    assert!((selection.similarities[4] - 0.6).abs() < 1e-5);
+    assert_eq!(selection.cells, vec![0, 4, 8]);
+    let nine_grid = nine_grid.with_config(NineGrid0Config { selection: CellSelection::Absolute(1.1), ..Default::default() });
+    assert!(nine_grid.run(&image, "车").unwrap().cells.is_empty());
+
+    // sigmoid(10s - 5) >= 0.5 即 s >= 0.5
+    let calibrated = CellSelection::Calibrated { scale: 10.0, bias: -5.0, min_probability: 0.5 };
+    assert_eq!(calibrated.select(&selection.similarities), vec![0, 4]);
+    let margin = CellSelection::Margin { min_similarity: 0.2, margin: 0.5 };
+    assert_eq!(margin.select(&selection.similarities), vec![0, 4]);
+    // 相对阈值总会选中最相似的格子，绝对阈值不会
+    assert_eq!(CellSelection::Margin { min_similarity: 0.0, margin: 0.03 }.select(&[0.1, 0.05]), vec![0]);
+    assert!(CellSelection::Absolute(0.25).select(&[0.1, 0.05]).is_empty());
+}
+
+#[test]
+fn test_word_piece_tokenizer() {
+    use crate::tokenizer::{PromptTokenizer, WordPieceTokenizer};
+
+    let tokenizer = WordPieceTokenizer::from_vocab_str("[PAD]\n[UNK]\n[CLS]\n[SEP]\n公\n交\n车\nbus\n##es", 12);
+    // 中文逐字切分，英文按最长匹配切分为bus ##es，无法切分的标点为[UNK]，不足的长度补[PAD]
+    assert_eq!(tokenizer.encode("公交车 Buses!"), vec![2, 4, 5, 6, 7, 8, 1, 3, 0, 0, 0, 0]);
+    // 无法完整切分的词整体为一个[UNK]
+    assert_eq!(tokenizer.encode("busy"), vec![2, 1, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+    // 超长时截断，保留结尾的[SEP]
+    let short = WordPieceTokenizer::from_vocab_str("[PAD]\n[UNK]\n[CLS]\n[SEP]\n公\n交\n车", 4);
+    assert_eq!(short.encode("公交车"), vec![2, 4, 5, 3]);
+}
+
+#[test]
+fn test_ctc_decode() {
+    use crate::ctc::{beam_decode, greedy_decode, log_softmax};
+    use ndarray::array;
+
+    // 类别0为blank
+    let probs = array![[0.1f32, 0.8, 0.1], [0.1, 0.7, 0.2], [0.9, 0.05, 0.05], [0.2, 0.6, 0.2], [0.1, 0.1, 0.8], [0.3, 0.1, 0.6]];
+    let log_probs = probs.mapv(f32::ln);
+    // 相邻的重复合并，隔着blank的重复保留
+    let (labels, score) = greedy_decode(&log_probs.view(), 0);
+    assert_eq!(labels, vec![1, 1, 2]);
+    assert!((score - 0.8 * 0.6 * 0.8).abs() < 1e-5);
+    assert_eq!(greedy_decode(&array![[0.0f32, -5.0], [0.0, -5.0]].view(), 0).0, Vec::<usize>::new());
+    let softmax = log_softmax(&probs.mapv(|p| p.ln() + 3.0).view());
+    assert!(softmax.iter().zip(&log_probs).all(|(a, b)| (a - b).abs() < 1e-5));
+
+    // 每一步blank都最可能，但 "a" 的所有路径之和 0.4·0.4 + 0.4·0.6 + 0.6·0.4 = 0.64 高于空序列的 0.36
+    let ambiguous = array![[0.6f32, 0.4], [0.6, 0.4]].mapv(f32::ln);
+    assert!(greedy_decode(&ambiguous.view(), 0).0.is_empty());
+    let (labels, p) = beam_decode(&ambiguous.view(), 0, 4);
+    assert_eq!(labels, vec![1]);
+    assert!((p - 0.64).abs() < 1e-5);
+
+    // 重复字符只有隔着blank的路径 a _ a 能得到 "aa"
+    let repeated = array![[0.1f32, 0.9], [0.9, 0.1], [0.1, 0.9]].mapv(f32::ln);
+    let (labels, p) = beam_decode(&repeated.view(), 0, 4);
+    assert_eq!(labels, vec![1, 1]);
+    assert!((p - 0.729).abs() < 1e-5);
+    // 束宽为1时与贪心解码的结果相同
+    assert_eq!(beam_decode(&log_probs.view(), 0, 1).0, greedy_decode(&log_probs.view(), 0).0);
+}
+
+#[test]
+#[cfg(feature = "slider_gap_0")]
+fn test_slider_gap() {
+    use crate::backend::{TensorData, TensorInfo};
+    use crate::captcha::{SliderGap0, SliderGap0Config};
+    use crate::model::Model;
+    use crate::testing::MockBackend;
+    use image::{DynamicImage, RgbImage};
+    use ndarray::array;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    // 端到端输出，每行为 (x1, y1, x2, y2, 置信度, 类别)，坐标为100x100的模型输入坐标
+    let output = array![[[10.0f32, 40.0, 20.0, 60.0, 0.6, 0.0], [30.0, 40.0, 40.0, 60.0, 0.9, 0.0], [70.0, 40.0, 80.0, 60.0, 0.1, 0.0]]];
+    let model = MockBackend::new(vec![TensorInfo::f32("images", vec![1, 3, 100, 100])], vec![TensorInfo::f32("output0", vec![1, 3, 6])])
+        .with_response(HashMap::from([("output0".to_string(), TensorData::from(output))]));
+    let environment = CaptchaEnvironment::default();
+    environment.insert_backend(Model::SliderGap, Arc::new(model)).unwrap();
+    let slider: SliderGap0 = environment.load_captcha_breaker().unwrap();
+    // 背景图宽为模型输入的两倍，偏移量按原图坐标返回
+    let image = DynamicImage::ImageRgb8(RgbImage::new(200, 100));
+    assert_eq!(slider.run(&image).unwrap(), Some(60.0));
+    let gap = slider.run_detailed(&image).unwrap().unwrap();
+    assert!((gap.confidence - 0.9).abs() < 1e-6);
+
+    let slider = slider.with_config(SliderGap0Config { confidence_threshold: 0.95, ..Default::default() });
+    assert_eq!(slider.run(&image).unwrap(), None);
+}
+
+#[test]
+#[cfg(all(feature = "server", feature = "audio"))]
+fn test_server_audio_route() {
+    use crate::backend::{ElementType, TensorData, TensorInfo};
+    use crate::model::Model;
+    use crate::server::{ServerConfig, router};
+    use crate::testing::MockBackend;
+    use axum::body::{Body, to_bytes};
+    use axum::http::{Request, StatusCode};
+    use base64::Engine;
+    use base64::engine::general_purpose::STANDARD;
+    use ndarray::{Array3, ArrayD, IxDyn};
+    use std::collections::HashMap;
+    use std::io::Cursor;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    // 其他开启的验证码只需要能通过构建与预热
+    let environment = CaptchaEnvironment::default();
+    for &model in Model::ALL {
+        let spec = model.io_spec();
+        let rank = |rank: Option<usize>| rank.unwrap_or(3);
+        let shape = |rank: usize| [vec![1], vec![8; rank - 1]].concat();
+        let input = TensorInfo { name: "input".to_string(), shape: shape(rank(spec.input_rank)), element_type: spec.input_type };
+        let output = shape(rank(spec.output_rank));
+        let data = TensorData::F32(ArrayD::zeros(IxDyn(&output.iter().map(|&d| d as usize).collect::<Vec<_>>())));
+        let mock = MockBackend::new(vec![input], vec![TensorInfo::f32("output", output)]).with_response(HashMap::from([("output".to_string(), data)]));
+        environment.insert_backend(model, Arc::new(mock)).unwrap();
+    }
+    // 三个时间步依次为 '1'、blank、'2'
+    let mut logits = Array3::<f32>::zeros((1, 3, 37));
+    for (t, class) in [2, 0, 3].into_iter().enumerate() {
+        logits[[0, t, class]] = 10.0;
+    }
+    let asr = MockBackend::new(
+        vec![TensorInfo { name: "mel".to_string(), shape: vec![1, 80, -1], element_type: ElementType::F32 }],
+        vec![TensorInfo::f32("logits", vec![1, -1, 37])],
+    )
+    .with_response(HashMap::from([("logits".to_string(), TensorData::from(logits))]));
+    environment.insert_backend(Model::AudioAsr, Arc::new(asr)).unwrap();
+    let app = router(&environment, &ServerConfig::default()).unwrap();
+
+    let mut wav = Cursor::new(vec![]);
+    let spec = hound::WavSpec { channels: 1, sample_rate: 16000, bits_per_sample: 16, sample_format: hound::SampleFormat::Int };
+    let mut writer = hound::WavWriter::new(&mut wav, spec).unwrap();
+    for i in 0..8000 {
+        writer.write_sample(((i as f32 * 0.05).sin() * 8000.0) as i16).unwrap();
+    }
+    writer.finalize().unwrap();
+    let data_url = format!("data:audio/wav;base64,{}", STANDARD.encode(wav.into_inner()));
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let post = |image: &str| {
+        let request = Request::post("/audio_captcha_0")
+            .header("content-type", "application/json")
+            .body(Body::from(serde_json::json!({ "image": image }).to_string()))
+            .unwrap();
+        runtime.block_on(async {
+            let response = app.clone().oneshot(request).await.unwrap();
+            let status = response.status();
+            let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            (status, serde_json::from_slice::<serde_json::Value>(&body).unwrap())
+        })
+    };
+    // 与图片接口一样接受data URL
+    let (status, body) = post(&data_url);
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["text"], "12");
+    let (status, body) = post("not base64!");
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(body["error"].is_string());
+}
+
+#[test]
+#[cfg(feature = "ffi")]
+fn test_ffi_last_error_is_cleared() {
+    use crate::ffi::{CaptchaStatus, captcha_env_free, captcha_env_new, captcha_last_error};
+    use std::ffi::CStr;
+    use std::ptr;
+
+    assert_eq!(unsafe { captcha_env_new(ptr::null(), ptr::null_mut()) }, CaptchaStatus::NullPointer);
+    let error = captcha_last_error();
+    assert!(!error.is_null());
+    assert_eq!(unsafe { CStr::from_ptr(error) }.to_str().unwrap(), "null pointer");
+    // 之后成功的调用清除上一次的错误
+    let mut env = ptr::null_mut();
+    assert_eq!(unsafe { captcha_env_new(ptr::null(), &mut env) }, CaptchaStatus::Ok);
+    assert!(captcha_last_error().is_null());
+    unsafe { captcha_env_free(env) };
+}
+
+#[test]
+fn test_warmup_inputs() {
+    use crate::backend::{ElementType, TensorData, TensorInfo};
+    use crate::testing::{MockBackend, RecordingBackend};
+    use crate::warmup::warmup_backend;
+    use ndarray::array;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    let ids = TensorInfo { name: "input_ids".to_string(), shape: vec![1, -1], element_type: ElementType::I64 };
+    let mock = MockBackend::new(vec![TensorInfo::f32("images", vec![-1, 3, -1, 8]), ids], vec![TensorInfo::f32("y", vec![1])])
+        .with_response(HashMap::from([("y".to_string(), TensorData::from(array![0.0f32]))]));
+    let recording = RecordingBackend::new(Arc::new(mock));
+    warmup_backend(&recording).unwrap();
+    // 批次维度取1，其余动态维度取64
+    let inputs = &recording.fixture().calls[0].inputs;
+    assert_eq!((inputs[0].1.shape(), inputs[0].1.element_type()), (&[1, 3, 64, 8][..], ElementType::F32));
+    assert_eq!((inputs[1].1.shape(), inputs[1].1.element_type()), (&[1, 64][..], ElementType::I64));
+
+    // 无法构造的输入类型直接报错，不会用f32代替
+    let other = TensorInfo { name: "mask".to_string(), shape: vec![1, 4], element_type: ElementType::Other };
+    let mock = MockBackend::new(vec![other], vec![TensorInfo::f32("y", vec![1])])
+        .with_response(HashMap::from([("y".to_string(), TensorData::from(array![0.0f32]))]));
+    let err = warmup_backend(&mock).unwrap_err().to_string();
+    assert!(err.contains("mask"), "{}", err);
+    assert_eq!(mock.calls(), 0);
+}
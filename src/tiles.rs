@@ -0,0 +1,306 @@
+//! 拼图类验证码共用的切块与边缘相容度计算
+
+use crate::input;
+use crate::lap;
+use image::{DynamicImage, GenericImageView, RgbImage};
+use ndarray::Array2;
+use std::error::Error;
+
+/// 把图片按网格切成 rows*cols 块，按行优先顺序返回
+///
+/// 网格为0行或0列、图片不足以让每块至少有1像素时返回错误
+pub fn split_tiles(image: &DynamicImage, rows: u32, cols: u32) -> Result<Vec<RgbImage>, Box<dyn Error>> {
+    if rows == 0 || cols == 0 {
+        return Err(format!("网格需要至少1行1列，实际为{}x{}", rows, cols).into());
+    }
+    let (width, height) = (image.width() / cols, image.height() / rows);
+    if width == 0 || height == 0 {
+        return Err(format!("{}x{}的图片无法切成{}x{}块", image.width(), image.height(), rows, cols).into());
+    }
+    let rgb = input::to_rgb8(image, input::DEFAULT_BACKGROUND);
+    let mut tiles = Vec::with_capacity((rows * cols) as usize);
+    for r in 0..rows {
+        for c in 0..cols {
+            tiles.push(rgb.view(c * width, r * height, width, height).to_image());
+        }
+    }
+    Ok(tiles)
+}
+
+/// 相邻两列(或两行)像素之间的平均差异，用于寻找切块边界
+fn discontinuity(image: &RgbImage, vertical_lines: bool) -> Vec<f32> {
+    let (width, height) = image.dimensions();
+    let (lines, length) = if vertical_lines { (width, height) } else { (height, width) };
+    (0..lines.saturating_sub(1))
+        .map(|i| {
+            let mut sum = 0.0;
+            for k in 0..length {
+                let (a, b) = if vertical_lines {
+                    (image.get_pixel(i, k), image.get_pixel(i + 1, k))
+                } else {
+                    (image.get_pixel(k, i), image.get_pixel(k, i + 1))
+                };
+                sum += (0..3).map(|c| (a[c] as f32 - b[c] as f32).abs()).sum::<f32>();
+            }
+            sum / length.max(1) as f32
+        })
+        .collect()
+}
+
+/// 在等分位置上差异明显高于平均水平时认为是切块边界，返回最可能的切分数
+fn detect_splits(profile: &[f32], max_splits: u32) -> u32 {
+    if profile.is_empty() {
+        return 1;
+    }
+    let mean = profile.iter().sum::<f32>() / profile.len().max(1) as f32;
+    let mut best = (1, 0.0);
+    for k in 2..=max_splits {
+        let len = profile.len() + 1;
+        let score = (1..k)
+            .map(|i| {
+                // 边界可能偏离等分位置一个像素
+                let pos = (i as usize * len / k as usize).saturating_sub(1);
+                (pos.saturating_sub(1)..=(pos + 1).min(profile.len() - 1))
+                    .map(|p| profile[p])
+                    .fold(0.0, f32::max)
+            })
+            .sum::<f32>()
+            / (k - 1).max(1) as f32;
+        // 更细的切分必须明显更好，否则2x2的图也会匹配4x4
+        if score > mean * 2.0 && score > best.1 * 0.9 {
+            best = (k, score);
+        }
+    }
+    best.0
+}
+
+/// 检测切块网格，返回 (rows, cols)
+pub fn detect_grid(image: &DynamicImage, max_splits: u32) -> (u32, u32) {
+    let rgb = input::to_rgb8(image, input::DEFAULT_BACKGROUND);
+    let cols = detect_splits(&discontinuity(&rgb, true), max_splits);
+    let rows = detect_splits(&discontinuity(&rgb, false), max_splits);
+    (rows, cols)
+}
+
+fn edge_difference(a: &RgbImage, b: &RgbImage, horizontal: bool) -> f32 {
+    let (width, height) = a.dimensions();
+    let length = if horizontal { height } else { width };
+    let mut sum = 0.0;
+    for k in 0..length {
+        let (p, q) = if horizontal {
+            (a.get_pixel(width - 1, k), b.get_pixel(0, k))
+        } else {
+            (a.get_pixel(k, height - 1), b.get_pixel(k, 0))
+        };
+        sum += (0..3).map(|c| (p[c] as f32 - q[c] as f32).powi(2)).sum::<f32>();
+    }
+    (sum / length.max(1) as f32).sqrt()
+}
+
+/// 相容度矩阵，`horizontal[[i, j]]` 为tile i放在tile j左边的接缝代价，
+/// `vertical[[i, j]]` 为tile i放在tile j上边的接缝代价
+pub struct Compatibility {
+    pub horizontal: Array2<f32>,
+    pub vertical: Array2<f32>,
+}
+
+impl Compatibility {
+    /// 所有tile的尺寸需要相同且不为空，例如 [`split_tiles`] 的结果
+    pub fn new(tiles: &[RgbImage]) -> Result<Self, Box<dyn Error>> {
+        if let Some(first) = tiles.first() {
+            let size = first.dimensions();
+            if size.0 == 0 || size.1 == 0 {
+                return Err("图块为空".into());
+            }
+            if tiles.iter().any(|tile| tile.dimensions() != size) {
+                return Err("图块的尺寸不一致".into());
+            }
+        }
+        let n = tiles.len();
+        Ok(Compatibility {
+            horizontal: Array2::from_shape_fn((n, n), |(i, j)| edge_difference(&tiles[i], &tiles[j], true)),
+            vertical: Array2::from_shape_fn((n, n), |(i, j)| edge_difference(&tiles[i], &tiles[j], false)),
+        })
+    }
+
+    /// 排列的总接缝代价，`arrangement[p]` 为位置p上的tile
+    pub fn total_cost(&self, arrangement: &[usize], cols: usize) -> f32 {
+        let mut cost = 0.0;
+        for p in 0..arrangement.len() {
+            if (p + 1) % cols != 0 && p + 1 < arrangement.len() {
+                cost += self.horizontal[[arrangement[p], arrangement[p + 1]]];
+            }
+            if p + cols < arrangement.len() {
+                cost += self.vertical[[arrangement[p], arrangement[p + cols]]];
+            }
+        }
+        cost
+    }
+
+    /// 在当前排列的邻居固定的前提下，把tile t放到位置p的代价
+    fn placement_cost(&self, arrangement: &[usize], cols: usize, t: usize, p: usize) -> f32 {
+        let n = arrangement.len();
+        let mut cost = 0.0;
+        let neighbor = |q: usize| Some(arrangement[q]).filter(|&u| u != t);
+        if !p.is_multiple_of(cols) {
+            cost += neighbor(p - 1).map_or(0.0, |u| self.horizontal[[u, t]]);
+        }
+        if !(p + 1).is_multiple_of(cols) && p + 1 < n {
+            cost += neighbor(p + 1).map_or(0.0, |u| self.horizontal[[t, u]]);
+        }
+        if p >= cols {
+            cost += neighbor(p - cols).map_or(0.0, |u| self.vertical[[u, t]]);
+        }
+        if p + cols < n {
+            cost += neighbor(p + cols).map_or(0.0, |u| self.vertical[[t, u]]);
+        }
+        cost
+    }
+
+    /// 交换任意两块中代价最低的排列
+    pub fn best_swap(&self, cols: usize) -> Vec<usize> {
+        let n = self.horizontal.nrows();
+        let mut best: Vec<usize> = (0..n).collect();
+        let mut best_cost = self.total_cost(&best, cols);
+        for a in 0..n {
+            for b in a + 1..n {
+                let mut arrangement: Vec<usize> = (0..n).collect();
+                arrangement.swap(a, b);
+                let cost = self.total_cost(&arrangement, cols);
+                if cost < best_cost {
+                    best_cost = cost;
+                    best = arrangement;
+                }
+            }
+        }
+        best
+    }
+
+    /// 迭代求解排列：固定邻居后用LAPJV把所有tile同时分配到位置，直到排列不再改进
+    pub fn best_arrangement(&self, cols: usize, max_iterations: usize) -> Result<Vec<usize>, lap::LapJVError> {
+        let n = self.horizontal.nrows();
+        let mut arrangement: Vec<usize> = (0..n).collect();
+        let mut best_cost = self.total_cost(&arrangement, cols);
+        for _ in 0..max_iterations {
+            let costs = Array2::from_shape_fn((n, n), |(t, p)| self.placement_cost(&arrangement, cols, t, p));
+            let (tile_to_pos, _) = lap::lapjv(&costs)?;
+            let mut next = vec![0; n];
+            for (t, &p) in tile_to_pos.iter().enumerate() {
+                next[p] = t;
+            }
+            let cost = self.total_cost(&next, cols);
+            if cost >= best_cost {
+                break;
+            }
+            best_cost = cost;
+            arrangement = next;
+        }
+        Ok(arrangement)
+    }
+
+    /// 局部搜索：反复尝试交换两块或把一块插入其他位置，直到总代价不再下降
+    ///
+    /// LAPJV每轮固定邻居求解，可能停在两两成对互换的局部最优，搜索后通常可以跳出
+    pub fn refine(&self, mut arrangement: Vec<usize>, cols: usize) -> Vec<usize> {
+        let n = arrangement.len();
+        let mut best_cost = self.total_cost(&arrangement, cols);
+        let mut improved = true;
+        while improved {
+            improved = false;
+            for i in 0..n {
+                for j in (0..n).filter(|&j| j != i) {
+                    let mut swapped = arrangement.clone();
+                    swapped.swap(i, j);
+                    let mut moved = arrangement.clone();
+                    let tile = moved.remove(i);
+                    moved.insert(j, tile);
+                    for candidate in [swapped, moved] {
+                        let cost = self.total_cost(&candidate, cols);
+                        if cost < best_cost - f32::EPSILON {
+                            best_cost = cost;
+                            arrangement = candidate;
+                            improved = true;
+                        }
+                    }
+                }
+            }
+        }
+        arrangement
+    }
+}
+
+/// 把排列分解为一系列交换，依次执行后每个位置p上为 `arrangement[p]` 号tile
+pub fn swaps_for(arrangement: &[usize]) -> Vec<(usize, usize)> {
+    let mut current: Vec<usize> = (0..arrangement.len()).collect();
+    let mut position: Vec<usize> = (0..arrangement.len()).collect();
+    let mut swaps = vec![];
+    for p in 0..arrangement.len() {
+        let tile = arrangement[p];
+        if current[p] != tile {
+            let q = position[tile];
+            swaps.push((p, q));
+            position[current[p]] = q;
+            position[tile] = p;
+            current.swap(p, q);
+        }
+    }
+    swaps
+}
+
+/// 把排列分解为一系列拖动：每次把位置 `from` 上的tile取出，插入后位于位置 `to`，其余tile依次挪动
+///
+/// 位于最长递增子序列中的tile保持不动，拖动次数最少
+pub fn insertions_for(arrangement: &[usize]) -> Vec<(usize, usize)> {
+    let n = arrangement.len();
+    let mut target = vec![0; n];
+    for (p, &tile) in arrangement.iter().enumerate() {
+        target[tile] = p;
+    }
+    // 初始时位置p上为p号tile
+    let mut keep = vec![false; n];
+    for tile in longest_increasing(&target) {
+        keep[tile] = true;
+    }
+    let mut current: Vec<usize> = (0..n).collect();
+    let mut moves = vec![];
+    for p in 0..n {
+        let tile = arrangement[p];
+        if keep[tile] {
+            continue;
+        }
+        let from = current.iter().position(|&t| t == tile).unwrap_or_default();
+        current.remove(from);
+        // 按目标顺序处理，前一个tile已经就位
+        let to = if p == 0 {
+            0
+        } else {
+            current.iter().position(|&t| t == arrangement[p - 1]).map_or(0, |i| i + 1)
+        };
+        current.insert(to, tile);
+        moves.push((from, to));
+    }
+    moves
+}
+
+/// 严格递增的最长子序列的下标，O(n log n)
+fn longest_increasing(values: &[usize]) -> Vec<usize> {
+    let mut tails: Vec<usize> = vec![];
+    let mut previous = vec![None; values.len()];
+    for (i, &value) in values.iter().enumerate() {
+        let k = tails.partition_point(|&j| values[j] < value);
+        previous[i] = k.checked_sub(1).map(|k| tails[k]);
+        if k == tails.len() {
+            tails.push(i);
+        } else {
+            tails[k] = i;
+        }
+    }
+    let mut res = vec![];
+    let mut next = tails.last().copied();
+    while let Some(i) = next {
+        res.push(i);
+        next = previous[i];
+    }
+    res.reverse();
+    res
+}
@@ -0,0 +1,182 @@
+//! 识别流程各阶段的耗时统计
+//!
+//! 每次识别的耗时通过 [`Timings`] 返回；开启 `metrics` feature 后，
+//! 耗时与识别结果计数还会通过 [metrics](https://docs.rs/metrics) 门面上报：
+//! - `captcha_breaker_stage_seconds` 直方图，标签 `breaker`、`stage`
+//! - `captcha_breaker_solves_total` 计数器，标签 `breaker`、`outcome`(solved/failed/cancelled)
+
+use crate::lap::{ErrorKind, LapJVError};
+use std::error::Error;
+use std::time::Duration;
+#[cfg(any(feature = "chinese_click_0", feature = "shape_click_0"))]
+use std::time::Instant;
+
+/// 识别流程的阶段
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Stage {
+    Preprocess,
+    Detect,
+    Crop,
+    Embed,
+    Assign,
+}
+
+impl Stage {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Stage::Preprocess => "preprocess",
+            Stage::Detect => "detect",
+            Stage::Crop => "crop",
+            Stage::Embed => "embed",
+            Stage::Assign => "assign",
+        }
+    }
+}
+
+/// 一次识别中各阶段的耗时
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Timings {
+    pub preprocess: Duration,
+    pub detect: Duration,
+    pub crop: Duration,
+    pub embed: Duration,
+    pub assign: Duration,
+}
+
+impl Timings {
+    pub fn get(&self, stage: Stage) -> Duration {
+        match stage {
+            Stage::Preprocess => self.preprocess,
+            Stage::Detect => self.detect,
+            Stage::Crop => self.crop,
+            Stage::Embed => self.embed,
+            Stage::Assign => self.assign,
+        }
+    }
+
+    pub fn total(&self) -> Duration {
+        self.preprocess + self.detect + self.crop + self.embed + self.assign
+    }
+
+    #[cfg(any(feature = "chinese_click_0", feature = "shape_click_0"))]
+    fn get_mut(&mut self, stage: Stage) -> &mut Duration {
+        match stage {
+            Stage::Preprocess => &mut self.preprocess,
+            Stage::Detect => &mut self.detect,
+            Stage::Crop => &mut self.crop,
+            Stage::Embed => &mut self.embed,
+            Stage::Assign => &mut self.assign,
+        }
+    }
+}
+
+/// 识别结果
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(rename_all = "lowercase"))]
+pub enum Outcome {
+    Solved,
+    Failed,
+    /// 分配求解被 [`Cancellation`](crate::lap::Cancellation) 取消
+    Cancelled,
+}
+
+impl Outcome {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Outcome::Solved => "solved",
+            Outcome::Failed => "failed",
+            Outcome::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn of<T>(result: &Result<T, Box<dyn Error>>) -> Outcome {
+        match result {
+            Ok(_) => Outcome::Solved,
+            Err(e) => match e.downcast_ref::<LapJVError>() {
+                Some(e) if matches!(e.kind(), ErrorKind::Cancelled) => Outcome::Cancelled,
+                _ => Outcome::Failed,
+            },
+        }
+    }
+}
+
+/// 按阶段计时的记录器
+///
+/// 开启 `tracing` feature 时每次识别对应一个 `solve` span，每个阶段是它的子span
+#[cfg(any(feature = "chinese_click_0", feature = "shape_click_0"))]
+pub(crate) struct Recorder {
+    #[cfg(feature = "metrics")]
+    breaker: &'static str,
+    timings: Timings,
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+}
+
+#[cfg(any(feature = "chinese_click_0", feature = "shape_click_0"))]
+impl Recorder {
+    pub(crate) fn new(breaker: &'static str) -> Self {
+        #[cfg(not(any(feature = "metrics", feature = "tracing")))]
+        let _ = breaker;
+        Recorder {
+            #[cfg(feature = "metrics")]
+            breaker,
+            timings: Timings::default(),
+            #[cfg(feature = "tracing")]
+            span: tracing::info_span!(
+                "solve",
+                breaker,
+                detections = tracing::field::Empty,
+                questions = tracing::field::Empty,
+                answers = tracing::field::Empty,
+                min_confidence = tracing::field::Empty,
+                mean_confidence = tracing::field::Empty,
+                total_cost = tracing::field::Empty,
+                recovered_answers = tracing::field::Empty,
+                score = tracing::field::Empty,
+            ),
+        }
+    }
+
+    /// 记录计数类字段，字段名必须是 `solve` span中声明过的
+    pub(crate) fn record_count(&self, field: &'static str, value: usize) {
+        #[cfg(feature = "tracing")]
+        self.span.record(field, value as u64);
+        #[cfg(not(feature = "tracing"))]
+        let _ = (field, value);
+    }
+
+    /// 记录数值类字段，字段名必须是 `solve` span中声明过的
+    pub(crate) fn record_value(&self, field: &'static str, value: f32) {
+        #[cfg(feature = "tracing")]
+        self.span.record(field, value as f64);
+        #[cfg(not(feature = "tracing"))]
+        let _ = (field, value);
+    }
+
+    /// 执行f并把耗时累加到stage
+    pub(crate) fn time<T>(&mut self, stage: Stage, f: impl FnOnce() -> T) -> T {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(parent: &self.span, "stage", stage = stage.name()).entered();
+        let start = Instant::now();
+        let res = f();
+        let elapsed = start.elapsed();
+        *self.timings.get_mut(stage) += elapsed;
+        #[cfg(feature = "metrics")]
+        metrics::histogram!("captcha_breaker_stage_seconds", "breaker" => self.breaker, "stage" => stage.name())
+            .record(elapsed.as_secs_f64());
+        res
+    }
+
+    pub(crate) fn finish(self) -> Timings {
+        self.timings
+    }
+}
+
+/// 记录一次识别的结果
+#[cfg(any(feature = "chinese_click_0", feature = "shape_click_0"))]
+pub(crate) fn record_outcome(breaker: &'static str, outcome: Outcome) {
+    #[cfg(feature = "metrics")]
+    metrics::counter!("captcha_breaker_solves_total", "breaker" => breaker, "outcome" => outcome.name()).increment(1);
+    #[cfg(not(feature = "metrics"))]
+    let _ = (breaker, outcome);
+}
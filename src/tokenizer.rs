@@ -0,0 +1,108 @@
+//! 图文模型使用的文本分词
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::Path;
+
+/// 把提示词转换为模型输入的token id
+pub trait PromptTokenizer: Send + Sync {
+    fn encode(&self, text: &str) -> Vec<i64>;
+}
+
+/// BERT风格的WordPiece分词器(Chinese-CLIP等模型使用)
+///
+/// 中日韩字符逐字切分，其余文本按空白和标点切分后做最长匹配
+#[derive(Debug, Clone)]
+pub struct WordPieceTokenizer {
+    vocab: HashMap<String, i64>,
+    /// 输出的固定长度，不足时补 [PAD]
+    pub context_length: usize,
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x4E00..=0x9FFF | 0x3400..=0x4DBF | 0x20000..=0x2A6DF | 0xF900..=0xFAFF | 0x2F800..=0x2FA1F)
+}
+
+impl WordPieceTokenizer {
+    /// vocab.txt格式，每行一个token，行号为id
+    pub fn from_vocab_str(vocab: &str, context_length: usize) -> Self {
+        let vocab = vocab
+            .lines()
+            .enumerate()
+            .map(|(i, token)| (token.trim_end().to_string(), i as i64))
+            .collect();
+        WordPieceTokenizer { vocab, context_length }
+    }
+
+    pub fn from_vocab_file(path: impl AsRef<Path>, context_length: usize) -> Result<Self, Box<dyn Error>> {
+        Ok(Self::from_vocab_str(&std::fs::read_to_string(path)?, context_length))
+    }
+
+    fn id(&self, token: &str) -> i64 {
+        self.vocab.get(token).or_else(|| self.vocab.get("[UNK]")).copied().unwrap_or(100)
+    }
+
+    fn words(text: &str) -> Vec<String> {
+        let mut words = vec![];
+        let mut current = String::new();
+        for c in text.to_lowercase().chars() {
+            if c.is_whitespace() || c.is_ascii_punctuation() || is_cjk(c) || (!c.is_alphanumeric() && !c.is_ascii()) {
+                if !current.is_empty() {
+                    words.push(std::mem::take(&mut current));
+                }
+                if !c.is_whitespace() {
+                    words.push(c.to_string());
+                }
+            } else {
+                current.push(c);
+            }
+        }
+        if !current.is_empty() {
+            words.push(current);
+        }
+        words
+    }
+
+    fn word_pieces(&self, word: &str, out: &mut Vec<i64>) {
+        let chars: Vec<char> = word.chars().collect();
+        let mut start = 0;
+        let mut pieces = vec![];
+        while start < chars.len() {
+            let mut end = chars.len();
+            let mut found = None;
+            while start < end {
+                let piece: String = chars[start..end].iter().collect();
+                let piece = if start > 0 { format!("##{}", piece) } else { piece };
+                if let Some(&id) = self.vocab.get(&piece) {
+                    found = Some(id);
+                    break;
+                }
+                end -= 1;
+            }
+            match found {
+                Some(id) => pieces.push(id),
+                None => {
+                    // 无法切分的词整体作为[UNK]
+                    out.push(self.id("[UNK]"));
+                    return;
+                }
+            }
+            start = end;
+        }
+        out.extend(pieces);
+    }
+}
+
+impl PromptTokenizer for WordPieceTokenizer {
+    fn encode(&self, text: &str) -> Vec<i64> {
+        let mut ids = vec![self.id("[CLS]")];
+        for word in Self::words(text) {
+            self.word_pieces(&word, &mut ids);
+        }
+        ids.truncate(self.context_length.saturating_sub(1));
+        ids.push(self.id("[SEP]"));
+        ids.resize(self.context_length.max(ids.len()), self.id("[PAD]"));
+        ids
+    }
+}
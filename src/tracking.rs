@@ -0,0 +1,278 @@
+//! 视频流验证码
+//!
+//! 目标在连续的帧中移动时，逐帧检测的结果会抖动甚至丢失。[`IouTracker`] 用LAPJV按交并比把检测框关联到已有轨迹，
+//! 并按匀速运动预测位置；[`FrameSolver`] 逐帧推入图片，确认的轨迹稳定后给出答案
+
+use crate::detection::{BBox, YoloDetector};
+use crate::geometry::Rect;
+use crate::lap::{LapJVError, Solver};
+use crate::matching::assign;
+use image::DynamicImage;
+use ndarray::Array2;
+use std::error::Error;
+
+#[derive(Debug, Clone)]
+pub struct TrackerConfig {
+    /// 检测框与轨迹预测位置的交并比低于该值时不关联
+    pub iou_threshold: f32,
+    /// 连续丢失超过该帧数的轨迹被删除
+    pub max_missed: usize,
+    /// 关联到检测框的帧数达到该值后轨迹才被确认
+    pub min_hits: usize,
+    /// 位置、速度与置信度的平滑系数，越大越跟随最新一帧的检测
+    pub smoothing: f32,
+}
+
+impl Default for TrackerConfig {
+    fn default() -> Self {
+        TrackerConfig {
+            iou_threshold: 0.3,
+            max_missed: 3,
+            min_hits: 3,
+            smoothing: 0.6,
+        }
+    }
+}
+
+/// 一条轨迹
+#[derive(Debug, Clone, PartialEq)]
+pub struct Track {
+    pub id: u64,
+    /// 最近一次关联后平滑的框
+    pub bbox: BBox,
+    /// 平滑后的置信度
+    pub confidence: f32,
+    /// 每帧的位移(像素)
+    pub velocity: (f32, f32),
+    /// 关联到检测框的总帧数
+    pub hits: usize,
+    /// 连续未关联到检测框的帧数
+    pub missed: usize,
+}
+
+impl Track {
+    /// 按匀速运动预测frames帧之后的位置，frames从最近一次关联算起
+    pub fn predict(&self, frames: f32) -> Rect<f32> {
+        let rect = self.bbox.rect;
+        let (dx, dy) = (self.velocity.0 * frames, self.velocity.1 * frames);
+        Rect::new(rect.x_min + dx, rect.y_min + dy, rect.x_max + dx, rect.y_max + dy)
+    }
+
+    /// 当前帧的位置
+    pub fn position(&self) -> Rect<f32> {
+        self.predict(self.missed as f32)
+    }
+}
+
+/// 基于交并比的多目标跟踪
+#[derive(Debug, Clone, Default)]
+pub struct IouTracker {
+    config: TrackerConfig,
+    tracks: Vec<Track>,
+    next_id: u64,
+}
+
+impl IouTracker {
+    pub fn new(config: TrackerConfig) -> Self {
+        IouTracker {
+            config,
+            tracks: vec![],
+            next_id: 0,
+        }
+    }
+
+    pub fn config(&self) -> &TrackerConfig {
+        &self.config
+    }
+
+    /// 所有轨迹，包括尚未确认的
+    pub fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    /// 已确认且当前帧没有丢失的轨迹
+    pub fn confirmed(&self) -> impl Iterator<Item = &Track> {
+        self.tracks
+            .iter()
+            .filter(|track| track.hits >= self.config.min_hits && track.missed == 0)
+    }
+
+    /// 用一帧的检测结果更新轨迹
+    ///
+    /// 成本为 `1 - IoU(预测位置, 检测框)`，由LAPJV求解全局最优的关联，交并比低于阈值的关联会被拒绝
+    pub fn update(&mut self, detections: &[BBox]) -> Result<(), LapJVError> {
+        let predicted: Vec<Rect<f32>> = self.tracks.iter().map(|track| track.predict(track.missed as f32 + 1.0)).collect();
+        let costs = Array2::from_shape_fn((predicted.len(), detections.len()), |(i, j)| {
+            1.0 - predicted[i].iou(&detections[j].rect)
+        });
+        let matches = assign(&costs, Solver::LapJV)?;
+        let mut detected = vec![false; detections.len()];
+        let alpha = self.config.smoothing.clamp(0.0, 1.0);
+        for (i, track) in self.tracks.iter_mut().enumerate() {
+            let matched = matches[i].filter(|&j| 1.0 - costs[[i, j]] >= self.config.iou_threshold);
+            let Some(j) = matched else {
+                track.missed += 1;
+                continue;
+            };
+            detected[j] = true;
+            let detection = &detections[j];
+            let elapsed = track.missed as f32 + 1.0;
+            let (old_x, old_y) = track.bbox.center();
+            let (pred, det) = (predicted[i], detection.rect);
+            let lerp = |p: f32, d: f32| p + (d - p) * alpha;
+            let rect = Rect::new(
+                lerp(pred.x_min, det.x_min),
+                lerp(pred.y_min, det.y_min),
+                lerp(pred.x_max, det.x_max),
+                lerp(pred.y_max, det.y_max),
+            );
+            let (new_x, new_y) = rect.center();
+            let step = ((new_x - old_x) / elapsed, (new_y - old_y) / elapsed);
+            track.velocity = if track.hits == 1 {
+                step
+            } else {
+                (lerp(track.velocity.0, step.0), lerp(track.velocity.1, step.1))
+            };
+            track.confidence = lerp(track.confidence, detection.confidence);
+            track.bbox = BBox {
+                rect,
+                confidence: track.confidence,
+                ..detection.clone()
+            };
+            track.hits += 1;
+            track.missed = 0;
+        }
+        let max_missed = self.config.max_missed;
+        self.tracks.retain(|track| track.missed <= max_missed);
+        for (detection, _) in detections.iter().zip(detected).filter(|(_, detected)| !detected) {
+            self.tracks.push(Track {
+                id: self.next_id,
+                bbox: detection.clone(),
+                confidence: detection.confidence,
+                velocity: (0.0, 0.0),
+                hits: 1,
+                missed: 0,
+            });
+            self.next_id += 1;
+        }
+        Ok(())
+    }
+
+    pub fn reset(&mut self) {
+        self.tracks.clear();
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FrameSolverConfig {
+    pub tracker: TrackerConfig,
+    /// 确认的轨迹不变，且各轨迹平滑置信度的变化都不超过该值时视为收敛
+    pub tolerance: f32,
+    /// 连续收敛该帧数后答案稳定
+    pub stable_frames: usize,
+}
+
+impl Default for FrameSolverConfig {
+    fn default() -> Self {
+        FrameSolverConfig {
+            tracker: TrackerConfig::default(),
+            tolerance: 0.05,
+            stable_frames: 3,
+        }
+    }
+}
+
+/// [`FrameSolver::push`] 的结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameResult {
+    /// 已推入的帧数
+    pub frame: usize,
+    /// 当前帧中已确认的轨迹，按id排序
+    pub tracks: Vec<Track>,
+    /// 答案是否已经稳定，稳定后 `tracks` 即为答案
+    pub stable: bool,
+}
+
+type DetectFn<'a> = Box<dyn Fn(&DynamicImage) -> Result<Vec<BBox>, Box<dyn Error>> + 'a>;
+
+/// 逐帧推入视频流验证码，跟踪检测结果直到答案稳定
+pub struct FrameSolver<'a> {
+    detect: DetectFn<'a>,
+    tracker: IouTracker,
+    config: FrameSolverConfig,
+    frames: usize,
+    streak: usize,
+    previous: Vec<(u64, f32)>,
+}
+
+impl<'a> FrameSolver<'a> {
+    /// detect为单帧的检测函数
+    pub fn new(detect: impl Fn(&DynamicImage) -> Result<Vec<BBox>, Box<dyn Error>> + 'a) -> Self {
+        let config = FrameSolverConfig::default();
+        FrameSolver {
+            detect: Box::new(detect),
+            tracker: IouTracker::new(config.tracker.clone()),
+            config,
+            frames: 0,
+            streak: 0,
+            previous: vec![],
+        }
+    }
+
+    /// 使用检测模型逐帧检测
+    pub fn with_detector(detector: &'a YoloDetector) -> Self {
+        FrameSolver::new(|frame| detector.detect(frame))
+    }
+
+    /// 设置配置并清空已有的轨迹
+    pub fn with_config(mut self, config: FrameSolverConfig) -> Self {
+        self.tracker = IouTracker::new(config.tracker.clone());
+        self.config = config;
+        self.reset();
+        self
+    }
+
+    pub fn config(&self) -> &FrameSolverConfig {
+        &self.config
+    }
+
+    pub fn tracker(&self) -> &IouTracker {
+        &self.tracker
+    }
+
+    /// 检测一帧并更新轨迹
+    pub fn push(&mut self, frame: &DynamicImage) -> Result<FrameResult, Box<dyn Error>> {
+        let detections = (self.detect)(frame)?;
+        self.push_detections(&detections)
+    }
+
+    /// 使用已有的检测结果更新轨迹，适合调用方自行检测的场景
+    pub fn push_detections(&mut self, detections: &[BBox]) -> Result<FrameResult, Box<dyn Error>> {
+        self.tracker.update(detections)?;
+        self.frames += 1;
+        let mut tracks: Vec<Track> = self.tracker.confirmed().cloned().collect();
+        tracks.sort_by_key(|track| track.id);
+        let current: Vec<(u64, f32)> = tracks.iter().map(|track| (track.id, track.confidence)).collect();
+        let converged = !current.is_empty()
+            && current.len() == self.previous.len()
+            && current
+                .iter()
+                .zip(&self.previous)
+                .all(|(a, b)| a.0 == b.0 && (a.1 - b.1).abs() <= self.config.tolerance);
+        self.streak = if converged { self.streak + 1 } else { 0 };
+        self.previous = current;
+        Ok(FrameResult {
+            frame: self.frames,
+            tracks,
+            stable: self.streak >= self.config.stable_frames,
+        })
+    }
+
+    /// 清空轨迹，开始新的验证码
+    pub fn reset(&mut self) {
+        self.tracker.reset();
+        self.frames = 0;
+        self.streak = 0;
+        self.previous.clear();
+    }
+}
@@ -0,0 +1,210 @@
+//! 仿人工的鼠标轨迹
+//!
+//! 点选与滑块验证码会检测鼠标移动的特征，这里根据目标点或滑动距离生成带时间戳的轨迹，
+//! 由浏览器自动化框架按时间回放。路径为加入随机控制点的三次贝塞尔曲线，
+//! 速度符合最小加加速度(minimum jerk)曲线，并叠加随终点衰减的抖动
+
+use crate::rng::SplitMix64;
+
+/// 轨迹上的一个采样点，`t` 为相对起点的毫秒数
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TrajectoryPoint {
+    pub x: f32,
+    pub y: f32,
+    pub t: u32,
+}
+
+/// 生成的轨迹
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Trajectory {
+    pub points: Vec<TrajectoryPoint>,
+    /// 需要按下鼠标的采样点下标(点选时为每个目标点，滑块时为起点)
+    pub presses: Vec<usize>,
+    /// 需要松开鼠标的采样点下标
+    pub releases: Vec<usize>,
+}
+
+impl Trajectory {
+    /// 轨迹总时长(毫秒)
+    pub fn duration(&self) -> u32 {
+        self.points.last().map_or(0, |p| p.t)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TrajectoryConfig {
+    /// 采样间隔(毫秒)，实际间隔会在 ±30% 内随机
+    pub sample_interval: u32,
+    /// Fitts定律 `T = a + b * log2(D / W + 1)` 中的a(毫秒)
+    pub fitts_a: f32,
+    /// Fitts定律中的b(毫秒)
+    pub fitts_b: f32,
+    /// Fitts定律中的目标宽度(像素)
+    pub target_width: f32,
+    /// 控制点偏离直线的最大比例
+    pub curvature: f32,
+    /// 路径抖动的标准差(像素)
+    pub noise: f32,
+    /// 滑块超出目标后回拉的最大距离(像素)，为0时不超出
+    pub overshoot: f32,
+    /// 点击时按下的时长范围(毫秒)
+    pub press_duration: (u32, u32),
+    /// 两次点击之间停顿的时长范围(毫秒)
+    pub pause: (u32, u32),
+    pub seed: u64,
+}
+
+impl Default for TrajectoryConfig {
+    fn default() -> Self {
+        TrajectoryConfig {
+            sample_interval: 16,
+            fitts_a: 120.0,
+            fitts_b: 160.0,
+            target_width: 20.0,
+            curvature: 0.25,
+            noise: 0.8,
+            overshoot: 6.0,
+            press_duration: (60, 140),
+            pause: (150, 450),
+            seed: 0x7a11,
+        }
+    }
+}
+
+struct Builder<'a> {
+    config: &'a TrajectoryConfig,
+    rng: SplitMix64,
+    trajectory: Trajectory,
+    t: u32,
+}
+
+impl<'a> Builder<'a> {
+    fn new(config: &'a TrajectoryConfig, start: (f32, f32)) -> Self {
+        Builder {
+            rng: SplitMix64::new(config.seed),
+            trajectory: Trajectory {
+                points: vec![TrajectoryPoint {
+                    x: start.0,
+                    y: start.1,
+                    t: 0,
+                }],
+                ..Default::default()
+            },
+            config,
+            t: 0,
+        }
+    }
+
+    fn position(&self) -> (f32, f32) {
+        let last = self.trajectory.points.last().unwrap();
+        (last.x, last.y)
+    }
+
+    fn range(&mut self, (min, max): (u32, u32)) -> u32 {
+        min + (self.rng.next_f32() * max.saturating_sub(min) as f32) as u32
+    }
+
+    /// 停在当前位置
+    fn hold(&mut self, duration: u32) {
+        let (x, y) = self.position();
+        self.t += duration.max(1);
+        self.trajectory.points.push(TrajectoryPoint { x, y, t: self.t });
+    }
+
+    /// 沿贝塞尔曲线移动到target，最后一个采样点恰好落在target上
+    fn move_to(&mut self, target: (f32, f32)) {
+        let start = self.position();
+        let (dx, dy) = (target.0 - start.0, target.1 - start.1);
+        let distance = (dx * dx + dy * dy).sqrt();
+        if distance < 0.5 {
+            return;
+        }
+        let config = self.config;
+        let duration = config.fitts_a + config.fitts_b * (distance / config.target_width + 1.0).log2();
+        // 控制点在连线的1/3与2/3处沿法线方向随机偏移
+        let normal = (-dy / distance, dx / distance);
+        let offset = |rng: &mut SplitMix64| rng.next_signed() * config.curvature * distance;
+        let (o1, o2) = (offset(&mut self.rng), offset(&mut self.rng));
+        let c1 = (start.0 + dx / 3.0 + normal.0 * o1, start.1 + dy / 3.0 + normal.1 * o1);
+        let c2 = (start.0 + dx * 2.0 / 3.0 + normal.0 * o2, start.1 + dy * 2.0 / 3.0 + normal.1 * o2);
+
+        let mut elapsed = 0.0;
+        loop {
+            let interval = config.sample_interval as f32 * (1.0 + 0.3 * self.rng.next_signed());
+            elapsed += interval.max(1.0);
+            let u = (elapsed / duration).min(1.0);
+            let s = minimum_jerk(u);
+            let (mut x, mut y) = bezier(start, c1, c2, target, s);
+            if u < 1.0 {
+                let noise = config.noise * (1.0 - s);
+                x += self.rng.next_normal() * noise;
+                y += self.rng.next_normal() * noise;
+            }
+            self.t += interval.max(1.0).round() as u32;
+            self.trajectory.points.push(TrajectoryPoint { x, y, t: self.t });
+            if u >= 1.0 {
+                break;
+            }
+        }
+    }
+
+    fn press(&mut self) {
+        self.trajectory.presses.push(self.trajectory.points.len() - 1);
+    }
+
+    fn release(&mut self) {
+        self.trajectory.releases.push(self.trajectory.points.len() - 1);
+    }
+}
+
+/// 从start依次移动到每个目标点并点击
+pub fn click_path(start: (f32, f32), targets: &[(f32, f32)], config: &TrajectoryConfig) -> Trajectory {
+    let mut builder = Builder::new(config, start);
+    for (i, &target) in targets.iter().enumerate() {
+        if i > 0 {
+            let pause = builder.range(config.pause);
+            builder.hold(pause);
+        }
+        builder.move_to(target);
+        builder.press();
+        let press = builder.range(config.press_duration);
+        builder.hold(press);
+        builder.release();
+    }
+    builder.trajectory
+}
+
+/// 从滑块所在的start按下，向右拖动distance像素后松开，可能先超出目标再回拉
+pub fn slide_path(start: (f32, f32), distance: f32, config: &TrajectoryConfig) -> Trajectory {
+    let mut builder = Builder::new(config, start);
+    builder.press();
+    let press = builder.range(config.press_duration);
+    builder.hold(press);
+    let drift = builder.rng.next_signed() * 3.0;
+    let target = (start.0 + distance, start.1 + drift);
+    let overshoot = config.overshoot * builder.rng.next_f32();
+    if overshoot >= 1.0 {
+        builder.move_to((target.0 + overshoot, target.1));
+        let pause = builder.range((40, 120));
+        builder.hold(pause);
+    }
+    builder.move_to(target);
+    let pause = builder.range((30, 90));
+    builder.hold(pause);
+    builder.release();
+    builder.trajectory
+}
+
+/// 最小加加速度曲线，把时间进度映射为路程进度
+fn minimum_jerk(u: f32) -> f32 {
+    u * u * u * (10.0 - 15.0 * u + 6.0 * u * u)
+}
+
+fn bezier(p0: (f32, f32), p1: (f32, f32), p2: (f32, f32), p3: (f32, f32), s: f32) -> (f32, f32) {
+    let r = 1.0 - s;
+    let (a, b, c, d) = (r * r * r, 3.0 * r * r * s, 3.0 * r * s * s, s * s * s);
+    (
+        a * p0.0 + b * p1.0 + c * p2.0 + d * p3.0,
+        a * p0.1 + b * p1.1 + c * p2.1 + d * p3.1,
+    )
+}
@@ -0,0 +1,103 @@
+//! 答案质量评估
+//!
+//! 根据点击坐标与匹配成本给出答案的等级，调用方据此决定提交、重试还是转人工
+
+use ndarray::Array2;
+use std::collections::HashSet;
+
+/// 答案等级
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Grade {
+    /// 可以直接提交
+    Good,
+    /// 匹配不够明确，建议重试
+    Uncertain,
+    /// 答案无效(点击在图片外、答案重复或缺失)，建议刷新验证码或转人工
+    Bad,
+}
+
+#[derive(Debug, Clone)]
+pub struct ValidationConfig {
+    /// 最佳匹配与次佳匹配的相对差距低于该值时视为不明确
+    pub min_margin: f32,
+    /// 匹配距离高于该值时视为不明确
+    pub max_distance: Option<f32>,
+}
+
+impl Default for ValidationConfig {
+    fn default() -> Self {
+        ValidationConfig {
+            min_margin: 0.1,
+            max_distance: None,
+        }
+    }
+}
+
+/// 评估结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct Validation {
+    pub grade: Grade,
+    /// 所有点击坐标都在图片范围内
+    pub points_inside: bool,
+    /// 每个问题都匹配到了互不相同的答案
+    pub distinct: bool,
+    /// 每个问题的匹配距离
+    pub distances: Vec<f32>,
+    /// 每个问题 (次佳距离 - 最佳距离) / 次佳距离，只有一个候选时为1
+    pub margins: Vec<f32>,
+}
+
+/// 评估一组答案
+///
+/// `cost_matrix` 的行为问题、列为候选答案，`matches[i]` 为第i个问题选中的列
+pub fn validate(
+    points: &[(f32, f32)],
+    image_size: (u32, u32),
+    cost_matrix: &Array2<f32>,
+    matches: &[Option<usize>],
+    config: &ValidationConfig,
+) -> Validation {
+    let (width, height) = (image_size.0 as f32, image_size.1 as f32);
+    let points_inside = points
+        .iter()
+        .all(|&(x, y)| (0.0..width).contains(&x) && (0.0..height).contains(&y));
+    let mut seen = HashSet::new();
+    let distinct = matches.iter().all(|m| m.is_some_and(|a| seen.insert(a)));
+
+    let mut distances = vec![];
+    let mut margins = vec![];
+    for (q, a) in matches.iter().enumerate() {
+        let Some(a) = *a else { continue };
+        let row = cost_matrix.row(q);
+        let best = row[a];
+        let second = row
+            .iter()
+            .enumerate()
+            .filter(|&(j, _)| j != a)
+            .map(|(_, &c)| c)
+            .fold(f32::INFINITY, f32::min);
+        distances.push(best);
+        margins.push(if second.is_finite() && second > 0.0 {
+            (second - best) / second
+        } else {
+            1.0
+        });
+    }
+
+    let grade = if !points_inside || !distinct || matches.is_empty() {
+        Grade::Bad
+    } else if margins.iter().any(|&m| m < config.min_margin)
+        || config.max_distance.is_some_and(|max| distances.iter().any(|&d| d > max))
+    {
+        Grade::Uncertain
+    } else {
+        Grade::Good
+    };
+    Validation {
+        grade,
+        points_inside,
+        distinct,
+        distances,
+        margins,
+    }
+}
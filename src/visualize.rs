@@ -0,0 +1,83 @@
+use crate::geometry::Rect;
+use image::{Rgba, RgbaImage};
+
+/// 每组匹配使用的颜色，按问题顺序循环
+pub const PALETTE: [Rgba<u8>; 6] = [
+    Rgba([255, 64, 64, 255]),
+    Rgba([64, 200, 64, 255]),
+    Rgba([64, 128, 255, 255]),
+    Rgba([255, 200, 0, 255]),
+    Rgba([200, 64, 255, 255]),
+    Rgba([0, 220, 220, 255]),
+];
+
+/// 未匹配的框使用的颜色
+pub const UNMATCHED: Rgba<u8> = Rgba([160, 160, 160, 255]);
+
+fn put(image: &mut RgbaImage, x: i64, y: i64, color: Rgba<u8>) {
+    if x >= 0 && y >= 0 && (x as u32) < image.width() && (y as u32) < image.height() {
+        image.put_pixel(x as u32, y as u32, color);
+    }
+}
+
+/// 画矩形边框
+pub fn draw_rect(image: &mut RgbaImage, rect: Rect<f32>, color: Rgba<u8>) {
+    let (x0, y0, x1, y1) = (rect.x_min as i64, rect.y_min as i64, rect.x_max as i64, rect.y_max as i64);
+    for x in x0..=x1 {
+        put(image, x, y0, color);
+        put(image, x, y1, color);
+    }
+    for y in y0..=y1 {
+        put(image, x0, y, color);
+        put(image, x1, y, color);
+    }
+}
+
+/// 画线段(Bresenham)
+pub fn draw_line(image: &mut RgbaImage, from: (f32, f32), to: (f32, f32), color: Rgba<u8>) {
+    let (mut x0, mut y0) = (from.0 as i64, from.1 as i64);
+    let (x1, y1) = (to.0 as i64, to.1 as i64);
+    let dx = (x1 - x0).abs();
+    let dy = -(y1 - y0).abs();
+    let sx = if x0 < x1 { 1 } else { -1 };
+    let sy = if y0 < y1 { 1 } else { -1 };
+    let mut err = dx + dy;
+    loop {
+        put(image, x0, y0, color);
+        if x0 == x1 && y0 == y1 {
+            break;
+        }
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x0 += sx;
+        }
+        if e2 <= dx {
+            err += dx;
+            y0 += sy;
+        }
+    }
+}
+
+/// 画实心点
+pub fn draw_point(image: &mut RgbaImage, (x, y): (f32, f32), radius: i64, color: Rgba<u8>) {
+    let (cx, cy) = (x as i64, y as i64);
+    for dy in -radius..=radius {
+        for dx in -radius..=radius {
+            if dx * dx + dy * dy <= radius * radius {
+                put(image, cx + dx, cy + dy, color);
+            }
+        }
+    }
+}
+
+/// 在框上方画置信度条，长度为框宽乘以置信度
+pub fn draw_confidence(image: &mut RgbaImage, rect: Rect<f32>, confidence: f32, color: Rgba<u8>) {
+    let width = (rect.width() * confidence.clamp(0.0, 1.0)) as i64;
+    let y = rect.y_min as i64 - 3;
+    for dy in 0..2 {
+        for dx in 0..=width {
+            put(image, rect.x_min as i64 + dx, y + dy, color);
+        }
+    }
+}
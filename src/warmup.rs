@@ -0,0 +1,47 @@
+//! 模型预热
+//!
+//! 首次推理时ONNX Runtime才会完成图优化与内存分配，耗时可能达到数百毫秒，
+//! 对延迟敏感的服务可以在启动时先用全零输入推理一次
+
+use crate::backend::{ElementType, InferenceBackend, TensorData};
+use ndarray::ArrayD;
+use std::error::Error;
+
+/// 动态维度(批次维度除外)使用的长度
+const DYNAMIC_DIM: usize = 64;
+
+/// 用全零输入推理一次
+pub fn warmup_backend(backend: &dyn InferenceBackend) -> Result<(), Box<dyn Error>> {
+    let inputs = backend
+        .inputs()
+        .iter()
+        .map(|input| {
+            let shape: Vec<usize> = input
+                .shape
+                .iter()
+                .enumerate()
+                .map(|(i, &d)| match d {
+                    d if d > 0 => d as usize,
+                    _ if i == 0 => 1,
+                    _ => DYNAMIC_DIM,
+                })
+                .collect();
+            let data = match input.element_type {
+                ElementType::F32 => TensorData::F32(ArrayD::zeros(shape)),
+                ElementType::I64 => TensorData::I64(ArrayD::zeros(shape)),
+                ElementType::Other => return Err(format!("无法为输入 {} 构造预热数据，只支持f32与i64", input)),
+            };
+            Ok((input.name.clone(), data))
+        })
+        .collect::<Result<_, _>>()?;
+    backend.run(inputs)?;
+    Ok(())
+}
+
+/// 依次预热多个模型
+pub fn warmup_backends(backends: &[&dyn InferenceBackend]) -> Result<(), Box<dyn Error>> {
+    for backend in backends {
+        warmup_backend(*backend)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,52 @@
+//! 浏览器/wasm32的wasm-bindgen接口
+//!
+//! 目前ONNX Runtime无法在 `wasm32-unknown-unknown` 上运行，这里只导出不需要模型的部分：
+//! 拼图还原、分配求解和CTC解码；需要模型的验证码可以在JS侧推理后把输出交给这些函数做后处理
+//!
+//! ```shell
+//! wasm-pack build --target web -- --no-default-features --features wasm
+//! ```
+
+use crate::captcha::{PuzzleMode, PuzzleRestore0, PuzzleRestore0Config};
+use crate::ctc;
+use crate::lap;
+use ndarray::Array2;
+use wasm_bindgen::prelude::*;
+
+fn js_error(e: impl std::fmt::Display) -> JsError {
+    JsError::new(&e.to_string())
+}
+
+/// 还原拼图，返回行优先的排列：还原后位置p上应放置当前位于 `arrangement[p]` 的块
+///
+/// `rows` 或 `cols` 为0时自动检测网格，`swap_only` 为true时只考虑两块被交换的情况
+#[wasm_bindgen(js_name = solvePuzzle)]
+pub fn solve_puzzle(image: &[u8], rows: u32, cols: u32, swap_only: bool) -> Result<Vec<u32>, JsError> {
+    let config = PuzzleRestore0Config {
+        mode: if swap_only { PuzzleMode::Swap } else { PuzzleMode::Restore },
+        grid: (rows > 0 && cols > 0).then_some((rows, cols)),
+        ..Default::default()
+    };
+    let solution = PuzzleRestore0::new()
+        .with_config(config)
+        .run_from_bytes(image)
+        .map_err(js_error)?;
+    Ok(solution.arrangement.into_iter().map(|i| i as u32).collect())
+}
+
+/// 求解 `dim × dim` 的分配问题，`costs` 为行优先的成本矩阵，返回每一行分配的列
+#[wasm_bindgen]
+pub fn lapjv(costs: &[f64], dim: usize) -> Result<Vec<u32>, JsError> {
+    let matrix = Array2::from_shape_vec((dim, dim), costs.to_vec()).map_err(js_error)?;
+    let (row_to_col, _) = lap::lapjv(&matrix).map_err(js_error)?;
+    Ok(row_to_col.into_iter().map(|c| c as u32).collect())
+}
+
+/// 对 (时间步, 类别数) 的logits做CTC贪心解码，类别0为blank
+#[wasm_bindgen(js_name = ctcGreedyDecode)]
+pub fn ctc_greedy_decode(logits: &[f32], num_classes: usize) -> Result<Vec<u32>, JsError> {
+    let steps = logits.len() / num_classes.max(1);
+    let logits = Array2::from_shape_vec((steps, num_classes), logits.to_vec()).map_err(js_error)?;
+    let (labels, _) = ctc::greedy_decode(&ctc::log_softmax(&logits.view()).view(), 0);
+    Ok(labels.into_iter().map(|l| l as u32).collect())
+}